@@ -10,4 +10,4 @@ fi
 docker start swissrpg-compile-container
 # Run the build command inside of the container
 # TODO: replace 'cp /tmp/target/release...' with Cargo's --out-dir option once it is stable
-docker exec -it swissrpg-compile-container /bin/bash -lc 'cd /opt && cargo build --release --target-dir /tmp/target && strip /tmp/target/release/swissrpg-discord-bot && cp /tmp/target/release/swissrpg-discord-bot /opt/'
\ No newline at end of file
+docker exec -it swissrpg-compile-container /bin/bash -lc 'cd /opt && cargo build --release --target-dir /tmp/target && strip /tmp/target/release/swissrpg-discord-bot /tmp/target/release/botctl && cp /tmp/target/release/swissrpg-discord-bot /tmp/target/release/botctl /opt/'
\ No newline at end of file
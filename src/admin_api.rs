@@ -0,0 +1,412 @@
+use futures::future;
+use futures::{Future, Stream};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use lazy_static::lazy_static;
+use redis::{Commands, PipelineCommands};
+use serde::{Deserialize, Serialize};
+use serenity::http::CacheHttp;
+use serenity::model::id::ChannelId;
+use serenity::prelude::{Mutex, RwLock};
+use std::sync::Arc;
+
+// A small JSON API on the bot's HTTP server for scripts (and eventually a
+// dashboard), mirroring the organizer commands:
+// * GET /api/series lists the event series
+// * POST /api/sync/meetup and POST /api/sync/discord start a sync
+// * PUT /api/users/{discord_id}/meetup with {"meetup_id": ...} links a user,
+//   DELETE on the same URL unlinks them
+// * POST /api/channels/{channel_id}/close marks a channel for closing
+// It is only enabled if ADMIN_API_TOKEN is set, and every request needs an
+// "Authorization: Bearer <token>" header. The mutations go through the same
+// functions as the Discord commands (minus the confirmation step) and are
+// refused during maintenance.
+lazy_static! {
+    static ref USER_MEETUP_URL_REGEX: regex::Regex =
+        regex::Regex::new(r"^/api/users/(?P<discord_id>[0-9]+)/meetup$").unwrap();
+    static ref CHANNEL_CLOSE_URL_REGEX: regex::Regex =
+        regex::Regex::new(r"^/api/channels/(?P<channel_id>[0-9]+)/close$").unwrap();
+}
+
+pub type ResponseFuture = Box<dyn Future<Item = Response<Body>, Error = crate::BoxedError> + Send>;
+
+pub struct AdminApi {
+    token: String,
+    redis_client: crate::redis_namespace::Client,
+    discord_api: crate::discord_bot::CacheAndHttp,
+    bot_id: u64,
+    task_scheduler: Arc<Mutex<white_rabbit::Scheduler>>,
+    async_meetup_client: Arc<RwLock<Option<crate::meetup_api::AsyncClient>>>,
+    event_source: crate::event_source::SharedEventSource,
+    future_spawner: futures::sync::mpsc::Sender<crate::meetup_sync::BoxedFuture<(), ()>>,
+}
+
+#[derive(Serialize)]
+struct Series {
+    id: String,
+    discord_channel: Option<u64>,
+    r#type: Option<String>,
+    next_meetup_event: Option<String>,
+    sync_paused: bool,
+    sync_failures: u64,
+}
+
+#[derive(Deserialize)]
+struct LinkRequest {
+    meetup_id: u64,
+}
+
+fn json_response(
+    status: StatusCode,
+    body: serde_json::Value,
+) -> Result<Response<Body>, crate::BoxedError> {
+    Ok(Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string().into())?)
+}
+
+fn error_response(status: StatusCode, message: &str) -> Result<Response<Body>, crate::BoxedError> {
+    json_response(status, serde_json::json!({ "error": message }))
+}
+
+impl AdminApi {
+    pub fn new(
+        token: String,
+        redis_client: crate::redis_namespace::Client,
+        discord_api: crate::discord_bot::CacheAndHttp,
+        bot_id: u64,
+        task_scheduler: Arc<Mutex<white_rabbit::Scheduler>>,
+        async_meetup_client: Arc<RwLock<Option<crate::meetup_api::AsyncClient>>>,
+        event_source: crate::event_source::SharedEventSource,
+        future_spawner: futures::sync::mpsc::Sender<crate::meetup_sync::BoxedFuture<(), ()>>,
+    ) -> Self {
+        AdminApi {
+            token: token,
+            redis_client: redis_client,
+            discord_api: discord_api,
+            bot_id: bot_id,
+            task_scheduler: task_scheduler,
+            async_meetup_client: async_meetup_client,
+            event_source: event_source,
+            future_spawner: future_spawner,
+        }
+    }
+
+    pub fn handle(&self, req: Request<Body>) -> ResponseFuture {
+        let path = req.uri().path().to_string();
+        let response = self.route(req);
+        // Don't let the details of internal server errors leak
+        Box::new(response.or_else(move |err| {
+            eprintln!("Error in the admin API: {}", err);
+            crate::error_reporting::report(&err, &[("api", path.as_str())]);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+        }))
+    }
+
+    fn is_authorized(&self, req: &Request<Body>) -> bool {
+        let token = req
+            .headers()
+            .get(hyper::header::AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| {
+                if header.starts_with("Bearer ") {
+                    Some(header["Bearer ".len()..].trim())
+                } else {
+                    None
+                }
+            });
+        match token {
+            Some(token) => ring::constant_time::verify_slices_are_equal(
+                token.as_bytes(),
+                self.token.as_bytes(),
+            )
+            .is_ok(),
+            None => false,
+        }
+    }
+
+    fn route(&self, req: Request<Body>) -> ResponseFuture {
+        if !self.is_authorized(&req) {
+            return Box::new(future::result(error_response(
+                StatusCode::UNAUTHORIZED,
+                "Missing or invalid token",
+            )));
+        }
+        let (method, path) = (req.method().clone(), req.uri().path().to_string());
+        // Like the mutating commands, mutations are refused during maintenance
+        if method != Method::GET {
+            let maintenance_mode = self
+                .redis_client
+                .get_connection()
+                .map_err(Into::into)
+                .and_then(|mut con| crate::maintenance_mode::is_active(&mut con));
+            match maintenance_mode {
+                Ok(false) => (),
+                Ok(true) => {
+                    return Box::new(future::result(error_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "Maintenance mode is active",
+                    )))
+                }
+                Err(err) => return Box::new(future::err(err)),
+            }
+        }
+        if let (&Method::GET, "/api/series") = (&method, path.as_str()) {
+            Box::new(future::result(self.list_series()))
+        } else if let (&Method::POST, "/api/sync/meetup") = (&method, path.as_str()) {
+            Box::new(future::result(self.sync_meetup()))
+        } else if let (&Method::POST, "/api/sync/discord") = (&method, path.as_str()) {
+            Box::new(future::result(self.sync_discord()))
+        } else if let Some(captures) = USER_MEETUP_URL_REGEX.captures(&path) {
+            let discord_id = match captures.name("discord_id").unwrap().as_str().parse() {
+                Ok(discord_id) => discord_id,
+                Err(_) => {
+                    return Box::new(future::result(error_response(
+                        StatusCode::BAD_REQUEST,
+                        "Invalid Discord ID",
+                    )))
+                }
+            };
+            match method {
+                Method::PUT => self.link_user(discord_id, req.into_body()),
+                Method::DELETE => Box::new(future::result(self.unlink_user(discord_id))),
+                _ => Box::new(future::result(error_response(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    "Method not allowed",
+                ))),
+            }
+        } else if let (&Method::POST, Some(captures)) =
+            (&method, CHANNEL_CLOSE_URL_REGEX.captures(&path))
+        {
+            match captures.name("channel_id").unwrap().as_str().parse() {
+                Ok(channel_id) => {
+                    Box::new(future::result(self.close_channel(ChannelId(channel_id))))
+                }
+                Err(_) => Box::new(future::result(error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Invalid channel ID",
+                ))),
+            }
+        } else {
+            Box::new(future::result(error_response(
+                StatusCode::NOT_FOUND,
+                "Unknown route",
+            )))
+        }
+    }
+
+    fn list_series(&self) -> Result<Response<Body>, crate::BoxedError> {
+        let mut redis_connection = self.redis_client.get_connection()?;
+        let mut series_ids: Vec<String> = redis_connection.smembers("event_series")?;
+        series_ids.sort_unstable();
+        let mut series = Vec::with_capacity(series_ids.len());
+        for series_id in series_ids {
+            let (discord_channel, series_type, next_meetup_event, sync_paused, sync_failures): (
+                Option<u64>,
+                Option<String>,
+                Option<String>,
+                Option<bool>,
+                Option<u64>,
+            ) = redis::pipe()
+                .get(format!("event_series:{}:discord_channel", series_id))
+                .get(format!("event_series:{}:type", series_id))
+                .get(format!("event_series:{}:next_meetup_event", series_id))
+                .get(format!("event_series:{}:sync_paused", series_id))
+                .get(format!("event_series:{}:sync_failures", series_id))
+                .query(&mut redis_connection)?;
+            series.push(Series {
+                id: series_id,
+                discord_channel: discord_channel,
+                r#type: series_type,
+                next_meetup_event: next_meetup_event,
+                sync_paused: sync_paused.unwrap_or(false),
+                sync_failures: sync_failures.unwrap_or(0),
+            });
+        }
+        json_response(StatusCode::OK, serde_json::json!({ "series": series }))
+    }
+
+    fn sync_meetup(&self) -> Result<Response<Body>, crate::BoxedError> {
+        match crate::meetup_sync::start_sync_task(
+            self.event_source.clone(),
+            self.redis_client.clone(),
+            &mut self.future_spawner.clone(),
+        ) {
+            Ok(()) => json_response(StatusCode::ACCEPTED, serde_json::json!({})),
+            Err(err) => error_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                &format!(
+                    "Could not submit the Meetup synchronization task to the queue \
+                     (full={}, disconnected={})",
+                    err.is_full(),
+                    err.is_disconnected()
+                ),
+            ),
+        }
+    }
+
+    fn sync_discord(&self) -> Result<Response<Body>, crate::BoxedError> {
+        crate::persistent_tasks::schedule_task(
+            &self.task_scheduler,
+            &self.redis_client,
+            &self.discord_api,
+            self.bot_id,
+            crate::persistent_tasks::TaskType::SyncDiscord,
+            white_rabbit::Utc::now(),
+            /*recurring*/ false,
+        )?;
+        json_response(StatusCode::ACCEPTED, serde_json::json!({}))
+    }
+
+    fn link_user(&self, discord_id: u64, body: Body) -> ResponseFuture {
+        let redis_client = self.redis_client.clone();
+        let discord_api = self.discord_api.clone();
+        let async_meetup_client = self.async_meetup_client.clone();
+        let future = body.concat2().from_err::<crate::BoxedError>().and_then(
+            move |body| -> ResponseFuture {
+                let meetup_id = match serde_json::from_slice::<LinkRequest>(&body) {
+                    Ok(link_request) => link_request.meetup_id,
+                    Err(err) => {
+                        return Box::new(future::result(error_response(
+                            StatusCode::BAD_REQUEST,
+                            &format!("Invalid request body: {}", err),
+                        )))
+                    }
+                };
+                let mut redis_connection = match redis_client.get_connection() {
+                    Ok(redis_connection) => redis_connection,
+                    Err(err) => return Box::new(future::err(err.into())),
+                };
+                let link_conflict = match crate::discord_bot::Handler::meetup_link_conflict(
+                    discord_id,
+                    meetup_id,
+                    &mut redis_connection,
+                ) {
+                    Ok(link_conflict) => link_conflict,
+                    Err(err) => return Box::new(future::err(err)),
+                };
+                match link_conflict {
+                    Some(crate::discord_bot_commands::MeetupLinkConflict::DiscordUserLinked {
+                        meetup_id: linked_meetup_id,
+                    }) => {
+                        return Box::new(future::result(json_response(
+                            StatusCode::CONFLICT,
+                            serde_json::json!({
+                                "error": "The Discord user is already linked",
+                                "meetup_id": linked_meetup_id,
+                            }),
+                        )))
+                    }
+                    Some(crate::discord_bot_commands::MeetupLinkConflict::MeetupUserLinked {
+                        discord_id: linked_discord_id,
+                    }) => {
+                        return Box::new(future::result(json_response(
+                            StatusCode::CONFLICT,
+                            serde_json::json!({
+                                "error": "The Meetup account is already linked",
+                                "discord_id": linked_discord_id,
+                            }),
+                        )))
+                    }
+                    None => (),
+                }
+                // Test whether the specified Meetup user actually exists
+                let async_meetup_client = match *async_meetup_client.read() {
+                    Some(ref async_meetup_client) => async_meetup_client.clone(),
+                    None => {
+                        return Box::new(future::result(error_response(
+                            StatusCode::SERVICE_UNAVAILABLE,
+                            "Meetup API unavailable",
+                        )))
+                    }
+                };
+                Box::new(
+                    async_meetup_client
+                        .get_member_profile(Some(meetup_id))
+                        .from_err::<crate::BoxedError>()
+                        .and_then(move |meetup_user| {
+                            if meetup_user.is_none() {
+                                return error_response(
+                                    StatusCode::NOT_FOUND,
+                                    "Meetup profile not found",
+                                );
+                            }
+                            let successful = crate::discord_bot::Handler::store_meetup_link(
+                                discord_id,
+                                meetup_id,
+                                &mut redis_connection,
+                                &discord_api,
+                            )?;
+                            if successful {
+                                json_response(
+                                    StatusCode::OK,
+                                    serde_json::json!({
+                                        "discord_id": discord_id,
+                                        "meetup_id": meetup_id,
+                                    }),
+                                )
+                            } else {
+                                error_response(
+                                    StatusCode::CONFLICT,
+                                    "One of the accounts was linked in the meantime",
+                                )
+                            }
+                        }),
+                )
+            },
+        );
+        Box::new(future)
+    }
+
+    fn unlink_user(&self, discord_id: u64) -> Result<Response<Body>, crate::BoxedError> {
+        let mut redis_connection = self.redis_client.get_connection()?;
+        let unlinked_meetup_id = crate::discord_bot::Handler::remove_meetup_link(
+            discord_id,
+            &mut redis_connection,
+            Some(&self.discord_api),
+        )?;
+        match unlinked_meetup_id {
+            Some(meetup_id) => json_response(
+                StatusCode::OK,
+                serde_json::json!({
+                    "discord_id": discord_id,
+                    "meetup_id": meetup_id,
+                }),
+            ),
+            None => error_response(StatusCode::NOT_FOUND, "No Meetup account is linked"),
+        }
+    }
+
+    fn close_channel(&self, channel_id: ChannelId) -> Result<Response<Body>, crate::BoxedError> {
+        let mut redis_connection = self.redis_client.get_connection()?;
+        let channel_access =
+            crate::discord_bot::Handler::get_channel_access(channel_id.0, &mut redis_connection)?;
+        if channel_access.is_none() {
+            return error_response(StatusCode::NOT_FOUND, "Not a bot controlled channel");
+        }
+        if !crate::discord_bot::Handler::is_channel_closeable(channel_id, &mut redis_connection)? {
+            return error_response(
+                StatusCode::CONFLICT,
+                "The channel can't be closed before its expiration time",
+            );
+        }
+        if !crate::discord_bot::Handler::mark_channel_for_closing(
+            channel_id,
+            &mut redis_connection,
+        )? {
+            return error_response(
+                StatusCode::CONFLICT,
+                "The channel is already marked for closing",
+            );
+        }
+        // Let the channel know, like after the command
+        let _ = channel_id.say(
+            self.discord_api.http(),
+            crate::strings::CHANNEL_MARKED_FOR_CLOSING,
+        );
+        json_response(
+            StatusCode::OK,
+            serde_json::json!({ "channel_id": channel_id.0 }),
+        )
+    }
+}
@@ -0,0 +1,220 @@
+use redis::Commands;
+use std::env;
+use std::sync::Arc;
+use swissrpg_discord_bot::secrets::exit_on_config_error;
+use swissrpg_discord_bot::{
+    discord_bot, identity, meetup_api, meetup_backfill, redis_benchmark, redis_dump,
+    redis_namespace, secrets, token_encryption, vacuum,
+};
+
+// Maintenance tool that works on the bot's Redis database directly, e.g. while
+// the bot is stopped. It reads the same configuration as the bot
+// (REDIS_URL, REDIS_KEY_PREFIX, ...). Commands that need to talk to Discord
+// also need DISCORD_TOKEN.
+const USAGE: &'static str = "Usage: botctl <command>
+
+Commands:
+    orphans list                  List the channels and roles the bot could not delete
+    orphans fix                   Delete them from Discord (needs DISCORD_TOKEN)
+    series dump <series ID>       Print all Redis keys of an event series as JSON
+    user unlink <Discord ID>      Unlink a user's Meetup account (and remove their
+                                  linked role if DISCORD_TOKEN is set)
+    import past-events <group>    Import the past events of a Meetup group (by
+                                  urlname) as historical series
+    migrate redis-prefix          Move all keys into the namespace REDIS_KEY_PREFIX
+    migrate encrypt-tokens        Encrypt the OAuth2 tokens stored in plain text
+    migrate identities            Move the Meetup and email links to identities (the
+                                  bot also does this when it starts)
+    benchmark redis-round-trips   Measure the Redis round trips of the Discord sync";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let redis_url = exit_on_config_error(secrets::load_or(
+        "REDIS_URL",
+        "redis://127.0.0.1/",
+        secrets::validate_redis_url,
+    ));
+    let redis_key_prefix = env::var("REDIS_KEY_PREFIX").unwrap_or_default();
+    let redis_client = redis_namespace::Client::open(&redis_url, &redis_key_prefix)
+        .expect("Could not create a Redis client");
+    let result = match args.as_slice() {
+        ["orphans", "list"] => list_orphans(&redis_client),
+        ["orphans", "fix"] => fix_orphans(&redis_client),
+        ["series", "dump", series_id] => dump_series(&redis_client, series_id),
+        ["user", "unlink", discord_id] => unlink_user(&redis_client, discord_id),
+        ["import", "past-events", urlname] => import_past_events(&redis_client, urlname),
+        ["migrate", "redis-prefix"] => {
+            redis_namespace::migrate_to_prefix(&redis_url, &redis_key_prefix).map(|num_migrated| {
+                println!(
+                    "Moved {} Redis keys into the namespace \"{}\"",
+                    num_migrated, redis_key_prefix
+                )
+            })
+        }
+        ["migrate", "encrypt-tokens"] => encrypt_tokens(&redis_client),
+        ["migrate", "identities"] => migrate_identities(&redis_client),
+        ["benchmark", "redis-round-trips"] => benchmark_redis(&redis_client),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+// Only a REST client, the cache stays empty
+fn discord_api() -> Option<discord_bot::CacheAndHttp> {
+    let discord_token = exit_on_config_error(secrets::load_optional(
+        "DISCORD_TOKEN",
+        secrets::validate_discord_token,
+    ))?;
+    Some(discord_bot::CacheAndHttp {
+        cache: Default::default(),
+        http: Arc::new(serenity::http::raw::Http::new_with_token(&format!(
+            "Bot {}",
+            discord_token
+        ))),
+    })
+}
+
+fn list_orphans(
+    redis_client: &redis_namespace::Client,
+) -> Result<(), swissrpg_discord_bot::BoxedError> {
+    let mut redis_connection = redis_client.get_connection()?;
+    for channel_id in vacuum::orphaned_channel_ids(&mut redis_connection)? {
+        println!("channel {}", channel_id);
+    }
+    for role_id in vacuum::orphaned_role_ids(&mut redis_connection)? {
+        println!("role {}", role_id);
+    }
+    Ok(())
+}
+
+fn fix_orphans(
+    redis_client: &redis_namespace::Client,
+) -> Result<(), swissrpg_discord_bot::BoxedError> {
+    let discord_api = match discord_api() {
+        Some(discord_api) => discord_api,
+        None => {
+            eprintln!("Deleting orphans needs DISCORD_TOKEN");
+            std::process::exit(1);
+        }
+    };
+    let mut redis_connection = redis_client.get_connection()?;
+    let num_channels = vacuum::delete_orphaned_channels(&mut redis_connection, &discord_api)?;
+    let num_roles = vacuum::delete_orphaned_roles(&mut redis_connection, &discord_api)?;
+    println!(
+        "Deleted {} orphaned channels and {} orphaned roles",
+        num_channels, num_roles
+    );
+    Ok(())
+}
+
+fn dump_series(
+    redis_client: &redis_namespace::Client,
+    series_id: &str,
+) -> Result<(), swissrpg_discord_bot::BoxedError> {
+    let mut redis_connection = redis_client.get_connection()?;
+    let keys = redis_dump::series_keys(series_id, &mut redis_connection)?;
+    if keys.is_empty() {
+        eprintln!("Found no keys for the series {}", series_id);
+        std::process::exit(1);
+    }
+    let dump = redis_dump::dump_keys(&keys, &mut redis_connection)?;
+    println!("{}", serde_json::to_string_pretty(&dump)?);
+    Ok(())
+}
+
+fn unlink_user(
+    redis_client: &redis_namespace::Client,
+    discord_id: &str,
+) -> Result<(), swissrpg_discord_bot::BoxedError> {
+    let discord_id: u64 = discord_id.parse()?;
+    let discord_api = discord_api();
+    if discord_api.is_none() {
+        println!("DISCORD_TOKEN is not set, the linked role stays");
+    }
+    let mut redis_connection = redis_client.get_connection()?;
+    match discord_bot::Handler::remove_meetup_link(
+        discord_id,
+        &mut redis_connection,
+        discord_api.as_ref(),
+    )? {
+        Some(meetup_id) => println!(
+            "Unlinked the Meetup account {} from user {}",
+            meetup_id, discord_id
+        ),
+        None => println!("User {} has no linked Meetup account", discord_id),
+    }
+    Ok(())
+}
+
+// Uses the organizer's Meetup access token, like the bot
+fn import_past_events(
+    redis_client: &redis_namespace::Client,
+    urlname: &str,
+) -> Result<(), swissrpg_discord_bot::BoxedError> {
+    let mut redis_connection = redis_client.get_connection()?;
+    let meetup_access_token: Option<String> = redis_connection.get("meetup_access_token")?;
+    let meetup_access_token = match meetup_access_token {
+        Some(meetup_access_token) => token_encryption::decrypt(&meetup_access_token)?,
+        None => {
+            eprintln!("There is no Meetup access token yet, an organizer needs to log in first");
+            std::process::exit(1);
+        }
+    };
+    let meetup_client = meetup_api::Client::new(&meetup_access_token);
+    let summary =
+        meetup_backfill::import_past_events(&meetup_client, urlname, &mut redis_connection)?;
+    println!(
+        "Imported {} events into {} new series ({} events were already known, {} were ignored)",
+        summary.num_imported_events,
+        summary.num_new_series,
+        summary.num_known_events,
+        summary.num_ignored_events
+    );
+    Ok(())
+}
+
+fn encrypt_tokens(
+    redis_client: &redis_namespace::Client,
+) -> Result<(), swissrpg_discord_bot::BoxedError> {
+    let mut redis_connection = redis_client.get_connection()?;
+    let num_encrypted = token_encryption::migrate(&mut redis_connection)?;
+    println!("Encrypted {} tokens", num_encrypted);
+    Ok(())
+}
+
+fn migrate_identities(
+    redis_client: &redis_namespace::Client,
+) -> Result<(), swissrpg_discord_bot::BoxedError> {
+    let mut redis_connection = redis_client.get_connection()?;
+    let num_migrated = identity::migrate(&mut redis_connection)?;
+    println!("Migrated {} account links to identities", num_migrated);
+    Ok(())
+}
+
+// Only reads from Redis
+fn benchmark_redis(
+    redis_client: &redis_namespace::Client,
+) -> Result<(), swissrpg_discord_bot::BoxedError> {
+    let redis_connection = redis_client.get_connection()?;
+    let result = redis_benchmark::run(redis_connection)?;
+    println!(
+        "Read the role assignments of {} users in {} reads",
+        result.num_users, result.num_reads
+    );
+    println!(
+        "Unbatched: {} round trips in {} ms",
+        result.unbatched_round_trips, result.unbatched_ms
+    );
+    println!(
+        "Batched: {} round trips in {} ms",
+        result.batched_round_trips, result.batched_ms
+    );
+    Ok(())
+}
@@ -0,0 +1,308 @@
+use rand::Rng;
+use redis::{Commands, PipelineCommands};
+use serenity::model::id::ChannelId;
+
+// Dice rolls for the RPG channels: "roll 3d6+2", optionally with advantage or
+// disadvantage (the whole roll is made twice and the higher or lower total is
+// kept). With "gmroll", the result is sent via DM instead, which hosts can use
+// for rolls the players shouldn't see.
+// Public rolls in a channel are kept in a short per-channel log.
+pub const MAX_DICE: u32 = 100;
+pub const MAX_SIDES: u32 = 1000;
+const MAX_MODIFIER: i64 = 10000;
+const MAX_TERMS: usize = 10;
+const ROLL_LOG_LENGTH: isize = 50;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Mode {
+    Normal,
+    Advantage,
+    Disadvantage,
+}
+
+impl Mode {
+    pub fn from_name(name: Option<&str>) -> Mode {
+        match name.map(|name| name.to_lowercase()) {
+            Some(ref name) if name.starts_with("adv") => Mode::Advantage,
+            Some(ref name) if name.starts_with("dis") => Mode::Disadvantage,
+            _ => Mode::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Term {
+    // The sign is -1 if the dice are subtracted
+    Dice {
+        sign: i64,
+        num_dice: u32,
+        sides: u32,
+    },
+    Modifier(i64),
+}
+
+// A single evaluation of the expression
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub total: i64,
+    // E.g. "[4, 1, 6] + 2"
+    pub details: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Roll {
+    // The normalized expression, e.g. "3d6+2"
+    pub expression: String,
+    pub mode: Mode,
+    pub kept: Outcome,
+    // The other outcome of a roll with advantage or disadvantage
+    pub discarded: Option<Outcome>,
+}
+
+// Accepts sums of dice ("d20", "3d6") and numbers, e.g. "2d6 + 1d4 - 1".
+// Returns None if the expression is invalid or exceeds the limits.
+fn parse(expression: &str) -> Option<Vec<Term>> {
+    let expression: String = expression
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    if expression.is_empty() {
+        return None;
+    }
+    let mut terms = vec![];
+    let mut total_dice: u32 = 0;
+    // Split into signed terms, keeping the sign with the term
+    let mut rest = expression.as_str();
+    while !rest.is_empty() {
+        let (sign, term_start) = match rest.chars().next() {
+            Some('+') => (1, 1),
+            Some('-') => (-1, 1),
+            _ if terms.is_empty() => (1, 0),
+            _ => return None,
+        };
+        let term_end = rest[term_start..]
+            .find(|c| c == '+' || c == '-')
+            .map(|index| index + term_start)
+            .unwrap_or(rest.len());
+        let term = &rest[term_start..term_end];
+        rest = &rest[term_end..];
+        if terms.len() >= MAX_TERMS {
+            return None;
+        }
+        if let Some(d_index) = term.find('d') {
+            let num_dice = match &term[..d_index] {
+                "" => 1,
+                num_dice => num_dice.parse::<u32>().ok()?,
+            };
+            let sides = term[d_index + 1..].parse::<u32>().ok()?;
+            // Each term is checked on its own first, such that the sum can't
+            // overflow
+            if num_dice == 0 || num_dice > MAX_DICE || sides < 2 || sides > MAX_SIDES {
+                return None;
+            }
+            total_dice = num_dice.checked_add(total_dice)?;
+            if total_dice > MAX_DICE {
+                return None;
+            }
+            terms.push(Term::Dice {
+                sign,
+                num_dice,
+                sides,
+            });
+        } else {
+            let modifier = term.parse::<i64>().ok()?;
+            if modifier > MAX_MODIFIER {
+                return None;
+            }
+            terms.push(Term::Modifier(sign * modifier));
+        }
+    }
+    // At least one die, otherwise this is just arithmetic
+    if !terms.iter().any(|term| match term {
+        Term::Dice { .. } => true,
+        Term::Modifier(_) => false,
+    }) {
+        return None;
+    }
+    Some(terms)
+}
+
+fn normalize(terms: &[Term]) -> String {
+    let mut expression = String::new();
+    for (i, term) in terms.iter().enumerate() {
+        let (sign, term) = match *term {
+            Term::Dice {
+                sign,
+                num_dice,
+                sides,
+            } => (sign, format!("{}d{}", num_dice, sides)),
+            Term::Modifier(modifier) => (modifier.signum(), modifier.abs().to_string()),
+        };
+        if sign < 0 {
+            expression.push('-');
+        } else if i > 0 {
+            expression.push('+');
+        }
+        expression.push_str(&term);
+    }
+    expression
+}
+
+fn evaluate<R: Rng>(terms: &[Term], rng: &mut R) -> Outcome {
+    let mut total = 0;
+    let mut details = String::new();
+    for (i, term) in terms.iter().enumerate() {
+        let (sign, term_total, term_details) = match *term {
+            Term::Dice {
+                sign,
+                num_dice,
+                sides,
+            } => {
+                let results: Vec<i64> = (0..num_dice)
+                    .map(|_| rng.gen_range(1, sides as i64 + 1))
+                    .collect();
+                let term_details = format!(
+                    "[{}]",
+                    results
+                        .iter()
+                        .map(|result| result.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                (sign, results.iter().sum::<i64>(), term_details)
+            }
+            Term::Modifier(modifier) => (
+                modifier.signum(),
+                modifier.abs(),
+                modifier.abs().to_string(),
+            ),
+        };
+        if sign < 0 {
+            details.push_str(if i > 0 { " - " } else { "-" });
+        } else if i > 0 {
+            details.push_str(" + ");
+        }
+        details.push_str(&term_details);
+        total += sign * term_total;
+    }
+    Outcome { total, details }
+}
+
+pub fn roll(expression: &str, mode: Mode) -> Option<Roll> {
+    let terms = parse(expression)?;
+    let mut rng = rand::thread_rng();
+    let first = evaluate(&terms, &mut rng);
+    let (kept, discarded) = match mode {
+        Mode::Normal => (first, None),
+        Mode::Advantage | Mode::Disadvantage => {
+            let second = evaluate(&terms, &mut rng);
+            let first_is_kept = if mode == Mode::Advantage {
+                first.total >= second.total
+            } else {
+                first.total <= second.total
+            };
+            if first_is_kept {
+                (first, Some(second))
+            } else {
+                (second, Some(first))
+            }
+        }
+    };
+    Some(Roll {
+        expression: normalize(&terms),
+        mode,
+        kept,
+        discarded,
+    })
+}
+
+fn redis_roll_log_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:roll_log", channel_id.0)
+}
+
+// Most recent first
+pub fn log_roll<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    discord_id: u64,
+    roll: &Roll,
+) -> Result<(), crate::BoxedError> {
+    let redis_roll_log_key = redis_roll_log_key(channel_id);
+    let entry = crate::strings::ROLL_LOG_ENTRY(chrono::Utc::now(), discord_id, roll);
+    let _: () = redis::pipe()
+        .lpush(&redis_roll_log_key, entry)
+        .ignore()
+        .ltrim(&redis_roll_log_key, 0, ROLL_LOG_LENGTH - 1)
+        .ignore()
+        .query(con)?;
+    Ok(())
+}
+
+pub fn roll_log<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<Vec<String>, crate::BoxedError> {
+    Ok(con.lrange(redis_roll_log_key(channel_id), 0, -1)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sums_of_dice_and_modifiers() {
+        assert_eq!(
+            parse("2d6 + D4 - 1"),
+            Some(vec![
+                Term::Dice {
+                    sign: 1,
+                    num_dice: 2,
+                    sides: 6
+                },
+                Term::Dice {
+                    sign: 1,
+                    num_dice: 1,
+                    sides: 4
+                },
+                Term::Modifier(-1),
+            ])
+        );
+        assert_eq!(normalize(&parse("2d6 + D4 - 1").unwrap()), "2d6+1d4-1");
+    }
+
+    #[test]
+    fn rejects_zero_dice_and_sides() {
+        assert_eq!(parse("0d6"), None);
+        assert_eq!(parse("1d0"), None);
+        assert_eq!(parse("1d1"), None);
+        assert_eq!(parse("5"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn rejects_terms_over_the_limits() {
+        assert!(parse(&format!("{}d{}", MAX_DICE, MAX_SIDES)).is_some());
+        assert_eq!(parse(&format!("{}d6", MAX_DICE + 1)), None);
+        assert_eq!(parse(&format!("1d{}", MAX_SIDES + 1)), None);
+        assert_eq!(parse(&format!("60d6+{}d6", MAX_DICE - 59)), None);
+        assert_eq!(parse(&format!("1d6+{}", MAX_MODIFIER + 1)), None);
+        assert_eq!(parse(&vec!["1d6"; MAX_TERMS + 1].join("+")), None);
+        assert_eq!(parse("99999999999999999999d6"), None);
+    }
+
+    #[test]
+    fn rejects_dice_counts_that_overflow() {
+        // Wraps around to 0 in u32
+        assert_eq!(parse("2147483648d6+2147483648d6"), None);
+        assert_eq!(parse("4294967295d6+1d6"), None);
+    }
+
+    #[test]
+    fn rolls_within_the_range() {
+        let terms = parse(&format!("{}d{}+3", MAX_DICE, MAX_SIDES)).unwrap();
+        let outcome = evaluate(&terms, &mut rand::thread_rng());
+        assert!(outcome.total >= MAX_DICE as i64 + 3);
+        assert!(outcome.total <= (MAX_DICE * MAX_SIDES) as i64 + 3);
+    }
+}
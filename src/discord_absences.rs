@@ -0,0 +1,92 @@
+use redis::{Commands, PipelineCommands};
+use serenity::model::id::ChannelId;
+use std::collections::HashMap;
+
+// Players can let the hosts know that they can't make it to the next session
+// with the "absent" command. Absences belong to the session (Meetup event),
+// so they show up in the member export until the session is over and then
+// expire by themselves.
+pub const MAX_REASON_CHARS: usize = 200;
+// Absences are kept for a bit after the session, in case the hosts want to
+// look them up afterwards
+const RETENTION_HOURS: i64 = 12;
+
+fn redis_event_absences_key(event_id: &str) -> String {
+    format!("meetup_event:{}:absences", event_id)
+}
+
+// The next upcoming session (event ID and time) of the channel's series
+pub fn next_session(
+    channel_id: ChannelId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<Option<(String, chrono::DateTime<chrono::Utc>)>, crate::BoxedError> {
+    let redis_channel_series_key = format!("discord_channel:{}:event_series", channel_id.0);
+    let series_id: Option<String> = redis_connection.get(&redis_channel_series_key)?;
+    let series_id = match series_id {
+        Some(series_id) => series_id,
+        None => return Ok(None),
+    };
+    let redis_series_events_key = format!("event_series:{}:meetup_events", series_id);
+    let event_ids: Vec<String> = redis_connection.smembers(&redis_series_events_key)?;
+    let now = chrono::Utc::now();
+    let mut next_session = None;
+    for event_id in event_ids {
+        let time: Option<String> =
+            redis_connection.hget(format!("meetup_event:{}", event_id), "time")?;
+        if let Some(time) = time {
+            let time = chrono::DateTime::parse_from_rfc3339(&time)?.with_timezone(&chrono::Utc);
+            let is_earlier = match &next_session {
+                Some((_, next_time)) => time < *next_time,
+                None => true,
+            };
+            if time > now && is_earlier {
+                next_session = Some((event_id, time));
+            }
+        }
+    }
+    Ok(next_session)
+}
+
+// Marks the user as absent from the next session. Returns the time of that
+// session, or None if there is no upcoming session.
+pub fn mark_absent(
+    channel_id: ChannelId,
+    discord_id: u64,
+    reason: Option<&str>,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, crate::BoxedError> {
+    let (event_id, event_time) = match next_session(channel_id, redis_connection)? {
+        Some(next_session) => next_session,
+        None => return Ok(None),
+    };
+    let redis_event_absences_key = redis_event_absences_key(&event_id);
+    let reason: String = reason
+        .unwrap_or("")
+        .trim()
+        .chars()
+        .take(MAX_REASON_CHARS)
+        .collect();
+    let expiration_time = event_time + chrono::Duration::hours(RETENTION_HOURS);
+    let _: () = redis::pipe()
+        .hset(&redis_event_absences_key, discord_id, reason)
+        .ignore()
+        .expire_at(
+            &redis_event_absences_key,
+            expiration_time.timestamp() as usize,
+        )
+        .ignore()
+        .query(redis_connection)?;
+    Ok(Some(event_time))
+}
+
+// The absences for the next session, mapping Discord IDs to the (possibly
+// empty) reason
+pub fn next_session_absences(
+    channel_id: ChannelId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<HashMap<u64, String>, crate::BoxedError> {
+    match next_session(channel_id, redis_connection)? {
+        Some((event_id, _)) => Ok(redis_connection.hgetall(redis_event_absences_key(&event_id))?),
+        None => Ok(HashMap::new()),
+    }
+}
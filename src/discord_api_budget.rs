@@ -0,0 +1,78 @@
+// The Discord sync shares Discord's rate limits with the commands that
+// members use, so a large sync could leave the commands waiting for minutes.
+// Each sync therefore gets a budget of Discord API calls. Calls that commands
+// make in the meantime count against it as well (the counter in sync_stats is
+// global), so the sync backs off while the server is busy. Once
+// LOW_PRIORITY_PERCENT of the budget is used, cosmetic steps (topics, channel
+// names, role colours and positions) are deferred, and once the budget is
+// used up the remaining series and guild-wide steps are left for the next
+// sync. Series with deferred work are marked dirty, such that the next sync
+// picks them up, and the recurring sync runs again after
+// DEFERRED_RETRY_MINUTES instead of its regular interval.
+pub const MAX_API_CALLS_PER_SYNC: u64 = 2000;
+const LOW_PRIORITY_PERCENT: u64 = 75;
+pub const DEFERRED_RETRY_MINUTES: i64 = 5;
+
+// Keeps track of how many Discord API calls the current sync has used
+pub struct ApiBudget {
+    start_calls: usize,
+    deferred_steps: u64,
+    deferred_series: u64,
+}
+
+impl ApiBudget {
+    pub fn new() -> Self {
+        ApiBudget {
+            start_calls: crate::sync_stats::discord_api_calls(),
+            deferred_steps: 0,
+            deferred_series: 0,
+        }
+    }
+
+    pub fn num_used(&self) -> u64 {
+        crate::sync_stats::discord_api_calls().saturating_sub(self.start_calls) as u64
+    }
+
+    // Returns whether a cosmetic step may still run, otherwise counts it as
+    // deferred
+    pub fn allows_low_priority(&mut self) -> bool {
+        if self.num_used() * 100 >= MAX_API_CALLS_PER_SYNC * LOW_PRIORITY_PERCENT {
+            self.deferred_steps += 1;
+            return false;
+        }
+        true
+    }
+
+    // Returns whether a guild-wide step after the series (e.g. the cleanups)
+    // may still run, otherwise counts it as deferred
+    pub fn allows_step(&mut self) -> bool {
+        if self.num_used() >= MAX_API_CALLS_PER_SYNC {
+            self.deferred_steps += 1;
+            return false;
+        }
+        true
+    }
+
+    // Returns whether another series may be synced, otherwise counts it as
+    // deferred
+    pub fn allows_series(&mut self) -> bool {
+        if self.num_used() >= MAX_API_CALLS_PER_SYNC {
+            self.deferred_series += 1;
+            return false;
+        }
+        true
+    }
+
+    // How many cosmetic and guild-wide steps this sync deferred so far
+    pub fn num_deferred_steps(&self) -> u64 {
+        self.deferred_steps
+    }
+
+    pub fn num_deferred_series(&self) -> u64 {
+        self.deferred_series
+    }
+
+    pub fn has_deferred(&self) -> bool {
+        self.deferred_steps > 0 || self.deferred_series > 0
+    }
+}
@@ -2,19 +2,23 @@ use crate::strings;
 use futures::Future;
 use serenity::{
     model::{
-        channel::Channel, channel::Message, gateway::Ready, guild::Member, id::GuildId, id::UserId,
+        channel::Channel, channel::GuildChannel, channel::Message, channel::Reaction,
+        event::MessageUpdateEvent, gateway::Ready, guild::Guild, guild::Member, guild::Role,
+        id::GuildId, id::RoleId, id::UserId,
     },
     prelude::*,
 };
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::prelude::*;
 
 pub fn create_discord_client(
     discord_token: &str,
-    redis_client: redis::Client,
+    redis_client: crate::redis_namespace::Client,
     meetup_client: Arc<RwLock<Option<crate::meetup_api::Client>>>,
     async_meetup_client: Arc<RwLock<Option<crate::meetup_api::AsyncClient>>>,
+    event_source: crate::event_source::SharedEventSource,
     task_scheduler: Arc<Mutex<white_rabbit::Scheduler>>,
     futures_spawner: futures::sync::mpsc::Sender<crate::meetup_sync::BoxedFuture<(), ()>>,
 ) -> crate::Result<Client> {
@@ -44,6 +48,7 @@ pub fn create_discord_client(
         data.insert::<RegexesKey>(Arc::new(regexes));
         data.insert::<MeetupClientKey>(meetup_client);
         data.insert::<AsyncMeetupClientKey>(async_meetup_client);
+        data.insert::<EventSourceKey>(event_source);
         data.insert::<RedisClientKey>(redis_client);
         data.insert::<TaskSchedulerKey>(task_scheduler);
         data.insert::<FuturesSpawnerKey>(futures_spawner);
@@ -64,7 +69,7 @@ impl TypeMapKey for BotNameKey {
 
 pub struct RedisConnectionKey;
 impl TypeMapKey for RedisConnectionKey {
-    type Value = Arc<Mutex<redis::Connection>>;
+    type Value = Arc<Mutex<crate::redis_namespace::Connection>>;
 }
 
 pub struct RegexesKey;
@@ -82,9 +87,14 @@ impl TypeMapKey for AsyncMeetupClientKey {
     type Value = Arc<RwLock<Option<crate::meetup_api::AsyncClient>>>;
 }
 
+pub struct EventSourceKey;
+impl TypeMapKey for EventSourceKey {
+    type Value = crate::event_source::SharedEventSource;
+}
+
 pub struct RedisClientKey;
 impl TypeMapKey for RedisClientKey {
-    type Value = redis::Client;
+    type Value = crate::redis_namespace::Client;
 }
 
 pub struct TaskSchedulerKey;
@@ -97,6 +107,9 @@ impl TypeMapKey for FuturesSpawnerKey {
     type Value = futures::sync::mpsc::Sender<crate::meetup_sync::BoxedFuture<(), ()>>;
 }
 
+// All Discord API requests of the syncing tasks go through `http()`, which
+// counts them for the sync statistics and lets commands go first (see
+// discord_call_priority)
 #[derive(Clone)]
 pub struct CacheAndHttp {
     pub cache: serenity::cache::CacheRwLock,
@@ -108,6 +121,8 @@ impl serenity::http::CacheHttp for CacheAndHttp {
         Some(&self.cache)
     }
     fn http(&self) -> &serenity::http::raw::Http {
+        crate::discord_call_priority::wait_for_turn();
+        crate::sync_stats::record_discord_api_call();
         &self.http
     }
 }
@@ -117,19 +132,187 @@ impl serenity::http::CacheHttp for &CacheAndHttp {
         Some(&self.cache)
     }
     fn http(&self) -> &serenity::http::raw::Http {
+        crate::discord_call_priority::wait_for_turn();
+        crate::sync_stats::record_discord_api_call();
         &self.http
     }
 }
 
 pub struct Handler;
 
+static BOOT_SEQUENCE_STARTED: AtomicBool = AtomicBool::new(false);
+
+impl Handler {
+    // Catches up on everything that might have been missed while the bot was offline:
+    // - verifies that the organizer's Meetup token still works
+    // - runs overdue channel deletions and expiration reminders
+    // - puts the commands scheduled by organizers back into the scheduler
+    // - runs a full Meetup sync followed by a Discord sync, which then keeps
+    //   recurring at the configured interval
+    fn run_boot_sequence(ctx: &Context) {
+        // Only one instance needs to catch up
+        if !crate::leader_election::is_leader() {
+            println!("Not the leader, skipping the boot sequence");
+            return;
+        }
+        println!("Running the boot sequence");
+        let (meetup_client, event_source, redis_client, bot_id, task_scheduler, mut future_spawner) = {
+            let data = ctx.data.read();
+            (
+                data.get::<MeetupClientKey>()
+                    .expect("Meetup client was not set")
+                    .clone(),
+                data.get::<EventSourceKey>()
+                    .expect("Event source was not set")
+                    .clone(),
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone(),
+                *data.get::<BotIdKey>().expect("Bot ID was not set"),
+                data.get::<TaskSchedulerKey>()
+                    .expect("Task scheduler was not set")
+                    .clone(),
+                data.get::<FuturesSpawnerKey>()
+                    .expect("Future spawner was not set")
+                    .clone(),
+            )
+        };
+        let discord_api = CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        // Step 1: Verify the organizer token. This waits for Meetup, so it
+        // runs on the scheduler rather than holding up the ready event.
+        {
+            let discord_api = discord_api.clone();
+            let redis_client = redis_client.clone();
+            task_scheduler
+                .lock()
+                .add_task_datetime(white_rabbit::Utc::now(), move |_| {
+                    Self::verify_organizer_token(&meetup_client, &redis_client, &discord_api);
+                    white_rabbit::DateResult::Done
+                });
+        }
+        // Step 2: Run overdue channel deletions and expiration reminders
+        if let Err(err) = crate::persistent_tasks::schedule_task(
+            &task_scheduler,
+            &redis_client,
+            &discord_api,
+            bot_id.0,
+            crate::persistent_tasks::TaskType::EndOfGame,
+            white_rabbit::Utc::now(),
+            /*recurring*/ false,
+        ) {
+            eprintln!(
+                "Boot sequence: could not schedule the end of game task: {}",
+                err
+            );
+        }
+        // Overdue scheduled commands are run right away as well
+        if let Err(err) = crate::scheduled_commands::rehydrate(ctx, &redis_client) {
+            eprintln!(
+                "Boot sequence: could not rehydrate the scheduled commands: {}",
+                err
+            );
+        }
+        // Step 3: Run a full Meetup sync and sync Discord once it is done.
+        // The recurring Discord sync is brought forward for that (replacing
+        // the one scheduled at startup) and settles into its interval after.
+        let sync_task = Box::new(
+            crate::meetup_sync::sync_task(event_source, redis_client.clone())
+                .timeout(Duration::from_secs(360))
+                .then(move |res| {
+                    if let Err(err) = res {
+                        eprintln!("Boot sequence: Meetup sync failed: {}", err);
+                    }
+                    // Sync Discord even if the Meetup sync failed, since
+                    // there might be changes from before the restart. Every
+                    // series is dirty for that, the recurring sync leaves the
+                    // ones over its API budget to the following runs.
+                    let mark_result = redis_client
+                        .get_connection()
+                        .map_err(crate::BoxedError::from)
+                        .and_then(|mut con| crate::discord_sync::mark_all_series_dirty(&mut con));
+                    if let Err(err) = mark_result {
+                        eprintln!(
+                            "Boot sequence: could not mark the series for the Discord sync: {}",
+                            err
+                        );
+                    }
+                    if let Err(err) = crate::persistent_tasks::schedule_task(
+                        &task_scheduler,
+                        &redis_client,
+                        &discord_api,
+                        bot_id.0,
+                        crate::persistent_tasks::TaskType::SyncDiscord,
+                        white_rabbit::Utc::now(),
+                        /*recurring*/ true,
+                    ) {
+                        eprintln!(
+                            "Boot sequence: could not schedule the Discord sync: {}",
+                            err
+                        );
+                    }
+                    Ok(())
+                }),
+        );
+        if let Err(err) = future_spawner.try_send(sync_task) {
+            eprintln!(
+                "Boot sequence: could not submit the Meetup sync (full={}, disconnected={})",
+                err.is_full(),
+                err.is_disconnected()
+            );
+        }
+    }
+
+    // Lets the organizers know if the Meetup token is missing or doesn't
+    // work anymore
+    fn verify_organizer_token(
+        meetup_client: &Arc<RwLock<Option<crate::meetup_api::Client>>>,
+        redis_client: &crate::redis_namespace::Client,
+        discord_api: &CacheAndHttp,
+    ) {
+        let token_is_valid = match *meetup_client.read() {
+            Some(ref meetup_client) => match meetup_client.get_member_profile(None) {
+                Ok(Some(_)) => true,
+                Ok(None) => false,
+                Err(err) => {
+                    eprintln!(
+                        "Boot sequence: could not verify the organizer token: {}",
+                        err
+                    );
+                    false
+                }
+            },
+            None => false,
+        };
+        if token_is_valid {
+            return;
+        }
+        eprintln!("Boot sequence: the organizer's Meetup token is missing or invalid");
+        let notify_result = redis_client
+            .get_connection()
+            .map_err(crate::BoxedError::from)
+            .and_then(|mut con| {
+                crate::discord_notifications::notify_organizers(
+                    discord_api,
+                    strings::ORGANIZER_TOKEN_INVALID_NOTIFICATION,
+                    &mut con,
+                )
+            });
+        if let Err(err) = notify_result {
+            eprintln!("Boot sequence: could not notify the organizers: {}", err);
+        }
+    }
+}
+
 impl EventHandler for Handler {
     // Set a handler for the `message` event - so that whenever a new message
     // is received - the closure (or function) passed will be called.
     //
     // Event handlers are dispatched through a threadpool, and so multiple
     // events can be dispatched simultaneously.
-    fn message(&self, ctx: Context, msg: Message) {
+    fn message(&self, ctx: Context, mut msg: Message) {
         let (bot_id, regexes) = {
             let data = ctx.data.read();
             let regexes = data
@@ -139,10 +322,31 @@ impl EventHandler for Handler {
             let bot_id = data.get::<BotIdKey>().expect("Bot ID was not set").clone();
             (bot_id, regexes)
         };
-        // Ignore all messages written by the bot itself
-        if msg.author.id == bot_id {
+        // Ignore all messages written by the bot itself, other bots and the
+        // users on the ignore list
+        if msg.author.id == bot_id || msg.author.bot {
             return;
         }
+        {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            let is_ignored = redis_client
+                .get_connection()
+                .map_err(Into::into)
+                .and_then(|mut con| crate::ignore_list::is_ignored(&mut con, msg.author.id.0));
+            match is_ignored {
+                Ok(true) => return,
+                Ok(false) => (),
+                Err(err) => eprintln!(
+                    "Could not check whether user {} is ignored: {}",
+                    msg.author.id.0, err
+                ),
+            }
+        }
         // Ignore all messages that might have come from another guild
         // (shouldn't happen) but who knows
         if let Some(guild_id) = msg.guild_id {
@@ -150,6 +354,20 @@ impl EventHandler for Handler {
                 return;
             }
         }
+        // Regular messages in bot-managed channels go through moderation first
+        if msg.guild_id.is_some() {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            match crate::discord_moderation::moderate_message(&ctx, &msg, &redis_client) {
+                Ok(true) => return,
+                Ok(false) => (),
+                Err(err) => eprintln!("Could not moderate message {}: {}", msg.id.0, err),
+            }
+        }
         let channel = match msg.channel_id.to_channel(&ctx) {
             Ok(channel) => channel,
             _ => return,
@@ -159,6 +377,17 @@ impl EventHandler for Handler {
             Channel::Private(_) => true,
             _ => false,
         };
+        // Discord mentions members who have a nickname as <@!id>. The regexes
+        // accept both forms, but the checks below and the alias resolution
+        // look for the plain one.
+        let bot_nickname_mention = format!("<@!{}>", bot_id.0);
+        if msg.content.starts_with(&bot_nickname_mention) {
+            msg.content = format!(
+                "{}{}",
+                regexes.bot_mention,
+                &msg.content[bot_nickname_mention.len()..]
+            );
+        }
         // If the message is not a direct message and does not start with a
         // mention of the bot, ignore it
         if !is_dm && !msg.content.starts_with(&regexes.bot_mention) {
@@ -169,6 +398,107 @@ impl EventHandler for Handler {
         if is_dm && msg.content.starts_with(&regexes.bot_mention) {
             is_dm = false;
         }
+        // Commands can be fixed by editing the message, but an edit that
+        // doesn't change the command shouldn't run it again
+        {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            let is_new_command =
+                redis_client
+                    .get_connection()
+                    .map_err(Into::into)
+                    .and_then(|mut con| {
+                        crate::discord_edited_commands::remember_command(
+                            &mut con,
+                            msg.id,
+                            &msg.content,
+                        )
+                    });
+            match is_new_command {
+                Ok(true) => (),
+                Ok(false) => return,
+                Err(err) => eprintln!("Could not remember command {}: {}", msg.id.0, err),
+            }
+        }
+        // Until the command is handled, the background tasks' Discord API
+        // calls wait for it
+        let _command_guard = crate::discord_call_priority::start_command();
+        // Count the command for the usage report. Aliases are counted when
+        // they come back resolved.
+        {
+            let command_text = if is_dm {
+                &msg.content[..]
+            } else {
+                &msg.content[regexes.bot_mention.len()..]
+            };
+            if let Some(command) = crate::discord_command_aliases::identify(command_text, is_dm) {
+                let redis_client = {
+                    let data = ctx.data.read();
+                    data.get::<RedisClientKey>()
+                        .expect("Redis client was not set")
+                        .clone()
+                };
+                let recorded =
+                    redis_client
+                        .get_connection()
+                        .map_err(Into::into)
+                        .and_then(|mut con| {
+                            crate::discord_command_usage::record(
+                                &mut con,
+                                &command,
+                                msg.author.id.0,
+                            )
+                        });
+                if let Err(err) = recorded {
+                    eprintln!("Could not record the usage of \"{}\": {}", command, err);
+                }
+            }
+        }
+        // In busy channels, the command message is deleted once the command
+        // was handled (when the guard goes out of scope) and the replies are
+        // transient
+        let _tidy_guard = if is_dm {
+            None
+        } else {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            match crate::discord_tidy::begin(&ctx, &msg, &regexes, &redis_client) {
+                Ok(guard) => guard,
+                Err(err) => {
+                    eprintln!("Could not check whether to tidy up {}: {}", msg.id.0, err);
+                    None
+                }
+            }
+        };
+        // During maintenance, refuse everything that would change Discord or Redis
+        if regexes.is_mutating_command(is_dm, &msg.content) {
+            let mut redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            match crate::maintenance_mode::is_active(&mut redis_client) {
+                Ok(false) => (),
+                Ok(true) => {
+                    let _ = crate::discord_tidy::say(&ctx, &msg, strings::MAINTENANCE_MODE_ACTIVE);
+                    return;
+                }
+                Err(err) => {
+                    eprintln!("Could not check for maintenance mode: {}", err);
+                    let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+                    return;
+                }
+            }
+        }
         // TODO: might want to use a RegexSet here to speed up matching
         if regexes.stop_organizer(is_dm).is_match(&msg.content) {
             // This is only for organizers
@@ -181,7 +511,7 @@ impl EventHandler for Handler {
                 )
                 .unwrap_or(false)
             {
-                let _ = msg.channel_id.say(&ctx.http, strings::NOT_AN_ORGANISER);
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
                 return;
             }
             std::process::Command::new("sudo")
@@ -193,7 +523,14 @@ impl EventHandler for Handler {
             match Self::link_meetup(&ctx, &msg, user_id) {
                 Err(err) => {
                     eprintln!("Error: {}", err);
-                    let _ = msg.channel_id.say(&ctx.http, strings::UNSPECIFIED_ERROR);
+                    crate::error_reporting::report(
+                        &err,
+                        &[
+                            ("command", "link_meetup"),
+                            ("user", &msg.author.id.to_string()),
+                        ],
+                    );
+                    let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
                     return;
                 }
                 _ => return,
@@ -209,27 +546,34 @@ impl EventHandler for Handler {
                 )
                 .unwrap_or(false)
             {
-                let _ = msg.channel_id.say(&ctx.http, strings::NOT_AN_ORGANISER);
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
                 return;
             }
-            let discord_id = captures.name("mention_id").unwrap().as_str();
+            let discord_id = crate::discord_bot_commands::resolve_user(&ctx.cache, &captures);
             let meetup_id = captures.name("meetupid").unwrap().as_str();
             // Try to convert the specified ID to an integer
-            let (discord_id, meetup_id) =
-                match (discord_id.parse::<u64>(), meetup_id.parse::<u64>()) {
-                    (Ok(id1), Ok(id2)) => (id1, id2),
-                    _ => {
-                        let _ = msg.channel_id.say(
-                            &ctx.http,
-                            "Seems like the specified Discord or Meetup ID is invalid",
-                        );
-                        return;
-                    }
-                };
+            let (discord_id, meetup_id) = match (discord_id, meetup_id.parse::<u64>()) {
+                (Some(id1), Ok(id2)) => (id1.0, id2),
+                _ => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        "Seems like the specified Discord user or Meetup ID is invalid",
+                    );
+                    return;
+                }
+            };
             match Self::link_meetup_organizer(&ctx, &msg, &regexes, discord_id, meetup_id) {
                 Err(err) => {
                     eprintln!("Error: {}", err);
-                    let _ = msg.channel_id.say(&ctx.http, strings::UNSPECIFIED_ERROR);
+                    crate::error_reporting::report(
+                        &err,
+                        &[
+                            ("command", "link_meetup_organizer"),
+                            ("user", &msg.author.id.to_string()),
+                        ],
+                    );
+                    let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
                     return;
                 }
                 _ => return,
@@ -239,90 +583,223 @@ impl EventHandler for Handler {
             match Self::unlink_meetup(&ctx, &msg, /*is_organizer_command*/ false, user_id) {
                 Err(err) => {
                     eprintln!("Error: {}", err);
-                    let _ = msg.channel_id.say(&ctx.http, strings::UNSPECIFIED_ERROR);
+                    crate::error_reporting::report(
+                        &err,
+                        &[
+                            ("command", "unlink_meetup"),
+                            ("user", &msg.author.id.to_string()),
+                        ],
+                    );
+                    let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
                     return;
                 }
                 _ => return,
             }
-        } else if let Some(captures) = regexes
-            .unlink_meetup_organizer(is_dm)
-            .captures(&msg.content)
-        {
-            let discord_id = captures.name("mention_id").unwrap().as_str();
-            // Try to convert the specified ID to an integer
-            let discord_id = match discord_id.parse::<u64>() {
-                Ok(id) => id,
-                _ => {
-                    let _ = msg
-                        .channel_id
-                        .say(&ctx.http, "Seems like the specified Discord ID is invalid");
-                    return;
-                }
+        } else if regexes.linking_help(is_dm).is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
             };
-            match Self::unlink_meetup(&ctx, &msg, /*is_organizer_command*/ true, discord_id) {
-                Err(err) => {
-                    eprintln!("Error: {}", err);
-                    let _ = msg.channel_id.say(&ctx.http, strings::UNSPECIFIED_ERROR);
-                    return;
-                }
-                _ => return,
+            if let Err(err) = Self::linking_help(&ctx, &msg, redis_client) {
+                eprintln!("Error in linking_help: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "linking_help"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
             }
-        } else if regexes.sync_meetup_mention.is_match(&msg.content) {
-            // This is only for organizers
-            if !msg
-                .author
-                .has_role(
-                    &ctx,
-                    crate::discord_sync::GUILD_ID,
-                    crate::discord_sync::ORGANIZER_ID,
-                )
-                .unwrap_or(false)
+        } else if let Some(captures) = regexes.report_dm.captures(&msg.content) {
+            if !is_dm {
+                return;
+            }
+            let text = captures.name("text").unwrap().as_str();
+            let anonymous = captures.name("anonymous").is_some();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::report_to_organizers(&ctx, &msg, text, anonymous, redis_client)
             {
-                let _ = msg.channel_id.say(&ctx.http, strings::NOT_AN_ORGANISER);
+                eprintln!("Error in report_to_organizers: {}", err);
+                // Don't reveal who sent an anonymous report
+                let user = if anonymous {
+                    "anonymous".to_string()
+                } else {
+                    msg.author.id.to_string()
+                };
+                crate::error_reporting::report(
+                    &err,
+                    &[("command", "report_to_organizers"), ("user", &user)],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.register_email_dm.captures(&msg.content) {
+            if !is_dm {
                 return;
             }
-            let (async_meetup_client, redis_client, mut future_spawner) = {
+            let email = captures.name("email").unwrap().as_str();
+            let redis_client = {
                 let data = ctx.data.read();
-                let async_meetup_client = data
-                    .get::<AsyncMeetupClientKey>()
-                    .expect("Async Meetup client was not set")
-                    .clone();
-                let redis_client = data
-                    .get::<RedisClientKey>()
+                data.get::<RedisClientKey>()
                     .expect("Redis client was not set")
-                    .clone();
-                let future_spawner = data
-                    .get::<FuturesSpawnerKey>()
-                    .expect("Future spawner was not set")
-                    .clone();
-                (async_meetup_client, redis_client, future_spawner)
+                    .clone()
             };
-            let sync_task = Box::new(
-                crate::meetup_sync::sync_task(async_meetup_client, redis_client)
-                    .map_err(|err| {
-                        eprintln!("Syncing task failed: {}", err);
-                        err
-                    })
-                    .timeout(Duration::from_secs(60))
-                    .map_err(|err| {
-                        eprintln!("Syncing task timed out: {}", err);
-                    }),
-            );
-            // Send the syncing future to the executor
-            match future_spawner.try_send(sync_task) {
-                Ok(()) => {
-                    let _ = msg.channel_id.say(
-                        &ctx.http,
-                        "Started asynchronous Meetup synchronization task",
-                    );
-                }
-                Err(err) => {
-                    let _ = msg
-                        .channel_id
-                        .say(&ctx.http, format!("Could not submit asynchronous Meetup synchronization task to the queue (full={}, disconnected={})", err.is_full(), err.is_disconnected()));
-                }
+            if let Err(err) = Self::register_email(&ctx, &msg, email, redis_client) {
+                eprintln!("Error in register_email: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "register_email"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.unregister_email_dm.is_match(&msg.content) {
+            if !is_dm {
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::unregister_email(&ctx, &msg, redis_client) {
+                eprintln!("Error in unregister_email: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "unregister_email"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.delete_my_data_dm.is_match(&msg.content) {
+            if !is_dm {
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::delete_my_data(&ctx, &msg, redis_client) {
+                eprintln!("Error in delete_my_data: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "delete_my_data"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.claim_ticket_dm.captures(&msg.content) {
+            if !is_dm {
+                return;
+            }
+            let order_id = captures.name("order_id").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::claim_ticket(&ctx, &msg, order_id, redis_client) {
+                eprintln!("Error in claim_ticket: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "claim_ticket"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.list_identities_dm.is_match(&msg.content) {
+            if !is_dm {
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_identities(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_identities: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_identities"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.primary_identity_dm.captures(&msg.content) {
+            if !is_dm {
+                return;
+            }
+            let identity = captures.name("identity").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::set_primary_identity(&ctx, &msg, identity, redis_client) {
+                eprintln!("Error in set_primary_identity: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_primary_identity"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.nickname_sync_dm.captures(&msg.content) {
+            if !is_dm {
+                return;
+            }
+            let enabled = captures
+                .name("setting")
+                .unwrap()
+                .as_str()
+                .eq_ignore_ascii_case("on");
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::nickname_sync(&ctx, &msg, enabled, redis_client) {
+                eprintln!("Error in nickname_sync: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "nickname_sync"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.unregister_email_organizer_dm.captures(&msg.content)
+        {
+            if !is_dm {
+                return;
             }
-        } else if regexes.sync_discord_mention.is_match(&msg.content) {
             // This is only for organizers
             if !msg
                 .author
@@ -333,42 +810,31 @@ impl EventHandler for Handler {
                 )
                 .unwrap_or(false)
             {
-                let _ = msg.channel_id.say(&ctx.http, strings::NOT_AN_ORGANISER);
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
                 return;
             }
-            let (redis_client, bot_id, task_scheduler) = {
+            let email = captures.name("email").unwrap().as_str();
+            let redis_client = {
                 let data = ctx.data.read();
-                let redis_client = data
-                    .get::<RedisClientKey>()
+                data.get::<RedisClientKey>()
                     .expect("Redis client was not set")
-                    .clone();
-                let bot_id = *data.get::<BotIdKey>().expect("Bot ID was not set");
-                let task_scheduler = data
-                    .get::<TaskSchedulerKey>()
-                    .expect("Task scheduler was not set")
-                    .clone();
-                (redis_client, bot_id, task_scheduler)
+                    .clone()
             };
-            // Send the syncing task to the scheduler
-            task_scheduler.lock().add_task_datetime(
-                white_rabbit::Utc::now(),
-                crate::discord_sync::create_sync_discord_task(
-                    redis_client,
-                    CacheAndHttp {
-                        cache: ctx.cache.clone(),
-                        http: ctx.http.clone(),
-                    },
-                    bot_id.0,
-                    /*recurring*/ false,
-                ),
-            );
-            let _ = msg
-                .channel_id
-                .say(&ctx.http, "Started Discord synchronization task");
-        } else if regexes
-            .send_expiration_reminder_organizer_mention
-            .is_match(&msg.content)
-        {
+            if let Err(err) = Self::unregister_email_address(&ctx, &msg, email, redis_client) {
+                eprintln!("Error in unregister_email_address: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "unregister_email_address"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.approve_email_organizer_dm.captures(&msg.content) {
+            if !is_dm {
+                return;
+            }
             // This is only for organizers
             if !msg
                 .author
@@ -379,12 +845,463 @@ impl EventHandler for Handler {
                 )
                 .unwrap_or(false)
             {
-                let _ = msg.channel_id.say(&ctx.http, strings::NOT_AN_ORGANISER);
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
                 return;
             }
-            let (redis_client, bot_id, task_scheduler) = {
-                let data = ctx.data.read();
-                let redis_client = data
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::approve_email(&ctx, &msg, discord_id, redis_client) {
+                eprintln!("Error in approve_email: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "approve_email"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.host_digest(is_dm).captures(&msg.content) {
+            let enabled = captures
+                .name("toggle")
+                .unwrap()
+                .as_str()
+                .eq_ignore_ascii_case("on");
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::toggle_host_digest(&ctx, &msg, enabled, redis_client) {
+                eprintln!("Error in toggle_host_digest: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "toggle_host_digest"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.subscribe_mention.captures(&msg.content) {
+            let tag = captures.name("tag").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::subscribe(&ctx, &msg, tag, redis_client) {
+                eprintln!("Error in subscribe: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "subscribe"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.roll(is_dm).captures(&msg.content) {
+            let expression = captures.name("expression").unwrap().as_str();
+            let mode = crate::dice_roller::Mode::from_name(
+                captures.name("mode").map(|mode| mode.as_str()),
+            );
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::roll_dice(
+                &ctx,
+                &msg,
+                expression,
+                mode,
+                /*private*/ false,
+                redis_client,
+            ) {
+                eprintln!("Error in roll_dice: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "roll_dice"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.gm_roll_mention.captures(&msg.content) {
+            let expression = captures.name("expression").unwrap().as_str();
+            let mode = crate::dice_roller::Mode::from_name(
+                captures.name("mode").map(|mode| mode.as_str()),
+            );
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::roll_dice(
+                &ctx,
+                &msg,
+                expression,
+                mode,
+                /*private*/ true,
+                redis_client,
+            ) {
+                eprintln!("Error in roll_dice: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "roll_dice"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.roll_log_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::show_roll_log(&ctx, &msg, redis_client) {
+                eprintln!("Error in show_roll_log: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "show_roll_log"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.set_safety_host_mention.captures(&msg.content) {
+            let field = match crate::discord_safety::Field::from_name(
+                captures.name("field").unwrap().as_str(),
+            ) {
+                Some(field) => field,
+                None => return,
+            };
+            let value = captures.name("value").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::set_safety_field(&ctx, &msg, field, value, redis_client) {
+                eprintln!("Error in set_safety_field: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_safety_field"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.show_safety_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::show_safety(&ctx, &msg, redis_client) {
+                eprintln!("Error in show_safety: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "show_safety"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.set_character_mention.captures(&msg.content) {
+            let sheet_url = captures.name("url").unwrap().as_str();
+            let name = captures.name("name").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::set_character(&ctx, &msg, sheet_url, name, redis_client) {
+                eprintln!("Error in set_character: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_character"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.remove_character_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::remove_character(&ctx, &msg, redis_client) {
+                eprintln!("Error in remove_character: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "remove_character"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.party_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::show_party(&ctx, &msg, redis_client) {
+                eprintln!("Error in show_party: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "show_party"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.add_macro_host_mention.captures(&msg.content) {
+            let name = captures.name("name").unwrap().as_str();
+            let text = captures.name("text").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::add_macro(&ctx, &msg, name, text, redis_client) {
+                eprintln!("Error in add_macro: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "add_macro"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.remove_macro_host_mention.captures(&msg.content) {
+            let name = captures.name("name").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::remove_macro(&ctx, &msg, name, redis_client) {
+                eprintln!("Error in remove_macro: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "remove_macro"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.list_macros_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_macros(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_macros: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_macros"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.absent_mention.captures(&msg.content) {
+            let reason = captures.name("reason").map(|reason| reason.as_str());
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::mark_absent(&ctx, &msg, reason, redis_client) {
+                eprintln!("Error in mark_absent: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "mark_absent"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.unsubscribe_mention.captures(&msg.content) {
+            let tag = captures.name("tag").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::unsubscribe(&ctx, &msg, tag, redis_client) {
+                eprintln!("Error in unsubscribe: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "unsubscribe"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.list_subscriptions_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_subscriptions(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_subscriptions: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_subscriptions"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .unlink_meetup_organizer(is_dm)
+            .captures(&msg.content)
+        {
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            match Self::unlink_meetup(&ctx, &msg, /*is_organizer_command*/ true, discord_id) {
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    crate::error_reporting::report(
+                        &err,
+                        &[
+                            ("command", "unlink_meetup_organizer"),
+                            ("user", &msg.author.id.to_string()),
+                        ],
+                    );
+                    let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+                    return;
+                }
+                _ => return,
+            }
+        } else if regexes.sync_meetup_mention.is_match(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let (event_source, redis_client, mut future_spawner) = {
+                let data = ctx.data.read();
+                let event_source = data
+                    .get::<EventSourceKey>()
+                    .expect("Event source was not set")
+                    .clone();
+                let redis_client = data
+                    .get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone();
+                let future_spawner = data
+                    .get::<FuturesSpawnerKey>()
+                    .expect("Future spawner was not set")
+                    .clone();
+                (event_source, redis_client, future_spawner)
+            };
+            // Send the syncing future to the executor
+            match crate::meetup_sync::start_sync_task(
+                event_source,
+                redis_client,
+                &mut future_spawner,
+            ) {
+                Ok(()) => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        "Started asynchronous Meetup synchronization task",
+                    );
+                }
+                Err(err) => {
+                    let _ = crate::discord_tidy::say(&ctx, &msg, format!("Could not submit asynchronous Meetup synchronization task to the queue (full={}, disconnected={})", err.is_full(), err.is_disconnected()));
+                }
+            }
+        } else if regexes.sync_discord_mention.is_match(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let (redis_client, bot_id, task_scheduler) = {
+                let data = ctx.data.read();
+                let redis_client = data
                     .get::<RedisClientKey>()
                     .expect("Redis client was not set")
                     .clone();
@@ -396,163 +1313,2862 @@ impl EventHandler for Handler {
                 (redis_client, bot_id, task_scheduler)
             };
             // Send the syncing task to the scheduler
-            task_scheduler.lock().add_task_datetime(
+            if let Err(err) = crate::persistent_tasks::schedule_task(
+                &task_scheduler,
+                &redis_client,
+                &CacheAndHttp {
+                    cache: ctx.cache.clone(),
+                    http: ctx.http.clone(),
+                },
+                bot_id.0,
+                crate::persistent_tasks::TaskType::SyncDiscord,
                 white_rabbit::Utc::now(),
-                crate::discord_end_of_game::create_end_of_game_task(
-                    redis_client,
-                    CacheAndHttp {
-                        cache: ctx.cache.clone(),
-                        http: ctx.http.clone(),
-                    },
-                    bot_id.0,
-                    /*recurring*/ false,
-                ),
-            );
-            let _ = msg
-                .channel_id
-                .say(&ctx.http, "Started expiration reminder task");
+                /*recurring*/ false,
+            ) {
+                eprintln!(
+                    "Could not schedule the Discord synchronization task: {}",
+                    err
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+                return;
+            }
+            let _ = crate::discord_tidy::say(&ctx, &msg, "Started Discord synchronization task");
+        } else if regexes
+            .send_expiration_reminder_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let (redis_client, bot_id, task_scheduler) = {
+                let data = ctx.data.read();
+                let redis_client = data
+                    .get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone();
+                let bot_id = *data.get::<BotIdKey>().expect("Bot ID was not set");
+                let task_scheduler = data
+                    .get::<TaskSchedulerKey>()
+                    .expect("Task scheduler was not set")
+                    .clone();
+                (redis_client, bot_id, task_scheduler)
+            };
+            // Send the end of game task to the scheduler
+            if let Err(err) = crate::persistent_tasks::schedule_task(
+                &task_scheduler,
+                &redis_client,
+                &CacheAndHttp {
+                    cache: ctx.cache.clone(),
+                    http: ctx.http.clone(),
+                },
+                bot_id.0,
+                crate::persistent_tasks::TaskType::EndOfGame,
+                white_rabbit::Utc::now(),
+                /*recurring*/ false,
+            ) {
+                eprintln!("Could not schedule the expiration reminder task: {}", err);
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+                return;
+            }
+            let _ = crate::discord_tidy::say(&ctx, &msg, "Started expiration reminder task");
         } else if let Some(captures) = regexes.add_user_mention.captures(&msg.content) {
-            // Get the Discord ID of the user that is supposed to
+            // Get the Discord IDs of the users that are supposed to
             // be added to the channel
-            let discord_id = captures.name("mention_id").unwrap().as_str();
-            // Try to convert the specified ID to an integer
-            let discord_id = match discord_id.parse::<u64>() {
-                Ok(id) => id,
-                _ => {
-                    let _ = msg
-                        .channel_id
-                        .say(&ctx.http, strings::CHANNEL_ADD_USER_INVALID_DISCORD);
-                    return;
-                }
+            let users = crate::discord_bot_commands::resolve_users(
+                &ctx.cache,
+                &regexes,
+                captures.name("users").unwrap().as_str(),
+            );
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
             };
+            let result = if users.len() == 1 {
+                let discord_id = match users[0].1 {
+                    Some(id) => id,
+                    None => {
+                        let _ = crate::discord_tidy::say(
+                            &ctx,
+                            &msg,
+                            strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                        );
+                        return;
+                    }
+                };
+                Self::channel_add_or_remove_user(
+                    &ctx,
+                    &msg,
+                    discord_id,
+                    /*add*/ true,
+                    /*as_host*/ false,
+                    redis_client,
+                )
+            } else {
+                // Several users at once get a single summary
+                Self::channel_add_or_remove_users(
+                    &ctx,
+                    &msg,
+                    &users,
+                    /*add*/ true,
+                    redis_client,
+                )
+            };
+            if let Err(err) = result {
+                eprintln!("Error in add user: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "add_user"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.add_host_mention.captures(&msg.content) {
+            // Get the Discord ID of the user that is supposed to
+            // be added to the channel
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::channel_add_or_remove_user(
+                &ctx,
+                &msg,
+                discord_id,
+                /*add*/ true,
+                /*as_host*/ true,
+                redis_client,
+            ) {
+                eprintln!("Error in add host: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "add_host"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, "Something went wrong");
+            }
+        } else if let Some(captures) = regexes.add_guest_mention.captures(&msg.content) {
+            // Try to convert the specified ID to an integer
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let period = match crate::discord_guests::parse_period(
+                captures.name("amount").unwrap().as_str(),
+                captures.name("unit").unwrap().as_str(),
+            ) {
+                Some(period) => period,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::GUEST_PERIOD_INVALID(crate::discord_guests::MAX_GUEST_DAYS),
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::add_guest(&ctx, &msg, discord_id, period, redis_client) {
+                eprintln!("Error in add_guest: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "add_guest"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.remove_user_mention.captures(&msg.content) {
+            // Get the Discord IDs of the users that are supposed to
+            // be removed from this channel
+            let users = crate::discord_bot_commands::resolve_users(
+                &ctx.cache,
+                &regexes,
+                captures.name("users").unwrap().as_str(),
+            );
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            let result = if users.len() == 1 {
+                let discord_id = match users[0].1 {
+                    Some(id) => id,
+                    None => {
+                        let _ = crate::discord_tidy::say(
+                            &ctx,
+                            &msg,
+                            strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                        );
+                        return;
+                    }
+                };
+                Self::channel_add_or_remove_user(
+                    &ctx,
+                    &msg,
+                    discord_id,
+                    /*add*/ false,
+                    /*as_host*/ false,
+                    redis_client,
+                )
+            } else {
+                // Several users at once get a single summary
+                Self::channel_add_or_remove_users(
+                    &ctx,
+                    &msg,
+                    &users,
+                    /*add*/ false,
+                    redis_client,
+                )
+            };
+            if let Err(err) = result {
+                eprintln!("Error in remove user: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "remove_user"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, "Something went wrong");
+            }
+        } else if regexes.remove_replied_user_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::remove_replied_user(&ctx, &msg, redis_client) {
+                eprintln!("Error in remove_replied_user: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "remove_replied_user"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.remove_host_mention.captures(&msg.content) {
+            // Get the Discord ID of the host that is supposed to
+            // be removed from this channel
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::channel_add_or_remove_user(
+                &ctx,
+                &msg,
+                discord_id,
+                /*add*/ false,
+                /*as_host*/ true,
+                redis_client,
+            ) {
+                eprintln!("Error in remove host: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "remove_host"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.add_cohost_mention.captures(&msg.content) {
+            // Get the Discord ID of the user that is supposed to
+            // become a co-host of the channel
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::channel_add_or_remove_cohost(
+                &ctx,
+                &msg,
+                discord_id,
+                /*add*/ true,
+                redis_client,
+            ) {
+                eprintln!("Error in add cohost: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "add_cohost"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.remove_cohost_mention.captures(&msg.content) {
+            // Get the Discord ID of the co-host that is supposed to
+            // be demoted
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::channel_add_or_remove_cohost(
+                &ctx,
+                &msg,
+                discord_id,
+                /*add*/ false,
+                redis_client,
+            ) {
+                eprintln!("Error in remove cohost: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "remove_cohost"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.tables_host_mention.captures(&msg.content) {
+            // Values that don't fit are certainly too many tables
+            let num_tables = captures
+                .name("count")
+                .unwrap()
+                .as_str()
+                .parse::<usize>()
+                .unwrap_or(usize::max_value());
+            let (redis_client, bot_id) = {
+                let data = ctx.data.read();
+                (
+                    data.get::<RedisClientKey>()
+                        .expect("Redis client was not set")
+                        .clone(),
+                    *data.get::<BotIdKey>().expect("Bot ID was not set"),
+                )
+            };
+            if let Err(err) =
+                Self::split_into_tables(&ctx, &msg, num_tables, bot_id.0, redis_client)
+            {
+                eprintln!("Error in split_into_tables: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "split_into_tables"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.export_members_host_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::export_members(&ctx, &msg, redis_client) {
+                eprintln!("Error in export_members: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "export_members"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.session_channels_host_mention.captures(&msg.content)
+        {
+            let enabled = captures
+                .name("toggle")
+                .unwrap()
+                .as_str()
+                .eq_ignore_ascii_case("on");
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::toggle_session_channels(&ctx, &msg, enabled, redis_client) {
+                eprintln!("Error in toggle_session_channels: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "toggle_session_channels"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.sync_pause_host_mention.captures(&msg.content) {
+            let paused = captures
+                .name("toggle")
+                .unwrap()
+                .as_str()
+                .eq_ignore_ascii_case("pause");
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::toggle_sync_pause(&ctx, &msg, paused, redis_client) {
+                eprintln!("Error in toggle_sync_pause: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "toggle_sync_pause"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.style_host_mention.captures(&msg.content) {
+            let colour = captures.name("colour").map(|colour| colour.as_str());
+            let emoji = captures.name("emoji").map(|emoji| emoji.as_str());
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::style_channel(&ctx, &msg, colour, emoji, redis_client) {
+                eprintln!("Error in style_channel: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "style_channel"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.hide_history_host_mention.captures(&msg.content) {
+            // Anything but "off" is a number of days
+            let days = captures.name("days").unwrap().as_str();
+            let days = if days.eq_ignore_ascii_case("off") {
+                None
+            } else {
+                match days.parse::<u64>() {
+                    Ok(days) => Some(days),
+                    Err(_) => {
+                        let _ = crate::discord_tidy::say(
+                            &ctx,
+                            &msg,
+                            strings::HIDE_HISTORY_INVALID_DAYS(
+                                crate::discord_history_privacy::MAX_HIDE_DAYS,
+                            ),
+                        );
+                        return;
+                    }
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::set_history_hiding(&ctx, &msg, days, redis_client) {
+                eprintln!("Error in set_history_hiding: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_history_hiding"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.channel_settings_host_mention.is_match(&msg.content) {
+            let (redis_client, bot_id) = {
+                let data = ctx.data.read();
+                (
+                    data.get::<RedisClientKey>()
+                        .expect("Redis client was not set")
+                        .clone(),
+                    *data.get::<BotIdKey>().expect("Bot ID was not set"),
+                )
+            };
+            if let Err(err) = Self::channel_settings(&ctx, &msg, None, bot_id.0, redis_client) {
+                eprintln!("Error in channel_settings: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "channel_settings"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .change_channel_setting_host_mention
+            .captures(&msg.content)
+        {
+            let setting = captures.name("setting").unwrap().as_str();
+            let value = captures.name("value").unwrap().as_str();
+            let (redis_client, bot_id) = {
+                let data = ctx.data.read();
+                (
+                    data.get::<RedisClientKey>()
+                        .expect("Redis client was not set")
+                        .clone(),
+                    *data.get::<BotIdKey>().expect("Bot ID was not set"),
+                )
+            };
+            if let Err(err) =
+                Self::channel_settings(&ctx, &msg, Some((setting, value)), bot_id.0, redis_client)
+            {
+                eprintln!("Error in channel_settings: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "channel_settings"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.recap_host_mention.captures(&msg.content) {
+            let text = captures.name("text").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) =
+                Self::store_recap(&ctx, &msg, msg.id, msg.author.id.0, text, redis_client)
+            {
+                eprintln!("Error in store_recap: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "store_recap"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.recap_reply_host_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::store_replied_recap(&ctx, &msg, redis_client) {
+                eprintln!("Error in store_replied_recap: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "store_replied_recap"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.list_recaps_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_recaps(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_recaps: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_recaps"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.close_channel_host_mention.is_match(&msg.content) {
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::close_channel(&ctx, &msg, redis_client) {
+                eprintln!("Error in close_channel: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "close_channel"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .list_flagged_links_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_flagged_links(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_flagged_links: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_flagged_links"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .toggle_feature_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let enable = captures
+                .name("toggle")
+                .unwrap()
+                .as_str()
+                .eq_ignore_ascii_case("enable");
+            let feature_name = captures.name("feature").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::toggle_feature(&ctx, &msg, feature_name, enable, redis_client) {
+                eprintln!("Error in toggle_feature: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "toggle_feature"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .quick_command_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let command_name = captures.name("command").unwrap().as_str();
+            let emoji = captures.name("emoji").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) =
+                Self::set_quick_command_emoji(&ctx, &msg, command_name, emoji, redis_client)
+            {
+                eprintln!("Error in set_quick_command_emoji: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_quick_command_emoji"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .host_verification_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let mode_name = captures.name("mode").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::set_host_verification_mode(&ctx, &msg, mode_name, redis_client)
+            {
+                eprintln!("Error in set_host_verification_mode: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_host_verification_mode"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .community_digest_channel_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            let channel_id = captures
+                .name("channel_id")
+                .and_then(|channel_id| channel_id.as_str().parse::<u64>().ok());
+            if let Err(err) =
+                Self::set_community_digest_channel(&ctx, &msg, channel_id, redis_client)
+            {
+                eprintln!("Error in set_community_digest_channel: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_community_digest_channel"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .community_digest_schedule_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            let weekday = captures.name("weekday").unwrap().as_str();
+            let hour = captures.name("hour").unwrap().as_str();
+            if let Err(err) =
+                Self::set_community_digest_schedule(&ctx, &msg, weekday, hour, redis_client)
+            {
+                eprintln!("Error in set_community_digest_schedule: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_community_digest_schedule"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .community_digest_template_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            let template = captures.name("template").unwrap().as_str();
+            if let Err(err) =
+                Self::set_community_digest_template(&ctx, &msg, template, redis_client)
+            {
+                eprintln!("Error in set_community_digest_template: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_community_digest_template"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .crosspost_webhook_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            let url = captures.name("url").map(|url| url.as_str());
+            if let Err(err) = Self::set_crosspost_webhook(&ctx, &msg, url, redis_client) {
+                eprintln!("Error in set_crosspost_webhook: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_crosspost_webhook"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .crosspost_template_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            let template = captures.name("template").unwrap().as_str();
+            if let Err(err) = Self::set_crosspost_template(&ctx, &msg, template, redis_client) {
+                eprintln!("Error in set_crosspost_template: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_crosspost_template"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .subscription_role_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let tag = captures.name("tag").unwrap().as_str();
+            let role_id = captures
+                .name("role_id")
+                .and_then(|role_id| role_id.as_str().parse::<u64>().ok());
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::set_subscription_role(&ctx, &msg, tag, role_id, redis_client) {
+                eprintln!("Error in set_subscription_role: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_subscription_role"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .list_tag_rules_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_tag_rules(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_tag_rules: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_tag_rules"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.tag_rule_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let tag = captures.name("tag").unwrap().as_str();
+            let keywords = captures.name("keywords").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::set_tag_rule(&ctx, &msg, tag, keywords, redis_client) {
+                eprintln!("Error in set_tag_rule: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_tag_rule"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.sync_stats_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let num_runs = captures
+                .name("num_runs")
+                .and_then(|num_runs| num_runs.as_str().parse::<usize>().ok())
+                .unwrap_or(crate::sync_stats::DEFAULT_SHOWN_RUNS)
+                .min(crate::sync_stats::MAX_RECORDED_RUNS);
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::sync_stats(&ctx, &msg, num_runs, redis_client) {
+                eprintln!("Error in sync_stats: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "sync_stats"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.preview_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let series_id = captures.name("series_id").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::preview_sync(&ctx, &msg, series_id, redis_client) {
+                eprintln!("Error in preview_sync: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "preview_sync"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.config_get_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let key = captures.name("key").map(|key| key.as_str());
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::config_get(&ctx, &msg, key, redis_client) {
+                eprintln!("Error in config_get: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "config_get"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.config_set_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let key = captures.name("key").unwrap().as_str();
+            let value = captures.name("value").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::config_set(&ctx, &msg, key, value, redis_client) {
+                eprintln!("Error in config_set: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "config_set"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.config_log_organizer_mention.is_match(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::config_log(&ctx, &msg, redis_client) {
+                eprintln!("Error in config_log: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "config_log"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.setup_owner_mention.is_match(&msg.content)
+            || regexes.setup_step_owner_mention.is_match(&msg.content)
+        {
+            // This is only for the owner of the server
+            if !crate::discord_setup::is_guild_owner(&ctx.cache, msg.author.id) {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::SETUP_NOT_GUILD_OWNER);
+                return;
+            }
+            let change = regexes
+                .setup_step_owner_mention
+                .captures(&msg.content)
+                .map(|captures| {
+                    (
+                        captures.name("step").unwrap().as_str(),
+                        captures.name("value").unwrap().as_str(),
+                    )
+                });
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::setup(&ctx, &msg, change, bot_id.0, redis_client) {
+                eprintln!("Error in setup: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[("command", "setup"), ("user", &msg.author.id.to_string())],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .schedule_command_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let command = captures.name("command").unwrap().as_str();
+            let due_time = captures.name("due_time").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) =
+                Self::schedule_command(&ctx, &msg, &regexes, command, due_time, redis_client)
+            {
+                eprintln!("Error in schedule_command: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "schedule_command"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .list_scheduled_commands_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_scheduled_commands(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_scheduled_commands: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_scheduled_commands"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .unschedule_command_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let command_id = captures.name("command_id").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::unschedule_command(&ctx, &msg, command_id, redis_client) {
+                eprintln!("Error in unschedule_command: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "unschedule_command"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.quarantine_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::quarantine_user(&ctx, &msg, discord_id, redis_client) {
+                eprintln!("Error in quarantine_user: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "quarantine_user"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.release_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::release_user(&ctx, &msg, discord_id, redis_client) {
+                eprintln!("Error in release_user: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "release_user"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.ignore_user_organizer_mention.captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let ignored = captures.name("toggle").unwrap().as_str().to_lowercase() == "ignore";
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::set_user_ignored(&ctx, &msg, discord_id, ignored, redis_client)
+            {
+                eprintln!("Error in set_user_ignored: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_user_ignored"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .list_ignored_users_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_ignored_users(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_ignored_users: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_ignored_users"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .invite_create_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let label = captures.name("label").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::invite_create(&ctx, &msg, label, redis_client) {
+                eprintln!("Error in invite_create: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "invite_create"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .invite_stats_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::invite_stats(&ctx, &msg, redis_client) {
+                eprintln!("Error in invite_stats: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "invite_stats"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .usage_report_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::usage_report(&ctx, &msg, redis_client) {
+                eprintln!("Error in usage_report: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "usage_report"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .storage_report_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::storage_report(&ctx, &msg, redis_client) {
+                eprintln!("Error in storage_report: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "storage_report"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .list_duplicate_events_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_duplicate_events(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_duplicate_events: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_duplicate_events"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .allow_duplicate_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let event_id = captures.name("event_id").unwrap().as_str();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::allow_duplicate(&ctx, &msg, event_id, redis_client) {
+                eprintln!("Error in allow_duplicate: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "allow_duplicate"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .list_features_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_features(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_features: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_features"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.block_link_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            let block = captures
+                .name("toggle")
+                .unwrap()
+                .as_str()
+                .eq_ignore_ascii_case("block");
+            let pattern = captures.name("pattern").unwrap().as_str();
+            if let Err(err) = Self::block_link_pattern(&ctx, &msg, pattern, block, redis_client) {
+                eprintln!("Error in block_link_pattern: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "block_link_pattern"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .list_blocked_links_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_blocked_links(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_blocked_links: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_blocked_links"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.maintenance_organizer_mention.captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let active = captures
+                .name("toggle")
+                .unwrap()
+                .as_str()
+                .eq_ignore_ascii_case("on");
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::toggle_maintenance_mode(&ctx, &msg, active, redis_client) {
+                eprintln!("Error in toggle_maintenance_mode: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "toggle_maintenance_mode"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.share_meetup_mention.captures(&msg.content) {
+            let member_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures) {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::share_meetup(&ctx, &msg, member_id, redis_client) {
+                eprintln!("Error in share_meetup: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "share_meetup"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.unshare_meetup_mention.captures(&msg.content) {
+            let member_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures) {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let is_organizer = msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false);
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) =
+                Self::unshare_meetup(&ctx, &msg, member_id, is_organizer, redis_client)
+            {
+                eprintln!("Error in unshare_meetup: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "unshare_meetup"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .approve_household_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let member_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures) {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::approve_household(&ctx, &msg, member_id, redis_client) {
+                eprintln!("Error in approve_household: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "approve_household"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .reject_household_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let member_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures) {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::reject_household(&ctx, &msg, member_id, redis_client) {
+                eprintln!("Error in reject_household: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "reject_household"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .list_household_requests_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_household_requests(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_household_requests: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_household_requests"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .confirm_link_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let meetup_id = match captures.name("meetupid").unwrap().as_str().parse::<u64>() {
+                Ok(id) => id,
+                _ => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        "Seems like the specified Meetup ID is invalid",
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::confirm_link(&ctx, &msg, &regexes, meetup_id, redis_client) {
+                eprintln!("Error in confirm_link: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "confirm_link"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .dismiss_link_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let meetup_id = match captures.name("meetupid").unwrap().as_str().parse::<u64>() {
+                Ok(id) => id,
+                _ => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        "Seems like the specified Meetup ID is invalid",
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::dismiss_link(&ctx, &msg, meetup_id, redis_client) {
+                eprintln!("Error in dismiss_link: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "dismiss_link"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .suggest_links_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::suggest_links(&ctx, &msg, redis_client) {
+                eprintln!("Error in suggest_links: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "suggest_links"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .inactive_members_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let min_days = captures
+                .name("days")
+                .and_then(|days| days.as_str().parse::<u32>().ok())
+                .unwrap_or(crate::discord_inactive_members::DEFAULT_MIN_DAYS);
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::list_inactive_members(&ctx, &msg, min_days, redis_client) {
+                eprintln!("Error in list_inactive_members: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_inactive_members"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .nudge_inactive_members_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let min_days = captures
+                .name("days")
+                .and_then(|days| days.as_str().parse::<u32>().ok())
+                .unwrap_or(crate::discord_inactive_members::DEFAULT_MIN_DAYS);
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::nudge_inactive_members(&ctx, &msg, min_days, redis_client) {
+                eprintln!("Error in nudge_inactive_members: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "nudge_inactive_members"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.whois_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::whois(&ctx, &msg, discord_id, redis_client) {
+                eprintln!("Error in whois: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[("command", "whois"), ("user", &msg.author.id.to_string())],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .user_channels_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let discord_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures)
+            {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::CHANNEL_ADD_USER_INVALID_DISCORD,
+                    );
+                    return;
+                }
+            };
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::user_channels(&ctx, &msg, discord_id, redis_client) {
+                eprintln!("Error in user_channels: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "user_channels"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes.dues_role_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let role_id = match captures.name("role_id").unwrap().as_str().parse::<u64>() {
+                Ok(id) => id,
+                _ => {
+                    let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+                    return;
+                }
+            };
+            let gated = captures.name("off").is_none();
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::set_dues_role(&ctx, &msg, role_id, gated, redis_client) {
+                eprintln!("Error in set_dues_role: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "set_dues_role"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .cleanup_roles_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::cleanup_roles(&ctx, &msg, redis_client) {
+                eprintln!("Error in cleanup_roles: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "cleanup_roles"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes.role_usage_organizer_mention.is_match(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
+            };
+            if let Err(err) = Self::role_usage(&ctx, &msg, redis_client) {
+                eprintln!("Error in role_usage: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "role_usage"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .approve_creation_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
             let redis_client = {
                 let data = ctx.data.read();
                 data.get::<RedisClientKey>()
                     .expect("Redis client was not set")
                     .clone()
             };
-            if let Err(err) = Self::channel_add_or_remove_user(
-                &ctx,
-                &msg,
-                discord_id,
-                /*add*/ true,
-                /*as_host*/ false,
-                redis_client,
-            ) {
-                eprintln!("Error in add user: {}", err);
-                let _ = msg.channel_id.say(&ctx.http, strings::UNSPECIFIED_ERROR);
+            if let Err(err) = Self::approve_creation(&ctx, &msg, redis_client) {
+                eprintln!("Error in approve_creation: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "approve_creation"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
             }
-        } else if let Some(captures) = regexes.add_host_mention.captures(&msg.content) {
-            // Get the Discord ID of the user that is supposed to
-            // be added to the channel
-            let discord_id = captures.name("mention_id").unwrap().as_str();
-            // Try to convert the specified ID to an integer
-            let discord_id = match discord_id.parse::<u64>() {
-                Ok(id) => id,
-                _ => {
-                    let _ = msg
-                        .channel_id
-                        .say(&ctx.http, strings::CHANNEL_ADD_USER_INVALID_DISCORD);
-                    return;
-                }
+        } else if regexes
+            .check_permissions_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let redis_client = {
+                let data = ctx.data.read();
+                data.get::<RedisClientKey>()
+                    .expect("Redis client was not set")
+                    .clone()
             };
+            if let Err(err) = Self::check_permissions(&ctx, &msg, redis_client) {
+                eprintln!("Error in check_permissions: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "check_permissions"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if regexes
+            .list_organizer_tokens_organizer_mention
+            .is_match(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
             let redis_client = {
                 let data = ctx.data.read();
                 data.get::<RedisClientKey>()
                     .expect("Redis client was not set")
                     .clone()
             };
-            if let Err(err) = Self::channel_add_or_remove_user(
-                &ctx,
-                &msg,
-                discord_id,
-                /*add*/ true,
-                /*as_host*/ true,
-                redis_client,
-            ) {
-                eprintln!("Error in add host: {}", err);
-                let _ = msg.channel_id.say(&ctx.http, "Something went wrong");
+            if let Err(err) = Self::list_organizer_tokens(&ctx, &msg, redis_client) {
+                eprintln!("Error in list_organizer_tokens: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "list_organizer_tokens"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
             }
-        } else if let Some(captures) = regexes.remove_user_mention.captures(&msg.content) {
-            // Get the Discord ID of the user that is supposed to
-            // be removed from this channel
-            let discord_id = captures.name("mention_id").unwrap().as_str();
-            // Try to convert the specified ID to an integer
-            let discord_id = match discord_id.parse::<u64>() {
+        } else if let Some(captures) = regexes
+            .revoke_organizer_token_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            let meetup_user_id = match captures.name("meetupid").unwrap().as_str().parse::<u64>() {
                 Ok(id) => id,
                 _ => {
-                    let _ = msg
-                        .channel_id
-                        .say(&ctx.http, strings::CHANNEL_ADD_USER_INVALID_DISCORD);
+                    let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
                     return;
                 }
             };
-            let redis_client = {
+            let (redis_client, meetup_client, async_meetup_client) = {
                 let data = ctx.data.read();
-                data.get::<RedisClientKey>()
-                    .expect("Redis client was not set")
-                    .clone()
+                (
+                    data.get::<RedisClientKey>()
+                        .expect("Redis client was not set")
+                        .clone(),
+                    data.get::<MeetupClientKey>()
+                        .expect("Meetup client was not set")
+                        .clone(),
+                    data.get::<AsyncMeetupClientKey>()
+                        .expect("Async Meetup client was not set")
+                        .clone(),
+                )
             };
-            if let Err(err) = Self::channel_add_or_remove_user(
+            if let Err(err) = Self::revoke_organizer_token(
                 &ctx,
                 &msg,
-                discord_id,
-                /*add*/ false,
-                /*as_host*/ false,
+                meetup_user_id,
                 redis_client,
+                (meetup_client, async_meetup_client),
             ) {
-                eprintln!("Error in remove user: {}", err);
-                let _ = msg.channel_id.say(&ctx.http, "Something went wrong");
+                eprintln!("Error in revoke_organizer_token: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "revoke_organizer_token"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
             }
-        } else if let Some(captures) = regexes.remove_host_mention.captures(&msg.content) {
-            // Get the Discord ID of the host that is supposed to
-            // be removed from this channel
-            let discord_id = captures.name("mention_id").unwrap().as_str();
-            // Try to convert the specified ID to an integer
-            let discord_id = match discord_id.parse::<u64>() {
-                Ok(id) => id,
-                _ => {
-                    let _ = msg
-                        .channel_id
-                        .say(&ctx.http, strings::CHANNEL_ADD_USER_INVALID_DISCORD);
+        } else if let Some(captures) = regexes
+            .debug_welcome_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            // Debug commands are only available in staging deployments
+            if !crate::staging_mode::is_active() {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::DEBUG_COMMANDS_UNAVAILABLE);
+                return;
+            }
+            let user_id = match crate::discord_bot_commands::resolve_user(&ctx.cache, &captures) {
+                Some(id) => id.0,
+                None => {
+                    let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
                     return;
                 }
             };
-            let redis_client = {
+            if let Err(err) = Self::debug_welcome(&ctx, &msg, user_id) {
+                eprintln!("Error in debug_welcome: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "debug_welcome"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
+            }
+        } else if let Some(captures) = regexes
+            .debug_sync_series_organizer_mention
+            .captures(&msg.content)
+        {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            // Debug commands are only available in staging deployments
+            if !crate::staging_mode::is_active() {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::DEBUG_COMMANDS_UNAVAILABLE);
+                return;
+            }
+            let series_id = captures.name("series_id").unwrap().as_str();
+            let (redis_client, bot_id) = {
                 let data = ctx.data.read();
-                data.get::<RedisClientKey>()
+                let redis_client = data
+                    .get::<RedisClientKey>()
                     .expect("Redis client was not set")
-                    .clone()
+                    .clone();
+                let bot_id = *data.get::<BotIdKey>().expect("Bot ID was not set");
+                (redis_client, bot_id)
             };
-            if let Err(err) = Self::channel_add_or_remove_user(
-                &ctx,
-                &msg,
-                discord_id,
-                /*add*/ false,
-                /*as_host*/ true,
-                redis_client,
-            ) {
-                eprintln!("Error in remove host: {}", err);
-                let _ = msg.channel_id.say(&ctx.http, strings::UNSPECIFIED_ERROR);
+            if let Err(err) = Self::debug_sync_series(&ctx, &msg, series_id, redis_client, bot_id.0)
+            {
+                eprintln!("Error in debug_sync_series: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "debug_sync_series"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
             }
-        } else if regexes.close_channel_host_mention.is_match(&msg.content) {
+        } else if let Some(captures) = regexes.debug_keys_organizer_mention.captures(&msg.content) {
+            // This is only for organizers
+            if !msg
+                .author
+                .has_role(
+                    &ctx,
+                    crate::discord_sync::GUILD_ID,
+                    crate::discord_sync::ORGANIZER_ID,
+                )
+                .unwrap_or(false)
+            {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::NOT_AN_ORGANISER);
+                return;
+            }
+            // Debug commands are only available in staging deployments
+            if !crate::staging_mode::is_active() {
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::DEBUG_COMMANDS_UNAVAILABLE);
+                return;
+            }
+            let pattern = captures.name("pattern").unwrap().as_str();
             let redis_client = {
                 let data = ctx.data.read();
                 data.get::<RedisClientKey>()
                     .expect("Redis client was not set")
                     .clone()
             };
-            if let Err(err) = Self::close_channel(&ctx, &msg, redis_client) {
-                eprintln!("Error in close_channel: {}", err);
-                let _ = msg.channel_id.say(&ctx.http, strings::UNSPECIFIED_ERROR);
-            }
-        } else if msg.content == "test" {
-            if let Some(user) = UserId(456545153923022849).to_user_cached(&ctx) {
-                Self::send_welcome_message(&ctx, &user.read());
-                println!("Sent welcome message!");
+            if let Err(err) = Self::debug_keys(&ctx, &msg, pattern, redis_client) {
+                eprintln!("Error in debug_keys: {}", err);
+                crate::error_reporting::report(
+                    &err,
+                    &[
+                        ("command", "debug_keys"),
+                        ("user", &msg.author.id.to_string()),
+                    ],
+                );
+                let _ = crate::discord_tidy::say(&ctx, &msg, strings::UNSPECIFIED_ERROR);
             }
         } else {
-            let _ = msg.channel_id.say(&ctx.http, strings::INVALID_COMMAND);
+            let command_text = if is_dm {
+                msg.content.clone()
+            } else {
+                msg.content[regexes.bot_mention.len()..].to_string()
+            };
+            // Try again with the alias resolved, which goes through all the
+            // checks above again
+            if let Some(resolved) = crate::discord_command_aliases::resolve(&command_text, is_dm) {
+                msg.content = if is_dm {
+                    resolved
+                } else {
+                    format!("{} {}", regexes.bot_mention, resolved)
+                };
+                self.message(ctx, msg);
+                return;
+            }
+            // Hosts can add their own replies to a channel
+            if !is_dm {
+                let redis_client = {
+                    let data = ctx.data.read();
+                    data.get::<RedisClientKey>()
+                        .expect("Redis client was not set")
+                        .clone()
+                };
+                match Self::run_macro(&ctx, &msg, command_text.trim(), redis_client) {
+                    Ok(true) => return,
+                    Ok(false) => (),
+                    Err(err) => eprintln!("Error in run_macro: {}", err),
+                }
+            }
+            match crate::discord_command_aliases::suggest(&command_text, is_dm) {
+                Some(usage) => {
+                    let _ = crate::discord_tidy::say(
+                        &ctx,
+                        &msg,
+                        strings::INVALID_COMMAND_SUGGESTION(usage),
+                    );
+                }
+                None => {
+                    let _ = crate::discord_tidy::say(&ctx, &msg, strings::INVALID_COMMAND);
+                }
+            }
+        }
+    }
+
+    // Edits of recent commands are handled like new messages
+    fn message_update(&self, ctx: Context, update: MessageUpdateEvent) {
+        // The update only contains the content if it changed
+        let content = match update.content {
+            Some(ref content) => content,
+            None => return,
+        };
+        if !crate::discord_edited_commands::is_within_edit_window(update.id) {
+            return;
+        }
+        // Only fetch the whole message if it might be a command, which is the
+        // case for all direct messages and for mentions of the bot
+        let bot_id = {
+            let data = ctx.data.read();
+            *data.get::<BotIdKey>().expect("Bot ID was not set")
+        };
+        let is_dm = update.guild_id.is_none();
+        if !is_dm
+            && !content.starts_with(&format!("<@{}>", bot_id.0))
+            && !content.starts_with(&format!("<@!{}>", bot_id.0))
+        {
+            return;
+        }
+        match update.channel_id.message(&ctx.http, update.id) {
+            Ok(msg) => self.message(ctx, msg),
+            Err(err) => eprintln!("Could not fetch edited message {}: {}", update.id.0, err),
         }
     }
 
@@ -562,14 +4178,174 @@ impl EventHandler for Handler {
     // private channels, and more.
     //
     // In this case, just print what the current user's username is.
-    fn ready(&self, _: Context, ready: Ready) {
+    fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
+        // The ready event is sent again after reconnects, but we only
+        // want to catch up once after starting the bot
+        if !BOOT_SEQUENCE_STARTED.swap(true, Ordering::SeqCst) {
+            Self::run_boot_sequence(&ctx);
+        }
+    }
+
+    fn reaction_add(&self, ctx: Context, add_reaction: Reaction) {
+        let (bot_id, redis_client) = {
+            let data = ctx.data.read();
+            let bot_id = *data.get::<BotIdKey>().expect("Bot ID was not set");
+            let redis_client = data
+                .get::<RedisClientKey>()
+                .expect("Redis client was not set")
+                .clone();
+            (bot_id, redis_client)
+        };
+        // Ignore all reactions added by the bot itself
+        if add_reaction.user_id == bot_id {
+            return;
+        }
+        if let Err(err) =
+            crate::discord_confirmations::handle_reaction(&ctx, &add_reaction, &redis_client)
+        {
+            eprintln!("Error handling a reaction: {}", err);
+            let _ = add_reaction
+                .channel_id
+                .say(&ctx.http, strings::UNSPECIFIED_ERROR);
+        }
+        if let Err(err) =
+            crate::discord_pagination::handle_reaction(&ctx, &add_reaction, &redis_client)
+        {
+            eprintln!("Error turning a page: {}", err);
+        }
+        if let Err(err) =
+            crate::discord_channel_approval::handle_reaction(&ctx, &add_reaction, &redis_client)
+        {
+            eprintln!("Error handling a channel proposal: {}", err);
+            let _ = add_reaction
+                .channel_id
+                .say(&ctx.http, strings::UNSPECIFIED_ERROR);
+        }
+        if let Err(err) =
+            crate::discord_quick_commands::handle_reaction(&ctx, &add_reaction, &redis_client)
+        {
+            eprintln!("Error handling a quick command: {}", err);
+            let _ = add_reaction
+                .channel_id
+                .say(&ctx.http, strings::UNSPECIFIED_ERROR);
+        }
+    }
+
+    fn reaction_remove(&self, ctx: Context, removed_reaction: Reaction) {
+        let (bot_id, redis_client) = {
+            let data = ctx.data.read();
+            let bot_id = *data.get::<BotIdKey>().expect("Bot ID was not set");
+            let redis_client = data
+                .get::<RedisClientKey>()
+                .expect("Redis client was not set")
+                .clone();
+            (bot_id, redis_client)
+        };
+        if removed_reaction.user_id == bot_id {
+            return;
+        }
+        if let Err(err) =
+            crate::discord_pagination::handle_reaction(&ctx, &removed_reaction, &redis_client)
+        {
+            eprintln!("Error turning a page: {}", err);
+        }
     }
 
     fn guild_member_addition(&self, ctx: Context, guild_id: GuildId, new_member: Member) {
         if guild_id != crate::discord_sync::GUILD_ID {
             return;
         }
+        let mut redis_client = {
+            let data = ctx.data.read();
+            data.get::<RedisClientKey>()
+                .expect("Redis client was not set")
+                .clone()
+        };
+        // Find out which invite brought the new member
+        if !new_member.user.read().bot {
+            let discord_api = CacheAndHttp {
+                cache: ctx.cache.clone(),
+                http: ctx.http.clone(),
+            };
+            let label = redis_client
+                .get_connection()
+                .map_err(Into::into)
+                .and_then(|mut con| crate::discord_invites::record_join(&mut con, &discord_api));
+            match label {
+                Ok(Some(label)) => println!(
+                    "User {} joined through the invite \"{}\"",
+                    new_member.user.read().id.0,
+                    label
+                ),
+                Ok(None) => (),
+                Err(err) => eprintln!("Could not attribute a join to an invite: {}", err),
+            }
+        }
+        match crate::feature_flags::is_enabled(
+            &mut redis_client,
+            guild_id,
+            crate::feature_flags::Feature::WelcomeMessage,
+        ) {
+            Ok(true) => (),
+            Ok(false) => return,
+            Err(err) => {
+                eprintln!(
+                    "Could not check whether welcome messages are enabled: {}",
+                    err
+                );
+                return;
+            }
+        }
         Self::send_welcome_message(&ctx, &new_member.user.read());
     }
+
+    // Keep the Discord sync's snapshot of the guild's roles and channels up-to-date
+    fn guild_create(&self, _ctx: Context, guild: Guild, _is_new: bool) {
+        crate::discord_state_cache::update_from_guild(&guild);
+    }
+
+    fn guild_role_create(&self, _ctx: Context, guild_id: GuildId, new: Role) {
+        if guild_id == crate::discord_sync::GUILD_ID {
+            crate::discord_state_cache::upsert_role(&new);
+        }
+    }
+
+    fn guild_role_update(
+        &self,
+        _ctx: Context,
+        guild_id: GuildId,
+        _old_data_if_available: Option<Role>,
+        new: Role,
+    ) {
+        if guild_id == crate::discord_sync::GUILD_ID {
+            crate::discord_state_cache::upsert_role(&new);
+        }
+    }
+
+    fn guild_role_delete(
+        &self,
+        _ctx: Context,
+        guild_id: GuildId,
+        removed_role_id: RoleId,
+        _removed_role_data_if_available: Option<Role>,
+    ) {
+        if guild_id == crate::discord_sync::GUILD_ID {
+            crate::discord_state_cache::remove_role(removed_role_id);
+        }
+    }
+
+    fn channel_create(&self, _ctx: Context, channel: Arc<RwLock<GuildChannel>>) {
+        let channel = channel.read();
+        if channel.guild_id == crate::discord_sync::GUILD_ID {
+            crate::discord_state_cache::insert_channel(channel.id);
+        }
+    }
+
+    fn channel_delete(&self, _ctx: Context, channel: Arc<RwLock<GuildChannel>>) {
+        let channel = channel.read();
+        if channel.guild_id == crate::discord_sync::GUILD_ID {
+            crate::discord_state_cache::remove_channel(channel.id);
+        }
+    }
 }
@@ -2,14 +2,31 @@ use crate::error::BoxedError;
 use crate::strings;
 use redis::{Commands, PipelineCommands};
 use regex::Regex;
-use serenity::{model::channel::Message, model::user::User, prelude::*};
+use serenity::{
+    model::channel::Channel, model::channel::ChannelType, model::channel::Message,
+    model::id::ChannelId, model::id::MessageId, model::id::RoleId, model::id::UserId,
+    model::user::User, prelude::*,
+};
 use simple_error::SimpleError;
 use std::borrow::Cow;
 
-const MENTION_PATTERN: &'static str = r"<@(?P<mention_id>[0-9]+)>";
+// Commands that act on a user take a mention (Discord mentions members who
+// have a nickname as <@!id>), a raw Discord ID or a name#discriminator tag.
+// The latter two also work for users who can't be mentioned, e.g. because
+// they left the server.
+const USER_PATTERN: &'static str =
+    r"(?:<@!?(?P<mention_id>[0-9]+)>|(?P<user_id>[0-9]{15,20})|(?P<user_tag>[^\s@#:<>]+#[0-9]{4}))";
+
+// Several users separated by whitespace. Since a group name can't appear
+// twice, the list is split into single users with USER_PATTERN afterwards.
+const USER_LIST_PATTERN: &'static str = r"(?P<users>(?:<@!?[0-9]+>|[0-9]{15,20}|[^\s@#:<>]+#[0-9]{4})(?:\s+(?:<@!?[0-9]+>|[0-9]{15,20}|[^\s@#:<>]+#[0-9]{4}))*)";
+
+// Each suggestion is a message of its own, to show both pictures
+const MAX_LINK_SUGGESTIONS: usize = 10;
 
 pub struct Regexes {
     pub bot_mention: String,
+    pub user_argument: Regex,
     pub link_meetup_dm: Regex,
     pub link_meetup_mention: Regex,
     pub link_meetup_organizer_dm: Regex,
@@ -20,14 +37,119 @@ pub struct Regexes {
     pub unlink_meetup_organizer_mention: Regex,
     pub sync_meetup_mention: Regex,
     pub sync_discord_mention: Regex,
+    pub sync_stats_organizer_mention: Regex,
+    pub preview_organizer_mention: Regex,
+    pub config_get_organizer_mention: Regex,
+    pub config_set_organizer_mention: Regex,
+    pub config_log_organizer_mention: Regex,
+    pub setup_owner_mention: Regex,
+    pub setup_step_owner_mention: Regex,
+    pub schedule_command_organizer_mention: Regex,
+    pub list_scheduled_commands_organizer_mention: Regex,
+    pub unschedule_command_organizer_mention: Regex,
     pub add_user_mention: Regex,
     pub add_host_mention: Regex,
+    pub add_guest_mention: Regex,
     pub remove_user_mention: Regex,
+    pub remove_replied_user_mention: Regex,
     pub remove_host_mention: Regex,
+    pub add_cohost_mention: Regex,
+    pub remove_cohost_mention: Regex,
+    pub quarantine_organizer_mention: Regex,
+    pub release_organizer_mention: Regex,
+    pub ignore_user_organizer_mention: Regex,
+    pub list_ignored_users_organizer_mention: Regex,
+    pub invite_create_organizer_mention: Regex,
+    pub invite_stats_organizer_mention: Regex,
+    pub usage_report_organizer_mention: Regex,
+    pub storage_report_organizer_mention: Regex,
+    pub list_duplicate_events_organizer_mention: Regex,
+    pub allow_duplicate_organizer_mention: Regex,
+    pub tables_host_mention: Regex,
+    pub session_channels_host_mention: Regex,
+    pub sync_pause_host_mention: Regex,
+    pub style_host_mention: Regex,
+    pub hide_history_host_mention: Regex,
+    pub channel_settings_host_mention: Regex,
+    pub change_channel_setting_host_mention: Regex,
+    pub export_members_host_mention: Regex,
+    pub host_digest_dm: Regex,
+    pub host_digest_mention: Regex,
+    pub linking_help_dm: Regex,
+    pub linking_help_mention: Regex,
+    // Only via DM, so that nobody else sees the report
+    pub report_dm: Regex,
+    // Only via DM, since email addresses are private
+    pub register_email_dm: Regex,
+    pub unregister_email_dm: Regex,
+    pub delete_my_data_dm: Regex,
+    pub unregister_email_organizer_dm: Regex,
+    pub approve_email_organizer_dm: Regex,
+    pub claim_ticket_dm: Regex,
+    pub list_identities_dm: Regex,
+    pub primary_identity_dm: Regex,
+    pub nickname_sync_dm: Regex,
+    pub subscribe_mention: Regex,
+    pub unsubscribe_mention: Regex,
+    pub absent_mention: Regex,
+    pub set_character_mention: Regex,
+    pub set_safety_host_mention: Regex,
+    pub show_safety_mention: Regex,
+    pub remove_character_mention: Regex,
+    pub party_mention: Regex,
+    pub add_macro_host_mention: Regex,
+    pub remove_macro_host_mention: Regex,
+    pub list_macros_mention: Regex,
+    pub roll_dm: Regex,
+    pub roll_mention: Regex,
+    pub gm_roll_mention: Regex,
+    pub roll_log_mention: Regex,
+    pub list_subscriptions_mention: Regex,
+    pub subscription_role_organizer_mention: Regex,
+    pub list_tag_rules_organizer_mention: Regex,
+    pub tag_rule_organizer_mention: Regex,
     pub stop_organizer_dm: Regex,
     pub stop_organizer_mention: Regex,
     pub send_expiration_reminder_organizer_mention: Regex,
     pub close_channel_host_mention: Regex,
+    pub recap_host_mention: Regex,
+    pub recap_reply_host_mention: Regex,
+    pub list_recaps_mention: Regex,
+    pub list_flagged_links_organizer_mention: Regex,
+    pub toggle_feature_organizer_mention: Regex,
+    pub list_features_organizer_mention: Regex,
+    pub block_link_organizer_mention: Regex,
+    pub list_blocked_links_organizer_mention: Regex,
+    pub quick_command_organizer_mention: Regex,
+    pub host_verification_organizer_mention: Regex,
+    pub community_digest_channel_organizer_mention: Regex,
+    pub community_digest_schedule_organizer_mention: Regex,
+    pub community_digest_template_organizer_mention: Regex,
+    pub crosspost_webhook_organizer_mention: Regex,
+    pub crosspost_template_organizer_mention: Regex,
+    pub maintenance_organizer_mention: Regex,
+    pub approve_creation_organizer_mention: Regex,
+    pub share_meetup_mention: Regex,
+    pub unshare_meetup_mention: Regex,
+    pub approve_household_organizer_mention: Regex,
+    pub reject_household_organizer_mention: Regex,
+    pub list_household_requests_organizer_mention: Regex,
+    pub confirm_link_organizer_mention: Regex,
+    pub dismiss_link_organizer_mention: Regex,
+    pub suggest_links_organizer_mention: Regex,
+    pub inactive_members_organizer_mention: Regex,
+    pub nudge_inactive_members_organizer_mention: Regex,
+    pub whois_organizer_mention: Regex,
+    pub user_channels_organizer_mention: Regex,
+    pub dues_role_organizer_mention: Regex,
+    pub cleanup_roles_organizer_mention: Regex,
+    pub role_usage_organizer_mention: Regex,
+    pub check_permissions_organizer_mention: Regex,
+    pub list_organizer_tokens_organizer_mention: Regex,
+    pub revoke_organizer_token_organizer_mention: Regex,
+    pub debug_welcome_organizer_mention: Regex,
+    pub debug_sync_series_organizer_mention: Regex,
+    pub debug_keys_organizer_mention: Regex,
 }
 
 impl Regexes {
@@ -63,6 +185,30 @@ impl Regexes {
         }
     }
 
+    pub fn host_digest(&self, is_dm: bool) -> &Regex {
+        if is_dm {
+            &self.host_digest_dm
+        } else {
+            &self.host_digest_mention
+        }
+    }
+
+    pub fn linking_help(&self, is_dm: bool) -> &Regex {
+        if is_dm {
+            &self.linking_help_dm
+        } else {
+            &self.linking_help_mention
+        }
+    }
+
+    pub fn roll(&self, is_dm: bool) -> &Regex {
+        if is_dm {
+            &self.roll_dm
+        } else {
+            &self.roll_mention
+        }
+    }
+
     pub fn stop_organizer(&self, is_dm: bool) -> &Regex {
         if is_dm {
             &self.stop_organizer_dm
@@ -70,18 +216,227 @@ impl Regexes {
             &self.stop_organizer_mention
         }
     }
+
+    // Whether the command would change anything in Discord or Redis and
+    // therefore needs to be refused during maintenance
+    pub fn is_mutating_command(&self, is_dm: bool, content: &str) -> bool {
+        let mutating_regexes = [
+            self.link_meetup(is_dm),
+            self.link_meetup_organizer(is_dm),
+            self.unlink_meetup(is_dm),
+            self.unlink_meetup_organizer(is_dm),
+            self.host_digest(is_dm),
+            self.linking_help(is_dm),
+            &self.register_email_dm,
+            &self.unregister_email_dm,
+            &self.delete_my_data_dm,
+            &self.unregister_email_organizer_dm,
+            &self.approve_email_organizer_dm,
+            &self.claim_ticket_dm,
+            &self.primary_identity_dm,
+            &self.nickname_sync_dm,
+            &self.subscribe_mention,
+            &self.unsubscribe_mention,
+            &self.absent_mention,
+            &self.set_character_mention,
+            &self.set_safety_host_mention,
+            &self.remove_character_mention,
+            &self.add_macro_host_mention,
+            &self.remove_macro_host_mention,
+            &self.subscription_role_organizer_mention,
+            &self.tag_rule_organizer_mention,
+            &self.sync_meetup_mention,
+            &self.sync_discord_mention,
+            &self.add_user_mention,
+            &self.add_host_mention,
+            &self.add_guest_mention,
+            &self.remove_user_mention,
+            &self.remove_replied_user_mention,
+            &self.remove_host_mention,
+            &self.add_cohost_mention,
+            &self.remove_cohost_mention,
+            &self.quarantine_organizer_mention,
+            &self.release_organizer_mention,
+            &self.ignore_user_organizer_mention,
+            &self.invite_create_organizer_mention,
+            &self.allow_duplicate_organizer_mention,
+            &self.tables_host_mention,
+            &self.session_channels_host_mention,
+            &self.sync_pause_host_mention,
+            &self.style_host_mention,
+            &self.hide_history_host_mention,
+            &self.change_channel_setting_host_mention,
+            &self.send_expiration_reminder_organizer_mention,
+            &self.close_channel_host_mention,
+            &self.recap_host_mention,
+            &self.recap_reply_host_mention,
+            &self.toggle_feature_organizer_mention,
+            &self.block_link_organizer_mention,
+            &self.quick_command_organizer_mention,
+            &self.host_verification_organizer_mention,
+            &self.community_digest_channel_organizer_mention,
+            &self.community_digest_schedule_organizer_mention,
+            &self.community_digest_template_organizer_mention,
+            &self.crosspost_webhook_organizer_mention,
+            &self.crosspost_template_organizer_mention,
+            &self.approve_creation_organizer_mention,
+            &self.share_meetup_mention,
+            &self.unshare_meetup_mention,
+            &self.approve_household_organizer_mention,
+            &self.reject_household_organizer_mention,
+            &self.confirm_link_organizer_mention,
+            &self.dismiss_link_organizer_mention,
+            &self.suggest_links_organizer_mention,
+            &self.nudge_inactive_members_organizer_mention,
+            &self.dues_role_organizer_mention,
+            &self.cleanup_roles_organizer_mention,
+            &self.revoke_organizer_token_organizer_mention,
+            &self.debug_sync_series_organizer_mention,
+            &self.config_set_organizer_mention,
+            &self.schedule_command_organizer_mention,
+            &self.unschedule_command_organizer_mention,
+            &self.setup_step_owner_mention,
+        ];
+        mutating_regexes.iter().any(|regex| regex.is_match(content))
+    }
+
+    // The class of a (non-DM) command, which decides whether it gets tidied
+    // up. See discord_tidy.
+    pub fn command_class(&self, content: &str) -> Option<crate::discord_tidy::CommandClass> {
+        let player_regexes = [
+            &self.link_meetup_mention,
+            &self.unlink_meetup_mention,
+            &self.host_digest_mention,
+            &self.linking_help_mention,
+            &self.subscribe_mention,
+            &self.unsubscribe_mention,
+            &self.absent_mention,
+            &self.set_character_mention,
+            &self.show_safety_mention,
+            &self.remove_character_mention,
+            &self.party_mention,
+            &self.list_macros_mention,
+            &self.roll_mention,
+            &self.gm_roll_mention,
+            &self.roll_log_mention,
+            &self.list_subscriptions_mention,
+            &self.list_recaps_mention,
+            &self.share_meetup_mention,
+            &self.unshare_meetup_mention,
+        ];
+        let host_regexes = [
+            &self.add_user_mention,
+            &self.add_host_mention,
+            &self.add_guest_mention,
+            &self.remove_user_mention,
+            &self.remove_replied_user_mention,
+            &self.remove_host_mention,
+            &self.add_cohost_mention,
+            &self.remove_cohost_mention,
+            &self.tables_host_mention,
+            &self.session_channels_host_mention,
+            &self.sync_pause_host_mention,
+            &self.style_host_mention,
+            &self.hide_history_host_mention,
+            &self.channel_settings_host_mention,
+            &self.change_channel_setting_host_mention,
+            &self.export_members_host_mention,
+            &self.set_safety_host_mention,
+            &self.add_macro_host_mention,
+            &self.remove_macro_host_mention,
+            &self.close_channel_host_mention,
+            // Not "recap <text>": the recap is the message itself, so deleting
+            // it would lose what discord_recaps stored a link to. A recap by
+            // reply links to the replied-to message instead.
+            &self.recap_reply_host_mention,
+        ];
+        let organizer_regexes = [
+            &self.sync_meetup_mention,
+            &self.sync_discord_mention,
+            &self.link_meetup_organizer_mention,
+            &self.unlink_meetup_organizer_mention,
+            &self.sync_stats_organizer_mention,
+            &self.preview_organizer_mention,
+            &self.config_get_organizer_mention,
+            &self.config_set_organizer_mention,
+            &self.config_log_organizer_mention,
+            &self.setup_owner_mention,
+            &self.setup_step_owner_mention,
+            &self.schedule_command_organizer_mention,
+            &self.list_scheduled_commands_organizer_mention,
+            &self.unschedule_command_organizer_mention,
+            &self.quarantine_organizer_mention,
+            &self.release_organizer_mention,
+            &self.ignore_user_organizer_mention,
+            &self.list_ignored_users_organizer_mention,
+            &self.invite_create_organizer_mention,
+            &self.invite_stats_organizer_mention,
+            &self.usage_report_organizer_mention,
+            &self.storage_report_organizer_mention,
+            &self.list_duplicate_events_organizer_mention,
+            &self.allow_duplicate_organizer_mention,
+            &self.subscription_role_organizer_mention,
+            &self.list_tag_rules_organizer_mention,
+            &self.tag_rule_organizer_mention,
+            &self.stop_organizer_mention,
+            &self.send_expiration_reminder_organizer_mention,
+            &self.list_flagged_links_organizer_mention,
+            &self.toggle_feature_organizer_mention,
+            &self.list_features_organizer_mention,
+            &self.block_link_organizer_mention,
+            &self.list_blocked_links_organizer_mention,
+            &self.quick_command_organizer_mention,
+            &self.host_verification_organizer_mention,
+            &self.community_digest_channel_organizer_mention,
+            &self.community_digest_schedule_organizer_mention,
+            &self.community_digest_template_organizer_mention,
+            &self.crosspost_webhook_organizer_mention,
+            &self.crosspost_template_organizer_mention,
+            &self.maintenance_organizer_mention,
+            &self.approve_creation_organizer_mention,
+            &self.approve_household_organizer_mention,
+            &self.reject_household_organizer_mention,
+            &self.list_household_requests_organizer_mention,
+            &self.confirm_link_organizer_mention,
+            &self.dismiss_link_organizer_mention,
+            &self.suggest_links_organizer_mention,
+            &self.inactive_members_organizer_mention,
+            &self.nudge_inactive_members_organizer_mention,
+            &self.whois_organizer_mention,
+            &self.user_channels_organizer_mention,
+            &self.dues_role_organizer_mention,
+            &self.cleanup_roles_organizer_mention,
+            &self.role_usage_organizer_mention,
+            &self.check_permissions_organizer_mention,
+            &self.list_organizer_tokens_organizer_mention,
+            &self.revoke_organizer_token_organizer_mention,
+            &self.debug_welcome_organizer_mention,
+            &self.debug_sync_series_organizer_mention,
+            &self.debug_keys_organizer_mention,
+        ];
+        let is_match = |regexes: &[&Regex]| regexes.iter().any(|regex| regex.is_match(content));
+        if is_match(&player_regexes) {
+            Some(crate::discord_tidy::CommandClass::Player)
+        } else if is_match(&host_regexes) {
+            Some(crate::discord_tidy::CommandClass::Host)
+        } else if is_match(&organizer_regexes) {
+            Some(crate::discord_tidy::CommandClass::Organizer)
+        } else {
+            None
+        }
+    }
 }
 
 pub fn compile_regexes(bot_id: u64) -> Regexes {
-    let bot_mention = format!(r"<@{}>", bot_id);
+    let bot_mention = format!(r"<@!?{}>", bot_id);
     let link_meetup_dm = r"^link[ -]?meetup\s*$";
     let link_meetup_mention = format!(
         r"^{bot_mention}\s+link[ -]?meetup\s*$",
         bot_mention = bot_mention
     );
     let link_meetup_organizer = format!(
-        r"link[ -]?meetup\s+{mention_pattern}\s+(?P<meetupid>[0-9]+)",
-        mention_pattern = MENTION_PATTERN
+        r"link[ -]?meetup\s+{user_pattern}\s+(?P<meetupid>[0-9]+)",
+        user_pattern = USER_PATTERN
     );
     let link_meetup_organizer_dm = format!(
         r"^{link_meetup_organizer}\s*$",
@@ -100,8 +455,8 @@ pub fn compile_regexes(bot_id: u64) -> Regexes {
         unlink_meetup = unlink_meetup
     );
     let unlink_meetup_organizer = format!(
-        r"unlink[ -]?meetup\s+{mention_pattern}",
-        mention_pattern = MENTION_PATTERN
+        r"unlink[ -]?meetup\s+{user_pattern}",
+        user_pattern = USER_PATTERN
     );
     let unlink_meetup_organizer_dm = format!(
         r"^{unlink_meetup_organizer}\s*$",
@@ -120,25 +475,214 @@ pub fn compile_regexes(bot_id: u64) -> Regexes {
         r"^{bot_mention}\s+sync\s+discord\s*$",
         bot_mention = bot_mention
     );
+    let sync_stats_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)sync\s+stats(?:\s+(?P<num_runs>[0-9]{{1,3}}))?\s*$",
+        bot_mention = bot_mention
+    );
+    let preview_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)preview\s+(?P<series_id>[^\s]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let config_get_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)config\s+get(?:\s+(?P<key>[^\s]+))?\s*$",
+        bot_mention = bot_mention
+    );
+    let config_set_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)config\s+set\s+(?P<key>[^\s]+)\s+(?P<value>[^\s]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let config_log_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)config\s+log\s*$",
+        bot_mention = bot_mention
+    );
+    let setup_owner_mention = format!(
+        r"^{bot_mention}\s+(?i)set\s*up\s*$",
+        bot_mention = bot_mention
+    );
+    let setup_step_owner_mention = format!(
+        r"^{bot_mention}\s+(?i)set\s*up\s+(?P<step>[^\s]+)\s+(?P<value>[^\s]+)\s*$",
+        bot_mention = bot_mention
+    );
+    // Phones like to turn the quotes into curly ones
+    let schedule_command_organizer_mention = format!(
+        r#"^{bot_mention}\s+(?i)schedule\s+["“](?P<command>[^"“”]+)["”]\s+at\s+(?P<due_time>.+?)\s*$"#,
+        bot_mention = bot_mention
+    );
+    let list_scheduled_commands_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)scheduled\s+commands\s*$",
+        bot_mention = bot_mention
+    );
+    let unschedule_command_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)unschedule\s+(?P<command_id>[A-Za-z0-9_-]+)\s*$",
+        bot_mention = bot_mention
+    );
     let add_user_mention = format!(
-        r"^{bot_mention}\s+add\s+{mention_pattern}\s*$",
+        r"^{bot_mention}\s+add\s+{user_list_pattern}\s*$",
         bot_mention = bot_mention,
-        mention_pattern = MENTION_PATTERN,
+        user_list_pattern = USER_LIST_PATTERN,
     );
     let add_host_mention = format!(
-        r"^{bot_mention}\s+add\s+host\s+{mention_pattern}\s*$",
+        r"^{bot_mention}\s+add\s+host\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let add_guest_mention = format!(
+        r"^{bot_mention}\s+(?i)add\s+guest\s+{user_pattern}\s+for\s+(?P<amount>[0-9]+)\s+(?P<unit>hours?|days?|weeks?)\s*$",
         bot_mention = bot_mention,
-        mention_pattern = MENTION_PATTERN,
+        user_pattern = USER_PATTERN,
     );
     let remove_user_mention = format!(
-        r"^{bot_mention}\s+remove\s+{mention_pattern}\s*$",
+        r"^{bot_mention}\s+remove\s+{user_list_pattern}\s*$",
         bot_mention = bot_mention,
-        mention_pattern = MENTION_PATTERN,
+        user_list_pattern = USER_LIST_PATTERN,
     );
+    // In a reply to a message of the user to remove
+    let remove_replied_user_mention =
+        format!(r"^{bot_mention}\s+remove\s*$", bot_mention = bot_mention);
     let remove_host_mention = format!(
-        r"^{bot_mention}\s+remove\s+host\s+{mention_pattern}\s*$",
+        r"^{bot_mention}\s+remove\s+host\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let add_cohost_mention = format!(
+        r"^{bot_mention}\s+add\s+co-?host\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let remove_cohost_mention = format!(
+        r"^{bot_mention}\s+remove\s+co-?host\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let tables_host_mention = format!(
+        r"^{bot_mention}\s+(?i)tables\s+(?P<count>[0-9]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let export_members_host_mention = format!(
+        r"^{bot_mention}\s+(?i)export\s+members\s*$",
+        bot_mention = bot_mention
+    );
+    let session_channels_host_mention = format!(
+        r"^{bot_mention}\s+(?i)session\s+channels\s+(?P<toggle>on|off)\s*$",
+        bot_mention = bot_mention
+    );
+    let sync_pause_host_mention = format!(
+        r"^{bot_mention}\s+(?i)(?P<toggle>pause|resume)\s+sync\s*$",
+        bot_mention = bot_mention
+    );
+    let style_host_mention = format!(
+        r"^{bot_mention}\s+(?i)style(?:\s+colou?r\s+(?P<colour>\S+))?(?:\s+emoji\s+(?P<emoji>\S+))?\s*$",
+        bot_mention = bot_mention
+    );
+    let hide_history_host_mention = format!(
+        r"^{bot_mention}\s+(?i)hide\s+history\s+(?P<days>[0-9]+|off)\s*$",
+        bot_mention = bot_mention
+    );
+    let channel_settings_host_mention = format!(
+        r"^{bot_mention}\s+(?i)settings\s*$",
+        bot_mention = bot_mention
+    );
+    let change_channel_setting_host_mention = format!(
+        r"^{bot_mention}\s+(?i)settings\s+(?P<setting>[^\s]+)\s+(?P<value>.+?)\s*$",
+        bot_mention = bot_mention
+    );
+    let host_digest_dm = r"^(?i)digest\s+(?P<toggle>on|off)\s*$";
+    let host_digest_mention = format!(
+        r"^{bot_mention}\s+(?i)digest\s+(?P<toggle>on|off)\s*$",
+        bot_mention = bot_mention
+    );
+    let linking_help_dm = r"^(?i)linking\s+help\s*$";
+    let linking_help_mention = format!(
+        r"^{bot_mention}\s+(?i)linking\s+help\s*$",
+        bot_mention = bot_mention
+    );
+    let report_dm = r"^(?is)report(?P<anonymous>\s+anonymous(?:ly)?)?\s+(?P<text>.+?)\s*$";
+    let register_email_dm = r"^(?i)register\s+e-?mail\s+(?P<email>\S+)\s*$";
+    let unregister_email_dm = r"^(?i)unregister\s+e-?mail\s*$";
+    let delete_my_data_dm = r"^(?i)delete\s+my\s+data\s*$";
+    let unregister_email_organizer_dm = r"^(?i)unregister\s+e-?mail\s+(?P<email>\S+)\s*$";
+    let approve_email_organizer_dm = format!(
+        r"^(?i)approve\s+e-?mail\s+{user_pattern}\s*$",
+        user_pattern = USER_PATTERN,
+    );
+    let claim_ticket_dm = r"^(?i)claim\s+ticket\s+#?(?P<order_id>[0-9]+)\s*$";
+    let list_identities_dm = r"^(?i)identities\s*$";
+    let primary_identity_dm = r"^(?i)primary\s+identity\s+(?P<identity>\S+)\s*$";
+    let nickname_sync_dm = r"^(?i)nickname\s+sync\s+(?P<setting>on|off)\s*$";
+    let subscribe_mention = format!(
+        r"^{bot_mention}\s+(?i)subscribe\s+(?P<tag>[a-z0-9_-]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let unsubscribe_mention = format!(
+        r"^{bot_mention}\s+(?i)unsubscribe\s+(?P<tag>[a-z0-9_-]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let absent_mention = format!(
+        r"^{bot_mention}\s+(?i)absent(?:\s+(?P<reason>.+?))?\s*$",
+        bot_mention = bot_mention
+    );
+    let roll =
+        r"(?i)roll\s+(?P<expression>[0-9d+\-\s]+?)(?:\s+(?P<mode>adv|advantage|dis|disadvantage))?";
+    let roll_dm = format!(r"^{roll}\s*$", roll = roll);
+    let roll_mention = format!(
+        r"^{bot_mention}\s+{roll}\s*$",
+        bot_mention = bot_mention,
+        roll = roll
+    );
+    let gm_roll_mention = format!(
+        r"^{bot_mention}\s+(?i)gm{roll}\s*$",
         bot_mention = bot_mention,
-        mention_pattern = MENTION_PATTERN,
+        roll = roll
+    );
+    let roll_log_mention = format!(
+        r"^{bot_mention}\s+(?i)roll\s+log\s*$",
+        bot_mention = bot_mention
+    );
+    // Safety info can span several lines
+    let set_safety_host_mention = format!(
+        r"^{bot_mention}\s+(?is)safety\s+set\s+(?P<field>lines|veils|tools)\s+(?P<value>.+?)\s*$",
+        bot_mention = bot_mention
+    );
+    let show_safety_mention = format!(
+        r"^{bot_mention}\s+(?i)safety\s*$",
+        bot_mention = bot_mention
+    );
+    let set_character_mention = format!(
+        r"^{bot_mention}\s+(?i)character\s+set\s+(?P<url>\S+)\s+(?P<name>.+?)\s*$",
+        bot_mention = bot_mention
+    );
+    let remove_character_mention = format!(
+        r"^{bot_mention}\s+(?i)character\s+remove\s*$",
+        bot_mention = bot_mention
+    );
+    let party_mention = format!(r"^{bot_mention}\s+(?i)party\s*$", bot_mention = bot_mention);
+    let add_macro_host_mention = format!(
+        r"^{bot_mention}\s+(?i)macro\s+add\s+(?P<name>\S+)\s+(?s)(?P<text>.+?)\s*$",
+        bot_mention = bot_mention
+    );
+    let remove_macro_host_mention = format!(
+        r"^{bot_mention}\s+(?i)macro\s+remove\s+(?P<name>\S+)\s*$",
+        bot_mention = bot_mention
+    );
+    let list_macros_mention = format!(
+        r"^{bot_mention}\s+(?i)macros\s*$",
+        bot_mention = bot_mention
+    );
+    let list_subscriptions_mention = format!(
+        r"^{bot_mention}\s+(?i)subscriptions\s*$",
+        bot_mention = bot_mention
+    );
+    let subscription_role_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)subscription\s+role\s+(?P<tag>[a-z0-9_-]+)\s+(?:<@&(?P<role_id>[0-9]+)>|off)\s*$",
+        bot_mention = bot_mention
+    );
+    let list_tag_rules_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)tag\s+rules\s*$",
+        bot_mention = bot_mention
+    );
+    let tag_rule_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)tag\s+rule\s+(?P<tag>[a-z0-9_-]+)\s+(?P<keywords>.+?)\s*$",
+        bot_mention = bot_mention
     );
     let stop_organizer_dm = r"^(?i)stop\s*$";
     let stop_organizer_mention =
@@ -151,8 +695,212 @@ pub fn compile_regexes(bot_id: u64) -> Regexes {
         r"^{bot_mention}\s+(?i)close\s+channel\s*$",
         bot_mention = bot_mention
     );
+    // Recaps can span several lines
+    let recap_host_mention = format!(
+        r"^{bot_mention}\s+(?is)recap\s+(?P<text>.+?)\s*$",
+        bot_mention = bot_mention
+    );
+    // In a reply to the message with the recap
+    let recap_reply_host_mention =
+        format!(r"^{bot_mention}\s+(?i)recap\s*$", bot_mention = bot_mention);
+    let list_recaps_mention = format!(
+        r"^{bot_mention}\s+(?i)recaps\s*$",
+        bot_mention = bot_mention
+    );
+    let list_flagged_links_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)list\s+flagged\s+links\s*$",
+        bot_mention = bot_mention
+    );
+    let toggle_feature_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)feature\s+(?P<toggle>enable|disable)\s+(?P<feature>[a-z_]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let list_features_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)features\s*$",
+        bot_mention = bot_mention
+    );
+    let block_link_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)(?P<toggle>block|unblock)\s+links?\s+(?P<pattern>\S+)\s*$",
+        bot_mention = bot_mention
+    );
+    let list_blocked_links_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)blocked\s+links\s*$",
+        bot_mention = bot_mention
+    );
+    let quick_command_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)quick\s+command\s+(?P<command>schedule|join|remove)\s+(?P<emoji>\S+)\s*$",
+        bot_mention = bot_mention
+    );
+    let host_verification_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)host\s+verification\s+(?P<mode>off|alert|enforce)\s*$",
+        bot_mention = bot_mention
+    );
+    let community_digest_channel_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)community\s+digest\s+channel\s+(?:<#(?P<channel_id>[0-9]+)>|off)\s*$",
+        bot_mention = bot_mention
+    );
+    let community_digest_schedule_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)community\s+digest\s+schedule\s+(?P<weekday>[a-z]+)\s+(?P<hour>[0-9]{{1,2}})(?::00)?\s*$",
+        bot_mention = bot_mention
+    );
+    let community_digest_template_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)community\s+digest\s+template\s+(?s)(?P<template>.+?)\s*$",
+        bot_mention = bot_mention
+    );
+    // Discord users might wrap the URL in <> to suppress the preview
+    let crosspost_webhook_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)crosspost\s+webhook\s+(?:<?(?P<url>https://\S+?)>?|off)\s*$",
+        bot_mention = bot_mention
+    );
+    let crosspost_template_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)crosspost\s+template\s+(?s)(?P<template>.+?)\s*$",
+        bot_mention = bot_mention
+    );
+    let quarantine_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)quarantine\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let release_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)release\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let ignore_user_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)(?P<toggle>ignore|unignore)\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let list_ignored_users_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)ignored\s+users\s*$",
+        bot_mention = bot_mention
+    );
+    let invite_create_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)invite\s+create\s+(?P<label>.+?)\s*$",
+        bot_mention = bot_mention
+    );
+    let invite_stats_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)invite\s+stats\s*$",
+        bot_mention = bot_mention
+    );
+    let usage_report_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)usage\s+report\s*$",
+        bot_mention = bot_mention
+    );
+    let storage_report_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)storage\s+report\s*$",
+        bot_mention = bot_mention
+    );
+    let list_duplicate_events_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)list\s+duplicate\s+events\s*$",
+        bot_mention = bot_mention
+    );
+    let allow_duplicate_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)allow\s+duplicate\s+(?P<event_id>[a-zA-Z0-9]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let maintenance_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)maintenance\s+(?P<toggle>on|off)\s*$",
+        bot_mention = bot_mention
+    );
+    let approve_creation_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)approve\s+creation\s*$",
+        bot_mention = bot_mention
+    );
+    let share_meetup_mention = format!(
+        r"^{bot_mention}\s+(?i)share\s+meetup\s+(?:with\s+)?{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let unshare_meetup_mention = format!(
+        r"^{bot_mention}\s+(?i)unshare\s+meetup\s+(?:with\s+)?{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let approve_household_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)approve\s+household\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let reject_household_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)reject\s+household\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let list_household_requests_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)household\s+requests\s*$",
+        bot_mention = bot_mention
+    );
+    let confirm_link_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)confirm\s+link\s+(?P<meetupid>[0-9]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let dismiss_link_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)dismiss\s+link\s+(?P<meetupid>[0-9]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let suggest_links_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)suggest\s+links\s*$",
+        bot_mention = bot_mention
+    );
+    let inactive_members_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)inactive\s+members(?:\s+(?P<days>[0-9]{{1,4}}))?\s*$",
+        bot_mention = bot_mention
+    );
+    let nudge_inactive_members_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)nudge\s+inactive\s+members(?:\s+(?P<days>[0-9]{{1,4}}))?\s*$",
+        bot_mention = bot_mention
+    );
+    let whois_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)whois\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN
+    );
+    let user_channels_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)channels\s+(?:of\s+)?{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN
+    );
+    let dues_role_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)dues\s+role\s+<@&(?P<role_id>[0-9]+)>(?:\s+(?P<off>off))?\s*$",
+        bot_mention = bot_mention
+    );
+    let cleanup_roles_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)clean\s*up\s+roles\s*$",
+        bot_mention = bot_mention
+    );
+    let role_usage_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)role\s+(?:usage|limit)\s*$",
+        bot_mention = bot_mention
+    );
+    let check_permissions_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)check\s+permissions\s*$",
+        bot_mention = bot_mention
+    );
+    let list_organizer_tokens_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)organizer\s+tokens\s*$",
+        bot_mention = bot_mention
+    );
+    let revoke_organizer_token_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)revoke\s+organizer\s+token\s+(?P<meetupid>[0-9]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let debug_welcome_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)debug\s+welcome\s+{user_pattern}\s*$",
+        bot_mention = bot_mention,
+        user_pattern = USER_PATTERN,
+    );
+    let debug_sync_series_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)debug\s+sync-series\s+(?P<series_id>[^\s]+)\s*$",
+        bot_mention = bot_mention
+    );
+    let debug_keys_organizer_mention = format!(
+        r"^{bot_mention}\s+(?i)debug\s+keys\s+(?P<pattern>[^\s]+)\s*$",
+        bot_mention = bot_mention
+    );
     Regexes {
-        bot_mention: bot_mention,
+        bot_mention: format!("<@{}>", bot_id),
+        user_argument: Regex::new(USER_PATTERN).unwrap(),
         link_meetup_dm: Regex::new(link_meetup_dm).unwrap(),
         link_meetup_mention: Regex::new(link_meetup_mention.as_str()).unwrap(),
         link_meetup_organizer_dm: Regex::new(link_meetup_organizer_dm.as_str()).unwrap(),
@@ -164,10 +912,79 @@ pub fn compile_regexes(bot_id: u64) -> Regexes {
             .unwrap(),
         sync_meetup_mention: Regex::new(sync_meetup_mention.as_str()).unwrap(),
         sync_discord_mention: Regex::new(sync_discord_mention.as_str()).unwrap(),
+        sync_stats_organizer_mention: Regex::new(sync_stats_organizer_mention.as_str()).unwrap(),
+        preview_organizer_mention: Regex::new(preview_organizer_mention.as_str()).unwrap(),
+        config_get_organizer_mention: Regex::new(config_get_organizer_mention.as_str()).unwrap(),
+        config_set_organizer_mention: Regex::new(config_set_organizer_mention.as_str()).unwrap(),
+        config_log_organizer_mention: Regex::new(config_log_organizer_mention.as_str()).unwrap(),
+        setup_owner_mention: Regex::new(setup_owner_mention.as_str()).unwrap(),
+        setup_step_owner_mention: Regex::new(setup_step_owner_mention.as_str()).unwrap(),
+        schedule_command_organizer_mention: Regex::new(schedule_command_organizer_mention.as_str())
+            .unwrap(),
+        list_scheduled_commands_organizer_mention: Regex::new(
+            list_scheduled_commands_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        unschedule_command_organizer_mention: Regex::new(
+            unschedule_command_organizer_mention.as_str(),
+        )
+        .unwrap(),
         add_user_mention: Regex::new(add_user_mention.as_str()).unwrap(),
         add_host_mention: Regex::new(add_host_mention.as_str()).unwrap(),
+        add_guest_mention: Regex::new(add_guest_mention.as_str()).unwrap(),
         remove_user_mention: Regex::new(remove_user_mention.as_str()).unwrap(),
+        remove_replied_user_mention: Regex::new(remove_replied_user_mention.as_str()).unwrap(),
         remove_host_mention: Regex::new(remove_host_mention.as_str()).unwrap(),
+        add_cohost_mention: Regex::new(add_cohost_mention.as_str()).unwrap(),
+        remove_cohost_mention: Regex::new(remove_cohost_mention.as_str()).unwrap(),
+        tables_host_mention: Regex::new(tables_host_mention.as_str()).unwrap(),
+        session_channels_host_mention: Regex::new(session_channels_host_mention.as_str()).unwrap(),
+        sync_pause_host_mention: Regex::new(sync_pause_host_mention.as_str()).unwrap(),
+        style_host_mention: Regex::new(style_host_mention.as_str()).unwrap(),
+        hide_history_host_mention: Regex::new(hide_history_host_mention.as_str()).unwrap(),
+        channel_settings_host_mention: Regex::new(channel_settings_host_mention.as_str()).unwrap(),
+        change_channel_setting_host_mention: Regex::new(
+            change_channel_setting_host_mention.as_str(),
+        )
+        .unwrap(),
+        export_members_host_mention: Regex::new(export_members_host_mention.as_str()).unwrap(),
+        host_digest_dm: Regex::new(host_digest_dm).unwrap(),
+        host_digest_mention: Regex::new(host_digest_mention.as_str()).unwrap(),
+        linking_help_dm: Regex::new(linking_help_dm).unwrap(),
+        linking_help_mention: Regex::new(linking_help_mention.as_str()).unwrap(),
+        report_dm: Regex::new(report_dm).unwrap(),
+        register_email_dm: Regex::new(register_email_dm).unwrap(),
+        unregister_email_dm: Regex::new(unregister_email_dm).unwrap(),
+        delete_my_data_dm: Regex::new(delete_my_data_dm).unwrap(),
+        unregister_email_organizer_dm: Regex::new(unregister_email_organizer_dm).unwrap(),
+        approve_email_organizer_dm: Regex::new(approve_email_organizer_dm.as_str()).unwrap(),
+        claim_ticket_dm: Regex::new(claim_ticket_dm).unwrap(),
+        list_identities_dm: Regex::new(list_identities_dm).unwrap(),
+        primary_identity_dm: Regex::new(primary_identity_dm).unwrap(),
+        nickname_sync_dm: Regex::new(nickname_sync_dm).unwrap(),
+        subscribe_mention: Regex::new(subscribe_mention.as_str()).unwrap(),
+        unsubscribe_mention: Regex::new(unsubscribe_mention.as_str()).unwrap(),
+        absent_mention: Regex::new(absent_mention.as_str()).unwrap(),
+        set_character_mention: Regex::new(set_character_mention.as_str()).unwrap(),
+        set_safety_host_mention: Regex::new(set_safety_host_mention.as_str()).unwrap(),
+        show_safety_mention: Regex::new(show_safety_mention.as_str()).unwrap(),
+        remove_character_mention: Regex::new(remove_character_mention.as_str()).unwrap(),
+        party_mention: Regex::new(party_mention.as_str()).unwrap(),
+        add_macro_host_mention: Regex::new(add_macro_host_mention.as_str()).unwrap(),
+        remove_macro_host_mention: Regex::new(remove_macro_host_mention.as_str()).unwrap(),
+        list_macros_mention: Regex::new(list_macros_mention.as_str()).unwrap(),
+        roll_dm: Regex::new(roll_dm.as_str()).unwrap(),
+        roll_mention: Regex::new(roll_mention.as_str()).unwrap(),
+        gm_roll_mention: Regex::new(gm_roll_mention.as_str()).unwrap(),
+        roll_log_mention: Regex::new(roll_log_mention.as_str()).unwrap(),
+        list_subscriptions_mention: Regex::new(list_subscriptions_mention.as_str()).unwrap(),
+        subscription_role_organizer_mention: Regex::new(
+            subscription_role_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        list_tag_rules_organizer_mention: Regex::new(list_tag_rules_organizer_mention.as_str())
+            .unwrap(),
+        tag_rule_organizer_mention: Regex::new(tag_rule_organizer_mention.as_str()).unwrap(),
         stop_organizer_dm: Regex::new(stop_organizer_dm).unwrap(),
         stop_organizer_mention: Regex::new(stop_organizer_mention.as_str()).unwrap(),
         send_expiration_reminder_organizer_mention: Regex::new(
@@ -175,56 +992,229 @@ pub fn compile_regexes(bot_id: u64) -> Regexes {
         )
         .unwrap(),
         close_channel_host_mention: Regex::new(close_channel_host_mention.as_str()).unwrap(),
-    }
-}
-
-impl crate::discord_bot::Handler {
-    pub fn link_meetup(ctx: &Context, msg: &Message, user_id: u64) -> crate::Result<()> {
-        let redis_key_d2m = format!("discord_user:{}:meetup_user", user_id);
-        let (redis_connection_mutex, meetup_client_mutex, bot_id) = {
-            let data = ctx.data.read();
-            (
-                data.get::<crate::discord_bot::RedisConnectionKey>()
-                    .ok_or_else(|| SimpleError::new("Redis connection was not set"))?
-                    .clone(),
-                data.get::<crate::discord_bot::MeetupClientKey>()
-                    .ok_or_else(|| SimpleError::new("Meetup client was not set"))?
-                    .clone(),
-                data.get::<crate::discord_bot::BotIdKey>()
-                    .ok_or_else(|| SimpleError::new("Bot ID was not set"))?
-                    .clone(),
-            )
-        };
-        // Check if there is already a meetup id linked to this user
-        // and issue a warning
-        let linked_meetup_id: Option<u64> = {
-            let mut redis_connection = redis_connection_mutex.lock();
-            redis_connection.get(&redis_key_d2m)?
-        };
-        if let Some(linked_meetup_id) = linked_meetup_id {
-            match *meetup_client_mutex.read() {
-                Some(ref meetup_client) => {
-                    match meetup_client.get_member_profile(Some(linked_meetup_id))? {
-                        Some(user) => {
-                            let _ = msg.author.direct_message(ctx, |message| {
-                                message.content(strings::DISCORD_ALREADY_LINKED_MESSAGE1(
-                                    &user.name, bot_id.0,
-                                ))
-                            });
-                            let _ = msg.react(ctx, "\u{2705}");
-                        }
-                        _ => {
-                            let _ = msg.author.direct_message(ctx, |message| {
-                                message
-                                    .content(strings::NONEXISTENT_MEETUP_LINKED_MESSAGE(bot_id.0))
-                            });
-                            let _ = msg.react(ctx, "\u{2705}");
-                        }
-                    }
-                }
-                _ => {
-                    let _ = msg.author.direct_message(ctx, |message| {
-                        message.content(strings::DISCORD_ALREADY_LINKED_MESSAGE2(bot_id.0))
+        recap_host_mention: Regex::new(recap_host_mention.as_str()).unwrap(),
+        recap_reply_host_mention: Regex::new(recap_reply_host_mention.as_str()).unwrap(),
+        list_recaps_mention: Regex::new(list_recaps_mention.as_str()).unwrap(),
+        list_flagged_links_organizer_mention: Regex::new(
+            list_flagged_links_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        toggle_feature_organizer_mention: Regex::new(toggle_feature_organizer_mention.as_str())
+            .unwrap(),
+        quick_command_organizer_mention: Regex::new(quick_command_organizer_mention.as_str())
+            .unwrap(),
+        host_verification_organizer_mention: Regex::new(
+            host_verification_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        community_digest_channel_organizer_mention: Regex::new(
+            community_digest_channel_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        community_digest_schedule_organizer_mention: Regex::new(
+            community_digest_schedule_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        community_digest_template_organizer_mention: Regex::new(
+            community_digest_template_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        crosspost_webhook_organizer_mention: Regex::new(
+            crosspost_webhook_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        crosspost_template_organizer_mention: Regex::new(
+            crosspost_template_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        quarantine_organizer_mention: Regex::new(quarantine_organizer_mention.as_str()).unwrap(),
+        release_organizer_mention: Regex::new(release_organizer_mention.as_str()).unwrap(),
+        ignore_user_organizer_mention: Regex::new(ignore_user_organizer_mention.as_str()).unwrap(),
+        list_ignored_users_organizer_mention: Regex::new(
+            list_ignored_users_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        invite_create_organizer_mention: Regex::new(invite_create_organizer_mention.as_str())
+            .unwrap(),
+        invite_stats_organizer_mention: Regex::new(invite_stats_organizer_mention.as_str())
+            .unwrap(),
+        usage_report_organizer_mention: Regex::new(usage_report_organizer_mention.as_str())
+            .unwrap(),
+        storage_report_organizer_mention: Regex::new(storage_report_organizer_mention.as_str())
+            .unwrap(),
+        list_duplicate_events_organizer_mention: Regex::new(
+            list_duplicate_events_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        allow_duplicate_organizer_mention: Regex::new(allow_duplicate_organizer_mention.as_str())
+            .unwrap(),
+        list_features_organizer_mention: Regex::new(list_features_organizer_mention.as_str())
+            .unwrap(),
+        block_link_organizer_mention: Regex::new(block_link_organizer_mention.as_str()).unwrap(),
+        list_blocked_links_organizer_mention: Regex::new(
+            list_blocked_links_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        maintenance_organizer_mention: Regex::new(maintenance_organizer_mention.as_str()).unwrap(),
+        share_meetup_mention: Regex::new(share_meetup_mention.as_str()).unwrap(),
+        unshare_meetup_mention: Regex::new(unshare_meetup_mention.as_str()).unwrap(),
+        approve_household_organizer_mention: Regex::new(
+            approve_household_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        reject_household_organizer_mention: Regex::new(reject_household_organizer_mention.as_str())
+            .unwrap(),
+        list_household_requests_organizer_mention: Regex::new(
+            list_household_requests_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        confirm_link_organizer_mention: Regex::new(confirm_link_organizer_mention.as_str())
+            .unwrap(),
+        dismiss_link_organizer_mention: Regex::new(dismiss_link_organizer_mention.as_str())
+            .unwrap(),
+        suggest_links_organizer_mention: Regex::new(suggest_links_organizer_mention.as_str())
+            .unwrap(),
+        inactive_members_organizer_mention: Regex::new(inactive_members_organizer_mention.as_str())
+            .unwrap(),
+        nudge_inactive_members_organizer_mention: Regex::new(
+            nudge_inactive_members_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        whois_organizer_mention: Regex::new(whois_organizer_mention.as_str()).unwrap(),
+        user_channels_organizer_mention: Regex::new(user_channels_organizer_mention.as_str())
+            .unwrap(),
+        dues_role_organizer_mention: Regex::new(dues_role_organizer_mention.as_str()).unwrap(),
+        cleanup_roles_organizer_mention: Regex::new(cleanup_roles_organizer_mention.as_str())
+            .unwrap(),
+        role_usage_organizer_mention: Regex::new(role_usage_organizer_mention.as_str()).unwrap(),
+        approve_creation_organizer_mention: Regex::new(approve_creation_organizer_mention.as_str())
+            .unwrap(),
+        check_permissions_organizer_mention: Regex::new(
+            check_permissions_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        list_organizer_tokens_organizer_mention: Regex::new(
+            list_organizer_tokens_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        revoke_organizer_token_organizer_mention: Regex::new(
+            revoke_organizer_token_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        debug_welcome_organizer_mention: Regex::new(debug_welcome_organizer_mention.as_str())
+            .unwrap(),
+        debug_sync_series_organizer_mention: Regex::new(
+            debug_sync_series_organizer_mention.as_str(),
+        )
+        .unwrap(),
+        debug_keys_organizer_mention: Regex::new(debug_keys_organizer_mention.as_str()).unwrap(),
+    }
+}
+
+/// Resolves the user argument of a command matched by `USER_PATTERN`.
+/// Tags are looked up among the users in the cache, so they only work for
+/// users the bot has seen since it started.
+pub fn resolve_user(
+    cache: &serenity::cache::CacheRwLock,
+    captures: &regex::Captures,
+) -> Option<UserId> {
+    if let Some(id) = captures.name("mention_id").or(captures.name("user_id")) {
+        return id.as_str().parse::<u64>().ok().map(UserId);
+    }
+    let tag = captures.name("user_tag")?.as_str().to_lowercase();
+    let cache = cache.read();
+    let user = cache
+        .users
+        .values()
+        .find(|user| user.read().tag().to_lowercase() == tag)?;
+    let id = user.read().id;
+    Some(id)
+}
+
+// Returns whether the user was a co-host of the channel. Their permission
+// overwrite is up to the caller.
+pub fn forget_cohost<C: redis::ConnectionLike>(
+    channel_id: ChannelId,
+    discord_id: u64,
+    con: &mut C,
+) -> Result<bool, BoxedError> {
+    let redis_channel_cohosts_key = format!("discord_channel:{}:cohosts", channel_id.0);
+    let was_cohost: bool = con.srem(&redis_channel_cohosts_key, discord_id)?;
+    Ok(was_cohost)
+}
+
+/// Splits a list of users matched by `USER_LIST_PATTERN` and resolves each of
+/// them. The users are returned as written as well, for error messages.
+pub fn resolve_users(
+    cache: &serenity::cache::CacheRwLock,
+    regexes: &Regexes,
+    users: &str,
+) -> Vec<(String, Option<u64>)> {
+    regexes
+        .user_argument
+        .captures_iter(users)
+        .map(|captures| {
+            let discord_id = resolve_user(cache, &captures).map(|id| id.0);
+            (captures[0].to_string(), discord_id)
+        })
+        .collect()
+}
+
+impl crate::discord_bot::Handler {
+    pub fn link_meetup(ctx: &Context, msg: &Message, user_id: u64) -> crate::Result<()> {
+        let (redis_connection_mutex, meetup_client_mutex, bot_id) = {
+            let data = ctx.data.read();
+            (
+                data.get::<crate::discord_bot::RedisConnectionKey>()
+                    .ok_or_else(|| SimpleError::new("Redis connection was not set"))?
+                    .clone(),
+                data.get::<crate::discord_bot::MeetupClientKey>()
+                    .ok_or_else(|| SimpleError::new("Meetup client was not set"))?
+                    .clone(),
+                data.get::<crate::discord_bot::BotIdKey>()
+                    .ok_or_else(|| SimpleError::new("Bot ID was not set"))?
+                    .clone(),
+            )
+        };
+        // Quarantined users can't link their account
+        let is_quarantined = {
+            let mut redis_connection = redis_connection_mutex.lock();
+            crate::quarantine::is_quarantined(&mut *redis_connection, user_id)?
+        };
+        if is_quarantined {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::USER_QUARANTINED_REFUSED);
+            return Ok(());
+        }
+        // Check if there is already a meetup id linked to this user
+        // and issue a warning
+        let linked_meetup_id: Option<u64> = {
+            let mut redis_connection = redis_connection_mutex.lock();
+            crate::identity::meetup_id(user_id, &mut *redis_connection)?
+        };
+        if let Some(linked_meetup_id) = linked_meetup_id {
+            match *meetup_client_mutex.read() {
+                Some(ref meetup_client) => {
+                    match meetup_client.get_member_profile(Some(linked_meetup_id))? {
+                        Some(user) => {
+                            let _ = msg.author.direct_message(ctx, |message| {
+                                message.content(strings::DISCORD_ALREADY_LINKED_MESSAGE1(
+                                    &user.name, bot_id.0,
+                                ))
+                            });
+                            let _ = msg.react(ctx, "\u{2705}");
+                        }
+                        _ => {
+                            let _ = msg.author.direct_message(ctx, |message| {
+                                message
+                                    .content(strings::NONEXISTENT_MEETUP_LINKED_MESSAGE(bot_id.0))
+                            });
+                            let _ = msg.react(ctx, "\u{2705}");
+                        }
+                    }
+                }
+                _ => {
+                    let _ = msg.author.direct_message(ctx, |message| {
+                        message.content(strings::DISCORD_ALREADY_LINKED_MESSAGE2(bot_id.0))
                     });
                     let _ = msg.react(ctx, "\u{2705}");
                 }
@@ -233,15 +1223,12 @@ impl crate::discord_bot::Handler {
         }
         let url =
             crate::meetup_oauth2::generate_meetup_linking_link(&redis_connection_mutex, user_id)?;
-        let dm = msg.author.direct_message(ctx, |message| {
-            message.content(strings::MEETUP_LINKING_MESSAGE(&url))
-        });
-        match dm {
+        match crate::discord_linking_wizard::start(ctx, &msg.author, &url) {
             Ok(_) => {
                 let _ = msg.react(ctx, "\u{2705}");
             }
             Err(why) => {
-                eprintln!("Error sending Meetup linking DM: {:?}", why);
+                eprintln!("Error starting the Meetup linking wizard: {:?}", why);
                 let _ = msg.reply(ctx, "There was an error trying to send you instructions.");
             }
         }
@@ -255,8 +1242,6 @@ impl crate::discord_bot::Handler {
         user_id: u64,
         meetup_id: u64,
     ) -> crate::Result<()> {
-        let redis_key_d2m = format!("discord_user:{}:meetup_user", user_id);
-        let redis_key_m2d = format!("meetup_user:{}:discord_user", meetup_id);
         let (redis_connection_mutex, meetup_client_mutex) = {
             let data = ctx.data.read();
             (
@@ -268,26 +1253,28 @@ impl crate::discord_bot::Handler {
                     .clone(),
             )
         };
-        // Check if there is already a meetup id linked to this user
-        // and issue a warning
-        let linked_meetup_id: Option<u64> = {
-            let mut redis_connection = redis_connection_mutex.lock();
-            redis_connection.get(&redis_key_d2m)?
-        };
-        if let Some(linked_meetup_id) = linked_meetup_id {
-            if linked_meetup_id == meetup_id {
-                let _ = msg.channel_id.say(
-                    &ctx.http,
+        // Check if either account is already linked and issue a warning
+        let link_conflict =
+            Self::meetup_link_conflict(user_id, meetup_id, &mut *redis_connection_mutex.lock())?;
+        match link_conflict {
+            Some(MeetupLinkConflict::DiscordUserLinked {
+                meetup_id: linked_meetup_id,
+            }) if linked_meetup_id == meetup_id => {
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
                     format!(
                         "All good, this Meetup account was already linked to <@{}>",
                         user_id
                     ),
                 );
                 return Ok(());
-            } else {
+            }
+            Some(MeetupLinkConflict::DiscordUserLinked { .. }) => {
                 // TODO: answer in DM?
-                let _ = msg.channel_id.say(
-                    &ctx.http,
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
                     format!(
                         "<@{discord_id}> is already linked to a different Meetup account. \
                          If you want to change this, unlink the currently \
@@ -299,25 +1286,22 @@ impl crate::discord_bot::Handler {
                 );
                 return Ok(());
             }
-        }
-        // Check if this meetup id is already linked
-        // and issue a warning
-        let linked_discord_id: Option<u64> = {
-            let mut redis_connection = redis_connection_mutex.lock();
-            redis_connection.get(&redis_key_m2d)?
-        };
-        if let Some(linked_discord_id) = linked_discord_id {
-            let _ = msg.author.direct_message(ctx, |message_builder| {
-                message_builder.content(format!(
-                    "This Meetup account is alread linked to <@{linked_discord_id}>. \
-                     If you want to change this, unlink the Meetup account first \
-                     by writing\n\
-                     {bot_mention} unlink meetup <@{linked_discord_id}>",
-                    linked_discord_id = linked_discord_id,
-                    bot_mention = regexes.bot_mention
-                ))
-            });
-            return Ok(());
+            Some(MeetupLinkConflict::MeetupUserLinked {
+                discord_id: linked_discord_id,
+            }) => {
+                let _ = msg.author.direct_message(ctx, |message_builder| {
+                    message_builder.content(format!(
+                        "This Meetup account is alread linked to <@{linked_discord_id}>. \
+                         If you want to change this, unlink the Meetup account first \
+                         by writing\n\
+                         {bot_mention} unlink meetup <@{linked_discord_id}>",
+                        linked_discord_id = linked_discord_id,
+                        bot_mention = regexes.bot_mention
+                    ))
+                });
+                return Ok(());
+            }
+            None => (),
         }
         // The user has not yet linked their meetup account.
         // Test whether the specified Meetup user actually exists.
@@ -328,40 +1312,34 @@ impl crate::discord_bot::Handler {
             .get_member_profile(Some(meetup_id))?;
         match meetup_user {
             None => {
-                let _ = msg.channel_id.say(
-                    &ctx.http,
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
                     "It looks like this Meetup profile does not exist",
                 );
                 return Ok(());
             }
             Some(meetup_user) => {
-                let mut successful = false;
-                {
-                    let mut redis_connection = redis_connection_mutex.lock();
-                    // Try to atomically set the meetup id
-                    redis::transaction(
-                        &mut *redis_connection,
-                        &[&redis_key_d2m, &redis_key_m2d],
-                        |con, pipe| {
-                            let linked_meetup_id: Option<u64> = con.get(&redis_key_d2m)?;
-                            let linked_discord_id: Option<u64> = con.get(&redis_key_m2d)?;
-                            if linked_meetup_id.is_some() || linked_discord_id.is_some() {
-                                // The meetup id was linked in the meantime, abort
-                                successful = false;
-                                // Execute empty transaction just to get out of the closure
-                                pipe.query(con)
-                            } else {
-                                pipe.sadd("meetup_users", meetup_id)
-                                    .sadd("discord_users", user_id)
-                                    .set(&redis_key_d2m, meetup_id)
-                                    .set(&redis_key_m2d, user_id);
-                                successful = true;
-                                pipe.query(con)
-                            }
-                        },
-                    )?;
-                }
+                let discord_api = crate::discord_bot::CacheAndHttp {
+                    cache: ctx.cache.clone(),
+                    http: ctx.http.clone(),
+                };
+                let successful = Self::store_meetup_link(
+                    user_id,
+                    meetup_id,
+                    &mut *redis_connection_mutex.lock(),
+                    &discord_api,
+                )?;
                 if successful {
+                    if let Err(err) = crate::discord_nicknames::sync_linked_user(
+                        user_id,
+                        meetup_id,
+                        &meetup_user.name,
+                        &mut *redis_connection_mutex.lock(),
+                        &discord_api,
+                    ) {
+                        eprintln!("Could not sync the nickname of user {}: {}", user_id, err);
+                    }
                     let photo_url = meetup_user.photo.as_ref().map(|p| p.thumb_link.as_str());
                     let _ = msg.channel_id.send_message(&ctx.http, |message| {
                         message.embed(|embed| {
@@ -379,42 +1357,170 @@ impl crate::discord_bot::Handler {
                     });
                     return Ok(());
                 } else {
-                    let _ = msg
-                        .channel_id
-                        .say(&ctx.http, "Could not assign meetup id (timing error)");
+                    let _ = crate::discord_tidy::say(
+                        ctx,
+                        msg,
+                        "Could not assign meetup id (timing error)",
+                    );
                     return Ok(());
                 }
             }
         }
     }
 
+    pub fn meetup_link_conflict(
+        user_id: u64,
+        meetup_id: u64,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> crate::Result<Option<MeetupLinkConflict>> {
+        let linked_meetup_id = crate::identity::meetup_id(user_id, redis_connection)?;
+        if let Some(linked_meetup_id) = linked_meetup_id {
+            return Ok(Some(MeetupLinkConflict::DiscordUserLinked {
+                meetup_id: linked_meetup_id,
+            }));
+        }
+        let linked_discord_id = crate::identity::meetup_discord_user(meetup_id, redis_connection)?;
+        Ok(
+            linked_discord_id.map(|linked_discord_id| MeetupLinkConflict::MeetupUserLinked {
+                discord_id: linked_discord_id,
+            }),
+        )
+    }
+
+    // Returns false if either account was linked in the meantime
+    pub fn store_meetup_link(
+        user_id: u64,
+        meetup_id: u64,
+        redis_connection: &mut crate::redis_namespace::Connection,
+        discord_api: &crate::discord_bot::CacheAndHttp,
+    ) -> crate::Result<bool> {
+        let identity = crate::identity::Identity::meetup(meetup_id);
+        let redis_identities_key = crate::identity::redis_identities_key(user_id);
+        let redis_meetup_discord_user_key = identity.redis_discord_user_key();
+        let mut successful = false;
+        // Try to atomically set the meetup id
+        redis::transaction(
+            redis_connection,
+            &[&redis_identities_key, &redis_meetup_discord_user_key],
+            |con, pipe| {
+                let linked_meetup_identity = crate::identity::linked_identity(
+                    user_id,
+                    crate::identity::Provider::Meetup,
+                    con,
+                )?;
+                let linked_discord_id = crate::identity::discord_user(&identity, con)?;
+                if linked_meetup_identity.is_some() || linked_discord_id.is_some() {
+                    // The meetup id was linked in the meantime, abort
+                    successful = false;
+                    // Execute empty transaction just to get out of the closure
+                    pipe.query(con)
+                } else {
+                    pipe.sadd("meetup_users", meetup_id)
+                        .sadd("discord_users", user_id);
+                    crate::identity::add_link(pipe, user_id, &identity);
+                    successful = true;
+                    pipe.query(con)
+                }
+            },
+        )?;
+        if successful {
+            crate::identity::invalidate_cached_links(user_id, &identity);
+            // The user gets their channel roles with the next sync
+            if let Err(err) =
+                crate::discord_sync::mark_member_series_dirty(redis_connection, meetup_id)
+            {
+                eprintln!(
+                    "Could not mark the series of user {} for syncing: {}",
+                    user_id, err
+                );
+            }
+            crate::event_webhooks::emit(crate::event_webhooks::Event::UserLinked {
+                discord_id: user_id,
+                meetup_id: meetup_id,
+            });
+            if let Err(err) = crate::discord_linking_wizard::grant_linked_role(user_id, discord_api)
+            {
+                eprintln!(
+                    "Could not give the linked role to user {}: {}",
+                    user_id, err
+                );
+            }
+        }
+        Ok(successful)
+    }
+
     pub fn unlink_meetup(
         ctx: &Context,
         msg: &Message,
         is_organizer_command: bool,
         user_id: u64,
     ) -> crate::Result<()> {
-        let redis_key_d2m = format!("discord_user:{}:meetup_user", user_id);
-        let redis_connection_mutex = {
+        let redis_client = {
             ctx.data
                 .read()
-                .get::<crate::discord_bot::RedisConnectionKey>()
-                .ok_or_else(|| SimpleError::new("Redis connection was not set"))?
+                .get::<crate::discord_bot::RedisClientKey>()
+                .ok_or_else(|| SimpleError::new("Redis client was not set"))?
                 .clone()
         };
-        let mut redis_connection = redis_connection_mutex.lock();
+        // A connection of our own rather than the shared one, such that
+        // nobody waits for us while we post the confirmation prompt
+        let mut redis_connection = redis_client.get_connection()?;
         // Check if there is actually a meetup id linked to this user
-        let linked_meetup_id: Option<u64> = redis_connection.get(&redis_key_d2m)?;
+        let linked_meetup_id = crate::identity::meetup_id(user_id, &mut redis_connection)?;
         match linked_meetup_id {
-            Some(meetup_id) => {
-                let redis_key_m2d = format!("meetup_user:{}:discord_user", meetup_id);
-                redis_connection.del(&[&redis_key_d2m, &redis_key_m2d])?;
+            Some(_) => {
+                let prompt = if is_organizer_command {
+                    Cow::Owned(strings::MEETUP_UNLINK_ORGANIZER_CONFIRMATION(user_id))
+                } else {
+                    Cow::Borrowed(strings::MEETUP_UNLINK_CONFIRMATION)
+                };
+                crate::discord_confirmations::request_confirmation(
+                    ctx,
+                    msg.channel_id,
+                    msg.author.id,
+                    crate::discord_confirmations::PendingAction::UnlinkMeetup {
+                        discord_id: user_id,
+                        is_organizer_command: is_organizer_command,
+                    },
+                    &prompt,
+                    &mut redis_connection,
+                )?;
+            }
+            None => {
+                let message = if is_organizer_command {
+                    Cow::Owned(format!(
+                        "There was seemingly no meetup account linked to <@{}>",
+                        user_id
+                    ))
+                } else {
+                    Cow::Borrowed(strings::MEETUP_UNLINK_NOT_LINKED)
+                };
+                let _ = crate::discord_tidy::say(ctx, msg, message);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn unlink_meetup_confirmed(
+        ctx: &Context,
+        channel_id: ChannelId,
+        is_organizer_command: bool,
+        user_id: u64,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> crate::Result<()> {
+        // The link might have changed while we were waiting for the confirmation
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        match Self::remove_meetup_link(user_id, redis_connection, Some(&discord_api))? {
+            Some(_) => {
                 let message = if is_organizer_command {
                     Cow::Owned(format!("Unlinked <@{}>'s Meetup account", user_id))
                 } else {
                     Cow::Borrowed(strings::MEETUP_UNLINK_SUCCESS)
                 };
-                let _ = msg.channel_id.say(&ctx.http, message);
+                let _ = channel_id.say(&ctx.http, message);
             }
             None => {
                 let message = if is_organizer_command {
@@ -425,15 +1531,44 @@ impl crate::discord_bot::Handler {
                 } else {
                     Cow::Borrowed(strings::MEETUP_UNLINK_NOT_LINKED)
                 };
-                let _ = msg.channel_id.say(&ctx.http, message);
+                let _ = channel_id.say(&ctx.http, message);
             }
         }
         Ok(())
     }
 
-    fn get_channel_roles(
+    // Returns the Meetup ID that was unlinked, if any. Without a Discord
+    // connection (in botctl), the linked role is left as it is.
+    pub fn remove_meetup_link(
+        user_id: u64,
+        redis_connection: &mut crate::redis_namespace::Connection,
+        discord_api: Option<&crate::discord_bot::CacheAndHttp>,
+    ) -> crate::Result<Option<u64>> {
+        let linked_meetup_id = crate::identity::meetup_id(user_id, redis_connection)?;
+        if let Some(meetup_id) = linked_meetup_id {
+            crate::identity::remove_link(
+                user_id,
+                &crate::identity::Identity::meetup(meetup_id),
+                redis_connection,
+            )?;
+            crate::households::dissolve(meetup_id, redis_connection)?;
+            if let Some(discord_api) = discord_api {
+                if let Err(err) =
+                    crate::discord_linking_wizard::revoke_linked_role(user_id, discord_api)
+                {
+                    eprintln!(
+                        "Could not remove the linked role from user {}: {}",
+                        user_id, err
+                    );
+                }
+            }
+        }
+        Ok(linked_meetup_id)
+    }
+
+    pub fn get_channel_roles(
         channel_id: u64,
-        redis_connection: &mut redis::Connection,
+        redis_connection: &mut crate::redis_namespace::Connection,
     ) -> Result<Option<ChannelRoles>, BoxedError> {
         // Check that this message came from a bot controlled channel
         let redis_channel_role_key = format!("discord_channel:{}:discord_role", channel_id);
@@ -458,219 +1593,5582 @@ impl crate::discord_bot::Handler {
         }
     }
 
-    pub fn close_channel(
-        ctx: &Context,
-        msg: &Message,
-        redis_client: redis::Client,
-    ) -> Result<(), BoxedError> {
-        let mut redis_connection = redis_client.get_connection()?;
-        // Check whether this is a bot controlled channel
-        let channel_roles = Self::get_channel_roles(msg.channel_id.0, &mut redis_connection)?;
-        let channel_roles = match channel_roles {
-            Some(roles) => roles,
-            None => {
-                let _ = msg
-                    .channel_id
-                    .say(&ctx.http, strings::CHANNEL_NOT_BOT_CONTROLLED);
-                return Ok(());
+    // Like get_channel_roles, but also recognizes the bot controlled channels
+    // that grant access with member overwrites
+    pub fn get_channel_access(
+        channel_id: u64,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<Option<ChannelAccess>, BoxedError> {
+        match Self::get_channel_roles(channel_id, redis_connection)? {
+            Some(channel_roles) => Ok(Some(ChannelAccess::Roles(channel_roles))),
+            None if crate::discord_channel_access::uses_overwrites(
+                redis_connection,
+                ChannelId(channel_id),
+            )? =>
+            {
+                Ok(Some(ChannelAccess::Overwrites))
             }
-        };
-        // This is only for organizers and channel hosts
-        let is_organizer = msg
-            .author
-            .has_role(
-                ctx,
-                crate::discord_sync::GUILD_ID,
-                crate::discord_sync::ORGANIZER_ID,
-            )
-            .unwrap_or(false);
-        let is_host = msg
-            .author
-            .has_role(ctx, crate::discord_sync::GUILD_ID, channel_roles.host)
-            .unwrap_or(false);
-        if !is_organizer && !is_host {
-            let _ = msg.channel_id.say(&ctx.http, strings::NOT_A_CHANNEL_ADMIN);
-            return Ok(());
+            None => Ok(None),
         }
-        // Check if there is a channel expiration time in the future
-        let redis_channel_expiration_key =
-            format!("discord_channel:{}:expiration_time", msg.channel_id.0);
-        let expiration_time: Option<String> =
-            redis_connection.get(&redis_channel_expiration_key)?;
-        let expiration_time = expiration_time
-            .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
-            .transpose()?
-            .map(|t| t.with_timezone(&chrono::Utc));
-        if let Some(expiration_time) = expiration_time {
-            if expiration_time > chrono::Utc::now() {
-                let _ = msg
-                    .channel_id
-                    .say(&ctx.http, strings::CHANNEL_NOT_YET_CLOSEABLE);
-                return Ok(());
+    }
+
+    // Gives the user the player or host permissions of the channel
+    fn grant_channel_access(
+        ctx: &Context,
+        channel_id: ChannelId,
+        discord_id: u64,
+        as_host: bool,
+        channel_access: &ChannelAccess,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<(), BoxedError> {
+        match channel_access {
+            ChannelAccess::Roles(channel_roles) => {
+                let role_id = if as_host {
+                    channel_roles.host
+                } else {
+                    channel_roles.user
+                };
+                ctx.http
+                    .add_member_role(crate::discord_sync::GUILD_ID.0, discord_id, role_id)?;
             }
-        }
-        // Schedule this channel for deletion
-        // TODO: in 24 hours
-        let new_deletion_time = chrono::Utc::now();
-        let redis_channel_deletion_key =
-            format!("discord_channel:{}:deletion_time", msg.channel_id.0);
-        let current_deletion_time: Option<String> =
-            redis_connection.get(&redis_channel_deletion_key)?;
-        let current_deletion_time = current_deletion_time
-            .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
-            .transpose()?
-            .map(|t| t.with_timezone(&chrono::Utc));
-        if let Some(current_deletion_time) = current_deletion_time {
-            if new_deletion_time > current_deletion_time {
-                let _ = msg
-                    .channel_id
-                    .say(&ctx.http, strings::CHANNEL_ALREADY_MARKED_FOR_CLOSING);
-                return Ok(());
+            ChannelAccess::Overwrites => {
+                let discord_api = crate::discord_bot::CacheAndHttp {
+                    cache: ctx.cache.clone(),
+                    http: ctx.http.clone(),
+                };
+                crate::discord_channel_access::grant(
+                    channel_id,
+                    discord_id,
+                    as_host,
+                    redis_connection,
+                    &discord_api,
+                )?;
             }
         }
-        let _: () =
-            redis_connection.set(&redis_channel_deletion_key, new_deletion_time.to_rfc3339())?;
-        let _ = msg
-            .channel_id
-            .say(&ctx.http, strings::CHANNEL_MARKED_FOR_CLOSING);
         Ok(())
     }
 
-    pub fn channel_add_or_remove_user(
+    fn revoke_channel_access(
         ctx: &Context,
-        msg: &Message,
+        channel_id: ChannelId,
         discord_id: u64,
-        add: bool,
         as_host: bool,
-        redis_client: redis::Client,
+        channel_access: &ChannelAccess,
+        redis_connection: &mut crate::redis_namespace::Connection,
     ) -> Result<(), BoxedError> {
-        let mut redis_connection = redis_client.get_connection()?;
-        // Check whether this is a bot controlled channel
-        let channel_roles = Self::get_channel_roles(msg.channel_id.0, &mut redis_connection)?;
-        let channel_roles = match channel_roles {
-            Some(roles) => roles,
-            None => {
-                let _ = msg
-                    .channel_id
-                    .say(&ctx.http, strings::CHANNEL_NOT_BOT_CONTROLLED);
-                return Ok(());
+        match channel_access {
+            ChannelAccess::Roles(channel_roles) => {
+                let role_id = if as_host {
+                    channel_roles.host
+                } else {
+                    channel_roles.user
+                };
+                ctx.http.remove_member_role(
+                    crate::discord_sync::GUILD_ID.0,
+                    discord_id,
+                    role_id,
+                )?;
             }
-        };
-        // This is only for organizers and channel hosts
-        let is_organizer = msg
-            .author
-            .has_role(
-                ctx,
-                crate::discord_sync::GUILD_ID,
-                crate::discord_sync::ORGANIZER_ID,
-            )
-            .unwrap_or(false);
-        let is_host = msg
-            .author
-            .has_role(ctx, crate::discord_sync::GUILD_ID, channel_roles.host)
-            .unwrap_or(false);
-        if !is_organizer && !is_host {
-            let _ = msg.channel_id.say(&ctx.http, strings::NOT_A_CHANNEL_ADMIN);
-            return Ok(());
+            ChannelAccess::Overwrites => {
+                let discord_api = crate::discord_bot::CacheAndHttp {
+                    cache: ctx.cache.clone(),
+                    http: ctx.http.clone(),
+                };
+                crate::discord_channel_access::revoke(
+                    channel_id,
+                    discord_id,
+                    as_host,
+                    redis_connection,
+                    &discord_api,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    // Whether the user has the player permissions of the channel
+    pub fn is_channel_player(
+        ctx: &Context,
+        user: &User,
+        channel_id: ChannelId,
+        channel_access: &ChannelAccess,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<bool, BoxedError> {
+        match channel_access {
+            ChannelAccess::Roles(channel_roles) => Ok(user.has_role(
+                ctx,
+                crate::discord_sync::GUILD_ID,
+                RoleId(channel_roles.user),
+            )?),
+            ChannelAccess::Overwrites => {
+                crate::discord_channel_access::is_player(redis_connection, channel_id, user.id.0)
+            }
+        }
+    }
+
+    // Pings the host role, or each host in channels without roles
+    pub fn hosts_mention(
+        channel_id: ChannelId,
+        channel_access: &ChannelAccess,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<String, BoxedError> {
+        match channel_access {
+            ChannelAccess::Roles(channel_roles) => Ok(format!("<@&{}>", channel_roles.host)),
+            ChannelAccess::Overwrites => {
+                let host_ids = crate::discord_channel_access::hosts(redis_connection, channel_id)?;
+                Ok(host_ids
+                    .iter()
+                    .map(|host_id| format!("<@{}>", host_id))
+                    .collect::<Vec<_>>()
+                    .join(" "))
+            }
+        }
+    }
+
+    // Organizers and the channel's hosts can manage a bot controlled channel.
+    // Used by both text commands and reaction shortcuts.
+    pub fn is_channel_admin(
+        ctx: &Context,
+        user: &User,
+        channel_id: ChannelId,
+        channel_access: &ChannelAccess,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<bool, BoxedError> {
+        let is_organizer = user
+            .has_role(
+                ctx,
+                crate::discord_sync::GUILD_ID,
+                crate::discord_sync::ORGANIZER_ID,
+            )
+            .unwrap_or(false);
+        if is_organizer {
+            return Ok(true);
+        }
+        let is_host = match channel_access {
+            ChannelAccess::Roles(channel_roles) => user
+                .has_role(ctx, crate::discord_sync::GUILD_ID, channel_roles.host)
+                .unwrap_or(false),
+            ChannelAccess::Overwrites => {
+                crate::discord_channel_access::is_host(redis_connection, channel_id, user.id.0)?
+            }
+        };
+        if !is_host {
+            return Ok(false);
+        }
+        // Make sure that the host role was not just handed out manually
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        crate::host_verification::check_host(channel_id, user.id, redis_connection, &discord_api)
+    }
+
+    pub fn close_channel(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        if !Self::is_channel_closeable(msg.channel_id, &mut redis_connection)? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_YET_CLOSEABLE);
+            return Ok(());
+        }
+        crate::discord_confirmations::request_confirmation(
+            ctx,
+            msg.channel_id,
+            msg.author.id,
+            crate::discord_confirmations::PendingAction::CloseChannel,
+            strings::CHANNEL_CLOSE_CONFIRMATION,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn close_channel_confirmed(
+        ctx: &Context,
+        channel_id: ChannelId,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<(), BoxedError> {
+        if Self::mark_channel_for_closing(channel_id, redis_connection)? {
+            let _ = channel_id.say(&ctx.http, strings::CHANNEL_MARKED_FOR_CLOSING);
+        } else {
+            let _ = channel_id.say(&ctx.http, strings::CHANNEL_ALREADY_MARKED_FOR_CLOSING);
+        }
+        Ok(())
+    }
+
+    // Channels can't be closed before their expiration time
+    pub fn is_channel_closeable(
+        channel_id: ChannelId,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<bool, BoxedError> {
+        let redis_channel_expiration_key =
+            format!("discord_channel:{}:expiration_time", channel_id.0);
+        let expiration_time: Option<String> =
+            redis_connection.get(&redis_channel_expiration_key)?;
+        let expiration_time = expiration_time
+            .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
+            .transpose()?
+            .map(|t| t.with_timezone(&chrono::Utc));
+        Ok(match expiration_time {
+            Some(expiration_time) => expiration_time <= chrono::Utc::now(),
+            None => true,
+        })
+    }
+
+    // Schedules the channel for deletion. Returns false if it already was.
+    pub fn mark_channel_for_closing(
+        channel_id: ChannelId,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<bool, BoxedError> {
+        // TODO: in 24 hours
+        let new_deletion_time = chrono::Utc::now();
+        let redis_channel_deletion_key = format!("discord_channel:{}:deletion_time", channel_id.0);
+        let current_deletion_time: Option<String> =
+            redis_connection.get(&redis_channel_deletion_key)?;
+        let current_deletion_time = current_deletion_time
+            .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
+            .transpose()?
+            .map(|t| t.with_timezone(&chrono::Utc));
+        if let Some(current_deletion_time) = current_deletion_time {
+            if new_deletion_time > current_deletion_time {
+                return Ok(false);
+            }
+        }
+        let _: () =
+            redis_connection.set(&redis_channel_deletion_key, new_deletion_time.to_rfc3339())?;
+        Ok(true)
+    }
+
+    // "@bot remove" in a reply to a message of the user to remove
+    pub fn remove_replied_user(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let replied_to = match crate::discord_replies::referenced_message(&ctx.http, msg)? {
+            Some(replied_to) => replied_to,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_REPLY);
+                return Ok(());
+            }
+        };
+        Self::channel_add_or_remove_user(
+            ctx,
+            msg,
+            replied_to.author_id.0,
+            /*add*/ false,
+            /*as_host*/ false,
+            redis_client,
+        )
+    }
+
+    pub fn channel_add_or_remove_user(
+        ctx: &Context,
+        msg: &Message,
+        discord_id: u64,
+        add: bool,
+        as_host: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
         }
         if add {
+            if crate::quarantine::is_quarantined(&mut redis_connection, discord_id)? {
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
+                    strings::CHANNEL_ADD_USER_QUARANTINED(discord_id),
+                );
+                return Ok(());
+            }
             // Try to add the user to the channel
-            match ctx.http.add_member_role(
-                crate::discord_sync::GUILD_ID.0,
+            if Self::add_user_to_channel(
+                ctx,
+                msg.channel_id,
                 discord_id,
-                channel_roles.user,
-            ) {
-                Ok(()) => {
-                    let _ = msg
-                        .channel_id
-                        .say(&ctx.http, format!("Welcome <@{}>!", discord_id));
-                }
-                Err(err) => {
-                    eprintln!("Could not assign channel role: {}", err);
-                    let _ = msg
-                        .channel_id
-                        .say(&ctx.http, strings::CHANNEL_ROLE_ADD_ERROR);
-                }
+                &channel_access,
+                &mut redis_connection,
+            )? {
+                let _ = crate::discord_tidy::say(ctx, msg, format!("Welcome <@{}>!", discord_id));
+                Self::send_safety_note(ctx, msg.channel_id, discord_id, &mut redis_connection);
+            } else {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_ROLE_ADD_ERROR);
             }
             if as_host {
-                match ctx.http.add_member_role(
-                    crate::discord_sync::GUILD_ID.0,
+                match Self::grant_channel_access(
+                    ctx,
+                    msg.channel_id,
                     discord_id,
-                    channel_roles.host,
+                    /*as_host*/ true,
+                    &channel_access,
+                    &mut redis_connection,
                 ) {
                     Ok(()) => {
-                        let _ = msg
-                            .channel_id
-                            .say(&ctx.http, strings::CHANNEL_ADDED_NEW_HOST(discord_id));
+                        // Remember this host for the host verification
+                        let redis_channel_manual_hosts_key =
+                            format!("discord_channel:{}:manual_hosts", msg.channel_id.0);
+                        let _: () =
+                            redis_connection.sadd(redis_channel_manual_hosts_key, discord_id)?;
+                        let _ = crate::discord_tidy::say(
+                            ctx,
+                            msg,
+                            strings::CHANNEL_ADDED_NEW_HOST(discord_id),
+                        );
                     }
                     Err(err) => {
                         eprintln!("Could not assign channel role: {}", err);
-                        let _ = msg
-                            .channel_id
-                            .say(&ctx.http, strings::CHANNEL_ROLE_ADD_ERROR);
+                        let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_ROLE_ADD_ERROR);
                     }
                 }
             }
             Ok(())
         } else {
-            // Try to remove the user from the channel
-            match ctx.http.remove_member_role(
-                crate::discord_sync::GUILD_ID.0,
+            if !Self::remove_user_from_channel(
+                ctx,
+                msg.channel_id,
                 discord_id,
-                channel_roles.host,
+                as_host,
+                &channel_access,
+                &mut redis_connection,
+            )? {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_ROLE_REMOVE_ERROR);
+            }
+            Ok(())
+        }
+    }
+
+    // Adds or removes several players at once and sums up the outcome in a
+    // single message, instead of one message per user. Each user is given as
+    // written in the command, together with the Discord ID it resolved to.
+    pub fn channel_add_or_remove_users(
+        ctx: &Context,
+        msg: &Message,
+        users: &[(String, Option<u64>)],
+        add: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        let mut succeeded = vec![];
+        let mut failed = vec![];
+        for (user, discord_id) in users {
+            let discord_id = match discord_id {
+                Some(id) => *id,
+                None => {
+                    failed.push((user.clone(), strings::CHANNEL_BATCH_UNKNOWN_USER));
+                    continue;
+                }
+            };
+            // Mentioning the same user twice doesn't do anything twice
+            if succeeded.contains(&discord_id) {
+                continue;
+            }
+            let done = if add {
+                if crate::quarantine::is_quarantined(&mut redis_connection, discord_id)? {
+                    failed.push((
+                        format!("<@{}>", discord_id),
+                        strings::CHANNEL_BATCH_QUARANTINED,
+                    ));
+                    continue;
+                }
+                Self::add_user_to_channel(
+                    ctx,
+                    msg.channel_id,
+                    discord_id,
+                    &channel_access,
+                    &mut redis_connection,
+                )?
+            } else {
+                Self::remove_user_from_channel(
+                    ctx,
+                    msg.channel_id,
+                    discord_id,
+                    /*as_host*/ false,
+                    &channel_access,
+                    &mut redis_connection,
+                )?
+            };
+            if done {
+                succeeded.push(discord_id);
+            } else {
+                failed.push((
+                    format!("<@{}>", discord_id),
+                    strings::CHANNEL_BATCH_ROLE_ERROR,
+                ));
+            }
+        }
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::CHANNEL_BATCH_RESULT(add, &succeeded, &failed),
+        );
+        if add {
+            for &discord_id in &succeeded {
+                Self::send_safety_note(ctx, msg.channel_id, discord_id, &mut redis_connection);
+            }
+        }
+        Ok(())
+    }
+
+    // Gives a user the player role (or overwrite) of a channel. Returns false
+    // if that didn't work.
+    fn add_user_to_channel(
+        ctx: &Context,
+        channel_id: ChannelId,
+        discord_id: u64,
+        channel_access: &ChannelAccess,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<bool, BoxedError> {
+        if let Err(err) = Self::grant_channel_access(
+            ctx,
+            channel_id,
+            discord_id,
+            /*as_host*/ false,
+            channel_access,
+            redis_connection,
+        ) {
+            eprintln!("Could not assign channel role: {}", err);
+            return Ok(false);
+        }
+        // Guests who are added as regular players stay
+        crate::discord_guests::forget_guest(channel_id, discord_id, redis_connection)?;
+        // Players who are added back can read the history again
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        if let Err(err) = crate::discord_history_privacy::unhide_history_for(
+            channel_id,
+            discord_id,
+            redis_connection,
+            &discord_api,
+        ) {
+            eprintln!(
+                "Could not unhide the history of channel {} for user {}: {}",
+                channel_id.0, discord_id, err
+            );
+        }
+        Ok(true)
+    }
+
+    fn send_safety_note(
+        ctx: &Context,
+        channel_id: ChannelId,
+        discord_id: u64,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) {
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        if let Err(err) = crate::discord_safety::send_safety_note(
+            channel_id,
+            discord_id,
+            redis_connection,
+            &discord_api,
+        ) {
+            eprintln!(
+                "Could not send the safety note to user {}: {}",
+                discord_id, err
+            );
+        }
+    }
+
+    pub fn add_guest(
+        ctx: &Context,
+        msg: &Message,
+        discord_id: u64,
+        period: chrono::Duration,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        if crate::quarantine::is_quarantined(&mut redis_connection, discord_id)? {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::CHANNEL_ADD_USER_QUARANTINED(discord_id),
+            );
+            return Ok(());
+        }
+        if let Err(err) = Self::grant_channel_access(
+            ctx,
+            msg.channel_id,
+            discord_id,
+            /*as_host*/ false,
+            &channel_access,
+            &mut redis_connection,
+        ) {
+            eprintln!("Could not assign channel role: {}", err);
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_ROLE_ADD_ERROR);
+            return Ok(());
+        }
+        let expiration_time = chrono::Utc::now() + period;
+        crate::discord_guests::add_guest(
+            msg.channel_id,
+            discord_id,
+            msg.author.id.0,
+            expiration_time,
+            &mut redis_connection,
+        )?;
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::CHANNEL_ADDED_GUEST(discord_id, expiration_time),
+        );
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        if let Err(err) = crate::discord_safety::send_safety_note(
+            msg.channel_id,
+            discord_id,
+            &mut redis_connection,
+            &discord_api,
+        ) {
+            eprintln!(
+                "Could not send the safety note to user {}: {}",
+                discord_id, err
+            );
+        }
+        Ok(())
+    }
+
+    // Returns false if a channel role (or overwrite) couldn't be removed
+    fn remove_user_from_channel(
+        ctx: &Context,
+        channel_id: ChannelId,
+        discord_id: u64,
+        as_host: bool,
+        channel_access: &ChannelAccess,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<bool, BoxedError> {
+        let mut removed = true;
+        // Co-hosts have a permission overwrite of their own that would keep
+        // them in the channel. Forgetting them first also lets the revocation
+        // below remove the overwrite in channels without roles.
+        if !as_host && forget_cohost(channel_id, discord_id, redis_connection)? {
+            match channel_id.delete_permission(
+                &ctx.http,
+                serenity::model::channel::PermissionOverwriteType::Member(UserId(discord_id)),
             ) {
+                Ok(()) => (),
+                Err(ref err) if crate::vacuum::is_not_found(err) => (),
                 Err(err) => {
-                    eprintln!("Could not remove host channel role: {}", err);
-                    let _ = msg
-                        .channel_id
-                        .say(&ctx.http, strings::CHANNEL_ROLE_REMOVE_ERROR);
+                    eprintln!("Could not remove co-host permissions: {}", err);
+                    removed = false;
                 }
-                _ => (),
             }
-            if !as_host {
-                match ctx.http.remove_member_role(
-                    crate::discord_sync::GUILD_ID.0,
-                    discord_id,
-                    channel_roles.user,
-                ) {
-                    Err(err) => {
-                        eprintln!("Could not remove channel role: {}", err);
-                        let _ = msg
-                            .channel_id
-                            .say(&ctx.http, strings::CHANNEL_ROLE_REMOVE_ERROR);
-                    }
-                    _ => (),
+        }
+        // Try to remove the user from the channel
+        match Self::revoke_channel_access(
+            ctx,
+            channel_id,
+            discord_id,
+            /*as_host*/ true,
+            channel_access,
+            redis_connection,
+        ) {
+            Err(err) => {
+                eprintln!("Could not remove host channel role: {}", err);
+                removed = false;
+            }
+            _ => (),
+        }
+        if !as_host {
+            match Self::revoke_channel_access(
+                ctx,
+                channel_id,
+                discord_id,
+                /*as_host*/ false,
+                channel_access,
+                redis_connection,
+            ) {
+                Err(err) => {
+                    eprintln!("Could not remove channel role: {}", err);
+                    removed = false;
                 }
+                _ => (),
             }
-            // Remember which users were removed manually
-            if as_host {
-                let redis_channel_removed_hosts_key =
-                    format!("discord_channel:{}:removed_hosts", msg.channel_id.0);
-                redis_connection.sadd(redis_channel_removed_hosts_key, discord_id)?;
-            } else {
-                let redis_channel_removed_users_key =
-                    format!("discord_channel:{}:removed_users", msg.channel_id.0);
-                redis_connection.sadd(redis_channel_removed_users_key, discord_id)?
+        }
+        let redis_channel_manual_hosts_key =
+            format!("discord_channel:{}:manual_hosts", channel_id.0);
+        let _: () = redis_connection.srem(redis_channel_manual_hosts_key, discord_id)?;
+        // Remember which users were removed manually
+        if as_host {
+            let redis_channel_removed_hosts_key =
+                format!("discord_channel:{}:removed_hosts", channel_id.0);
+            redis_connection.sadd(redis_channel_removed_hosts_key, discord_id)?;
+        } else {
+            let redis_channel_removed_users_key =
+                format!("discord_channel:{}:removed_users", channel_id.0);
+            let _: () = redis_connection.sadd(redis_channel_removed_users_key, discord_id)?;
+            crate::discord_guests::forget_guest(channel_id, discord_id, redis_connection)?;
+            // Depending on the channel's settings, removed players also can't
+            // read the history for a while
+            let discord_api = crate::discord_bot::CacheAndHttp {
+                cache: ctx.cache.clone(),
+                http: ctx.http.clone(),
+            };
+            if let Err(err) = crate::discord_history_privacy::hide_history_from(
+                channel_id,
+                discord_id,
+                redis_connection,
+                &discord_api,
+            ) {
+                eprintln!(
+                    "Could not hide the history of channel {} from user {}: {}",
+                    channel_id.0, discord_id, err
+                );
             }
-            Ok(())
         }
+        Ok(removed)
     }
 
-    pub fn send_welcome_message(ctx: &Context, user: &User) {
-        let _ = user.direct_message(ctx, |message_builder| {
-            message_builder
-                .content(crate::strings::WELCOME_MESSAGE_PART1)
-                .embed(|embed_builder| {
-                    embed_builder
-                        .colour(serenity::utils::Colour::new(0xFF1744))
-                        .title(crate::strings::WELCOME_MESSAGE_PART2_EMBED_TITLE)
-                        .description(crate::strings::WELCOME_MESSAGE_PART2_EMBED_CONTENT)
-                })
-        });
+    // Called once a host confirmed removing a user via the reaction shortcut
+    pub fn remove_user_confirmed(
+        ctx: &Context,
+        channel_id: ChannelId,
+        discord_id: u64,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<(), BoxedError> {
+        // The channel might have been closed in the meantime
+        let channel_access = match Self::get_channel_access(channel_id.0, redis_connection)? {
+            Some(access) => access,
+            None => {
+                let _ = channel_id.say(&ctx.http, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        if Self::remove_user_from_channel(
+            ctx,
+            channel_id,
+            discord_id,
+            /*as_host*/ false,
+            &channel_access,
+            redis_connection,
+        )? {
+            let _ = channel_id.say(&ctx.http, strings::CHANNEL_REMOVED_USER(discord_id));
+        } else {
+            let _ = channel_id.say(&ctx.http, strings::CHANNEL_ROLE_REMOVE_ERROR);
+        }
+        Ok(())
     }
-}
 
-struct ChannelRoles {
-    user: u64,
-    host: u64,
+    // Co-hosts can manage messages in the channel but are not channel admins
+    pub fn channel_add_or_remove_cohost(
+        ctx: &Context,
+        msg: &Message,
+        discord_id: u64,
+        add: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        let redis_channel_cohosts_key = format!("discord_channel:{}:cohosts", msg.channel_id.0);
+        if add {
+            // Co-hosts are also players of the channel
+            if let Err(err) = Self::grant_channel_access(
+                ctx,
+                msg.channel_id,
+                discord_id,
+                /*as_host*/ false,
+                &channel_access,
+                &mut redis_connection,
+            ) {
+                eprintln!("Could not assign channel role: {}", err);
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_ROLE_ADD_ERROR);
+                return Ok(());
+            }
+            msg.channel_id.create_permission(
+                &ctx.http,
+                &crate::discord_sync::cohost_permission_overwrite(UserId(discord_id)),
+            )?;
+            let _: () = redis_connection.sadd(&redis_channel_cohosts_key, discord_id)?;
+            let _ =
+                crate::discord_tidy::say(ctx, msg, strings::CHANNEL_ADDED_NEW_COHOST(discord_id));
+        } else {
+            // The user stays in the channel as a player
+            forget_cohost(msg.channel_id, discord_id, &mut redis_connection)?;
+            msg.channel_id.delete_permission(
+                &ctx.http,
+                serenity::model::channel::PermissionOverwriteType::Member(UserId(discord_id)),
+            )?;
+            // Without roles, the player needs their own overwrite back
+            if let ChannelAccess::Overwrites = channel_access {
+                let discord_api = crate::discord_bot::CacheAndHttp {
+                    cache: ctx.cache.clone(),
+                    http: ctx.http.clone(),
+                };
+                crate::discord_channel_access::sync_member(
+                    msg.channel_id,
+                    discord_id,
+                    &mut redis_connection,
+                    &discord_api,
+                )?;
+            }
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_REMOVED_COHOST(discord_id));
+        }
+        Ok(())
+    }
+
+    // Randomly splits the players of a one-shot into several table channels
+    pub fn split_into_tables(
+        ctx: &Context,
+        msg: &Message,
+        num_tables: usize,
+        bot_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        if num_tables < 2 || num_tables > crate::discord_tables::MAX_TABLES {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::TABLES_INVALID_COUNT(crate::discord_tables::MAX_TABLES),
+            );
+            return Ok(());
+        }
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let result = crate::discord_tables::create_tables(
+            msg.channel_id,
+            &channel_access,
+            num_tables,
+            bot_id,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        let reply = match result {
+            crate::discord_tables::TablesResult::Created(tables) => {
+                let tables: Vec<_> = tables
+                    .into_iter()
+                    .map(|(channel_id, player_ids)| {
+                        (
+                            channel_id.0,
+                            player_ids.into_iter().map(|id| id.0).collect(),
+                        )
+                    })
+                    .collect();
+                strings::TABLES_CREATED(&tables)
+            }
+            crate::discord_tables::TablesResult::AlreadyExist => {
+                strings::TABLES_ALREADY_EXIST.to_string()
+            }
+            crate::discord_tables::TablesResult::NotEnoughPlayers(num_players) => {
+                strings::TABLES_NOT_ENOUGH_PLAYERS(num_players)
+            }
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    pub fn export_members(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let csv = crate::discord_member_export::export_members_csv(
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        // The list contains personal data, so it is only sent via DM
+        let filename = format!(
+            "members-{}-{}.csv",
+            msg.channel_id.0,
+            chrono::Utc::now().format("%Y-%m-%d")
+        );
+        let dm_result = msg.author.create_dm_channel(ctx).and_then(|dm_channel| {
+            dm_channel.id.send_files(
+                &ctx.http,
+                vec![(csv.as_bytes(), filename.as_str())],
+                |message_builder| {
+                    message_builder.content(strings::MEMBER_EXPORT_DM(msg.channel_id.0))
+                },
+            )
+        });
+        match dm_result {
+            Ok(_) => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::MEMBER_EXPORT_SENT);
+            }
+            Err(err) => {
+                eprintln!("Could not send the member export: {}", err);
+                let _ = crate::discord_tidy::say(ctx, msg, strings::MEMBER_EXPORT_DM_FAILED);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn toggle_session_channels(
+        ctx: &Context,
+        msg: &Message,
+        enabled: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        // Session channels inherit the channel's roles
+        if let ChannelAccess::Overwrites = channel_access {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NEEDS_ROLES);
+            return Ok(());
+        }
+        let redis_channel_series_key = format!("discord_channel:{}:event_series", msg.channel_id.0);
+        let series_id: Option<String> = redis_connection.get(&redis_channel_series_key)?;
+        let series_id = match series_id {
+            Some(series_id) => series_id,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        crate::discord_session_channels::set_enabled(&mut redis_connection, &series_id, enabled)?;
+        crate::discord_sync::mark_series_dirty(&mut redis_connection, &series_id)?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::SESSION_CHANNELS_TOGGLED(enabled));
+        Ok(())
+    }
+
+    pub fn toggle_sync_pause(
+        ctx: &Context,
+        msg: &Message,
+        paused: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        let redis_channel_series_key = format!("discord_channel:{}:event_series", msg.channel_id.0);
+        let series_id: Option<String> = redis_connection.get(&redis_channel_series_key)?;
+        let series_id = match series_id {
+            Some(series_id) => series_id,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        crate::discord_sync::set_sync_paused(&mut redis_connection, &series_id, paused)?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::SERIES_SYNC_PAUSE_TOGGLED(paused));
+        Ok(())
+    }
+
+    // "none" (or "default") resets the colour or removes the emoji
+    pub fn style_channel(
+        ctx: &Context,
+        msg: &Message,
+        colour: Option<&str>,
+        emoji: Option<&str>,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        if colour.is_none() && emoji.is_none() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::STYLE_USAGE);
+            return Ok(());
+        }
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        let is_reset = |value: &str| {
+            value.eq_ignore_ascii_case("none") || value.eq_ignore_ascii_case("default")
+        };
+        // The colour is the colour of the player role
+        if let (ChannelAccess::Overwrites, Some(_)) = (&channel_access, colour) {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NEEDS_ROLES);
+            return Ok(());
+        }
+        let colour = match colour {
+            Some(colour) if is_reset(colour) => Some(None),
+            Some(colour) => match crate::discord_channel_style::parse_colour(colour) {
+                Some(colour) => Some(Some(colour)),
+                None => {
+                    let _ = crate::discord_tidy::say(ctx, msg, strings::STYLE_INVALID_COLOUR);
+                    return Ok(());
+                }
+            },
+            None => None,
+        };
+        let emoji = match emoji {
+            Some(emoji) if is_reset(emoji) => Some(None),
+            Some(emoji) => match crate::discord_channel_style::parse_emoji(emoji) {
+                Some(emoji) => Some(Some(emoji)),
+                None => {
+                    let _ = crate::discord_tidy::say(ctx, msg, strings::STYLE_INVALID_EMOJI);
+                    return Ok(());
+                }
+            },
+            None => None,
+        };
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        if let (Some(colour), ChannelAccess::Roles(channel_roles)) = (colour, &channel_access) {
+            crate::discord_channel_style::set_colour(
+                &mut redis_connection,
+                msg.channel_id,
+                colour,
+            )?;
+            crate::discord_channel_style::apply_role_colour(
+                RoleId(channel_roles.user),
+                colour,
+                &discord_api,
+            )?;
+        }
+        if let Some(emoji) = emoji {
+            crate::discord_channel_style::set_emoji(
+                &mut redis_connection,
+                msg.channel_id,
+                emoji.as_ref().map(String::as_str),
+            )?;
+            let is_renamed = crate::discord_channel_style::sync_channel_name(
+                msg.channel_id,
+                &mut redis_connection,
+                &discord_api,
+            )?;
+            if !is_renamed {
+                // The next Discord sync renames the channel
+                let redis_channel_series_key =
+                    format!("discord_channel:{}:event_series", msg.channel_id.0);
+                let series_id: Option<String> = redis_connection.get(&redis_channel_series_key)?;
+                if let Some(series_id) = series_id {
+                    crate::discord_sync::mark_series_dirty(&mut redis_connection, &series_id)?;
+                }
+                let _ = crate::discord_tidy::say(ctx, msg, strings::STYLE_RENAME_DEFERRED);
+                return Ok(());
+            }
+        }
+        let _ = crate::discord_tidy::say(ctx, msg, strings::STYLE_UPDATED);
+        Ok(())
+    }
+
+    // "@bot recap" in a reply to the message with the recap
+    pub fn store_replied_recap(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let recap = match crate::discord_replies::referenced_message(&ctx.http, msg)? {
+            Some(recap) if !recap.content.trim().is_empty() => recap,
+            _ => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_REPLY);
+                return Ok(());
+            }
+        };
+        Self::store_recap(
+            ctx,
+            msg,
+            recap.id,
+            recap.author_id.0,
+            &recap.content,
+            redis_client,
+        )
+    }
+
+    // The recap links to the message it was written in
+    pub fn store_recap(
+        ctx: &Context,
+        msg: &Message,
+        recap_message_id: MessageId,
+        recap_author_id: u64,
+        text: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        if text.chars().count() > crate::discord_recaps::MAX_RECAP_CHARS {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::RECAP_TOO_LONG(crate::discord_recaps::MAX_RECAP_CHARS),
+            );
+            return Ok(());
+        }
+        match crate::discord_recaps::store(
+            msg.channel_id,
+            recap_message_id,
+            recap_author_id,
+            text,
+            &mut redis_connection,
+        )? {
+            Some(date) => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::RECAP_STORED(date));
+            }
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn list_recaps(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        if Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?.is_none() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+            return Ok(());
+        }
+        let recaps = crate::discord_recaps::list(&mut redis_connection, msg.channel_id)?;
+        if recaps.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_RECAPS);
+            return Ok(());
+        }
+        let lines: Vec<_> = recaps
+            .iter()
+            .map(|(date, recap)| strings::RECAP_LINE(date, recap))
+            .collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::RECAPS_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn set_history_hiding(
+        ctx: &Context,
+        msg: &Message,
+        days: Option<u64>,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        if let Some(days) = days {
+            if days < 1 || days > crate::discord_history_privacy::MAX_HIDE_DAYS {
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
+                    strings::HIDE_HISTORY_INVALID_DAYS(
+                        crate::discord_history_privacy::MAX_HIDE_DAYS,
+                    ),
+                );
+                return Ok(());
+            }
+        }
+        crate::discord_history_privacy::set_hide_days(&mut redis_connection, msg.channel_id, days)?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::HIDE_HISTORY_SET(days));
+        Ok(())
+    }
+
+    // Shows the channel's settings or changes one of them
+    pub fn channel_settings(
+        ctx: &Context,
+        msg: &Message,
+        change: Option<(&str, &str)>,
+        bot_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        let mut settings =
+            crate::discord_channel_settings::settings(&mut redis_connection, msg.channel_id)?;
+        let (setting_name, value) = match change {
+            Some(change) => change,
+            None => {
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
+                    strings::CHANNEL_SETTINGS(&settings, bot_id),
+                );
+                return Ok(());
+            }
+        };
+        let setting = match crate::discord_channel_settings::Setting::from_name(setting_name) {
+            Some(setting) => setting,
+            None => {
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
+                    strings::CHANNEL_SETTING_UNKNOWN(setting_name),
+                );
+                return Ok(());
+            }
+        };
+        if !settings.apply(setting, value) {
+            let _ =
+                crate::discord_tidy::say(ctx, msg, strings::CHANNEL_SETTING_INVALID_VALUE(setting));
+            return Ok(());
+        }
+        crate::discord_channel_settings::save(&mut redis_connection, msg.channel_id, &settings)?;
+        // The topic and the session message are updated by the Discord sync
+        let redis_channel_series_key = format!("discord_channel:{}:event_series", msg.channel_id.0);
+        let series_id: Option<String> = redis_connection.get(&redis_channel_series_key)?;
+        if let Some(series_id) = series_id {
+            crate::discord_sync::mark_series_dirty(&mut redis_connection, &series_id)?;
+        }
+        let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_SETTING_CHANGED(setting));
+        Ok(())
+    }
+
+    // Called by users who got stuck in the linking wizard
+    pub fn linking_help(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        if crate::discord_linking_wizard::request_help(
+            msg.author.id.0,
+            &redis_client,
+            &discord_api,
+        )? {
+            println!("User {} asked for help with linking", msg.author.id.0);
+            let _ = crate::discord_tidy::say(ctx, msg, strings::LINKING_HELP_REQUESTED);
+        } else {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::LINKING_HELP_ALREADY_REQUESTED);
+        }
+        Ok(())
+    }
+
+    pub fn report_to_organizers(
+        ctx: &Context,
+        msg: &Message,
+        text: &str,
+        anonymous: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        if text.chars().count() > crate::discord_reports::MAX_REPORT_CHARS {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::REPORT_TOO_LONG(crate::discord_reports::MAX_REPORT_CHARS),
+            );
+            return Ok(());
+        }
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        crate::discord_reports::send_report(
+            msg,
+            text,
+            anonymous,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::REPORT_SENT(anonymous));
+        Ok(())
+    }
+
+    pub fn register_email(
+        ctx: &Context,
+        msg: &Message,
+        email: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        if !crate::email_registration::is_valid_email(email) {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::EMAIL_INVALID);
+            return Ok(());
+        }
+        let email = crate::email_registration::normalize(email);
+        let mut redis_connection = redis_client.get_connection()?;
+        let registration =
+            crate::email_registration::request(msg.author.id.0, &email, &mut redis_connection)?;
+        let reply = match registration {
+            crate::email_registration::Registration::Pending => {
+                let discord_api = crate::discord_bot::CacheAndHttp {
+                    cache: ctx.cache.clone(),
+                    http: ctx.http.clone(),
+                };
+                crate::discord_notifications::notify_organizers(
+                    &discord_api,
+                    &strings::EMAIL_APPROVAL_REQUESTED(msg.author.id.0, &email),
+                    &mut redis_connection,
+                )?;
+                strings::EMAIL_REGISTRATION_PENDING(&email)
+            }
+            crate::email_registration::Registration::Registered => {
+                strings::EMAIL_REGISTERED(&email)
+            }
+            crate::email_registration::Registration::Unchanged => {
+                strings::EMAIL_ALREADY_REGISTERED.to_string()
+            }
+            crate::email_registration::Registration::TakenByOtherUser => {
+                strings::EMAIL_TAKEN.to_string()
+            }
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    pub fn unregister_email(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        match crate::email_registration::unregister(msg.author.id.0, &mut redis_connection)? {
+            Some(email) => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::EMAIL_UNREGISTERED(&email));
+            }
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::EMAIL_NOT_REGISTERED);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn delete_my_data(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::discord_confirmations::request_confirmation(
+            ctx,
+            msg.channel_id,
+            msg.author.id,
+            crate::discord_confirmations::PendingAction::DeleteUserData {
+                discord_id: msg.author.id.0,
+            },
+            strings::DELETE_MY_DATA_CONFIRMATION,
+            &mut redis_connection,
+        )
+    }
+
+    pub fn delete_my_data_confirmed(
+        ctx: &Context,
+        channel_id: ChannelId,
+        discord_id: u64,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<(), BoxedError> {
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        crate::discord_data_deletion::delete_user_data(discord_id, redis_connection, &discord_api)?;
+        let _ = channel_id.say(&ctx.http, strings::USER_DATA_DELETED);
+        Ok(())
+    }
+
+    // Asks to register the address of an Eventbrite order
+    pub fn claim_ticket(
+        ctx: &Context,
+        msg: &Message,
+        order_id: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let api_token = match crate::secrets::load("EVENTBRITE_API_TOKEN")? {
+            Some(api_token) => api_token,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::TICKET_CLAIMING_UNAVAILABLE);
+                return Ok(());
+            }
+        };
+        let email = match crate::eventbrite::order_email(&api_token, order_id)? {
+            Some(ref email) if crate::email_registration::is_valid_email(email) => email.clone(),
+            _ => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::TICKET_NOT_FOUND);
+                return Ok(());
+            }
+        };
+        // Knowing the order number doesn't prove that the order is the
+        // user's, so this needs an organizer's approval like "register email"
+        let mut redis_connection = redis_client.get_connection()?;
+        let registration =
+            crate::email_registration::request(msg.author.id.0, &email, &mut redis_connection)?;
+        let reply = match registration {
+            crate::email_registration::Registration::Pending => {
+                let discord_api = crate::discord_bot::CacheAndHttp {
+                    cache: ctx.cache.clone(),
+                    http: ctx.http.clone(),
+                };
+                crate::discord_notifications::notify_organizers(
+                    &discord_api,
+                    &strings::TICKET_APPROVAL_REQUESTED(
+                        msg.author.id.0,
+                        order_id,
+                        &crate::email_registration::normalize(&email),
+                    ),
+                    &mut redis_connection,
+                )?;
+                strings::TICKET_CLAIM_PENDING
+            }
+            crate::email_registration::Registration::Registered => strings::TICKET_CLAIMED,
+            crate::email_registration::Registration::Unchanged => strings::EMAIL_ALREADY_REGISTERED,
+            crate::email_registration::Registration::TakenByOtherUser => strings::EMAIL_TAKEN,
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    pub fn list_identities(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let identities = crate::identity::identities(msg.author.id.0, &mut redis_connection)?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::IDENTITIES(&identities));
+        Ok(())
+    }
+
+    pub fn set_primary_identity(
+        ctx: &Context,
+        msg: &Message,
+        identity: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let identity = crate::identity::Identity::parse(identity);
+        let mut redis_connection = redis_client.get_connection()?;
+        let is_linked = match identity {
+            Some(ref identity) => {
+                crate::identity::set_primary(msg.author.id.0, identity, &mut redis_connection)?
+            }
+            None => false,
+        };
+        let reply = match identity {
+            Some(ref identity) if is_linked => strings::PRIMARY_IDENTITY_SET(identity),
+            _ => strings::PRIMARY_IDENTITY_NOT_LINKED.to_string(),
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    pub fn nickname_sync(
+        ctx: &Context,
+        msg: &Message,
+        enabled: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        crate::discord_nicknames::set_opted_out(
+            msg.author.id.0,
+            !enabled,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        let reply = if enabled {
+            strings::NICKNAME_SYNC_ENABLED
+        } else {
+            strings::NICKNAME_SYNC_DISABLED
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    // Registers the address that the user asked for
+    pub fn approve_email(
+        ctx: &Context,
+        msg: &Message,
+        discord_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let (email, registration) =
+            match crate::email_registration::approve(discord_id, &mut redis_connection)? {
+                Some(approval) => approval,
+                None => {
+                    let _ = crate::discord_tidy::say(ctx, msg, strings::EMAIL_NO_PENDING_REQUEST);
+                    return Ok(());
+                }
+            };
+        if registration == crate::email_registration::Registration::TakenByOtherUser {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::EMAIL_TAKEN_WHILE_PENDING);
+            return Ok(());
+        }
+        let _ = crate::discord_tidy::say(ctx, msg, strings::EMAIL_APPROVED(discord_id, &email));
+        let user = UserId(discord_id).to_user(ctx)?;
+        user.direct_message(ctx, |message_builder| {
+            message_builder.content(strings::EMAIL_REGISTERED(&email))
+        })?;
+        Ok(())
+    }
+
+    // Frees an address that somebody else registered
+    pub fn unregister_email_address(
+        ctx: &Context,
+        msg: &Message,
+        email: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        match crate::email_registration::unregister_address(email, &mut redis_connection)? {
+            Some(discord_id) => {
+                let _ =
+                    crate::discord_tidy::say(ctx, msg, strings::EMAIL_ADDRESS_FREED(discord_id));
+            }
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::EMAIL_ADDRESS_NOT_REGISTERED);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn mark_absent(
+        ctx: &Context,
+        msg: &Message,
+        reason: Option<&str>,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for the players of this channel
+        if !Self::is_channel_player(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::ABSENT_NOT_A_PLAYER);
+            return Ok(());
+        }
+        match crate::discord_absences::mark_absent(
+            msg.channel_id,
+            msg.author.id.0,
+            reason,
+            &mut redis_connection,
+        )? {
+            Some(session_time) => {
+                let hosts_mention =
+                    Self::hosts_mention(msg.channel_id, &channel_access, &mut redis_connection)?;
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
+                    strings::ABSENCE_RECORDED(
+                        msg.author.id.0,
+                        &hosts_mention,
+                        session_time,
+                        reason,
+                    ),
+                );
+            }
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::ABSENT_NO_UPCOMING_SESSION);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_safety_field(
+        ctx: &Context,
+        msg: &Message,
+        field: crate::discord_safety::Field,
+        value: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        if value.chars().count() > crate::discord_safety::MAX_FIELD_CHARS {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::SAFETY_FIELD_TOO_LONG(crate::discord_safety::MAX_FIELD_CHARS),
+            );
+            return Ok(());
+        }
+        // "none" removes the field
+        let value = if value.eq_ignore_ascii_case("none") {
+            None
+        } else {
+            Some(value)
+        };
+        crate::discord_safety::set_field(&mut redis_connection, msg.channel_id, field, value)?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        crate::discord_safety::sync_safety_message(
+            msg.channel_id,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        let _ =
+            crate::discord_tidy::say(ctx, msg, strings::SAFETY_FIELD_SET(field, value.is_some()));
+        Ok(())
+    }
+
+    pub fn show_safety(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let safety_info =
+            crate::discord_safety::safety_info(&mut redis_connection, msg.channel_id)?;
+        if safety_info.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_SAFETY_INFO);
+        } else {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::SAFETY_MESSAGE(&safety_info));
+        }
+        Ok(())
+    }
+
+    pub fn set_character(
+        ctx: &Context,
+        msg: &Message,
+        sheet_url: &str,
+        name: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for the players of this channel
+        if !Self::is_channel_player(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CHARACTER_NOT_A_PLAYER);
+            return Ok(());
+        }
+        if !crate::discord_characters::is_valid_sheet_url(sheet_url) {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CHARACTER_INVALID_SHEET_URL);
+            return Ok(());
+        }
+        if name.chars().count() > crate::discord_characters::MAX_CHARACTER_NAME_CHARS {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::CHARACTER_NAME_TOO_LONG(
+                    crate::discord_characters::MAX_CHARACTER_NAME_CHARS,
+                ),
+            );
+            return Ok(());
+        }
+        let character = crate::discord_characters::Character {
+            name: name.to_string(),
+            sheet_url: sheet_url.to_string(),
+        };
+        crate::discord_characters::set_character(
+            &mut redis_connection,
+            msg.channel_id,
+            msg.author.id.0,
+            &character,
+        )?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::CHARACTER_SET(&character.name));
+        Ok(())
+    }
+
+    pub fn remove_character(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        if crate::discord_characters::remove_character(
+            &mut redis_connection,
+            msg.channel_id,
+            msg.author.id.0,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CHARACTER_REMOVED);
+        } else {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_CHARACTER);
+        }
+        Ok(())
+    }
+
+    pub fn show_party(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // The party is made up of the role members
+        let channel_roles = match channel_access {
+            ChannelAccess::Roles(channel_roles) => channel_roles,
+            ChannelAccess::Overwrites => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NEEDS_ROLES);
+                return Ok(());
+            }
+        };
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let party = crate::discord_characters::party(
+            msg.channel_id,
+            RoleId(channel_roles.user),
+            RoleId(channel_roles.host),
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        let lines: Vec<_> = party
+            .iter()
+            .map(|(discord_id, is_host, character)| {
+                strings::PARTY_LINE(*discord_id, *is_host, character.as_ref())
+            })
+            .collect();
+        if lines.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::PARTY_EMPTY);
+            return Ok(());
+        }
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::PARTY_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn add_macro(
+        ctx: &Context,
+        msg: &Message,
+        name: &str,
+        text: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        if !crate::discord_macros::is_valid_name(name) {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::MACRO_INVALID_NAME(crate::discord_macros::MAX_MACRO_NAME_CHARS),
+            );
+            return Ok(());
+        }
+        if !crate::discord_macros::is_valid_text(text) {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::MACRO_INVALID_TEXT(crate::discord_macros::MAX_MACRO_TEXT_CHARS),
+            );
+            return Ok(());
+        }
+        if crate::discord_macros::add_macro(&mut redis_connection, msg.channel_id, name, text)? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::MACRO_ADDED(&name.to_lowercase()));
+        } else {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::MACRO_LIMIT_REACHED(crate::discord_macros::MAX_MACROS_PER_CHANNEL),
+            );
+        }
+        Ok(())
+    }
+
+    pub fn remove_macro(
+        ctx: &Context,
+        msg: &Message,
+        name: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        // Check whether this is a bot controlled channel
+        let channel_access = Self::get_channel_access(msg.channel_id.0, &mut redis_connection)?;
+        let channel_access = match channel_access {
+            Some(access) => access,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CHANNEL_NOT_BOT_CONTROLLED);
+                return Ok(());
+            }
+        };
+        // This is only for organizers and channel hosts
+        if !Self::is_channel_admin(
+            ctx,
+            &msg.author,
+            msg.channel_id,
+            &channel_access,
+            &mut redis_connection,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NOT_A_CHANNEL_ADMIN);
+            return Ok(());
+        }
+        if crate::discord_macros::remove_macro(&mut redis_connection, msg.channel_id, name)? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::MACRO_REMOVED);
+        } else {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::MACRO_NOT_FOUND(name));
+        }
+        Ok(())
+    }
+
+    pub fn list_macros(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let names = crate::discord_macros::macro_names(&mut redis_connection, msg.channel_id)?;
+        if names.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::MACROS_NONE);
+        } else {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::MACROS_LIST(&names));
+        }
+        Ok(())
+    }
+
+    // Called for messages that didn't match any command. Returns false if
+    // there is no such macro in this channel.
+    pub fn run_macro(
+        ctx: &Context,
+        msg: &Message,
+        name: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<bool, BoxedError> {
+        if !crate::discord_macros::is_valid_name(name) {
+            return Ok(false);
+        }
+        let mut redis_connection = redis_client.get_connection()?;
+        match crate::discord_macros::macro_text(&mut redis_connection, msg.channel_id, name)? {
+            Some(text) => {
+                let _ = crate::discord_tidy::say(ctx, msg, text);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn roll_dice(
+        ctx: &Context,
+        msg: &Message,
+        expression: &str,
+        mode: crate::dice_roller::Mode,
+        private: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let roll = match crate::dice_roller::roll(expression, mode) {
+            Some(roll) => roll,
+            None => {
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
+                    strings::INVALID_DICE_EXPRESSION(
+                        crate::dice_roller::MAX_DICE,
+                        crate::dice_roller::MAX_SIDES,
+                    ),
+                );
+                return Ok(());
+            }
+        };
+        if private {
+            let dm_result = msg.author.direct_message(ctx, |message_builder| {
+                message_builder.content(strings::GM_ROLL_RESULT(msg.channel_id.0, &roll))
+            });
+            match dm_result {
+                Ok(_) => {
+                    let _ = crate::discord_tidy::say(
+                        ctx,
+                        msg,
+                        strings::GM_ROLL_ANNOUNCEMENT(msg.author.id.0),
+                    );
+                }
+                Err(err) => {
+                    eprintln!("Could not send the GM roll: {}", err);
+                    let _ = crate::discord_tidy::say(ctx, msg, strings::GM_ROLL_DM_FAILED);
+                }
+            }
+            return Ok(());
+        }
+        let _ = crate::discord_tidy::say(ctx, msg, strings::ROLL_RESULT(msg.author.id.0, &roll));
+        // Rolls in DMs are nobody else's business
+        if msg.is_private() {
+            return Ok(());
+        }
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::dice_roller::log_roll(
+            &mut redis_connection,
+            msg.channel_id,
+            msg.author.id.0,
+            &roll,
+        )?;
+        Ok(())
+    }
+
+    pub fn show_roll_log(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let lines = crate::dice_roller::roll_log(&mut redis_connection, msg.channel_id)?;
+        if lines.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_ROLLS_LOGGED);
+            return Ok(());
+        }
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::ROLL_LOG_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn toggle_host_digest(
+        ctx: &Context,
+        msg: &Message,
+        enabled: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::discord_host_digest::set_opted_out(
+            &mut redis_connection,
+            msg.author.id.0,
+            !enabled,
+        )?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::HOST_DIGEST_TOGGLED(enabled));
+        Ok(())
+    }
+
+    pub fn subscribe(
+        ctx: &Context,
+        msg: &Message,
+        tag: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let tag = tag.to_lowercase();
+        let mut redis_connection = redis_client.get_connection()?;
+        let tag_rules = crate::event_tags::TagRules::load(&mut redis_connection)?;
+        if !tag_rules.is_known_tag(&tag) {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::SUBSCRIPTION_UNKNOWN_TAG(&tag, &tag_rules.tags()),
+            );
+            return Ok(());
+        }
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let was_new = crate::discord_subscriptions::subscribe(
+            msg.author.id.0,
+            &tag,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::SUBSCRIBED(&tag, was_new));
+        Ok(())
+    }
+
+    pub fn unsubscribe(
+        ctx: &Context,
+        msg: &Message,
+        tag: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let tag = tag.to_lowercase();
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let was_subscribed = crate::discord_subscriptions::unsubscribe(
+            msg.author.id.0,
+            &tag,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::UNSUBSCRIBED(&tag, was_subscribed));
+        Ok(())
+    }
+
+    pub fn list_subscriptions(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let tags =
+            crate::discord_subscriptions::subscriptions(&mut redis_connection, msg.author.id.0)?;
+        let tag_rules = crate::event_tags::TagRules::load(&mut redis_connection)?;
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::SUBSCRIPTIONS_LIST(&tags, &tag_rules.tags()),
+        );
+        Ok(())
+    }
+
+    pub fn set_subscription_role(
+        ctx: &Context,
+        msg: &Message,
+        tag: &str,
+        role_id: Option<u64>,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let tag = tag.to_lowercase();
+        let mut redis_connection = redis_client.get_connection()?;
+        let tag_rules = crate::event_tags::TagRules::load(&mut redis_connection)?;
+        if !tag_rules.is_known_tag(&tag) {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::SUBSCRIPTION_UNKNOWN_TAG(&tag, &tag_rules.tags()),
+            );
+            return Ok(());
+        }
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        crate::discord_subscriptions::set_tag_role(
+            &tag,
+            role_id,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::SUBSCRIPTION_ROLE_SET(&tag, role_id));
+        Ok(())
+    }
+
+    pub fn list_tag_rules(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let tag_rules = crate::event_tags::TagRules::load(&mut redis_connection)?;
+        let lines: Vec<_> = tag_rules
+            .rules()
+            .iter()
+            .map(|rule| strings::TAG_RULE_LINE(&rule.tag, &rule.keywords))
+            .collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::TAG_RULES_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    // "off" disables the tag, "default" restores its default keywords
+    pub fn set_tag_rule(
+        ctx: &Context,
+        msg: &Message,
+        tag: &str,
+        keywords: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let tag = tag.to_lowercase();
+        let mut redis_connection = redis_client.get_connection()?;
+        if keywords.eq_ignore_ascii_case("default") {
+            crate::event_tags::reset_rule(&mut redis_connection, &tag)?;
+            let _ = crate::discord_tidy::say(ctx, msg, strings::TAG_RULE_RESET(&tag));
+        } else {
+            let keywords = if keywords.eq_ignore_ascii_case("off") {
+                ""
+            } else {
+                keywords
+            };
+            let keywords = crate::event_tags::set_rule(&mut redis_connection, &tag, keywords)?;
+            let _ = crate::discord_tidy::say(ctx, msg, strings::TAG_RULE_SET(&tag, &keywords));
+        }
+        Ok(())
+    }
+
+    pub fn list_flagged_links(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let flagged_meetup_ids: Vec<u64> = redis_connection.smembers("flagged_meetup_users")?;
+        let departures = crate::meetup_membership::departures(&mut redis_connection)?;
+        if flagged_meetup_ids.is_empty() && departures.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_FLAGGED_LINKS);
+            return Ok(());
+        }
+        let mut lines = Vec::with_capacity(flagged_meetup_ids.len() + departures.len());
+        for meetup_id in flagged_meetup_ids {
+            let redis_key_m2d = crate::identity::redis_meetup_discord_user_key(meetup_id);
+            let redis_user_profile_key = format!("meetup_user:{}:profile", meetup_id);
+            let (discord_id, meetup_name): (Option<u64>, Option<String>) = redis::pipe()
+                .get(&redis_key_m2d)
+                .hget(&redis_user_profile_key, "name")
+                .query(&mut redis_connection)?;
+            let discord_user = match discord_id {
+                Some(discord_id) => format!("<@{}>", discord_id),
+                None => "nobody".to_string(),
+            };
+            lines.push(format!(
+                "Meetup user {} ({}) linked to {}",
+                meetup_id,
+                meetup_name.as_ref().map(String::as_str).unwrap_or("unknown name"),
+                discord_user
+            ));
+        }
+        // Members that left the Meetup groups
+        for (meetup_id, left_time) in departures {
+            let discord_id =
+                crate::identity::meetup_discord_user(meetup_id, &mut redis_connection)?;
+            let discord_user = match discord_id {
+                Some(discord_id) => format!("<@{}>", discord_id),
+                None => "nobody".to_string(),
+            };
+            lines.push(format!(
+                "Meetup user {} linked to {} left the Meetup groups on {}",
+                meetup_id,
+                discord_user,
+                left_time.format("%Y-%m-%d")
+            ));
+        }
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::FLAGGED_LINKS_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn toggle_feature(
+        ctx: &Context,
+        msg: &Message,
+        feature_name: &str,
+        enable: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let feature = match crate::feature_flags::Feature::from_name(feature_name) {
+            Some(feature) => feature,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::UNKNOWN_FEATURE(feature_name));
+                return Ok(());
+            }
+        };
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::feature_flags::set_enabled(
+            &mut redis_connection,
+            crate::discord_sync::GUILD_ID,
+            feature,
+            enable,
+        )?;
+        crate::discord_sync::mark_all_series_dirty(&mut redis_connection)?;
+        let _ =
+            crate::discord_tidy::say(ctx, msg, strings::FEATURE_TOGGLED(feature.name(), enable));
+        Ok(())
+    }
+
+    pub fn set_quick_command_emoji(
+        ctx: &Context,
+        msg: &Message,
+        command_name: &str,
+        emoji: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let command = match crate::discord_quick_commands::QuickCommand::from_name(command_name) {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+        // Custom emojis (like <:name:id>) are not supported
+        if emoji.starts_with('<') || emoji.chars().any(char::is_alphanumeric) {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::QUICK_COMMAND_INVALID_EMOJI);
+            return Ok(());
+        }
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::discord_quick_commands::set_emoji(&mut redis_connection, command, emoji)?;
+        crate::discord_sync::mark_all_series_dirty(&mut redis_connection)?;
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::QUICK_COMMAND_EMOJI_SET(command.name(), emoji),
+        );
+        Ok(())
+    }
+
+    pub fn set_host_verification_mode(
+        ctx: &Context,
+        msg: &Message,
+        mode_name: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mode = match crate::host_verification::Mode::from_name(mode_name) {
+            Some(mode) => mode,
+            None => return Ok(()),
+        };
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::host_verification::set_mode(&mut redis_connection, mode)?;
+        let _ =
+            crate::discord_tidy::say(ctx, msg, strings::HOST_VERIFICATION_MODE_SET(mode.name()));
+        Ok(())
+    }
+
+    pub fn set_community_digest_channel(
+        ctx: &Context,
+        msg: &Message,
+        channel_id: Option<u64>,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::discord_community_digest::set_channel(
+            &mut redis_connection,
+            channel_id.map(ChannelId),
+        )?;
+        let config = crate::discord_community_digest::config(&mut redis_connection)?;
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::COMMUNITY_DIGEST_CHANNEL_SET(
+                channel_id,
+                crate::discord_community_digest::weekday_name(config.weekday),
+                config.hour,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn set_community_digest_schedule(
+        ctx: &Context,
+        msg: &Message,
+        weekday: &str,
+        hour: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let weekday = crate::discord_community_digest::parse_weekday(weekday);
+        let hour = hour.parse::<u32>().ok().filter(|hour| *hour < 24);
+        let (weekday, hour) = match (weekday, hour) {
+            (Some(weekday), Some(hour)) => (weekday, hour),
+            _ => {
+                let _ =
+                    crate::discord_tidy::say(ctx, msg, strings::COMMUNITY_DIGEST_INVALID_SCHEDULE);
+                return Ok(());
+            }
+        };
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::discord_community_digest::set_schedule(&mut redis_connection, weekday, hour)?;
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::COMMUNITY_DIGEST_SCHEDULE_SET(
+                crate::discord_community_digest::weekday_name(weekday),
+                hour,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn set_community_digest_template(
+        ctx: &Context,
+        msg: &Message,
+        template: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        if template.eq_ignore_ascii_case("default") {
+            crate::discord_community_digest::set_template(&mut redis_connection, None)?;
+            let _ = crate::discord_tidy::say(ctx, msg, strings::COMMUNITY_DIGEST_TEMPLATE_RESET);
+        } else {
+            crate::discord_community_digest::set_template(&mut redis_connection, Some(template))?;
+            let _ = crate::discord_tidy::say(ctx, msg, strings::COMMUNITY_DIGEST_TEMPLATE_SET);
+        }
+        Ok(())
+    }
+
+    pub fn set_crosspost_webhook(
+        ctx: &Context,
+        msg: &Message,
+        url: Option<&str>,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        if let Some(url) = url {
+            if !crate::discord_crosspost::is_valid_webhook_url(url) {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CROSSPOST_WEBHOOK_INVALID);
+                return Ok(());
+            }
+            // Anybody with the URL can post through the webhook
+            if let Err(err) = msg.delete(ctx) {
+                eprintln!("Could not delete the message with the webhook URL: {}", err);
+            }
+        }
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::discord_crosspost::set_webhook_url(url, &mut redis_connection)?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::CROSSPOST_WEBHOOK_SET(url.is_some()));
+        Ok(())
+    }
+
+    pub fn set_crosspost_template(
+        ctx: &Context,
+        msg: &Message,
+        template: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        if template.eq_ignore_ascii_case("default") {
+            crate::discord_crosspost::set_template(&mut redis_connection, None)?;
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CROSSPOST_TEMPLATE_RESET);
+        } else {
+            crate::discord_crosspost::set_template(&mut redis_connection, Some(template))?;
+            let _ = crate::discord_tidy::say(ctx, msg, strings::CROSSPOST_TEMPLATE_SET);
+        }
+        Ok(())
+    }
+
+    pub fn quarantine_user(
+        ctx: &Context,
+        msg: &Message,
+        discord_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let num_channels =
+            crate::quarantine::quarantine(discord_id, &mut redis_connection, &discord_api)?;
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::USER_QUARANTINED(discord_id, num_channels),
+        );
+        Ok(())
+    }
+
+    pub fn release_user(
+        ctx: &Context,
+        msg: &Message,
+        discord_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        if crate::quarantine::release(&mut redis_connection, discord_id)? {
+            crate::discord_sync::mark_all_series_dirty(&mut redis_connection)?;
+            let _ = crate::discord_tidy::say(ctx, msg, strings::USER_RELEASED(discord_id));
+        } else {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::USER_NOT_QUARANTINED(discord_id));
+        }
+        Ok(())
+    }
+
+    pub fn set_user_ignored(
+        ctx: &Context,
+        msg: &Message,
+        discord_id: u64,
+        ignored: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let changed = crate::ignore_list::set_ignored(&mut redis_connection, discord_id, ignored)?;
+        if changed && !ignored {
+            // The sync might have skipped the user's channels
+            crate::discord_sync::mark_all_series_dirty(&mut redis_connection)?;
+        }
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::USER_IGNORED(discord_id, ignored, changed),
+        );
+        Ok(())
+    }
+
+    pub fn list_ignored_users(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let mut discord_ids = crate::ignore_list::ignored_users(&mut redis_connection)?;
+        discord_ids.sort_unstable();
+        let _ = crate::discord_tidy::say(ctx, msg, strings::IGNORED_USERS(&discord_ids));
+        Ok(())
+    }
+
+    // Creates an invite whose joins are counted for the label. It leads to the
+    // server's system channel if there is one, to this channel otherwise.
+    pub fn invite_create(
+        ctx: &Context,
+        msg: &Message,
+        label: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let label = match crate::discord_invites::normalize_label(label) {
+            Some(label) => label,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::INVITE_LABEL_INVALID());
+                return Ok(());
+            }
+        };
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let channel_id = crate::discord_sync::GUILD_ID
+            .to_guild_cached(&ctx)
+            .and_then(|guild| guild.read().system_channel_id)
+            .unwrap_or(msg.channel_id);
+        let code = crate::discord_invites::create_invite(
+            &label,
+            channel_id,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        println!(
+            "User {} created the invite {} for \"{}\"",
+            msg.author.id.0, code, label
+        );
+        let _ = crate::discord_tidy::say(ctx, msg, strings::INVITE_CREATED(&label, &code));
+        Ok(())
+    }
+
+    // Lists how many members joined through the invites of each label
+    pub fn invite_stats(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let (stats, num_unattributed) = crate::discord_invites::join_stats(&mut redis_connection)?;
+        if stats.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_INVITES);
+            return Ok(());
+        }
+        let mut lines: Vec<_> = stats
+            .iter()
+            .map(|(label, num_joins)| strings::INVITE_STATS_LINE(label, *num_joins))
+            .collect();
+        lines.push(strings::INVITE_STATS_UNATTRIBUTED(num_unattributed));
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::INVITE_STATS_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn usage_report(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let report = crate::discord_command_usage::report(&mut redis_connection)?;
+        let mut lines = vec![strings::USAGE_REPORT_SUMMARY(
+            report.commands.iter().map(|usage| usage.num_uses).sum(),
+            report.num_users,
+        )];
+        lines.extend(report.commands.iter().map(|usage| {
+            strings::USAGE_REPORT_LINE(&usage.command, usage.num_uses, usage.num_users)
+        }));
+        if !report.unused_commands.is_empty() {
+            lines.push(strings::USAGE_REPORT_UNUSED(&report.unused_commands));
+        }
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            &strings::USAGE_REPORT_TITLE(crate::discord_command_usage::REPORT_DAYS),
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn list_duplicate_events(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let flagged_events = crate::meetup_dedup::flagged_events(&mut redis_connection)?;
+        if flagged_events.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_DUPLICATE_EVENTS);
+            return Ok(());
+        }
+        let mut lines = Vec::with_capacity(flagged_events.len());
+        for (event_id, flagged_event) in &flagged_events {
+            let time = chrono::DateTime::parse_from_rfc3339(&flagged_event.time)?
+                .with_timezone(&chrono::Utc);
+            // The channels of the series, where they have one
+            let mut candidates = Vec::with_capacity(flagged_event.candidate_series.len());
+            for series_id in &flagged_event.candidate_series {
+                let redis_series_channel_key =
+                    format!("event_series:{}:discord_channel", series_id);
+                let channel_id: Option<u64> = redis_connection.get(&redis_series_channel_key)?;
+                candidates.push(match channel_id {
+                    Some(channel_id) => format!("<#{}>", channel_id),
+                    None => format!("series {}", series_id),
+                });
+            }
+            lines.push(strings::DUPLICATE_EVENT_LINE(
+                event_id,
+                flagged_event,
+                time,
+                &candidates,
+            ));
+        }
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::DUPLICATE_EVENTS_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn allow_duplicate(
+        ctx: &Context,
+        msg: &Message,
+        event_id: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        if crate::meetup_dedup::allow(event_id, &mut redis_connection)? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::DUPLICATE_ALLOWED(event_id));
+        } else {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::DUPLICATE_NOT_FLAGGED);
+        }
+        Ok(())
+    }
+
+    pub fn storage_report(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let stats = crate::redis_storage::collect_stats(&mut redis_connection)?;
+        let mut lines = vec![];
+        if let Some(num_keys_before) =
+            crate::redis_storage::unexpected_growth(&mut redis_connection, stats.num_keys)?
+        {
+            lines.push(strings::STORAGE_GROWTH_WARNING(
+                num_keys_before,
+                stats.num_keys,
+            ));
+        }
+        lines.extend(stats.families.iter().map(|family_stats| {
+            strings::STORAGE_REPORT_LINE(
+                &family_stats.family,
+                family_stats.num_keys,
+                family_stats.estimated_bytes,
+                family_stats.num_without_ttl,
+            )
+        }));
+        let total_bytes = stats
+            .families
+            .iter()
+            .map(|family_stats| family_stats.estimated_bytes)
+            .sum();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            &strings::STORAGE_REPORT_TITLE(stats.num_keys, total_bytes),
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn list_features(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let mut lines = Vec::with_capacity(crate::feature_flags::Feature::ALL.len());
+        for feature in &crate::feature_flags::Feature::ALL {
+            let enabled = crate::feature_flags::is_enabled(
+                &mut redis_connection,
+                crate::discord_sync::GUILD_ID,
+                *feature,
+            )?;
+            lines.push(format!(
+                "{} `{}`",
+                if enabled { "\u{2705}" } else { "\u{274C}" },
+                feature.name()
+            ));
+        }
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::FEATURES_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn block_link_pattern(
+        ctx: &Context,
+        msg: &Message,
+        pattern: &str,
+        block: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        if pattern.chars().count() > crate::discord_moderation::MAX_PATTERN_CHARS {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::BLOCKED_LINK_PATTERN_TOO_LONG(
+                    crate::discord_moderation::MAX_PATTERN_CHARS,
+                ),
+            );
+            return Ok(());
+        }
+        let mut redis_connection = redis_client.get_connection()?;
+        let changed = if block {
+            crate::discord_moderation::block_link_pattern(&mut redis_connection, pattern)?
+        } else {
+            crate::discord_moderation::unblock_link_pattern(&mut redis_connection, pattern)?
+        };
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::BLOCKED_LINK_PATTERN_TOGGLED(pattern, block, changed),
+        );
+        Ok(())
+    }
+
+    pub fn list_blocked_links(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let patterns = crate::discord_moderation::blocked_link_patterns(&mut redis_connection)?;
+        if patterns.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_BLOCKED_LINK_PATTERNS);
+            return Ok(());
+        }
+        let lines: Vec<_> = patterns
+            .iter()
+            .map(|pattern| format!("`{}`", pattern))
+            .collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::BLOCKED_LINK_PATTERNS_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn toggle_maintenance_mode(
+        ctx: &Context,
+        msg: &Message,
+        active: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::maintenance_mode::set_active(&mut redis_connection, active)?;
+        let reply = if active {
+            strings::MAINTENANCE_MODE_ON
+        } else {
+            strings::MAINTENANCE_MODE_OFF
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    pub fn approve_creation(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::discord_creation_budget::approve_next_sync(&mut redis_connection)?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::CREATION_APPROVED);
+        Ok(())
+    }
+
+    pub fn share_meetup(
+        ctx: &Context,
+        msg: &Message,
+        member_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        match crate::households::request(msg.author.id.0, member_id, &mut redis_connection)? {
+            crate::households::Request::Requested { meetup_id } => {
+                let discord_api = crate::discord_bot::CacheAndHttp {
+                    cache: ctx.cache.clone(),
+                    http: ctx.http.clone(),
+                };
+                crate::discord_notifications::notify_organizers(
+                    &discord_api,
+                    &strings::HOUSEHOLD_REQUEST_NOTIFICATION(msg.author.id.0, member_id, meetup_id),
+                    &mut redis_connection,
+                )?;
+                let _ = crate::discord_tidy::say(ctx, msg, strings::HOUSEHOLD_REQUESTED(member_id));
+            }
+            crate::households::Request::NotLinked => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::HOUSEHOLD_NOT_LINKED);
+            }
+            crate::households::Request::MemberLinked => {
+                let _ =
+                    crate::discord_tidy::say(ctx, msg, strings::HOUSEHOLD_MEMBER_LINKED(member_id));
+            }
+        }
+        Ok(())
+    }
+
+    // The member, the owner of the shared account and organizers can end the
+    // sharing
+    pub fn unshare_meetup(
+        ctx: &Context,
+        msg: &Message,
+        member_id: u64,
+        is_organizer: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let meetup_id =
+            match crate::households::household_meetup_user(member_id, &mut redis_connection)? {
+                Some(meetup_id) => meetup_id,
+                None => {
+                    let _ = crate::discord_tidy::say(
+                        ctx,
+                        msg,
+                        strings::HOUSEHOLD_NOT_A_MEMBER(member_id),
+                    );
+                    return Ok(());
+                }
+            };
+        let is_owner =
+            crate::identity::meetup_id(msg.author.id.0, &mut redis_connection)? == Some(meetup_id);
+        if !is_organizer && !is_owner && msg.author.id.0 != member_id {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::HOUSEHOLD_NOT_ALLOWED);
+            return Ok(());
+        }
+        crate::households::remove_member(member_id, &mut redis_connection)?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::HOUSEHOLD_MEMBER_REMOVED(member_id));
+        Ok(())
+    }
+
+    pub fn approve_household(
+        ctx: &Context,
+        msg: &Message,
+        member_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let reply = match crate::households::approve(member_id, &mut redis_connection)? {
+            Some(meetup_id) => strings::HOUSEHOLD_APPROVED(member_id, meetup_id),
+            None => strings::HOUSEHOLD_NO_REQUEST(member_id),
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    pub fn reject_household(
+        ctx: &Context,
+        msg: &Message,
+        member_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let reply = if crate::households::reject(member_id, &mut redis_connection)? {
+            strings::HOUSEHOLD_REJECTED(member_id)
+        } else {
+            strings::HOUSEHOLD_NO_REQUEST(member_id)
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    pub fn list_household_requests(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let requests = crate::households::pending_requests(&mut redis_connection)?;
+        if requests.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::HOUSEHOLD_NO_PENDING_REQUESTS);
+            return Ok(());
+        }
+        let mut lines = Vec::with_capacity(requests.len());
+        for (member_id, meetup_id) in requests {
+            let owner_id = crate::identity::meetup_discord_user(meetup_id, &mut redis_connection)?;
+            lines.push(strings::HOUSEHOLD_PENDING_REQUEST(
+                member_id, meetup_id, owner_id,
+            ));
+        }
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::HOUSEHOLD_REQUESTS_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    // Links the suggested Discord user, with the same checks as "link meetup"
+    pub fn confirm_link(
+        ctx: &Context,
+        msg: &Message,
+        regexes: &Regexes,
+        meetup_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let discord_id = {
+            let mut redis_connection = redis_client.get_connection()?;
+            crate::link_suggestions::take(meetup_id, &mut redis_connection)?
+        };
+        match discord_id {
+            Some(discord_id) => {
+                Self::link_meetup_organizer(ctx, msg, regexes, discord_id, meetup_id)
+            }
+            None => {
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
+                    strings::LINK_SUGGESTION_NOT_FOUND(meetup_id),
+                );
+                Ok(())
+            }
+        }
+    }
+
+    pub fn dismiss_link(
+        ctx: &Context,
+        msg: &Message,
+        meetup_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let reply = if crate::link_suggestions::dismiss(meetup_id, &mut redis_connection)? {
+            strings::LINK_SUGGESTION_DISMISSED(meetup_id)
+        } else {
+            strings::LINK_SUGGESTION_NOT_FOUND(meetup_id)
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    // Shows the best matches between Meetup members and guild members, with
+    // the Meetup photo and the Discord avatar to compare
+    pub fn suggest_links(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let suggestions = crate::link_suggestions::rank_suggestions(
+            &mut redis_connection,
+            &discord_api,
+            MAX_LINK_SUGGESTIONS,
+        )?;
+        if suggestions.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::LINK_SUGGESTIONS_NONE);
+            return Ok(());
+        }
+        let bot_id = ctx.cache.read().user.id.0;
+        let _ =
+            crate::discord_tidy::say(ctx, msg, strings::LINK_SUGGESTIONS_TITLE(suggestions.len()));
+        for suggestion in suggestions {
+            let _ = msg.channel_id.send_message(&ctx.http, |message| {
+                message.embed(|embed| {
+                    embed.title(&suggestion.meetup_name);
+                    embed.author(|author| {
+                        author
+                            .name(&suggestion.discord_tag)
+                            .icon_url(&suggestion.discord_avatar)
+                    });
+                    embed.description(strings::LINK_SUGGESTION(
+                        suggestion.meetup_id,
+                        suggestion.discord_id,
+                        suggestion.similarity,
+                        bot_id,
+                    ));
+                    if let Some(ref photo_url) = suggestion.meetup_photo {
+                        embed.thumbnail(photo_url)
+                    } else {
+                        embed
+                    }
+                })
+            });
+        }
+        Ok(())
+    }
+
+    pub fn list_inactive_members(
+        ctx: &Context,
+        msg: &Message,
+        min_days: u32,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let members = crate::discord_inactive_members::inactive_members(
+            min_days,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        if members.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::INACTIVE_MEMBERS_NONE(min_days));
+            return Ok(());
+        }
+        let lines: Vec<_> = members
+            .iter()
+            .map(|member| {
+                strings::INACTIVE_MEMBER_LINE(
+                    member.discord_id,
+                    &member.tag,
+                    member.joined_at,
+                    member.last_nudge_time,
+                )
+            })
+            .collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            &strings::INACTIVE_MEMBERS_TITLE(members.len(), min_days),
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn nudge_inactive_members(
+        ctx: &Context,
+        msg: &Message,
+        min_days: u32,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let members = crate::discord_inactive_members::members_to_nudge(
+            min_days,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        if members.is_empty() {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::INACTIVE_MEMBERS_NONE_TO_NUDGE(min_days),
+            );
+            return Ok(());
+        }
+        crate::discord_confirmations::request_confirmation(
+            ctx,
+            msg.channel_id,
+            msg.author.id,
+            crate::discord_confirmations::PendingAction::NudgeInactiveMembers {
+                min_days: min_days,
+            },
+            &strings::NUDGE_INACTIVE_MEMBERS_PROMPT(members.len()),
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    // Called once an organizer confirmed the nudge. The list is built again,
+    // in case members linked their account in the meantime.
+    pub fn nudge_inactive_members_confirmed(
+        ctx: &Context,
+        channel_id: ChannelId,
+        min_days: u32,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<(), BoxedError> {
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let (num_nudged, num_failed) =
+            crate::discord_inactive_members::nudge(min_days, redis_connection, &discord_api)?;
+        let _ = channel_id.say(
+            &ctx.http,
+            strings::INACTIVE_MEMBERS_NUDGED(num_nudged, num_failed),
+        );
+        Ok(())
+    }
+
+    // Shows the linked accounts, the household and the membership dues.
+    // Members of a household share the dues of the household's account.
+    pub fn whois(
+        ctx: &Context,
+        msg: &Message,
+        discord_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let identities = crate::identity::identities(discord_id, &mut redis_connection)?;
+        let household_meetup_id =
+            crate::households::household_meetup_user(discord_id, &mut redis_connection)?;
+        let dues_group = crate::guild_config::dues_group(&mut redis_connection)?;
+        let meetup_id = identities
+            .iter()
+            .filter_map(|identity| identity.meetup_id())
+            .next()
+            .or(household_meetup_id);
+        let dues = match (dues_group, meetup_id) {
+            (Some(_), Some(meetup_id)) => {
+                crate::meetup_dues::dues(meetup_id, &mut redis_connection)?
+            }
+            _ => None,
+        };
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::WHOIS(
+                discord_id,
+                &identities,
+                household_meetup_id,
+                dues_group,
+                dues.as_ref(),
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn user_channels(
+        ctx: &Context,
+        msg: &Message,
+        discord_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let channels = crate::discord_user_channels::user_channels(
+            discord_id,
+            &mut redis_connection,
+            &discord_api,
+        )?;
+        if channels.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::USER_CHANNELS_NONE(discord_id));
+            return Ok(());
+        }
+        // Users who left the server might not be in the cache anymore
+        let user_name = match ctx.cache.read().user(discord_id) {
+            Some(user) => user.read().tag(),
+            None => discord_id.to_string(),
+        };
+        let lines: Vec<_> = channels
+            .iter()
+            .map(|(channel_id, channel)| strings::USER_CHANNELS_LINE(channel_id.0, channel))
+            .collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            &strings::USER_CHANNELS_TITLE(&user_name),
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn set_dues_role(
+        ctx: &Context,
+        msg: &Message,
+        role_id: u64,
+        gated: bool,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        crate::meetup_dues::set_gated(role_id, gated, &mut redis_connection)?;
+        let reply = if gated {
+            strings::DUES_ROLE_SET(
+                role_id,
+                crate::guild_config::dues_group(&mut redis_connection)?,
+            )
+        } else {
+            strings::DUES_ROLE_REMOVED(role_id)
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    // Lists the roles of gone channels and ended series and asks whether to
+    // delete them
+    pub fn cleanup_roles(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let candidates =
+            crate::discord_role_cleanup::candidates(&mut redis_connection, &discord_api)?;
+        if candidates.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::ROLE_CLEANUP_NONE);
+            return Ok(());
+        }
+        let lines: Vec<_> = candidates
+            .iter()
+            .map(|candidate| strings::ROLE_CLEANUP_LINE(candidate))
+            .collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            &strings::ROLE_CLEANUP_TITLE(candidates.len()),
+            &lines,
+            &mut redis_connection,
+        )?;
+        crate::discord_confirmations::request_confirmation(
+            ctx,
+            msg.channel_id,
+            msg.author.id,
+            crate::discord_confirmations::PendingAction::CleanupRoles,
+            &strings::ROLE_CLEANUP_PROMPT(candidates.len()),
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    // Called once an organizer confirmed the cleanup. The list is built
+    // again, in case a series got new events in the meantime.
+    pub fn cleanup_roles_confirmed(
+        ctx: &Context,
+        channel_id: ChannelId,
+        redis_connection: &mut crate::redis_namespace::Connection,
+    ) -> Result<(), BoxedError> {
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let (num_deleted, num_failed) =
+            crate::discord_role_cleanup::cleanup(redis_connection, &discord_api)?;
+        let _ = channel_id.say(
+            &ctx.http,
+            strings::ROLE_CLEANUP_DONE(num_deleted, num_failed),
+        );
+        Ok(())
+    }
+
+    pub fn role_usage(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let usage = crate::discord_role_limit::role_usage(&mut redis_connection, &discord_api)?;
+        let policy = crate::guild_config::role_pruning(&mut redis_connection)?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::ROLE_USAGE(&usage, policy));
+        Ok(())
+    }
+
+    // Verifies that the bot has all the permissions it needs and reports a
+    // checklist with suggested fixes
+    pub fn check_permissions(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let bot_user_id = ctx.cache.read().user.id;
+        let guild = match crate::discord_sync::GUILD_ID.to_guild_cached(&ctx) {
+            Some(guild) => guild,
+            None => return Err(SimpleError::new("Could not find the guild in the cache").into()),
+        };
+        let guild = guild.read();
+        // (passed, check, fix)
+        let mut checks: Vec<(bool, String, &'static str)> = vec![];
+        // Guild wide permissions
+        let guild_permissions = guild.member_permissions(bot_user_id);
+        checks.push((
+            guild_permissions.manage_channels(),
+            strings::PERMISSION_CHECK_MANAGE_CHANNELS.to_string(),
+            strings::PERMISSION_FIX_GUILD_PERMISSION,
+        ));
+        checks.push((
+            guild_permissions.manage_roles(),
+            strings::PERMISSION_CHECK_MANAGE_ROLES.to_string(),
+            strings::PERMISSION_FIX_GUILD_PERMISSION,
+        ));
+        // Permissions in the configured categories
+        let categories = [
+            crate::guild_config::one_shot_category(&mut redis_connection)?,
+            crate::guild_config::campaign_category(&mut redis_connection)?,
+        ];
+        for category_id in categories.iter().filter_map(|id| *id) {
+            let category_permissions = guild.permissions_in(category_id, bot_user_id);
+            checks.push((
+                category_permissions.manage_channels() && category_permissions.manage_roles(),
+                strings::PERMISSION_CHECK_CATEGORY(category_id.0),
+                strings::PERMISSION_FIX_CATEGORY,
+            ));
+        }
+        // Reactions are needed for confirmations and pagination
+        let channel_permissions = guild.permissions_in(msg.channel_id, bot_user_id);
+        checks.push((
+            channel_permissions.add_reactions() && channel_permissions.read_message_history(),
+            strings::PERMISSION_CHECK_REACTIONS.to_string(),
+            strings::PERMISSION_FIX_REACTIONS,
+        ));
+        // The bot can only manage roles that are below its own highest role
+        let bot_position = guild
+            .members
+            .get(&bot_user_id)
+            .map(|member| {
+                member
+                    .roles
+                    .iter()
+                    .filter_map(|role_id| guild.roles.get(role_id))
+                    .map(|role| role.position)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .unwrap_or(0);
+        let mut managed_role_ids: Vec<u64> =
+            redis_connection.sunion(&["discord_roles", "discord_host_roles"])?;
+        if let Some(game_master_role) =
+            crate::guild_config::game_master_role(&mut redis_connection)?
+        {
+            managed_role_ids.push(game_master_role.0);
+        }
+        let roles_above_bot: Vec<_> = managed_role_ids
+            .into_iter()
+            .filter_map(|role_id| guild.roles.get(&RoleId(role_id)))
+            .filter(|role| role.position >= bot_position)
+            .map(|role| role.name.clone())
+            .collect();
+        checks.push((
+            roles_above_bot.is_empty(),
+            strings::PERMISSION_CHECK_ROLE_HIERARCHY(&roles_above_bot),
+            strings::PERMISSION_FIX_ROLE_HIERARCHY,
+        ));
+        drop(guild);
+        // Direct messages are needed for notifications and Meetup linking
+        checks.push((
+            msg.author.create_dm_channel(ctx).is_ok(),
+            strings::PERMISSION_CHECK_DIRECT_MESSAGES.to_string(),
+            strings::PERMISSION_FIX_DIRECT_MESSAGES,
+        ));
+        let lines: Vec<_> = checks
+            .into_iter()
+            .map(|(passed, check, fix)| {
+                if passed {
+                    format!("\u{2705} {}", check)
+                } else {
+                    format!("\u{274C} {}\n    \u{2192} {}", check, fix)
+                }
+            })
+            .collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::PERMISSION_CHECK_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn sync_stats(
+        ctx: &Context,
+        msg: &Message,
+        num_runs: usize,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let runs = crate::sync_stats::recent_runs(&mut redis_connection, num_runs)?;
+        if runs.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_SYNC_STATS);
+            return Ok(());
+        }
+        let mut lines = vec![];
+        let totals: Vec<_> = runs.iter().map(|run| run.total).collect();
+        if let Some(summary) = crate::sync_stats::summarize(&totals) {
+            lines.push(strings::SYNC_STATS_LINE("total", &summary));
+        }
+        for step in crate::sync_stats::STEPS {
+            // Optional steps only count in the runs where they ran
+            let step_stats: Vec<_> = runs
+                .iter()
+                .filter_map(|run| run.steps.get(*step).cloned())
+                .collect();
+            if let Some(summary) = crate::sync_stats::summarize(&step_stats) {
+                lines.push(strings::SYNC_STATS_LINE(step, &summary));
+            }
+        }
+        if let Some(summary) = crate::sync_stats::summarize_budget(&runs) {
+            lines.push(strings::SYNC_STATS_BUDGET_LINE(&summary));
+        }
+        if let Some(summary) = crate::sync_stats::summarize_queue(&runs) {
+            lines.push(strings::SYNC_STATS_QUEUE_LINE(&summary));
+        }
+        let average_num_series =
+            runs.iter().map(|run| run.num_series).sum::<u64>() / runs.len() as u64;
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            &strings::SYNC_STATS_TITLE(runs.len(), average_num_series),
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    // Shows what the next Discord sync would change for the series
+    pub fn preview_sync(
+        ctx: &Context,
+        msg: &Message,
+        series_id: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let series_exists: bool = redis_connection.sismember("event_series", series_id)?;
+        if !series_exists {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::DEBUG_UNKNOWN_SERIES(series_id));
+            return Ok(());
+        }
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let preview =
+            crate::discord_sync_preview::preview(series_id, &mut redis_connection, &discord_api)?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::SYNC_PREVIEW(series_id, &preview));
+        Ok(())
+    }
+
+    // Shows one or all of the runtime settings
+    pub fn config_get(
+        ctx: &Context,
+        msg: &Message,
+        key_name: Option<&str>,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let keys = match key_name {
+            Some(key_name) => match crate::guild_config::ConfigKey::from_name(key_name) {
+                Some(key) => vec![key],
+                None => {
+                    let _ =
+                        crate::discord_tidy::say(ctx, msg, strings::CONFIG_UNKNOWN_KEY(key_name));
+                    return Ok(());
+                }
+            },
+            None => crate::guild_config::ConfigKey::ALL.to_vec(),
+        };
+        let mut lines = Vec::with_capacity(keys.len());
+        for key in keys {
+            let value = crate::guild_config::get_override(&mut redis_connection, key)?;
+            lines.push(strings::CONFIG_VALUE_LINE(
+                key,
+                value.as_ref().map(String::as_str),
+            ));
+        }
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::CONFIG_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    // Changes a runtime setting, "default" removes the override
+    pub fn config_set(
+        ctx: &Context,
+        msg: &Message,
+        key_name: &str,
+        value: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let key = match crate::guild_config::ConfigKey::from_name(key_name) {
+            Some(key) => key,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CONFIG_UNKNOWN_KEY(key_name));
+                return Ok(());
+            }
+        };
+        let value = if value.eq_ignore_ascii_case("default") {
+            None
+        } else {
+            match key.parse_value(value) {
+                Some(value) => Some(value),
+                None => {
+                    let _ = crate::discord_tidy::say(ctx, msg, strings::CONFIG_INVALID_VALUE(key));
+                    return Ok(());
+                }
+            }
+        };
+        // Make sure that the channel exists and is of the right kind
+        let channel_id = value
+            .as_ref()
+            .filter(|_| key.is_channel())
+            .and_then(|value| value.parse::<u64>().ok());
+        if let Some(channel_id) = channel_id {
+            let is_valid = match ChannelId(channel_id).to_channel(ctx) {
+                Ok(Channel::Category(category)) => {
+                    key != crate::guild_config::ConfigKey::StaffChannel
+                        && category.read().guild_id == crate::discord_sync::GUILD_ID
+                }
+                Ok(Channel::Guild(channel)) => {
+                    let channel = channel.read();
+                    key == crate::guild_config::ConfigKey::StaffChannel
+                        && channel.kind == ChannelType::Text
+                        && channel.guild_id == crate::discord_sync::GUILD_ID
+                }
+                Ok(_) => false,
+                Err(err) => {
+                    eprintln!("Could not find channel {}: {}", channel_id, err);
+                    false
+                }
+            };
+            if !is_valid {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::CONFIG_INVALID_VALUE(key));
+                return Ok(());
+            }
+        }
+        // Switching to a source the bot has no credentials for would break the sync
+        if key == crate::guild_config::ConfigKey::EventSource {
+            let kind = value
+                .as_ref()
+                .and_then(|value| crate::event_source::SourceKind::from_name(value));
+            if let Some(kind) = kind {
+                if !kind.is_configured() {
+                    let _ = crate::discord_tidy::say(
+                        ctx,
+                        msg,
+                        strings::CONFIG_EVENT_SOURCE_NOT_CONFIGURED(kind.name()),
+                    );
+                    return Ok(());
+                }
+            }
+        }
+        crate::guild_config::set(
+            &mut redis_connection,
+            key,
+            value.as_ref().map(String::as_str),
+            msg.author.id.0,
+        )?;
+        println!(
+            "User {} set the config value {} to {:?}",
+            msg.author.id.0,
+            key.name(),
+            value
+        );
+        // Otherwise the new interval would only apply after the sync that is
+        // already scheduled, which might be a day away
+        if key == crate::guild_config::ConfigKey::SyncInterval {
+            let (bot_id, task_scheduler) = {
+                let data = ctx.data.read();
+                (
+                    *data
+                        .get::<crate::discord_bot::BotIdKey>()
+                        .ok_or_else(|| SimpleError::new("Bot ID was not set"))?,
+                    data.get::<crate::discord_bot::TaskSchedulerKey>()
+                        .ok_or_else(|| SimpleError::new("Task scheduler was not set"))?
+                        .clone(),
+                )
+            };
+            let interval = crate::guild_config::sync_interval(&mut redis_connection)?;
+            crate::persistent_tasks::schedule_task(
+                &task_scheduler,
+                &redis_client,
+                &crate::discord_bot::CacheAndHttp {
+                    cache: ctx.cache.clone(),
+                    http: ctx.http.clone(),
+                },
+                bot_id.0,
+                crate::persistent_tasks::TaskType::SyncDiscord,
+                white_rabbit::Utc::now() + white_rabbit::Duration::minutes(interval.num_minutes()),
+                /*recurring*/ true,
+            )?;
+        }
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::CONFIG_VALUE_SET(key, value.as_ref().map(String::as_str)),
+        );
+        Ok(())
+    }
+
+    // Lists the changes to the runtime settings
+    pub fn config_log(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let entries = crate::guild_config::audit_log(&mut redis_connection)?;
+        if entries.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_CONFIG_CHANGES);
+            return Ok(());
+        }
+        let lines: Vec<_> = entries.iter().map(strings::CONFIG_LOG_LINE).collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::CONFIG_LOG_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    // Walks the owner of the server through setting up the bot, one step
+    // per command
+    pub fn setup(
+        ctx: &Context,
+        msg: &Message,
+        change: Option<(&str, &str)>,
+        bot_id: u64,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        let (step_name, value) = match change {
+            Some(change) => change,
+            None => {
+                let mut steps = vec![];
+                for &step in crate::discord_setup::SetupStep::ALL.iter() {
+                    let value = crate::discord_setup::current_value(
+                        step,
+                        &mut redis_connection,
+                        &discord_api,
+                    )?;
+                    steps.push((step, value));
+                }
+                let next_step = steps
+                    .iter()
+                    .find(|(_, value)| value.is_none())
+                    .map(|(step, _)| *step);
+                let organizer_role = crate::discord_setup::role_name(
+                    &discord_api,
+                    crate::discord_sync::ORGANIZER_ID,
+                );
+                let _ = crate::discord_tidy::say(
+                    ctx,
+                    msg,
+                    strings::SETUP_OVERVIEW(&steps, &organizer_role, next_step, bot_id),
+                );
+                return Ok(());
+            }
+        };
+        let step = match crate::discord_setup::SetupStep::from_name(step_name) {
+            Some(step) => step,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::SETUP_UNKNOWN_STEP(step_name));
+                return Ok(());
+            }
+        };
+        let value = match crate::discord_setup::parse_value(value) {
+            Some(value) => value,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::SETUP_INVALID_VALUE(step));
+                return Ok(());
+            }
+        };
+        if !crate::discord_setup::apply(
+            step,
+            value,
+            msg.author.id.0,
+            bot_id,
+            &mut redis_connection,
+            &discord_api,
+        )? {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::SETUP_INVALID_VALUE(step));
+            return Ok(());
+        }
+        println!("User {} set up the step {}", msg.author.id.0, step.name());
+        let value = crate::discord_setup::current_value(step, &mut redis_connection, &discord_api)?;
+        let next_step = crate::discord_setup::next_step(step, &mut redis_connection, &discord_api)?;
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::SETUP_STEP_DONE(step, value.as_ref().map(String::as_str), next_step, bot_id),
+        );
+        Ok(())
+    }
+
+    // Enqueues a command to be run in this channel at the given time
+    pub fn schedule_command(
+        ctx: &Context,
+        msg: &Message,
+        regexes: &Regexes,
+        command: &str,
+        due_time: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let command = command.trim();
+        let due_time = match crate::scheduled_commands::parse_due_time(due_time) {
+            Some(due_time) => due_time,
+            None => {
+                let _ = crate::discord_tidy::say(ctx, msg, strings::SCHEDULE_COMMAND_INVALID_TIME);
+                return Ok(());
+            }
+        };
+        let now = chrono::Utc::now();
+        if due_time <= now
+            || due_time > now + chrono::Duration::days(crate::scheduled_commands::MAX_SCHEDULE_DAYS)
+        {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::SCHEDULE_COMMAND_TIME_OUT_OF_RANGE(
+                    crate::scheduled_commands::MAX_SCHEDULE_DAYS,
+                ),
+            );
+            return Ok(());
+        }
+        // Commands that schedule commands could keep each other alive forever
+        let scheduled_content = format!("{} {}", regexes.bot_mention, command);
+        if regexes
+            .schedule_command_organizer_mention
+            .is_match(&scheduled_content)
+        {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::SCHEDULE_COMMAND_NESTED);
+            return Ok(());
+        }
+        let command_id =
+            crate::scheduled_commands::schedule(ctx, msg, command, due_time, &redis_client)?;
+        println!(
+            "User {} scheduled the command \"{}\" for {} ({})",
+            msg.author.id.0, command, due_time, command_id
+        );
+        let _ = crate::discord_tidy::say(
+            ctx,
+            msg,
+            strings::COMMAND_SCHEDULED(&command_id, command, &due_time),
+        );
+        Ok(())
+    }
+
+    pub fn list_scheduled_commands(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let scheduled_commands = crate::scheduled_commands::list(&mut redis_connection)?;
+        if scheduled_commands.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_SCHEDULED_COMMANDS);
+            return Ok(());
+        }
+        let lines: Vec<_> = scheduled_commands
+            .iter()
+            .map(|(command_id, scheduled_command)| {
+                strings::SCHEDULED_COMMAND_LINE(command_id, scheduled_command)
+            })
+            .collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::SCHEDULED_COMMANDS_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn unschedule_command(
+        ctx: &Context,
+        msg: &Message,
+        command_id: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        if crate::scheduled_commands::cancel(&mut redis_connection, command_id)? {
+            println!(
+                "User {} cancelled the scheduled command {}",
+                msg.author.id.0, command_id
+            );
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::SCHEDULED_COMMAND_CANCELLED(command_id),
+            );
+        } else {
+            let _ = crate::discord_tidy::say(
+                ctx,
+                msg,
+                strings::SCHEDULED_COMMAND_NOT_FOUND(command_id),
+            );
+        }
+        Ok(())
+    }
+
+    pub fn list_organizer_tokens(
+        ctx: &Context,
+        msg: &Message,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let accounts = crate::meetup_organizer_tokens::list(&mut redis_connection)?;
+        if accounts.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::NO_ORGANIZER_TOKENS);
+            return Ok(());
+        }
+        let lines: Vec<_> = accounts
+            .iter()
+            .map(|account| {
+                strings::ORGANIZER_TOKEN_LINE(
+                    &account.name,
+                    account.meetup_user_id,
+                    account.healthy,
+                    account.last_check.as_ref().map(String::as_str),
+                    account.is_active,
+                )
+            })
+            .collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            strings::ORGANIZER_TOKENS_TITLE,
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn revoke_organizer_token(
+        ctx: &Context,
+        msg: &Message,
+        meetup_user_id: u64,
+        redis_client: crate::redis_namespace::Client,
+        meetup_clients: crate::meetup_organizer_tokens::MeetupClients,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let revoked = crate::meetup_organizer_tokens::revoke(
+            &mut redis_connection,
+            meetup_user_id,
+            &meetup_clients,
+        )?;
+        let reply = if revoked {
+            strings::ORGANIZER_TOKEN_REVOKED(meetup_user_id)
+        } else {
+            strings::UNKNOWN_ORGANIZER_TOKEN(meetup_user_id)
+        };
+        let _ = crate::discord_tidy::say(ctx, msg, reply);
+        Ok(())
+    }
+
+    pub fn debug_welcome(ctx: &Context, msg: &Message, user_id: u64) -> Result<(), BoxedError> {
+        let user = UserId(user_id).to_user(ctx)?;
+        Self::send_welcome_message(ctx, &user);
+        let _ = crate::discord_tidy::say(ctx, msg, strings::DEBUG_WELCOME_SENT(user_id));
+        Ok(())
+    }
+
+    pub fn debug_sync_series(
+        ctx: &Context,
+        msg: &Message,
+        series_id: &str,
+        redis_client: crate::redis_namespace::Client,
+        bot_id: u64,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let series_exists: bool = redis_connection.sismember("event_series", series_id)?;
+        if !series_exists {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::DEBUG_UNKNOWN_SERIES(series_id));
+            return Ok(());
+        }
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: ctx.cache.clone(),
+            http: ctx.http.clone(),
+        };
+        crate::discord_sync::sync_single_event_series(
+            series_id,
+            &mut redis_connection,
+            &discord_api,
+            bot_id,
+        )?;
+        let _ = crate::discord_tidy::say(ctx, msg, strings::DEBUG_SERIES_SYNCED(series_id));
+        Ok(())
+    }
+
+    pub fn debug_keys(
+        ctx: &Context,
+        msg: &Message,
+        pattern: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<(), BoxedError> {
+        let mut redis_connection = redis_client.get_connection()?;
+        let mut keys: Vec<String> = redis_connection.keys(pattern)?;
+        if keys.is_empty() {
+            let _ = crate::discord_tidy::say(ctx, msg, strings::DEBUG_NO_KEYS(pattern));
+            return Ok(());
+        }
+        keys.sort_unstable();
+        let lines: Vec<_> = keys.iter().map(|key| format!("`{}`", key)).collect();
+        crate::discord_pagination::send_paginated(
+            ctx,
+            msg.channel_id,
+            &strings::DEBUG_KEYS_TITLE(pattern, keys.len()),
+            &lines,
+            &mut redis_connection,
+        )?;
+        Ok(())
+    }
+
+    pub fn send_welcome_message(ctx: &Context, user: &User) {
+        let _ = user.direct_message(ctx, |message_builder| {
+            message_builder
+                .content(crate::strings::WELCOME_MESSAGE_PART1)
+                .embed(|embed_builder| {
+                    embed_builder
+                        .colour(serenity::utils::Colour::new(0xFF1744))
+                        .title(crate::strings::WELCOME_MESSAGE_PART2_EMBED_TITLE)
+                        .description(crate::strings::WELCOME_MESSAGE_PART2_EMBED_CONTENT)
+                })
+        });
+    }
+}
+
+// Why a Discord user can't be linked to a Meetup account
+pub enum MeetupLinkConflict {
+    // The Discord user is already linked, possibly to this very Meetup account
+    DiscordUserLinked { meetup_id: u64 },
+    // The Meetup account is already linked to a different Discord user
+    MeetupUserLinked { discord_id: u64 },
+}
+
+pub struct ChannelRoles {
+    pub user: u64,
+    pub host: u64,
+}
+
+// How the players and hosts of a bot controlled channel get access to it
+pub enum ChannelAccess {
+    Roles(ChannelRoles),
+    // See discord_channel_access
+    Overwrites,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOT_ID: u64 = 1234;
+
+    fn regexes() -> Regexes {
+        compile_regexes(BOT_ID)
+    }
+
+    fn assert_matches(regex: &Regex, messages: &[&str]) {
+        for message in messages {
+            assert!(
+                regex.is_match(message),
+                "{:?} should match {}",
+                message,
+                regex.as_str()
+            );
+        }
+    }
+
+    fn assert_no_match(regex: &Regex, messages: &[&str]) {
+        for message in messages {
+            assert!(
+                !regex.is_match(message),
+                "{:?} should not match {}",
+                message,
+                regex.as_str()
+            );
+        }
+    }
+
+    fn capture<'t>(regex: &Regex, message: &'t str, name: &str) -> Option<&'t str> {
+        regex
+            .captures(message)
+            .and_then(|captures| captures.name(name))
+            .map(|capture| capture.as_str())
+    }
+
+    // Remembers the commands it gets and answers each with 1
+    #[derive(Default)]
+    struct RecordingConnection {
+        commands: Vec<Vec<u8>>,
+    }
+
+    impl redis::ConnectionLike for RecordingConnection {
+        fn req_packed_command(&mut self, cmd: &[u8]) -> redis::RedisResult<redis::Value> {
+            self.commands.push(cmd.to_vec());
+            Ok(redis::Value::Int(1))
+        }
+
+        fn req_packed_commands(
+            &mut self,
+            cmd: &[u8],
+            _offset: usize,
+            count: usize,
+        ) -> redis::RedisResult<Vec<redis::Value>> {
+            self.commands.push(cmd.to_vec());
+            Ok(vec![redis::Value::Int(1); count])
+        }
+
+        fn get_db(&self) -> i64 {
+            0
+        }
+    }
+
+    #[test]
+    fn bot_mention() {
+        let regexes = regexes();
+        assert_eq!(regexes.bot_mention, "<@1234>");
+        // Only mentions of this bot, with a space before the command
+        assert_no_match(
+            &regexes.party_mention,
+            &["<@5678> party", "<@1234>party", "party", "<@&1234> party"],
+        );
+        // Nickname mentions, extra whitespace and mixed case
+        assert_matches(
+            &regexes.party_mention,
+            &[
+                "<@1234> party",
+                "<@!1234> party",
+                "<@1234>   party  ",
+                "<@1234>\tparty\n",
+                "<@1234> PaRtY",
+            ],
+        );
+    }
+
+    #[test]
+    fn user_arguments() {
+        let regexes = regexes();
+        // Every command that takes a user accepts the nickname form
+        let commands = [
+            (&regexes.add_host_mention, "<@1234> add host {}"),
+            (
+                &regexes.add_guest_mention,
+                "<@1234> add guest {} for 3 days",
+            ),
+            (&regexes.remove_host_mention, "<@1234> remove host {}"),
+            (&regexes.add_cohost_mention, "<@1234> add cohost {}"),
+            (&regexes.remove_cohost_mention, "<@1234> remove cohost {}"),
+            (
+                &regexes.quarantine_organizer_mention,
+                "<@1234> quarantine {}",
+            ),
+            (&regexes.release_organizer_mention, "<@1234> release {}"),
+            (&regexes.ignore_user_organizer_mention, "<@1234> ignore {}"),
+            (
+                &regexes.ignore_user_organizer_mention,
+                "<@1234> unignore {}",
+            ),
+            (&regexes.share_meetup_mention, "<@1234> share meetup {}"),
+            (&regexes.unshare_meetup_mention, "<@1234> unshare meetup {}"),
+            (
+                &regexes.approve_household_organizer_mention,
+                "<@1234> approve household {}",
+            ),
+            (
+                &regexes.reject_household_organizer_mention,
+                "<@1234> reject household {}",
+            ),
+            (&regexes.whois_organizer_mention, "<@1234> whois {}"),
+            (
+                &regexes.user_channels_organizer_mention,
+                "<@1234> channels {}",
+            ),
+            (
+                &regexes.debug_welcome_organizer_mention,
+                "<@1234> debug welcome {}",
+            ),
+            (
+                &regexes.link_meetup_organizer_mention,
+                "<@1234> link meetup {} 42",
+            ),
+            (&regexes.link_meetup_organizer_dm, "link meetup {} 42"),
+            (
+                &regexes.unlink_meetup_organizer_mention,
+                "<@1234> unlink meetup {}",
+            ),
+            (&regexes.unlink_meetup_organizer_dm, "unlink meetup {}"),
+        ];
+        for (regex, template) in commands.iter() {
+            for mention in &["<@5678>", "<@!5678>"] {
+                let message = template.replace("{}", mention);
+                assert_eq!(
+                    capture(regex, &message, "mention_id"),
+                    Some("5678"),
+                    "{:?} should match {}",
+                    message,
+                    regex.as_str()
+                );
+            }
+            // Users who can't be mentioned can be given by ID or tag
+            let message = template.replace("{}", "123456789012345678");
+            assert_eq!(
+                capture(regex, &message, "user_id"),
+                Some("123456789012345678")
+            );
+            let message = template.replace("{}", "Tordek#0042");
+            assert_eq!(capture(regex, &message, "user_tag"), Some("Tordek#0042"));
+            // Role mentions, channel mentions, numbers that are too short to
+            // be IDs and incomplete tags are not users
+            for not_a_user in &[
+                "<@&5678>",
+                "<#5678>",
+                "5678",
+                "<@5678",
+                "Tordek",
+                "Tordek#42",
+                "@Tordek#0042",
+            ] {
+                assert_no_match(regex, &[&template.replace("{}", not_a_user)]);
+            }
+        }
+    }
+
+    #[test]
+    fn user_lists() {
+        let regexes = regexes();
+        for (regex, command) in &[
+            (&regexes.add_user_mention, "add"),
+            (&regexes.remove_user_mention, "remove"),
+        ] {
+            let message = format!(
+                "<@1234> {}  <@5678> <@!9012>\t123456789012345678 Tordek#0042 ",
+                command
+            );
+            let users = capture(regex, &message, "users").unwrap();
+            let users: Vec<_> = regexes
+                .user_argument
+                .captures_iter(users)
+                .map(|captures| captures[0].to_string())
+                .collect();
+            assert_eq!(
+                users,
+                ["<@5678>", "<@!9012>", "123456789012345678", "Tordek#0042"]
+            );
+            assert_eq!(
+                capture(regex, &format!("<@1234> {} <@!5678>", command), "users"),
+                Some("<@!5678>")
+            );
+            for not_a_list in &["<@5678>, <@9012>", "<@5678> and <@9012>", "<@5678> <@&42>"] {
+                assert_no_match(regex, &[&format!("<@1234> {} {}", command, not_a_list)]);
+            }
+        }
+    }
+
+    #[test]
+    fn linking() {
+        let regexes = regexes();
+        assert_matches(
+            &regexes.link_meetup_dm,
+            &["link meetup", "link-meetup", "linkmeetup", "link meetup  "],
+        );
+        assert_no_match(
+            &regexes.link_meetup_dm,
+            &[
+                "link meetup now",
+                "unlink meetup",
+                "link  meetup",
+                "<@1234> link meetup",
+            ],
+        );
+        assert_matches(
+            &regexes.link_meetup_mention,
+            &["<@1234> link meetup", "<@!1234>   link-meetup "],
+        );
+        assert_no_match(
+            &regexes.link_meetup_mention,
+            &["<@5678> link meetup", "<@1234>link meetup", "link meetup"],
+        );
+        let message = "<@1234> link meetup <@!5678>  42 ";
+        assert_eq!(
+            capture(&regexes.link_meetup_organizer_mention, message, "meetupid"),
+            Some("42")
+        );
+        assert_no_match(
+            &regexes.link_meetup_organizer_mention,
+            &[
+                "<@1234> link meetup <@5678>",
+                "<@1234> link meetup 42 <@5678>",
+                "<@1234> link meetup <@5678> abc",
+            ],
+        );
+        assert_no_match(&regexes.link_meetup_organizer_dm, &["link meetup <@5678>"]);
+        assert_matches(
+            &regexes.unlink_meetup_dm,
+            &["unlink meetup", "unlink-meetup  "],
+        );
+        assert_no_match(
+            &regexes.unlink_meetup_dm,
+            &["unlink meetup <@5678>", "link meetup"],
+        );
+        assert_matches(
+            &regexes.unlink_meetup_mention,
+            &["<@1234> unlink meetup", "<@!1234> unlinkmeetup"],
+        );
+        assert_no_match(
+            &regexes.unlink_meetup_mention,
+            &["<@1234> unlink meetup <@5678>", "<@1234> link meetup"],
+        );
+        assert_no_match(&regexes.unlink_meetup_organizer_dm, &["unlink meetup"]);
+        assert_no_match(
+            &regexes.unlink_meetup_organizer_mention,
+            &["<@1234> unlink meetup"],
+        );
+        assert_matches(
+            &regexes.linking_help_dm,
+            &["linking help", "Linking  HELP "],
+        );
+        assert_no_match(&regexes.linking_help_dm, &["linking", "help"]);
+        assert_matches(
+            &regexes.linking_help_mention,
+            &["<@1234> linking help", "<@!1234> Linking Help"],
+        );
+        assert_no_match(&regexes.linking_help_mention, &["<@1234> linking"]);
+        assert_matches(
+            &regexes.confirm_link_organizer_mention,
+            &["<@1234> confirm link 42", "<@1234> Confirm Link  42 "],
+        );
+        assert_no_match(
+            &regexes.confirm_link_organizer_mention,
+            &["<@1234> confirm link abc", "<@1234> confirm link"],
+        );
+        assert_matches(
+            &regexes.dismiss_link_organizer_mention,
+            &["<@1234> dismiss link 42", "<@!1234> DISMISS link 42"],
+        );
+        assert_no_match(
+            &regexes.dismiss_link_organizer_mention,
+            &["<@1234> dismiss link", "<@1234> dismiss links 42"],
+        );
+        assert_matches(
+            &regexes.suggest_links_organizer_mention,
+            &["<@1234> suggest links", "<@1234> Suggest Links "],
+        );
+        assert_no_match(
+            &regexes.suggest_links_organizer_mention,
+            &["<@1234> suggest link", "<@1234> suggest links now"],
+        );
+        assert_matches(
+            &regexes.share_meetup_mention,
+            &[
+                "<@1234> share meetup with <@5678>",
+                "<@1234> Share Meetup <@5678>",
+            ],
+        );
+        assert_no_match(
+            &regexes.share_meetup_mention,
+            &["<@1234> share meetup", "<@1234> unshare meetup <@5678>"],
+        );
+        assert_matches(
+            &regexes.unshare_meetup_mention,
+            &[
+                "<@1234> unshare meetup with <@5678>",
+                "<@1234> UNSHARE meetup <@5678>",
+            ],
+        );
+        assert_no_match(
+            &regexes.unshare_meetup_mention,
+            &["<@1234> unshare meetup", "<@1234> share meetup <@5678>"],
+        );
+        assert_matches(
+            &regexes.approve_household_organizer_mention,
+            &["<@1234> approve household <@5678>"],
+        );
+        assert_no_match(
+            &regexes.approve_household_organizer_mention,
+            &[
+                "<@1234> approve household",
+                "<@1234> approve households <@5678>",
+            ],
+        );
+        assert_matches(
+            &regexes.reject_household_organizer_mention,
+            &["<@1234> Reject Household <@5678>"],
+        );
+        assert_no_match(
+            &regexes.reject_household_organizer_mention,
+            &["<@1234> reject household"],
+        );
+        assert_matches(
+            &regexes.list_household_requests_organizer_mention,
+            &["<@1234> household requests", "<@1234> Household  Requests"],
+        );
+        assert_no_match(
+            &regexes.list_household_requests_organizer_mention,
+            &[
+                "<@1234> household request",
+                "<@1234> household requests all",
+            ],
+        );
+        assert_matches(
+            &regexes.whois_organizer_mention,
+            &["<@1234> whois <@5678>", "<@1234> WhoIs <@!5678> "],
+        );
+        assert_no_match(&regexes.whois_organizer_mention, &["<@1234> whois"]);
+        assert_matches(
+            &regexes.user_channels_organizer_mention,
+            &["<@1234> channels <@5678>", "<@1234> Channels of <@!5678> "],
+        );
+        assert_no_match(
+            &regexes.user_channels_organizer_mention,
+            &["<@1234> channels", "<@1234> channel <@5678>"],
+        );
+    }
+
+    #[test]
+    fn identities() {
+        let regexes = regexes();
+        assert_eq!(
+            capture(
+                &regexes.register_email_dm,
+                "Register E-Mail a@example.com ",
+                "email"
+            ),
+            Some("a@example.com")
+        );
+        assert_matches(
+            &regexes.register_email_dm,
+            &["register email a@example.com"],
+        );
+        assert_no_match(
+            &regexes.register_email_dm,
+            &[
+                "register email",
+                "register email a@example.com b@example.com",
+            ],
+        );
+        assert_matches(
+            &regexes.unregister_email_dm,
+            &["unregister email", "Unregister e-mail "],
+        );
+        assert_no_match(
+            &regexes.unregister_email_dm,
+            &["unregister email a@example.com", "register email"],
+        );
+        assert_matches(
+            &regexes.delete_my_data_dm,
+            &["delete my data", "Delete  my DATA "],
+        );
+        assert_no_match(
+            &regexes.delete_my_data_dm,
+            &["delete data", "delete my data now"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.unregister_email_organizer_dm,
+                "unregister email a@example.com",
+                "email"
+            ),
+            Some("a@example.com")
+        );
+        assert_no_match(
+            &regexes.unregister_email_organizer_dm,
+            &["unregister email"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.approve_email_organizer_dm,
+                "Approve E-Mail <@!1234> ",
+                "mention_id"
+            ),
+            Some("1234")
+        );
+        assert_matches(
+            &regexes.approve_email_organizer_dm,
+            &[
+                "approve email 123456789012345678",
+                "approve email user#1234",
+            ],
+        );
+        assert_no_match(
+            &regexes.approve_email_organizer_dm,
+            &["approve email", "approve email a@example.com"],
+        );
+        assert_eq!(
+            capture(&regexes.claim_ticket_dm, "Claim Ticket #12345", "order_id"),
+            Some("12345")
+        );
+        assert_matches(&regexes.claim_ticket_dm, &["claim ticket 12345 "]);
+        assert_no_match(
+            &regexes.claim_ticket_dm,
+            &["claim ticket", "claim ticket abc", "claim ticket ##12345"],
+        );
+        assert_matches(&regexes.list_identities_dm, &["identities", "Identities "]);
+        assert_no_match(&regexes.list_identities_dm, &["identity", "identities all"]);
+        assert_eq!(
+            capture(
+                &regexes.primary_identity_dm,
+                "primary identity meetup",
+                "identity"
+            ),
+            Some("meetup")
+        );
+        assert_no_match(
+            &regexes.primary_identity_dm,
+            &["primary identity", "primary identity meetup warhorn"],
+        );
+        assert_eq!(
+            capture(&regexes.nickname_sync_dm, "Nickname Sync OFF", "setting"),
+            Some("OFF")
+        );
+        assert_no_match(
+            &regexes.nickname_sync_dm,
+            &["nickname sync", "nickname sync maybe"],
+        );
+    }
+
+    #[test]
+    fn sync_and_config() {
+        let regexes = regexes();
+        assert_matches(
+            &regexes.sync_meetup_mention,
+            &["<@1234> sync meetup", "<@!1234>  sync   meetup "],
+        );
+        assert_no_match(
+            &regexes.sync_meetup_mention,
+            &["<@1234> sync", "<@1234> sync meetup now", "sync meetup"],
+        );
+        assert_matches(
+            &regexes.sync_discord_mention,
+            &["<@1234> sync discord", "<@!1234> sync  discord\n"],
+        );
+        assert_no_match(
+            &regexes.sync_discord_mention,
+            &["<@1234> sync discords", "<@1234> sync meetup"],
+        );
+        assert_matches(
+            &regexes.sync_stats_organizer_mention,
+            &["<@1234> sync stats"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.sync_stats_organizer_mention,
+                "<@1234> Sync Stats 10",
+                "num_runs"
+            ),
+            Some("10")
+        );
+        assert_no_match(
+            &regexes.sync_stats_organizer_mention,
+            &["<@1234> sync stats 1000", "<@1234> sync stats ten"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.preview_organizer_mention,
+                "<@1234> PREVIEW  abc123 ",
+                "series_id"
+            ),
+            Some("abc123")
+        );
+        assert_no_match(
+            &regexes.preview_organizer_mention,
+            &["<@1234> preview", "<@1234> preview abc 123"],
+        );
+        assert_matches(
+            &regexes.config_get_organizer_mention,
+            &["<@1234> config get"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.config_get_organizer_mention,
+                "<@1234> Config Get sync_interval_minutes",
+                "key"
+            ),
+            Some("sync_interval_minutes")
+        );
+        assert_no_match(
+            &regexes.config_get_organizer_mention,
+            &["<@1234> config get a b", "<@1234> config"],
+        );
+        let message = "<@1234> config  SET role_pruning always ";
+        assert_eq!(
+            capture(&regexes.config_set_organizer_mention, message, "key"),
+            Some("role_pruning")
+        );
+        assert_eq!(
+            capture(&regexes.config_set_organizer_mention, message, "value"),
+            Some("always")
+        );
+        assert_no_match(
+            &regexes.config_set_organizer_mention,
+            &[
+                "<@1234> config set role_pruning",
+                "<@1234> config set a b c",
+            ],
+        );
+        assert_matches(
+            &regexes.config_log_organizer_mention,
+            &["<@1234> config log", "<@1234> Config Log "],
+        );
+        assert_no_match(
+            &regexes.config_log_organizer_mention,
+            &["<@1234> config log 5"],
+        );
+        assert_matches(
+            &regexes.setup_owner_mention,
+            &["<@1234> setup", "<@!1234> Set up "],
+        );
+        let message = "<@1234> setup staff_channel <#5678>";
+        assert_eq!(
+            capture(&regexes.setup_step_owner_mention, message, "step"),
+            Some("staff_channel")
+        );
+        assert_eq!(
+            capture(&regexes.setup_step_owner_mention, message, "value"),
+            Some("<#5678>")
+        );
+        assert_no_match(
+            &regexes.setup_step_owner_mention,
+            &[
+                "<@1234> setup",
+                "<@1234> setup staff_channel",
+                "<@1234> setup a b c",
+            ],
+        );
+        assert_matches(
+            &regexes.maintenance_organizer_mention,
+            &["<@1234> maintenance on", "<@1234> Maintenance OFF"],
+        );
+        assert_no_match(
+            &regexes.maintenance_organizer_mention,
+            &["<@1234> maintenance", "<@1234> maintenance later"],
+        );
+        assert_matches(
+            &regexes.approve_creation_organizer_mention,
+            &["<@1234> approve creation", "<@1234> Approve  Creation"],
+        );
+        assert_no_match(
+            &regexes.approve_creation_organizer_mention,
+            &["<@1234> approve", "<@1234> approve creations"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.toggle_feature_organizer_mention,
+                "<@1234> Feature Disable voice_channels",
+                "feature"
+            ),
+            Some("voice_channels")
+        );
+        assert_matches(
+            &regexes.toggle_feature_organizer_mention,
+            &["<@1234> feature enable channel_approval"],
+        );
+        assert_no_match(
+            &regexes.toggle_feature_organizer_mention,
+            &[
+                "<@1234> feature enable",
+                "<@1234> feature toggle voice_channels",
+            ],
+        );
+        assert_matches(
+            &regexes.list_features_organizer_mention,
+            &["<@1234> features", "<@!1234> FEATURES "],
+        );
+        assert_no_match(
+            &regexes.list_features_organizer_mention,
+            &["<@1234> feature"],
+        );
+        assert_matches(
+            &regexes.host_verification_organizer_mention,
+            &[
+                "<@1234> host verification enforce",
+                "<@1234> Host Verification Off",
+            ],
+        );
+        assert_no_match(
+            &regexes.host_verification_organizer_mention,
+            &[
+                "<@1234> host verification",
+                "<@1234> host verification strict",
+            ],
+        );
+        assert_matches(
+            &regexes.stop_organizer_mention,
+            &["<@1234> stop", "<@!1234> STOP "],
+        );
+        assert_no_match(&regexes.stop_organizer_mention, &["<@1234> stop it"]);
+        assert_matches(&regexes.stop_organizer_dm, &["stop", "Stop "]);
+        assert_no_match(&regexes.stop_organizer_dm, &["stop it", "<@1234> stop"]);
+        assert_matches(
+            &regexes.send_expiration_reminder_organizer_mention,
+            &["<@1234> remind expiration", "<@1234> Remind  Expiration"],
+        );
+        assert_no_match(
+            &regexes.send_expiration_reminder_organizer_mention,
+            &["<@1234> remind", "<@1234> remind expirations"],
+        );
+    }
+
+    #[test]
+    fn scheduled_commands() {
+        let regexes = regexes();
+        let regex = &regexes.schedule_command_organizer_mention;
+        let message = "<@1234> schedule \"sync discord\" at 2030-01-01 18:00 ";
+        assert_eq!(capture(regex, message, "command"), Some("sync discord"));
+        assert_eq!(
+            capture(regex, message, "due_time"),
+            Some("2030-01-01 18:00")
+        );
+        // Phones turn the quotes into curly ones
+        let message = "<@1234> Schedule “sync discord” AT tomorrow 9:00";
+        assert_eq!(capture(regex, message, "command"), Some("sync discord"));
+        assert_eq!(capture(regex, message, "due_time"), Some("tomorrow 9:00"));
+        assert_no_match(
+            regex,
+            &[
+                "<@1234> schedule sync discord at 18:00",
+                "<@1234> schedule \"sync discord\"",
+                "<@1234> schedule \"\" at 18:00",
+            ],
+        );
+        assert_matches(
+            &regexes.list_scheduled_commands_organizer_mention,
+            &["<@1234> scheduled commands", "<@1234> Scheduled Commands "],
+        );
+        assert_no_match(
+            &regexes.list_scheduled_commands_organizer_mention,
+            &["<@1234> scheduled command"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.unschedule_command_organizer_mention,
+                "<@1234> unschedule abc_1-2",
+                "command_id"
+            ),
+            Some("abc_1-2")
+        );
+        assert_no_match(
+            &regexes.unschedule_command_organizer_mention,
+            &["<@1234> unschedule", "<@1234> unschedule a b"],
+        );
+    }
+
+    #[test]
+    fn channel_members() {
+        let regexes = regexes();
+        assert_matches(
+            &regexes.add_user_mention,
+            &["<@1234> add <@5678>", "<@!1234>   add   <@!5678>  "],
+        );
+        assert_no_match(
+            &regexes.add_user_mention,
+            &["<@1234> add host <@5678>", "<@1234> add"],
+        );
+        assert_matches(&regexes.add_host_mention, &["<@1234> add host <@5678>"]);
+        assert_no_match(
+            &regexes.add_host_mention,
+            &["<@1234> add <@5678>", "<@1234> add host"],
+        );
+        let regex = &regexes.add_guest_mention;
+        let message = "<@1234> Add Guest <@!5678> FOR 2 Weeks";
+        assert_eq!(capture(regex, message, "amount"), Some("2"));
+        assert_eq!(capture(regex, message, "unit"), Some("Weeks"));
+        assert_matches(
+            regex,
+            &[
+                "<@1234> add guest <@5678> for 1 hour",
+                "<@1234> add guest <@5678> for 3 days ",
+            ],
+        );
+        assert_no_match(
+            regex,
+            &[
+                "<@1234> add guest <@5678>",
+                "<@1234> add guest <@5678> for 3 months",
+                "<@1234> add guest <@5678> for a day",
+            ],
+        );
+        assert_matches(&regexes.remove_user_mention, &["<@1234> remove <@5678>"]);
+        assert_no_match(
+            &regexes.remove_user_mention,
+            &["<@1234> remove host <@5678>", "<@1234> remove"],
+        );
+        assert_matches(
+            &regexes.remove_replied_user_mention,
+            &["<@1234> remove", "<@1234> remove "],
+        );
+        assert_no_match(
+            &regexes.remove_replied_user_mention,
+            &["<@1234> remove <@5678>", "<@1234> remove host"],
+        );
+        assert_matches(
+            &regexes.remove_host_mention,
+            &["<@1234> remove host <@5678>"],
+        );
+        assert_no_match(&regexes.remove_host_mention, &["<@1234> remove <@5678>"]);
+        assert_matches(
+            &regexes.add_cohost_mention,
+            &["<@1234> add cohost <@5678>", "<@1234> add co-host <@5678>"],
+        );
+        assert_no_match(
+            &regexes.add_cohost_mention,
+            &["<@1234> add co host <@5678>", "<@1234> add host <@5678>"],
+        );
+        assert_matches(
+            &regexes.remove_cohost_mention,
+            &[
+                "<@1234> remove cohost <@5678>",
+                "<@1234> remove co-host <@5678>",
+            ],
+        );
+        assert_no_match(
+            &regexes.remove_cohost_mention,
+            &["<@1234> remove co host <@5678>"],
+        );
+        assert_matches(
+            &regexes.quarantine_organizer_mention,
+            &["<@1234> quarantine <@5678>", "<@1234> QUARANTINE <@!5678>"],
+        );
+        assert_no_match(
+            &regexes.quarantine_organizer_mention,
+            &["<@1234> quarantine"],
+        );
+        assert_matches(
+            &regexes.release_organizer_mention,
+            &["<@1234> release <@5678>", "<@1234> Release <@!5678>"],
+        );
+        assert_no_match(&regexes.release_organizer_mention, &["<@1234> release"]);
+        assert_eq!(
+            capture(
+                &regexes.ignore_user_organizer_mention,
+                "<@1234> UnIgnore <@5678>",
+                "toggle"
+            ),
+            Some("UnIgnore")
+        );
+        assert_no_match(
+            &regexes.ignore_user_organizer_mention,
+            &["<@1234> ignore", "<@1234> ignored users"],
+        );
+        assert_matches(
+            &regexes.list_ignored_users_organizer_mention,
+            &["<@1234> ignored users", "<@1234> Ignored  Users "],
+        );
+        assert_no_match(
+            &regexes.list_ignored_users_organizer_mention,
+            &["<@1234> ignored", "<@1234> ignored users <@5678>"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.invite_create_organizer_mention,
+                "<@1234> invite create  Flyers at the library ",
+                "label"
+            ),
+            Some("Flyers at the library")
+        );
+        assert_no_match(
+            &regexes.invite_create_organizer_mention,
+            &["<@1234> invite create", "<@1234> invite create "],
+        );
+        assert_matches(
+            &regexes.invite_stats_organizer_mention,
+            &["<@1234> invite stats", "<@1234> Invite  Stats "],
+        );
+        assert_no_match(
+            &regexes.invite_stats_organizer_mention,
+            &["<@1234> invite create stats now", "<@1234> invites"],
+        );
+        assert_matches(
+            &regexes.usage_report_organizer_mention,
+            &["<@1234> usage report", "<@1234> Usage  Report "],
+        );
+        assert_no_match(
+            &regexes.usage_report_organizer_mention,
+            &["<@1234> usage", "<@1234> usage report now"],
+        );
+        assert_matches(
+            &regexes.storage_report_organizer_mention,
+            &["<@1234> storage report", "<@1234> Storage Report "],
+        );
+        assert_no_match(
+            &regexes.storage_report_organizer_mention,
+            &["<@1234> storage", "<@1234> storage reports"],
+        );
+        assert_matches(
+            &regexes.list_duplicate_events_organizer_mention,
+            &[
+                "<@1234> list duplicate events",
+                "<@1234> List Duplicate  Events ",
+            ],
+        );
+        assert_eq!(
+            capture(
+                &regexes.allow_duplicate_organizer_mention,
+                "<@1234> Allow duplicate 267539421",
+                "event_id"
+            ),
+            Some("267539421")
+        );
+        assert_eq!(
+            capture(
+                &regexes.allow_duplicate_organizer_mention,
+                "<@1234> allow duplicate qpxzkrybcnbfb ",
+                "event_id"
+            ),
+            Some("qpxzkrybcnbfb")
+        );
+        assert_no_match(
+            &regexes.allow_duplicate_organizer_mention,
+            &["<@1234> allow duplicate", "<@1234> allow duplicate 123 456"],
+        );
+        assert_matches(
+            &regexes.debug_welcome_organizer_mention,
+            &["<@1234> debug welcome <@5678>"],
+        );
+        assert_no_match(
+            &regexes.debug_welcome_organizer_mention,
+            &["<@1234> debug welcome"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.inactive_members_organizer_mention,
+                "<@1234> Inactive Members 90",
+                "days"
+            ),
+            Some("90")
+        );
+        assert_matches(
+            &regexes.inactive_members_organizer_mention,
+            &["<@1234> inactive members"],
+        );
+        assert_no_match(
+            &regexes.inactive_members_organizer_mention,
+            &["<@1234> inactive members 12345", "<@1234> inactive"],
+        );
+        assert_matches(
+            &regexes.nudge_inactive_members_organizer_mention,
+            &[
+                "<@1234> nudge inactive members",
+                "<@1234> Nudge inactive members 30",
+            ],
+        );
+        assert_no_match(
+            &regexes.nudge_inactive_members_organizer_mention,
+            &[
+                "<@1234> nudge inactive members 12345",
+                "<@1234> inactive members",
+            ],
+        );
+    }
+
+    #[test]
+    fn channel_management() {
+        let regexes = regexes();
+        assert_eq!(
+            capture(&regexes.tables_host_mention, "<@1234> Tables 3 ", "count"),
+            Some("3")
+        );
+        assert_no_match(
+            &regexes.tables_host_mention,
+            &["<@1234> tables", "<@1234> tables three"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.session_channels_host_mention,
+                "<@1234> Session Channels OFF",
+                "toggle"
+            ),
+            Some("OFF")
+        );
+        assert_no_match(
+            &regexes.session_channels_host_mention,
+            &["<@1234> session channels", "<@1234> session channels maybe"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.sync_pause_host_mention,
+                "<@1234> Resume Sync",
+                "toggle"
+            ),
+            Some("Resume")
+        );
+        assert_matches(&regexes.sync_pause_host_mention, &["<@1234> pause sync"]);
+        assert_no_match(
+            &regexes.sync_pause_host_mention,
+            &["<@1234> pause", "<@1234> stop sync"],
+        );
+        let regex = &regexes.style_host_mention;
+        let message = "<@1234> Style Colour #aa33ff emoji 🐉";
+        assert_eq!(capture(regex, message, "colour"), Some("#aa33ff"));
+        assert_eq!(capture(regex, message, "emoji"), Some("🐉"));
+        assert_eq!(
+            capture(regex, "<@1234> style color none", "colour"),
+            Some("none")
+        );
+        assert_eq!(
+            capture(regex, "<@1234> style emoji 🐉", "emoji"),
+            Some("🐉")
+        );
+        assert_matches(regex, &["<@1234> style"]);
+        assert_no_match(
+            regex,
+            &[
+                "<@1234> style emoji 🐉 colour #aa33ff",
+                "<@1234> style colour",
+                "<@1234> style font serif",
+            ],
+        );
+        assert_matches(
+            &regexes.hide_history_host_mention,
+            &["<@1234> hide history 7", "<@1234> Hide History OFF"],
+        );
+        assert_no_match(
+            &regexes.hide_history_host_mention,
+            &["<@1234> hide history", "<@1234> hide history forever"],
+        );
+        assert_matches(
+            &regexes.channel_settings_host_mention,
+            &["<@1234> settings", "<@!1234> Settings "],
+        );
+        assert_no_match(
+            &regexes.channel_settings_host_mention,
+            &["<@1234> settings closing archive"],
+        );
+        let regex = &regexes.change_channel_setting_host_mention;
+        let message = "<@1234> Settings reaction_signup off ";
+        assert_eq!(capture(regex, message, "setting"), Some("reaction_signup"));
+        assert_eq!(capture(regex, message, "value"), Some("off"));
+        assert_no_match(regex, &["<@1234> settings", "<@1234> settings closing"]);
+        assert_matches(
+            &regexes.export_members_host_mention,
+            &["<@1234> export members", "<@1234> Export Members "],
+        );
+        assert_no_match(
+            &regexes.export_members_host_mention,
+            &["<@1234> export", "<@1234> export members csv"],
+        );
+        assert_matches(
+            &regexes.close_channel_host_mention,
+            &["<@1234> close channel", "<@1234> Close  Channel"],
+        );
+        assert_no_match(
+            &regexes.close_channel_host_mention,
+            &["<@1234> close", "<@1234> close channel now"],
+        );
+        // Recaps can span several lines
+        assert_eq!(
+            capture(
+                &regexes.recap_host_mention,
+                "<@1234> Recap The party met\nthe dragon\n",
+                "text"
+            ),
+            Some("The party met\nthe dragon")
+        );
+        assert_no_match(
+            &regexes.recap_host_mention,
+            &["<@1234> recap", "<@1234> recaps"],
+        );
+        assert_matches(
+            &regexes.list_recaps_mention,
+            &["<@1234> recaps", "<@1234> RECAPS "],
+        );
+        assert_no_match(&regexes.list_recaps_mention, &["<@1234> recap"]);
+        assert_matches(
+            &regexes.recap_reply_host_mention,
+            &["<@1234> recap", "<@1234> Recap "],
+        );
+        assert_no_match(
+            &regexes.recap_reply_host_mention,
+            &["<@1234> recaps", "<@1234> recap The party met the dragon"],
+        );
+        assert_eq!(
+            capture(&regexes.host_digest_dm, "Digest OFF", "toggle"),
+            Some("OFF")
+        );
+        assert_no_match(&regexes.host_digest_dm, &["digest", "digest maybe"]);
+        assert_matches(
+            &regexes.host_digest_mention,
+            &["<@1234> digest on", "<@!1234> Digest Off"],
+        );
+        assert_no_match(&regexes.host_digest_mention, &["<@1234> digest"]);
+        assert_matches(
+            &regexes.cleanup_roles_organizer_mention,
+            &["<@1234> cleanup roles", "<@1234> Clean Up Roles"],
+        );
+        assert_no_match(
+            &regexes.cleanup_roles_organizer_mention,
+            &["<@1234> clean roles", "<@1234> cleanup"],
+        );
+        assert_matches(
+            &regexes.role_usage_organizer_mention,
+            &["<@1234> role usage", "<@1234> Role Limit"],
+        );
+        assert_no_match(
+            &regexes.role_usage_organizer_mention,
+            &["<@1234> roles", "<@1234> role"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.dues_role_organizer_mention,
+                "<@1234> dues role <@&42> OFF",
+                "off"
+            ),
+            Some("OFF")
+        );
+        assert_matches(
+            &regexes.dues_role_organizer_mention,
+            &["<@1234> dues role <@&42>"],
+        );
+        assert_no_match(
+            &regexes.dues_role_organizer_mention,
+            &["<@1234> dues role off", "<@1234> dues role <@42>"],
+        );
+    }
+
+    #[test]
+    fn players() {
+        let regexes = regexes();
+        assert_matches(
+            &regexes.absent_mention,
+            &["<@1234> absent", "<@1234> Absent "],
+        );
+        assert_eq!(
+            capture(
+                &regexes.absent_mention,
+                "<@1234> absent sick, sorry ",
+                "reason"
+            ),
+            Some("sick, sorry")
+        );
+        assert_no_match(&regexes.absent_mention, &["<@1234> absentee"]);
+        let regex = &regexes.set_character_mention;
+        let message = "<@1234> Character Set https://example.com/sheet/1 Tordek the Bold";
+        assert_eq!(
+            capture(regex, message, "url"),
+            Some("https://example.com/sheet/1")
+        );
+        assert_eq!(capture(regex, message, "name"), Some("Tordek the Bold"));
+        assert_no_match(
+            regex,
+            &["<@1234> character set https://example.com/sheet/1"],
+        );
+        assert_matches(
+            &regexes.remove_character_mention,
+            &["<@1234> character remove", "<@1234> Character  Remove "],
+        );
+        assert_no_match(
+            &regexes.remove_character_mention,
+            &["<@1234> character remove Tordek"],
+        );
+        assert_no_match(&regexes.party_mention, &["<@1234> party time"]);
+        // Safety info can span several lines
+        let regex = &regexes.set_safety_host_mention;
+        let message = "<@1234> Safety Set Tools X-card\nopen door";
+        assert_eq!(capture(regex, message, "field"), Some("Tools"));
+        assert_eq!(capture(regex, message, "value"), Some("X-card\nopen door"));
+        assert_matches(regex, &["<@1234> safety set lines spiders, gore"]);
+        assert_no_match(
+            regex,
+            &["<@1234> safety set colours red", "<@1234> safety set lines"],
+        );
+        assert_matches(
+            &regexes.show_safety_mention,
+            &["<@1234> safety", "<@1234> SAFETY "],
+        );
+        assert_no_match(
+            &regexes.show_safety_mention,
+            &["<@1234> safety set lines spiders"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.subscribe_mention,
+                "<@1234> Subscribe One-Shot",
+                "tag"
+            ),
+            Some("One-Shot")
+        );
+        assert_no_match(
+            &regexes.subscribe_mention,
+            &[
+                "<@1234> subscribe",
+                "<@1234> subscribe dnd 5e",
+                "<@1234> subscribe d&d",
+                "<@1234> unsubscribe dnd5e",
+            ],
+        );
+        assert_matches(&regexes.unsubscribe_mention, &["<@1234> unsubscribe dnd5e"]);
+        assert_no_match(
+            &regexes.unsubscribe_mention,
+            &["<@1234> unsubscribe", "<@1234> subscribe dnd5e"],
+        );
+        assert_matches(
+            &regexes.list_subscriptions_mention,
+            &["<@1234> subscriptions", "<@1234> Subscriptions "],
+        );
+        assert_no_match(
+            &regexes.list_subscriptions_mention,
+            &["<@1234> subscription"],
+        );
+    }
+
+    #[test]
+    fn rolls() {
+        let regexes = regexes();
+        assert_eq!(
+            capture(&regexes.roll_dm, "roll 2d6 + 3", "expression"),
+            Some("2d6 + 3")
+        );
+        assert_eq!(
+            capture(&regexes.roll_dm, "Roll 1d20 adv", "mode"),
+            Some("adv")
+        );
+        assert_eq!(
+            capture(&regexes.roll_dm, "roll 1d20 dis", "expression"),
+            Some("1d20")
+        );
+        assert_no_match(&regexes.roll_dm, &["roll", "roll abc", "<@1234> roll 1d20"]);
+        assert_eq!(
+            capture(
+                &regexes.roll_mention,
+                "<@!1234> ROLL 1d20 disadvantage",
+                "mode"
+            ),
+            Some("disadvantage")
+        );
+        assert_no_match(
+            &regexes.roll_mention,
+            &["<@1234> roll log", "<@1234> roll", "roll 1d20"],
+        );
+        assert_matches(
+            &regexes.gm_roll_mention,
+            &["<@1234> gmroll 1d20", "<@1234> GMRoll 2d6+1 adv"],
+        );
+        assert_no_match(&regexes.gm_roll_mention, &["<@1234> roll 1d20"]);
+        assert_matches(
+            &regexes.roll_log_mention,
+            &["<@1234> roll log", "<@1234> Roll  Log "],
+        );
+        assert_no_match(
+            &regexes.roll_log_mention,
+            &["<@1234> roll log 5", "<@1234> roll 1d20"],
+        );
+    }
+
+    #[test]
+    fn macros_and_reports() {
+        let regexes = regexes();
+        let regex = &regexes.add_macro_host_mention;
+        let message = "<@1234> Macro Add loot Roll on the table\nthen split ";
+        assert_eq!(capture(regex, message, "name"), Some("loot"));
+        assert_eq!(
+            capture(regex, message, "text"),
+            Some("Roll on the table\nthen split")
+        );
+        assert_no_match(regex, &["<@1234> macro add loot", "<@1234> macro add"]);
+        assert_eq!(
+            capture(
+                &regexes.remove_macro_host_mention,
+                "<@1234> macro remove loot",
+                "name"
+            ),
+            Some("loot")
+        );
+        assert_no_match(
+            &regexes.remove_macro_host_mention,
+            &["<@1234> macro remove", "<@1234> macro remove loot now"],
+        );
+        assert_matches(
+            &regexes.list_macros_mention,
+            &["<@1234> macros", "<@1234> Macros "],
+        );
+        assert_no_match(&regexes.list_macros_mention, &["<@1234> macro"]);
+        // Reports can span several lines
+        let regex = &regexes.report_dm;
+        assert_eq!(
+            capture(regex, "report Somebody was rude", "text"),
+            Some("Somebody was rude")
+        );
+        let message = "Report anonymously Something\nhappened";
+        assert!(capture(regex, message, "anonymous").is_some());
+        assert_eq!(capture(regex, message, "text"), Some("Something\nhappened"));
+        assert!(capture(regex, "report it", "anonymous").is_none());
+        assert_no_match(regex, &["report", "reports are nice", "<@1234> report it"]);
+    }
+
+    #[test]
+    fn community_settings() {
+        let regexes = regexes();
+        assert_matches(
+            &regexes.list_flagged_links_organizer_mention,
+            &["<@1234> list flagged links", "<@1234> List Flagged Links "],
+        );
+        assert_no_match(
+            &regexes.list_flagged_links_organizer_mention,
+            &["<@1234> flagged links"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.block_link_organizer_mention,
+                "<@1234> Unblock Links *.example.com",
+                "pattern"
+            ),
+            Some("*.example.com")
+        );
+        assert_matches(
+            &regexes.block_link_organizer_mention,
+            &["<@1234> block link example.com"],
+        );
+        assert_no_match(
+            &regexes.block_link_organizer_mention,
+            &["<@1234> block link", "<@1234> block link a b"],
+        );
+        assert_matches(
+            &regexes.list_blocked_links_organizer_mention,
+            &["<@1234> blocked links", "<@1234> Blocked  Links"],
+        );
+        assert_no_match(
+            &regexes.list_blocked_links_organizer_mention,
+            &["<@1234> blocked link"],
+        );
+        let regex = &regexes.quick_command_organizer_mention;
+        let message = "<@1234> Quick Command JOIN 🙋";
+        assert_eq!(capture(regex, message, "command"), Some("JOIN"));
+        assert_eq!(capture(regex, message, "emoji"), Some("🙋"));
+        assert_no_match(
+            regex,
+            &[
+                "<@1234> quick command leave 🙋",
+                "<@1234> quick command join",
+            ],
+        );
+        assert_eq!(
+            capture(
+                &regexes.subscription_role_organizer_mention,
+                "<@1234> subscription role dnd5e <@&42>",
+                "role_id"
+            ),
+            Some("42")
+        );
+        assert_matches(
+            &regexes.subscription_role_organizer_mention,
+            &["<@1234> Subscription Role dnd5e OFF"],
+        );
+        assert_no_match(
+            &regexes.subscription_role_organizer_mention,
+            &[
+                "<@1234> subscription role dnd5e <@42>",
+                "<@1234> subscription role dnd5e",
+            ],
+        );
+        assert_matches(
+            &regexes.list_tag_rules_organizer_mention,
+            &["<@1234> tag rules", "<@1234> Tag Rules "],
+        );
+        assert_no_match(
+            &regexes.list_tag_rules_organizer_mention,
+            &["<@1234> tag rule"],
+        );
+        let regex = &regexes.tag_rule_organizer_mention;
+        let message = "<@1234> Tag Rule horror cthulhu, call of cthulhu ";
+        assert_eq!(capture(regex, message, "tag"), Some("horror"));
+        assert_eq!(
+            capture(regex, message, "keywords"),
+            Some("cthulhu, call of cthulhu")
+        );
+        assert_no_match(regex, &["<@1234> tag rule horror", "<@1234> tag rules"]);
+        assert_eq!(
+            capture(
+                &regexes.community_digest_channel_organizer_mention,
+                "<@1234> community digest channel <#42>",
+                "channel_id"
+            ),
+            Some("42")
+        );
+        assert_matches(
+            &regexes.community_digest_channel_organizer_mention,
+            &["<@1234> Community Digest Channel Off"],
+        );
+        assert_no_match(
+            &regexes.community_digest_channel_organizer_mention,
+            &["<@1234> community digest channel general"],
+        );
+        let regex = &regexes.community_digest_schedule_organizer_mention;
+        let message = "<@1234> Community Digest Schedule Friday 9:00";
+        assert_eq!(capture(regex, message, "weekday"), Some("Friday"));
+        assert_eq!(capture(regex, message, "hour"), Some("9"));
+        assert_matches(regex, &["<@1234> community digest schedule monday 18"]);
+        assert_no_match(
+            regex,
+            &[
+                "<@1234> community digest schedule monday 18:30",
+                "<@1234> community digest schedule monday",
+            ],
+        );
+        assert_eq!(
+            capture(
+                &regexes.community_digest_template_organizer_mention,
+                "<@1234> community digest template This week:\n{events}",
+                "template"
+            ),
+            Some("This week:\n{events}")
+        );
+        assert_no_match(
+            &regexes.community_digest_template_organizer_mention,
+            &["<@1234> community digest template"],
+        );
+        // Discord users might wrap the URL in <> to suppress the preview
+        let regex = &regexes.crosspost_webhook_organizer_mention;
+        for message in &[
+            "<@1234> crosspost webhook https://example.com/hook",
+            "<@1234> Crosspost Webhook <https://example.com/hook> ",
+        ] {
+            assert_eq!(
+                capture(regex, message, "url"),
+                Some("https://example.com/hook")
+            );
+        }
+        assert_matches(regex, &["<@1234> crosspost webhook off"]);
+        assert_no_match(
+            regex,
+            &[
+                "<@1234> crosspost webhook http://example.com/hook",
+                "<@1234> crosspost webhook",
+            ],
+        );
+        assert_eq!(
+            capture(
+                &regexes.crosspost_template_organizer_mention,
+                "<@1234> crosspost template New: {title}",
+                "template"
+            ),
+            Some("New: {title}")
+        );
+        assert_no_match(
+            &regexes.crosspost_template_organizer_mention,
+            &["<@1234> crosspost template"],
+        );
+    }
+
+    #[test]
+    fn debugging() {
+        let regexes = regexes();
+        assert_matches(
+            &regexes.check_permissions_organizer_mention,
+            &["<@1234> check permissions", "<@1234> Check  Permissions "],
+        );
+        assert_no_match(
+            &regexes.check_permissions_organizer_mention,
+            &["<@1234> check permission"],
+        );
+        assert_matches(
+            &regexes.list_organizer_tokens_organizer_mention,
+            &["<@1234> organizer tokens", "<@1234> Organizer Tokens"],
+        );
+        assert_no_match(
+            &regexes.list_organizer_tokens_organizer_mention,
+            &["<@1234> organizer token"],
+        );
+        assert_eq!(
+            capture(
+                &regexes.revoke_organizer_token_organizer_mention,
+                "<@1234> Revoke Organizer Token 42",
+                "meetupid"
+            ),
+            Some("42")
+        );
+        assert_no_match(
+            &regexes.revoke_organizer_token_organizer_mention,
+            &[
+                "<@1234> revoke organizer token",
+                "<@1234> revoke organizer token abc",
+            ],
+        );
+        assert_eq!(
+            capture(
+                &regexes.debug_sync_series_organizer_mention,
+                "<@1234> debug sync-series abc123",
+                "series_id"
+            ),
+            Some("abc123")
+        );
+        assert_no_match(
+            &regexes.debug_sync_series_organizer_mention,
+            &[
+                "<@1234> debug sync series abc123",
+                "<@1234> debug sync-series",
+            ],
+        );
+        assert_eq!(
+            capture(
+                &regexes.debug_keys_organizer_mention,
+                "<@1234> Debug Keys discord_channel:*",
+                "pattern"
+            ),
+            Some("discord_channel:*")
+        );
+        assert_no_match(
+            &regexes.debug_keys_organizer_mention,
+            &["<@1234> debug keys", "<@1234> debug keys a b"],
+        );
+    }
+
+    #[test]
+    fn mutating_commands() {
+        let regexes = regexes();
+        assert!(regexes.is_mutating_command(false, "<@1234> add <@5678>"));
+        assert!(regexes.is_mutating_command(false, "<@!1234> add <@!5678>"));
+        assert!(regexes.is_mutating_command(false, "<@1234> config set role_pruning off"));
+        assert!(regexes.is_mutating_command(true, "link meetup"));
+        assert!(!regexes.is_mutating_command(false, "<@1234> party"));
+        assert!(!regexes.is_mutating_command(false, "<@1234> config get"));
+        assert!(!regexes.is_mutating_command(true, "identities"));
+    }
+
+    #[test]
+    fn command_classes() {
+        use crate::discord_tidy::CommandClass;
+        let regexes = regexes();
+        let class = |message: &str| regexes.command_class(message);
+        assert_eq!(class("<@1234> roll 2d6"), Some(CommandClass::Player));
+        assert_eq!(class("<@!1234> party"), Some(CommandClass::Player));
+        assert_eq!(class("<@1234> add <@5678>"), Some(CommandClass::Host));
+        assert_eq!(
+            class("<@1234> settings tidy player,host"),
+            Some(CommandClass::Host)
+        );
+        assert_eq!(
+            class("<@1234> whois <@5678>"),
+            Some(CommandClass::Organizer)
+        );
+        assert_eq!(class("<@1234> sync meetup"), Some(CommandClass::Organizer));
+        assert_eq!(class("<@1234> recap The party found the map"), None);
+        assert_eq!(class("<@1234> recap"), Some(CommandClass::Host));
+        assert_eq!(class("<@1234> hello"), None);
+        assert_eq!(class("roll 2d6"), None);
+    }
+
+    #[test]
+    fn removed_users_stop_being_cohosts() {
+        let mut con = RecordingConnection::default();
+        assert!(forget_cohost(ChannelId(5), 7, &mut con).unwrap());
+        assert_eq!(
+            con.commands,
+            vec![redis::cmd("SREM")
+                .arg("discord_channel:5:cohosts")
+                .arg(7)
+                .get_packed_command()]
+        );
+    }
 }
@@ -0,0 +1,129 @@
+use lazy_static::lazy_static;
+use std::cell::Cell;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+// Serenity makes its Discord API calls on the calling thread, and the
+// background tasks (most of all the Discord sync) share the rate limits with
+// the commands. When the sync is busy, commands have to wait for the rate
+// limits that it used up. Commands therefore take precedence: while a command
+// is being handled, the calls of the background tasks (everything that goes
+// through CacheAndHttp::http) queue up until no command is in flight anymore.
+// A background call waits at most MAX_BACKGROUND_WAIT_MS, so that a steady
+// stream of commands can't stall the sync. Calls that a command makes through
+// CacheAndHttp never wait, since the command would be waiting for itself.
+// The depth of the queue and the waiting times are recorded with each sync
+// run (see sync_stats).
+const MAX_BACKGROUND_WAIT_MS: u64 = 5000;
+
+struct QueueState {
+    num_commands_in_flight: usize,
+    num_waiting: usize,
+    metrics: QueueMetrics,
+}
+
+lazy_static! {
+    static ref QUEUE: (Mutex<QueueState>, Condvar) = (
+        Mutex::new(QueueState {
+            num_commands_in_flight: 0,
+            num_waiting: 0,
+            metrics: QueueMetrics::default(),
+        }),
+        Condvar::new()
+    );
+}
+
+thread_local! {
+    // How many commands this thread is handling (aliases handle the resolved
+    // command within the original one)
+    static THREAD_COMMANDS: Cell<usize> = Cell::new(0);
+}
+
+// Cumulative since the start of the process, apart from the peaks
+#[derive(Debug, Default, Clone, Copy)]
+pub struct QueueMetrics {
+    // Background calls that had to wait for a command
+    pub num_waited_calls: u64,
+    pub total_wait_ms: u64,
+    // Since the last call to reset_peaks
+    pub peak_wait_ms: u64,
+    pub peak_depth: u64,
+}
+
+// Marks the current thread as handling a command until it is dropped
+pub struct CommandGuard {
+    _private: (),
+}
+
+pub fn start_command() -> CommandGuard {
+    THREAD_COMMANDS.with(|num_commands| num_commands.set(num_commands.get() + 1));
+    let (state, _) = &*QUEUE;
+    if let Ok(mut state) = state.lock() {
+        state.num_commands_in_flight += 1;
+    }
+    CommandGuard { _private: () }
+}
+
+impl Drop for CommandGuard {
+    fn drop(&mut self) {
+        THREAD_COMMANDS.with(|num_commands| num_commands.set(num_commands.get() - 1));
+        let (state, condvar) = &*QUEUE;
+        if let Ok(mut state) = state.lock() {
+            state.num_commands_in_flight = state.num_commands_in_flight.saturating_sub(1);
+            if state.num_commands_in_flight == 0 {
+                condvar.notify_all();
+            }
+        }
+    }
+}
+
+// Called before every Discord API call of the background tasks
+pub fn wait_for_turn() {
+    let is_command = THREAD_COMMANDS.with(|num_commands| num_commands.get() > 0);
+    if is_command {
+        return;
+    }
+    let (state, condvar) = &*QUEUE;
+    let mut state = match state.lock() {
+        Ok(state) => state,
+        // A thread panicked while holding the lock, don't hold up the call
+        Err(_) => return,
+    };
+    if state.num_commands_in_flight == 0 {
+        return;
+    }
+    state.num_waiting += 1;
+    state.metrics.peak_depth = state.metrics.peak_depth.max(state.num_waiting as u64);
+    let started = Instant::now();
+    let deadline = started + Duration::from_millis(MAX_BACKGROUND_WAIT_MS);
+    while state.num_commands_in_flight > 0 {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        state = match condvar.wait_timeout(state, deadline - now) {
+            Ok((state, _)) => state,
+            Err(_) => return,
+        };
+    }
+    state.num_waiting -= 1;
+    let elapsed = started.elapsed();
+    let wait_ms = elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis());
+    state.metrics.num_waited_calls += 1;
+    state.metrics.total_wait_ms += wait_ms;
+    state.metrics.peak_wait_ms = state.metrics.peak_wait_ms.max(wait_ms);
+}
+
+pub fn metrics() -> QueueMetrics {
+    let (state, _) = &*QUEUE;
+    state.lock().map(|state| state.metrics).unwrap_or_default()
+}
+
+// Starts a new measurement of the peaks, e.g. for the next sync run
+pub fn reset_peaks() {
+    let (state, _) = &*QUEUE;
+    if let Ok(mut state) = state.lock() {
+        state.metrics.peak_wait_ms = 0;
+        state.metrics.peak_depth = 0;
+    }
+}
@@ -0,0 +1,416 @@
+use redis::{Commands, PipelineCommands};
+use serenity::http::CacheHttp;
+use serenity::model::channel::{PermissionOverwrite, PermissionOverwriteType};
+use serenity::model::id::{ChannelId, UserId};
+use serenity::model::permissions::Permissions;
+use std::collections::HashSet;
+
+// With "config set channel_access overwrites", the Discord sync doesn't create
+// a player and a host role for new channels. Each player and host gets a
+// permission overwrite of their own in the channel instead, such that the
+// channels don't count towards Discord's role limit. The players and hosts of
+// these channels are stored in Redis, and the add, remove and host commands
+// change them just like they hand out the roles in channels that have roles.
+// Channels keep the way they were set up: switching the option only affects
+// channels that don't have roles yet. Features that are built on the roles
+// (the party, session and voice channels and the role colour) are not
+// available in channels without roles.
+
+// Channels (Discord ID) whose access is granted with member overwrites
+const REDIS_OVERWRITE_CHANNELS_KEY: &'static str = "discord_overwrite_channels";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AccessMode {
+    Roles,
+    Overwrites,
+}
+
+impl AccessMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccessMode::Roles => "roles",
+            AccessMode::Overwrites => "overwrites",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<AccessMode> {
+        [AccessMode::Roles, AccessMode::Overwrites]
+            .iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+fn redis_players_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:overwrite_players", channel_id.0)
+}
+
+fn redis_hosts_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:overwrite_hosts", channel_id.0)
+}
+
+pub fn uses_overwrites<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<bool, crate::BoxedError> {
+    Ok(con.sismember(REDIS_OVERWRITE_CHANNELS_KEY, channel_id.0)?)
+}
+
+// Whether the channel (None for a channel that doesn't exist yet) uses
+// overwrites or would switch to them with the next sync. Only reads from
+// Redis, for the sync preview.
+pub fn would_use_overwrites<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: Option<ChannelId>,
+) -> Result<bool, crate::BoxedError> {
+    if let Some(channel_id) = channel_id {
+        if uses_overwrites(con, channel_id)? {
+            return Ok(true);
+        }
+    }
+    if crate::guild_config::channel_access(con)? != AccessMode::Overwrites {
+        return Ok(false);
+    }
+    match channel_id {
+        Some(channel_id) => {
+            let has_role: bool =
+                con.exists(format!("discord_channel:{}:discord_role", channel_id.0))?;
+            Ok(!has_role)
+        }
+        None => Ok(true),
+    }
+}
+
+// Called by the Discord sync for each channel. Returns whether the channel
+// uses overwrites, switching channels without roles over if the guild is in
+// overwrites mode.
+pub fn sync_channel_mode(
+    channel_id: ChannelId,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<bool, crate::BoxedError> {
+    if uses_overwrites(con, channel_id)? {
+        return Ok(true);
+    }
+    if !would_use_overwrites(con, Some(channel_id))? {
+        return Ok(false);
+    }
+    let _: () = con.sadd(REDIS_OVERWRITE_CHANNELS_KEY, channel_id.0)?;
+    println!(
+        "Discord sync: channel {} grants access with member overwrites",
+        channel_id.0
+    );
+    Ok(true)
+}
+
+pub fn is_player<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    discord_id: u64,
+) -> Result<bool, crate::BoxedError> {
+    Ok(con.sismember(redis_players_key(channel_id), discord_id)?)
+}
+
+pub fn is_host<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    discord_id: u64,
+) -> Result<bool, crate::BoxedError> {
+    Ok(con.sismember(redis_hosts_key(channel_id), discord_id)?)
+}
+
+pub fn players<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<Vec<u64>, crate::BoxedError> {
+    Ok(con.smembers(redis_players_key(channel_id))?)
+}
+
+pub fn hosts<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<Vec<u64>, crate::BoxedError> {
+    Ok(con.smembers(redis_hosts_key(channel_id))?)
+}
+
+// The players and hosts of all channels that use overwrites
+pub fn all_members<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<HashSet<u64>, crate::BoxedError> {
+    let channel_ids: Vec<u64> = con.smembers(REDIS_OVERWRITE_CHANNELS_KEY)?;
+    if channel_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let keys: Vec<String> = channel_ids
+        .into_iter()
+        .flat_map(|channel_id| {
+            vec![
+                redis_players_key(ChannelId(channel_id)),
+                redis_hosts_key(ChannelId(channel_id)),
+            ]
+        })
+        .collect();
+    Ok(con.sunion(keys)?)
+}
+
+// The channels using overwrites where the user is a player or a host
+pub fn member_channels<C: redis::ConnectionLike>(
+    con: &mut C,
+    discord_id: u64,
+) -> Result<Vec<ChannelId>, crate::BoxedError> {
+    Ok(memberships(con, discord_id)?
+        .into_iter()
+        .map(|(channel_id, _, _)| channel_id)
+        .collect())
+}
+
+// The same, together with whether the user is a player and whether they are
+// a host there
+pub fn memberships<C: redis::ConnectionLike>(
+    con: &mut C,
+    discord_id: u64,
+) -> Result<Vec<(ChannelId, bool, bool)>, crate::BoxedError> {
+    let channel_ids: Vec<u64> = con.smembers(REDIS_OVERWRITE_CHANNELS_KEY)?;
+    if channel_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut pipe = redis::pipe();
+    for &channel_id in &channel_ids {
+        pipe.sismember(redis_players_key(ChannelId(channel_id)), discord_id)
+            .sismember(redis_hosts_key(ChannelId(channel_id)), discord_id);
+    }
+    let memberships: Vec<(bool, bool)> = pipe.query(con)?;
+    Ok(channel_ids
+        .into_iter()
+        .zip(memberships)
+        .filter(|&(_, (is_player, is_host))| is_player || is_host)
+        .map(|(channel_id, (is_player, is_host))| (ChannelId(channel_id), is_player, is_host))
+        .collect())
+}
+
+// The same permissions that the player and host roles give. Archived
+// channels are read-only.
+fn member_overwrite(
+    discord_id: u64,
+    is_player: bool,
+    is_host: bool,
+    archived: bool,
+) -> Option<PermissionOverwrite> {
+    let mut allow = if is_host {
+        Permissions::READ_MESSAGES | Permissions::MENTION_EVERYONE | Permissions::MANAGE_MESSAGES
+    } else if is_player {
+        Permissions::READ_MESSAGES | Permissions::MENTION_EVERYONE
+    } else {
+        return None;
+    };
+    let mut deny = Permissions::empty();
+    if archived {
+        allow.remove(Permissions::SEND_MESSAGES);
+        deny.insert(Permissions::SEND_MESSAGES);
+    }
+    Some(PermissionOverwrite {
+        allow: allow,
+        deny: deny,
+        kind: PermissionOverwriteType::Member(UserId(discord_id)),
+    })
+}
+
+// Creates, updates or removes the member's overwrite according to Redis.
+// Co-hosts are left alone, their own overwrite gives them access already.
+pub fn sync_member(
+    channel_id: ChannelId,
+    discord_id: u64,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let (is_player, is_host, is_cohost, archived): (bool, bool, bool, Option<bool>) = redis::pipe()
+        .sismember(redis_players_key(channel_id), discord_id)
+        .sismember(redis_hosts_key(channel_id), discord_id)
+        .sismember(
+            format!("discord_channel:{}:cohosts", channel_id.0),
+            discord_id,
+        )
+        .get(format!("discord_channel:{}:archived", channel_id.0))
+        .query(con)?;
+    if is_cohost {
+        return Ok(());
+    }
+    match member_overwrite(discord_id, is_player, is_host, archived.unwrap_or(false)) {
+        Some(overwrite) => {
+            // The overwrite replaces the one that hid the history
+            let _: () = con.zrem(
+                format!("discord_channel:{}:history_hidden_until", channel_id.0),
+                discord_id,
+            )?;
+            channel_id.create_permission(discord_api.http(), &overwrite)?;
+        }
+        None => match channel_id.delete_permission(
+            discord_api.http(),
+            PermissionOverwriteType::Member(UserId(discord_id)),
+        ) {
+            Ok(()) => (),
+            Err(ref err) if crate::vacuum::is_not_found(err) => (),
+            Err(err) => return Err(err.into()),
+        },
+    }
+    Ok(())
+}
+
+// Used by the quarantine: takes all access away. Returns whether the user was
+// a player or a host of the channel.
+pub fn remove_member(
+    channel_id: ChannelId,
+    discord_id: u64,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    let (num_players, num_hosts): (u32, u32) = redis::pipe()
+        .srem(redis_players_key(channel_id), discord_id)
+        .srem(redis_hosts_key(channel_id), discord_id)
+        .query(con)?;
+    if num_players == 0 && num_hosts == 0 {
+        return Ok(false);
+    }
+    sync_member(channel_id, discord_id, con, discord_api)?;
+    Ok(true)
+}
+
+// Like the player and host roles, being a host and being a player are
+// independent: the host permissions include the player permissions
+fn redis_members_key(channel_id: ChannelId, as_host: bool) -> String {
+    if as_host {
+        redis_hosts_key(channel_id)
+    } else {
+        redis_players_key(channel_id)
+    }
+}
+
+pub fn grant(
+    channel_id: ChannelId,
+    discord_id: u64,
+    as_host: bool,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let _: () = con.sadd(redis_members_key(channel_id, as_host), discord_id)?;
+    sync_member(channel_id, discord_id, con, discord_api)
+}
+
+pub fn revoke(
+    channel_id: ChannelId,
+    discord_id: u64,
+    as_host: bool,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let _: () = con.srem(redis_members_key(channel_id, as_host), discord_id)?;
+    sync_member(channel_id, discord_id, con, discord_api)
+}
+
+// Part of the Discord sync, instead of handing out the roles: adds the RSVP'd
+// players and hosts and makes sure that everybody's overwrite is in place
+// and up-to-date (e.g. after the channel was archived or opened again).
+// Like the roles, nobody is removed automatically.
+pub fn sync_overwrites(
+    series_id: &str,
+    channel_id: ChannelId,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    // Other bots are always ignored
+    let is_not_bot = |id: &u64| !crate::ignore_list::is_bot(discord_api, *id);
+    let player_ids: Vec<u64> =
+        crate::discord_sync::role_assignment_candidates(series_id, channel_id, false, con)?
+            .into_iter()
+            .filter(is_not_bot)
+            .collect();
+    let host_ids: Vec<u64> =
+        crate::discord_sync::role_assignment_candidates(series_id, channel_id, true, con)?
+            .into_iter()
+            .filter(is_not_bot)
+            .collect();
+    let current_player_ids: HashSet<u64> = con.smembers(redis_players_key(channel_id))?;
+    let new_player_ids: Vec<u64> = player_ids
+        .iter()
+        .filter(|id| !current_player_ids.contains(id))
+        .cloned()
+        .collect();
+    if !player_ids.is_empty() || !host_ids.is_empty() {
+        let mut pipe = redis::pipe();
+        if !player_ids.is_empty() {
+            pipe.sadd(redis_players_key(channel_id), &player_ids)
+                .ignore();
+        }
+        if !host_ids.is_empty() {
+            pipe.sadd(redis_hosts_key(channel_id), &host_ids).ignore();
+        }
+        let _: () = pipe.query(con)?;
+    }
+    sync_members(channel_id, con, discord_api)?;
+    // Let new players know about the safety tools of the game
+    for discord_id in new_player_ids {
+        if let Err(err) =
+            crate::discord_safety::send_safety_note(channel_id, discord_id, con, discord_api)
+        {
+            eprintln!(
+                "Could not send the safety note to user {}: {}",
+                discord_id, err
+            );
+        }
+    }
+    Ok(())
+}
+
+// Only touches the overwrites that are missing or differ
+pub fn sync_members(
+    channel_id: ChannelId,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let overwrites = match channel_id.to_channel(discord_api)?.guild() {
+        Some(channel) => channel.read().permission_overwrites.clone(),
+        None => return Ok(()),
+    };
+    let (player_ids, host_ids, cohost_ids, archived): (
+        HashSet<u64>,
+        HashSet<u64>,
+        HashSet<u64>,
+        Option<bool>,
+    ) = redis::pipe()
+        .smembers(redis_players_key(channel_id))
+        .smembers(redis_hosts_key(channel_id))
+        .smembers(format!("discord_channel:{}:cohosts", channel_id.0))
+        .get(format!("discord_channel:{}:archived", channel_id.0))
+        .query(con)?;
+    for &discord_id in player_ids.union(&host_ids) {
+        if cohost_ids.contains(&discord_id) {
+            continue;
+        }
+        let overwrite = match member_overwrite(
+            discord_id,
+            player_ids.contains(&discord_id),
+            host_ids.contains(&discord_id),
+            archived.unwrap_or(false),
+        ) {
+            Some(overwrite) => overwrite,
+            None => continue,
+        };
+        let is_up_to_date = overwrites.iter().any(|current| match current.kind {
+            PermissionOverwriteType::Member(user_id) => {
+                user_id.0 == discord_id
+                    && current.allow == overwrite.allow
+                    && current.deny == overwrite.deny
+            }
+            _ => false,
+        });
+        if is_up_to_date {
+            continue;
+        }
+        if let Err(err) = channel_id.create_permission(discord_api.http(), &overwrite) {
+            eprintln!(
+                "Could not give user {} access to channel {}: {}",
+                discord_id, channel_id.0, err
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use crate::strings;
+use redis::{Commands, PipelineCommands};
+use serenity::{
+    model::{channel::Reaction, channel::ReactionType, id::MessageId},
+    prelude::*,
+};
+
+// With the "channel_approval" feature enabled, the Discord sync doesn't create
+// channels for new event series on its own. It posts a proposal with the
+// series name, the next event and its hosts to the staff channel instead, and
+// an organizer approves or rejects it by reacting. Only approved series get a
+// channel and roles. Rejected series are remembered and not proposed again,
+// but reacting to the old proposal changes the decision as long as the series
+// has no channel yet. Series that already have a channel are not affected.
+
+// Maps event series IDs to the decision
+const REDIS_DECISIONS_KEY: &'static str = "channel_approval:decisions";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl Decision {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Decision::Pending => "pending",
+            Decision::Approved => "approved",
+            Decision::Rejected => "rejected",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Decision> {
+        [Decision::Pending, Decision::Approved, Decision::Rejected]
+            .iter()
+            .find(|decision| decision.name() == name)
+            .cloned()
+    }
+}
+
+// The series (ID and name) that a proposal message is about
+fn redis_proposal_key(message_id: MessageId) -> String {
+    format!("channel_approval:proposal:{}", message_id.0)
+}
+
+pub fn decision<C: redis::ConnectionLike>(
+    con: &mut C,
+    series_id: &str,
+) -> Result<Option<Decision>, crate::BoxedError> {
+    let decision: Option<String> = con.hget(REDIS_DECISIONS_KEY, series_id)?;
+    Ok(decision.and_then(|decision| Decision::from_name(&decision)))
+}
+
+// Called by the Discord sync before it syncs the channel of a series. Returns
+// whether the sync may go ahead and posts the proposal the first time it is
+// called for a series without a channel.
+pub fn may_create_channel(
+    series_id: &str,
+    series_name: &str,
+    next_event_id: &str,
+    next_event_time: chrono::DateTime<chrono::Utc>,
+    next_event_link: &str,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    if !crate::feature_flags::is_enabled(
+        con,
+        crate::discord_sync::GUILD_ID,
+        crate::feature_flags::Feature::ChannelApproval,
+    )? {
+        return Ok(true);
+    }
+    let has_channel: bool = con.exists(format!("event_series:{}:discord_channel", series_id))?;
+    if has_channel {
+        return Ok(true);
+    }
+    match decision(con, series_id)? {
+        Some(Decision::Approved) => Ok(true),
+        Some(Decision::Pending) | Some(Decision::Rejected) => Ok(false),
+        None => {
+            propose(
+                series_id,
+                series_name,
+                next_event_id,
+                next_event_time,
+                next_event_link,
+                con,
+                discord_api,
+            )?;
+            Ok(false)
+        }
+    }
+}
+
+fn propose(
+    series_id: &str,
+    series_name: &str,
+    next_event_id: &str,
+    next_event_time: chrono::DateTime<chrono::Utc>,
+    next_event_link: &str,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    // Organizers need a message to react to, DMs to each of them would not
+    // show who decided
+    let staff_channel_id = match crate::guild_config::staff_channel(con)? {
+        Some(staff_channel_id) => staff_channel_id,
+        None => {
+            eprintln!(
+                "Discord sync: not proposing a channel for series {}, there is no staff channel",
+                series_id
+            );
+            return Ok(());
+        }
+    };
+    let meetup_host_ids: Vec<u64> =
+        con.smembers(format!("meetup_event:{}:meetup_hosts", next_event_id))?;
+    let mut pipe = redis::pipe();
+    for &meetup_host_id in &meetup_host_ids {
+        pipe.get(crate::identity::redis_meetup_discord_user_key(
+            meetup_host_id,
+        ));
+    }
+    let discord_host_ids: Vec<Option<u64>> = if meetup_host_ids.is_empty() {
+        vec![]
+    } else {
+        pipe.query(con)?
+    };
+    let hosts: Vec<_> = meetup_host_ids.into_iter().zip(discord_host_ids).collect();
+    let message = staff_channel_id.say(
+        &discord_api.http,
+        strings::CHANNEL_PROPOSAL(
+            series_name,
+            next_event_time,
+            next_event_link,
+            &hosts,
+            crate::discord_confirmations::CONFIRM_EMOJI,
+            crate::discord_confirmations::CANCEL_EMOJI,
+        ),
+    )?;
+    let _: () = redis::pipe()
+        .hset_multiple(
+            redis_proposal_key(message.id),
+            &[("series_id", series_id), ("series_name", series_name)],
+        )
+        .ignore()
+        .hset(REDIS_DECISIONS_KEY, series_id, Decision::Pending.name())
+        .ignore()
+        .query(con)?;
+    let _ = message.react(discord_api, crate::discord_confirmations::CONFIRM_EMOJI);
+    let _ = message.react(discord_api, crate::discord_confirmations::CANCEL_EMOJI);
+    println!(
+        "Discord sync: proposed a channel for series {} \"{}\"",
+        series_id, series_name
+    );
+    Ok(())
+}
+
+// Approves or rejects the series if an organizer reacted to its proposal
+pub fn handle_reaction(
+    ctx: &Context,
+    reaction: &Reaction,
+    redis_client: &crate::redis_namespace::Client,
+) -> crate::Result<()> {
+    let new_decision = match &reaction.emoji {
+        ReactionType::Unicode(emoji) if emoji == crate::discord_confirmations::CONFIRM_EMOJI => {
+            Decision::Approved
+        }
+        ReactionType::Unicode(emoji) if emoji == crate::discord_confirmations::CANCEL_EMOJI => {
+            Decision::Rejected
+        }
+        _ => return Ok(()),
+    };
+    let mut redis_connection = redis_client.get_connection()?;
+    let (series_id, series_name): (Option<String>, Option<String>) = redis_connection.hget(
+        redis_proposal_key(reaction.message_id),
+        &["series_id", "series_name"],
+    )?;
+    let (series_id, series_name) = match (series_id, series_name) {
+        (Some(series_id), Some(series_name)) => (series_id, series_name),
+        _ => return Ok(()),
+    };
+    let is_organizer = reaction
+        .user(ctx)?
+        .has_role(
+            ctx,
+            crate::discord_sync::GUILD_ID,
+            crate::discord_sync::ORGANIZER_ID,
+        )
+        .unwrap_or(false);
+    if !is_organizer {
+        return Ok(());
+    }
+    // Once the channel exists, there is nothing left to decide
+    let has_channel: bool =
+        redis_connection.exists(format!("event_series:{}:discord_channel", series_id))?;
+    if has_channel || decision(&mut redis_connection, &series_id)? == Some(new_decision) {
+        return Ok(());
+    }
+    let _: () = redis_connection.hset(REDIS_DECISIONS_KEY, &series_id, new_decision.name())?;
+    let reply = if new_decision == Decision::Approved {
+        // Picked up by the next Discord sync
+        crate::discord_sync::mark_series_dirty(&mut redis_connection, &series_id)?;
+        strings::CHANNEL_PROPOSAL_APPROVED(reaction.user_id.0, &series_name)
+    } else {
+        strings::CHANNEL_PROPOSAL_REJECTED(reaction.user_id.0, &series_name)
+    };
+    let _ = reaction.channel_id.say(&ctx.http, reply);
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use chrono::TimeZone;
+use redis::{Commands, PipelineCommands};
+use serenity::model::id::ChannelId;
+
+// Discord allows only two edits of a channel's name or topic per ten minutes.
+// Serenity waits for a rate limit to reset, so a topic that keeps changing
+// would hold up the whole Discord sync for minutes. The sync therefore spends
+// a per-channel edit budget and defers edits that don't fit into it to a later
+// run. The recurring sync runs again as soon as the first deferred edit fits
+// into the budget. To not waste the budget, it remembers a hash of the topic
+// and category it applied last and leaves the channel alone while they stay
+// the same.
+pub const EDIT_BUDGET: isize = 2;
+pub const EDIT_WINDOW_SECS: i64 = 10 * 60;
+// When the deferred edits can be retried, by channel
+const REDIS_RETRY_TIMES_KEY: &'static str = "discord_channel_edits:retry_times";
+
+fn redis_edit_times_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:edit_times", channel_id.0)
+}
+
+fn redis_applied_topic_hash_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:applied_topic_hash", channel_id.0)
+}
+
+// Identifies a topic and category, independently of the Rust version (the
+// hashes are kept across restarts)
+pub fn topic_hash(topic: &str, category: Option<ChannelId>) -> String {
+    let state = format!(
+        "{}\n{}",
+        category.map(|category| category.0).unwrap_or(0),
+        topic
+    );
+    ring::digest::digest(&ring::digest::SHA256, state.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn is_topic_applied<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    hash: &str,
+) -> Result<bool, crate::BoxedError> {
+    let applied_hash: Option<String> = con.get(redis_applied_topic_hash_key(channel_id))?;
+    Ok(applied_hash.as_ref().map(String::as_str) == Some(hash))
+}
+
+pub fn record_topic_applied<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    hash: &str,
+) -> Result<(), crate::BoxedError> {
+    let _: () = con.set(redis_applied_topic_hash_key(channel_id), hash)?;
+    Ok(())
+}
+
+// Returns false if the channel was already edited EDIT_BUDGET times within
+// the last EDIT_WINDOW_SECS, in which case the edit should be deferred
+pub fn try_spend_edit<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<bool, crate::BoxedError> {
+    let key = redis_edit_times_key(channel_id);
+    let now = chrono::Utc::now().timestamp_millis();
+    let window_start = now - EDIT_WINDOW_SECS * 1000;
+    let (num_recent_edits, oldest_edit): (isize, Vec<(String, f64)>) = redis::pipe()
+        .zrembyscore(&key, "-inf", window_start)
+        .ignore()
+        .zcard(&key)
+        .zrange_withscores(&key, 0, 0)
+        .query(con)?;
+    if num_recent_edits >= EDIT_BUDGET {
+        // The edit fits into the budget once the oldest one leaves the window
+        let retry_time = oldest_edit
+            .first()
+            .map(|&(_, edit_time)| edit_time as i64 + EDIT_WINDOW_SECS * 1000)
+            .unwrap_or(now + EDIT_WINDOW_SECS * 1000);
+        let _: () = con.zadd(REDIS_RETRY_TIMES_KEY, channel_id.0, retry_time)?;
+        return Ok(false);
+    }
+    let _: () = redis::pipe()
+        .zadd(&key, now, now)
+        .ignore()
+        .expire(&key, EDIT_WINDOW_SECS as usize)
+        .ignore()
+        .query(con)?;
+    Ok(true)
+}
+
+// The earliest time at which a deferred edit fits into its channel's budget.
+// Forgets the deferred edits, since the sync that retries them defers them
+// again if need be.
+pub fn take_retry_time<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, crate::BoxedError> {
+    let (earliest,): (Vec<(String, f64)>,) = redis::pipe()
+        .atomic()
+        .zrange_withscores(REDIS_RETRY_TIMES_KEY, 0, 0)
+        .del(REDIS_RETRY_TIMES_KEY)
+        .ignore()
+        .query(con)?;
+    Ok(earliest
+        .first()
+        .map(|&(_, retry_time)| chrono::Utc.timestamp_millis(retry_time as i64)))
+}
@@ -0,0 +1,283 @@
+use redis::Commands;
+use serenity::model::id::ChannelId;
+
+// Options that the hosts of a bot controlled channel can change with the
+// "settings" command. They are stored in a hash per channel, fields that are
+// missing (or invalid) fall back to the default.
+pub const MAX_REMINDER_LEAD_HOURS: u32 = 7 * 24;
+// Discord limits channel topics to 1024 characters
+pub const MAX_TOPIC_CHARS: usize = 1024;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Setting {
+    Reminders,
+    ReminderLeadTime,
+    Topic,
+    Closing,
+    ReactionSignup,
+    Tidy,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 6] = [
+        Setting::Reminders,
+        Setting::ReminderLeadTime,
+        Setting::Topic,
+        Setting::Closing,
+        Setting::ReactionSignup,
+        Setting::Tidy,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Setting::Reminders => "reminders",
+            Setting::ReminderLeadTime => "reminder_lead_time",
+            Setting::Topic => "topic",
+            Setting::Closing => "closing",
+            Setting::ReactionSignup => "reaction_signup",
+            Setting::Tidy => "tidy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Setting> {
+        Setting::ALL
+            .iter()
+            .find(|setting| setting.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+// What happens to the channel once it is closed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClosingMode {
+    Delete,
+    // The channel is made read-only and kept
+    Archive,
+}
+
+impl ClosingMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClosingMode::Delete => "delete",
+            ClosingMode::Archive => "archive",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ClosingMode> {
+        [ClosingMode::Delete, ClosingMode::Archive]
+            .iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+pub struct ChannelSettings {
+    // Whether the channel gets expiration reminders
+    pub reminders: bool,
+    // How long before the channel expires the first reminder is sent
+    pub reminder_lead_hours: u32,
+    // None for the default topic, see `topic`
+    pub topic_template: Option<String>,
+    pub closing: ClosingMode,
+    // Whether players can ask to join the next session by reacting to the
+    // session message
+    pub reaction_signup: bool,
+    // The classes of commands that are cleaned up after in this channel,
+    // None for the server-wide "tidy_commands" config
+    pub tidy: Option<Vec<crate::discord_tidy::CommandClass>>,
+}
+
+impl Default for ChannelSettings {
+    fn default() -> Self {
+        ChannelSettings {
+            reminders: true,
+            reminder_lead_hours: 0,
+            topic_template: None,
+            closing: ClosingMode::Delete,
+            reaction_signup: true,
+            tidy: None,
+        }
+    }
+}
+
+impl ChannelSettings {
+    // Changes a setting from the text given to the "settings" command.
+    // Returns false if the value is not valid for the setting.
+    pub fn apply(&mut self, setting: Setting, value: &str) -> bool {
+        let value = value.trim();
+        match setting {
+            Setting::Reminders => match parse_toggle(value) {
+                Some(enabled) => self.reminders = enabled,
+                None => return false,
+            },
+            Setting::ReminderLeadTime => match value.parse::<u32>() {
+                Ok(hours) if hours <= MAX_REMINDER_LEAD_HOURS => self.reminder_lead_hours = hours,
+                _ => return false,
+            },
+            Setting::Topic => {
+                if value.eq_ignore_ascii_case("default") {
+                    self.topic_template = None;
+                } else if value.is_empty() || value.chars().count() > MAX_TOPIC_CHARS {
+                    return false;
+                } else {
+                    self.topic_template = Some(value.to_string());
+                }
+            }
+            Setting::Closing => match ClosingMode::from_name(value) {
+                Some(mode) => self.closing = mode,
+                None => return false,
+            },
+            Setting::ReactionSignup => match parse_toggle(value) {
+                Some(enabled) => self.reaction_signup = enabled,
+                None => return false,
+            },
+            Setting::Tidy => {
+                if value.eq_ignore_ascii_case("default") {
+                    self.tidy = None;
+                } else {
+                    match crate::discord_tidy::parse_classes(value) {
+                        Some(classes) => self.tidy = Some(classes),
+                        None => return false,
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    // The channel topic for the next session. The template's placeholders
+    // {name}, {link}, {location}, {session} and {tags} are replaced with the
+    // session's details. Only campaigns have a session number.
+    pub fn topic(
+        &self,
+        event_name: &str,
+        event_link: &str,
+        event_location: &crate::event_location::Location,
+        session_number: Option<u64>,
+        series_tags: &[String],
+    ) -> String {
+        let location = crate::strings::EVENT_LOCATION(event_location);
+        let topic = match &self.topic_template {
+            Some(template) => template
+                .replace("{name}", event_name)
+                .replace("{link}", event_link)
+                .replace(
+                    "{location}",
+                    location.as_ref().map(String::as_str).unwrap_or(""),
+                )
+                .replace(
+                    "{session}",
+                    &session_number
+                        .map(|number| number.to_string())
+                        .unwrap_or_default(),
+                )
+                .replace("{tags}", &series_tags.join(", ")),
+            None => {
+                let mut topic = match session_number {
+                    Some(number) => format!("Next session (Session {}): {}", number, event_link),
+                    None => format!("Next session: {}", event_link),
+                };
+                if let Some(location) = location {
+                    topic.push_str(&format!(" | {}", location));
+                }
+                if !series_tags.is_empty() {
+                    topic.push_str(&format!(" | Tags: {}", series_tags.join(", ")));
+                }
+                topic
+            }
+        };
+        topic.chars().take(MAX_TOPIC_CHARS).collect()
+    }
+}
+
+fn parse_toggle(value: &str) -> Option<bool> {
+    if value.eq_ignore_ascii_case("on") {
+        Some(true)
+    } else if value.eq_ignore_ascii_case("off") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn redis_settings_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:settings", channel_id.0)
+}
+
+pub fn settings<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<ChannelSettings, crate::BoxedError> {
+    let (reminders, reminder_lead_hours, topic_template, closing, reaction_signup, tidy): (
+        Option<bool>,
+        Option<u32>,
+        Option<String>,
+        Option<String>,
+        Option<bool>,
+        Option<String>,
+    ) = con.hget(
+        redis_settings_key(channel_id),
+        &[
+            "reminders",
+            "reminder_lead_hours",
+            "topic_template",
+            "closing",
+            "reaction_signup",
+            "tidy",
+        ],
+    )?;
+    let default = ChannelSettings::default();
+    Ok(ChannelSettings {
+        reminders: reminders.unwrap_or(default.reminders),
+        reminder_lead_hours: reminder_lead_hours
+            .filter(|hours| *hours <= MAX_REMINDER_LEAD_HOURS)
+            .unwrap_or(default.reminder_lead_hours),
+        topic_template: topic_template,
+        closing: closing
+            .and_then(|closing| ClosingMode::from_name(&closing))
+            .unwrap_or(default.closing),
+        reaction_signup: reaction_signup.unwrap_or(default.reaction_signup),
+        tidy: tidy.and_then(|tidy| crate::discord_tidy::parse_classes(&tidy)),
+    })
+}
+
+pub fn save<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    settings: &ChannelSettings,
+) -> Result<(), crate::BoxedError> {
+    let redis_settings_key = redis_settings_key(channel_id);
+    let mut pipe = redis::pipe();
+    pipe.hset(&redis_settings_key, "reminders", settings.reminders)
+        .ignore()
+        .hset(
+            &redis_settings_key,
+            "reminder_lead_hours",
+            settings.reminder_lead_hours,
+        )
+        .ignore()
+        .hset(&redis_settings_key, "closing", settings.closing.name())
+        .ignore()
+        .hset(
+            &redis_settings_key,
+            "reaction_signup",
+            settings.reaction_signup,
+        )
+        .ignore();
+    match &settings.topic_template {
+        Some(template) => pipe.hset(&redis_settings_key, "topic_template", template),
+        None => pipe.hdel(&redis_settings_key, "topic_template"),
+    }
+    .ignore();
+    match &settings.tidy {
+        Some(classes) => pipe.hset(
+            &redis_settings_key,
+            "tidy",
+            crate::discord_tidy::classes_name(classes),
+        ),
+        None => pipe.hdel(&redis_settings_key, "tidy"),
+    }
+    .ignore();
+    let _: () = pipe.query(con)?;
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use redis::Commands;
+use serenity::http::CacheHttp;
+use serenity::model::id::{ChannelId, RoleId};
+
+// Hosts can give their channel's player role a colour of its own and put an
+// emoji in front of the channel name with "style colour #aa33ff emoji 🐉".
+// The style is stored per channel and applied by the Discord sync, so it stays
+// when the channel is synced with Meetup again. The emoji that was last put
+// in front of the name is remembered, such that it can be replaced or removed
+// without touching the rest of the name (which hosts may have changed).
+
+// Discord doesn't allow more than 100 characters in a channel name
+const MAX_CHANNEL_NAME_CHARS: usize = 100;
+
+// Flags and emojis with skin tones or joiners take several characters
+const MAX_EMOJI_CHARS: usize = 8;
+
+pub struct ChannelStyle {
+    pub colour: Option<u64>,
+    pub emoji: Option<String>,
+}
+
+fn redis_channel_style_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:style", channel_id.0)
+}
+
+pub fn style<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<ChannelStyle, crate::BoxedError> {
+    let (colour, emoji): (Option<u64>, Option<String>) =
+        con.hget(redis_channel_style_key(channel_id), &["colour", "emoji"])?;
+    Ok(ChannelStyle {
+        colour: colour,
+        emoji: emoji,
+    })
+}
+
+// Passing None goes back to the default colour
+pub fn set_colour<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    colour: Option<u64>,
+) -> Result<(), crate::BoxedError> {
+    let redis_channel_style_key = redis_channel_style_key(channel_id);
+    let _: () = match colour {
+        Some(colour) => con.hset(&redis_channel_style_key, "colour", colour)?,
+        None => con.hdel(&redis_channel_style_key, "colour")?,
+    };
+    Ok(())
+}
+
+// Passing None removes the emoji from the channel name
+pub fn set_emoji<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    emoji: Option<&str>,
+) -> Result<(), crate::BoxedError> {
+    let redis_channel_style_key = redis_channel_style_key(channel_id);
+    let _: () = match emoji {
+        Some(emoji) => con.hset(&redis_channel_style_key, "emoji", emoji)?,
+        None => con.hdel(&redis_channel_style_key, "emoji")?,
+    };
+    Ok(())
+}
+
+// Accepts "#aa33ff" and "aa33ff"
+pub fn parse_colour(colour: &str) -> Option<u64> {
+    let hex = colour.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    u64::from_str_radix(hex, 16).ok()
+}
+
+// Only Unicode emojis, custom emojis ("<:name:id>") can't be part of a
+// channel name
+pub fn parse_emoji(emoji: &str) -> Option<String> {
+    let emoji = emoji.trim();
+    let num_chars = emoji.chars().count();
+    if num_chars == 0 || num_chars > MAX_EMOJI_CHARS {
+        return None;
+    }
+    if emoji
+        .chars()
+        .any(|c| c.is_ascii() || c.is_alphanumeric() || c.is_whitespace())
+    {
+        return None;
+    }
+    Some(emoji.to_string())
+}
+
+// Puts the emoji in front of the channel name, replacing the one that was
+// there before. Returns false if the rename had to be deferred because the
+// channel's edit budget is used up (see discord_channel_edits).
+pub fn sync_channel_name(
+    channel_id: ChannelId,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    let redis_channel_style_key = redis_channel_style_key(channel_id);
+    let (emoji, applied_emoji): (Option<String>, Option<String>) =
+        con.hget(&redis_channel_style_key, &["emoji", "applied_emoji"])?;
+    if emoji.is_none() && applied_emoji.is_none() {
+        return Ok(true);
+    }
+    let current_name = match channel_id.to_channel(discord_api)? {
+        serenity::model::channel::Channel::Guild(channel) => channel.read().name.clone(),
+        _ => return Ok(true),
+    };
+    // Only the prefix that the sync put there, dashes or emojis that the host
+    // added to the name stay
+    let base_name = match applied_emoji {
+        Some(ref applied_emoji) => {
+            let applied_prefix = format!("{}-", applied_emoji);
+            if current_name.starts_with(&applied_prefix) {
+                &current_name[applied_prefix.len()..]
+            } else {
+                current_name.as_str()
+            }
+        }
+        None => current_name.as_str(),
+    };
+    let name: String = match emoji {
+        Some(ref emoji) => format!("{}-{}", emoji, base_name),
+        None => base_name.to_string(),
+    }
+    .chars()
+    .take(MAX_CHANNEL_NAME_CHARS)
+    .collect();
+    if name != current_name {
+        // The applied emoji stays the same, so that the rename is retried
+        if !crate::discord_channel_edits::try_spend_edit(con, channel_id)? {
+            println!(
+                "Discord event sync: deferring the rename of channel {}, it was edited too \
+                 often recently",
+                channel_id.0
+            );
+            return Ok(false);
+        }
+        channel_id.edit(discord_api.http(), |channel_edit| channel_edit.name(&name))?;
+        println!(
+            "Discord event sync: renamed channel {} to \"{}\"",
+            channel_id.0, name
+        );
+    }
+    let _: () = match emoji {
+        Some(emoji) => con.hset(&redis_channel_style_key, "applied_emoji", emoji)?,
+        None => con.hdel(&redis_channel_style_key, "applied_emoji")?,
+    };
+    Ok(true)
+}
+
+// Right away instead of with the next sync, which also wouldn't reset the
+// colour to the default
+pub fn apply_role_colour(
+    role_id: RoleId,
+    colour: Option<u64>,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let edited_role =
+        crate::discord_sync::GUILD_ID.edit_role(discord_api.http(), role_id, |role_builder| {
+            role_builder.colour(colour.unwrap_or(0))
+        })?;
+    crate::discord_state_cache::upsert_role(&edited_role);
+    Ok(())
+}
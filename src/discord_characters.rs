@@ -0,0 +1,105 @@
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, RoleId};
+use simple_error::SimpleError;
+use std::collections::HashMap;
+
+// Players can register their character (name and a link to the character
+// sheet) for each channel they play in, and everybody can look up the party
+// with the "party" command. Characters of players who left the channel are
+// not shown, but kept in case they come back.
+pub const MAX_CHARACTER_NAME_CHARS: usize = 100;
+const MAX_SHEET_URL_CHARS: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Character {
+    pub name: String,
+    pub sheet_url: String,
+}
+
+fn redis_channel_characters_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:characters", channel_id.0)
+}
+
+pub fn is_valid_sheet_url(url: &str) -> bool {
+    (url.starts_with("https://") || url.starts_with("http://"))
+        && url.len() <= MAX_SHEET_URL_CHARS
+        && !url.contains(char::is_whitespace)
+}
+
+pub fn set_character<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    discord_id: u64,
+    character: &Character,
+) -> Result<(), crate::BoxedError> {
+    let _: () = con.hset(
+        redis_channel_characters_key(channel_id),
+        discord_id,
+        serde_json::to_string(character)?,
+    )?;
+    Ok(())
+}
+
+// Returns false if the player didn't have a character
+pub fn remove_character<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    discord_id: u64,
+) -> Result<bool, crate::BoxedError> {
+    let num_removed: u32 = con.hdel(redis_channel_characters_key(channel_id), discord_id)?;
+    Ok(num_removed > 0)
+}
+
+// The current members of the channel (Discord ID and whether they are a
+// host), hosts first, each with their character if they registered one
+pub fn party(
+    channel_id: ChannelId,
+    user_role_id: RoleId,
+    host_role_id: RoleId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Vec<(u64, bool, Option<Character>)>, crate::BoxedError> {
+    let characters: HashMap<u64, String> =
+        redis_connection.hgetall(redis_channel_characters_key(channel_id))?;
+    let mut members: Vec<(u64, String, bool)> = {
+        let guild = crate::discord_sync::GUILD_ID
+            .to_guild_cached(&discord_api.cache)
+            .ok_or_else(|| SimpleError::new("Could not find the guild in the cache"))?;
+        let guild = guild.read();
+        guild
+            .members
+            .values()
+            .filter(|member| {
+                member.roles.contains(&user_role_id) || member.roles.contains(&host_role_id)
+            })
+            .map(|member| {
+                let user = member.user.read();
+                (
+                    user.id.0,
+                    member.display_name().to_lowercase(),
+                    member.roles.contains(&host_role_id),
+                )
+            })
+            .collect()
+    };
+    members.sort_unstable_by(|a, b| b.2.cmp(&a.2).then_with(|| a.1.cmp(&b.1)));
+    Ok(members
+        .into_iter()
+        .map(|(discord_id, _, is_host)| {
+            let character = characters.get(&discord_id).and_then(|character| {
+                match serde_json::from_str::<Character>(character) {
+                    Ok(character) => Some(character),
+                    Err(err) => {
+                        eprintln!(
+                            "Could not parse the character of user {} in channel {}: {}",
+                            discord_id, channel_id.0, err
+                        );
+                        None
+                    }
+                }
+            });
+            (discord_id, is_host, character)
+        })
+        .collect())
+}
@@ -0,0 +1,745 @@
+// Helps with commands that almost match. When no command matches a message,
+// the message handler first tries to resolve a declared alias (and drops
+// polite filler words like "please"), and otherwise suggests the command
+// that looks the most like what was typed.
+// Only the literal words at the start of a command are taken into account,
+// the arguments are passed on as they were typed.
+
+pub struct Command {
+    // How the command is shown in suggestions. Words starting with "<" or "@"
+    // or containing "|" are arguments.
+    pub usage: &'static str,
+    // Alternative spellings of the literal words of the usage
+    pub aliases: &'static [&'static str],
+    // Whether the command also works in a direct message to the bot
+    pub dm: bool,
+}
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        usage: "link meetup",
+        aliases: &[
+            "link",
+            "connect meetup",
+            "meetup link",
+            "link my meetup",
+            "link account",
+        ],
+        dm: true,
+    },
+    Command {
+        usage: "unlink meetup",
+        aliases: &[
+            "unlink",
+            "disconnect meetup",
+            "unlink my meetup",
+            "unlink account",
+        ],
+        dm: true,
+    },
+    Command {
+        usage: "linking help",
+        aliases: &["help linking", "link help", "help with linking"],
+        dm: true,
+    },
+    Command {
+        usage: "digest on|off",
+        aliases: &["host digest"],
+        dm: true,
+    },
+    Command {
+        usage: "subscribe <tag>",
+        aliases: &["sub", "follow"],
+        dm: false,
+    },
+    Command {
+        usage: "unsubscribe <tag>",
+        aliases: &["unsub", "unfollow"],
+        dm: false,
+    },
+    Command {
+        usage: "subscriptions",
+        aliases: &["my subscriptions", "list subscriptions"],
+        dm: false,
+    },
+    Command {
+        usage: "roll <dice> adv|dis",
+        aliases: &["dice", "r"],
+        dm: true,
+    },
+    Command {
+        usage: "gmroll <dice> adv|dis",
+        aliases: &["gm roll", "secret roll", "private roll"],
+        dm: false,
+    },
+    Command {
+        usage: "roll log",
+        aliases: &["rolls", "roll history"],
+        dm: false,
+    },
+    Command {
+        usage: "report <text>",
+        aliases: &["complain", "report to organizers"],
+        dm: true,
+    },
+    Command {
+        usage: "report anonymously <text>",
+        aliases: &["anonymous report", "report anonymous"],
+        dm: true,
+    },
+    Command {
+        usage: "register email <address>",
+        aliases: &["register my email", "set email", "add email"],
+        dm: true,
+    },
+    Command {
+        usage: "unregister email",
+        aliases: &["remove email", "forget my email", "delete email"],
+        dm: true,
+    },
+    Command {
+        usage: "identities",
+        aliases: &[
+            "my identities",
+            "my accounts",
+            "linked accounts",
+            "list identities",
+        ],
+        dm: true,
+    },
+    Command {
+        usage: "primary identity <provider>:<ID>",
+        aliases: &["set primary identity", "primary account", "make primary"],
+        dm: true,
+    },
+    Command {
+        usage: "nickname sync on|off",
+        aliases: &["nickname on", "nickname off", "sync nickname"],
+        dm: true,
+    },
+    Command {
+        usage: "claim ticket <order number>",
+        aliases: &["claim my ticket", "claim order", "link ticket"],
+        dm: true,
+    },
+    Command {
+        usage: "safety set lines|veils|tools <text>|none",
+        aliases: &["set safety", "safety tools set"],
+        dm: false,
+    },
+    Command {
+        usage: "safety",
+        aliases: &["safety tools", "lines and veils"],
+        dm: false,
+    },
+    Command {
+        usage: "character set <url> <name>",
+        aliases: &["set character", "char set", "character add"],
+        dm: false,
+    },
+    Command {
+        usage: "character remove",
+        aliases: &["remove character", "char remove"],
+        dm: false,
+    },
+    Command {
+        usage: "party",
+        aliases: &["characters", "list characters", "roster"],
+        dm: false,
+    },
+    Command {
+        usage: "macro add <name> <text>",
+        aliases: &["add macro", "new macro", "macro set"],
+        dm: false,
+    },
+    Command {
+        usage: "macro remove <name>",
+        aliases: &["remove macro", "delete macro", "macro delete"],
+        dm: false,
+    },
+    Command {
+        usage: "macros",
+        aliases: &["list macros", "macro list"],
+        dm: false,
+    },
+    Command {
+        usage: "absent <reason>",
+        aliases: &["cant make it", "can't make it", "absence"],
+        dm: false,
+    },
+    Command {
+        usage: "share meetup @user",
+        aliases: &["share my meetup", "share account", "household add"],
+        dm: false,
+    },
+    Command {
+        usage: "unshare meetup @user",
+        aliases: &["stop sharing meetup", "household remove"],
+        dm: false,
+    },
+    Command {
+        usage: "add host @user",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "add guest @user for <period>",
+        aliases: &["add trial player", "add trial", "invite guest"],
+        dm: false,
+    },
+    Command {
+        usage: "add cohost @user",
+        aliases: &["add co host"],
+        dm: false,
+    },
+    Command {
+        usage: "add @user [@user ...]",
+        aliases: &["invite"],
+        dm: false,
+    },
+    Command {
+        usage: "remove host @user",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "remove cohost @user",
+        aliases: &["remove co host"],
+        dm: false,
+    },
+    Command {
+        usage: "remove @user [@user ...]",
+        aliases: &["kick"],
+        dm: false,
+    },
+    Command {
+        usage: "tables <count>",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "export members",
+        aliases: &["export"],
+        dm: false,
+    },
+    Command {
+        usage: "session channels on|off",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "pause sync",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "resume sync",
+        aliases: &["unpause sync"],
+        dm: false,
+    },
+    Command {
+        usage: "style colour <#hex>|none emoji <emoji>|none",
+        aliases: &["channel style", "role colour", "role color"],
+        dm: false,
+    },
+    Command {
+        usage: "hide history <days>|off",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "settings <setting> <value>",
+        aliases: &["channel settings", "setting"],
+        dm: false,
+    },
+    Command {
+        usage: "remind expiration",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "recap <text>",
+        aliases: &["session recap", "add recap"],
+        dm: false,
+    },
+    Command {
+        usage: "recaps",
+        aliases: &["list recaps", "journal"],
+        dm: false,
+    },
+    Command {
+        usage: "close channel",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "sync meetup",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "sync discord",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "sync stats",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "cleanup roles",
+        aliases: &["clean up roles", "delete old roles"],
+        dm: false,
+    },
+    Command {
+        usage: "role usage",
+        aliases: &["role limit", "role count", "how many roles"],
+        dm: false,
+    },
+    Command {
+        usage: "preview <series ID>",
+        aliases: &["sync preview", "dry run", "preview sync"],
+        dm: false,
+    },
+    Command {
+        usage: "config get|set|log",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "setup <step> <value>",
+        aliases: &["set up"],
+        dm: false,
+    },
+    Command {
+        usage: "schedule \"<command>\" at <time>",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "scheduled commands",
+        aliases: &["list scheduled commands"],
+        dm: false,
+    },
+    Command {
+        usage: "unschedule <id>",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "tag rules",
+        aliases: &["list tag rules"],
+        dm: false,
+    },
+    Command {
+        usage: "tag rule <tag> <keywords>",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "subscription role <tag> @role|off",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "list flagged links",
+        aliases: &["flagged links"],
+        dm: false,
+    },
+    Command {
+        usage: "features",
+        aliases: &["list features"],
+        dm: false,
+    },
+    Command {
+        usage: "feature enable|disable <feature>",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "block links <pattern>",
+        aliases: &["block link", "ban links", "ban link"],
+        dm: false,
+    },
+    Command {
+        usage: "unblock links <pattern>",
+        aliases: &["unblock link", "unban links", "unban link"],
+        dm: false,
+    },
+    Command {
+        usage: "blocked links",
+        aliases: &["list blocked links", "link blocklist"],
+        dm: false,
+    },
+    Command {
+        usage: "quick command schedule|join|remove <emoji>",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "host verification off|alert|enforce",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "community digest channel|schedule|template",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "crosspost webhook <url>|off",
+        aliases: &["cross-post webhook", "cross post webhook"],
+        dm: false,
+    },
+    Command {
+        usage: "crosspost template <template>|default",
+        aliases: &["cross-post template", "cross post template"],
+        dm: false,
+    },
+    Command {
+        usage: "quarantine @user",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "release @user",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "ignore @user",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "unignore @user",
+        aliases: &["stop ignoring"],
+        dm: false,
+    },
+    Command {
+        usage: "ignored users",
+        aliases: &["ignore list", "list ignored"],
+        dm: false,
+    },
+    Command {
+        usage: "invite create <label>",
+        aliases: &["create invite", "new invite"],
+        dm: false,
+    },
+    Command {
+        usage: "invite stats",
+        aliases: &["invites", "invite sources"],
+        dm: false,
+    },
+    Command {
+        usage: "usage report",
+        aliases: &["usage stats", "command usage"],
+        dm: false,
+    },
+    Command {
+        usage: "storage report",
+        aliases: &["memory report", "redis report"],
+        dm: false,
+    },
+    Command {
+        usage: "list duplicate events",
+        aliases: &["duplicate events", "duplicates"],
+        dm: false,
+    },
+    Command {
+        usage: "maintenance on|off",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "approve household @user",
+        aliases: &["accept household"],
+        dm: false,
+    },
+    Command {
+        usage: "reject household @user",
+        aliases: &["deny household", "decline household"],
+        dm: false,
+    },
+    Command {
+        usage: "household requests",
+        aliases: &[
+            "households",
+            "pending households",
+            "list household requests",
+        ],
+        dm: false,
+    },
+    Command {
+        usage: "suggest links",
+        aliases: &["link suggestions", "match members"],
+        dm: false,
+    },
+    Command {
+        usage: "inactive members <days>",
+        aliases: &[
+            "inactive users",
+            "unlinked members",
+            "list inactive members",
+        ],
+        dm: false,
+    },
+    Command {
+        usage: "nudge inactive members <days>",
+        aliases: &[
+            "nudge inactive users",
+            "nudge unlinked members",
+            "nudge inactive",
+        ],
+        dm: false,
+    },
+    Command {
+        usage: "whois @user",
+        aliases: &["who is", "user info", "member info"],
+        dm: false,
+    },
+    Command {
+        usage: "channels @user",
+        aliases: &["channels of", "user channels", "member channels"],
+        dm: false,
+    },
+    Command {
+        usage: "dues role @role",
+        aliases: &["gate role", "dues gate", "paid role"],
+        dm: false,
+    },
+    Command {
+        usage: "confirm link <Meetup ID>",
+        aliases: &["accept link", "approve link"],
+        dm: false,
+    },
+    Command {
+        usage: "dismiss link <Meetup ID>",
+        aliases: &["reject link", "decline link"],
+        dm: false,
+    },
+    Command {
+        usage: "approve creation",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "check permissions",
+        aliases: &[],
+        dm: false,
+    },
+    Command {
+        usage: "organizer tokens",
+        aliases: &["organiser tokens"],
+        dm: false,
+    },
+    Command {
+        usage: "revoke organizer token <meetup id>",
+        aliases: &["revoke organiser token"],
+        dm: false,
+    },
+];
+
+// Words that people like to add to commands, only dropped at the end
+const FILLER_WORDS: &[&str] = &["please", "pls", "plz", "thanks", "thank you", "thx"];
+
+fn is_argument(word: &str) -> bool {
+    word.starts_with('<') || word.starts_with('@') || word.starts_with('"') || word.contains('|')
+}
+
+impl Command {
+    // The words of the usage up to the first argument
+    fn literal_words(&self) -> Vec<&'static str> {
+        self.usage
+            .split_whitespace()
+            .take_while(|word| !is_argument(word))
+            .collect()
+    }
+}
+
+fn starts_with_words(words: &[&str], prefix: &str) -> bool {
+    let prefix: Vec<_> = prefix.split_whitespace().collect();
+    prefix.len() <= words.len()
+        && words
+            .iter()
+            .zip(&prefix)
+            .all(|(word, prefix_word)| word.eq_ignore_ascii_case(prefix_word))
+}
+
+fn strip_fillers(text: &str) -> &str {
+    let mut text =
+        text.trim_end_matches(|c: char| c == '!' || c == '?' || c == '.' || c.is_whitespace());
+    loop {
+        // ASCII only, such that the byte offsets stay the same
+        let lowercase_text = text.to_ascii_lowercase();
+        let filler = FILLER_WORDS.iter().find(|filler| {
+            lowercase_text.ends_with(*filler)
+                && (text.len() == filler.len()
+                    || lowercase_text[..text.len() - filler.len()].ends_with(char::is_whitespace))
+        });
+        match filler {
+            Some(filler) => {
+                text = text[..text.len() - filler.len()]
+                    .trim_end_matches(|c: char| c == ',' || c.is_whitespace());
+            }
+            None => return text,
+        }
+    }
+}
+
+// Rewrites the text of a command (without the bot mention) that didn't match
+// any command into the canonical form. Returns None if there is nothing to
+// rewrite, in which case the rewritten text wouldn't match either.
+pub fn resolve(text: &str, is_dm: bool) -> Option<String> {
+    // Without the filler words, the text might match as is. If it doesn't,
+    // the aliases are tried on the next attempt.
+    let stripped = strip_fillers(text.trim());
+    if stripped.len() < text.trim().len() {
+        return Some(stripped.to_string());
+    }
+    let words: Vec<_> = stripped.split_whitespace().collect();
+    // The longest alias wins, such that "link help" isn't taken for "link"
+    // followed by "help"
+    COMMANDS
+        .iter()
+        .filter(|command| command.dm || !is_dm)
+        // Aliases like "link" are a prefix of the command itself
+        .filter(|command| !starts_with_words(&words, &command.literal_words().join(" ")))
+        .flat_map(|command| command.aliases.iter().map(move |alias| (command, alias)))
+        .filter(|(_, alias)| starts_with_words(&words, alias))
+        .min_by_key(|(_, alias)| std::cmp::Reverse(alias.split_whitespace().count()))
+        .map(|(command, alias)| {
+            let num_alias_words = alias.split_whitespace().count();
+            let mut resolved = command.literal_words();
+            resolved.extend(&words[num_alias_words..]);
+            resolved.join(" ")
+        })
+}
+
+// The literal words of the command that the text is an invocation of, e.g.
+// "add host" for "add host @user". The longest match wins, such that "roll log"
+// isn't taken for "roll". None for texts that only match once aliases or
+// filler words are resolved, since `resolve` rewrites those anyway.
+pub fn identify(text: &str, is_dm: bool) -> Option<String> {
+    if strip_fillers(text.trim()).len() < text.trim().len() {
+        return None;
+    }
+    let words: Vec<_> = text.split_whitespace().collect();
+    COMMANDS
+        .iter()
+        .filter(|command| command.dm || !is_dm)
+        .map(Command::literal_words)
+        .filter(|literal_words| {
+            !literal_words.is_empty() && starts_with_words(&words, &literal_words.join(" "))
+        })
+        .max_by_key(|literal_words| literal_words.len())
+        .map(|literal_words| literal_words.join(" "))
+}
+
+// The names that `identify` returns, for all commands
+pub fn command_names() -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for command in COMMANDS {
+        let name = command.literal_words().join(" ");
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+// Whether a command or one of its aliases starts with this word, e.g. for
+// names that hosts give to their own replies, which would never be reached
+pub fn is_command_word(word: &str) -> bool {
+    COMMANDS.iter().any(|command| {
+        std::iter::once(command.usage)
+            .chain(command.aliases.iter().cloned())
+            .filter_map(|text| text.split_whitespace().next())
+            .any(|first_word| !is_argument(first_word) && first_word.eq_ignore_ascii_case(word))
+    })
+}
+
+// The usage of the command that is closest to the text, if any is close
+// enough to be a typo
+pub fn suggest(text: &str, is_dm: bool) -> Option<&'static str> {
+    let text = text.trim().to_ascii_lowercase();
+    let words: Vec<_> = text.split_whitespace().collect();
+    COMMANDS
+        .iter()
+        .filter(|command| command.dm || !is_dm)
+        .filter_map(|command| {
+            let literal = command.literal_words().join(" ");
+            let num_literal_words = command.literal_words().len();
+            // Compare with the same number of words, and also with all words
+            // squashed together for things like "linkmeetup"
+            let typed = words
+                .iter()
+                .take(num_literal_words)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let distance = std::cmp::min(
+                edit_distance(&typed, &literal),
+                edit_distance(&words.concat(), &literal.replace(' ', "")),
+            );
+            let max_distance = std::cmp::max(1, literal.chars().count() / 4);
+            if distance <= max_distance {
+                Some((distance, command.usage))
+            } else {
+                None
+            }
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, usage)| usage)
+}
+
+// Levenshtein distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = Vec::with_capacity(b.len() + 1);
+        row.push(i + 1);
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_row[j] + if a_char == *b_char { 0 } else { 1 };
+            row.push(substitution.min(previous_row[j + 1] + 1).min(row[j] + 1));
+        }
+        previous_row = row;
+    }
+    previous_row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_aliases() {
+        assert_eq!(
+            resolve("connect meetup", true),
+            Some("link meetup".to_string())
+        );
+        assert_eq!(
+            resolve("kick <@5678> <@9012>", false),
+            Some("remove <@5678> <@9012>".to_string())
+        );
+        // Commands that don't work in DMs aren't resolved there
+        assert_eq!(resolve("kick <@5678>", true), None);
+        assert_eq!(resolve("link meetup", true), None);
+        assert_eq!(
+            resolve("link meetup please", true),
+            Some("link meetup".to_string())
+        );
+    }
+
+    #[test]
+    fn prefers_the_longest_alias() {
+        // "link" is an alias of "link meetup"
+        assert_eq!(resolve("link help", true), Some("linking help".to_string()));
+        assert_eq!(
+            resolve("link my meetup", true),
+            Some("link meetup".to_string())
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use redis::{Commands, PipelineCommands};
+use std::collections::{HashMap, HashSet};
+
+// Organizers want to know which features are actually used, without sending
+// anything to an external analytics service. Every command is counted per
+// day, together with the members who used it, under the name of its literal
+// words (see discord_command_aliases::identify). The counts are kept for
+// RETENTION_DAYS, and "usage report" sums up the last REPORT_DAYS.
+pub const REPORT_DAYS: i64 = 30;
+const RETENTION_DAYS: i64 = REPORT_DAYS + 5;
+pub const RETENTION_SECS: usize = (RETENTION_DAYS * 24 * 60 * 60) as usize;
+
+fn redis_day_key(day: chrono::NaiveDate) -> String {
+    format!("command_usage:{}", day.format("%Y-%m-%d"))
+}
+
+fn redis_day_users_key(day: chrono::NaiveDate, command: &str) -> String {
+    format!("command_usage:{}:users:{}", day.format("%Y-%m-%d"), command)
+}
+
+pub struct CommandUsage {
+    pub command: String,
+    pub num_uses: u64,
+    pub num_users: usize,
+}
+
+pub struct UsageReport {
+    // Most used first
+    pub commands: Vec<CommandUsage>,
+    pub num_users: usize,
+    // Commands that nobody used in the reported period
+    pub unused_commands: Vec<String>,
+}
+
+pub fn record<C: redis::ConnectionLike>(
+    con: &mut C,
+    command: &str,
+    user_id: u64,
+) -> Result<(), crate::BoxedError> {
+    let today = chrono::Utc::now().date().naive_utc();
+    let day_key = redis_day_key(today);
+    let users_key = redis_day_users_key(today, command);
+    let _: () = redis::pipe()
+        .hincr(&day_key, command, 1)
+        .ignore()
+        .expire(&day_key, RETENTION_SECS)
+        .ignore()
+        .sadd(&users_key, user_id)
+        .ignore()
+        .expire(&users_key, RETENTION_SECS)
+        .ignore()
+        .query(con)?;
+    Ok(())
+}
+
+pub fn report<C: redis::ConnectionLike>(con: &mut C) -> Result<UsageReport, crate::BoxedError> {
+    let today = chrono::Utc::now().date().naive_utc();
+    let days: Vec<_> = (0..REPORT_DAYS)
+        .map(|days_ago| today - chrono::Duration::days(days_ago))
+        .collect();
+    let mut pipe = redis::pipe();
+    for &day in &days {
+        pipe.hgetall(redis_day_key(day));
+    }
+    let day_counts: Vec<HashMap<String, u64>> = pipe.query(con)?;
+    // The members of each day, to count every member once per command
+    let mut pipe = redis::pipe();
+    let mut num_uses: HashMap<String, u64> = HashMap::new();
+    for (&day, counts) in days.iter().zip(&day_counts) {
+        for (command, count) in counts {
+            *num_uses.entry(command.clone()).or_insert(0) += count;
+            pipe.smembers(redis_day_users_key(day, command));
+        }
+    }
+    let day_users: Vec<Vec<u64>> = if num_uses.is_empty() {
+        vec![]
+    } else {
+        pipe.query(con)?
+    };
+    let mut users: HashMap<&str, HashSet<u64>> = HashMap::new();
+    let day_commands = day_counts.iter().flat_map(|counts| counts.keys());
+    for (command, user_ids) in day_commands.zip(day_users) {
+        users.entry(command.as_str()).or_default().extend(user_ids);
+    }
+    let num_users = users
+        .values()
+        .flat_map(|user_ids| user_ids.iter())
+        .collect::<HashSet<_>>()
+        .len();
+    let mut commands: Vec<_> = num_uses
+        .iter()
+        .map(|(command, &num_uses)| CommandUsage {
+            command: command.clone(),
+            num_uses: num_uses,
+            num_users: users.get(command.as_str()).map(HashSet::len).unwrap_or(0),
+        })
+        .collect();
+    commands.sort_by(|usage1, usage2| {
+        usage2
+            .num_uses
+            .cmp(&usage1.num_uses)
+            .then_with(|| usage1.command.cmp(&usage2.command))
+    });
+    let unused_commands = crate::discord_command_aliases::command_names()
+        .into_iter()
+        .filter(|command| !num_uses.contains_key(command))
+        .collect();
+    Ok(UsageReport {
+        commands: commands,
+        num_users: num_users,
+        unused_commands: unused_commands,
+    })
+}
@@ -0,0 +1,364 @@
+use crate::strings;
+use chrono::{Datelike, Timelike};
+use redis::Commands;
+use serenity::model::id::ChannelId;
+
+// Once a week, the bot posts a digest of what's new in the community to a
+// public channel chosen by the organizers: newly opened games that still have
+// free spots, newly created campaign channels and this week's one-shots.
+// Organizers can change the day, the hour and the template of the post.
+const ANNOUNCED_SERIES_KEY: &'static str = "community_digest:announced_event_series";
+const ANNOUNCED_CHANNELS_KEY: &'static str = "community_digest:announced_discord_channels";
+const LAST_POSTED_KEY: &'static str = "community_digest:last_posted_time";
+const DEFAULT_WEEKDAY: chrono::Weekday = chrono::Weekday::Mon;
+const DEFAULT_HOUR: u32 = 10;
+// Discord limits messages to 2000 characters
+const MAX_MESSAGE_CHARS: usize = 1900;
+
+pub struct Config {
+    pub channel_id: Option<ChannelId>,
+    pub weekday: chrono::Weekday,
+    pub hour: u32,
+    pub template: String,
+}
+
+const WEEKDAYS: [chrono::Weekday; 7] = [
+    chrono::Weekday::Mon,
+    chrono::Weekday::Tue,
+    chrono::Weekday::Wed,
+    chrono::Weekday::Thu,
+    chrono::Weekday::Fri,
+    chrono::Weekday::Sat,
+    chrono::Weekday::Sun,
+];
+
+// Accepts full and abbreviated day names ("monday", "Mon")
+pub fn parse_weekday(name: &str) -> Option<chrono::Weekday> {
+    if name.len() < 3 {
+        return None;
+    }
+    WEEKDAYS
+        .iter()
+        .find(|weekday| {
+            let full_name = weekday_name(**weekday);
+            full_name.starts_with(&name.to_lowercase()) && name.len() <= full_name.len()
+        })
+        .cloned()
+}
+
+pub fn weekday_name(weekday: chrono::Weekday) -> &'static str {
+    match weekday {
+        chrono::Weekday::Mon => "monday",
+        chrono::Weekday::Tue => "tuesday",
+        chrono::Weekday::Wed => "wednesday",
+        chrono::Weekday::Thu => "thursday",
+        chrono::Weekday::Fri => "friday",
+        chrono::Weekday::Sat => "saturday",
+        chrono::Weekday::Sun => "sunday",
+    }
+}
+
+fn redis_config_key() -> String {
+    format!("guild:{}:community_digest", crate::discord_sync::GUILD_ID.0)
+}
+
+pub fn config<C: redis::ConnectionLike>(con: &mut C) -> Result<Config, crate::BoxedError> {
+    let (channel_id, weekday, hour, template): (
+        Option<u64>,
+        Option<String>,
+        Option<u32>,
+        Option<String>,
+    ) = con.hget(
+        redis_config_key(),
+        &["discord_channel", "weekday", "hour", "template"],
+    )?;
+    Ok(Config {
+        channel_id: channel_id.map(ChannelId),
+        weekday: weekday
+            .and_then(|weekday| parse_weekday(&weekday))
+            .unwrap_or(DEFAULT_WEEKDAY),
+        hour: hour.filter(|hour| *hour < 24).unwrap_or(DEFAULT_HOUR),
+        template: template
+            .unwrap_or_else(|| strings::COMMUNITY_DIGEST_DEFAULT_TEMPLATE.to_string()),
+    })
+}
+
+// Setting a channel starts the digest. Everything that exists at this point
+// is considered to be known already, so the first post isn't a wall of text.
+pub fn set_channel<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: Option<ChannelId>,
+) -> Result<(), crate::BoxedError> {
+    match channel_id {
+        Some(channel_id) => {
+            let _: () = redis::pipe()
+                .hset(redis_config_key(), "discord_channel", channel_id.0)
+                .ignore()
+                .sunionstore(
+                    ANNOUNCED_SERIES_KEY,
+                    vec![ANNOUNCED_SERIES_KEY, "event_series"],
+                )
+                .ignore()
+                .sunionstore(
+                    ANNOUNCED_CHANNELS_KEY,
+                    vec![ANNOUNCED_CHANNELS_KEY, "discord_channels"],
+                )
+                .ignore()
+                .query(con)?;
+        }
+        None => {
+            let _: () = con.hdel(redis_config_key(), "discord_channel")?;
+        }
+    }
+    Ok(())
+}
+
+pub fn set_schedule<C: redis::ConnectionLike>(
+    con: &mut C,
+    weekday: chrono::Weekday,
+    hour: u32,
+) -> Result<(), crate::BoxedError> {
+    let _: () = con.hset_multiple(
+        redis_config_key(),
+        &[
+            ("weekday", weekday_name(weekday).to_string()),
+            ("hour", hour.to_string()),
+        ],
+    )?;
+    Ok(())
+}
+
+// Passing None restores the default template
+pub fn set_template<C: redis::ConnectionLike>(
+    con: &mut C,
+    template: Option<&str>,
+) -> Result<(), crate::BoxedError> {
+    match template {
+        Some(template) => {
+            let _: () = con.hset(redis_config_key(), "template", template)?;
+        }
+        None => {
+            let _: () = con.hdel(redis_config_key(), "template")?;
+        }
+    }
+    Ok(())
+}
+
+// Checks every hour whether it is time for the digest
+pub fn create_community_digest_task(
+    redis_client: crate::redis_namespace::Client,
+    discord_api: crate::discord_bot::CacheAndHttp,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    move |_ctx| {
+        if let Err(err) = post_community_digest_if_due(&redis_client, &discord_api) {
+            eprintln!("Community digest task failed: {}", err);
+            crate::error_reporting::report(&err, &[("task", "community_digest")]);
+        }
+        let now = white_rabbit::Utc::now();
+        let next_hour =
+            (now + white_rabbit::Duration::hours(1))
+                .date()
+                .and_hms((now.hour() + 1) % 24, 0, 0);
+        white_rabbit::DateResult::Repeat(next_hour)
+    }
+}
+
+fn post_community_digest_if_due(
+    redis_client: &crate::redis_namespace::Client,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let mut con = redis_client.get_connection()?;
+    let config = config(&mut con)?;
+    let channel_id = match config.channel_id {
+        Some(channel_id) => channel_id,
+        None => return Ok(()),
+    };
+    let now = chrono::Utc::now();
+    if now.weekday() != config.weekday || now.hour() != config.hour {
+        return Ok(());
+    }
+    // Don't post twice if the task runs more than once in the hour (e.g.
+    // after a restart)
+    let last_posted_time: Option<String> = con.get(LAST_POSTED_KEY)?;
+    if let Some(last_posted_time) = last_posted_time {
+        let last_posted_time =
+            chrono::DateTime::parse_from_rfc3339(&last_posted_time)?.with_timezone(&chrono::Utc);
+        if now - last_posted_time < chrono::Duration::hours(23) {
+            return Ok(());
+        }
+    }
+    post_community_digest(channel_id, &config.template, &mut con, discord_api)?;
+    let _: () = con.set(LAST_POSTED_KEY, now.to_rfc3339())?;
+    Ok(())
+}
+
+pub fn post_community_digest(
+    channel_id: ChannelId,
+    template: &str,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    // Forget about series and channels that don't exist anymore
+    let _: () = redis::pipe()
+        .sinterstore(
+            ANNOUNCED_SERIES_KEY,
+            vec![ANNOUNCED_SERIES_KEY, "event_series"],
+        )
+        .ignore()
+        .sinterstore(
+            ANNOUNCED_CHANNELS_KEY,
+            vec![ANNOUNCED_CHANNELS_KEY, "discord_channels"],
+        )
+        .ignore()
+        .query(con)?;
+    let now = chrono::Utc::now();
+    let event_series: Vec<String> = con.smembers("event_series")?;
+    let mut new_games = vec![];
+    let mut one_shots = vec![];
+    let mut announced_series_ids = vec![];
+    for series_id in &event_series {
+        let series_type: Option<String> = con.get(format!("event_series:{}:type", series_id))?;
+        let upcoming_events = upcoming_events(series_id, con)?;
+        let series_tags = crate::event_tags::series_tags(con, series_id)?;
+        // Newly opened games are announced as soon as their next session has
+        // free spots
+        let already_announced: bool = con.sismember(ANNOUNCED_SERIES_KEY, series_id)?;
+        if !already_announced {
+            if let Some(next_event) = upcoming_events.first() {
+                if next_event.free_spots != Some(0) {
+                    new_games.push(strings::COMMUNITY_DIGEST_GAME_LINE(
+                        &next_event.name,
+                        next_event.time,
+                        &next_event.link,
+                        next_event.free_spots,
+                        &series_tags,
+                    ));
+                    announced_series_ids.push(series_id.clone());
+                }
+            }
+        }
+        if series_type.as_ref().map(String::as_str) == Some("adventure") {
+            for event in &upcoming_events {
+                if event.time < now + chrono::Duration::days(7) {
+                    one_shots.push(strings::COMMUNITY_DIGEST_GAME_LINE(
+                        &event.name,
+                        event.time,
+                        &event.link,
+                        event.free_spots,
+                        &series_tags,
+                    ));
+                }
+            }
+        }
+    }
+    let mut new_channels = vec![];
+    let mut announced_channel_ids = vec![];
+    let channel_ids: Vec<u64> = con.sdiff(vec!["discord_channels", ANNOUNCED_CHANNELS_KEY])?;
+    for new_channel_id in channel_ids {
+        let series_id: Option<String> =
+            con.get(format!("discord_channel:{}:event_series", new_channel_id))?;
+        let series_type: Option<String> = match series_id {
+            Some(series_id) => con.get(format!("event_series:{}:type", series_id))?,
+            None => None,
+        };
+        if series_type.as_ref().map(String::as_str) == Some("campaign") {
+            new_channels.push(strings::COMMUNITY_DIGEST_CHANNEL_LINE(new_channel_id));
+        }
+        // Adventure channels are covered by the one-shots
+        announced_channel_ids.push(new_channel_id);
+    }
+    let section = |lines: &[String]| {
+        if lines.is_empty() {
+            strings::COMMUNITY_DIGEST_NOTHING.to_string()
+        } else {
+            lines.join("\n")
+        }
+    };
+    let content = template
+        .replace("{new_games}", &section(&new_games))
+        .replace("{new_channels}", &section(&new_channels))
+        .replace("{one_shots}", &section(&one_shots));
+    for chunk in split_message(&content) {
+        channel_id.say(&discord_api.http, chunk)?;
+    }
+    // Only remember what was announced once the post went through
+    if !announced_series_ids.is_empty() {
+        let _: () = con.sadd(ANNOUNCED_SERIES_KEY, announced_series_ids)?;
+    }
+    if !announced_channel_ids.is_empty() {
+        let _: () = con.sadd(ANNOUNCED_CHANNELS_KEY, announced_channel_ids)?;
+    }
+    Ok(())
+}
+
+// Also used for cross-posting open games
+pub struct UpcomingEvent {
+    pub id: String,
+    pub name: String,
+    pub time: chrono::DateTime<chrono::Utc>,
+    pub link: String,
+    // None if the event has no RSVP limit
+    pub free_spots: Option<u32>,
+}
+
+pub fn upcoming_events(
+    series_id: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Vec<UpcomingEvent>, crate::BoxedError> {
+    let redis_series_events_key = format!("event_series:{}:meetup_events", series_id);
+    let event_ids: Vec<String> = con.smembers(&redis_series_events_key)?;
+    let now = chrono::Utc::now();
+    let mut upcoming = Vec::with_capacity(event_ids.len());
+    for event_id in event_ids {
+        let redis_event_key = format!("meetup_event:{}", event_id);
+        let (time, name, link, rsvp_limit): (
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<u32>,
+        ) = con.hget(&redis_event_key, &["time", "name", "link", "rsvp_limit"])?;
+        if let (Some(time), Some(name), Some(link)) = (time, name, link) {
+            let time = chrono::DateTime::parse_from_rfc3339(&time)?.with_timezone(&chrono::Utc);
+            if time < now {
+                continue;
+            }
+            let free_spots = match rsvp_limit {
+                Some(rsvp_limit) => {
+                    let num_rsvps: u32 =
+                        con.scard(format!("meetup_event:{}:meetup_users", event_id))?;
+                    Some(rsvp_limit.saturating_sub(num_rsvps))
+                }
+                None => None,
+            };
+            upcoming.push(UpcomingEvent {
+                id: event_id,
+                name: name,
+                time: time,
+                link: link,
+                free_spots: free_spots,
+            });
+        }
+    }
+    upcoming.sort_unstable_by_key(|event| event.time);
+    Ok(upcoming)
+}
+
+// Splits the message at line breaks such that each part fits into a Discord
+// message
+fn split_message(content: &str) -> Vec<String> {
+    let mut chunks = vec![];
+    let mut chunk = String::new();
+    for line in content.lines() {
+        if !chunk.is_empty() && chunk.len() + line.len() + 1 > MAX_MESSAGE_CHARS {
+            chunks.push(std::mem::replace(&mut chunk, String::new()));
+        }
+        if !chunk.is_empty() {
+            chunk.push('\n');
+        }
+        chunk.push_str(line);
+    }
+    if !chunk.trim().is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
@@ -0,0 +1,159 @@
+use crate::strings;
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::{channel::Reaction, channel::ReactionType, id::ChannelId, id::UserId},
+    prelude::*,
+};
+
+pub const CONFIRM_EMOJI: &'static str = "\u{2705}";
+pub const CANCEL_EMOJI: &'static str = "\u{274C}";
+
+// Pending actions expire if they are not confirmed within five minutes
+pub const PENDING_ACTION_TTL_SECS: usize = 5 * 60;
+
+// An action that only gets executed once the user who requested it
+// confirms it by reacting to the bot's confirmation prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PendingAction {
+    CloseChannel,
+    UnlinkMeetup {
+        discord_id: u64,
+        is_organizer_command: bool,
+    },
+    RemoveUser {
+        discord_id: u64,
+    },
+    NudgeInactiveMembers {
+        min_days: u32,
+    },
+    CleanupRoles,
+    DeleteUserData {
+        discord_id: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingActionState {
+    action: PendingAction,
+    requested_by: u64,
+    channel_id: u64,
+}
+
+// Posts a confirmation prompt into the specified channel and remembers the
+// pending action in Redis until it is either confirmed, cancelled or expired
+pub fn request_confirmation(
+    ctx: &Context,
+    channel_id: ChannelId,
+    requested_by: UserId,
+    action: PendingAction,
+    prompt: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> crate::Result<()> {
+    let prompt_message = channel_id.say(
+        &ctx.http,
+        strings::CONFIRMATION_PROMPT(prompt, CONFIRM_EMOJI, CANCEL_EMOJI),
+    )?;
+    let state = PendingActionState {
+        action: action,
+        requested_by: requested_by.0,
+        channel_id: channel_id.0,
+    };
+    let state = serde_json::to_string(&state)?;
+    let redis_pending_action_key = format!("pending_action:{}", prompt_message.id.0);
+    let _: () = redis_connection.set_ex(
+        &redis_pending_action_key,
+        state,
+        PENDING_ACTION_TTL_SECS,
+    )?;
+    let _ = prompt_message.react(ctx, CONFIRM_EMOJI);
+    let _ = prompt_message.react(ctx, CANCEL_EMOJI);
+    Ok(())
+}
+
+// Checks whether a reaction confirms or cancels a pending action and if so,
+// executes or discards it
+pub fn handle_reaction(
+    ctx: &Context,
+    reaction: &Reaction,
+    redis_client: &crate::redis_namespace::Client,
+) -> crate::Result<()> {
+    let confirmed = match &reaction.emoji {
+        ReactionType::Unicode(emoji) if emoji == CONFIRM_EMOJI => true,
+        ReactionType::Unicode(emoji) if emoji == CANCEL_EMOJI => false,
+        _ => return Ok(()),
+    };
+    let mut redis_connection = redis_client.get_connection()?;
+    let redis_pending_action_key = format!("pending_action:{}", reaction.message_id.0);
+    let state: Option<String> = redis_connection.get(&redis_pending_action_key)?;
+    let state: PendingActionState = match state {
+        Some(state) => serde_json::from_str(&state)?,
+        None => return Ok(()),
+    };
+    // Only the user who requested the action can confirm or cancel it
+    if state.requested_by != reaction.user_id.0 {
+        return Ok(());
+    }
+    // Take the pending action out of Redis, such that it can only be executed once
+    let deleted: u32 = redis_connection.del(&redis_pending_action_key)?;
+    if deleted == 0 {
+        return Ok(());
+    }
+    let channel_id = ChannelId(state.channel_id);
+    let _ = channel_id.delete_message(&ctx.http, reaction.message_id);
+    if !confirmed {
+        let _ = channel_id.say(&ctx.http, strings::CONFIRMATION_CANCELLED);
+        return Ok(());
+    }
+    // The maintenance mode might have been turned on in the meantime
+    if crate::maintenance_mode::is_active(&mut redis_connection)? {
+        let _ = channel_id.say(&ctx.http, strings::MAINTENANCE_MODE_ACTIVE);
+        return Ok(());
+    }
+    match state.action {
+        PendingAction::CloseChannel => crate::discord_bot::Handler::close_channel_confirmed(
+            ctx,
+            channel_id,
+            &mut redis_connection,
+        ),
+        PendingAction::UnlinkMeetup {
+            discord_id,
+            is_organizer_command,
+        } => crate::discord_bot::Handler::unlink_meetup_confirmed(
+            ctx,
+            channel_id,
+            is_organizer_command,
+            discord_id,
+            &mut redis_connection,
+        ),
+        PendingAction::RemoveUser { discord_id } => {
+            crate::discord_bot::Handler::remove_user_confirmed(
+                ctx,
+                channel_id,
+                discord_id,
+                &mut redis_connection,
+            )
+        }
+        PendingAction::NudgeInactiveMembers { min_days } => {
+            crate::discord_bot::Handler::nudge_inactive_members_confirmed(
+                ctx,
+                channel_id,
+                min_days,
+                &mut redis_connection,
+            )
+        }
+        PendingAction::CleanupRoles => crate::discord_bot::Handler::cleanup_roles_confirmed(
+            ctx,
+            channel_id,
+            &mut redis_connection,
+        ),
+        PendingAction::DeleteUserData { discord_id } => {
+            crate::discord_bot::Handler::delete_my_data_confirmed(
+                ctx,
+                channel_id,
+                discord_id,
+                &mut redis_connection,
+            )
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::strings;
+use redis::{Commands, PipelineCommands};
+use serenity::http::CacheHttp;
+
+// How many channels and roles a single Discord sync may create without an
+// organizer approving it. A broken Meetup sync should not be able to flood
+// the server.
+pub const MAX_NEW_CHANNELS_PER_SYNC: u64 = 5;
+pub const MAX_NEW_ROLES_PER_SYNC: u64 = 2 * MAX_NEW_CHANNELS_PER_SYNC;
+
+// Hard limits imposed by Discord
+const GUILD_CHANNEL_LIMIT: u64 = 500;
+pub const GUILD_ROLE_LIMIT: u64 = 250;
+// Organizers get warned once the guild reaches this share of a limit
+const GUILD_LIMIT_WARNING_PERCENT: u64 = 90;
+
+const REDIS_APPROVAL_KEY: &'static str = "discord_sync:creation_approved";
+const REDIS_EXCEEDED_NOTIFIED_KEY: &'static str = "discord_sync:creation_budget_notified";
+const REDIS_LIMIT_WARNED_KEY: &'static str = "discord_sync:guild_limit_warned";
+
+// Keeps track of how many more channels and roles the current sync may create
+pub struct CreationBudget {
+    num_channels: u64,
+    num_roles: u64,
+    channels_left: u64,
+    roles_left: u64,
+    denied_channels: u64,
+    denied_roles: u64,
+}
+
+impl CreationBudget {
+    // Determines the budget for one sync. An organizer's approval lifts the
+    // per-sync budget for exactly one sync, Discord's limits always apply.
+    pub fn new(
+        con: &mut crate::redis_namespace::Connection,
+        discord_api: &crate::discord_bot::CacheAndHttp,
+    ) -> Result<Self, crate::BoxedError> {
+        let (approved,): (bool,) = redis::pipe()
+            .exists(REDIS_APPROVAL_KEY)
+            .del(REDIS_APPROVAL_KEY)
+            .ignore()
+            .query(con)?;
+        let num_channels = discord_api
+            .http()
+            .get_channels(crate::discord_sync::GUILD_ID.0)?
+            .len() as u64;
+        let num_roles = discord_api
+            .http()
+            .get_guild_roles(crate::discord_sync::GUILD_ID.0)?
+            .len() as u64;
+        let channels_left = GUILD_CHANNEL_LIMIT.saturating_sub(num_channels);
+        let roles_left = GUILD_ROLE_LIMIT.saturating_sub(num_roles);
+        let (channels_left, roles_left) = if approved {
+            println!("Discord sync: channel and role creation was approved by an organizer");
+            (channels_left, roles_left)
+        } else {
+            (
+                std::cmp::min(channels_left, MAX_NEW_CHANNELS_PER_SYNC),
+                std::cmp::min(roles_left, MAX_NEW_ROLES_PER_SYNC),
+            )
+        };
+        Ok(CreationBudget {
+            num_channels: num_channels,
+            num_roles: num_roles,
+            channels_left: channels_left,
+            roles_left: roles_left,
+            denied_channels: 0,
+            denied_roles: 0,
+        })
+    }
+
+    // Returns whether another channel may be created and if so, deducts it from the budget
+    pub fn try_spend_channel(&mut self) -> bool {
+        if self.channels_left == 0 {
+            self.denied_channels += 1;
+            return false;
+        }
+        self.channels_left -= 1;
+        self.num_channels += 1;
+        true
+    }
+
+    // How many channels and roles this sync was not allowed to create so far
+    pub fn num_denied(&self) -> u64 {
+        self.denied_channels + self.denied_roles
+    }
+
+    // Returns whether another role may be created and if so, deducts it from the budget
+    pub fn try_spend_role(&mut self) -> bool {
+        if self.roles_left == 0 {
+            self.denied_roles += 1;
+            return false;
+        }
+        self.roles_left -= 1;
+        self.num_roles += 1;
+        true
+    }
+
+    // Lets the organizers know if the sync wanted to create more than it was
+    // allowed to and if the guild is getting close to Discord's limits.
+    // Each of these is only announced once until the situation resolves.
+    pub fn finish(
+        self,
+        con: &mut crate::redis_namespace::Connection,
+        discord_api: &crate::discord_bot::CacheAndHttp,
+    ) -> Result<(), crate::BoxedError> {
+        if self.denied_channels > 0 || self.denied_roles > 0 {
+            println!(
+                "Discord sync: skipped creating {} channels and {} roles due to the creation budget",
+                self.denied_channels, self.denied_roles
+            );
+            let newly_notified: bool = con.set_nx(REDIS_EXCEEDED_NOTIFIED_KEY, true)?;
+            if newly_notified {
+                crate::discord_notifications::notify_organizers(
+                    discord_api,
+                    &strings::CREATION_BUDGET_EXCEEDED_NOTIFICATION(
+                        self.denied_channels,
+                        self.denied_roles,
+                    ),
+                    con,
+                )?;
+            }
+        } else {
+            let _: () = con.del(REDIS_EXCEEDED_NOTIFIED_KEY)?;
+        }
+        let close_to_limit = self.num_channels * 100
+            >= GUILD_CHANNEL_LIMIT * GUILD_LIMIT_WARNING_PERCENT
+            || self.num_roles * 100 >= GUILD_ROLE_LIMIT * GUILD_LIMIT_WARNING_PERCENT;
+        if close_to_limit {
+            let newly_warned: bool = con.set_nx(REDIS_LIMIT_WARNED_KEY, true)?;
+            if newly_warned {
+                crate::discord_notifications::notify_organizers(
+                    discord_api,
+                    &strings::GUILD_LIMIT_WARNING_NOTIFICATION(
+                        self.num_channels,
+                        GUILD_CHANNEL_LIMIT,
+                        self.num_roles,
+                        GUILD_ROLE_LIMIT,
+                    ),
+                    con,
+                )?;
+            }
+        } else {
+            let _: () = con.del(REDIS_LIMIT_WARNED_KEY)?;
+        }
+        Ok(())
+    }
+}
+
+// Lifts the per-sync creation budget for the next Discord sync
+pub fn approve_next_sync<C: redis::ConnectionLike>(con: &mut C) -> Result<(), crate::BoxedError> {
+    let _: () = con.set(REDIS_APPROVAL_KEY, true)?;
+    Ok(())
+}
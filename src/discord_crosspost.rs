@@ -0,0 +1,248 @@
+use lazy_static::lazy_static;
+use redis::{Commands, PipelineCommands};
+use serde::Deserialize;
+
+// Communities with a public hub server next to their member server can have
+// the games with free spots cross-posted there. The posts go through a
+// Discord webhook of the other server (so the bot doesn't need to be a member
+// there) and are kept up to date by a task: the number of free spots is
+// updated, and the post is removed once the game is full or over.
+// Each series has at most one post, about its next session.
+const REDIS_CROSSPOSTED_SERIES_KEY: &'static str = "crosspost:event_series";
+
+lazy_static! {
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+fn redis_config_key() -> String {
+    format!("guild:{}:crosspost", crate::discord_sync::GUILD_ID.0)
+}
+
+fn redis_series_crosspost_key(series_id: &str) -> String {
+    format!("event_series:{}:crosspost", series_id)
+}
+
+pub fn is_valid_webhook_url(url: &str) -> bool {
+    (url.starts_with("https://discord.com/api/webhooks/")
+        || url.starts_with("https://discordapp.com/api/webhooks/"))
+        && !url.contains(char::is_whitespace)
+        && !url.contains('?')
+}
+
+pub fn webhook_url<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<String>, crate::BoxedError> {
+    Ok(con.hget(redis_config_key(), "webhook_url")?)
+}
+
+pub fn template<C: redis::ConnectionLike>(con: &mut C) -> Result<String, crate::BoxedError> {
+    let template: Option<String> = con.hget(redis_config_key(), "template")?;
+    Ok(template.unwrap_or_else(|| crate::strings::CROSSPOST_DEFAULT_TEMPLATE.to_string()))
+}
+
+// Passing None stops cross-posting. The posts made through the previous
+// webhook are removed, since they couldn't be updated anymore.
+pub fn set_webhook_url(
+    url: Option<&str>,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    let series_ids: Vec<String> = con.smembers(REDIS_CROSSPOSTED_SERIES_KEY)?;
+    if let Some(old_url) = webhook_url(con)? {
+        for series_id in &series_ids {
+            if let Err(err) = remove_post(&old_url, series_id, con) {
+                eprintln!(
+                    "Could not remove the cross-post of series {}: {}",
+                    series_id, err
+                );
+            }
+        }
+    }
+    // Forget about the posts in any case
+    let mut pipe = redis::pipe();
+    for series_id in &series_ids {
+        pipe.del(redis_series_crosspost_key(series_id)).ignore();
+    }
+    pipe.del(REDIS_CROSSPOSTED_SERIES_KEY).ignore();
+    match url {
+        Some(url) => pipe.hset(redis_config_key(), "webhook_url", url).ignore(),
+        None => pipe.hdel(redis_config_key(), "webhook_url").ignore(),
+    };
+    let _: () = pipe.query(con)?;
+    Ok(())
+}
+
+// Passing None restores the default template. Existing posts are updated by
+// the next run of the task.
+pub fn set_template<C: redis::ConnectionLike>(
+    con: &mut C,
+    template: Option<&str>,
+) -> Result<(), crate::BoxedError> {
+    match template {
+        Some(template) => {
+            let _: () = con.hset(redis_config_key(), "template", template)?;
+        }
+        None => {
+            let _: () = con.hdel(redis_config_key(), "template")?;
+        }
+    }
+    Ok(())
+}
+
+// Runs every 15 minutes on the leader
+pub fn create_crosspost_task(
+    redis_client: crate::redis_namespace::Client,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    move |_ctx| {
+        if let Err(err) = sync_crossposts(&redis_client) {
+            eprintln!("Cross-posting task failed: {}", err);
+            crate::error_reporting::report(&err, &[("task", "crosspost")]);
+        }
+        white_rabbit::DateResult::Repeat(
+            white_rabbit::Utc::now() + white_rabbit::Duration::minutes(15),
+        )
+    }
+}
+
+fn sync_crossposts(redis_client: &crate::redis_namespace::Client) -> Result<(), crate::BoxedError> {
+    let mut con = redis_client.get_connection()?;
+    // Like the other tasks, wait until maintenance is over
+    if crate::maintenance_mode::is_active(&mut con)? {
+        return Ok(());
+    }
+    let webhook_url = match webhook_url(&mut con)? {
+        Some(webhook_url) => webhook_url,
+        None => return Ok(()),
+    };
+    let template = template(&mut con)?;
+    // Series that were removed in the meantime still need their post removed
+    let series_ids: Vec<String> = con.sunion(vec!["event_series", REDIS_CROSSPOSTED_SERIES_KEY])?;
+    for series_id in series_ids {
+        if let Err(err) = sync_crosspost(&webhook_url, &template, &series_id, &mut con) {
+            eprintln!(
+                "Could not update the cross-post of series {}: {}",
+                series_id, err
+            );
+        }
+    }
+    Ok(())
+}
+
+fn sync_crosspost(
+    webhook_url: &str,
+    template: &str,
+    series_id: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    let redis_series_crosspost_key = redis_series_crosspost_key(series_id);
+    let (posted_event_id, message_id, posted_content): (
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    ) = con.hget(
+        &redis_series_crosspost_key,
+        &["meetup_event", "message_id", "content"],
+    )?;
+    let upcoming_events = crate::discord_community_digest::upcoming_events(series_id, con)?;
+    let open_event = upcoming_events
+        .first()
+        .filter(|event| event.free_spots != Some(0));
+    let open_event = match open_event {
+        Some(open_event) => open_event,
+        None => {
+            // The game is full or over
+            if message_id.is_some() {
+                remove_post(webhook_url, series_id, con)?;
+            }
+            return Ok(());
+        }
+    };
+    let tags = crate::event_tags::series_tags(con, series_id)?;
+    let content = crate::strings::CROSSPOST(
+        template,
+        &open_event.name,
+        open_event.time,
+        &open_event.link,
+        open_event.free_spots,
+        &tags,
+    );
+    if let Some(message_id) = message_id {
+        if posted_event_id.as_ref() == Some(&open_event.id) {
+            if posted_content.as_ref() != Some(&content) {
+                edit_message(webhook_url, &message_id, &content)?;
+                let _: () = con.hset(&redis_series_crosspost_key, "content", &content)?;
+            }
+            return Ok(());
+        }
+        // The post is about a previous session
+        remove_post(webhook_url, series_id, con)?;
+    }
+    let message_id = post_message(webhook_url, &content)?;
+    let _: () = redis::pipe()
+        .hset_multiple(
+            &redis_series_crosspost_key,
+            &[
+                ("meetup_event", open_event.id.as_str()),
+                ("message_id", message_id.as_str()),
+                ("content", content.as_str()),
+            ],
+        )
+        .ignore()
+        .sadd(REDIS_CROSSPOSTED_SERIES_KEY, series_id)
+        .ignore()
+        .query(con)?;
+    Ok(())
+}
+
+fn remove_post(
+    webhook_url: &str,
+    series_id: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    let redis_series_crosspost_key = redis_series_crosspost_key(series_id);
+    let message_id: Option<String> = con.hget(&redis_series_crosspost_key, "message_id")?;
+    if let Some(message_id) = message_id {
+        let response = HTTP_CLIENT
+            .delete(&format!("{}/messages/{}", webhook_url, message_id))
+            .send()?;
+        // Somebody might have deleted the post by hand
+        if response.status() != reqwest::StatusCode::NOT_FOUND {
+            response.error_for_status()?;
+        }
+    }
+    let _: () = redis::pipe()
+        .del(&redis_series_crosspost_key)
+        .ignore()
+        .srem(REDIS_CROSSPOSTED_SERIES_KEY, series_id)
+        .ignore()
+        .query(con)?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct WebhookMessage {
+    id: String,
+}
+
+// Returns the ID of the new message
+fn post_message(webhook_url: &str, content: &str) -> Result<String, crate::BoxedError> {
+    let message: WebhookMessage = HTTP_CLIENT
+        .post(&format!("{}?wait=true", webhook_url))
+        .json(&serde_json::json!({ "content": content }))
+        .send()?
+        .error_for_status()?
+        .json()?;
+    Ok(message.id)
+}
+
+fn edit_message(
+    webhook_url: &str,
+    message_id: &str,
+    content: &str,
+) -> Result<(), crate::BoxedError> {
+    HTTP_CLIENT
+        .patch(&format!("{}/messages/{}", webhook_url, message_id))
+        .json(&serde_json::json!({ "content": content }))
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
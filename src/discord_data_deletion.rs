@@ -0,0 +1,122 @@
+use redis::{Commands, PipelineCommands};
+
+// Users can have the bot forget about them with "delete my data" in a DM,
+// once they confirmed it. This removes the accounts they linked (Meetup,
+// email and Warhorn), their open requests to organizers, their household,
+// subscriptions and characters, and what the nickname sync and the linking
+// wizard remembered about them. Moderation data like quarantines and the
+// ignore list stays, and so does what they posted in channels (recaps, dice
+// rolls).
+
+pub fn delete_user_data(
+    discord_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    // The Meetup account takes its household and the linked role with it
+    crate::discord_bot::Handler::remove_meetup_link(
+        discord_id,
+        redis_connection,
+        Some(discord_api),
+    )?;
+    for tag in crate::discord_subscriptions::subscriptions(redis_connection, discord_id)? {
+        if let Err(err) = crate::discord_subscriptions::unsubscribe(
+            discord_id,
+            &tag,
+            redis_connection,
+            discord_api,
+        ) {
+            eprintln!(
+                "Could not unsubscribe user {} from \"{}\": {}",
+                discord_id, tag, err
+            );
+        }
+    }
+    forget_user(discord_id, redis_connection)
+}
+
+// Everything that doesn't involve Discord
+fn forget_user<C: redis::ConnectionLike>(
+    discord_id: u64,
+    con: &mut C,
+) -> Result<(), crate::BoxedError> {
+    for identity in crate::identity::identities(discord_id, con)? {
+        crate::identity::remove_link(discord_id, &identity, con)?;
+    }
+    crate::households::remove_member(discord_id, con)?;
+    let channel_ids: Vec<u64> = con.smembers("discord_channels")?;
+    let mut pipe = redis::pipe();
+    pipe.hdel(
+        crate::email_registration::REDIS_PENDING_REQUESTS_KEY,
+        discord_id,
+    )
+    .ignore()
+    .hdel(crate::households::REDIS_PENDING_REQUESTS_KEY, discord_id)
+    .ignore()
+    .hdel("inactive_members:nudge_times", discord_id)
+    .ignore()
+    .srem("nickname_sync_opt_outs", discord_id)
+    .ignore()
+    .del(&[
+        format!("discord_user:{}:subscriptions", discord_id),
+        format!("discord_user:{}:synced_nickname", discord_id),
+        format!("discord_user:{}:linking_wizard", discord_id),
+        format!("discord_user:{}:linking_help_requested", discord_id),
+    ])
+    .ignore();
+    for channel_id in channel_ids {
+        pipe.hdel(
+            format!("discord_channel:{}:characters", channel_id),
+            discord_id,
+        )
+        .ignore();
+    }
+    let _: () = pipe.query(con)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Remembers the commands it gets and answers each with nil
+    #[derive(Default)]
+    struct RecordingConnection {
+        commands: Vec<Vec<u8>>,
+    }
+
+    impl redis::ConnectionLike for RecordingConnection {
+        fn req_packed_command(&mut self, cmd: &[u8]) -> redis::RedisResult<redis::Value> {
+            self.commands.push(cmd.to_vec());
+            Ok(redis::Value::Nil)
+        }
+
+        fn req_packed_commands(
+            &mut self,
+            cmd: &[u8],
+            _offset: usize,
+            count: usize,
+        ) -> redis::RedisResult<Vec<redis::Value>> {
+            self.commands.push(cmd.to_vec());
+            Ok(vec![redis::Value::Nil; count])
+        }
+
+        fn get_db(&self) -> i64 {
+            0
+        }
+    }
+
+    #[test]
+    fn withdraws_pending_requests() {
+        let mut con = RecordingConnection::default();
+        forget_user(5678, &mut con).unwrap();
+        let withdrawn = |key: &str| {
+            let hdel = redis::cmd("HDEL").arg(key).arg(5678).get_packed_command();
+            con.commands
+                .iter()
+                .any(|command| command.windows(hdel.len()).any(|part| part == &hdel[..]))
+        };
+        assert!(withdrawn("email_requests"));
+        assert!(withdrawn("household_requests"));
+    }
+}
@@ -0,0 +1,41 @@
+use chrono::{TimeZone, Utc};
+use redis::PipelineCommands;
+use serenity::model::id::MessageId;
+
+// Users often fix a typo in a command by editing their message. Edits of
+// messages that were sent at most EDIT_WINDOW_SECONDS ago are handled like new
+// messages. The content of each command is remembered for that long, such
+// that an edit that doesn't change the command (e.g. when Discord adds a link
+// preview) doesn't run it a second time.
+pub const EDIT_WINDOW_SECONDS: i64 = 120;
+
+// Discord IDs count milliseconds since the start of 2015
+const DISCORD_EPOCH_MILLIS: u64 = 1_420_070_400_000;
+
+fn redis_command_key(message_id: MessageId) -> String {
+    format!("discord_message:{}:command", message_id.0)
+}
+
+// The time at which a message was sent, which is part of its ID
+fn message_time(message_id: MessageId) -> chrono::DateTime<Utc> {
+    Utc.timestamp_millis(((message_id.0 >> 22) + DISCORD_EPOCH_MILLIS) as i64)
+}
+
+pub fn is_within_edit_window(message_id: MessageId) -> bool {
+    message_time(message_id) + chrono::Duration::seconds(EDIT_WINDOW_SECONDS) >= Utc::now()
+}
+
+// Returns false if the message was already handled with the same content
+pub fn remember_command<C: redis::ConnectionLike>(
+    con: &mut C,
+    message_id: MessageId,
+    content: &str,
+) -> Result<bool, crate::BoxedError> {
+    let key = redis_command_key(message_id);
+    let (previous_content,): (Option<String>,) = redis::pipe()
+        .getset(&key, content)
+        .expire(&key, EDIT_WINDOW_SECONDS as usize)
+        .ignore()
+        .query(con)?;
+    Ok(previous_content.as_ref().map(String::as_str) != Some(content))
+}
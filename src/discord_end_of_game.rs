@@ -5,7 +5,7 @@ use simple_error::SimpleError;
 
 // Sends channel deletion reminders to expired Discord channels
 pub fn create_end_of_game_task(
-    redis_client: redis::Client,
+    redis_client: crate::redis_namespace::Client,
     mut discord_api: crate::discord_bot::CacheAndHttp,
     bot_id: u64,
     recurring: bool,
@@ -14,6 +14,7 @@ pub fn create_end_of_game_task(
         let next_sync_time = match end_of_game_task(&redis_client, &mut discord_api, bot_id) {
             Err(err) => {
                 eprintln!("End of game task failed: {}", err);
+                crate::error_reporting::report(&err, &[("task", "end_of_game")]);
                 // Retry in an hour
                 white_rabbit::Utc::now() + white_rabbit::Duration::hours(1)
             }
@@ -33,7 +34,7 @@ pub fn create_end_of_game_task(
 }
 
 fn end_of_game_task(
-    redis_client: &redis::Client,
+    redis_client: &crate::redis_namespace::Client,
     discord_api: &mut crate::discord_bot::CacheAndHttp,
     bot_id: u64,
 ) -> Result<(), crate::BoxedError> {
@@ -54,6 +55,18 @@ fn end_of_game_task(
         // Then, check if the channel is due for deletion
         match delete_marked_channel(channel, &mut con, discord_api) {
             Ok(deletion_status) => {
+                if deletion_status == DeletionStatus::Deleted
+                    || deletion_status == DeletionStatus::Archived
+                {
+                    // The vacuum task only removes this later
+                    let series_id: redis::RedisResult<Option<String>> =
+                        con.get(format!("discord_channel:{}:event_series", channel));
+                    crate::event_webhooks::emit(crate::event_webhooks::Event::SeriesClosed {
+                        series_id: series_id.unwrap_or(None),
+                        channel_id: channel,
+                        archived: deletion_status == DeletionStatus::Archived,
+                    });
+                }
                 if deletion_status == DeletionStatus::NotDeleted {
                     // Lastly, send a reminder if necessary
                     if let Err(err) =
@@ -70,6 +83,11 @@ fn end_of_game_task(
             }
         }
     }
+    // Table channels of one-shots are only needed until the event is over
+    if let Err(err) = crate::discord_tables::delete_expired_tables(&mut con, discord_api) {
+        some_failed = true;
+        eprintln!("Table channel cleanup failed: {}", err);
+    }
     if some_failed {
         Err(SimpleError::new("One or more end of game tasks failed").into())
     } else {
@@ -79,7 +97,7 @@ fn end_of_game_task(
 
 fn update_series_channel_expiration(
     series_id: &str,
-    con: &mut redis::Connection,
+    con: &mut crate::redis_namespace::Connection,
 ) -> Result<(), crate::BoxedError> {
     let redis_series_events_key = format!("event_series:{}:meetup_events", &series_id);
     let redis_series_channel_key = format!("event_series:{}:discord_channel", &series_id);
@@ -145,7 +163,7 @@ fn update_series_channel_expiration(
                 },
                 None => None,
             };
-        let new_expiration_time = last_event_time + chrono::Duration::days(1);
+        let new_expiration_time = last_event_time + crate::guild_config::expiration_grace(con)?;
         let (new_expiration_time, needs_update) = match current_expiration_time {
             Some(current_expiration_time) => {
                 if current_expiration_time > new_expiration_time {
@@ -173,10 +191,28 @@ fn update_series_channel_expiration(
 
 fn send_channel_expiration_reminder(
     channel_id: u64,
-    con: &mut redis::Connection,
+    con: &mut crate::redis_namespace::Connection,
     discord_api: &mut crate::discord_bot::CacheAndHttp,
     bot_id: u64,
 ) -> Result<(), crate::BoxedError> {
+    if !crate::feature_flags::is_enabled(
+        con,
+        crate::discord_sync::GUILD_ID,
+        crate::feature_flags::Feature::ExpirationReminders,
+    )? {
+        return Ok(());
+    }
+    // The hosts might have turned the reminders off for their channel
+    let settings = crate::discord_channel_settings::settings(con, ChannelId(channel_id))?;
+    if !settings.reminders {
+        return Ok(());
+    }
+    // Archived channels are done for good
+    let redis_channel_archived_key = format!("discord_channel:{}:archived", channel_id);
+    let archived: Option<bool> = con.get(&redis_channel_archived_key)?;
+    if archived.unwrap_or(false) {
+        return Ok(());
+    }
     let redis_channel_expiration_key = format!("discord_channel:{}:expiration_time", channel_id);
     let redis_channel_reminder_time = format!(
         "discord_channel:{}:last_expiration_reminder_time",
@@ -193,8 +229,10 @@ fn send_channel_expiration_reminder(
         .transpose()?
         .map(|t| t.with_timezone(&chrono::Utc));
     if let Some(expiration_time) = expiration_time {
-        if expiration_time > chrono::Utc::now() {
-            // The expiration time hasn't come yet
+        let reminder_time =
+            expiration_time - chrono::Duration::hours(settings.reminder_lead_hours as i64);
+        if reminder_time > chrono::Utc::now() {
+            // The time for the first reminder hasn't come yet
             return Ok(());
         }
         if let Some(last_reminder_time) = last_reminder_time {
@@ -221,13 +259,16 @@ fn send_channel_expiration_reminder(
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum DeletionStatus {
     Deleted,
+    Archived,
     NotDeleted,
     AlreadyDeleted,
+    // The channel doesn't look like one the bot manages
+    Refused,
 }
 
 fn delete_marked_channel(
     channel_id: u64,
-    con: &mut redis::Connection,
+    con: &mut crate::redis_namespace::Connection,
     discord_api: &crate::discord_bot::CacheAndHttp,
 ) -> Result<DeletionStatus, crate::BoxedError> {
     // Check if there is an expiration time in the future
@@ -284,8 +325,44 @@ fn delete_marked_channel(
         }
     };
     if channel_exists {
+        if !crate::discord_ownership::verify_managed_channel(
+            ChannelId(channel_id),
+            "discord_channels",
+            con,
+            discord_api,
+        )? {
+            return Ok(DeletionStatus::Refused);
+        }
+        // Delete the channel's voice channel first, since it is only
+        // referenced from the channel
+        crate::discord_voice_channels::delete_voice_channel(
+            ChannelId(channel_id),
+            con,
+            discord_api,
+        )?;
+        // The hosts might want to keep the channel around, read-only
+        let settings = crate::discord_channel_settings::settings(con, ChannelId(channel_id))?;
+        if settings.closing == crate::discord_channel_settings::ClosingMode::Archive {
+            crate::discord_session_channels::archive_channel(
+                ChannelId(channel_id),
+                strings::CHANNEL_ARCHIVED,
+                discord_api,
+            )?;
+            let redis_channel_archived_key = format!("discord_channel:{}:archived", channel_id);
+            let _: () = redis::pipe()
+                .set(&redis_channel_archived_key, true)
+                .ignore()
+                .del(&redis_channel_deletion_key)
+                .ignore()
+                .query(con)?;
+            // Channels without roles have an overwrite for each member,
+            // which needs to be read-only as well
+            crate::discord_channel_access::sync_members(ChannelId(channel_id), con, discord_api)?;
+            return Ok(DeletionStatus::Archived);
+        }
         // Delete the channel from Discord
         ChannelId(channel_id).delete(&discord_api.http)?;
+        crate::discord_state_cache::remove_channel(ChannelId(channel_id));
         // Delete the channel deletion request from Redis
         let _: () = con.del(&redis_channel_deletion_key)?;
         // Let the vacuum task handle all other stale Redis keys
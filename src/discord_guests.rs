@@ -0,0 +1,212 @@
+use redis::{Commands, PipelineCommands};
+use serenity::http::CacheHttp;
+use serenity::model::id::{ChannelId, UserId};
+
+// Hosts can give somebody temporary access to their channel with
+// "add guest @user for 1 week", e.g. for a trial session. The guest gets the
+// channel's player role, which is removed again by a dedicated task once the
+// period is over, and the host who added them is told about it.
+// Guests who signed up on Meetup in the meantime keep their access, as do
+// guests who were added as regular players or removed by hand.
+// Pending expirations are identified by "{channel_id}:{discord_id}", the
+// queue is a sorted set with the expiration time as the score.
+pub const MAX_GUEST_DAYS: i64 = 8 * 7;
+const REDIS_GUEST_EXPIRATIONS_KEY: &'static str = "discord_guests:expirations";
+
+fn guest_id(channel_id: ChannelId, discord_id: u64) -> String {
+    format!("{}:{}", channel_id.0, discord_id)
+}
+
+fn parse_guest_id(guest_id: &str) -> Option<(ChannelId, u64)> {
+    let mut parts = guest_id.splitn(2, ':');
+    match (
+        parts.next().and_then(|id| id.parse().ok()),
+        parts.next().and_then(|id| id.parse().ok()),
+    ) {
+        (Some(channel_id), Some(discord_id)) => Some((ChannelId(channel_id), discord_id)),
+        _ => None,
+    }
+}
+
+fn redis_channel_guests_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:guests", channel_id.0)
+}
+
+// E.g. "1 week", "3 days" or "12 hours". Returns None for anything else or
+// periods longer than MAX_GUEST_DAYS.
+pub fn parse_period(amount: &str, unit: &str) -> Option<chrono::Duration> {
+    let unit = unit.to_lowercase();
+    let hours_per_unit = if unit.starts_with("hour") {
+        1
+    } else if unit.starts_with("day") {
+        24
+    } else if unit.starts_with("week") {
+        7 * 24
+    } else {
+        return None;
+    };
+    // Bounded, such that the multiplication can't overflow
+    let amount = amount
+        .parse::<i64>()
+        .ok()
+        .filter(|amount| *amount > 0 && *amount <= MAX_GUEST_DAYS * 24)?;
+    let period = chrono::Duration::hours(amount * hours_per_unit);
+    if period > chrono::Duration::days(MAX_GUEST_DAYS) {
+        None
+    } else {
+        Some(period)
+    }
+}
+
+// Adding the same guest again extends (or shortens) their access
+pub fn add_guest(
+    channel_id: ChannelId,
+    discord_id: u64,
+    added_by: u64,
+    expiration_time: chrono::DateTime<chrono::Utc>,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    let _: () = redis::pipe()
+        .zadd(
+            REDIS_GUEST_EXPIRATIONS_KEY,
+            guest_id(channel_id, discord_id),
+            expiration_time.timestamp(),
+        )
+        .ignore()
+        .hset(redis_channel_guests_key(channel_id), discord_id, added_by)
+        .ignore()
+        .query(redis_connection)?;
+    Ok(())
+}
+
+// Called when a guest was added as a regular player or removed by hand, so
+// that their access doesn't expire anymore
+pub fn forget_guest(
+    channel_id: ChannelId,
+    discord_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    let _: () = redis::pipe()
+        .zrem(
+            REDIS_GUEST_EXPIRATIONS_KEY,
+            guest_id(channel_id, discord_id),
+        )
+        .ignore()
+        .hdel(redis_channel_guests_key(channel_id), discord_id)
+        .ignore()
+        .query(redis_connection)?;
+    Ok(())
+}
+
+// Runs every five minutes on the leader
+pub fn create_guest_expiration_task(
+    redis_client: crate::redis_namespace::Client,
+    discord_api: crate::discord_bot::CacheAndHttp,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    move |_ctx| {
+        if let Err(err) = expire_guests(&redis_client, &discord_api) {
+            eprintln!("Guest expiration task failed: {}", err);
+            crate::error_reporting::report(&err, &[("task", "guest_expiration")]);
+        }
+        white_rabbit::DateResult::Repeat(
+            white_rabbit::Utc::now() + white_rabbit::Duration::minutes(5),
+        )
+    }
+}
+
+fn expire_guests(
+    redis_client: &crate::redis_namespace::Client,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let mut con = redis_client.get_connection()?;
+    // Like the other tasks, wait until maintenance is over
+    if crate::maintenance_mode::is_active(&mut con)? {
+        return Ok(());
+    }
+    let now = chrono::Utc::now().timestamp();
+    let expired_guest_ids: Vec<String> =
+        con.zrangebyscore(REDIS_GUEST_EXPIRATIONS_KEY, "-inf", now)?;
+    for guest_id in expired_guest_ids {
+        let (channel_id, discord_id) = match parse_guest_id(&guest_id) {
+            Some(guest) => guest,
+            None => {
+                eprintln!("Dropping the invalid guest {}", guest_id);
+                let _: () = con.zrem(REDIS_GUEST_EXPIRATIONS_KEY, &guest_id)?;
+                continue;
+            }
+        };
+        // Try again on the next run if this fails
+        if let Err(err) = expire_guest(channel_id, discord_id, &mut con, discord_api) {
+            eprintln!(
+                "Could not end the guest access of user {} to channel {}: {}",
+                discord_id, channel_id.0, err
+            );
+        }
+    }
+    Ok(())
+}
+
+fn expire_guest(
+    channel_id: ChannelId,
+    discord_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let added_by: Option<u64> =
+        redis_connection.hget(redis_channel_guests_key(channel_id), discord_id)?;
+    let channel_access =
+        crate::discord_bot::Handler::get_channel_access(channel_id.0, redis_connection)?;
+    let channel_access = match channel_access {
+        Some(channel_access) => channel_access,
+        // The channel is gone
+        None => return forget_guest(channel_id, discord_id, redis_connection),
+    };
+    // Guests who signed up on Meetup in the meantime stay
+    let redis_channel_series_key = format!("discord_channel:{}:event_series", channel_id.0);
+    let series_id: Option<String> = redis_connection.get(&redis_channel_series_key)?;
+    if let Some(series_id) = series_id {
+        let participants = crate::discord_sync::role_assignment_candidates(
+            &series_id,
+            channel_id,
+            /*is_host_role*/ false,
+            redis_connection,
+        )?;
+        if participants.contains(&discord_id) {
+            println!(
+                "Guest {} signed up for channel {} and keeps their access",
+                discord_id, channel_id.0
+            );
+            return forget_guest(channel_id, discord_id, redis_connection);
+        }
+    }
+    match channel_access {
+        crate::discord_bot_commands::ChannelAccess::Roles(channel_roles) => {
+            discord_api.http().remove_member_role(
+                crate::discord_sync::GUILD_ID.0,
+                discord_id,
+                channel_roles.user,
+            )?;
+        }
+        crate::discord_bot_commands::ChannelAccess::Overwrites => {
+            crate::discord_channel_access::revoke(
+                channel_id,
+                discord_id,
+                /*as_host*/ false,
+                redis_connection,
+                discord_api,
+            )?;
+        }
+    }
+    println!(
+        "Guest access of user {} to channel {} ended",
+        discord_id, channel_id.0
+    );
+    forget_guest(channel_id, discord_id, redis_connection)?;
+    if let Some(added_by) = added_by {
+        let host = UserId(added_by).to_user(discord_api)?;
+        host.direct_message(discord_api, |message_builder| {
+            message_builder.content(crate::strings::GUEST_ACCESS_ENDED(discord_id, channel_id.0))
+        })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use redis::Commands;
+use serenity::http::CacheHttp;
+use serenity::model::{
+    channel::PermissionOverwrite, channel::PermissionOverwriteType, id::ChannelId, id::UserId,
+    permissions::Permissions,
+};
+
+// For campaigns with privacy concerns, the hosts of a channel can have players
+// who are removed from the channel additionally lose access to the channel's
+// message history for a number of days. This way, a player who comes back in
+// the meantime doesn't get to read what was discussed in their absence.
+// The players get a member specific permission overwrite that denies
+// READ_MESSAGE_HISTORY, which the Discord sync removes again once the period
+// is over (and restores if it went missing before that).
+pub const MAX_HIDE_DAYS: u64 = 365;
+
+pub fn hide_days<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<Option<u64>, crate::BoxedError> {
+    let redis_channel_hide_days_key = format!("discord_channel:{}:hide_history_days", channel_id.0);
+    Ok(con.get(&redis_channel_hide_days_key)?)
+}
+
+// Players that were already removed keep their overwrite until their period
+// is over, even if the setting is turned off
+pub fn set_hide_days<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    days: Option<u64>,
+) -> Result<(), crate::BoxedError> {
+    let redis_channel_hide_days_key = format!("discord_channel:{}:hide_history_days", channel_id.0);
+    match days {
+        Some(days) => {
+            let _: () = con.set(&redis_channel_hide_days_key, days)?;
+        }
+        None => {
+            let _: () = con.del(&redis_channel_hide_days_key)?;
+        }
+    }
+    Ok(())
+}
+
+fn history_permission_overwrite(user_id: UserId) -> PermissionOverwrite {
+    PermissionOverwrite {
+        allow: Permissions::empty(),
+        deny: Permissions::READ_MESSAGE_HISTORY,
+        kind: PermissionOverwriteType::Member(user_id),
+    }
+}
+
+// Called when a player is removed from the channel. Does nothing unless the
+// channel's hosts turned it on.
+pub fn hide_history_from(
+    channel_id: ChannelId,
+    discord_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let days = match hide_days(redis_connection, channel_id)? {
+        Some(days) => days,
+        None => return Ok(()),
+    };
+    // Co-hosts have their own member specific overwrite, which would be
+    // replaced
+    let redis_channel_cohosts_key = format!("discord_channel:{}:cohosts", channel_id.0);
+    let is_cohost: bool = redis_connection.sismember(&redis_channel_cohosts_key, discord_id)?;
+    if is_cohost {
+        return Ok(());
+    }
+    // Remember the player first, such that the sync creates the overwrite in
+    // case it fails here
+    let hidden_until = chrono::Utc::now() + chrono::Duration::days(days as i64);
+    let redis_channel_history_hidden_key =
+        format!("discord_channel:{}:history_hidden_until", channel_id.0);
+    let _: () = redis_connection.zadd(
+        &redis_channel_history_hidden_key,
+        discord_id,
+        hidden_until.timestamp(),
+    )?;
+    channel_id.create_permission(
+        discord_api.http(),
+        &history_permission_overwrite(UserId(discord_id)),
+    )?;
+    Ok(())
+}
+
+// Called when a player is added to the channel again by a host, who
+// presumably wants them to see the history
+pub fn unhide_history_for(
+    channel_id: ChannelId,
+    discord_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let redis_channel_history_hidden_key =
+        format!("discord_channel:{}:history_hidden_until", channel_id.0);
+    let hidden_until: Option<i64> =
+        redis_connection.zscore(&redis_channel_history_hidden_key, discord_id)?;
+    if hidden_until.is_none() {
+        return Ok(());
+    }
+    channel_id.delete_permission(
+        discord_api.http(),
+        PermissionOverwriteType::Member(UserId(discord_id)),
+    )?;
+    let _: () = redis_connection.zrem(&redis_channel_history_hidden_key, discord_id)?;
+    Ok(())
+}
+
+// Part of syncing the channel's permissions: removes the overwrites whose
+// period is over and makes sure that the others are still in place.
+// Players who became co-hosts in the meantime are left alone, their
+// overwrite is the co-host one now.
+pub fn sync_history_overwrites(
+    channel_id: ChannelId,
+    cohost_ids: &[u64],
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let redis_channel_history_hidden_key =
+        format!("discord_channel:{}:history_hidden_until", channel_id.0);
+    let now = chrono::Utc::now().timestamp();
+    let expired_ids: Vec<u64> =
+        redis_connection.zrangebyscore(&redis_channel_history_hidden_key, "-inf", now)?;
+    for discord_id in expired_ids {
+        if !cohost_ids.contains(&discord_id) {
+            channel_id.delete_permission(
+                discord_api.http(),
+                PermissionOverwriteType::Member(UserId(discord_id)),
+            )?;
+        }
+        let _: () = redis_connection.zrem(&redis_channel_history_hidden_key, discord_id)?;
+    }
+    let hidden_ids: Vec<u64> = redis_connection.zrangebyscore(
+        &redis_channel_history_hidden_key,
+        format!("({}", now),
+        "+inf",
+    )?;
+    for discord_id in hidden_ids {
+        if cohost_ids.contains(&discord_id) {
+            let _: () = redis_connection.zrem(&redis_channel_history_hidden_key, discord_id)?;
+            continue;
+        }
+        channel_id.create_permission(
+            discord_api.http(),
+            &history_permission_overwrite(UserId(discord_id)),
+        )?;
+    }
+    Ok(())
+}
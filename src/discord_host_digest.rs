@@ -0,0 +1,276 @@
+use crate::strings;
+use chrono::Datelike;
+use redis::Commands;
+use serenity::model::id::UserId;
+use simple_error::SimpleError;
+use std::collections::HashMap;
+
+// Every Monday morning, each host gets a DM summarizing the upcoming sessions
+// of all the series they run: RSVP counts, attendees without a linked Discord
+// account and when the channels expire. Hosts can opt out of the digest.
+const DIGEST_WEEKDAY: chrono::Weekday = chrono::Weekday::Mon;
+const DIGEST_HOUR: u32 = 8;
+// Only sessions within the next weeks are relevant for the digest
+const DIGEST_LOOKAHEAD_DAYS: i64 = 14;
+// Discord limits messages to 2000 characters
+const MAX_MESSAGE_CHARS: usize = 1900;
+
+#[derive(Clone)]
+struct SessionDigest {
+    name: String,
+    time: chrono::DateTime<chrono::Utc>,
+    link: String,
+    num_rsvps: usize,
+    num_unlinked: usize,
+}
+
+#[derive(Clone)]
+struct SeriesDigest {
+    channel_id: Option<u64>,
+    expiration_time: Option<chrono::DateTime<chrono::Utc>>,
+    sessions: Vec<SessionDigest>,
+}
+
+pub fn create_host_digest_task(
+    redis_client: crate::redis_namespace::Client,
+    discord_api: crate::discord_bot::CacheAndHttp,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    move |_ctx| {
+        if let Err(err) = send_host_digests_if_due(&redis_client, &discord_api) {
+            eprintln!("Host digest task failed: {}", err);
+            crate::error_reporting::report(&err, &[("task", "host_digest")]);
+        }
+        white_rabbit::DateResult::Repeat(next_digest_time(white_rabbit::Utc::now()))
+    }
+}
+
+// Instances that only become the leader later (or restart) run this task at
+// other times as well, so remember when the digest was last sent
+fn send_host_digests_if_due(
+    redis_client: &crate::redis_namespace::Client,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let mut con = redis_client.get_connection()?;
+    let last_sent_time: Option<String> = con.get("host_digest_last_sent_time")?;
+    if let Some(last_sent_time) = last_sent_time {
+        let last_sent_time = white_rabbit::DateTime::parse_from_rfc3339(&last_sent_time)?
+            .with_timezone(&white_rabbit::Utc);
+        if white_rabbit::Utc::now() < next_digest_time(last_sent_time) {
+            return Ok(());
+        }
+    }
+    let _: () = con.set(
+        "host_digest_last_sent_time",
+        white_rabbit::Utc::now().to_rfc3339(),
+    )?;
+    send_host_digests(&mut con, discord_api)
+}
+
+// The next Monday at 8am (UTC) after `now`
+pub fn next_digest_time(
+    now: white_rabbit::DateTime<white_rabbit::Utc>,
+) -> white_rabbit::DateTime<white_rabbit::Utc> {
+    let days_until_digest =
+        (7 + DIGEST_WEEKDAY.num_days_from_monday() - now.weekday().num_days_from_monday()) % 7;
+    let mut next_time = (now + white_rabbit::Duration::days(days_until_digest as i64))
+        .date()
+        .and_hms(DIGEST_HOUR, 0, 0);
+    if next_time <= now {
+        next_time = next_time + white_rabbit::Duration::days(7);
+    }
+    next_time
+}
+
+pub fn is_opted_out<C: redis::ConnectionLike>(
+    con: &mut C,
+    discord_id: u64,
+) -> Result<bool, crate::BoxedError> {
+    Ok(con.sismember("host_digest_opt_outs", discord_id)?)
+}
+
+pub fn set_opted_out<C: redis::ConnectionLike>(
+    con: &mut C,
+    discord_id: u64,
+    opted_out: bool,
+) -> Result<(), crate::BoxedError> {
+    if opted_out {
+        let _: () = con.sadd("host_digest_opt_outs", discord_id)?;
+    } else {
+        let _: () = con.srem("host_digest_opt_outs", discord_id)?;
+    }
+    Ok(())
+}
+
+fn send_host_digests(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let event_series: Vec<String> = con.smembers("event_series")?;
+    // Collect the digest of each series and assign it to all of its hosts
+    let mut digests_per_host: HashMap<u64, Vec<SeriesDigest>> = HashMap::new();
+    for series_id in &event_series {
+        let (digest, host_ids) = match series_digest(series_id, con)? {
+            Some(digest_and_hosts) => digest_and_hosts,
+            None => continue,
+        };
+        for host_id in host_ids {
+            digests_per_host
+                .entry(host_id)
+                .or_default()
+                .push(digest.clone());
+        }
+    }
+    let quarantined_user_ids = crate::quarantine::quarantined_users(con)?;
+    let ignored_user_ids = crate::ignore_list::ignored_users(con)?;
+    let mut some_failed = false;
+    for (host_id, mut digests) in digests_per_host {
+        if is_opted_out(con, host_id)?
+            || quarantined_user_ids.contains(&host_id)
+            || ignored_user_ids.contains(&host_id)
+            || crate::ignore_list::is_bot(discord_api, host_id)
+        {
+            continue;
+        }
+        digests.sort_unstable_by_key(|digest| digest.sessions.first().map(|session| session.time));
+        let mut lines = vec![strings::HOST_DIGEST_INTRO.to_string()];
+        for digest in &digests {
+            lines.push(String::new());
+            lines.push(strings::HOST_DIGEST_SERIES_HEADER(
+                digest.channel_id,
+                &digest.sessions[0].name,
+            ));
+            for session in &digest.sessions {
+                lines.push(strings::HOST_DIGEST_SESSION_LINE(
+                    &session.name,
+                    session.time,
+                    &session.link,
+                    session.num_rsvps,
+                    session.num_unlinked,
+                ));
+            }
+            if let Some(expiration_time) = digest.expiration_time {
+                lines.push(strings::HOST_DIGEST_CHANNEL_EXPIRATION(expiration_time));
+            }
+        }
+        lines.push(String::new());
+        lines.push(strings::HOST_DIGEST_OUTRO.to_string());
+        if let Err(err) = send_dm_in_chunks(UserId(host_id), &lines, discord_api) {
+            some_failed = true;
+            eprintln!("Could not send the digest to host {}: {}", host_id, err);
+        }
+    }
+    if some_failed {
+        Err(SimpleError::new("One or more host digests could not be sent").into())
+    } else {
+        Ok(())
+    }
+}
+
+// Returns None if the series has no sessions in the near future
+fn series_digest(
+    series_id: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Option<(SeriesDigest, Vec<u64>)>, crate::BoxedError> {
+    let redis_series_events_key = format!("event_series:{}:meetup_events", series_id);
+    let event_ids: Vec<String> = con.smembers(&redis_series_events_key)?;
+    let now = chrono::Utc::now();
+    let mut sessions = vec![];
+    let mut host_ids = vec![];
+    for event_id in event_ids {
+        let redis_event_key = format!("meetup_event:{}", event_id);
+        let (time, name, link): (Option<String>, Option<String>, Option<String>) =
+            con.hget(&redis_event_key, &["time", "name", "link"])?;
+        let (time, name, link) = match (time, name, link) {
+            (Some(time), Some(name), Some(link)) => (time, name, link),
+            _ => continue,
+        };
+        let time = chrono::DateTime::parse_from_rfc3339(&time)?.with_timezone(&chrono::Utc);
+        if time < now || time > now + chrono::Duration::days(DIGEST_LOOKAHEAD_DAYS) {
+            continue;
+        }
+        let redis_event_users_key = format!("meetup_event:{}:meetup_users", event_id);
+        let meetup_user_ids: Vec<u64> = con.smembers(&redis_event_users_key)?;
+        let mut num_unlinked = 0;
+        for meetup_user_id in &meetup_user_ids {
+            let discord_id = crate::identity::meetup_discord_user(*meetup_user_id, con)?;
+            if discord_id.is_none() {
+                num_unlinked += 1;
+            }
+        }
+        let redis_event_hosts_key = format!("meetup_event:{}:meetup_hosts", event_id);
+        let meetup_host_ids: Vec<u64> = con.smembers(&redis_event_hosts_key)?;
+        for meetup_host_id in meetup_host_ids {
+            let discord_id = crate::identity::meetup_discord_user(meetup_host_id, con)?;
+            if let Some(discord_id) = discord_id {
+                if !host_ids.contains(&discord_id) {
+                    host_ids.push(discord_id);
+                }
+            }
+        }
+        sessions.push(SessionDigest {
+            name: name,
+            time: time,
+            link: link,
+            num_rsvps: meetup_user_ids.len(),
+            num_unlinked: num_unlinked,
+        });
+    }
+    if sessions.is_empty() {
+        return Ok(None);
+    }
+    sessions.sort_unstable_by_key(|session| session.time);
+    let redis_series_channel_key = format!("event_series:{}:discord_channel", series_id);
+    let channel_id: Option<u64> = con.get(&redis_series_channel_key)?;
+    let mut expiration_time = None;
+    if let Some(channel_id) = channel_id {
+        // Hosts that were added manually run the series as well
+        let manual_host_ids: Vec<u64> =
+            con.smembers(format!("discord_channel:{}:manual_hosts", channel_id))?;
+        for manual_host_id in manual_host_ids {
+            if !host_ids.contains(&manual_host_id) {
+                host_ids.push(manual_host_id);
+            }
+        }
+        let redis_channel_expiration_key =
+            format!("discord_channel:{}:expiration_time", channel_id);
+        let channel_expiration_time: Option<String> = con.get(&redis_channel_expiration_key)?;
+        expiration_time = channel_expiration_time
+            .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
+            .transpose()?
+            .map(|t| t.with_timezone(&chrono::Utc));
+    }
+    let digest = SeriesDigest {
+        channel_id: channel_id,
+        expiration_time: expiration_time,
+        sessions: sessions,
+    };
+    Ok(Some((digest, host_ids)))
+}
+
+// Splits the lines into as few messages as possible
+fn send_dm_in_chunks(
+    user_id: UserId,
+    lines: &[String],
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let user = user_id.to_user(discord_api)?;
+    let mut chunk = String::new();
+    for line in lines {
+        if !chunk.is_empty() && chunk.len() + line.len() + 1 > MAX_MESSAGE_CHARS {
+            user.direct_message(discord_api, |message_builder| {
+                message_builder.content(&chunk)
+            })?;
+            chunk.clear();
+        }
+        if !chunk.is_empty() {
+            chunk.push('\n');
+        }
+        chunk.push_str(line);
+    }
+    if !chunk.is_empty() {
+        user.direct_message(discord_api, |message_builder| {
+            message_builder.content(&chunk)
+        })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use redis::{Commands, PipelineCommands};
+use serenity::model::id::{ChannelId, UserId};
+
+// New hosts often don't know what the bot does for their channel. When the
+// Discord sync creates the channel of a series, it remembers the channel
+// here, and once the hosts are known (after the role assignments of the same
+// sync run) they get a DM with an orientation: where the channel is, how to
+// add players, how expiration works and the commands for hosts. Channels
+// whose hosts haven't linked their Discord account yet stay pending until
+// they do. Organizers can switch this off with the "host_orientation"
+// feature.
+const PENDING_CHANNELS_KEY: &'static str = "pending_host_orientation_discord_channels";
+
+// Called in the Redis transaction that persists a new channel
+pub fn mark_pending(pipe: &mut redis::Pipeline, channel_id: ChannelId) {
+    pipe.sadd(PENDING_CHANNELS_KEY, channel_id.0).ignore();
+}
+
+// Sends the orientation to the hosts of the channel if it is still pending
+pub fn send_pending(
+    series_id: &str,
+    channel_id: ChannelId,
+    bot_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let is_pending: bool = redis_connection.sismember(PENDING_CHANNELS_KEY, channel_id.0)?;
+    if !is_pending {
+        return Ok(());
+    }
+    if !crate::feature_flags::is_enabled(
+        redis_connection,
+        crate::discord_sync::GUILD_ID,
+        crate::feature_flags::Feature::HostOrientation,
+    )? {
+        let _: () = redis_connection.srem(PENDING_CHANNELS_KEY, channel_id.0)?;
+        return Ok(());
+    }
+    let host_ids = crate::discord_sync::role_assignment_candidates(
+        series_id,
+        channel_id,
+        /*is_host_role*/ true,
+        redis_connection,
+    )?;
+    if host_ids.is_empty() {
+        return Ok(());
+    }
+    let expiration_grace = crate::guild_config::expiration_grace(redis_connection)?;
+    let orientation =
+        crate::strings::HOST_ORIENTATION(channel_id.0, expiration_grace.num_hours(), bot_id);
+    for host_id in host_ids {
+        let dm_result = UserId(host_id).to_user(discord_api).and_then(|user| {
+            user.direct_message(discord_api, |message_builder| {
+                message_builder.content(&orientation)
+            })
+        });
+        match dm_result {
+            Ok(_) => println!(
+                "Sent the host orientation for channel {} to user {}",
+                channel_id.0, host_id
+            ),
+            // Most likely, the host doesn't accept DMs
+            Err(err) => eprintln!(
+                "Could not send the host orientation for channel {} to user {}: {}",
+                channel_id.0, host_id, err
+            ),
+        }
+    }
+    let _: () = redis_connection.srem(PENDING_CHANNELS_KEY, channel_id.0)?;
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use chrono::Utc;
+use redis::{Commands, PipelineCommands};
+use serenity::model::id::{RoleId, UserId};
+use simple_error::SimpleError;
+use std::collections::HashSet;
+
+// Keeps the member count honest: "inactive members <days>" lists the members
+// who joined the server more than that many days ago, never linked a Meetup
+// account and aren't a player or host in any of the bot's channels.
+// "nudge inactive members <days>" sends them a DM with a reminder how to get
+// started, after the organizer confirmed it. Members are nudged at most once
+// every 30 days.
+// Organizers, members of a household and ignored users don't count as
+// inactive.
+pub const DEFAULT_MIN_DAYS: u32 = 90;
+
+const NUDGE_INTERVAL_DAYS: i64 = 30;
+
+// Maps Discord IDs to the time (Unix timestamp) of the last nudge
+const REDIS_NUDGE_TIMES_KEY: &'static str = "inactive_members:nudge_times";
+
+pub struct InactiveMember {
+    pub discord_id: u64,
+    pub tag: String,
+    pub joined_at: chrono::DateTime<Utc>,
+    pub last_nudge_time: Option<chrono::DateTime<Utc>>,
+}
+
+impl InactiveMember {
+    fn can_be_nudged(&self) -> bool {
+        match self.last_nudge_time {
+            Some(last_nudge_time) => {
+                last_nudge_time + chrono::Duration::days(NUDGE_INTERVAL_DAYS) <= Utc::now()
+            }
+            None => true,
+        }
+    }
+}
+
+// The roles of all bot-managed channels, players and hosts
+fn channel_role_ids(
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<HashSet<RoleId>, crate::BoxedError> {
+    let channel_ids: Vec<u64> = con.smembers("discord_channels")?;
+    if channel_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let mut pipe = redis::pipe();
+    for channel_id in &channel_ids {
+        pipe.get(format!("discord_channel:{}:discord_role", channel_id))
+            .get(format!("discord_channel:{}:discord_host_role", channel_id));
+    }
+    let channel_roles: Vec<(Option<u64>, Option<u64>)> = pipe.query(con)?;
+    Ok(channel_roles
+        .into_iter()
+        .flat_map(|(role_id, host_role_id)| role_id.into_iter().chain(host_role_id))
+        .map(RoleId)
+        .collect())
+}
+
+// Longest on the server first. Takes five round trips to Redis.
+pub fn inactive_members(
+    min_days: u32,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Vec<InactiveMember>, crate::BoxedError> {
+    let joined_before = Utc::now() - chrono::Duration::days(min_days as i64);
+    let channel_role_ids = channel_role_ids(con)?;
+    // Channels without roles give access with member overwrites
+    let channel_member_ids = crate::discord_channel_access::all_members(con)?;
+    let ignored_user_ids = crate::ignore_list::ignored_users(con)?;
+    // Collect the members first, such that the guild is not locked while
+    // we query Redis
+    let candidates: Vec<(u64, String, chrono::DateTime<Utc>)> = {
+        let guild = crate::discord_sync::GUILD_ID
+            .to_guild_cached(&discord_api.cache)
+            .ok_or_else(|| SimpleError::new("Could not find the guild in the cache"))?;
+        let guild = guild.read();
+        let owner_id = guild.owner_id;
+        guild
+            .members
+            .values()
+            .filter_map(|member| {
+                let user = member.user.read();
+                if user.bot
+                    || user.id == owner_id
+                    || member.roles.contains(&crate::discord_sync::ORGANIZER_ID)
+                    || member
+                        .roles
+                        .iter()
+                        .any(|role_id| channel_role_ids.contains(role_id))
+                    || channel_member_ids.contains(&user.id.0)
+                    || ignored_user_ids.contains(&user.id.0)
+                {
+                    return None;
+                }
+                let joined_at = member.joined_at?.with_timezone(&Utc);
+                if joined_at > joined_before {
+                    return None;
+                }
+                Some((user.id.0, user.tag(), joined_at))
+            })
+            .collect()
+    };
+    if candidates.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut pipe = redis::pipe();
+    for &(discord_id, _, _) in &candidates {
+        pipe.smembers(crate::identity::redis_identities_key(discord_id))
+            .exists(crate::households::redis_household_meetup_user_key(
+                discord_id,
+            ))
+            .hget(REDIS_NUDGE_TIMES_KEY, discord_id);
+    }
+    let states: Vec<(Vec<String>, bool, Option<i64>)> = pipe.query(con)?;
+    let mut inactive_members: Vec<InactiveMember> = candidates
+        .into_iter()
+        .zip(states)
+        .filter_map(
+            |((discord_id, tag, joined_at), (identities, is_household_member, last_nudge_time))| {
+                let is_linked = identities
+                    .iter()
+                    .filter_map(|identity| crate::identity::Identity::parse(identity))
+                    .any(|identity| identity.meetup_id().is_some());
+                if is_linked || is_household_member {
+                    return None;
+                }
+                Some(InactiveMember {
+                    discord_id: discord_id,
+                    tag: tag,
+                    joined_at: joined_at,
+                    last_nudge_time: last_nudge_time.map(|timestamp| {
+                        chrono::DateTime::<Utc>::from_utc(
+                            chrono::NaiveDateTime::from_timestamp(timestamp, 0),
+                            Utc,
+                        )
+                    }),
+                })
+            },
+        )
+        .collect();
+    inactive_members.sort_unstable_by_key(|member| member.joined_at);
+    Ok(inactive_members)
+}
+
+// The members that a nudge would reach right now
+pub fn members_to_nudge(
+    min_days: u32,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Vec<InactiveMember>, crate::BoxedError> {
+    Ok(inactive_members(min_days, con, discord_api)?
+        .into_iter()
+        .filter(InactiveMember::can_be_nudged)
+        .collect())
+}
+
+// Returns the number of members that got the DM and the number of members
+// that couldn't be reached (most likely because they don't accept DMs)
+pub fn nudge(
+    min_days: u32,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(usize, usize), crate::BoxedError> {
+    let members = members_to_nudge(min_days, con, discord_api)?;
+    let (mut num_nudged, mut num_failed) = (0, 0);
+    for member in members {
+        let result = UserId(member.discord_id)
+            .to_user(discord_api)
+            .and_then(|user| {
+                user.direct_message(discord_api, |message_builder| {
+                    message_builder.content(crate::strings::INACTIVE_MEMBER_NUDGE)
+                })
+            });
+        // Members that couldn't be reached are tried again with the next nudge
+        if let Err(err) = result {
+            eprintln!(
+                "Could not nudge inactive member {}: {}",
+                member.discord_id, err
+            );
+            num_failed += 1;
+            continue;
+        }
+        let _: () = con.hset(
+            REDIS_NUDGE_TIMES_KEY,
+            member.discord_id,
+            Utc::now().timestamp(),
+        )?;
+        num_nudged += 1;
+    }
+    Ok((num_nudged, num_failed))
+}
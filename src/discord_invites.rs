@@ -0,0 +1,133 @@
+use redis::{Commands, PipelineCommands};
+use serenity::http::CacheHttp;
+use serenity::model::id::ChannelId;
+use std::collections::HashMap;
+
+// Organizers create an invite per place they advertise the community with
+// "invite create <label>" (e.g. "meetup", "flyers" or "friends"), to find out
+// what actually brings new members. Discord doesn't tell which invite a new
+// member used, but it counts the uses of each invite: when somebody joins,
+// the bot compares the counts with the ones it saw last and attributes the
+// join to the invite whose count went up. Joins through other invites, or
+// that can't be told apart because several counts went up at once, are
+// counted as unattributed. Listing the invites needs the "Manage Server"
+// permission.
+pub const MAX_LABEL_CHARS: usize = 50;
+
+fn redis_invite_labels_key() -> String {
+    format!("guild:{}:invite_labels", crate::discord_sync::GUILD_ID.0)
+}
+
+fn redis_invite_uses_key() -> String {
+    format!("guild:{}:invite_uses", crate::discord_sync::GUILD_ID.0)
+}
+
+fn redis_invite_joins_key() -> String {
+    format!("guild:{}:invite_joins", crate::discord_sync::GUILD_ID.0)
+}
+
+fn redis_unattributed_joins_key() -> String {
+    format!(
+        "guild:{}:unattributed_joins",
+        crate::discord_sync::GUILD_ID.0
+    )
+}
+
+// Labels are compared case-insensitively
+pub fn normalize_label(label: &str) -> Option<String> {
+    let label = label.trim().to_lowercase();
+    if label.is_empty() || label.chars().count() > MAX_LABEL_CHARS {
+        None
+    } else {
+        Some(label)
+    }
+}
+
+// Creates a permanent invite to the channel and returns its code
+pub fn create_invite(
+    label: &str,
+    channel_id: ChannelId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<String, crate::BoxedError> {
+    let invite = channel_id.create_invite(discord_api.http(), |invite_builder| {
+        invite_builder.max_age(0).max_uses(0).unique(true)
+    })?;
+    let _: () = redis::pipe()
+        .atomic()
+        .hset(redis_invite_labels_key(), &invite.code, label)
+        .ignore()
+        .hset(redis_invite_uses_key(), &invite.code, invite.uses)
+        .ignore()
+        .query(redis_connection)?;
+    Ok(invite.code)
+}
+
+// Called when somebody joins the server. Returns the label of the invite the
+// join was attributed to, if any.
+pub fn record_join(
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Option<String>, crate::BoxedError> {
+    let labels: HashMap<String, String> = redis_connection.hgetall(redis_invite_labels_key())?;
+    if labels.is_empty() {
+        let _: () = redis_connection.incr(redis_unattributed_joins_key(), 1)?;
+        return Ok(None);
+    }
+    let known_uses: HashMap<String, u64> = redis_connection.hgetall(redis_invite_uses_key())?;
+    let invites = crate::discord_sync::GUILD_ID.invites(discord_api.http())?;
+    let used_invites: Vec<_> = invites
+        .iter()
+        .filter(|invite| labels.contains_key(&invite.code))
+        .filter(|invite| invite.uses > known_uses.get(&invite.code).cloned().unwrap_or(0))
+        .collect();
+    let mut pipe = redis::pipe();
+    let label = match used_invites.as_slice() {
+        [invite] => {
+            // Count only this join. If several people joined through the
+            // same invite at once, the next join picks up the rest.
+            let label = &labels[&invite.code];
+            pipe.hincr(redis_invite_uses_key(), &invite.code, 1)
+                .ignore()
+                .hincr(redis_invite_joins_key(), label, 1)
+                .ignore();
+            Some(label.clone())
+        }
+        _ => {
+            for invite in &used_invites {
+                pipe.hset(redis_invite_uses_key(), &invite.code, invite.uses)
+                    .ignore();
+            }
+            pipe.incr(redis_unattributed_joins_key(), 1).ignore();
+            None
+        }
+    };
+    let _: () = pipe.query(redis_connection)?;
+    Ok(label)
+}
+
+// The number of joins per label (most first, including the labels without
+// any) and the number of unattributed joins
+pub fn join_stats(
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<(Vec<(String, u64)>, u64), crate::BoxedError> {
+    let (labels, joins, unattributed): (
+        HashMap<String, String>,
+        HashMap<String, u64>,
+        Option<u64>,
+    ) = redis::pipe()
+        .hgetall(redis_invite_labels_key())
+        .hgetall(redis_invite_joins_key())
+        .get(redis_unattributed_joins_key())
+        .query(redis_connection)?;
+    let mut stats: Vec<(String, u64)> = joins.into_iter().collect();
+    for label in labels.values() {
+        if !stats.iter().any(|(known_label, _)| known_label == label) {
+            stats.push((label.clone(), 0));
+        }
+    }
+    stats.sort_by(|(label1, joins1), (label2, joins2)| {
+        joins2.cmp(joins1).then_with(|| label1.cmp(label2))
+    });
+    Ok((stats, unattributed.unwrap_or(0)))
+}
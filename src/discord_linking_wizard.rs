@@ -0,0 +1,225 @@
+use redis::Commands;
+use serenity::{
+    http::CacheHttp,
+    model::id::UserId,
+    model::user::User,
+    prelude::{Context, RwLock},
+};
+use std::sync::Arc;
+
+// Guides a user through linking their Meetup profile in DMs. After the
+// "link meetup" command, the user gets the steps and the linking link, and
+// the wizard then polls Redis until the link shows up. Once it does, the user
+// gets a confirmation with the name of the linked Meetup profile (usually
+// right away from the OAuth2 callback, see `complete`). If nothing
+// happened before the timeout, the user gets troubleshooting options instead:
+// a new link with "link meetup" or help from an organizer with
+// "linking help".
+// The polling is not persisted, after a restart the user simply doesn't get
+// the confirmation (but the linking itself works as before).
+pub const WIZARD_TIMEOUT_MINUTES: i64 = 10;
+// The key expires a bit after the timeout in case the poll task never gets to
+// remove it
+pub const WIZARD_KEY_TTL_SECS: usize = (WIZARD_TIMEOUT_MINUTES * 60 + 5 * 60) as usize;
+const POLL_INTERVAL_SECONDS: i64 = 15;
+// Organizers only get one help request per user in this time
+pub const HELP_REQUEST_COOLDOWN_SECONDS: usize = 60 * 60;
+
+fn redis_wizard_key(discord_id: u64) -> String {
+    format!("discord_user:{}:linking_wizard", discord_id)
+}
+
+// Sends the steps and the link and starts polling. Starting a new wizard
+// stops the previous one of the same user.
+pub fn start(ctx: &Context, user: &User, linking_url: &str) -> Result<(), crate::BoxedError> {
+    let (redis_client, meetup_client, task_scheduler) = {
+        let data = ctx.data.read();
+        (
+            data.get::<crate::discord_bot::RedisClientKey>()
+                .expect("Redis client was not set")
+                .clone(),
+            data.get::<crate::discord_bot::MeetupClientKey>()
+                .expect("Meetup client was not set")
+                .clone(),
+            data.get::<crate::discord_bot::TaskSchedulerKey>()
+                .expect("Task scheduler was not set")
+                .clone(),
+        )
+    };
+    user.direct_message(ctx, |message| {
+        message.content(crate::strings::LINKING_WIZARD_STEPS(WIZARD_TIMEOUT_MINUTES))
+    })?;
+    user.direct_message(ctx, |message| {
+        message.content(crate::strings::MEETUP_LINKING_MESSAGE(linking_url))
+    })?;
+    let wizard_id = crate::meetup_oauth2::new_random_id(8);
+    let mut con = redis_client.get_connection()?;
+    let _: () = con.set_ex(redis_wizard_key(user.id.0), &wizard_id, WIZARD_KEY_TTL_SECS)?;
+    let discord_api = crate::discord_bot::CacheAndHttp {
+        cache: ctx.cache.clone(),
+        http: ctx.http.clone(),
+    };
+    let discord_id = user.id.0;
+    let deadline =
+        white_rabbit::Utc::now() + white_rabbit::Duration::minutes(WIZARD_TIMEOUT_MINUTES);
+    task_scheduler.lock().add_task_datetime(
+        white_rabbit::Utc::now() + white_rabbit::Duration::seconds(POLL_INTERVAL_SECONDS),
+        move |_| {
+            let now = white_rabbit::Utc::now();
+            match poll(
+                discord_id,
+                &wizard_id,
+                now >= deadline,
+                &redis_client,
+                &meetup_client,
+                &discord_api,
+            ) {
+                Ok(true) => white_rabbit::DateResult::Done,
+                Ok(false) => white_rabbit::DateResult::Repeat(
+                    now + white_rabbit::Duration::seconds(POLL_INTERVAL_SECONDS),
+                ),
+                Err(err) => {
+                    eprintln!(
+                        "Error in the linking wizard of user {}: {}",
+                        discord_id, err
+                    );
+                    if now >= deadline {
+                        white_rabbit::DateResult::Done
+                    } else {
+                        white_rabbit::DateResult::Repeat(
+                            now + white_rabbit::Duration::seconds(POLL_INTERVAL_SECONDS),
+                        )
+                    }
+                }
+            }
+        },
+    );
+    Ok(())
+}
+
+// Returns whether the wizard is over
+fn poll(
+    discord_id: u64,
+    wizard_id: &str,
+    timed_out: bool,
+    redis_client: &crate::redis_namespace::Client,
+    meetup_client: &Arc<RwLock<Option<crate::meetup_api::Client>>>,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    let mut con = redis_client.get_connection()?;
+    // Superseded by a newer wizard
+    let current_wizard_id: Option<String> = con.get(redis_wizard_key(discord_id))?;
+    if current_wizard_id.as_ref().map(String::as_str) != Some(wizard_id) {
+        return Ok(true);
+    }
+    let linked_meetup_id = crate::identity::meetup_id(discord_id, &mut con)?;
+    let message = match linked_meetup_id {
+        Some(meetup_id) => {
+            if let Err(err) = grant_linked_role(discord_id, discord_api) {
+                eprintln!(
+                    "Could not give the linked role to user {}: {}",
+                    discord_id, err
+                );
+            }
+            let meetup_name = match *meetup_client.read() {
+                Some(ref meetup_client) => meetup_client
+                    .get_member_profile(Some(meetup_id))
+                    .unwrap_or_else(|err| {
+                        eprintln!("Could not get the Meetup profile {}: {}", meetup_id, err);
+                        None
+                    })
+                    .map(|user| user.name),
+                None => None,
+            };
+            crate::strings::LINKING_WIZARD_SUCCESS(meetup_name.as_ref().map(String::as_str))
+        }
+        None if timed_out => crate::strings::LINKING_WIZARD_TIMEOUT(WIZARD_TIMEOUT_MINUTES),
+        None => return Ok(false),
+    };
+    let _: () = con.del(redis_wizard_key(discord_id))?;
+    let user = UserId(discord_id).to_user(discord_api)?;
+    user.direct_message(discord_api, |message_builder| {
+        message_builder.content(&message)
+    })?;
+    Ok(true)
+}
+
+// Called by the OAuth2 callback once the link was created, such that the
+// user gets the confirmation right away. This also ends the wizard, which
+// would otherwise send the confirmation a second time.
+pub fn complete(
+    discord_id: u64,
+    meetup_name: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let _: () = redis_connection.del(redis_wizard_key(discord_id))?;
+    if let Err(err) = grant_linked_role(discord_id, discord_api) {
+        eprintln!(
+            "Could not give the linked role to user {}: {}",
+            discord_id, err
+        );
+    }
+    let user = UserId(discord_id).to_user(discord_api)?;
+    user.direct_message(discord_api, |message_builder| {
+        message_builder.content(crate::strings::LINKING_WIZARD_SUCCESS(Some(meetup_name)))
+    })?;
+    Ok(())
+}
+
+// Does nothing if there is no linked role
+pub fn grant_linked_role(
+    discord_id: u64,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    if let Some(linked_role) = crate::discord_sync::LINKED_ROLE_ID {
+        discord_api.http().add_member_role(
+            crate::discord_sync::GUILD_ID.0,
+            discord_id,
+            linked_role.0,
+        )?;
+    }
+    Ok(())
+}
+
+pub fn revoke_linked_role(
+    discord_id: u64,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    if let Some(linked_role) = crate::discord_sync::LINKED_ROLE_ID {
+        discord_api.http().remove_member_role(
+            crate::discord_sync::GUILD_ID.0,
+            discord_id,
+            linked_role.0,
+        )?;
+    }
+    Ok(())
+}
+
+// Lets the organizers know that a user is stuck. Returns false if the user
+// already asked recently.
+pub fn request_help(
+    discord_id: u64,
+    redis_client: &crate::redis_namespace::Client,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    let mut con = redis_client.get_connection()?;
+    let redis_help_key = format!("discord_user:{}:linking_help_requested", discord_id);
+    // SET NX replies with nil if the key already exists
+    let set_result: Option<String> = redis::cmd("SET")
+        .arg(&redis_help_key)
+        .arg(1)
+        .arg("NX")
+        .arg("EX")
+        .arg(HELP_REQUEST_COOLDOWN_SECONDS)
+        .query(&mut con)?;
+    if set_result.is_none() {
+        return Ok(false);
+    }
+    crate::discord_notifications::notify_organizers(
+        discord_api,
+        &crate::strings::LINKING_HELP_NOTIFICATION(discord_id),
+        &mut con,
+    )?;
+    Ok(true)
+}
@@ -0,0 +1,81 @@
+use redis::{Commands, PipelineCommands};
+use serenity::model::id::ChannelId;
+
+// Hosts can teach the bot short replies for their channel, e.g. for house
+// rules or the link to the shared map: "macro add loot <text>" makes the bot
+// reply to "@bot loot" with the text. Macros only work in the channel they
+// were added in. Built-in commands always win, so names that a command starts
+// with are refused.
+pub const MAX_MACROS_PER_CHANNEL: usize = 25;
+pub const MAX_MACRO_NAME_CHARS: usize = 32;
+pub const MAX_MACRO_TEXT_CHARS: usize = 1500;
+
+fn redis_channel_macros_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:macros", channel_id.0)
+}
+
+// A single word of letters, digits, "-" and "_" that isn't taken by a command
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_MACRO_NAME_CHARS
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        && !crate::discord_command_aliases::is_command_word(name)
+}
+
+// Macros shouldn't be a way to ping the whole server
+pub fn is_valid_text(text: &str) -> bool {
+    text.chars().count() <= MAX_MACRO_TEXT_CHARS
+        && !text.contains("@everyone")
+        && !text.contains("@here")
+}
+
+// Returns false if the channel already has the maximum number of macros.
+// Adding a macro with an existing name replaces it.
+pub fn add_macro<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    name: &str,
+    text: &str,
+) -> Result<bool, crate::BoxedError> {
+    let redis_channel_macros_key = redis_channel_macros_key(channel_id);
+    let name = name.to_lowercase();
+    let (exists, num_macros): (bool, usize) = redis::pipe()
+        .hexists(&redis_channel_macros_key, &name)
+        .hlen(&redis_channel_macros_key)
+        .query(con)?;
+    if !exists && num_macros >= MAX_MACROS_PER_CHANNEL {
+        return Ok(false);
+    }
+    let _: () = con.hset(&redis_channel_macros_key, name, text)?;
+    Ok(true)
+}
+
+// Returns false if there was no such macro
+pub fn remove_macro<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    name: &str,
+) -> Result<bool, crate::BoxedError> {
+    let num_removed: u32 = con.hdel(redis_channel_macros_key(channel_id), name.to_lowercase())?;
+    Ok(num_removed > 0)
+}
+
+pub fn macro_text<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    name: &str,
+) -> Result<Option<String>, crate::BoxedError> {
+    Ok(con.hget(redis_channel_macros_key(channel_id), name.to_lowercase())?)
+}
+
+// Sorted by name
+pub fn macro_names<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<Vec<String>, crate::BoxedError> {
+    let mut names: Vec<String> = con.hkeys(redis_channel_macros_key(channel_id))?;
+    names.sort_unstable();
+    Ok(names)
+}
@@ -0,0 +1,186 @@
+use redis::Commands;
+use serenity::model::id::{ChannelId, RoleId, UserId};
+use simple_error::SimpleError;
+use std::collections::HashSet;
+
+// Hosts and organizers can export the members of a channel as CSV, e.g. for
+// a venue's sign-in sheet or for safety records.
+// The source tells whether the member is in the channel because of an RSVP
+// on Meetup or because they were added manually. The absence is the reason
+// given with the "absent" command for the next session ("absent" without one).
+const CSV_HEADER: &'static str = "discord_id,discord_tag,role,meetup_id,meetup_name,source,absence";
+
+pub fn export_members_csv(
+    channel_id: ChannelId,
+    channel_access: &crate::discord_bot_commands::ChannelAccess,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<String, crate::BoxedError> {
+    // Everybody who RSVP'd to (or hosts) one of the channel's events
+    let redis_channel_series_key = format!("discord_channel:{}:event_series", channel_id.0);
+    let series_id: Option<String> = redis_connection.get(&redis_channel_series_key)?;
+    let rsvp_meetup_ids: HashSet<u64> = match series_id {
+        Some(series_id) => {
+            let redis_series_events_key = format!("event_series:{}:meetup_events", series_id);
+            let event_ids: Vec<String> = redis_connection.smembers(&redis_series_events_key)?;
+            let mut redis_event_users_keys = Vec::with_capacity(2 * event_ids.len());
+            for event_id in &event_ids {
+                redis_event_users_keys.push(format!("meetup_event:{}:meetup_users", event_id));
+                redis_event_users_keys.push(format!("meetup_event:{}:meetup_hosts", event_id));
+            }
+            if redis_event_users_keys.is_empty() {
+                HashSet::new()
+            } else {
+                redis_connection.sunion(redis_event_users_keys)?
+            }
+        }
+        None => HashSet::new(),
+    };
+    // Collect the members first, such that the guild is not locked while
+    // we query Redis
+    let mut members: Vec<(u64, String, bool)> = match channel_access {
+        crate::discord_bot_commands::ChannelAccess::Roles(channel_roles) => {
+            let (user_role_id, host_role_id) =
+                (RoleId(channel_roles.user), RoleId(channel_roles.host));
+            let guild = crate::discord_sync::GUILD_ID
+                .to_guild_cached(&discord_api.cache)
+                .ok_or_else(|| SimpleError::new("Could not find the guild in the cache"))?;
+            let guild = guild.read();
+            guild
+                .members
+                .values()
+                .filter(|member| {
+                    member.roles.contains(&user_role_id) || member.roles.contains(&host_role_id)
+                })
+                .map(|member| {
+                    let user = member.user.read();
+                    (user.id.0, user.tag(), member.roles.contains(&host_role_id))
+                })
+                .collect()
+        }
+        crate::discord_bot_commands::ChannelAccess::Overwrites => {
+            let host_ids: HashSet<u64> =
+                crate::discord_channel_access::hosts(redis_connection, channel_id)?
+                    .into_iter()
+                    .collect();
+            let mut member_ids: HashSet<u64> =
+                crate::discord_channel_access::players(redis_connection, channel_id)?
+                    .into_iter()
+                    .collect();
+            member_ids.extend(&host_ids);
+            let guild = crate::discord_sync::GUILD_ID
+                .to_guild_cached(&discord_api.cache)
+                .ok_or_else(|| SimpleError::new("Could not find the guild in the cache"))?;
+            let guild = guild.read();
+            // Like with the roles, members who left the server are not listed
+            member_ids
+                .into_iter()
+                .filter_map(|discord_id| {
+                    let member = guild.members.get(&UserId(discord_id))?;
+                    let tag = member.user.read().tag();
+                    Some((discord_id, tag, host_ids.contains(&discord_id)))
+                })
+                .collect()
+        }
+    };
+    members.sort_unstable_by(|a, b| a.1.to_lowercase().cmp(&b.1.to_lowercase()));
+    let absences = crate::discord_absences::next_session_absences(channel_id, redis_connection)?;
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for (discord_id, discord_tag, is_host) in members {
+        let meetup_id = crate::identity::meetup_id(discord_id, redis_connection)?;
+        let meetup_name: Option<String> = match meetup_id {
+            Some(meetup_id) => {
+                redis_connection.hget(format!("meetup_user:{}:profile", meetup_id), "name")?
+            }
+            None => None,
+        };
+        let source = match meetup_id {
+            Some(meetup_id) if rsvp_meetup_ids.contains(&meetup_id) => "rsvp",
+            _ => "manual",
+        };
+        let fields = [
+            discord_id.to_string(),
+            discord_tag,
+            if is_host { "host" } else { "player" }.to_string(),
+            meetup_id.map(|id| id.to_string()).unwrap_or_default(),
+            meetup_name.unwrap_or_default(),
+            source.to_string(),
+            absence_field(absences.get(&discord_id).map(String::as_str)),
+        ];
+        let fields: Vec<_> = fields.iter().map(|field| escape_csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    Ok(csv)
+}
+
+// The reason is whatever the player wrote, see escape_csv_field
+fn absence_field(reason: Option<&str>) -> String {
+    match reason {
+        Some(reason) if reason.is_empty() => "absent".to_string(),
+        Some(reason) => reason.to_string(),
+        None => String::new(),
+    }
+}
+
+// Quotes fields that contain separators, quotes or line breaks (RFC 4180).
+// Names and absence reasons are up to the users, and spreadsheet apps run
+// fields that start like a formula, so those get a ' in front.
+fn escape_csv_field(field: &str) -> String {
+    let field = if field
+        .starts_with(|c| c == '=' || c == '+' || c == '-' || c == '@' || c == '\t' || c == '\r')
+    {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+    if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_plain_fields_alone() {
+        assert_eq!(escape_csv_field("player#1234"), "player#1234");
+        assert_eq!(escape_csv_field(""), "");
+        assert_eq!(escape_csv_field("a=b"), "a=b");
+    }
+
+    #[test]
+    fn quotes_separators_and_line_breaks() {
+        assert_eq!(escape_csv_field("Doe, Jane"), "\"Doe, Jane\"");
+        assert_eq!(escape_csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn defuses_formulas() {
+        assert_eq!(escape_csv_field("=1+1"), "'=1+1");
+        assert_eq!(escape_csv_field("+1"), "'+1");
+        assert_eq!(escape_csv_field("-1"), "'-1");
+        assert_eq!(escape_csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape_csv_field("\t=1"), "'\t=1");
+        assert_eq!(escape_csv_field("\r=1"), "\"'\r=1\"");
+        assert_eq!(
+            escape_csv_field("=HYPERLINK(\"http://example.com\",\"x\")"),
+            "\"'=HYPERLINK(\"\"http://example.com\"\",\"\"x\"\")\""
+        );
+    }
+
+    #[test]
+    fn exports_absence_reasons_safely() {
+        let export = |reason| escape_csv_field(&absence_field(reason));
+        assert_eq!(export(None), "");
+        assert_eq!(export(Some("")), "absent");
+        assert_eq!(export(Some("sick, sorry")), "\"sick, sorry\"");
+        assert_eq!(export(Some("=1+1")), "'=1+1");
+        assert_eq!(export(Some("@everyone")), "'@everyone");
+    }
+}
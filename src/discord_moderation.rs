@@ -0,0 +1,155 @@
+use lazy_static::lazy_static;
+use redis::Commands;
+use regex::Regex;
+use serenity::{model::channel::Message, prelude::*};
+
+// A lightweight moderation pass over the messages in bot-managed channels,
+// enabled with the "link_moderation" feature. Messages by players that
+// contain a Discord invite or a link matching one of the blocked patterns
+// are deleted, the player gets a DM and the deletion is logged for the
+// organizers. Hosts and organizers are exempt, and channels that the bot
+// doesn't manage are never touched.
+// Patterns are matched case-insensitively as plain text against each link.
+pub const MAX_PATTERN_CHARS: usize = 100;
+
+// Discord messages are limited to 2000 characters
+const MAX_LOGGED_CONTENT_CHARS: usize = 1000;
+
+lazy_static! {
+    static ref LINK_REGEX: Regex = Regex::new(r"(?i)https?://\S+").unwrap();
+    // Invites also work without the scheme
+    static ref INVITE_REGEX: Regex =
+        Regex::new(r"(?i)\b(discord\.gg|discord(app)?\.com/invite)/\S+").unwrap();
+}
+
+fn redis_blocked_link_patterns_key() -> String {
+    format!(
+        "guild:{}:blocked_link_patterns",
+        crate::discord_sync::GUILD_ID.0
+    )
+}
+
+pub fn blocked_link_patterns<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Vec<String>, crate::BoxedError> {
+    let mut patterns: Vec<String> = con.smembers(redis_blocked_link_patterns_key())?;
+    patterns.sort_unstable();
+    Ok(patterns)
+}
+
+// Returns false if the pattern was already blocked
+pub fn block_link_pattern<C: redis::ConnectionLike>(
+    con: &mut C,
+    pattern: &str,
+) -> Result<bool, crate::BoxedError> {
+    let num_added: u32 = con.sadd(
+        redis_blocked_link_patterns_key(),
+        pattern.trim().to_lowercase(),
+    )?;
+    Ok(num_added > 0)
+}
+
+// Returns false if the pattern wasn't blocked
+pub fn unblock_link_pattern<C: redis::ConnectionLike>(
+    con: &mut C,
+    pattern: &str,
+) -> Result<bool, crate::BoxedError> {
+    let num_removed: u32 = con.srem(
+        redis_blocked_link_patterns_key(),
+        pattern.trim().to_lowercase(),
+    )?;
+    Ok(num_removed > 0)
+}
+
+// Why the message has to go, if it has to
+fn violation(content: &str, patterns: &[String]) -> Option<String> {
+    if INVITE_REGEX.is_match(content) {
+        return Some("Discord invite".to_string());
+    }
+    LINK_REGEX.find_iter(content).find_map(|link| {
+        let link = link.as_str().to_lowercase();
+        patterns
+            .iter()
+            .find(|pattern| link.contains(pattern.as_str()))
+            .map(|pattern| format!("link matching \"{}\"", pattern))
+    })
+}
+
+// Returns whether the message was deleted
+pub fn moderate_message(
+    ctx: &Context,
+    msg: &Message,
+    redis_client: &crate::redis_namespace::Client,
+) -> Result<bool, crate::BoxedError> {
+    // Most messages don't contain any links, so check that before talking
+    // to Redis
+    if !LINK_REGEX.is_match(&msg.content) && !INVITE_REGEX.is_match(&msg.content) {
+        return Ok(false);
+    }
+    let mut redis_connection = redis_client.get_connection()?;
+    if !crate::feature_flags::is_enabled(
+        &mut redis_connection,
+        crate::discord_sync::GUILD_ID,
+        crate::feature_flags::Feature::LinkModeration,
+    )? {
+        return Ok(false);
+    }
+    // Only channels that the bot manages
+    let is_managed: bool = redis_connection.sismember("discord_channels", msg.channel_id.0)?;
+    if !is_managed {
+        return Ok(false);
+    }
+    let channel_access = match crate::discord_bot::Handler::get_channel_access(
+        msg.channel_id.0,
+        &mut redis_connection,
+    )? {
+        Some(channel_access) => channel_access,
+        None => return Ok(false),
+    };
+    let patterns = blocked_link_patterns(&mut redis_connection)?;
+    let reason = match violation(&msg.content, &patterns) {
+        Some(reason) => reason,
+        None => return Ok(false),
+    };
+    if crate::discord_bot::Handler::is_channel_admin(
+        ctx,
+        &msg.author,
+        msg.channel_id,
+        &channel_access,
+        &mut redis_connection,
+    )? {
+        return Ok(false);
+    }
+    msg.delete(ctx)?;
+    println!(
+        "Deleted a message by user {} in channel {} ({})",
+        msg.author.id.0, msg.channel_id.0, reason
+    );
+    let content: String = msg.content.chars().take(MAX_LOGGED_CONTENT_CHARS).collect();
+    let discord_api = crate::discord_bot::CacheAndHttp {
+        cache: ctx.cache.clone(),
+        http: ctx.http.clone(),
+    };
+    if let Err(err) = msg.author.direct_message(ctx, |message_builder| {
+        message_builder.content(crate::strings::MODERATION_DELETION_NOTE(
+            msg.channel_id.0,
+            &reason,
+        ))
+    }) {
+        eprintln!(
+            "Could not tell user {} about the deleted message: {}",
+            msg.author.id.0, err
+        );
+    }
+    crate::discord_notifications::notify_organizers(
+        &discord_api,
+        &crate::strings::MODERATION_DELETION_NOTIFICATION(
+            msg.author.id.0,
+            msg.channel_id.0,
+            &reason,
+            &content,
+        ),
+        &mut redis_connection,
+    )?;
+    Ok(true)
+}
@@ -0,0 +1,268 @@
+use redis::{Commands, PipelineCommands};
+use serenity::model::id::UserId;
+use simple_error::SimpleError;
+
+// Shows the Meetup first name of linked members in their Discord nickname, so
+// that players recognize each other at the table. Organizers pick the style
+// with "config set meetup_nicknames <style>", it is applied by the Discord sync
+// and right after a user links their account. Users that don't want it write
+// "nickname sync off" to the bot.
+// Nicknames that users chose themselves are left alone: the bot only changes
+// a nickname if there is none or if it is the one the bot set last time.
+const REDIS_OPT_OUTS_KEY: &'static str = "nickname_sync_opt_outs";
+
+// Discord doesn't allow longer nicknames
+const MAX_NICKNAME_CHARS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NicknameStyle {
+    Off,
+    // "Alice"
+    FirstName,
+    // "dragonslayer99 (Alice)"
+    Suffix,
+}
+
+impl NicknameStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NicknameStyle::Off => "off",
+            NicknameStyle::FirstName => "first_name",
+            NicknameStyle::Suffix => "suffix",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<NicknameStyle> {
+        [
+            NicknameStyle::Off,
+            NicknameStyle::FirstName,
+            NicknameStyle::Suffix,
+        ]
+        .iter()
+        .find(|style| style.name().eq_ignore_ascii_case(name))
+        .cloned()
+    }
+
+    // None if the style is off or the Meetup name is empty
+    fn nickname(&self, user_name: &str, meetup_name: &str) -> Option<String> {
+        let first_name = meetup_name.split_whitespace().next()?;
+        let nickname = match self {
+            NicknameStyle::Off => return None,
+            NicknameStyle::FirstName => first_name.chars().take(MAX_NICKNAME_CHARS).collect(),
+            NicknameStyle::Suffix => {
+                let suffix = format!(" ({})", first_name);
+                let max_user_name_chars = MAX_NICKNAME_CHARS.saturating_sub(suffix.chars().count());
+                let user_name: String = user_name.chars().take(max_user_name_chars).collect();
+                format!("{}{}", user_name, suffix)
+                    .chars()
+                    .take(MAX_NICKNAME_CHARS)
+                    .collect()
+            }
+        };
+        Some(nickname)
+    }
+}
+
+fn redis_synced_nickname_key(discord_id: u64) -> String {
+    format!("discord_user:{}:synced_nickname", discord_id)
+}
+
+fn redis_meetup_profile_key(meetup_id: u64) -> String {
+    format!("meetup_user:{}:profile", meetup_id)
+}
+
+// Changes the nickname unless the user chose one themselves
+fn apply_nickname(
+    discord_id: u64,
+    current_nickname: Option<&str>,
+    synced_nickname: Option<&str>,
+    nickname: &str,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    if current_nickname == Some(nickname) {
+        return Ok(());
+    }
+    if current_nickname.is_some() && current_nickname != synced_nickname {
+        return Ok(());
+    }
+    crate::discord_sync::GUILD_ID.edit_member(&discord_api.http, UserId(discord_id), |member| {
+        member.nickname(nickname)
+    })?;
+    let _: () = con.set(redis_synced_nickname_key(discord_id), nickname)?;
+    Ok(())
+}
+
+// The user's user name and nickname, None if they are not on the server (or
+// own it, the bot can't change the owner's nickname)
+fn guild_member(
+    discord_id: u64,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Option<(String, Option<String>)>, crate::BoxedError> {
+    let guild = crate::discord_sync::GUILD_ID
+        .to_guild_cached(&discord_api.cache)
+        .ok_or_else(|| SimpleError::new("Could not find the guild in the cache"))?;
+    let guild = guild.read();
+    if guild.owner_id.0 == discord_id {
+        return Ok(None);
+    }
+    Ok(guild
+        .members
+        .get(&UserId(discord_id))
+        .map(|member| (member.user.read().name.clone(), member.nick.clone())))
+}
+
+// Called once a user linked their Meetup account. Remembers the Meetup name,
+// the profile sync only runs once a day.
+pub fn sync_linked_user(
+    discord_id: u64,
+    meetup_id: u64,
+    meetup_name: &str,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let _: () = con.hset(redis_meetup_profile_key(meetup_id), "name", meetup_name)?;
+    let style = crate::guild_config::meetup_nicknames(con)?;
+    let (synced_nickname, is_opted_out): (Option<String>, bool) = redis::pipe()
+        .get(redis_synced_nickname_key(discord_id))
+        .sismember(REDIS_OPT_OUTS_KEY, discord_id)
+        .query(con)?;
+    if is_opted_out {
+        return Ok(());
+    }
+    let (user_name, current_nickname) = match guild_member(discord_id, discord_api)? {
+        Some(member) => member,
+        None => return Ok(()),
+    };
+    if let Some(nickname) = style.nickname(&user_name, meetup_name) {
+        apply_nickname(
+            discord_id,
+            current_nickname.as_ref().map(String::as_str),
+            synced_nickname.as_ref().map(String::as_str),
+            &nickname,
+            con,
+            discord_api,
+        )?;
+    }
+    Ok(())
+}
+
+// Brings the nicknames of all linked members up to date. Takes two round
+// trips to Redis, plus one for each changed nickname.
+pub fn sync_nicknames(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let style = crate::guild_config::meetup_nicknames(con)?;
+    if style == NicknameStyle::Off {
+        return Ok(());
+    }
+    // Collect the members first, such that the guild is not locked while
+    // we query Redis
+    let members: Vec<(u64, String, Option<String>)> = {
+        let guild = crate::discord_sync::GUILD_ID
+            .to_guild_cached(&discord_api.cache)
+            .ok_or_else(|| SimpleError::new("Could not find the guild in the cache"))?;
+        let guild = guild.read();
+        let owner_id = guild.owner_id;
+        guild
+            .members
+            .values()
+            .filter_map(|member| {
+                let user = member.user.read();
+                if user.bot || user.id == owner_id {
+                    None
+                } else {
+                    Some((user.id.0, user.name.clone(), member.nick.clone()))
+                }
+            })
+            .collect()
+    };
+    if members.is_empty() {
+        return Ok(());
+    }
+    let mut pipe = redis::pipe();
+    for &(discord_id, _, _) in &members {
+        pipe.smembers(crate::identity::redis_identities_key(discord_id));
+    }
+    let identities: Vec<Vec<String>> = pipe.query(con)?;
+    let linked_members: Vec<(u64, String, Option<String>, u64)> = members
+        .into_iter()
+        .zip(identities)
+        .filter_map(|((discord_id, user_name, nickname), identities)| {
+            identities
+                .iter()
+                .filter_map(|identity| crate::identity::Identity::parse(identity))
+                .filter_map(|identity| identity.meetup_id())
+                .min()
+                .map(|meetup_id| (discord_id, user_name, nickname, meetup_id))
+        })
+        .collect();
+    if linked_members.is_empty() {
+        return Ok(());
+    }
+    let mut pipe = redis::pipe();
+    for &(discord_id, _, _, meetup_id) in &linked_members {
+        pipe.hget(redis_meetup_profile_key(meetup_id), "name")
+            .get(redis_synced_nickname_key(discord_id))
+            .sismember(REDIS_OPT_OUTS_KEY, discord_id);
+    }
+    let states: Vec<(Option<String>, Option<String>, bool)> = pipe.query(con)?;
+    for (
+        (discord_id, user_name, current_nickname, _),
+        (meetup_name, synced_nickname, is_opted_out),
+    ) in linked_members.into_iter().zip(states)
+    {
+        let meetup_name = match meetup_name {
+            Some(meetup_name) if !is_opted_out => meetup_name,
+            _ => continue,
+        };
+        let nickname = match style.nickname(&user_name, &meetup_name) {
+            Some(nickname) => nickname,
+            None => continue,
+        };
+        // Users with a higher role than the bot's can't be renamed, which
+        // shouldn't stop the others from being synced
+        if let Err(err) = apply_nickname(
+            discord_id,
+            current_nickname.as_ref().map(String::as_str),
+            synced_nickname.as_ref().map(String::as_str),
+            &nickname,
+            con,
+            discord_api,
+        ) {
+            eprintln!(
+                "Could not sync the nickname of user {}: {}",
+                discord_id, err
+            );
+        }
+    }
+    Ok(())
+}
+
+// Opting out removes the nickname that the bot set, opting back in brings it
+// back with the next sync
+pub fn set_opted_out(
+    discord_id: u64,
+    opted_out: bool,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    if !opted_out {
+        let _: () = con.srem(REDIS_OPT_OUTS_KEY, discord_id)?;
+        return Ok(());
+    }
+    let _: () = con.sadd(REDIS_OPT_OUTS_KEY, discord_id)?;
+    let synced_nickname: Option<String> = con.get(redis_synced_nickname_key(discord_id))?;
+    let current_nickname =
+        guild_member(discord_id, discord_api)?.and_then(|(_, nickname)| nickname);
+    if synced_nickname.is_some() && current_nickname == synced_nickname {
+        crate::discord_sync::GUILD_ID.edit_member(
+            &discord_api.http,
+            UserId(discord_id),
+            |member| member.nickname(""),
+        )?;
+    }
+    let _: () = con.del(redis_synced_nickname_key(discord_id))?;
+    Ok(())
+}
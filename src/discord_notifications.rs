@@ -0,0 +1,108 @@
+use crate::strings;
+use redis::{Commands, PipelineCommands};
+use simple_error::SimpleError;
+
+// Number of consecutive sync failures of the same event series after which
+// the organizers get notified
+pub const SYNC_FAILURE_NOTIFICATION_THRESHOLD: u64 = 3;
+
+// Discord messages are limited to 2000 characters
+const MAX_ERROR_MESSAGE_CHARS: usize = 1500;
+
+pub fn record_series_sync_success(
+    series_id: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    let redis_series_failures_key = format!("event_series:{}:sync_failures", series_id);
+    let redis_series_notified_key = format!("event_series:{}:sync_failure_notified", series_id);
+    let _: () = con.del(&[&redis_series_failures_key, &redis_series_notified_key])?;
+    Ok(())
+}
+
+// Counts consecutive sync failures per event series and escalates to the
+// organizers once the threshold is reached. Only the first few failures are
+// logged in full, afterwards the organizers already know about the problem.
+pub fn record_series_sync_failure(
+    series_id: &str,
+    error: &crate::BoxedError,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let redis_series_failures_key = format!("event_series:{}:sync_failures", series_id);
+    let redis_series_notified_key = format!("event_series:{}:sync_failure_notified", series_id);
+    let num_failures: u64 = con.incr(&redis_series_failures_key, 1)?;
+    crate::event_webhooks::emit(crate::event_webhooks::Event::SyncFailed {
+        series_id: series_id.to_string(),
+        consecutive_failures: num_failures,
+        error: error.inner.to_string(),
+    });
+    if num_failures < SYNC_FAILURE_NOTIFICATION_THRESHOLD {
+        eprintln!(
+            "Discord event series syncing task failed ({} consecutive failures): {}",
+            num_failures, error
+        );
+        return Ok(());
+    }
+    // Only notify once per streak of failures
+    let (newly_notified,): (bool,) = redis::pipe()
+        .set_nx(&redis_series_notified_key, true)
+        .query(con)?;
+    if !newly_notified {
+        eprintln!(
+            "Discord event series {} failed to sync again ({} consecutive failures)",
+            series_id, num_failures
+        );
+        return Ok(());
+    }
+    eprintln!(
+        "Discord event series syncing task failed ({} consecutive failures), \
+         notifying organizers: {}",
+        num_failures, error
+    );
+    let mut error_message = error.inner.to_string();
+    if error_message.len() > MAX_ERROR_MESSAGE_CHARS {
+        let mut end = MAX_ERROR_MESSAGE_CHARS;
+        while !error_message.is_char_boundary(end) {
+            end -= 1;
+        }
+        error_message.truncate(end);
+    }
+    notify_organizers(
+        discord_api,
+        &strings::SERIES_SYNC_FAILURE_NOTIFICATION(series_id, num_failures, &error_message),
+        con,
+    )
+}
+
+// Posts a message to the staff channel if there is one and otherwise sends
+// it as a direct message to every organizer
+pub fn notify_organizers(
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    message: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    if let Some(staff_channel_id) = crate::guild_config::staff_channel(con)? {
+        staff_channel_id.say(&discord_api.http, message)?;
+        return Ok(());
+    }
+    let guild = match crate::discord_sync::GUILD_ID.to_guild_cached(&discord_api.cache) {
+        Some(guild) => guild,
+        None => return Err(SimpleError::new("Could not find the guild in the cache").into()),
+    };
+    let organizers: Vec<_> = guild
+        .read()
+        .members
+        .values()
+        .filter(|member| member.roles.contains(&crate::discord_sync::ORGANIZER_ID))
+        .map(|member| member.user.clone())
+        .collect();
+    for organizer in organizers {
+        let organizer = organizer.read();
+        if let Err(err) =
+            organizer.direct_message(discord_api, |message_builder| message_builder.content(message))
+        {
+            eprintln!("Could not notify organizer {}: {}", organizer.id, err);
+        }
+    }
+    Ok(())
+}
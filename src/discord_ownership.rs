@@ -0,0 +1,140 @@
+use redis::Commands;
+use serenity::model::{
+    channel::Channel,
+    id::{ChannelId, RoleId},
+};
+
+// Before the bot deletes a channel during cleanup or expiration, it makes sure
+// that the channel is actually one it manages: it has to be recorded in the
+// Redis set the cleanup got it from, and it has to be in one of the bot's
+// categories. Otherwise a reused ID or a manual edit of Redis could make the
+// bot delete a channel that the humans care about.
+// Roles get the same treatment: they have to be recorded in the Redis set,
+// and neither be one of the configured roles nor one that an integration
+// manages.
+// Refused deletions are left alone (and kept in Redis) until an organizer
+// takes a look. The organizers are alerted once per channel or role.
+const REDIS_REFUSED_CHANNELS_KEY: &'static str = "discord_ownership:refused_channels";
+const REDIS_REFUSED_ROLES_KEY: &'static str = "discord_ownership:refused_roles";
+
+// Returns whether the channel may be deleted. `redis_set_key` is the set of
+// channels the caller is cleaning up, e.g. "discord_channels".
+pub fn verify_managed_channel(
+    channel_id: ChannelId,
+    redis_set_key: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    match refusal_reason(channel_id, redis_set_key, redis_connection, discord_api)? {
+        None => Ok(true),
+        Some(reason) => {
+            eprintln!("Refusing to delete channel {}: {}", channel_id.0, reason);
+            let newly_refused: bool =
+                redis_connection.sadd(REDIS_REFUSED_CHANNELS_KEY, channel_id.0)?;
+            if newly_refused {
+                crate::discord_notifications::notify_organizers(
+                    discord_api,
+                    &crate::strings::CHANNEL_DELETION_REFUSED_NOTIFICATION(channel_id.0, &reason),
+                    redis_connection,
+                )?;
+            }
+            Ok(false)
+        }
+    }
+}
+
+fn refusal_reason(
+    channel_id: ChannelId,
+    redis_set_key: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Option<String>, crate::BoxedError> {
+    let is_recorded: bool = redis_connection.sismember(redis_set_key, channel_id.0)?;
+    if !is_recorded {
+        return Ok(Some(format!("it is not recorded in \"{}\"", redis_set_key)));
+    }
+    let bot_categories: Vec<ChannelId> = vec![
+        crate::guild_config::one_shot_category(redis_connection)?,
+        crate::guild_config::campaign_category(redis_connection)?,
+    ]
+    .into_iter()
+    .filter_map(|category| category)
+    .collect();
+    let staff_channel = crate::guild_config::staff_channel(redis_connection)?;
+    if bot_categories.contains(&channel_id) || staff_channel == Some(channel_id) {
+        return Ok(Some("it is one of the configured channels".to_string()));
+    }
+    let category_id = match channel_id.to_channel(discord_api)? {
+        Channel::Guild(channel) => {
+            let channel = channel.read();
+            if channel.guild_id != crate::discord_sync::GUILD_ID {
+                return Ok(Some("it belongs to a different server".to_string()));
+            }
+            channel.category_id
+        }
+        _ => return Ok(Some("it is not a server channel".to_string())),
+    };
+    // Without configured categories, there is nothing to compare with
+    if bot_categories.is_empty() {
+        return Ok(None);
+    }
+    match category_id {
+        Some(category_id) if bot_categories.contains(&category_id) => Ok(None),
+        Some(category_id) => Ok(Some(format!(
+            "it is in the category {} instead of one of the bot's categories",
+            category_id.0
+        ))),
+        None => Ok(Some("it is not in any of the bot's categories".to_string())),
+    }
+}
+
+// Returns whether the role may be deleted. `redis_set_key` is the set of roles
+// the caller is cleaning up, e.g. "discord_roles".
+pub fn verify_managed_role(
+    role_id: RoleId,
+    redis_set_key: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    match role_refusal_reason(role_id, redis_set_key, redis_connection, discord_api)? {
+        None => Ok(true),
+        Some(reason) => {
+            eprintln!("Refusing to delete role {}: {}", role_id.0, reason);
+            let newly_refused: bool = redis_connection.sadd(REDIS_REFUSED_ROLES_KEY, role_id.0)?;
+            if newly_refused {
+                crate::discord_notifications::notify_organizers(
+                    discord_api,
+                    &crate::strings::ROLE_DELETION_REFUSED_NOTIFICATION(role_id.0, &reason),
+                    redis_connection,
+                )?;
+            }
+            Ok(false)
+        }
+    }
+}
+
+fn role_refusal_reason(
+    role_id: RoleId,
+    redis_set_key: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Option<String>, crate::BoxedError> {
+    let is_recorded: bool = redis_connection.sismember(redis_set_key, role_id.0)?;
+    if !is_recorded {
+        return Ok(Some(format!("it is not recorded in \"{}\"", redis_set_key)));
+    }
+    let configured_roles = [
+        Some(RoleId(crate::discord_sync::GUILD_ID.0)),
+        Some(crate::discord_sync::ORGANIZER_ID),
+        crate::guild_config::game_master_role(redis_connection)?,
+    ];
+    if configured_roles.contains(&Some(role_id)) {
+        return Ok(Some("it is one of the configured roles".to_string()));
+    }
+    let roles = crate::discord_state_cache::roles(discord_api)?;
+    match roles.iter().find(|role| role.id == role_id) {
+        Some(role) if role.managed => Ok(Some("it is managed by an integration".to_string())),
+        // A role that is gone already can't do any harm
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,142 @@
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::{channel::Reaction, channel::ReactionType, id::ChannelId},
+    prelude::*,
+};
+
+pub const PREVIOUS_PAGE_EMOJI: &'static str = "\u{25C0}";
+pub const NEXT_PAGE_EMOJI: &'static str = "\u{25B6}";
+
+// Discord limits an embed's description to 2048 characters
+const MAX_PAGE_CHARS: usize = 2000;
+const MAX_PAGE_LINES: usize = 15;
+// Paginated messages can be browsed for an hour after they were sent
+pub const PAGINATION_TTL_SECS: usize = 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PaginationState {
+    title: String,
+    pages: Vec<String>,
+    current_page: usize,
+}
+
+// Splits the lines into pages that each fit into a single embed
+fn paginate(lines: &[String]) -> Vec<String> {
+    let mut pages = vec![];
+    let mut current_page = String::new();
+    let mut current_page_lines = 0;
+    for line in lines {
+        let needs_new_page = current_page_lines >= MAX_PAGE_LINES
+            || current_page.len() + line.len() + 1 > MAX_PAGE_CHARS;
+        if needs_new_page && current_page_lines > 0 {
+            pages.push(std::mem::replace(&mut current_page, String::new()));
+            current_page_lines = 0;
+        }
+        if current_page_lines > 0 {
+            current_page.push('\n');
+        }
+        // Truncate lines that would not even fit on a page of their own
+        if line.len() > MAX_PAGE_CHARS {
+            let mut end = MAX_PAGE_CHARS;
+            while !line.is_char_boundary(end) {
+                end -= 1;
+            }
+            current_page.push_str(&line[..end]);
+        } else {
+            current_page.push_str(line);
+        }
+        current_page_lines += 1;
+    }
+    if current_page_lines > 0 || pages.is_empty() {
+        pages.push(current_page);
+    }
+    pages
+}
+
+fn page_footer(current_page: usize, num_pages: usize) -> String {
+    format!("Page {}/{}", current_page + 1, num_pages)
+}
+
+// Sends the lines as an embed. If they don't fit into a single embed, they are
+// split into pages that can be browsed by reacting to the message.
+pub fn send_paginated(
+    ctx: &Context,
+    channel_id: ChannelId,
+    title: &str,
+    lines: &[String],
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> crate::Result<()> {
+    let pages = paginate(lines);
+    let num_pages = pages.len();
+    let message = channel_id.send_message(&ctx.http, |message_builder| {
+        message_builder.embed(|embed_builder| {
+            embed_builder.title(title).description(&pages[0]);
+            if num_pages > 1 {
+                embed_builder.footer(|footer| footer.text(page_footer(0, num_pages)));
+            }
+            embed_builder
+        })
+    })?;
+    if num_pages < 2 {
+        return Ok(());
+    }
+    let state = PaginationState {
+        title: title.to_string(),
+        pages: pages,
+        current_page: 0,
+    };
+    let redis_pagination_key = format!("paginated_message:{}", message.id.0);
+    let _: () = redis_connection.set_ex(
+        &redis_pagination_key,
+        serde_json::to_string(&state)?,
+        PAGINATION_TTL_SECS,
+    )?;
+    let _ = message.react(ctx, PREVIOUS_PAGE_EMOJI);
+    let _ = message.react(ctx, NEXT_PAGE_EMOJI);
+    Ok(())
+}
+
+// Turns the page of a paginated message. This is called for added as well as
+// removed reactions, such that users can flip back and forth without having
+// to remove their reaction first (which the bot cannot do for them in DMs).
+pub fn handle_reaction(
+    ctx: &Context,
+    reaction: &Reaction,
+    redis_client: &crate::redis_namespace::Client,
+) -> crate::Result<()> {
+    let forward = match &reaction.emoji {
+        ReactionType::Unicode(emoji) if emoji == NEXT_PAGE_EMOJI => true,
+        ReactionType::Unicode(emoji) if emoji == PREVIOUS_PAGE_EMOJI => false,
+        _ => return Ok(()),
+    };
+    let mut redis_connection = redis_client.get_connection()?;
+    let redis_pagination_key = format!("paginated_message:{}", reaction.message_id.0);
+    let state: Option<String> = redis_connection.get(&redis_pagination_key)?;
+    let mut state: PaginationState = match state {
+        Some(state) => serde_json::from_str(&state)?,
+        None => return Ok(()),
+    };
+    let num_pages = state.pages.len();
+    state.current_page = if forward {
+        (state.current_page + 1) % num_pages
+    } else {
+        (state.current_page + num_pages - 1) % num_pages
+    };
+    reaction
+        .channel_id
+        .edit_message(&ctx.http, reaction.message_id, |message_builder| {
+            message_builder.embed(|embed_builder| {
+                embed_builder
+                    .title(&state.title)
+                    .description(&state.pages[state.current_page])
+                    .footer(|footer| footer.text(page_footer(state.current_page, num_pages)))
+            })
+        })?;
+    let _: () = redis_connection.set_ex(
+        &redis_pagination_key,
+        serde_json::to_string(&state)?,
+        PAGINATION_TTL_SECS,
+    )?;
+    Ok(())
+}
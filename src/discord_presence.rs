@@ -0,0 +1,83 @@
+use redis::Commands;
+use serenity::client::bridge::gateway::ShardManager;
+use serenity::model::gateway::Activity;
+use serenity::prelude::Mutex;
+use std::sync::Arc;
+
+// The bot's status shows the next upcoming event of the community, e.g.
+// "Playing Curse of Strahd – Fri 19:00 UTC", such that members see at a
+// glance when the next game is. Every instance sets the status of the shards
+// it runs, so this task doesn't depend on the leader election. Organizers
+// can turn it off with the "next_event_status" config key.
+const REFRESH_INTERVAL_MINUTES: i64 = 10;
+// Discord cuts activity names off after 128 characters
+const MAX_ACTIVITY_CHARS: usize = 128;
+
+pub fn create_presence_task(
+    redis_client: crate::redis_namespace::Client,
+    shard_manager: Arc<Mutex<ShardManager>>,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    move |_ctx| {
+        if let Err(err) = update_presence(&redis_client, &shard_manager) {
+            eprintln!("Presence task failed: {}", err);
+            crate::error_reporting::report(&err, &[("task", "presence")]);
+        }
+        white_rabbit::DateResult::Repeat(
+            white_rabbit::Utc::now() + white_rabbit::Duration::minutes(REFRESH_INTERVAL_MINUTES),
+        )
+    }
+}
+
+fn update_presence(
+    redis_client: &crate::redis_namespace::Client,
+    shard_manager: &Arc<Mutex<ShardManager>>,
+) -> Result<(), crate::BoxedError> {
+    let mut con = redis_client.get_connection()?;
+    let activity = if crate::guild_config::next_event_status(&mut con)? {
+        next_event(&mut con)?.map(|(name, time)| {
+            let status = crate::strings::NEXT_EVENT_STATUS(&name, time);
+            Activity::playing(&status.chars().take(MAX_ACTIVITY_CHARS).collect::<String>())
+        })
+    } else {
+        None
+    };
+    let shard_manager = shard_manager.lock();
+    let runners = shard_manager.runners.lock();
+    for runner in runners.values() {
+        runner.runner_tx.set_activity(activity.clone());
+    }
+    Ok(())
+}
+
+// The name (shortened to the series name where possible) and time of the
+// next event of any series
+fn next_event(
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Option<(String, chrono::DateTime<chrono::Utc>)>, crate::BoxedError> {
+    let event_ids: Vec<String> = con.smembers("meetup_events")?;
+    let now = chrono::Utc::now();
+    let mut next_event = None;
+    for event_id in event_ids {
+        let redis_event_key = format!("meetup_event:{}", event_id);
+        let (time, name): (Option<String>, Option<String>) =
+            con.hget(&redis_event_key, &["time", "name"])?;
+        let (time, name) = match (time, name) {
+            (Some(time), Some(name)) => (time, name),
+            _ => continue,
+        };
+        let time = chrono::DateTime::parse_from_rfc3339(&time)?.with_timezone(&chrono::Utc);
+        let is_earlier = match next_event {
+            Some((_, next_time)) => time < next_time,
+            None => true,
+        };
+        if time >= now && is_earlier {
+            next_event = Some((name, time));
+        }
+    }
+    Ok(next_event.map(|(name, time)| {
+        let name = crate::discord_sync::series_name(&name)
+            .map(str::to_string)
+            .unwrap_or(name);
+        (name, time)
+    }))
+}
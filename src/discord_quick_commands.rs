@@ -0,0 +1,315 @@
+use crate::strings;
+use redis::Commands;
+use serenity::http::CacheHttp;
+use serenity::{
+    model::{channel::Reaction, channel::ReactionType, id::ChannelId, id::MessageId},
+    prelude::*,
+};
+
+// Reaction shortcuts for the most common commands in bot controlled channels.
+// Reacting to the pinned session message shows the schedule or asks the hosts
+// to join the next session, reacting to any other user's message lets a host
+// remove that user from the channel (after confirming).
+// Organizers can change the emoji of each shortcut.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum QuickCommand {
+    Schedule,
+    Join,
+    Remove,
+}
+
+impl QuickCommand {
+    pub const ALL: [QuickCommand; 3] = [
+        QuickCommand::Schedule,
+        QuickCommand::Join,
+        QuickCommand::Remove,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuickCommand::Schedule => "schedule",
+            QuickCommand::Join => "join",
+            QuickCommand::Remove => "remove",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<QuickCommand> {
+        QuickCommand::ALL
+            .iter()
+            .find(|command| command.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    pub fn default_emoji(&self) -> &'static str {
+        match self {
+            QuickCommand::Schedule => "\u{1F4C5}",
+            QuickCommand::Join => "\u{1F64B}",
+            QuickCommand::Remove => "\u{274C}",
+        }
+    }
+}
+
+pub fn emoji<C: redis::ConnectionLike>(
+    con: &mut C,
+    command: QuickCommand,
+) -> Result<String, crate::BoxedError> {
+    let redis_quick_commands_key =
+        format!("guild:{}:quick_commands", crate::discord_sync::GUILD_ID.0);
+    let emoji: Option<String> = con.hget(&redis_quick_commands_key, command.name())?;
+    Ok(emoji.unwrap_or_else(|| command.default_emoji().to_string()))
+}
+
+pub fn set_emoji<C: redis::ConnectionLike>(
+    con: &mut C,
+    command: QuickCommand,
+    emoji: &str,
+) -> Result<(), crate::BoxedError> {
+    let redis_quick_commands_key =
+        format!("guild:{}:quick_commands", crate::discord_sync::GUILD_ID.0);
+    let _: () = con.hset(&redis_quick_commands_key, command.name(), emoji)?;
+    Ok(())
+}
+
+// Routes a reaction in a bot controlled channel to the matching quick command
+pub fn handle_reaction(
+    ctx: &Context,
+    reaction: &Reaction,
+    redis_client: &crate::redis_namespace::Client,
+) -> crate::Result<()> {
+    let reaction_emoji = match &reaction.emoji {
+        ReactionType::Unicode(emoji) => emoji,
+        _ => return Ok(()),
+    };
+    let mut redis_connection = redis_client.get_connection()?;
+    let mut command = None;
+    for candidate in &QuickCommand::ALL {
+        if &emoji(&mut redis_connection, *candidate)? == reaction_emoji {
+            command = Some(*candidate);
+            break;
+        }
+    }
+    let command = match command {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+    let channel_access = match crate::discord_bot::Handler::get_channel_access(
+        reaction.channel_id.0,
+        &mut redis_connection,
+    )? {
+        Some(access) => access,
+        None => return Ok(()),
+    };
+    let redis_session_message_key =
+        format!("discord_channel:{}:session_message", reaction.channel_id.0);
+    let session_message_id: Option<u64> =
+        redis_connection.hget(&redis_session_message_key, "message_id")?;
+    let on_session_message = session_message_id == Some(reaction.message_id.0);
+    match command {
+        QuickCommand::Schedule if on_session_message => {
+            send_schedule(ctx, reaction, &mut redis_connection)?;
+            // Allow using the shortcut again
+            let _ = reaction.delete(ctx);
+        }
+        QuickCommand::Join if on_session_message => {
+            if crate::quarantine::is_quarantined(&mut redis_connection, reaction.user_id.0)? {
+                return Ok(());
+            }
+            // The hosts might not want join requests via reactions
+            if !crate::discord_channel_settings::settings(
+                &mut redis_connection,
+                reaction.channel_id,
+            )?
+            .reaction_signup
+            {
+                return Ok(());
+            }
+            let hosts_mention = crate::discord_bot::Handler::hosts_mention(
+                reaction.channel_id,
+                &channel_access,
+                &mut redis_connection,
+            )?;
+            let _ = reaction.channel_id.say(
+                &ctx.http,
+                strings::QUICK_COMMAND_JOIN_REQUEST(reaction.user_id.0, &hosts_mention),
+            );
+            let _ = reaction.delete(ctx);
+        }
+        QuickCommand::Remove if !on_session_message => {
+            let user = reaction.user(ctx)?;
+            if !crate::discord_bot::Handler::is_channel_admin(
+                ctx,
+                &user,
+                reaction.channel_id,
+                &channel_access,
+                &mut redis_connection,
+            )? {
+                return Ok(());
+            }
+            let message = reaction.message(&ctx.http)?;
+            // Ignore the bot's own messages (like confirmation prompts) and
+            // hosts removing themselves
+            let bot_id = *ctx
+                .data
+                .read()
+                .get::<crate::discord_bot::BotIdKey>()
+                .expect("Bot ID was not set");
+            if message.author.id == bot_id || message.author.id == reaction.user_id {
+                return Ok(());
+            }
+            // Removing is a change, which is not allowed during maintenance
+            if crate::maintenance_mode::is_active(&mut redis_connection)? {
+                let _ = reaction
+                    .channel_id
+                    .say(&ctx.http, strings::MAINTENANCE_MODE_ACTIVE);
+                return Ok(());
+            }
+            crate::discord_confirmations::request_confirmation(
+                ctx,
+                reaction.channel_id,
+                reaction.user_id,
+                crate::discord_confirmations::PendingAction::RemoveUser {
+                    discord_id: message.author.id.0,
+                },
+                &strings::QUICK_COMMAND_REMOVE_CONFIRMATION(message.author.id.0),
+                &mut redis_connection,
+            )?;
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+// Sends the user who asked for it a DM with the upcoming sessions of the channel
+fn send_schedule(
+    ctx: &Context,
+    reaction: &Reaction,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> crate::Result<()> {
+    let redis_channel_series_key =
+        format!("discord_channel:{}:event_series", reaction.channel_id.0);
+    let series_id: Option<String> = redis_connection.get(&redis_channel_series_key)?;
+    let series_id = match series_id {
+        Some(series_id) => series_id,
+        None => return Ok(()),
+    };
+    let redis_series_events_key = format!("event_series:{}:meetup_events", series_id);
+    let event_ids: Vec<String> = redis_connection.smembers(&redis_series_events_key)?;
+    let now = chrono::Utc::now();
+    let mut upcoming = Vec::with_capacity(event_ids.len());
+    for event_id in event_ids {
+        let redis_event_key = format!("meetup_event:{}", event_id);
+        let (time, name, link): (Option<String>, Option<String>, Option<String>) =
+            redis_connection.hget(&redis_event_key, &["time", "name", "link"])?;
+        if let (Some(time), Some(name), Some(link)) = (time, name, link) {
+            let time = chrono::DateTime::parse_from_rfc3339(&time)?.with_timezone(&chrono::Utc);
+            if time > now {
+                upcoming.push((time, name, link));
+            }
+        }
+    }
+    upcoming.sort_unstable_by_key(|(time, _, _)| *time);
+    let user = reaction.user(ctx)?;
+    if upcoming.is_empty() {
+        let _ = user.direct_message(ctx, |message_builder| {
+            message_builder.content(strings::QUICK_COMMAND_NO_UPCOMING_SESSIONS(
+                reaction.channel_id.0,
+            ))
+        });
+        return Ok(());
+    }
+    let mut lines = vec![strings::QUICK_COMMAND_SCHEDULE_CHANNEL(
+        reaction.channel_id.0,
+    )];
+    lines.extend(
+        upcoming
+            .iter()
+            .map(|(time, name, link)| strings::QUICK_COMMAND_SCHEDULE_LINE(name, *time, link)),
+    );
+    let dm_channel = user.create_dm_channel(ctx)?;
+    crate::discord_pagination::send_paginated(
+        ctx,
+        dm_channel.id,
+        strings::QUICK_COMMAND_SCHEDULE_TITLE,
+        &lines,
+        redis_connection,
+    )
+}
+
+// Keeps a pinned message with the next session and the available shortcuts
+// in each bot controlled channel
+pub fn sync_session_message(
+    channel_id: ChannelId,
+    session_number: Option<u64>,
+    next_event_name: &str,
+    next_event_time: chrono::DateTime<chrono::Utc>,
+    next_event_link: &str,
+    next_event_location: &crate::event_location::Location,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> crate::Result<()> {
+    // Without reaction signup, the join shortcut is not offered
+    let reaction_signup =
+        crate::discord_channel_settings::settings(redis_connection, channel_id)?.reaction_signup;
+    let join_emoji = if reaction_signup {
+        Some(emoji(redis_connection, QuickCommand::Join)?)
+    } else {
+        None
+    };
+    let latest_recap_link = crate::discord_recaps::latest_recap_link(redis_connection, channel_id)?;
+    let content = strings::SESSION_MESSAGE(
+        session_number,
+        next_event_name,
+        next_event_time,
+        next_event_link,
+        next_event_location,
+        &emoji(redis_connection, QuickCommand::Schedule)?,
+        join_emoji.as_ref().map(String::as_str),
+        &emoji(redis_connection, QuickCommand::Remove)?,
+        latest_recap_link.as_ref().map(String::as_str),
+    );
+    let redis_session_message_key = format!("discord_channel:{}:session_message", channel_id.0);
+    let (message_id, current_content): (Option<u64>, Option<String>) =
+        redis_connection.hget(&redis_session_message_key, &["message_id", "content"])?;
+    if let Some(message_id) = message_id {
+        if current_content.as_ref() == Some(&content) {
+            return Ok(());
+        }
+        match channel_id.edit_message(discord_api.http(), MessageId(message_id), |message| {
+            message.content(&content)
+        }) {
+            Ok(_) => {
+                let _: () =
+                    redis_connection.hset(&redis_session_message_key, "content", &content)?;
+                return Ok(());
+            }
+            // Most likely, somebody deleted the message. Post a new one.
+            Err(err) => eprintln!(
+                "Could not update the session message in channel {}: {}",
+                channel_id.0, err
+            ),
+        }
+    }
+    let message = channel_id.say(discord_api.http(), &content)?;
+    let _: () = redis_connection.hset_multiple(
+        &redis_session_message_key,
+        &[
+            ("message_id", message.id.0.to_string()),
+            ("content", content),
+        ],
+    )?;
+    // Pinning requires the "Manage Messages" permission
+    if let Err(err) = message.pin(discord_api) {
+        eprintln!(
+            "Could not pin the session message in channel {}: {}",
+            channel_id.0, err
+        );
+    }
+    let _ = message.react(
+        discord_api,
+        ReactionType::Unicode(emoji(redis_connection, QuickCommand::Schedule)?),
+    );
+    if let Some(join_emoji) = join_emoji {
+        let _ = message.react(discord_api, ReactionType::Unicode(join_emoji));
+    }
+    Ok(())
+}
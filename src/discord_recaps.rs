@@ -0,0 +1,139 @@
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, MessageId};
+
+// Hosts can keep a journal of their campaign with the "recap" command. A
+// recap belongs to the series and the date of the session it is about (the
+// latest past session, or today if there was none yet), so a second recap for
+// the same session replaces the first one.
+// The message with the command stays in the channel, the pinned session
+// message links to the latest one.
+pub const MAX_RECAP_CHARS: usize = 1500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recap {
+    pub text: String,
+    pub discord_user: u64,
+    pub channel_id: u64,
+    pub message_id: u64,
+}
+
+impl Recap {
+    pub fn link(&self) -> String {
+        message_link(ChannelId(self.channel_id), MessageId(self.message_id))
+    }
+}
+
+pub fn message_link(channel_id: ChannelId, message_id: MessageId) -> String {
+    format!(
+        "https://discordapp.com/channels/{}/{}/{}",
+        crate::discord_sync::GUILD_ID.0,
+        channel_id.0,
+        message_id.0
+    )
+}
+
+fn redis_series_recaps_key(series_id: &str) -> String {
+    format!("event_series:{}:recaps", series_id)
+}
+
+fn channel_series<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<Option<String>, crate::BoxedError> {
+    let redis_channel_series_key = format!("discord_channel:{}:event_series", channel_id.0);
+    Ok(con.get(&redis_channel_series_key)?)
+}
+
+// The date of the latest session that already took place
+fn latest_session_date<C: redis::ConnectionLike>(
+    con: &mut C,
+    series_id: &str,
+) -> Result<Option<chrono::NaiveDate>, crate::BoxedError> {
+    let redis_series_events_key = format!("event_series:{}:meetup_events", series_id);
+    let event_ids: Vec<String> = con.smembers(&redis_series_events_key)?;
+    let now = chrono::Utc::now();
+    let mut latest_time = None;
+    for event_id in event_ids {
+        let time: Option<String> = con.hget(format!("meetup_event:{}", event_id), "time")?;
+        if let Some(time) = time {
+            let time = chrono::DateTime::parse_from_rfc3339(&time)?.with_timezone(&chrono::Utc);
+            if time <= now && latest_time.map(|latest| time > latest).unwrap_or(true) {
+                latest_time = Some(time);
+            }
+        }
+    }
+    Ok(latest_time.map(|time| time.naive_utc().date()))
+}
+
+// Stores the recap for the latest session of the channel's series. Returns
+// the session date, or None if the channel doesn't belong to a series.
+pub fn store(
+    channel_id: ChannelId,
+    message_id: MessageId,
+    discord_user: u64,
+    text: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<Option<chrono::NaiveDate>, crate::BoxedError> {
+    let series_id = match channel_series(redis_connection, channel_id)? {
+        Some(series_id) => series_id,
+        None => return Ok(None),
+    };
+    let date = match latest_session_date(redis_connection, &series_id)? {
+        Some(date) => date,
+        None => chrono::Utc::now().naive_utc().date(),
+    };
+    let recap = Recap {
+        text: text.trim().to_string(),
+        discord_user: discord_user,
+        channel_id: channel_id.0,
+        message_id: message_id.0,
+    };
+    let _: () = redis_connection.hset(
+        redis_series_recaps_key(&series_id),
+        date.format("%Y-%m-%d").to_string(),
+        serde_json::to_string(&recap)?,
+    )?;
+    // Update the link in the session message
+    crate::discord_sync::mark_series_dirty(redis_connection, &series_id)?;
+    Ok(Some(date))
+}
+
+// All recaps of the channel's series by session date, most recent first
+pub fn list<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<Vec<(String, Recap)>, crate::BoxedError> {
+    let series_id = match channel_series(con, channel_id)? {
+        Some(series_id) => series_id,
+        None => return Ok(vec![]),
+    };
+    let recaps: std::collections::HashMap<String, String> =
+        con.hgetall(redis_series_recaps_key(&series_id))?;
+    let mut recaps: Vec<_> = recaps
+        .into_iter()
+        .filter_map(
+            |(date, recap)| match serde_json::from_str::<Recap>(&recap) {
+                Ok(recap) => Some((date, recap)),
+                Err(err) => {
+                    eprintln!(
+                        "Could not parse the recap of {} in series {}: {}",
+                        date, series_id, err
+                    );
+                    None
+                }
+            },
+        )
+        .collect();
+    recaps.sort_by(|(a, _), (b, _)| b.cmp(a));
+    Ok(recaps)
+}
+
+pub fn latest_recap_link<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<Option<String>, crate::BoxedError> {
+    Ok(list(con, channel_id)?
+        .first()
+        .map(|(_, recap)| recap.link()))
+}
@@ -0,0 +1,117 @@
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serenity::model::{
+    channel::Message,
+    id::{MessageId, UserId},
+};
+
+// Some commands can target the author of a message by replying to it, like
+// "@bot remove" or "@bot recap". Serenity 0.6 drops the message reference of
+// incoming messages, so we fetch the invoking message from Discord again to
+// find out what it replies to.
+const API_BASE_URL: &'static str = "https://discord.com/api/v10";
+
+lazy_static! {
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+#[derive(Debug, Deserialize)]
+struct RawUser {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawReferencedMessage {
+    id: String,
+    author: RawUser,
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMessage {
+    // Null if the referenced message was deleted, missing if this isn't a
+    // reply
+    #[serde(default)]
+    referenced_message: Option<RawReferencedMessage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferencedMessage {
+    pub id: MessageId,
+    pub author_id: UserId,
+    pub content: String,
+}
+
+fn parse_referenced_message(raw_message: RawMessage) -> Option<ReferencedMessage> {
+    let referenced_message = raw_message.referenced_message?;
+    Some(ReferencedMessage {
+        id: MessageId(referenced_message.id.parse().ok()?),
+        author_id: UserId(referenced_message.author.id.parse().ok()?),
+        content: referenced_message.content,
+    })
+}
+
+// The message that `msg` replies to, or None if it isn't a reply or the
+// message it replied to was deleted
+pub fn referenced_message(
+    http: &serenity::http::raw::Http,
+    msg: &Message,
+) -> Result<Option<ReferencedMessage>, crate::BoxedError> {
+    crate::discord_call_priority::wait_for_turn();
+    crate::sync_stats::record_discord_api_call();
+    let authorization = if http.token.starts_with("Bot ") {
+        http.token.clone()
+    } else {
+        format!("Bot {}", http.token)
+    };
+    let raw_message: RawMessage = HTTP_CLIENT
+        .get(&format!(
+            "{}/channels/{}/messages/{}",
+            API_BASE_URL, msg.channel_id.0, msg.id.0
+        ))
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .send()?
+        .error_for_status()?
+        .json()?;
+    Ok(parse_referenced_message(raw_message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Option<ReferencedMessage> {
+        parse_referenced_message(serde_json::from_str(json).unwrap())
+    }
+
+    #[test]
+    fn finds_the_replied_to_message() {
+        assert_eq!(
+            parse(
+                r#"{"id": "3", "type": 19, "content": "<@1> remove",
+                    "message_reference": {"message_id": "2"},
+                    "referenced_message": {"id": "2", "type": 0, "content": "We found the map",
+                                           "author": {"id": "5678", "username": "Tordek"}}}"#
+            ),
+            Some(ReferencedMessage {
+                id: MessageId(2),
+                author_id: UserId(5678),
+                content: "We found the map".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_messages_that_are_no_replies() {
+        assert_eq!(parse(r#"{"id": "3", "type": 0, "content": "hello"}"#), None);
+        // The replied-to message was deleted
+        assert_eq!(
+            parse(
+                r#"{"id": "3", "type": 19, "message_reference": {"message_id": "2"},
+                    "referenced_message": null}"#
+            ),
+            None
+        );
+    }
+}
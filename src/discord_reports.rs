@@ -0,0 +1,74 @@
+use redis::{Commands, PipelineCommands};
+use serenity::model::{channel::Message, id::ChannelId};
+
+// Players can report a problem to the organizers by sending "report <text>"
+// to the bot via DM. "report anonymously <text>" leaves out who sent it.
+// The report goes to the staff channel (or to every organizer if there is
+// none), together with links to the attachments and, unless the report is
+// anonymous, the bot-managed channels that the reporter is part of. Nothing
+// about reports is stored.
+pub const MAX_REPORT_CHARS: usize = 1500;
+
+// The bot-managed channels where the user has the player or host role
+pub fn reporter_channels(
+    discord_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Vec<ChannelId>, crate::BoxedError> {
+    let member_role_ids = match crate::discord_sync::GUILD_ID.member(discord_api, discord_id) {
+        Ok(member) => member.roles,
+        // The user might not be on the server (anymore)
+        Err(_) => return Ok(vec![]),
+    };
+    let channel_ids: Vec<u64> = redis_connection.smembers("discord_channels")?;
+    let mut reporter_channels = vec![];
+    for channel_id in channel_ids {
+        let redis_channel_role_key = format!("discord_channel:{}:discord_role", channel_id);
+        let redis_channel_host_role_key =
+            format!("discord_channel:{}:discord_host_role", channel_id);
+        let (role_id, host_role_id): (Option<u64>, Option<u64>) = redis::pipe()
+            .get(&redis_channel_role_key)
+            .get(&redis_channel_host_role_key)
+            .query(redis_connection)?;
+        let is_member = role_id.iter().chain(host_role_id.iter()).any(|role_id| {
+            member_role_ids
+                .iter()
+                .any(|member_role| member_role.0 == *role_id)
+        });
+        if is_member {
+            reporter_channels.push(ChannelId(channel_id));
+        }
+    }
+    // Channels without roles give access with member overwrites
+    reporter_channels.extend(crate::discord_channel_access::member_channels(
+        redis_connection,
+        discord_id,
+    )?);
+    Ok(reporter_channels)
+}
+
+pub fn send_report(
+    msg: &Message,
+    text: &str,
+    anonymous: bool,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let attachment_urls: Vec<&str> = msg
+        .attachments
+        .iter()
+        .map(|attachment| attachment.url.as_str())
+        .collect();
+    let report = if anonymous {
+        crate::strings::REPORT_NOTIFICATION(None, &[], text.trim(), &attachment_urls)
+    } else {
+        let channels = reporter_channels(msg.author.id.0, redis_connection, discord_api)?;
+        crate::strings::REPORT_NOTIFICATION(
+            Some(msg.author.id.0),
+            &channels,
+            text.trim(),
+            &attachment_urls,
+        )
+    };
+    crate::discord_notifications::notify_organizers(discord_api, &report, redis_connection)
+}
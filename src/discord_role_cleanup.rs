@@ -0,0 +1,214 @@
+use redis::{Commands, PipelineCommands};
+use serenity::http::CacheHttp;
+use serenity::model::id::{ChannelId, RoleId};
+use std::collections::{HashMap, HashSet};
+
+// "cleanup roles" finds the player and host roles that the bot created for
+// channels which are gone by now, or for series without upcoming events whose
+// last event is longer ago than the expiration grace period. Organizers get
+// the list and, once they confirm, the roles are deleted from Discord and
+// Redis. Otherwise these roles would count towards Discord's role limit
+// forever. Deleting the roles of an ended series that still has its channel
+// also takes away the access that the roles gave; the sync creates new roles
+// should the series get new events.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reason {
+    ChannelGone,
+    // The time of the last event
+    SeriesEnded(chrono::DateTime<chrono::Utc>),
+}
+
+pub struct Candidate {
+    pub role_id: RoleId,
+    // Falls back to the ID for roles that are already gone from Discord
+    pub name: String,
+    pub is_host_role: bool,
+    pub channel_id: Option<ChannelId>,
+    pub reason: Reason,
+}
+
+pub fn candidates(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Vec<Candidate>, crate::BoxedError> {
+    let (role_ids, host_role_ids, channel_ids): (Vec<u64>, Vec<u64>, HashSet<u64>) = redis::pipe()
+        .smembers("discord_roles")
+        .smembers("discord_host_roles")
+        .smembers("discord_channels")
+        .query(con)?;
+    let role_names: HashMap<RoleId, String> = crate::discord_state_cache::roles(discord_api)?
+        .into_iter()
+        .map(|role| (role.id, role.name))
+        .collect();
+    let grace = crate::guild_config::expiration_grace(con)?;
+    let now = chrono::Utc::now();
+    // Several roles belong to the same series, so remember what we found out
+    let mut series_ends: HashMap<String, Option<chrono::DateTime<chrono::Utc>>> = HashMap::new();
+    let mut candidates = vec![];
+    let roles = role_ids
+        .into_iter()
+        .map(|role_id| (role_id, false))
+        .chain(host_role_ids.into_iter().map(|role_id| (role_id, true)));
+    for (role_id, is_host_role) in roles {
+        let redis_role_channel_key = if is_host_role {
+            format!("discord_host_role:{}:discord_channel", role_id)
+        } else {
+            format!("discord_role:{}:discord_channel", role_id)
+        };
+        let channel_id: Option<u64> = con.get(&redis_role_channel_key)?;
+        let channel_id = channel_id.map(ChannelId);
+        let reason = match channel_id {
+            Some(channel_id)
+                if channel_ids.contains(&channel_id.0)
+                    && crate::discord_state_cache::channel_exists(channel_id, discord_api)? =>
+            {
+                let series_id: Option<String> =
+                    con.get(format!("discord_channel:{}:event_series", channel_id.0))?;
+                let series_id = match series_id {
+                    Some(series_id) => series_id,
+                    // Without a series there is no telling whether it ended
+                    None => continue,
+                };
+                let series_end = match series_ends.get(&series_id) {
+                    Some(series_end) => *series_end,
+                    None => {
+                        let series_end = series_end(&series_id, con)?;
+                        series_ends.insert(series_id, series_end);
+                        series_end
+                    }
+                };
+                match series_end {
+                    Some(last_event_time) if last_event_time + grace < now => {
+                        Reason::SeriesEnded(last_event_time)
+                    }
+                    _ => continue,
+                }
+            }
+            _ => Reason::ChannelGone,
+        };
+        let role_id = RoleId(role_id);
+        candidates.push(Candidate {
+            role_id: role_id,
+            name: role_names
+                .get(&role_id)
+                .cloned()
+                .unwrap_or_else(|| role_id.0.to_string()),
+            is_host_role: is_host_role,
+            channel_id: channel_id,
+            reason: reason,
+        });
+    }
+    candidates.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    Ok(candidates)
+}
+
+// The time of the last event, or None if the series has upcoming events
+// (or no events at all, which we can't judge)
+fn series_end(
+    series_id: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, crate::BoxedError> {
+    let (upcoming, past) = crate::discord_sync::series_events(series_id, con)?;
+    if !upcoming.is_empty() {
+        return Ok(None);
+    }
+    Ok(past.iter().map(|event| event.time).max())
+}
+
+pub fn delete(
+    candidate: &Candidate,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    delete_role(
+        candidate.role_id,
+        candidate.is_host_role,
+        candidate.channel_id,
+        con,
+        discord_api,
+    )
+}
+
+// Deletes a player or host role from Discord and forgets about it. A role
+// that is already gone from Discord counts as deleted, a role that doesn't
+// pass the ownership check is kept (see discord_ownership).
+pub fn delete_role(
+    role_id: RoleId,
+    is_host_role: bool,
+    channel_id: Option<ChannelId>,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let redis_roles_key = if is_host_role {
+        "discord_host_roles"
+    } else {
+        "discord_roles"
+    };
+    if !crate::discord_ownership::verify_managed_role(role_id, redis_roles_key, con, discord_api)? {
+        return Ok(());
+    }
+    match discord_api
+        .http()
+        .delete_role(crate::discord_sync::GUILD_ID.0, role_id.0)
+    {
+        Ok(()) => (),
+        Err(ref err) if crate::vacuum::is_not_found(err) => (),
+        Err(err) => return Err(err.into()),
+    }
+    crate::discord_state_cache::remove_role(role_id);
+    let redis_role_channel_key = if is_host_role {
+        format!("discord_host_role:{}:discord_channel", role_id.0)
+    } else {
+        format!("discord_role:{}:discord_channel", role_id.0)
+    };
+    let mut pipe = redis::pipe();
+    pipe.atomic()
+        .srem(redis_roles_key, role_id.0)
+        .ignore()
+        .del(&redis_role_channel_key)
+        .ignore();
+    if let Some(channel_id) = channel_id {
+        let redis_channel_role_key = if is_host_role {
+            format!("discord_channel:{}:discord_host_role", channel_id.0)
+        } else {
+            format!("discord_channel:{}:discord_role", channel_id.0)
+        };
+        // Only if the channel didn't get a new role in the meantime
+        let channel_role_id: Option<u64> = con.get(&redis_channel_role_key)?;
+        if channel_role_id == Some(role_id.0) {
+            pipe.del(&redis_channel_role_key).ignore();
+        }
+    }
+    let _: () = pipe.query(con)?;
+    Ok(())
+}
+
+// Builds the list again and deletes all roles on it. Returns the number of
+// deleted roles and the number of roles that could not be deleted.
+pub fn cleanup(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(usize, usize), crate::BoxedError> {
+    let mut num_deleted = 0;
+    let mut num_failed = 0;
+    for candidate in candidates(con, discord_api)? {
+        match delete(&candidate, con, discord_api) {
+            Ok(()) => {
+                println!(
+                    "Role cleanup: deleted role {} \"{}\"",
+                    candidate.role_id.0, candidate.name
+                );
+                num_deleted += 1;
+            }
+            Err(err) => {
+                eprintln!(
+                    "Role cleanup: could not delete role {}: {}",
+                    candidate.role_id.0, err
+                );
+                num_failed += 1;
+            }
+        }
+    }
+    Ok((num_deleted, num_failed))
+}
@@ -0,0 +1,296 @@
+use redis::{Commands, PipelineCommands};
+use serenity::http::CacheHttp;
+use serenity::model::channel::{PermissionOverwrite, PermissionOverwriteType};
+use serenity::model::id::{ChannelId, RoleId, UserId};
+use serenity::model::permissions::Permissions;
+use simple_error::SimpleError;
+use std::collections::{HashMap, HashSet};
+
+// Discord allows at most 250 roles per server and every channel of the bot
+// takes two of them. After each sync, the number of roles is checked and the
+// organizers are warned once the server uses 80% of the limit ("role usage"
+// shows the numbers at any time). Archived channels keep their roles only to
+// let their players read them, so with "config set role_pruning near_limit"
+// (only once the warning threshold is reached) or "always", the sync gives
+// each member of an archived channel a permission overwrite of their own,
+// with the permissions the channel's roles gave them, and deletes the roles.
+// Should the channel get a new session, the sync creates new roles and
+// removes these overwrites again.
+
+// Warn earlier than the creation budget, which only warns at 90% of the limit
+const ROLE_LIMIT_WARNING_PERCENT: u64 = 80;
+
+const REDIS_ROLE_LIMIT_WARNED_KEY: &'static str = "discord_sync:role_limit_warned";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PruningPolicy {
+    Off,
+    NearLimit,
+    Always,
+}
+
+impl PruningPolicy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PruningPolicy::Off => "off",
+            PruningPolicy::NearLimit => "near_limit",
+            PruningPolicy::Always => "always",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PruningPolicy> {
+        [
+            PruningPolicy::Off,
+            PruningPolicy::NearLimit,
+            PruningPolicy::Always,
+        ]
+        .iter()
+        .find(|policy| policy.name().eq_ignore_ascii_case(name))
+        .cloned()
+    }
+}
+
+pub struct RoleUsage {
+    // All roles of the server, including @everyone
+    pub num_roles: u64,
+    // The player and host roles of the bot's channels
+    pub num_managed_roles: u64,
+    // Archived channels that still have a player or host role
+    pub num_prunable_channels: u64,
+}
+
+impl RoleUsage {
+    pub fn limit(&self) -> u64 {
+        crate::discord_creation_budget::GUILD_ROLE_LIMIT
+    }
+
+    pub fn is_near_limit(&self) -> bool {
+        self.num_roles * 100 >= self.limit() * ROLE_LIMIT_WARNING_PERCENT
+    }
+}
+
+// The members whose permission overwrites replaced the roles of the channel
+fn redis_pruned_members_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:pruned_members", channel_id.0)
+}
+
+pub fn role_usage(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<RoleUsage, crate::BoxedError> {
+    let role_ids: HashSet<u64> = crate::discord_state_cache::roles(discord_api)?
+        .into_iter()
+        .map(|role| role.id.0)
+        .collect();
+    let managed_role_ids: Vec<u64> = con.sunion(&["discord_roles", "discord_host_roles"])?;
+    let num_managed_roles = managed_role_ids
+        .iter()
+        .filter(|role_id| role_ids.contains(role_id))
+        .count();
+    Ok(RoleUsage {
+        num_roles: role_ids.len() as u64,
+        num_managed_roles: num_managed_roles as u64,
+        num_prunable_channels: prunable_channels(con)?.len() as u64,
+    })
+}
+
+// Archived channels with their player and host role (if any)
+fn prunable_channels(
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Vec<(ChannelId, Option<RoleId>, Option<RoleId>)>, crate::BoxedError> {
+    let channel_ids: Vec<u64> = con.smembers("discord_channels")?;
+    if channel_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut pipe = redis::pipe();
+    for &channel_id in &channel_ids {
+        pipe.get(format!("discord_channel:{}:archived", channel_id))
+            .get(format!("discord_channel:{}:discord_role", channel_id))
+            .get(format!("discord_channel:{}:discord_host_role", channel_id));
+    }
+    let channel_fields: Vec<(Option<bool>, Option<u64>, Option<u64>)> = pipe.query(con)?;
+    Ok(channel_ids
+        .into_iter()
+        .zip(channel_fields)
+        .filter_map(|(channel_id, (archived, role_id, host_role_id))| {
+            if archived.unwrap_or(false) && (role_id.is_some() || host_role_id.is_some()) {
+                Some((
+                    ChannelId(channel_id),
+                    role_id.map(RoleId),
+                    host_role_id.map(RoleId),
+                ))
+            } else {
+                None
+            }
+        })
+        .collect())
+}
+
+// Part of the Discord sync: prunes the roles of archived channels if the
+// policy asks for it and warns the organizers if the server is close to the
+// limit. The warning is only sent once until the server is below the
+// threshold again.
+pub fn monitor(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let mut usage = role_usage(con, discord_api)?;
+    let policy = crate::guild_config::role_pruning(con)?;
+    let should_prune = match policy {
+        PruningPolicy::Off => false,
+        PruningPolicy::NearLimit => usage.is_near_limit(),
+        PruningPolicy::Always => true,
+    };
+    if should_prune && usage.num_prunable_channels > 0 {
+        let (num_channels, num_roles) = prune(con, discord_api)?;
+        println!(
+            "Role pruning: replaced {} roles of {} archived channels with member overwrites",
+            num_roles, num_channels
+        );
+        usage = role_usage(con, discord_api)?;
+    }
+    if usage.is_near_limit() {
+        let newly_warned: bool = con.set_nx(REDIS_ROLE_LIMIT_WARNED_KEY, true)?;
+        if newly_warned {
+            crate::discord_notifications::notify_organizers(
+                discord_api,
+                &crate::strings::ROLE_LIMIT_WARNING_NOTIFICATION(&usage, policy),
+                con,
+            )?;
+        }
+    } else {
+        let _: () = con.del(REDIS_ROLE_LIMIT_WARNED_KEY)?;
+    }
+    Ok(())
+}
+
+// Returns the number of pruned channels and deleted roles. A channel that
+// fails is skipped and tried again with the next sync.
+fn prune(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(usize, usize), crate::BoxedError> {
+    // Collect the members first, such that the guild is not locked while
+    // we talk to Discord
+    let members: Vec<(UserId, Vec<RoleId>)> = {
+        let guild = crate::discord_sync::GUILD_ID
+            .to_guild_cached(&discord_api.cache)
+            .ok_or_else(|| SimpleError::new("Could not find the guild in the cache"))?;
+        let guild = guild.read();
+        guild
+            .members
+            .values()
+            .map(|member| (member.user.read().id, member.roles.clone()))
+            .collect()
+    };
+    let mut num_channels = 0;
+    let mut num_roles = 0;
+    for (channel_id, role_id, host_role_id) in prunable_channels(con)? {
+        let roles: Vec<(RoleId, bool)> = role_id
+            .map(|role_id| (role_id, false))
+            .into_iter()
+            .chain(host_role_id.map(|host_role_id| (host_role_id, true)))
+            .collect();
+        match prune_channel(channel_id, &roles, &members, con, discord_api) {
+            Ok(()) => {
+                num_channels += 1;
+                num_roles += roles.len();
+            }
+            Err(err) => eprintln!(
+                "Role pruning: could not prune the roles of channel {}: {}",
+                channel_id.0, err
+            ),
+        }
+    }
+    Ok((num_channels, num_roles))
+}
+
+fn prune_channel(
+    channel_id: ChannelId,
+    roles: &[(RoleId, bool)],
+    members: &[(UserId, Vec<RoleId>)],
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let overwrites = match channel_id.to_channel(discord_api)?.guild() {
+        Some(channel) => channel.read().permission_overwrites.clone(),
+        None => return Ok(()),
+    };
+    // Members with both roles get the permissions of both
+    let mut member_overwrites: HashMap<UserId, PermissionOverwrite> = HashMap::new();
+    for &(role_id, _) in roles {
+        let role_overwrite = overwrites.iter().find(|overwrite| match overwrite.kind {
+            PermissionOverwriteType::Role(overwrite_role_id) => overwrite_role_id == role_id,
+            _ => false,
+        });
+        // Without an overwrite, the role doesn't give access to anything
+        let role_overwrite = match role_overwrite {
+            Some(role_overwrite) => role_overwrite,
+            None => continue,
+        };
+        for (user_id, member_role_ids) in members {
+            if !member_role_ids.contains(&role_id) {
+                continue;
+            }
+            // Co-hosts and removed players already have an overwrite of
+            // their own, which we leave alone
+            let has_overwrite = overwrites.iter().any(|overwrite| match overwrite.kind {
+                PermissionOverwriteType::Member(overwrite_user_id) => overwrite_user_id == *user_id,
+                _ => false,
+            });
+            if has_overwrite {
+                continue;
+            }
+            let member_overwrite =
+                member_overwrites
+                    .entry(*user_id)
+                    .or_insert_with(|| PermissionOverwrite {
+                        allow: Permissions::empty(),
+                        deny: Permissions::empty(),
+                        kind: PermissionOverwriteType::Member(*user_id),
+                    });
+            member_overwrite.allow.insert(role_overwrite.allow);
+            member_overwrite.deny.insert(role_overwrite.deny);
+        }
+    }
+    let redis_pruned_members_key = redis_pruned_members_key(channel_id);
+    for (user_id, member_overwrite) in &member_overwrites {
+        channel_id.create_permission(discord_api.http(), member_overwrite)?;
+        let _: () = con.sadd(&redis_pruned_members_key, user_id.0)?;
+    }
+    // Only now that the members have their own overwrites
+    for &(role_id, is_host_role) in roles {
+        crate::discord_role_cleanup::delete_role(
+            role_id,
+            is_host_role,
+            Some(channel_id),
+            con,
+            discord_api,
+        )?;
+    }
+    Ok(())
+}
+
+// Called by the Discord sync when an archived channel is opened again. The
+// overwrites deny sending messages, which would take precedence over the new
+// roles.
+pub fn unprune_channel(
+    channel_id: ChannelId,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let redis_pruned_members_key = redis_pruned_members_key(channel_id);
+    let user_ids: Vec<u64> = con.smembers(&redis_pruned_members_key)?;
+    for user_id in user_ids {
+        match channel_id.delete_permission(
+            discord_api.http(),
+            PermissionOverwriteType::Member(UserId(user_id)),
+        ) {
+            Ok(()) => (),
+            Err(ref err) if crate::vacuum::is_not_found(err) => (),
+            Err(err) => return Err(err.into()),
+        }
+        let _: () = con.srem(&redis_pruned_members_key, user_id)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,262 @@
+use redis::{Commands, PipelineCommands};
+use serenity::http::CacheHttp;
+use serenity::model::id::{ChannelId, RoleId, UserId};
+
+// Role assignments of the Discord sync that failed (e.g. because of a rate
+// limit or missing permissions) are queued here and retried with an
+// exponential backoff by a dedicated task, instead of waiting for the next
+// sync. After MAX_ATTEMPTS failed attempts, the organizers are notified and
+// the assignment is dropped.
+// Queued assignments are identified by "{discord_id}:{role_id}", the queue is
+// a sorted set with the time of the next attempt as the score.
+pub const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF_SECONDS: i64 = 60;
+const MAX_BACKOFF_SECONDS: i64 = 60 * 60;
+const REDIS_RETRY_QUEUE_KEY: &'static str = "discord_sync:role_retries";
+const REDIS_RETRY_ATTEMPTS_KEY: &'static str = "discord_sync:role_retry_attempts";
+
+fn assignment_id(discord_id: u64, role_id: RoleId) -> String {
+    format!("{}:{}", discord_id, role_id.0)
+}
+
+fn parse_assignment_id(assignment_id: &str) -> Option<(u64, RoleId)> {
+    let mut parts = assignment_id.splitn(2, ':');
+    match (
+        parts.next().and_then(|id| id.parse().ok()),
+        parts.next().and_then(|id| id.parse().ok()),
+    ) {
+        (Some(discord_id), Some(role_id)) => Some((discord_id, RoleId(role_id))),
+        _ => None,
+    }
+}
+
+// 1, 2, 4, ... minutes after the first, second, third, ... failure
+fn backoff(num_attempts: u32) -> chrono::Duration {
+    let seconds = INITIAL_BACKOFF_SECONDS
+        .saturating_mul(1 << std::cmp::min(num_attempts.saturating_sub(1), 16));
+    chrono::Duration::seconds(std::cmp::min(seconds, MAX_BACKOFF_SECONDS))
+}
+
+// Called when assigning the role failed. The assignment is retried later,
+// or escalated to the organizers if it failed too often.
+pub fn queue_failed_assignment(
+    discord_id: u64,
+    role_id: RoleId,
+    error: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let assignment_id = assignment_id(discord_id, role_id);
+    let num_attempts: u32 = redis_connection.hincr(REDIS_RETRY_ATTEMPTS_KEY, &assignment_id, 1)?;
+    if num_attempts >= MAX_ATTEMPTS {
+        eprintln!(
+            "Giving up on assigning user {} to role {} after {} attempts",
+            discord_id, role_id.0, num_attempts
+        );
+        forget(&assignment_id, redis_connection)?;
+        crate::discord_notifications::notify_organizers(
+            discord_api,
+            &crate::strings::ROLE_ASSIGNMENT_FAILED_NOTIFICATION(
+                discord_id,
+                role_id.0,
+                num_attempts,
+                error,
+            ),
+            redis_connection,
+        )?;
+        return Ok(());
+    }
+    let next_attempt = chrono::Utc::now() + backoff(num_attempts);
+    let _: () = redis_connection.zadd(
+        REDIS_RETRY_QUEUE_KEY,
+        &assignment_id,
+        next_attempt.timestamp(),
+    )?;
+    Ok(())
+}
+
+// Called when the sync managed to assign the role after all, such that
+// earlier failures don't count towards MAX_ATTEMPTS anymore
+pub fn assignment_succeeded(
+    discord_id: u64,
+    role_id: RoleId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    forget(&assignment_id(discord_id, role_id), redis_connection)
+}
+
+fn forget(
+    assignment_id: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    let _: () = redis::pipe()
+        .zrem(REDIS_RETRY_QUEUE_KEY, assignment_id)
+        .ignore()
+        .hdel(REDIS_RETRY_ATTEMPTS_KEY, assignment_id)
+        .ignore()
+        .query(redis_connection)?;
+    Ok(())
+}
+
+// Runs every minute on the leader
+pub fn create_role_retry_task(
+    redis_client: crate::redis_namespace::Client,
+    discord_api: crate::discord_bot::CacheAndHttp,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    move |_ctx| {
+        if let Err(err) = retry_due_assignments(&redis_client, &discord_api) {
+            eprintln!("Role assignment retry task failed: {}", err);
+            crate::error_reporting::report(&err, &[("task", "role_retries")]);
+        }
+        white_rabbit::DateResult::Repeat(
+            white_rabbit::Utc::now() + white_rabbit::Duration::minutes(1),
+        )
+    }
+}
+
+fn retry_due_assignments(
+    redis_client: &crate::redis_namespace::Client,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let mut con = redis_client.get_connection()?;
+    // Like the other tasks, wait until maintenance is over
+    if crate::maintenance_mode::is_active(&mut con)? {
+        return Ok(());
+    }
+    let now = chrono::Utc::now().timestamp();
+    let due_assignment_ids: Vec<String> = con.zrangebyscore(REDIS_RETRY_QUEUE_KEY, "-inf", now)?;
+    for assignment_id in due_assignment_ids {
+        let (discord_id, role_id) = match parse_assignment_id(&assignment_id) {
+            Some(assignment) => assignment,
+            None => {
+                eprintln!("Dropping the invalid role retry {}", assignment_id);
+                forget(&assignment_id, &mut con)?;
+                continue;
+            }
+        };
+        match is_still_wanted(discord_id, role_id, &mut con, discord_api) {
+            Ok(true) => (),
+            Ok(false) => {
+                forget(&assignment_id, &mut con)?;
+                continue;
+            }
+            Err(err) => {
+                // E.g. the user left the server
+                eprintln!(
+                    "Retry: could not check whether user {} still needs role {}: {}",
+                    discord_id, role_id.0, err
+                );
+                queue_failed_assignment(
+                    discord_id,
+                    role_id,
+                    &err.to_string(),
+                    &mut con,
+                    discord_api,
+                )?;
+                continue;
+            }
+        }
+        match discord_api.http().add_member_role(
+            crate::discord_sync::GUILD_ID.0,
+            discord_id,
+            role_id.0,
+        ) {
+            Ok(_) => {
+                println!(
+                    "Assigned user {} to role {} on retry",
+                    discord_id, role_id.0
+                );
+                forget(&assignment_id, &mut con)?;
+            }
+            Err(err) => {
+                eprintln!(
+                    "Retry: could not assign user {} to role {}: {}",
+                    discord_id, role_id.0, err
+                );
+                queue_failed_assignment(
+                    discord_id,
+                    role_id,
+                    &err.to_string(),
+                    &mut con,
+                    discord_api,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Things might have changed since the assignment failed: the user might have
+// gotten the role in the meantime, cancelled their RSVP, been removed from
+// the channel or been quarantined, or the role might be gone
+fn is_still_wanted(
+    discord_id: u64,
+    role_id: RoleId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    if !is_still_candidate(discord_id, role_id, redis_connection)? {
+        return Ok(false);
+    }
+    let role_exists = match crate::discord_sync::GUILD_ID.to_guild_cached(&discord_api.cache) {
+        Some(guild) => guild.read().roles.contains_key(&role_id),
+        // Let Discord decide
+        None => true,
+    };
+    if !role_exists {
+        return Ok(false);
+    }
+    let has_role = UserId(discord_id).to_user(discord_api)?.has_role(
+        discord_api,
+        crate::discord_sync::GUILD_ID,
+        role_id,
+    )?;
+    Ok(!has_role)
+}
+
+// Whether the Discord sync would still assign the role, going by the current
+// RSVPs (which also leaves out removed and quarantined users)
+fn is_still_candidate(
+    discord_id: u64,
+    role_id: RoleId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<bool, crate::BoxedError> {
+    let redis_role_channel_key = format!("discord_role:{}:discord_channel", role_id.0);
+    let redis_host_role_channel_key = format!("discord_host_role:{}:discord_channel", role_id.0);
+    let (channel_id, host_channel_id): (Option<u64>, Option<u64>) = redis::pipe()
+        .get(&redis_role_channel_key)
+        .get(&redis_host_role_channel_key)
+        .query(redis_connection)?;
+    let (channel_id, is_host_role) = match (channel_id, host_channel_id) {
+        (Some(channel_id), _) => (channel_id, false),
+        (_, Some(channel_id)) => (channel_id, true),
+        _ => {
+            // The only other role that the sync assigns
+            if crate::guild_config::game_master_role(redis_connection)? != Some(role_id) {
+                return Ok(false);
+            }
+            let series_ids: Vec<String> = redis_connection.smembers("event_series")?;
+            for series_id in series_ids {
+                let host_ids =
+                    crate::discord_sync::game_master_candidates(&series_id, redis_connection)?;
+                if host_ids.contains(&discord_id) {
+                    return Ok(true);
+                }
+            }
+            return Ok(false);
+        }
+    };
+    let redis_channel_series_key = format!("discord_channel:{}:event_series", channel_id);
+    let series_id: Option<String> = redis_connection.get(&redis_channel_series_key)?;
+    let series_id = match series_id {
+        Some(series_id) => series_id,
+        None => return Ok(false),
+    };
+    let candidate_ids = crate::discord_sync::role_assignment_candidates(
+        &series_id,
+        ChannelId(channel_id),
+        is_host_role,
+        redis_connection,
+    )?;
+    Ok(candidate_ids.contains(&discord_id))
+}
@@ -0,0 +1,173 @@
+use redis::{Commands, PipelineCommands};
+use serenity::http::CacheHttp;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+
+// Hosts can declare the lines (content that doesn't happen in the game),
+// veils (content that only happens off-screen) and safety tools (like the
+// X-card) of their game. The bot keeps a pinned message with them in the
+// channel and sends them to new players when they join the channel.
+pub const MAX_FIELD_CHARS: usize = 500;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Field {
+    Lines,
+    Veils,
+    Tools,
+}
+
+impl Field {
+    pub const ALL: [Field; 3] = [Field::Lines, Field::Veils, Field::Tools];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Lines => "lines",
+            Field::Veils => "veils",
+            Field::Tools => "tools",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Field> {
+        Field::ALL
+            .iter()
+            .find(|field| field.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SafetyInfo {
+    pub lines: Option<String>,
+    pub veils: Option<String>,
+    pub tools: Option<String>,
+}
+
+impl SafetyInfo {
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_none() && self.veils.is_none() && self.tools.is_none()
+    }
+}
+
+fn redis_safety_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:safety", channel_id.0)
+}
+
+fn redis_safety_message_key(channel_id: ChannelId) -> String {
+    format!("discord_channel:{}:safety_message", channel_id.0)
+}
+
+pub fn safety_info<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<SafetyInfo, crate::BoxedError> {
+    let (lines, veils, tools): (Option<String>, Option<String>, Option<String>) = con.hget(
+        redis_safety_key(channel_id),
+        &[
+            Field::Lines.name(),
+            Field::Veils.name(),
+            Field::Tools.name(),
+        ],
+    )?;
+    Ok(SafetyInfo {
+        lines,
+        veils,
+        tools,
+    })
+}
+
+// None removes the field
+pub fn set_field<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    field: Field,
+    value: Option<&str>,
+) -> Result<(), crate::BoxedError> {
+    match value {
+        Some(value) => {
+            let _: () = con.hset(redis_safety_key(channel_id), field.name(), value.trim())?;
+        }
+        None => {
+            let _: () = con.hdel(redis_safety_key(channel_id), field.name())?;
+        }
+    }
+    Ok(())
+}
+
+// Keeps the pinned safety message in line with the safety info, and removes
+// it once there is nothing to show anymore
+pub fn sync_safety_message(
+    channel_id: ChannelId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let safety_info = safety_info(redis_connection, channel_id)?;
+    let redis_safety_message_key = redis_safety_message_key(channel_id);
+    let (message_id, current_content): (Option<u64>, Option<String>) =
+        redis_connection.hget(&redis_safety_message_key, &["message_id", "content"])?;
+    if safety_info.is_empty() {
+        if let Some(message_id) = message_id {
+            // Most likely, somebody deleted the message already
+            if let Err(err) = channel_id.delete_message(discord_api.http(), MessageId(message_id)) {
+                eprintln!(
+                    "Could not delete the safety message in channel {}: {}",
+                    channel_id.0, err
+                );
+            }
+            let _: () = redis_connection.del(&redis_safety_message_key)?;
+        }
+        return Ok(());
+    }
+    let content = crate::strings::SAFETY_MESSAGE(&safety_info);
+    if let Some(message_id) = message_id {
+        if current_content.as_ref() == Some(&content) {
+            return Ok(());
+        }
+        match channel_id.edit_message(discord_api.http(), MessageId(message_id), |message| {
+            message.content(&content)
+        }) {
+            Ok(_) => {
+                let _: () =
+                    redis_connection.hset(&redis_safety_message_key, "content", &content)?;
+                return Ok(());
+            }
+            // Most likely, somebody deleted the message. Post a new one.
+            Err(err) => eprintln!(
+                "Could not update the safety message in channel {}: {}",
+                channel_id.0, err
+            ),
+        }
+    }
+    let message = channel_id.say(discord_api.http(), &content)?;
+    let _: () = redis::pipe()
+        .hset(&redis_safety_message_key, "message_id", message.id.0)
+        .ignore()
+        .hset(&redis_safety_message_key, "content", &content)
+        .ignore()
+        .query(redis_connection)?;
+    // Pinning requires the "Manage Messages" permission
+    if let Err(err) = message.pin(discord_api) {
+        eprintln!(
+            "Could not pin the safety message in channel {}: {}",
+            channel_id.0, err
+        );
+    }
+    Ok(())
+}
+
+// Part of welcoming a new player to the channel. Does nothing if the hosts
+// didn't declare anything.
+pub fn send_safety_note(
+    channel_id: ChannelId,
+    discord_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let safety_info = safety_info(redis_connection, channel_id)?;
+    if safety_info.is_empty() {
+        return Ok(());
+    }
+    let user = UserId(discord_id).to_user(discord_api)?;
+    user.direct_message(discord_api, |message_builder| {
+        message_builder.content(crate::strings::SAFETY_NOTE(channel_id.0, &safety_info))
+    })?;
+    Ok(())
+}
@@ -0,0 +1,272 @@
+use lazy_static::lazy_static;
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use serenity::http::ratelimiting::{Route, GLOBAL, ROUTES};
+use std::sync::Arc;
+
+// Upcoming Meetup events can be mirrored as Discord scheduled events, such
+// that members get Discord's native reminders even without a Meetup account.
+// Serenity does not know about the guild events API yet, so we talk to
+// Discord directly with the bot's token, but go through serenity's rate
+// limiter such that the requests don't run into the limits of the sync.
+const API_BASE_URL: &'static str = "https://discord.com/api/v10";
+// Meetup does not tell us how long a session takes, but Discord requires an
+// end time for external events
+const DEFAULT_EVENT_DURATION_HOURS: i64 = 4;
+const PRIVACY_LEVEL_GUILD_ONLY: u8 = 2;
+const ENTITY_TYPE_EXTERNAL: u8 = 3;
+// Limits imposed by Discord
+const MAX_NAME_LEN: usize = 100;
+const MAX_LOCATION_LEN: usize = 100;
+
+lazy_static! {
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+#[derive(Debug, Serialize)]
+struct EntityMetadata<'a> {
+    location: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct ScheduledEventParams<'a> {
+    name: &'a str,
+    description: &'a str,
+    scheduled_start_time: String,
+    scheduled_end_time: String,
+    privacy_level: u8,
+    entity_type: u8,
+    entity_metadata: EntityMetadata<'a>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScheduledEvent {
+    id: String,
+}
+
+// Makes sure that the Discord scheduled event of a Meetup event exists and
+// matches the Meetup event's name, time and link. Returns false if Discord
+// did not allow the bot to manage the scheduled events.
+pub fn sync_scheduled_event(
+    event_id: &str,
+    event_name: &str,
+    event_time: chrono::DateTime<chrono::Utc>,
+    event_link: &str,
+    event_location: &crate::event_location::Location,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    // Scheduled events are visible to the whole guild, so they never show the
+    // link to an online session
+    let location = match event_location {
+        crate::event_location::Location::Online { .. } => "Online",
+        crate::event_location::Location::Venue(venue) => venue.as_str(),
+        crate::event_location::Location::Unknown => event_link,
+    };
+    let redis_event_scheduled_event_key =
+        format!("meetup_event:{}:discord_scheduled_event", event_id);
+    let scheduled_event_id: Option<u64> = redis_connection.get(&redis_event_scheduled_event_key)?;
+    // Remember what we sent to Discord last time, such that we only update
+    // the scheduled event if something changed on Meetup
+    let synced_state = format!(
+        "{}|{}|{}|{}",
+        event_name,
+        event_time.to_rfc3339(),
+        event_link,
+        location
+    );
+    if let Some(scheduled_event_id) = scheduled_event_id {
+        let redis_scheduled_event_key = format!("discord_scheduled_event:{}", scheduled_event_id);
+        let last_synced_state: Option<String> =
+            redis_connection.hget(&redis_scheduled_event_key, "synced_state")?;
+        if last_synced_state.as_ref() == Some(&synced_state) {
+            return Ok(true);
+        }
+    }
+    let name: String = event_name.chars().take(MAX_NAME_LEN).collect();
+    let location: String = location.chars().take(MAX_LOCATION_LEN).collect();
+    let description = crate::strings::SCHEDULED_EVENT_DESCRIPTION(event_link);
+    let params = ScheduledEventParams {
+        name: &name,
+        description: &description,
+        scheduled_start_time: event_time.to_rfc3339(),
+        scheduled_end_time: (event_time + chrono::Duration::hours(DEFAULT_EVENT_DURATION_HOURS))
+            .to_rfc3339(),
+        privacy_level: PRIVACY_LEVEL_GUILD_ONLY,
+        entity_type: ENTITY_TYPE_EXTERNAL,
+        entity_metadata: EntityMetadata {
+            location: &location,
+        },
+    };
+    let events_url = format!(
+        "{}/guilds/{}/scheduled-events",
+        API_BASE_URL,
+        crate::discord_sync::GUILD_ID.0
+    );
+    let response = send(discord_api, || match scheduled_event_id {
+        Some(scheduled_event_id) => HTTP_CLIENT
+            .patch(&format!("{}/{}", events_url, scheduled_event_id))
+            .json(&params),
+        None => HTTP_CLIENT.post(&events_url).json(&params),
+    })?;
+    if response.status() == reqwest::StatusCode::FORBIDDEN {
+        return Ok(false);
+    }
+    if let Some(scheduled_event_id) = scheduled_event_id {
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            // Somebody deleted the scheduled event on Discord.
+            // Forget about it, it will be recreated during the next sync.
+            println!(
+                "Discord scheduled event {} of event {} does not exist anymore",
+                scheduled_event_id, event_id
+            );
+            forget_scheduled_event(scheduled_event_id, Some(event_id), redis_connection)?;
+            return Ok(true);
+        }
+    }
+    let scheduled_event: ScheduledEvent = response.error_for_status()?.json()?;
+    let scheduled_event_id: u64 = scheduled_event.id.parse()?;
+    println!(
+        "Discord event sync: synced scheduled event {} for event \"{}\"",
+        scheduled_event_id, event_name
+    );
+    let redis_scheduled_event_key = format!("discord_scheduled_event:{}", scheduled_event_id);
+    let _: () = redis::pipe()
+        .atomic()
+        .sadd("discord_scheduled_events", scheduled_event_id)
+        .ignore()
+        .set(&redis_event_scheduled_event_key, scheduled_event_id)
+        .ignore()
+        .hset_multiple(
+            &redis_scheduled_event_key,
+            &[("meetup_event", event_id), ("synced_state", &synced_state)],
+        )
+        .ignore()
+        .query(redis_connection)?;
+    Ok(true)
+}
+
+// Deletes the scheduled events of Meetup events that are not tracked anymore
+// (for example because they were cancelled) and forgets about past ones
+pub fn cleanup_scheduled_events(
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let scheduled_event_ids: Vec<u64> = redis_connection.smembers("discord_scheduled_events")?;
+    let now = chrono::Utc::now();
+    for scheduled_event_id in scheduled_event_ids {
+        let redis_scheduled_event_key = format!("discord_scheduled_event:{}", scheduled_event_id);
+        let event_id: Option<String> =
+            redis_connection.hget(&redis_scheduled_event_key, "meetup_event")?;
+        let event_time: Option<String> = match event_id {
+            Some(ref event_id) => {
+                let is_tracked: bool = redis_connection.sismember("meetup_events", event_id)?;
+                if is_tracked {
+                    redis_connection.hget(format!("meetup_event:{}", event_id), "time")?
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+        let event_time = event_time
+            .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
+            .transpose()?
+            .map(|t| t.with_timezone(&chrono::Utc));
+        match event_time {
+            Some(event_time) => {
+                // Discord ends external events by itself, so once the event
+                // is over there is nothing left to do
+                if now > event_time + chrono::Duration::hours(DEFAULT_EVENT_DURATION_HOURS) {
+                    forget_scheduled_event(
+                        scheduled_event_id,
+                        event_id.as_ref().map(String::as_str),
+                        redis_connection,
+                    )?;
+                }
+            }
+            None => {
+                let response = send(discord_api, || {
+                    HTTP_CLIENT.delete(&format!(
+                        "{}/guilds/{}/scheduled-events/{}",
+                        API_BASE_URL,
+                        crate::discord_sync::GUILD_ID.0,
+                        scheduled_event_id
+                    ))
+                })?;
+                // Most likely, the scheduled event was already deleted manually
+                if response.status() != reqwest::StatusCode::NOT_FOUND {
+                    response.error_for_status()?;
+                }
+                println!("Deleted Discord scheduled event {}", scheduled_event_id);
+                forget_scheduled_event(
+                    scheduled_event_id,
+                    event_id.as_ref().map(String::as_str),
+                    redis_connection,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn forget_scheduled_event(
+    scheduled_event_id: u64,
+    event_id: Option<&str>,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    let mut pipe = redis::pipe();
+    pipe.atomic()
+        .srem("discord_scheduled_events", scheduled_event_id)
+        .ignore()
+        .del(format!("discord_scheduled_event:{}", scheduled_event_id))
+        .ignore();
+    if let Some(event_id) = event_id {
+        pipe.del(format!("meetup_event:{}:discord_scheduled_event", event_id))
+            .ignore();
+    }
+    let _: () = pipe.query(redis_connection)?;
+    Ok(())
+}
+
+// Serenity has no route for the guild events API, so the requests share the
+// bucket of the guild. Like serenity's own requests, they wait for the global
+// rate limit and the bucket, and are sent again after a 429 (the request is
+// built anew each time).
+fn send<F>(
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    build_request: F,
+) -> Result<reqwest::Response, crate::BoxedError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    let route = Route::GuildsId(crate::discord_sync::GUILD_ID.0);
+    loop {
+        let authorization = authorization(discord_api);
+        // Blocks while another request waits out the global rate limit
+        drop(GLOBAL.lock());
+        let bucket = Arc::clone(ROUTES.lock().entry(route).or_default());
+        let mut bucket = bucket.lock();
+        bucket.pre_hook(&route);
+        let response = build_request()
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .send()?;
+        if !bucket.post_hook(&response, &route)? {
+            return Ok(response);
+        }
+    }
+}
+
+// Serenity stores the token with the "Bot " prefix already, but better be sure.
+// Called once per request, which is when the request is counted and waits for
+// the commands in flight.
+fn authorization(discord_api: &crate::discord_bot::CacheAndHttp) -> String {
+    crate::discord_call_priority::wait_for_turn();
+    crate::sync_stats::record_discord_api_call();
+    let token = &discord_api.http.token;
+    if token.starts_with("Bot ") {
+        token.clone()
+    } else {
+        format!("Bot {}", token)
+    }
+}
@@ -0,0 +1,283 @@
+use redis::Commands;
+use serenity::http::CacheHttp;
+use serenity::model::{
+    channel::ChannelType, channel::PermissionOverwrite, channel::PermissionOverwriteType,
+    id::ChannelId, id::RoleId, id::UserId, permissions::Permissions,
+};
+use simple_error::SimpleError;
+
+// Series can opt into a separate discussion channel per session, named after
+// the session's date. The channel is created a few days before the session,
+// made read-only (archived) the day after and deleted a week later.
+// (The Discord API version we use does not support threads, which would
+// otherwise be the natural fit for this.)
+const CREATION_LEAD_TIME_DAYS: i64 = 7;
+const ARCHIVE_DELAY_DAYS: i64 = 1;
+const DELETION_DELAY_DAYS: i64 = 8;
+
+pub fn is_enabled<C: redis::ConnectionLike>(
+    con: &mut C,
+    series_id: &str,
+) -> Result<bool, crate::BoxedError> {
+    let redis_series_session_channels_key = format!("event_series:{}:session_channels", series_id);
+    let enabled: Option<bool> = con.get(&redis_series_session_channels_key)?;
+    Ok(enabled.unwrap_or(false))
+}
+
+pub fn set_enabled<C: redis::ConnectionLike>(
+    con: &mut C,
+    series_id: &str,
+    enabled: bool,
+) -> Result<(), crate::BoxedError> {
+    let redis_series_session_channels_key = format!("event_series:{}:session_channels", series_id);
+    if enabled {
+        let _: () = con.set(&redis_series_session_channels_key, true)?;
+    } else {
+        let _: () = con.del(&redis_series_session_channels_key)?;
+    }
+    Ok(())
+}
+
+// Makes sure that an upcoming session has its own channel
+pub fn sync_session_channel(
+    event_id: &str,
+    event_name: &str,
+    event_time: chrono::DateTime<chrono::Utc>,
+    event_link: &str,
+    event_location: &crate::event_location::Location,
+    series_channel_id: ChannelId,
+    role_id: RoleId,
+    host_role_id: RoleId,
+    bot_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    creation_budget: &mut crate::discord_creation_budget::CreationBudget,
+) -> Result<(), crate::BoxedError> {
+    if event_time > chrono::Utc::now() + chrono::Duration::days(CREATION_LEAD_TIME_DAYS) {
+        // Too early
+        return Ok(());
+    }
+    let redis_event_session_channel_key =
+        format!("meetup_event:{}:discord_session_channel", event_id);
+    let session_channel_id: Option<u64> = redis_connection.get(&redis_event_session_channel_key)?;
+    if session_channel_id.is_some() {
+        return Ok(());
+    }
+    let series_channel = match series_channel_id.to_channel(discord_api)?.guild() {
+        Some(channel) => channel,
+        None => return Ok(()),
+    };
+    let (series_channel_name, category_id) = {
+        let channel = series_channel.read();
+        (channel.name.clone(), channel.category_id)
+    };
+    let channel_name = format!("{}-{}", series_channel_name, event_time.format("%Y-%m-%d"));
+    if !creation_budget.try_spend_channel() {
+        println!(
+            "Discord event sync: not creating session channel \"{}\", the creation budget is \
+             exhausted",
+            channel_name
+        );
+        return Ok(());
+    }
+    // Same permissions as the series channel
+    let role_everyone_id = RoleId(crate::discord_sync::GUILD_ID.0);
+    let permission_overwrites = vec![
+        PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::READ_MESSAGES,
+            kind: PermissionOverwriteType::Role(role_everyone_id),
+        },
+        PermissionOverwrite {
+            allow: Permissions::READ_MESSAGES,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(UserId(bot_id)),
+        },
+        PermissionOverwrite {
+            allow: Permissions::READ_MESSAGES | Permissions::MENTION_EVERYONE,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Role(role_id),
+        },
+        PermissionOverwrite {
+            allow: Permissions::READ_MESSAGES
+                | Permissions::MENTION_EVERYONE
+                | Permissions::MANAGE_MESSAGES,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Role(host_role_id),
+        },
+    ];
+    let session_channel =
+        crate::discord_sync::GUILD_ID.create_channel(discord_api.http(), |channel_builder| {
+            channel_builder
+                .name(&channel_name)
+                .kind(ChannelType::Text)
+                .permissions(permission_overwrites);
+            if let Some(category_id) = category_id {
+                channel_builder.category(category_id);
+            }
+            channel_builder
+        })?;
+    println!(
+        "Discord event sync: created session channel {} \"{}\"",
+        session_channel.id.0, &session_channel.name
+    );
+    let redis_session_channel_event_key = format!(
+        "discord_session_channel:{}:meetup_event",
+        session_channel.id.0
+    );
+    let _: () = redis::pipe()
+        .atomic()
+        .sadd("discord_session_channels", session_channel.id.0)
+        .ignore()
+        .set(&redis_event_session_channel_key, session_channel.id.0)
+        .ignore()
+        .set(&redis_session_channel_event_key, event_id)
+        .ignore()
+        .query(redis_connection)?;
+    let _ = session_channel.id.say(
+        discord_api.http(),
+        crate::strings::SESSION_CHANNEL_DETAILS(event_name, event_time, event_link, event_location),
+    );
+    Ok(())
+}
+
+// Archives the session channels of past sessions and deletes them some time later
+pub fn archive_and_delete_session_channels(
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let session_channel_ids: Vec<u64> = redis_connection.smembers("discord_session_channels")?;
+    let now = chrono::Utc::now();
+    let mut some_failed = false;
+    for session_channel_id in session_channel_ids {
+        // One channel that can't be cleaned up doesn't hold back the others.
+        // It is tried again with the next cleanup.
+        if let Err(err) = archive_or_delete_session_channel(
+            session_channel_id,
+            now,
+            redis_connection,
+            discord_api,
+        ) {
+            some_failed = true;
+            eprintln!(
+                "Could not clean up session channel {}: {}",
+                session_channel_id, err
+            );
+        }
+    }
+    if some_failed {
+        Err(SimpleError::new("One or more session channels could not be cleaned up").into())
+    } else {
+        Ok(())
+    }
+}
+
+fn archive_or_delete_session_channel(
+    session_channel_id: u64,
+    now: chrono::DateTime<chrono::Utc>,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let redis_session_channel_event_key = format!(
+        "discord_session_channel:{}:meetup_event",
+        session_channel_id
+    );
+    let redis_session_channel_archived_key =
+        format!("discord_session_channel:{}:archived", session_channel_id);
+    let event_id: Option<String> = redis_connection.get(&redis_session_channel_event_key)?;
+    // If the event is not tracked anymore, the session channel can go as well
+    let event_time: Option<String> = match event_id {
+        Some(ref event_id) => {
+            redis_connection.hget(format!("meetup_event:{}", event_id), "time")?
+        }
+        None => None,
+    };
+    let event_time = event_time
+        .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
+        .transpose()?
+        .map(|t| t.with_timezone(&chrono::Utc));
+    let (archive, delete) = match event_time {
+        Some(event_time) => (
+            now > event_time + chrono::Duration::days(ARCHIVE_DELAY_DAYS),
+            now > event_time + chrono::Duration::days(DELETION_DELAY_DAYS),
+        ),
+        None => (true, true),
+    };
+    if delete {
+        // Without the check, nothing gets deleted. The next cleanup tries again.
+        if !crate::discord_ownership::verify_managed_channel(
+            ChannelId(session_channel_id),
+            "discord_session_channels",
+            redis_connection,
+            discord_api,
+        )? {
+            return Ok(());
+        }
+        match ChannelId(session_channel_id).delete(discord_api.http()) {
+            Ok(_) => println!("Deleted session channel {}", session_channel_id),
+            // Most likely, the channel was already deleted manually
+            Err(err) => eprintln!(
+                "Could not delete session channel {}: {}",
+                session_channel_id, err
+            ),
+        }
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .srem("discord_session_channels", session_channel_id)
+            .ignore()
+            .del(&redis_session_channel_event_key)
+            .ignore()
+            .del(&redis_session_channel_archived_key)
+            .ignore();
+        if let Some(ref event_id) = event_id {
+            pipe.del(format!("meetup_event:{}:discord_session_channel", event_id))
+                .ignore();
+        }
+        let _: () = pipe.query(redis_connection)?;
+    } else if archive {
+        let archived: Option<bool> = redis_connection.get(&redis_session_channel_archived_key)?;
+        if archived.unwrap_or(false) {
+            return Ok(());
+        }
+        archive_channel(
+            ChannelId(session_channel_id),
+            crate::strings::SESSION_CHANNEL_ARCHIVED,
+            discord_api,
+        )?;
+        let _: () = redis_connection.set(&redis_session_channel_archived_key, true)?;
+        println!("Archived session channel {}", session_channel_id);
+    }
+    Ok(())
+}
+
+// Makes the channel read-only by denying everybody but the bot to send messages.
+// Also used for the main channels whose hosts chose to archive them when they
+// are closed.
+pub fn archive_channel(
+    channel_id: ChannelId,
+    goodbye_message: &str,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let channel = match channel_id.to_channel(discord_api)?.guild() {
+        Some(channel) => channel,
+        None => return Ok(()),
+    };
+    let role_overwrites: Vec<_> = channel
+        .read()
+        .permission_overwrites
+        .iter()
+        .filter(|overwrite| match overwrite.kind {
+            PermissionOverwriteType::Role(_) => true,
+            _ => false,
+        })
+        .cloned()
+        .collect();
+    // Say goodbye while we still can
+    let _ = channel_id.say(discord_api.http(), goodbye_message);
+    for mut overwrite in role_overwrites {
+        overwrite.allow.remove(Permissions::SEND_MESSAGES);
+        overwrite.deny.insert(Permissions::SEND_MESSAGES);
+        channel_id.create_permission(discord_api.http(), &overwrite)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use redis::{Commands, PipelineCommands};
+use serenity::model::id::ChannelId;
+
+// Counts the completed sessions of campaigns, such that the channel topic and
+// the session message can say "Session 17", and congratulates the channel when
+// it reaches a milestone.
+// Completed sessions are remembered by their event ID, which keeps the tally
+// correct if the Discord sync sees the same past event several times, and if
+// past events are removed from the series at some point.
+const MILESTONES: &[u64] = &[10, 25, 50, 75, 100, 150, 200, 250, 300];
+
+fn redis_completed_sessions_key(series_id: &str) -> String {
+    format!("event_series:{}:completed_sessions", series_id)
+}
+
+// Adds the past events to the tally. Returns the number of completed sessions
+// before and after.
+pub fn record_completed_sessions(
+    series_id: &str,
+    past_event_ids: &[&str],
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<(u64, u64), crate::BoxedError> {
+    let redis_completed_sessions_key = redis_completed_sessions_key(series_id);
+    let num_before: u64 = redis_connection.scard(&redis_completed_sessions_key)?;
+    if past_event_ids.is_empty() {
+        return Ok((num_before, num_before));
+    }
+    let num_added: u64 = redis_connection.sadd(&redis_completed_sessions_key, past_event_ids)?;
+    Ok((num_before, num_before + num_added))
+}
+
+// The number of the next session, only for campaigns
+pub fn next_session_number(
+    series_id: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<Option<u64>, crate::BoxedError> {
+    let redis_series_type_key = format!("event_series:{}:type", series_id);
+    let series_type: Option<String> = redis_connection.get(&redis_series_type_key)?;
+    if series_type.as_ref().map(String::as_str) != Some("campaign") {
+        return Ok(None);
+    }
+    let num_completed: u64 = redis_connection.scard(redis_completed_sessions_key(series_id))?;
+    Ok(Some(num_completed + 1))
+}
+
+// Like next_session_number, as if the past events were recorded already,
+// without recording them
+pub fn next_session_number_with(
+    series_id: &str,
+    past_event_ids: &[&str],
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<Option<u64>, crate::BoxedError> {
+    let next_session_number = match next_session_number(series_id, redis_connection)? {
+        Some(next_session_number) => next_session_number,
+        None => return Ok(None),
+    };
+    if past_event_ids.is_empty() {
+        return Ok(Some(next_session_number));
+    }
+    let redis_completed_sessions_key = redis_completed_sessions_key(series_id);
+    let mut pipe = redis::pipe();
+    for event_id in past_event_ids {
+        pipe.sismember(&redis_completed_sessions_key, *event_id);
+    }
+    let is_recorded: Vec<bool> = pipe.query(redis_connection)?;
+    let num_unrecorded = is_recorded.into_iter().filter(|recorded| !recorded).count() as u64;
+    Ok(Some(next_session_number + num_unrecorded))
+}
+
+// Posts a congratulation message if the channel just passed a milestone.
+// When a campaign is counted for the first time, its past milestones are not
+// announced anymore.
+pub fn announce_milestone(
+    channel_id: ChannelId,
+    num_before: u64,
+    num_after: u64,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    if num_before == 0 {
+        return Ok(());
+    }
+    let milestone = MILESTONES
+        .iter()
+        .filter(|&&milestone| num_before < milestone && milestone <= num_after)
+        .max();
+    if let Some(&milestone) = milestone {
+        println!(
+            "Channel {} reached its session milestone {}",
+            channel_id.0, milestone
+        );
+        channel_id.say(
+            &discord_api.http,
+            crate::strings::SESSION_MILESTONE_ANNOUNCEMENT(milestone),
+        )?;
+    }
+    Ok(())
+}
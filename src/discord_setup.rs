@@ -0,0 +1,289 @@
+use serenity::http::CacheHttp;
+use serenity::model::{
+    channel::Channel, channel::ChannelType, channel::PermissionOverwrite,
+    channel::PermissionOverwriteType, id::ChannelId, id::RoleId, id::UserId,
+    permissions::Permissions,
+};
+
+// The owner of a new server sets the bot up with "setup": the bot lists the
+// steps with their current values, and "setup <step> <value>" points a step
+// to an existing channel or role, creates a new one with "create" or clears
+// it with "none". The results go into the guild config (with an audit entry)
+// and, for the announcements, into the community digest config, so nothing
+// has to be edited by hand. The server itself and the organizer role are
+// compiled into the bot (see discord_sync) and can't be set up this way.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SetupStep {
+    OneShotCategory,
+    CampaignCategory,
+    GameMasterRole,
+    // Where organizer notifications and audit messages are posted
+    StaffChannel,
+    // Where the community digest announces new games
+    AnnouncementChannel,
+}
+
+impl SetupStep {
+    pub const ALL: [SetupStep; 5] = [
+        SetupStep::OneShotCategory,
+        SetupStep::CampaignCategory,
+        SetupStep::GameMasterRole,
+        SetupStep::StaffChannel,
+        SetupStep::AnnouncementChannel,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SetupStep::OneShotCategory => "one_shot_category",
+            SetupStep::CampaignCategory => "campaign_category",
+            SetupStep::GameMasterRole => "game_master_role",
+            SetupStep::StaffChannel => "staff_channel",
+            SetupStep::AnnouncementChannel => "announcement_channel",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SetupStep> {
+        SetupStep::ALL
+            .iter()
+            .find(|step| step.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    // The name of what "create" creates
+    fn default_name(&self) -> &'static str {
+        match self {
+            SetupStep::OneShotCategory => "One-Shots",
+            SetupStep::CampaignCategory => "Campaigns",
+            SetupStep::GameMasterRole => "Game Master",
+            SetupStep::StaffChannel => "bot-notifications",
+            SetupStep::AnnouncementChannel => "new-games",
+        }
+    }
+}
+
+pub enum SetupValue {
+    Create,
+    Existing(u64),
+    None,
+}
+
+// Channels and roles can be given as a mention or an ID
+pub fn parse_value(value: &str) -> Option<SetupValue> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("create") {
+        return Some(SetupValue::Create);
+    }
+    if value.eq_ignore_ascii_case("none") {
+        return Some(SetupValue::None);
+    }
+    value
+        .trim_start_matches("<#")
+        .trim_start_matches("<@&")
+        .trim_end_matches('>')
+        .parse::<u64>()
+        .ok()
+        .map(SetupValue::Existing)
+}
+
+pub fn is_guild_owner(cache: &serenity::cache::CacheRwLock, user_id: UserId) -> bool {
+    crate::discord_sync::GUILD_ID
+        .to_guild_cached(cache)
+        .map(|guild| guild.read().owner_id == user_id)
+        .unwrap_or(false)
+}
+
+fn cached_role_name(
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    role_id: RoleId,
+) -> Option<String> {
+    crate::discord_sync::GUILD_ID
+        .to_guild_cached(&discord_api.cache)
+        .and_then(|guild| {
+            guild
+                .read()
+                .roles
+                .get(&role_id)
+                .map(|role| role.name.clone())
+        })
+}
+
+// The name of a role of the server, such that listing it doesn't ping anyone
+pub fn role_name(discord_api: &crate::discord_bot::CacheAndHttp, role_id: RoleId) -> String {
+    cached_role_name(discord_api, role_id)
+        .map(|name| format!("@{}", name))
+        .unwrap_or_else(|| role_id.0.to_string())
+}
+
+// How the step is currently set up, for display. None if it isn't.
+pub fn current_value(
+    step: SetupStep,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Option<String>, crate::BoxedError> {
+    let channel_id = match step {
+        SetupStep::OneShotCategory => crate::guild_config::one_shot_category(redis_connection)?,
+        SetupStep::CampaignCategory => crate::guild_config::campaign_category(redis_connection)?,
+        SetupStep::StaffChannel => crate::guild_config::staff_channel(redis_connection)?,
+        SetupStep::AnnouncementChannel => {
+            crate::discord_community_digest::config(redis_connection)?.channel_id
+        }
+        SetupStep::GameMasterRole => {
+            return Ok(crate::guild_config::game_master_role(redis_connection)?
+                .map(|role_id| role_name(discord_api, role_id)));
+        }
+    };
+    Ok(channel_id.map(|channel_id| format!("<#{}>", channel_id.0)))
+}
+
+// The first step after the given one that isn't set up yet. Steps that were
+// skipped with "none" don't come up again.
+pub fn next_step(
+    after: SetupStep,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Option<SetupStep>, crate::BoxedError> {
+    let later_steps = SetupStep::ALL
+        .iter()
+        .skip_while(|&&step| step != after)
+        .skip(1);
+    for &step in later_steps {
+        if current_value(step, redis_connection, discord_api)?.is_none() {
+            return Ok(Some(step));
+        }
+    }
+    Ok(None)
+}
+
+// Checks that an existing channel or role fits the step
+fn is_valid_existing(
+    step: SetupStep,
+    id: u64,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> bool {
+    if step == SetupStep::GameMasterRole {
+        return cached_role_name(discord_api, RoleId(id)).is_some();
+    }
+    match ChannelId(id).to_channel(discord_api) {
+        Ok(Channel::Category(category)) => {
+            (step == SetupStep::OneShotCategory || step == SetupStep::CampaignCategory)
+                && category.read().guild_id == crate::discord_sync::GUILD_ID
+        }
+        Ok(Channel::Guild(channel)) => {
+            let channel = channel.read();
+            (step == SetupStep::StaffChannel || step == SetupStep::AnnouncementChannel)
+                && channel.kind == ChannelType::Text
+                && channel.guild_id == crate::discord_sync::GUILD_ID
+        }
+        Ok(_) => false,
+        Err(err) => {
+            eprintln!("Could not find channel {}: {}", id, err);
+            false
+        }
+    }
+}
+
+// Creates the category, channel or role of the step and returns its ID
+fn create(
+    step: SetupStep,
+    bot_id: u64,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<u64, crate::BoxedError> {
+    let role_everyone_id = RoleId(crate::discord_sync::GUILD_ID.0);
+    let (kind, permission_overwrites) = match step {
+        SetupStep::GameMasterRole => {
+            // The Discord sync takes care of the role's style
+            let role =
+                crate::discord_sync::GUILD_ID.create_role(discord_api.http(), |role_builder| {
+                    role_builder
+                        .name(step.default_name())
+                        .permissions(Permissions::empty())
+                })?;
+            crate::discord_state_cache::upsert_role(&role);
+            return Ok(role.id.0);
+        }
+        SetupStep::OneShotCategory | SetupStep::CampaignCategory => (ChannelType::Category, vec![]),
+        // Only for the organizers (and the bot)
+        SetupStep::StaffChannel => (
+            ChannelType::Text,
+            vec![
+                PermissionOverwrite {
+                    allow: Permissions::empty(),
+                    deny: Permissions::READ_MESSAGES,
+                    kind: PermissionOverwriteType::Role(role_everyone_id),
+                },
+                PermissionOverwrite {
+                    allow: Permissions::READ_MESSAGES,
+                    deny: Permissions::empty(),
+                    kind: PermissionOverwriteType::Member(UserId(bot_id)),
+                },
+                PermissionOverwrite {
+                    allow: Permissions::READ_MESSAGES,
+                    deny: Permissions::empty(),
+                    kind: PermissionOverwriteType::Role(crate::discord_sync::ORGANIZER_ID),
+                },
+            ],
+        ),
+        // Everybody can read the announcements, only the bot posts them
+        SetupStep::AnnouncementChannel => (
+            ChannelType::Text,
+            vec![
+                PermissionOverwrite {
+                    allow: Permissions::empty(),
+                    deny: Permissions::SEND_MESSAGES,
+                    kind: PermissionOverwriteType::Role(role_everyone_id),
+                },
+                PermissionOverwrite {
+                    allow: Permissions::SEND_MESSAGES,
+                    deny: Permissions::empty(),
+                    kind: PermissionOverwriteType::Member(UserId(bot_id)),
+                },
+            ],
+        ),
+    };
+    let channel =
+        crate::discord_sync::GUILD_ID.create_channel(discord_api.http(), |channel_builder| {
+            channel_builder
+                .name(step.default_name())
+                .kind(kind)
+                .permissions(permission_overwrites)
+        })?;
+    Ok(channel.id.0)
+}
+
+// Returns false if the value doesn't fit the step, e.g. a text channel was
+// given for a category
+pub fn apply(
+    step: SetupStep,
+    value: SetupValue,
+    discord_user: u64,
+    bot_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    let id = match value {
+        SetupValue::Create => Some(create(step, bot_id, discord_api)?),
+        SetupValue::Existing(id) => {
+            if !is_valid_existing(step, id, discord_api) {
+                return Ok(false);
+            }
+            Some(id)
+        }
+        SetupValue::None => None,
+    };
+    let config_key = match step {
+        SetupStep::OneShotCategory => crate::guild_config::ConfigKey::OneShotCategory,
+        SetupStep::CampaignCategory => crate::guild_config::ConfigKey::CampaignCategory,
+        SetupStep::GameMasterRole => crate::guild_config::ConfigKey::GameMasterRole,
+        SetupStep::StaffChannel => crate::guild_config::ConfigKey::StaffChannel,
+        SetupStep::AnnouncementChannel => {
+            crate::discord_community_digest::set_channel(redis_connection, id.map(ChannelId))?;
+            return Ok(true);
+        }
+    };
+    let value = id
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| "none".to_string());
+    crate::guild_config::set(redis_connection, config_key, Some(&value), discord_user)?;
+    Ok(true)
+}
@@ -0,0 +1,152 @@
+use lazy_static::lazy_static;
+use serenity::http::CacheHttp;
+use serenity::model::{guild::Guild, guild::Role, id::ChannelId, id::RoleId};
+use serenity::prelude::RwLock;
+use std::collections::{HashMap, HashSet};
+
+// A snapshot of the guild's roles and channels, which the Discord sync
+// consults instead of asking Discord whether the bot managed roles and
+// channels still exist. Serenity's cache only knows about the guild if this
+// process happens to run the guild's shard, so the sync could not rely on it.
+// The snapshot is kept up-to-date by the gateway events this process
+// receives, by the sync's own changes and by pulling it from Discord again
+// when it gets old or something is missing from it.
+const MAX_SNAPSHOT_AGE_MINUTES: i64 = 60;
+
+struct Snapshot {
+    roles: HashMap<RoleId, Role>,
+    channel_ids: HashSet<ChannelId>,
+    pull_time: chrono::DateTime<chrono::Utc>,
+}
+
+lazy_static! {
+    static ref SNAPSHOT: RwLock<Option<Snapshot>> = RwLock::new(None);
+}
+
+// Replaces the snapshot with the current state on Discord
+pub fn pull(discord_api: &crate::discord_bot::CacheAndHttp) -> Result<(), crate::BoxedError> {
+    let roles = discord_api
+        .http()
+        .get_guild_roles(crate::discord_sync::GUILD_ID.0)?;
+    let channels = discord_api
+        .http()
+        .get_channels(crate::discord_sync::GUILD_ID.0)?;
+    *SNAPSHOT.write() = Some(Snapshot {
+        roles: roles.into_iter().map(|role| (role.id, role)).collect(),
+        channel_ids: channels.into_iter().map(|channel| channel.id).collect(),
+        pull_time: chrono::Utc::now(),
+    });
+    Ok(())
+}
+
+// The periodic pull, run at the start of each sync
+pub fn pull_if_stale(
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let is_stale = match &*SNAPSHOT.read() {
+        Some(snapshot) => {
+            snapshot.pull_time + chrono::Duration::minutes(MAX_SNAPSHOT_AGE_MINUTES)
+                < chrono::Utc::now()
+        }
+        None => true,
+    };
+    if is_stale {
+        pull(discord_api)?;
+    }
+    Ok(())
+}
+
+// Forces a pull the next time the snapshot is used, for changes whose
+// outcome we don't know exactly
+pub fn invalidate() {
+    *SNAPSHOT.write() = None;
+}
+
+pub fn roles(
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Vec<Role>, crate::BoxedError> {
+    pull_if_stale(discord_api)?;
+    Ok(match &*SNAPSHOT.read() {
+        Some(snapshot) => snapshot.roles.values().cloned().collect(),
+        None => vec![],
+    })
+}
+
+// Only asks Discord if the role is missing from the snapshot
+pub fn role_exists(
+    role_id: RoleId,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    pull_if_stale(discord_api)?;
+    if contains_role(role_id) {
+        return Ok(true);
+    }
+    pull(discord_api)?;
+    Ok(contains_role(role_id))
+}
+
+// Only asks Discord if the channel is missing from the snapshot
+pub fn channel_exists(
+    channel_id: ChannelId,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    pull_if_stale(discord_api)?;
+    if contains_channel(channel_id) {
+        return Ok(true);
+    }
+    pull(discord_api)?;
+    Ok(contains_channel(channel_id))
+}
+
+fn contains_role(role_id: RoleId) -> bool {
+    match &*SNAPSHOT.read() {
+        Some(snapshot) => snapshot.roles.contains_key(&role_id),
+        None => false,
+    }
+}
+
+fn contains_channel(channel_id: ChannelId) -> bool {
+    match &*SNAPSHOT.read() {
+        Some(snapshot) => snapshot.channel_ids.contains(&channel_id),
+        None => false,
+    }
+}
+
+// The following keep the snapshot up-to-date with gateway events and the
+// sync's own changes. Without a snapshot there is nothing to update, the next
+// use pulls a fresh one anyway.
+
+pub fn update_from_guild(guild: &Guild) {
+    if guild.id != crate::discord_sync::GUILD_ID {
+        return;
+    }
+    *SNAPSHOT.write() = Some(Snapshot {
+        roles: guild.roles.clone(),
+        channel_ids: guild.channels.keys().cloned().collect(),
+        pull_time: chrono::Utc::now(),
+    });
+}
+
+pub fn upsert_role(role: &Role) {
+    if let Some(snapshot) = &mut *SNAPSHOT.write() {
+        snapshot.roles.insert(role.id, role.clone());
+    }
+}
+
+pub fn remove_role(role_id: RoleId) {
+    if let Some(snapshot) = &mut *SNAPSHOT.write() {
+        snapshot.roles.remove(&role_id);
+    }
+}
+
+pub fn insert_channel(channel_id: ChannelId) {
+    if let Some(snapshot) = &mut *SNAPSHOT.write() {
+        snapshot.channel_ids.insert(channel_id);
+    }
+}
+
+pub fn remove_channel(channel_id: ChannelId) {
+    if let Some(snapshot) = &mut *SNAPSHOT.write() {
+        snapshot.channel_ids.remove(&channel_id);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::strings;
+use redis::Commands;
+use serenity::model::id::UserId;
+
+// Users can subscribe to tags (see event_tags) to hear about new games.
+// Whenever a new event series shows up, its subscribers get a DM. Organizers
+// can assign a role to a tag instead, in which case subscribers get that role
+// and the role is pinged in the community digest channel.
+const ANNOUNCED_SERIES_KEY: &'static str = "subscriptions:announced_event_series";
+
+fn redis_tag_roles_key() -> String {
+    format!(
+        "guild:{}:subscription_roles",
+        crate::discord_sync::GUILD_ID.0
+    )
+}
+
+pub fn subscriptions<C: redis::ConnectionLike>(
+    con: &mut C,
+    discord_id: u64,
+) -> Result<Vec<String>, crate::BoxedError> {
+    let mut tags: Vec<String> =
+        con.smembers(format!("discord_user:{}:subscriptions", discord_id))?;
+    tags.sort_unstable();
+    Ok(tags)
+}
+
+// Returns false if the user was already subscribed
+pub fn subscribe(
+    discord_id: u64,
+    tag: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    let (added, _): (u32, u32) = redis::pipe()
+        .sadd(format!("discord_user:{}:subscriptions", discord_id), tag)
+        .sadd(
+            format!("subscription_tag:{}:discord_users", tag),
+            discord_id,
+        )
+        .query(redis_connection)?;
+    if let Some(role_id) = tag_role(redis_connection, tag)? {
+        discord_api
+            .http
+            .add_member_role(crate::discord_sync::GUILD_ID.0, discord_id, role_id)?;
+    }
+    Ok(added > 0)
+}
+
+// Returns false if the user was not subscribed
+pub fn unsubscribe(
+    discord_id: u64,
+    tag: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    let (removed, _): (u32, u32) = redis::pipe()
+        .srem(format!("discord_user:{}:subscriptions", discord_id), tag)
+        .srem(
+            format!("subscription_tag:{}:discord_users", tag),
+            discord_id,
+        )
+        .query(redis_connection)?;
+    if let Some(role_id) = tag_role(redis_connection, tag)? {
+        discord_api.http.remove_member_role(
+            crate::discord_sync::GUILD_ID.0,
+            discord_id,
+            role_id,
+        )?;
+    }
+    Ok(removed > 0)
+}
+
+pub fn tag_role<C: redis::ConnectionLike>(
+    con: &mut C,
+    tag: &str,
+) -> Result<Option<u64>, crate::BoxedError> {
+    Ok(con.hget(redis_tag_roles_key(), tag)?)
+}
+
+// Gives the role to everybody who is already subscribed to the tag.
+// Removing the role of a tag leaves the role assignments alone.
+pub fn set_tag_role(
+    tag: &str,
+    role_id: Option<u64>,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let role_id = match role_id {
+        Some(role_id) => role_id,
+        None => {
+            let _: () = redis_connection.hdel(redis_tag_roles_key(), tag)?;
+            return Ok(());
+        }
+    };
+    let _: () = redis_connection.hset(redis_tag_roles_key(), tag, role_id)?;
+    let subscriber_ids: Vec<u64> =
+        redis_connection.smembers(format!("subscription_tag:{}:discord_users", tag))?;
+    for subscriber_id in subscriber_ids {
+        if let Err(err) = discord_api.http.add_member_role(
+            crate::discord_sync::GUILD_ID.0,
+            subscriber_id,
+            role_id,
+        ) {
+            eprintln!(
+                "Could not assign the subscription role of tag {} to user {}: {}",
+                tag, subscriber_id, err
+            );
+        }
+    }
+    Ok(())
+}
+
+// Announces new event series to the subscribers of their tags
+pub fn notify_subscribers(
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    // The first time around, don't announce everything that already exists
+    let initialized: bool = redis_connection.exists(ANNOUNCED_SERIES_KEY)?;
+    if !initialized {
+        let _: () = redis_connection.sunionstore(ANNOUNCED_SERIES_KEY, vec!["event_series"])?;
+        return Ok(());
+    }
+    let new_series_ids: Vec<String> =
+        redis_connection.sdiff(vec!["event_series", ANNOUNCED_SERIES_KEY])?;
+    let announcement_channel_id =
+        crate::discord_community_digest::config(redis_connection)?.channel_id;
+    let quarantined_user_ids = crate::quarantine::quarantined_users(redis_connection)?;
+    for series_id in new_series_ids {
+        let next_event = match next_event(&series_id, redis_connection)? {
+            Some(next_event) => next_event,
+            // Wait until the series has an upcoming event
+            None => continue,
+        };
+        let (name, time, link) = next_event;
+        let tags = crate::event_tags::series_tags(redis_connection, &series_id)?;
+        // Tags with a role are announced by pinging the role
+        let mut role_ids = vec![];
+        let mut role_tags = vec![];
+        let mut dm_tags = vec![];
+        for tag in &tags {
+            match (tag_role(redis_connection, tag)?, announcement_channel_id) {
+                (Some(role_id), Some(_)) => {
+                    role_ids.push(role_id);
+                    role_tags.push(format!("subscription_tag:{}:discord_users", tag));
+                }
+                _ => dm_tags.push(tag.clone()),
+            }
+        }
+        if let (Some(channel_id), false) = (announcement_channel_id, role_ids.is_empty()) {
+            channel_id.say(
+                &discord_api.http,
+                strings::SUBSCRIPTION_ANNOUNCEMENT(&role_ids, &name, time, &link),
+            )?;
+        }
+        if !dm_tags.is_empty() {
+            let dm_tag_keys: Vec<_> = dm_tags
+                .iter()
+                .map(|tag| format!("subscription_tag:{}:discord_users", tag))
+                .collect();
+            let mut subscriber_ids: Vec<u64> = redis_connection.sunion(dm_tag_keys)?;
+            // Users who were pinged already don't need a DM as well
+            if !role_tags.is_empty() {
+                let pinged_ids: Vec<u64> = redis_connection.sunion(role_tags)?;
+                subscriber_ids.retain(|id| !pinged_ids.contains(id));
+            }
+            subscriber_ids.retain(|id| !quarantined_user_ids.contains(id));
+            for subscriber_id in subscriber_ids {
+                let user_tags: Vec<String> = subscriptions(redis_connection, subscriber_id)?
+                    .into_iter()
+                    .filter(|tag| dm_tags.contains(tag))
+                    .collect();
+                let dm_result = UserId(subscriber_id).to_user(discord_api).and_then(|user| {
+                    user.direct_message(discord_api, |message_builder| {
+                        message_builder
+                            .content(strings::SUBSCRIPTION_DM(&user_tags, &name, time, &link))
+                    })
+                });
+                if let Err(err) = dm_result {
+                    eprintln!(
+                        "Could not notify subscriber {} about a new game: {}",
+                        subscriber_id, err
+                    );
+                }
+            }
+        }
+        let _: () = redis_connection.sadd(ANNOUNCED_SERIES_KEY, &series_id)?;
+    }
+    Ok(())
+}
+
+// The next upcoming event (name, time and link) of the series
+fn next_event(
+    series_id: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<Option<(String, chrono::DateTime<chrono::Utc>, String)>, crate::BoxedError> {
+    let redis_series_events_key = format!("event_series:{}:meetup_events", series_id);
+    let event_ids: Vec<String> = redis_connection.smembers(&redis_series_events_key)?;
+    let now = chrono::Utc::now();
+    let mut next_event = None;
+    for event_id in event_ids {
+        let redis_event_key = format!("meetup_event:{}", event_id);
+        let (time, name, link): (Option<String>, Option<String>, Option<String>) =
+            redis_connection.hget(&redis_event_key, &["time", "name", "link"])?;
+        if let (Some(time), Some(name), Some(link)) = (time, name, link) {
+            let time = chrono::DateTime::parse_from_rfc3339(&time)?.with_timezone(&chrono::Utc);
+            let is_earlier = match &next_event {
+                Some((_, next_time, _)) => time < *next_time,
+                None => true,
+            };
+            if time > now && is_earlier {
+                next_event = Some((name, time, link));
+            }
+        }
+    }
+    Ok(next_event)
+}
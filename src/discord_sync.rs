@@ -3,8 +3,8 @@ use redis;
 use redis::{Commands, PipelineCommands};
 use serenity::http::CacheHttp;
 use serenity::model::{
-    channel::PermissionOverwrite, channel::PermissionOverwriteType, id::ChannelId, id::GuildId,
-    id::RoleId, id::UserId, permissions::Permissions,
+    channel::PermissionOverwrite, channel::PermissionOverwriteType, guild::Role, id::ChannelId,
+    id::GuildId, id::RoleId, id::UserId, permissions::Permissions,
 };
 use simple_error::SimpleError;
 use white_rabbit;
@@ -15,43 +15,133 @@ pub const ORGANIZER_ID: RoleId = RoleId(606829075226689536);
 pub const GAME_MASTER_ID: Option<RoleId> = Some(RoleId(606913167439822987));
 pub const ONE_SHOT_CATEGORY_ID: Option<ChannelId> = Some(ChannelId(607561808429056042));
 pub const CAMPAIGN_CATEGORY_ID: Option<ChannelId> = Some(ChannelId(607561949651402772));
+pub const STAFF_CHANNEL_ID: Option<ChannelId> = None;
+pub const ROLE_ANCHOR_ID: Option<RoleId> = None;
+// Given to everyone who linked their Meetup account
+pub const LINKED_ROLE_ID: Option<RoleId> = None;
 // SwissRPG:
 // pub const GUILD_ID: GuildId = GuildId(401856510709202945);
 // pub const ORGANIZER_ID: RoleId = RoleId(539447673988841492);
 // pub const GAME_MASTER_ID: Option<RoleId> = Some(RoleId(412946716892069888));
 // pub const ONE_SHOT_CATEGORY_ID: Option<ChannelId> = Some(ChannelId(562607292176924694));
 // pub const CAMPAIGN_CATEGORY_ID: Option<ChannelId> = Some(ChannelId(414074722259828736));
+// pub const STAFF_CHANNEL_ID: Option<ChannelId> = None;
+// pub const ROLE_ANCHOR_ID: Option<RoleId> = None;
+// pub const LINKED_ROLE_ID: Option<RoleId> = None;
+
+// How the roles managed by the bot should look.
+// Channel and host roles are additionally kept right above ROLE_ANCHOR_ID (if set),
+// such that they end up above the roles that can't be mentioned by everyone.
+pub struct RoleStyle {
+    pub colour: Option<u64>,
+    pub hoist: bool,
+}
+pub const CHANNEL_ROLE_STYLE: RoleStyle = RoleStyle {
+    colour: None,
+    hoist: false,
+};
+pub const HOST_ROLE_STYLE: RoleStyle = RoleStyle {
+    colour: Some(0xE67E22),
+    hoist: false,
+};
+pub const GAME_MASTER_ROLE_STYLE: RoleStyle = RoleStyle {
+    colour: None,
+    hoist: false,
+};
+
+// The recurring sync only syncs the series that meetup_sync marked as dirty,
+// plus the series that haven't been synced for a while. The latter catches
+// changes made on Discord and updates that only depend on the time.
+pub const REDIS_DIRTY_SERIES_KEY: &'static str = "discord_sync:dirty_event_series";
+const REDIS_SERIES_SYNC_TIMES_KEY: &'static str = "discord_sync:event_series_sync_times";
+const FULL_SYNC_INTERVAL_HOURS: i64 = 6;
 
 lazy_static! {
     static ref EVENT_NAME_REGEX: regex::Regex =
         regex::Regex::new(r"^\s*(?P<name>[^\[\(]+[^\s\[\(])").unwrap();
 }
 
-struct Event {
-    #[allow(dead_code)]
-    id: String,
-    name: String,
-    time: chrono::DateTime<chrono::Utc>,
-    link: String,
+pub struct Event {
+    pub id: String,
+    pub name: String,
+    pub time: chrono::DateTime<chrono::Utc>,
+    pub link: String,
+    pub location: crate::event_location::Location,
 }
 
 // Syncs Discord with the state of the Redis database
 pub fn create_sync_discord_task(
-    redis_client: redis::Client,
+    redis_client: crate::redis_namespace::Client,
     discord_api: crate::discord_bot::CacheAndHttp,
     bot_id: u64,
     recurring: bool,
 ) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
     move |_ctx| {
-        let next_sync_time = match sync_discord(&redis_client, &discord_api, bot_id) {
+        // One-off syncs are requested by organizers and sync everything
+        let full_sync = !recurring;
+        let next_sync_time = match sync_discord(&redis_client, &discord_api, bot_id, full_sync) {
             Err(err) => {
                 eprintln!("Discord syncing task failed: {}", err);
+                crate::error_reporting::report(&err, &[("task", "sync_discord")]);
                 // Retry in a minute
                 white_rabbit::Utc::now() + white_rabbit::Duration::minutes(1)
             }
-            _ => {
-                // Do another sync in 15 minutes
-                white_rabbit::Utc::now() + white_rabbit::Duration::minutes(15)
+            Ok(has_deferred) => {
+                // Do another sync after the interval the organizers configured
+                let interval_minutes = redis_client
+                    .get_connection()
+                    .map_err(crate::BoxedError::from)
+                    .and_then(|mut con| crate::guild_config::sync_interval(&mut con))
+                    .map(|interval| interval.num_minutes())
+                    .unwrap_or_else(|err| {
+                        eprintln!("Could not read the sync interval: {}", err);
+                        crate::guild_config::DEFAULT_SYNC_INTERVAL_MINUTES
+                    });
+                let next_sync_time =
+                    white_rabbit::Utc::now() + white_rabbit::Duration::minutes(interval_minutes);
+                // Pick up what didn't fit into the API budget soon, but give
+                // the rate limits some time to recover
+                let next_sync_time = if has_deferred {
+                    std::cmp::min(
+                        next_sync_time,
+                        white_rabbit::Utc::now()
+                            + white_rabbit::Duration::minutes(
+                                crate::discord_api_budget::DEFERRED_RETRY_MINUTES,
+                            ),
+                    )
+                } else {
+                    next_sync_time
+                };
+                // Or earlier, once the channel edits that were deferred fit
+                // into the edit budget again
+                let retry_time = if recurring {
+                    redis_client
+                        .get_connection()
+                        .map_err(crate::BoxedError::from)
+                        .and_then(|mut con| crate::discord_channel_edits::take_retry_time(&mut con))
+                        .unwrap_or_else(|err| {
+                            eprintln!(
+                                "Could not read the retry time of the channel edits: {}",
+                                err
+                            );
+                            None
+                        })
+                } else {
+                    None
+                };
+                match retry_time {
+                    Some(retry_time) => {
+                        let retry_delay_ms =
+                            (retry_time - chrono::Utc::now()).num_milliseconds().max(0);
+                        std::cmp::min(
+                            next_sync_time,
+                            white_rabbit::Utc::now()
+                                + white_rabbit::Duration::milliseconds(retry_delay_ms)
+                                + white_rabbit::Duration::seconds(5),
+                        )
+                    }
+                    None => next_sync_time,
+                }
             }
         };
         if recurring {
@@ -62,98 +152,453 @@ pub fn create_sync_discord_task(
     }
 }
 
+// Returns whether work was left for the next sync because of the API budget
 pub fn sync_discord(
-    redis_client: &redis::Client,
+    redis_client: &crate::redis_namespace::Client,
     discord_api: &crate::discord_bot::CacheAndHttp,
     bot_id: u64,
-) -> Result<(), crate::BoxedError> {
-    let redis_series_key = "event_series";
+    full_sync: bool,
+) -> Result<bool, crate::BoxedError> {
     let mut con = redis_client.get_connection()?;
-    let event_series: Vec<String> = con.smembers(redis_series_key)?;
+    let event_series = series_to_sync(&mut con, full_sync)?;
+    // Refresh the snapshot of the guild's roles and channels if it is old
+    crate::discord_state_cache::pull_if_stale(discord_api)?;
+    let mut creation_budget =
+        crate::discord_creation_budget::CreationBudget::new(&mut con, discord_api)?;
+    let mut api_budget = crate::discord_api_budget::ApiBudget::new();
+    let mut profile = crate::sync_stats::SyncProfile::new();
     let mut some_failed = false;
     for series in &event_series {
-        if let Err(err) = sync_event_series(series, &mut con, discord_api, bot_id) {
+        // Left alone while a host or organizer restructures the channel.
+        // Resuming marks the series dirty, so it is picked up right away.
+        match is_sync_paused(&mut con, series) {
+            Ok(true) => {
+                println!("Discord sync: skipping paused series {}", series);
+                continue;
+            }
+            Ok(false) => (),
+            Err(err) => eprintln!(
+                "Could not check whether the sync of series {} is paused: {}",
+                series, err
+            ),
+        }
+        // Leave the Discord API to the commands once the budget is used up
+        if !api_budget.allows_series() {
+            if let Err(err) = mark_series_dirty(&mut con, series) {
+                eprintln!("Could not defer the sync of series {}: {}", series, err);
+            }
+            continue;
+        }
+        let num_denied = creation_budget.num_denied();
+        let num_deferred = api_budget.num_deferred_steps();
+        let sync_result = sync_event_series(
+            series,
+            &mut con,
+            discord_api,
+            bot_id,
+            &mut creation_budget,
+            &mut api_budget,
+            &mut profile,
+        );
+        // Series that failed or were held back by the creation or API budget
+        // are retried in the next run
+        let bookkeeping_result = if sync_result.is_err()
+            || creation_budget.num_denied() > num_denied
+            || api_budget.num_deferred_steps() > num_deferred
+        {
+            mark_series_dirty(&mut con, series)
+        } else {
+            record_series_synced(&mut con, series)
+        };
+        if let Err(err) = bookkeeping_result {
+            eprintln!(
+                "Could not remember the sync state of series {}: {}",
+                series, err
+            );
+        }
+        let notification_result = match &sync_result {
+            Ok(()) => crate::discord_notifications::record_series_sync_success(series, &mut con),
+            Err(err) => {
+                some_failed = true;
+                crate::error_reporting::report(
+                    err,
+                    &[("task", "sync_event_series"), ("series_id", series)],
+                );
+                crate::discord_notifications::record_series_sync_failure(
+                    series,
+                    err,
+                    &mut con,
+                    discord_api,
+                )
+            }
+        };
+        if let Err(err) = notification_result {
+            eprintln!("Could not record the sync result of series {}: {}", series, err);
+        }
+    }
+    // Keep the game master role looking the way it is configured (a cosmetic
+    // step, so only within the API budget)
+    let game_master_role = crate::guild_config::game_master_role(&mut con).unwrap_or_else(|err| {
+        eprintln!("Could not look up the game master role: {}", err);
+        None
+    });
+    let game_master_role = game_master_role.filter(|_| api_budget.allows_low_priority());
+    if let Some(game_master_role) = game_master_role {
+        if let Err(err) = sync_role_style(
+            game_master_role,
+            &GAME_MASTER_ROLE_STYLE,
+            /*keep_above_anchor*/ false,
+            discord_api,
+        ) {
+            eprintln!("Could not sync the style of the game master role: {}", err);
+        }
+    }
+    // Archive and clean up the discussion channels of past sessions. Like the
+    // other guild-wide steps below, this runs with every sync, so it simply
+    // waits for the next run once the API budget is used up.
+    if api_budget.allows_step() {
+        if let Err(err) = crate::discord_session_channels::archive_and_delete_session_channels(
+            &mut con,
+            discord_api,
+        ) {
             some_failed = true;
-            eprintln!("Discord event series syncing task failed: {}", err);
+            eprintln!("Could not clean up the session channels: {}", err);
         }
     }
+    // Remove the scheduled events of sessions that were cancelled
+    if api_budget.allows_step() {
+        if let Err(err) =
+            crate::discord_scheduled_events::cleanup_scheduled_events(&mut con, discord_api)
+        {
+            some_failed = true;
+            eprintln!("Could not clean up the Discord scheduled events: {}", err);
+        }
+    }
+    // Suggest links for the Discord usernames that members gave when they RSVP'd
+    if api_budget.allows_step() {
+        if let Err(err) =
+            crate::link_suggestions::suggest_from_rsvp_answers(&mut con, discord_api, bot_id)
+        {
+            eprintln!("Could not suggest links from the RSVP answers: {}", err);
+        }
+    }
+    // Let the organizers know about members that left the Meetup groups and
+    // take their channels away once the grace period is over
+    if api_budget.allows_step() {
+        if let Err(err) = crate::meetup_membership::enforce_departures(&mut con, discord_api) {
+            eprintln!("Could not handle the members that left Meetup: {}", err);
+        }
+    }
+    // Give the dues roles to exactly the members whose dues are paid
+    if api_budget.allows_step() {
+        if let Err(err) = crate::meetup_dues::sync_gated_roles(&mut con, discord_api) {
+            eprintln!("Could not sync the dues roles: {}", err);
+        }
+    }
+    // Show the Meetup first names in the nicknames, if configured
+    if api_budget.allows_step() {
+        if let Err(err) = crate::discord_nicknames::sync_nicknames(&mut con, discord_api) {
+            eprintln!("Could not sync the nicknames: {}", err);
+        }
+    }
+    // Let subscribers know about new games
+    if api_budget.allows_step() {
+        if let Err(err) = crate::discord_subscriptions::notify_subscribers(&mut con, discord_api) {
+            eprintln!("Could not notify the subscribers about new games: {}", err);
+        }
+    }
+    // Prune the roles of archived channels if configured and warn the
+    // organizers if the server is running out of roles
+    if api_budget.allows_step() {
+        if let Err(err) = crate::discord_role_limit::monitor(&mut con, discord_api) {
+            eprintln!("Could not check the role limit: {}", err);
+        }
+    }
+    // Let the organizers know if channels or roles were held back (regardless
+    // of the API budget, it's a single message at most)
+    if let Err(err) = creation_budget.finish(&mut con, discord_api) {
+        eprintln!(
+            "Could not check the channel and role creation budget: {}",
+            err
+        );
+    }
+    // Let the organizers know if Meetup is currently unavailable
+    if let Err(err) = crate::meetup_circuit_breaker::announce_state_changes(&mut con, discord_api)
+    {
+        eprintln!("Could not announce the Meetup API status: {}", err);
+    }
+    if api_budget.has_deferred() {
+        println!(
+            "Discord sync: used {} of {} API calls, deferred {} cosmetic steps and {} series",
+            api_budget.num_used(),
+            crate::discord_api_budget::MAX_API_CALLS_PER_SYNC,
+            api_budget.num_deferred_steps(),
+            api_budget.num_deferred_series()
+        );
+    }
+    // Keep the timings of this run for the "sync stats" command
+    profile.record_api_budget(&api_budget);
+    if let Err(err) = profile.save(&mut con) {
+        eprintln!("Could not save the Discord sync statistics: {}", err);
+    }
     if some_failed {
         Err(SimpleError::new("One or more discord event series syncs failed").into())
     } else {
-        Ok(())
+        Ok(api_budget.has_deferred())
     }
 }
 
-/*
-For each event series:
-  - create a channel if it doesn't exist yet
-  - store it in Redis
-  - create a player role if it doesn't exist yet
-  - store it in Redis
-  - create a host role if it doesn't exist yet
-  - store it in Redis
-  - adjust channel permission overwrites if necessary
-  - find all enrolled Meetup users
-  - map those Meetup users to Discord users if possible
-  - assign the users (including hosts) the player role
-  - assign the hosts the host role
-*/
-fn sync_event_series(
+// The series to sync in this run: all of them for a full sync, otherwise
+// the dirty ones and the ones that weren't synced for FULL_SYNC_INTERVAL_HOURS
+fn series_to_sync(
+    redis_connection: &mut crate::redis_namespace::Connection,
+    full_sync: bool,
+) -> Result<Vec<String>, crate::BoxedError> {
+    // The dirty set is cleared right away, such that series that are changed
+    // while this sync is running are synced again in the next run
+    let (all_series, dirty_series, sync_times): (
+        Vec<String>,
+        Vec<String>,
+        std::collections::HashMap<String, i64>,
+    ) = redis::pipe()
+        .atomic()
+        .smembers("event_series")
+        .smembers(REDIS_DIRTY_SERIES_KEY)
+        .del(REDIS_DIRTY_SERIES_KEY)
+        .ignore()
+        .hgetall(REDIS_SERIES_SYNC_TIMES_KEY)
+        .query(redis_connection)?;
+    // Forget about series that don't exist anymore
+    let removed_series: Vec<_> = sync_times
+        .keys()
+        .filter(|series_id| !all_series.contains(series_id))
+        .cloned()
+        .collect();
+    if !removed_series.is_empty() {
+        let _: () = redis_connection.hdel(REDIS_SERIES_SYNC_TIMES_KEY, removed_series)?;
+    }
+    if full_sync {
+        return Ok(all_series);
+    }
+    let num_series = all_series.len();
+    let series_to_sync = select_series_to_sync(
+        all_series,
+        &dirty_series,
+        &sync_times,
+        chrono::Utc::now().timestamp(),
+    );
+    println!(
+        "Discord sync: syncing {} of {} event series",
+        series_to_sync.len(),
+        num_series
+    );
+    Ok(series_to_sync)
+}
+
+// The series that a recurring sync picks up: the dirty ones and the ones that
+// weren't synced since FULL_SYNC_INTERVAL_HOURS before `now` (or never)
+fn select_series_to_sync(
+    all_series: Vec<String>,
+    dirty_series: &[String],
+    sync_times: &std::collections::HashMap<String, i64>,
+    now: i64,
+) -> Vec<String> {
+    let stale_time = now - FULL_SYNC_INTERVAL_HOURS * 60 * 60;
+    all_series
+        .into_iter()
+        .filter(|series_id| {
+            dirty_series.contains(series_id)
+                || sync_times
+                    .get(series_id)
+                    .map(|&sync_time| sync_time < stale_time)
+                    .unwrap_or(true)
+        })
+        .collect()
+}
+
+// Makes the next recurring sync pick up the series
+pub fn mark_series_dirty<C: redis::ConnectionLike>(
+    con: &mut C,
     series_id: &str,
-    redis_connection: &mut redis::Connection,
+) -> Result<(), crate::BoxedError> {
+    let _: () = con.sadd(REDIS_DIRTY_SERIES_KEY, series_id)?;
+    Ok(())
+}
+
+// For changes that affect every series, like guild-wide settings
+pub fn mark_all_series_dirty<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<(), crate::BoxedError> {
+    let _: () = con.sunionstore(
+        REDIS_DIRTY_SERIES_KEY,
+        &[REDIS_DIRTY_SERIES_KEY, "event_series"],
+    )?;
+    Ok(())
+}
+
+// For a Meetup user who just linked their account: the series where they
+// RSVP'd or host, such that they get the channel roles with the next
+// recurring sync
+pub fn mark_member_series_dirty<C: redis::ConnectionLike>(
+    con: &mut C,
+    meetup_id: u64,
+) -> Result<(), crate::BoxedError> {
+    let series_ids: Vec<String> = con.smembers("event_series")?;
+    if series_ids.is_empty() {
+        return Ok(());
+    }
+    let mut pipe = redis::pipe();
+    for series_id in &series_ids {
+        pipe.smembers(format!("event_series:{}:meetup_events", series_id));
+    }
+    let series_event_ids: Vec<Vec<String>> = pipe.query(con)?;
+    let mut pipe = redis::pipe();
+    let mut checked_series_ids = vec![];
+    for (series_id, event_ids) in series_ids.iter().zip(&series_event_ids) {
+        for event_id in event_ids {
+            pipe.sismember(format!("meetup_event:{}:meetup_users", event_id), meetup_id)
+                .sismember(format!("meetup_event:{}:meetup_hosts", event_id), meetup_id);
+            checked_series_ids.push(series_id);
+        }
+    }
+    if checked_series_ids.is_empty() {
+        return Ok(());
+    }
+    let is_member: Vec<bool> = pipe.query(con)?;
+    let member_series_ids: std::collections::HashSet<&String> = checked_series_ids
+        .into_iter()
+        .zip(is_member.chunks(2))
+        .filter(|(_, is_member)| is_member.iter().any(|&is_member| is_member))
+        .map(|(series_id, _)| series_id)
+        .collect();
+    for series_id in member_series_ids {
+        mark_series_dirty(con, series_id)?;
+    }
+    Ok(())
+}
+
+pub fn is_sync_paused<C: redis::ConnectionLike>(
+    con: &mut C,
+    series_id: &str,
+) -> Result<bool, crate::BoxedError> {
+    let redis_series_sync_paused_key = format!("event_series:{}:sync_paused", series_id);
+    let paused: Option<bool> = con.get(&redis_series_sync_paused_key)?;
+    Ok(paused.unwrap_or(false))
+}
+
+pub fn set_sync_paused<C: redis::ConnectionLike>(
+    con: &mut C,
+    series_id: &str,
+    paused: bool,
+) -> Result<(), crate::BoxedError> {
+    let redis_series_sync_paused_key = format!("event_series:{}:sync_paused", series_id);
+    if paused {
+        let _: () = con.set(&redis_series_sync_paused_key, true)?;
+    } else {
+        let _: () = con.del(&redis_series_sync_paused_key)?;
+        mark_series_dirty(con, series_id)?;
+    }
+    Ok(())
+}
+
+fn record_series_synced<C: redis::ConnectionLike>(
+    con: &mut C,
+    series_id: &str,
+) -> Result<(), crate::BoxedError> {
+    let _: () = con.hset(
+        REDIS_SERIES_SYNC_TIMES_KEY,
+        series_id,
+        chrono::Utc::now().timestamp(),
+    )?;
+    Ok(())
+}
+
+// Syncs a single series outside of the regular sync, e.g. for debugging
+pub fn sync_single_event_series(
+    series_id: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
     discord_api: &crate::discord_bot::CacheAndHttp,
     bot_id: u64,
 ) -> Result<(), crate::BoxedError> {
-    // Only sync event series that have events in the future
+    let mut creation_budget =
+        crate::discord_creation_budget::CreationBudget::new(redis_connection, discord_api)?;
+    let sync_result = sync_event_series(
+        series_id,
+        redis_connection,
+        discord_api,
+        bot_id,
+        &mut creation_budget,
+        &mut crate::discord_api_budget::ApiBudget::new(),
+        &mut crate::sync_stats::SyncProfile::new(),
+    );
+    creation_budget.finish(redis_connection, discord_api)?;
+    sync_result
+}
+
+// The upcoming events of the series sorted by date, and its past events
+pub fn series_events(
+    series_id: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<(Vec<Event>, Vec<Event>), crate::BoxedError> {
     let redis_series_events_key = format!("event_series:{}:meetup_events", &series_id);
     let event_ids: Vec<String> = redis_connection.smembers(&redis_series_events_key)?;
+    // Read all events in a single round trip
+    let mut pipe = redis::pipe();
+    for event_id in &event_ids {
+        pipe.hget(
+            format!("meetup_event:{}", event_id),
+            &["time", "name", "link", "is_online", "location"],
+        );
+    }
+    let event_fields: Vec<(
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<bool>,
+        Option<String>,
+    )> = if event_ids.is_empty() {
+        vec![]
+    } else {
+        pipe.query(redis_connection)?
+    };
     let events: Vec<_> = event_ids
         .into_iter()
-        .filter_map(|event_id| {
-            let redis_event_key = format!("meetup_event:{}", event_id);
-            let tuple: redis::RedisResult<(String, String, String)> =
-                redis_connection.hget(&redis_event_key, &["time", "name", "link"]);
-            match tuple {
-                Ok((time, name, link)) => match chrono::DateTime::parse_from_rfc3339(&time) {
+        .zip(event_fields)
+        .filter_map(|(event_id, fields)| match fields {
+            (Some(time), Some(name), Some(link), is_online, location) => {
+                match chrono::DateTime::parse_from_rfc3339(&time) {
                     Ok(time) => Some(Event {
                         id: event_id,
                         name: name,
                         time: time.with_timezone(&chrono::Utc),
                         link: link,
+                        location: crate::event_location::Location::from_redis_fields(
+                            is_online, location,
+                        ),
                     }),
                     Err(err) => {
                         eprintln!("Error parsing event time for event {}: {}", time, err);
                         None
                     }
-                },
-                Err(err) => {
-                    eprintln!("Redis error when querying event time: {}", err);
-                    None
                 }
             }
+            _ => {
+                eprintln!("Event {} is missing its time, name or link", event_id);
+                None
+            }
         })
         .collect();
-    // Filter past events
+    // Separate past events, which only count as completed sessions
     let now = chrono::Utc::now();
-    let mut upcoming: Vec<_> = events
-        .into_iter()
-        .filter(|event| event.time > now)
-        .collect();
+    let (mut upcoming, past): (Vec<_>, Vec<_>) =
+        events.into_iter().partition(|event| event.time > now);
     // Sort by date
     upcoming.sort_unstable_by_key(|event| event.time);
-    let next_event = match upcoming.first() {
-        Some(event) => event,
-        None => {
-            println!(
-                "Event series \"{}\" seems to have no upcoming events associated with it, not syncing to Discord",
-                series_id
-            );
-            return Ok(());
-        }
-    };
-    let event_name = &next_event.name;
-    // Step 0: Figure out the title of this event series
-    // Parse the series name from the event title
+    Ok((upcoming, past))
+}
+
+// Parses the series name (which is also the channel name) from the event title
+pub fn series_name(event_name: &str) -> Result<&str, crate::BoxedError> {
     let series_name = match EVENT_NAME_REGEX.captures(event_name) {
         Some(captures) => captures.name("name").unwrap().as_str(),
         None => {
@@ -171,76 +616,329 @@ fn sync_event_series(
         ))
         .into());
     }
-    // Step 1: Sync the channel
-    let channel_id = sync_channel(
-        series_name,
+    Ok(series_name)
+}
+
+/*
+For each event series:
+  - collect the tags of the series' events
+  - create a channel if it doesn't exist yet
+  - store it in Redis
+  - create a player role if it doesn't exist yet
+  - store it in Redis
+  - create a host role if it doesn't exist yet
+  - store it in Redis
+  - adjust channel permission overwrites if necessary
+  - find all enrolled Meetup users
+  - map those Meetup users to Discord users if possible
+  - assign the users (including hosts) the player role
+  - assign the hosts the host role
+  - create a discussion channel for each upcoming session, if enabled for the series
+  - count the completed sessions of campaigns and celebrate milestones
+  - keep a pinned message about the next session with the quick command shortcuts
+  - mirror the upcoming sessions as Discord scheduled events, if enabled for the guild
+  - create a voice channel if the series is played online, if enabled for the guild
+New channels and roles are only created as long as the creation budget allows
+it, otherwise the series is skipped until the next sync. The topic, the
+channel name and the style of the roles are only synced while the API budget
+allows cosmetic steps.
+*/
+fn sync_event_series(
+    series_id: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    bot_id: u64,
+    creation_budget: &mut crate::discord_creation_budget::CreationBudget,
+    api_budget: &mut crate::discord_api_budget::ApiBudget,
+    profile: &mut crate::sync_stats::SyncProfile,
+) -> Result<(), crate::BoxedError> {
+    profile.count_series();
+    // Only sync event series that have events in the future
+    let (upcoming, past) = series_events(series_id, redis_connection)?;
+    let next_event = match upcoming.first() {
+        Some(event) => event,
+        None => {
+            println!(
+                "Event series \"{}\" seems to have no upcoming events associated with it, not syncing to Discord",
+                series_id
+            );
+            return Ok(());
+        }
+    };
+    let event_name = &next_event.name;
+    // Collect the tags of the series' events (used in the topic, the
+    // community digest and for subscriptions)
+    let series_tags = crate::event_tags::update_series_tags(redis_connection, series_id)?;
+    // Step 0: Figure out the title of this event series
+    let series_name = series_name(event_name)?;
+    // New series might need an organizer's approval first
+    if !crate::discord_channel_approval::may_create_channel(
         series_id,
-        bot_id,
-        redis_connection,
-        discord_api,
-    )?;
-    // Step 2: Sync the channel's associated role
-    let channel_role_id = sync_role(
         series_name,
-        /*is_host_role*/ false,
-        channel_id,
+        &next_event.id,
+        next_event.time,
+        &next_event.link,
         redis_connection,
         discord_api,
-    )?;
-    // Step 3: Sync the channel's associated host role
-    let host_role_name = format!("[Host] {}", series_name);
-    let channel_host_role_id = sync_role(
-        &host_role_name,
-        /*is_host_role*/ true,
-        channel_id,
+    )? {
+        return Ok(());
+    }
+    // Step 1: Sync the channel
+    let step = profile.start_step();
+    let channel_id = match sync_channel(
+        series_name,
+        series_id,
+        bot_id,
         redis_connection,
         discord_api,
-    )?;
+        creation_budget,
+    )? {
+        Some(channel_id) => channel_id,
+        None => return Ok(()),
+    };
+    profile.finish_step("channel", step);
+    // Channels that grant access with member overwrites don't get roles (see
+    // discord_channel_access)
+    let uses_overwrites =
+        crate::discord_channel_access::sync_channel_mode(channel_id, redis_connection)?;
+    let step = profile.start_step();
+    let channel_roles = if uses_overwrites {
+        None
+    } else {
+        // Step 2: Sync the channel's associated role
+        let channel_role_id = match sync_role(
+            series_name,
+            /*is_host_role*/ false,
+            channel_id,
+            redis_connection,
+            discord_api,
+            creation_budget,
+            api_budget,
+        )? {
+            Some(role_id) => role_id,
+            None => return Ok(()),
+        };
+        // Step 3: Sync the channel's associated host role
+        let host_role_name = format!("[Host] {}", series_name);
+        let channel_host_role_id = match sync_role(
+            &host_role_name,
+            /*is_host_role*/ true,
+            channel_id,
+            redis_connection,
+            discord_api,
+            creation_budget,
+            api_budget,
+        )? {
+            Some(role_id) => role_id,
+            None => return Ok(()),
+        };
+        Some((channel_role_id, channel_host_role_id))
+    };
+    profile.finish_step("roles", step);
     // Step 4: Sync the channel permissions
+    let step = profile.start_step();
     sync_channel_permissions(
         channel_id,
-        channel_role_id,
-        channel_host_role_id,
+        channel_roles,
         bot_id,
+        redis_connection,
         discord_api,
     )?;
+    profile.finish_step("permissions", step);
     // Step 5: Sync RSVP'd users
-    sync_user_role_assignments(
+    let step = profile.start_step();
+    match channel_roles {
+        Some((channel_role_id, channel_host_role_id)) => {
+            sync_user_role_assignments(
+                series_id,
+                channel_id,
+                channel_role_id,
+                /*is_host_role*/ false,
+                redis_connection,
+                discord_api,
+            )?;
+            sync_user_role_assignments(
+                series_id,
+                channel_id,
+                channel_host_role_id,
+                /*is_host_role*/ true,
+                redis_connection,
+                discord_api,
+            )?;
+        }
+        None => crate::discord_channel_access::sync_overwrites(
+            series_id,
+            channel_id,
+            redis_connection,
+            discord_api,
+        )?,
+    }
+    // Step 6: Make sure that event hosts have the guild's game master role
+    sync_game_master_role(series_id, redis_connection, discord_api)?;
+    // Step 6a: Welcome the hosts of a new channel
+    crate::discord_host_orientation::send_pending(
         series_id,
         channel_id,
-        channel_role_id,
-        /*is_host_role*/ false,
+        bot_id,
         redis_connection,
         discord_api,
     )?;
-    sync_user_role_assignments(
-        series_id,
+    profile.finish_step("role_assignments", step);
+    // Step 7: Count the completed sessions, which are shown in the topic and
+    // the session message of campaigns
+    let step = profile.start_step();
+    let past_event_ids: Vec<&str> = past.iter().map(|event| event.id.as_str()).collect();
+    let (num_sessions_before, num_sessions_after) =
+        crate::discord_session_counter::record_completed_sessions(
+            series_id,
+            &past_event_ids,
+            redis_connection,
+        )?;
+    let session_number =
+        crate::discord_session_counter::next_session_number(series_id, redis_connection)?;
+    if session_number.is_some() {
+        crate::discord_session_counter::announce_milestone(
+            channel_id,
+            num_sessions_before,
+            num_sessions_after,
+            discord_api,
+        )?;
+    }
+    // Step 7a: Keep the channel's topic and the emoji that the hosts picked
+    // in front of the channel name up-to-date
+    if api_budget.allows_low_priority() {
+        sync_channel_topic_and_category(
+            series_id,
+            channel_id,
+            &next_event,
+            session_number,
+            &series_tags,
+            redis_connection,
+            discord_api,
+        )?;
+        if !crate::discord_channel_style::sync_channel_name(
+            channel_id,
+            redis_connection,
+            discord_api,
+        )? {
+            mark_series_dirty(redis_connection, series_id)?;
+        }
+    }
+    // Step 7b: Keep the pinned session message up-to-date
+    crate::discord_quick_commands::sync_session_message(
         channel_id,
-        channel_host_role_id,
-        /*is_host_role*/ true,
+        session_number,
+        &next_event.name,
+        next_event.time,
+        &next_event.link,
+        &next_event.location,
         redis_connection,
         discord_api,
     )?;
-    // Step 6: Make sure that event hosts have the guild's game master role
-    sync_game_master_role(series_id, redis_connection, discord_api)?;
-    // Step 7: Keep the channel's topic up-to-date
-    sync_channel_topic_and_category(
-        series_id,
-        channel_id,
-        &next_event,
+    profile.finish_step("topic", step);
+    // Step 8: Create a discussion channel for each upcoming session (if
+    // enabled). Session channels inherit the roles, so channels without roles
+    // don't get any.
+    let session_channel_roles = match channel_roles {
+        Some(roles)
+            if crate::discord_session_channels::is_enabled(redis_connection, series_id)? =>
+        {
+            Some(roles)
+        }
+        _ => None,
+    };
+    if let Some((channel_role_id, channel_host_role_id)) = session_channel_roles {
+        let step = profile.start_step();
+        for event in &upcoming {
+            crate::discord_session_channels::sync_session_channel(
+                &event.id,
+                &event.name,
+                event.time,
+                &event.link,
+                &event.location,
+                channel_id,
+                channel_role_id,
+                channel_host_role_id,
+                bot_id,
+                redis_connection,
+                discord_api,
+                creation_budget,
+            )?;
+        }
+        profile.finish_step("session_channels", step);
+    }
+    // Step 9: Mirror the upcoming sessions as Discord scheduled events (if enabled)
+    if crate::feature_flags::is_enabled(
         redis_connection,
-        discord_api,
-    )?;
+        GUILD_ID,
+        crate::feature_flags::Feature::ScheduledEvents,
+    )? {
+        let step = profile.start_step();
+        for event in &upcoming {
+            let is_allowed = crate::discord_scheduled_events::sync_scheduled_event(
+                &event.id,
+                &event.name,
+                event.time,
+                &event.link,
+                &event.location,
+                redis_connection,
+                discord_api,
+            )?;
+            // Most likely the bot lacks the "Manage Events" permission. The
+            // rest of the series can still be synced.
+            if !is_allowed {
+                eprintln!(
+                    "Discord event sync: not allowed to mirror the sessions of channel {} as \
+                     scheduled events",
+                    channel_id.0
+                );
+                break;
+            }
+        }
+        profile.finish_step("scheduled_events", step);
+    }
+    // Step 10: Give online series a voice channel (if enabled). Voice channels
+    // are built on the roles as well.
+    let voice_channel_roles = match channel_roles {
+        Some(roles)
+            if crate::feature_flags::is_enabled(
+                redis_connection,
+                GUILD_ID,
+                crate::feature_flags::Feature::VoiceChannels,
+            )? =>
+        {
+            Some(roles)
+        }
+        _ => None,
+    };
+    if let Some((channel_role_id, channel_host_role_id)) = voice_channel_roles {
+        let step = profile.start_step();
+        crate::discord_voice_channels::sync_voice_channel(
+            channel_id,
+            channel_role_id,
+            channel_host_role_id,
+            next_event.location.is_online(),
+            bot_id,
+            redis_connection,
+            discord_api,
+            creation_budget,
+        )?;
+        profile.finish_step("voice_channel", step);
+    }
     Ok(())
 }
 
+// Returns None if the role does not exist yet and the creation budget does not
+// allow creating it
 fn sync_role(
     role_name: &str,
     is_host_role: bool,
     channel_id: ChannelId,
-    redis_connection: &mut redis::Connection,
+    redis_connection: &mut crate::redis_namespace::Connection,
     discord_api: &crate::discord_bot::CacheAndHttp,
-) -> Result<RoleId, crate::BoxedError> {
+    creation_budget: &mut crate::discord_creation_budget::CreationBudget,
+    api_budget: &mut crate::discord_api_budget::ApiBudget,
+) -> Result<Option<RoleId>, crate::BoxedError> {
     let max_retries = 1;
     let mut current_num_try = 0;
     loop {
@@ -248,28 +946,19 @@ fn sync_role(
             return Err(SimpleError::new("Role sync failed, max retries reached").into());
         }
         current_num_try += 1;
-        let role = sync_role_impl(
+        let role = match sync_role_impl(
             role_name,
             is_host_role,
             channel_id,
             redis_connection,
             discord_api,
-        )?;
-        // Make sure that the role ID that was returned actually exists on Discord
-        // First, check the cache
-        let role_exists = match GUILD_ID.to_guild_cached(&discord_api.cache) {
-            Some(guild) => guild.read().roles.contains_key(&role),
-            None => false,
-        };
-        // If it was not in the cache, check Discord
-        let role_exists = if role_exists {
-            true
-        } else {
-            let guild_roles = discord_api.http().get_guild_roles(GUILD_ID.0)?;
-            guild_roles
-                .iter()
-                .any(|guild_role| guild_role.id.0 == role.0)
+            creation_budget,
+        )? {
+            Some(role) => role,
+            None => return Ok(None),
         };
+        // Make sure that the role ID that was returned actually exists on Discord
+        let role_exists = crate::discord_state_cache::role_exists(role, discord_api)?;
         if !role_exists {
             // This role does not exist on Discord
             // Delete it from Redis and retry
@@ -304,8 +993,12 @@ fn sync_role(
             })?;
             continue;
         } else {
-            // The role exists on Discord, so everything is good
-            return Ok(role);
+            // The role exists on Discord, so make sure it looks right
+            if api_budget.allows_low_priority() {
+                let style = role_style(is_host_role, channel_id, redis_connection)?;
+                sync_role_style(role, &style, /*keep_above_anchor*/ true, discord_api)?;
+            }
+            return Ok(Some(role));
         }
     }
 }
@@ -314,9 +1007,10 @@ fn sync_role_impl(
     role_name: &str,
     is_host_role: bool,
     channel_id: ChannelId,
-    redis_connection: &mut redis::Connection,
+    redis_connection: &mut crate::redis_namespace::Connection,
     discord_api: &crate::discord_bot::CacheAndHttp,
-) -> Result<RoleId, crate::BoxedError> {
+    creation_budget: &mut crate::discord_creation_budget::CreationBudget,
+) -> Result<Option<RoleId>, crate::BoxedError> {
     let redis_channel_role_key = if is_host_role {
         format!("discord_channel:{}:discord_host_role", channel_id.0)
     } else {
@@ -327,19 +1021,33 @@ fn sync_role_impl(
         let channel_role: Option<u64> = redis_connection.get(&redis_channel_role_key)?;
         if let Some(channel_role) = channel_role {
             // The role already exists
-            return Ok(RoleId(channel_role));
+            return Ok(Some(RoleId(channel_role)));
         }
     }
     // The role doesn't exist yet -> try to create it
+    if !creation_budget.try_spend_role() {
+        println!(
+            "Discord event sync: not creating role \"{}\", the creation budget is exhausted",
+            role_name
+        );
+        return Ok(None);
+    }
+    let style = role_style(is_host_role, channel_id, redis_connection)?;
     let temp_channel_role = GUILD_ID.create_role(discord_api.http(), |role_builder| {
         role_builder
             .name(role_name)
             .permissions(Permissions::empty())
+            .hoist(style.hoist);
+        if let Some(colour) = style.colour {
+            role_builder.colour(colour);
+        }
+        role_builder
     })?;
     println!(
         "Discord event sync: created new temporary channel role {} \"{}\"",
         temp_channel_role.id.0, &temp_channel_role.name
     );
+    crate::discord_state_cache::upsert_role(&temp_channel_role);
     let redis_discord_roles_key = if is_host_role {
         "discord_host_roles"
     } else {
@@ -383,7 +1091,10 @@ fn sync_role_impl(
             .http()
             .delete_role(GUILD_ID.0, temp_channel_role.id.0)
         {
-            Ok(_) => println!("Successfully deleted temporary channel role"),
+            Ok(_) => {
+                crate::discord_state_cache::remove_role(temp_channel_role.id);
+                println!("Successfully deleted temporary channel role")
+            }
             Err(_) => {
                 eprintln!(
                     "Could not delete temporary channel role {}",
@@ -405,17 +1116,96 @@ fn sync_role_impl(
     // Return the channel role we got from Redis, no matter
     // if it was newly created or already existing
     channel_role
-        .map(|id| RoleId(id.0))
+        .map(|id| Some(RoleId(id.0)))
         .map_err(|err| err.into())
 }
 
+// Hosts can pick the colour of their channel role, see discord_channel_style
+fn role_style(
+    is_host_role: bool,
+    channel_id: ChannelId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<RoleStyle, crate::BoxedError> {
+    if is_host_role {
+        return Ok(RoleStyle {
+            colour: HOST_ROLE_STYLE.colour,
+            hoist: HOST_ROLE_STYLE.hoist,
+        });
+    }
+    let channel_style = crate::discord_channel_style::style(redis_connection, channel_id)?;
+    Ok(RoleStyle {
+        colour: channel_style.colour.or(CHANNEL_ROLE_STYLE.colour),
+        hoist: CHANNEL_ROLE_STYLE.hoist,
+    })
+}
+
+// Corrects the colour and hoisting of a role and, if requested, moves it
+// right above the anchor role in case it ended up below it
+fn sync_role_style(
+    role_id: RoleId,
+    style: &RoleStyle,
+    keep_above_anchor: bool,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let guild_roles: Vec<Role> = crate::discord_state_cache::roles(discord_api)?;
+    let role = match guild_roles.iter().find(|role| role.id == role_id) {
+        Some(role) => role,
+        None => return Err(SimpleError::new(format!("Role {} not found", role_id.0)).into()),
+    };
+    let colour_matches = match style.colour {
+        Some(colour) => role.colour.0 as u64 == colour,
+        None => true,
+    };
+    if !colour_matches || role.hoist != style.hoist {
+        let edited_role = GUILD_ID.edit_role(discord_api.http(), role_id, |role_builder| {
+            role_builder.hoist(style.hoist);
+            if let Some(colour) = style.colour {
+                role_builder.colour(colour);
+            }
+            role_builder
+        })?;
+        crate::discord_state_cache::upsert_role(&edited_role);
+        println!(
+            "Discord event sync: corrected the style of role {}",
+            role_id.0
+        );
+    }
+    if let (true, Some(anchor_id)) = (keep_above_anchor, ROLE_ANCHOR_ID) {
+        let anchor_position = match guild_roles.iter().find(|role| role.id == anchor_id) {
+            Some(anchor) => anchor.position,
+            None => {
+                return Err(
+                    SimpleError::new(format!("Anchor role {} not found", anchor_id.0)).into(),
+                )
+            }
+        };
+        if role.position <= anchor_position {
+            GUILD_ID.edit_role_position(
+                discord_api.http(),
+                role_id,
+                (anchor_position + 1) as u64,
+            )?;
+            // Moving a role shifts the positions of other roles as well
+            crate::discord_state_cache::invalidate();
+            println!(
+                "Discord event sync: moved role {} above the anchor role",
+                role_id.0
+            );
+        }
+    }
+    Ok(())
+}
+
+// Returns None if the channel does not exist yet and the creation budget does
+// not allow creating it
 fn sync_channel(
     channel_name: &str,
     event_series_id: &str,
     bot_id: u64,
-    redis_connection: &mut redis::Connection,
+    redis_connection: &mut crate::redis_namespace::Connection,
     discord_api: &crate::discord_bot::CacheAndHttp,
-) -> Result<ChannelId, crate::BoxedError> {
+    creation_budget: &mut crate::discord_creation_budget::CreationBudget,
+) -> Result<Option<ChannelId>, crate::BoxedError> {
     let max_retries = 1;
     let mut current_num_try = 0;
     loop {
@@ -423,34 +1213,19 @@ fn sync_channel(
             return Err(SimpleError::new("Channel sync failed, max retries reached").into());
         }
         current_num_try += 1;
-        let channel = sync_channel_impl(
+        let channel = match sync_channel_impl(
             channel_name,
             event_series_id,
             bot_id,
             redis_connection,
             discord_api,
-        )?;
-        // Make sure that the channel ID that was returned actually exists on Discord
-        let channel_exists = match channel.to_channel(discord_api) {
-            Ok(_) => true,
-            Err(err) => {
-                if let serenity::Error::Http(http_err) = &err {
-                    if let serenity::http::HttpError::UnsuccessfulRequest(response) =
-                        http_err.as_ref()
-                    {
-                        if response.status_code == reqwest::StatusCode::NOT_FOUND {
-                            false
-                        } else {
-                            return Err(err.into());
-                        }
-                    } else {
-                        return Err(err.into());
-                    }
-                } else {
-                    return Err(err.into());
-                }
-            }
+            creation_budget,
+        )? {
+            Some(channel) => channel,
+            None => return Ok(None),
         };
+        // Make sure that the channel ID that was returned actually exists on Discord
+        let channel_exists = crate::discord_state_cache::channel_exists(channel, discord_api)?;
         if !channel_exists {
             // This channel does not exist on Discord
             // Delete it from Redis and retry
@@ -479,7 +1254,7 @@ fn sync_channel(
             continue;
         } else {
             // The channel exists on Discord, so everything is good
-            return Ok(channel);
+            return Ok(Some(channel));
         }
     }
 }
@@ -488,19 +1263,27 @@ fn sync_channel_impl(
     channel_name: &str,
     event_series_id: &str,
     bot_id: u64,
-    redis_connection: &mut redis::Connection,
+    redis_connection: &mut crate::redis_namespace::Connection,
     discord_api: &crate::discord_bot::CacheAndHttp,
-) -> Result<ChannelId, crate::BoxedError> {
+    creation_budget: &mut crate::discord_creation_budget::CreationBudget,
+) -> Result<Option<ChannelId>, crate::BoxedError> {
     let redis_series_channel_key = format!("event_series:{}:discord_channel", event_series_id);
     // Check if the channel already exists
     {
         let channel: Option<u64> = redis_connection.get(&redis_series_channel_key)?;
         if let Some(channel) = channel {
             // The channel already exists
-            return Ok(ChannelId(channel));
+            return Ok(Some(ChannelId(channel)));
         }
     }
     // The channel doesn't exist yet -> try to create it
+    if !creation_budget.try_spend_channel() {
+        println!(
+            "Discord event sync: not creating channel \"{}\", the creation budget is exhausted",
+            channel_name
+        );
+        return Ok(None);
+    }
     // The @everyone role has the same id as the guild
     let role_everyone_id = RoleId(GUILD_ID.0);
     let permission_overwrites = vec![
@@ -524,6 +1307,7 @@ fn sync_channel_impl(
         "Discord event sync: created new temporary channel {} \"{}\"",
         temp_channel.id.0, &temp_channel.name
     );
+    crate::discord_state_cache::insert_channel(temp_channel.id);
     let redis_discord_channels_key = "discord_channels";
     let redis_channel_series_key = format!("discord_channel:{}:event_series", temp_channel.id.0);
     let channel: redis::RedisResult<(u64,)> = redis::transaction(
@@ -536,6 +1320,7 @@ fn sync_channel_impl(
                 pipe.get(&redis_series_channel_key).query(con)
             } else {
                 // Persist the new channel to Redis
+                crate::discord_host_orientation::mark_pending(pipe, temp_channel.id);
                 pipe.sadd(redis_discord_channels_key, temp_channel.id.0)
                     .ignore()
                     .set(&redis_series_channel_key, temp_channel.id.0)
@@ -556,7 +1341,10 @@ fn sync_channel_impl(
     if delete_temp_channel {
         println!("Trying to delete temporary channel");
         match discord_api.http().delete_channel(temp_channel.id.0) {
-            Ok(_) => println!("Successfully deleted temporary channel"),
+            Ok(_) => {
+                crate::discord_state_cache::remove_channel(temp_channel.id);
+                println!("Successfully deleted temporary channel")
+            }
             Err(_) => {
                 eprintln!("Could not delete temporary channel {}", temp_channel.id.0);
                 // Try to persist the information to Redis that we have an orphaned channel now
@@ -568,21 +1356,43 @@ fn sync_channel_impl(
         }
     } else {
         println!("Persisted new channel {}", temp_channel.id.0);
+        crate::event_webhooks::emit(crate::event_webhooks::Event::ChannelCreated {
+            series_id: event_series_id.to_string(),
+            channel_id: temp_channel.id.0,
+        });
     }
     // Return the channel we got from Redis, no matter
     // if it was newly created or already existing
-    channel.map(|id| ChannelId(id.0)).map_err(|err| err.into())
+    channel
+        .map(|id| Some(ChannelId(id.0)))
+        .map_err(|err| err.into())
+}
+
+// Co-hosts help running a channel (e.g. pinning and removing messages) but
+// are not hosts, so they can't make end-of-game decisions. Instead of a third
+// role, they get a member specific permission overwrite in the channel.
+// Meetup does not distinguish between hosts and co-hosts of an event, so
+// co-hosts are only ever assigned manually.
+pub fn cohost_permission_overwrite(user_id: UserId) -> PermissionOverwrite {
+    PermissionOverwrite {
+        allow: Permissions::READ_MESSAGES
+            | Permissions::MENTION_EVERYONE
+            | Permissions::MANAGE_MESSAGES,
+        deny: Permissions::empty(),
+        kind: PermissionOverwriteType::Member(user_id),
+    }
 }
 
 // Makes sure that the Discord channel has the appropriate permission
-// overwrites for the channel's role and host role.
+// overwrites for the channel's role, host role, co-hosts and removed players.
 // Specifically does not remove any additional permission overwrites
 // that the channel might have.
 fn sync_channel_permissions(
     channel_id: ChannelId,
-    role_id: RoleId,
-    host_role_id: RoleId,
+    // The player and host role, None for channels that use member overwrites
+    roles: Option<(RoleId, RoleId)>,
     bot_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
     discord_api: &crate::discord_bot::CacheAndHttp,
 ) -> Result<(), crate::BoxedError> {
     // The @everyone role has the same id as the guild
@@ -591,7 +1401,7 @@ fn sync_channel_permissions(
     // This is achieved by denying @everyone the READ_MESSAGES permission
     // but allowing the now role the READ_MESSAGES permission.
     // see: https://support.discordapp.com/hc/en-us/articles/206143877-How-do-I-set-up-a-Role-Exclusive-channel-
-    let permission_overwrites = [
+    let mut permission_overwrites = vec![
         PermissionOverwrite {
             allow: Permissions::empty(),
             deny: Permissions::READ_MESSAGES,
@@ -602,88 +1412,165 @@ fn sync_channel_permissions(
             deny: Permissions::empty(),
             kind: PermissionOverwriteType::Member(UserId(bot_id)),
         },
-        PermissionOverwrite {
+    ];
+    if let Some((role_id, host_role_id)) = roles {
+        permission_overwrites.push(PermissionOverwrite {
             allow: Permissions::READ_MESSAGES | Permissions::MENTION_EVERYONE,
             deny: Permissions::empty(),
             kind: PermissionOverwriteType::Role(role_id),
-        },
-        PermissionOverwrite {
+        });
+        permission_overwrites.push(PermissionOverwrite {
             allow: Permissions::READ_MESSAGES
                 | Permissions::MENTION_EVERYONE
                 | Permissions::MANAGE_MESSAGES,
             deny: Permissions::empty(),
             kind: PermissionOverwriteType::Role(host_role_id),
-        },
-    ];
+        });
+    }
+    // The players of an archived channel might have their own overwrites
+    // instead of the roles, which would keep them from sending messages
+    crate::discord_role_limit::unprune_channel(channel_id, redis_connection, discord_api)?;
     for permission_overwrite in &permission_overwrites {
         channel_id.create_permission(discord_api.http(), permission_overwrite)?;
     }
+    let redis_channel_cohosts_key = format!("discord_channel:{}:cohosts", channel_id.0);
+    let cohost_ids: Vec<u64> = redis_connection.smembers(&redis_channel_cohosts_key)?;
+    for &cohost_id in &cohost_ids {
+        channel_id.create_permission(
+            discord_api.http(),
+            &cohost_permission_overwrite(UserId(cohost_id)),
+        )?;
+    }
+    // A channel that was archived but got a new session is open again, since
+    // the overwrites above allow sending messages
+    let redis_channel_archived_key = format!("discord_channel:{}:archived", channel_id.0);
+    let _: () = redis_connection.del(&redis_channel_archived_key)?;
+    // Players who were removed might not be allowed to read the history
+    crate::discord_history_privacy::sync_history_overwrites(
+        channel_id,
+        &cohost_ids,
+        redis_connection,
+        discord_api,
+    )?;
     Ok(())
 }
 
-fn sync_user_role_assignments(
+// The Discord users who should have the channel's (host) role according to
+// the RSVPs on Meetup (including the households that share an RSVP'd Meetup
+// account, and the users that the event source resolved to Discord users
+// directly), minus the ones who were removed manually or are
+// quarantined. Takes three round trips to Redis, no matter how many users
+// there are. (A Lua script could do it in one, but it would have to build the
+// key names itself, bypassing the key namespace.)
+pub fn role_assignment_candidates<C: redis::ConnectionLike>(
     event_series_id: &str,
     channel: ChannelId,
-    role: RoleId,
     is_host_role: bool,
-    redis_connection: &mut redis::Connection,
-    discord_api: &crate::discord_bot::CacheAndHttp,
-) -> Result<(), crate::BoxedError> {
+    con: &mut C,
+) -> Result<Vec<u64>, crate::BoxedError> {
     // First, find all events belonging to this event series
     let redis_series_events_key = format!("event_series:{}:meetup_events", &event_series_id);
-    let event_ids: Vec<String> = redis_connection.smembers(&redis_series_events_key)?;
+    let event_ids: Vec<String> = con.smembers(&redis_series_events_key)?;
     if event_ids.is_empty() {
         println!(
             "Event series \"{}\" seems to have no events associated with it, not syncing to Discord",
             event_series_id
         );
-        return Ok(());
+        return Ok(vec![]);
     }
-    // Then, find all Meetup users RSVP'd to those events
-    let redis_event_users_keys: Vec<_> = event_ids
-        .iter()
-        .map(|event_id| {
-            if is_host_role {
-                format!("meetup_event:{}:meetup_hosts", event_id)
-            } else {
-                format!("meetup_event:{}:meetup_users", event_id)
-            }
-        })
-        .collect();
-    let (meetup_user_ids,): (Vec<u64>,) = redis::pipe()
-        .sunion(redis_event_users_keys)
-        .query(redis_connection)?;
-    // Now, try to associate the RSVP'd Meetup users with Discord users
-    let discord_user_ids: Result<Vec<Option<u64>>, _> = meetup_user_ids
-        .into_iter()
-        .map(|meetup_id| {
-            let redis_meetup_discord_key = format!("meetup_user:{}:discord_user", meetup_id);
-            redis_connection.get(&redis_meetup_discord_key)
-        })
-        .collect();
-    // Filter the None values
-    let discord_user_ids: Vec<_> = discord_user_ids?.into_iter().filter_map(|id| id).collect();
-    // Check whether any users have manually removed roles and don't add them back
+    // Then, find all Meetup users RSVP'd to those events and the users who
+    // should not get the role
+    let event_users_keys = |user_ids: crate::event_source::UserIds| -> Vec<String> {
+        event_ids
+            .iter()
+            .map(|event_id| {
+                if is_host_role {
+                    user_ids.event_hosts_key(event_id)
+                } else {
+                    user_ids.event_users_key(event_id)
+                }
+            })
+            .collect()
+    };
+    let redis_event_users_keys = event_users_keys(crate::event_source::UserIds::Meetup);
+    let redis_event_discord_users_keys = event_users_keys(crate::event_source::UserIds::Discord);
     let redis_channel_removed_hosts_key = format!("discord_channel:{}:removed_hosts", channel.0);
     let redis_channel_removed_users_key = format!("discord_channel:{}:removed_users", channel.0);
-    let ignore_discord_user_ids: Vec<u64> = if is_host_role {
+    let mut redis_ignored_users_keys = if is_host_role {
         // Don't automatically assign the host role to users that have either
         // been manually removed as a host or as a user from a channel
-        redis_connection.sunion(&[
-            &redis_channel_removed_hosts_key,
-            &redis_channel_removed_users_key,
-        ])?
+        vec![
+            redis_channel_removed_hosts_key,
+            redis_channel_removed_users_key,
+        ]
     } else {
         // Don't automatically assign the user role to user that have been
         // manually removed from a channel
-        redis_connection.smembers(&redis_channel_removed_users_key)?
+        vec![redis_channel_removed_users_key]
     };
-    // Lastly, actually assign the role to the Discord users
+    // Quarantined users don't get any channel roles
+    redis_ignored_users_keys.push(crate::quarantine::QUARANTINED_USERS_KEY.to_string());
+    // Neither do the users on the ignore list
+    redis_ignored_users_keys.push(crate::ignore_list::IGNORED_USERS_KEY.to_string());
+    let (meetup_user_ids, direct_discord_user_ids, ignore_discord_user_ids): (
+        Vec<u64>,
+        Vec<u64>,
+        Vec<u64>,
+    ) = redis::pipe()
+        .sunion(redis_event_users_keys)
+        .sunion(redis_event_discord_users_keys)
+        .sunion(redis_ignored_users_keys)
+        .query(con)?;
+    if meetup_user_ids.is_empty() {
+        return Ok(direct_discord_user_ids
+            .into_iter()
+            .filter(|id| !ignore_discord_user_ids.contains(id))
+            .collect());
+    }
+    // Now, try to associate the RSVP'd Meetup users with Discord users
+    let redis_meetup_discord_keys: Vec<_> = meetup_user_ids
+        .iter()
+        .map(|&meetup_id| crate::identity::redis_meetup_discord_user_key(meetup_id))
+        .collect();
+    // Households share a Meetup account, all of their members get the roles
+    let redis_household_members_keys: Vec<_> = meetup_user_ids
+        .iter()
+        .map(|&meetup_id| crate::households::redis_household_members_key(meetup_id))
+        .collect();
+    // Make sure that this is an MGET even if there is only a single key
+    let (discord_user_ids, household_member_ids): (Vec<Option<u64>>, Vec<u64>) = redis::pipe()
+        .cmd("MGET")
+        .arg(redis_meetup_discord_keys)
+        .sunion(redis_household_members_keys)
+        .query(con)?;
+    let mut discord_user_ids: Vec<u64> = discord_user_ids
+        .into_iter()
+        .filter_map(|id| id)
+        .chain(household_member_ids)
+        .chain(direct_discord_user_ids)
+        .filter(|id| !ignore_discord_user_ids.contains(id))
+        .collect();
+    discord_user_ids.sort_unstable();
+    discord_user_ids.dedup();
+    Ok(discord_user_ids)
+}
+
+fn sync_user_role_assignments(
+    event_series_id: &str,
+    channel: ChannelId,
+    role: RoleId,
+    is_host_role: bool,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let discord_user_ids =
+        role_assignment_candidates(event_series_id, channel, is_host_role, redis_connection)?;
+    // Actually assign the role to the Discord users
     for user_id in discord_user_ids {
-        if ignore_discord_user_ids.contains(&user_id) {
-            continue;
-        }
         match UserId(user_id).to_user(discord_api) {
+            // Other bots might be linked to a Meetup account, but they are
+            // always ignored
+            Ok(ref user) if user.bot => continue,
             Ok(user) => match user.has_role(discord_api, GUILD_ID, role) {
                 Ok(has_role) => {
                     if !has_role {
@@ -691,11 +1578,47 @@ fn sync_user_role_assignments(
                             .http()
                             .add_member_role(GUILD_ID.0, user_id, role.0)
                         {
-                            Ok(_) => println!("Assigned user {} to role {}", user_id, role.0),
-                            Err(err) => eprintln!(
-                                "Could not assign user {} to role {}: {}",
-                                user_id, role.0, err
-                            ),
+                            Ok(_) => {
+                                println!("Assigned user {} to role {}", user_id, role.0);
+                                if let Err(err) = crate::discord_role_retries::assignment_succeeded(
+                                    user_id,
+                                    role,
+                                    redis_connection,
+                                ) {
+                                    eprintln!("Could not clear the role assignment retry: {}", err);
+                                }
+                                // Let new players know about the safety tools of the game
+                                if !is_host_role {
+                                    if let Err(err) = crate::discord_safety::send_safety_note(
+                                        channel,
+                                        user_id,
+                                        redis_connection,
+                                        discord_api,
+                                    ) {
+                                        eprintln!(
+                                            "Could not send the safety note to user {}: {}",
+                                            user_id, err
+                                        );
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                eprintln!(
+                                    "Could not assign user {} to role {}: {}",
+                                    user_id, role.0, err
+                                );
+                                if let Err(err) =
+                                    crate::discord_role_retries::queue_failed_assignment(
+                                        user_id,
+                                        role,
+                                        &err.to_string(),
+                                        redis_connection,
+                                        discord_api,
+                                    )
+                                {
+                                    eprintln!("Could not queue the role assignment retry: {}", err);
+                                }
+                            }
                         }
                     }
                 }
@@ -710,45 +1633,92 @@ fn sync_user_role_assignments(
     Ok(())
 }
 
+// The linked Discord users that host any event of the series, minus the
+// quarantined ones
+pub fn game_master_candidates(
+    event_series_id: &str,
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<Vec<u64>, crate::BoxedError> {
+    // First, find all events belonging to this event series
+    let redis_series_events_key = format!("event_series:{}:meetup_events", &event_series_id);
+    let event_ids: Vec<String> = redis_connection.smembers(&redis_series_events_key)?;
+    if event_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    // Then, find all Meetup host of those events
+    let redis_event_hosts_keys: Vec<_> = event_ids
+        .iter()
+        .map(|event_id| format!("meetup_event:{}:meetup_hosts", event_id))
+        .collect();
+    let (meetup_host_ids,): (Vec<u64>,) = redis::pipe()
+        .sunion(redis_event_hosts_keys)
+        .query(redis_connection)?;
+    if meetup_host_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    // Now, try to associate the hosts with Discord users
+    let redis_meetup_host_discord_keys: Vec<_> = meetup_host_ids
+        .into_iter()
+        .map(crate::identity::redis_meetup_discord_user_key)
+        .collect();
+    let discord_host_ids: Vec<Option<u64>> = redis::cmd("MGET")
+        .arg(redis_meetup_host_discord_keys)
+        .query(redis_connection)?;
+    let quarantined_user_ids = crate::quarantine::quarantined_users(redis_connection)?;
+    Ok(discord_host_ids
+        .into_iter()
+        .filter_map(|id| id)
+        .filter(|id| !quarantined_user_ids.contains(id))
+        .collect())
+}
+
 fn sync_game_master_role(
     event_series_id: &str,
-    redis_connection: &mut redis::Connection,
+    redis_connection: &mut crate::redis_namespace::Connection,
     discord_api: &crate::discord_bot::CacheAndHttp,
 ) -> Result<(), crate::BoxedError> {
-    if let Some(game_master_role) = GAME_MASTER_ID {
-        // First, find all events belonging to this event series
-        let redis_series_events_key = format!("event_series:{}:meetup_events", &event_series_id);
-        let event_ids: Vec<String> = redis_connection.smembers(&redis_series_events_key)?;
-        if event_ids.is_empty() {
-            return Ok(());
-        }
-        // Then, find all Meetup host of those events
-        let redis_event_hosts_keys: Vec<_> = event_ids
-            .iter()
-            .map(|event_id| format!("meetup_event:{}:meetup_hosts", event_id))
-            .collect();
-        let (meetup_host_ids,): (Vec<u64>,) = redis::pipe()
-            .sunion(redis_event_hosts_keys)
-            .query(redis_connection)?;
-        // Now, try to associate the hosts with Discord users
-        let redis_meetup_host_discord_keys: Vec<_> = meetup_host_ids
-            .into_iter()
-            .map(|meetup_id| format!("meetup_user:{}:discord_user", meetup_id))
-            .collect();
-        let discord_host_ids: Vec<Option<u64>> = redis::cmd("MGET")
-            .arg(redis_meetup_host_discord_keys)
-            .query(redis_connection)?;
-        // Filter the None values
-        let discord_host_ids: Vec<_> = discord_host_ids.into_iter().filter_map(|id| id).collect();
-        // Lastly, actually assign the Game Master role to the hosts
+    if !crate::feature_flags::is_enabled(
+        redis_connection,
+        GUILD_ID,
+        crate::feature_flags::Feature::GameMasterRole,
+    )? {
+        return Ok(());
+    }
+    if let Some(game_master_role) = crate::guild_config::game_master_role(redis_connection)? {
+        let discord_host_ids = game_master_candidates(event_series_id, redis_connection)?;
+        // Actually assign the Game Master role to the hosts
         for host_id in discord_host_ids {
             match UserId(host_id).to_user(discord_api) {
                 Ok(user) => match user.has_role(discord_api, GUILD_ID, game_master_role) {
                     Ok(has_role) => {
                         if !has_role {
                             match discord_api.http().add_member_role(GUILD_ID.0, host_id, game_master_role.0) {
-                                Ok(_) => println!("Assigned user {} to the game master role", host_id),
-                                Err(err) => eprintln!("Could not assign user {} to the game master role: {}", host_id, err),
+                                Ok(_) => {
+                                    println!("Assigned user {} to the game master role", host_id);
+                                    if let Err(err) =
+                                        crate::discord_role_retries::assignment_succeeded(
+                                            host_id,
+                                            game_master_role,
+                                            redis_connection,
+                                        )
+                                    {
+                                        eprintln!("Could not clear the role assignment retry: {}", err);
+                                    }
+                                }
+                                Err(err) => {
+                                    eprintln!("Could not assign user {} to the game master role: {}", host_id, err);
+                                    if let Err(err) =
+                                        crate::discord_role_retries::queue_failed_assignment(
+                                            host_id,
+                                            game_master_role,
+                                            &err.to_string(),
+                                            redis_connection,
+                                            discord_api,
+                                        )
+                                    {
+                                        eprintln!("Could not queue the role assignment retry: {}", err);
+                                    }
+                                }
                             }
                         }
                     }
@@ -764,28 +1734,62 @@ fn sync_game_master_role(
     Ok(())
 }
 
-fn sync_channel_topic_and_category(
+// The topic and the category that the channel should have
+pub fn channel_topic_and_category(
     series_id: &str,
     channel_id: ChannelId,
     next_event: &Event,
-    redis_connection: &mut redis::Connection,
-    discord_api: &crate::discord_bot::CacheAndHttp,
-) -> Result<(), crate::BoxedError> {
-    // Sync the topic and the category
-    let topic = format!("Next session: {}", &next_event.link);
+    session_number: Option<u64>,
+    series_tags: &[String],
+    redis_connection: &mut crate::redis_namespace::Connection,
+) -> Result<(String, Option<ChannelId>), crate::BoxedError> {
+    let topic = crate::discord_channel_settings::settings(redis_connection, channel_id)?.topic(
+        &next_event.name,
+        &next_event.link,
+        &next_event.location,
+        session_number,
+        series_tags,
+    );
     let redis_series_type_key = format!("event_series:{}:type", series_id);
     let event_type: Option<String> = redis_connection.get(&redis_series_type_key)?;
     let category = match event_type.as_ref().map(String::as_str) {
-        Some("campaign") => CAMPAIGN_CATEGORY_ID,
-        Some("adventure") => ONE_SHOT_CATEGORY_ID,
+        Some("campaign") => crate::guild_config::campaign_category(redis_connection)?,
+        Some("adventure") => crate::guild_config::one_shot_category(redis_connection)?,
         _ => {
             eprintln!(
                 "Event series {} does not have a type of 'campaign' or 'adventure'",
                 series_id
             );
-            CAMPAIGN_CATEGORY_ID
+            crate::guild_config::campaign_category(redis_connection)?
         }
     };
+    Ok((topic, category))
+}
+
+fn sync_channel_topic_and_category(
+    series_id: &str,
+    channel_id: ChannelId,
+    next_event: &Event,
+    session_number: Option<u64>,
+    series_tags: &[String],
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    // Sync the topic (which the hosts might have customized) and the category
+    let (topic, category) = channel_topic_and_category(
+        series_id,
+        channel_id,
+        next_event,
+        session_number,
+        series_tags,
+        redis_connection,
+    )?;
+    // Nothing to do if this is what the sync applied last time. Changes made
+    // in Discord directly are only reverted once the topic changes.
+    let topic_hash = crate::discord_channel_edits::topic_hash(&topic, category);
+    if crate::discord_channel_edits::is_topic_applied(redis_connection, channel_id, &topic_hash)? {
+        return Ok(());
+    }
     let channel = channel_id.to_channel(discord_api)?;
     if let serenity::model::channel::Channel::Guild(channel) = channel {
         let channel_needs_update = {
@@ -804,7 +1808,17 @@ fn sync_channel_topic_and_category(
             topic_needs_update || category_needs_update
         };
         if channel_needs_update {
-            channel_id.edit(&discord_api.http, |channel_edit| {
+            // Retried by the next sync
+            if !crate::discord_channel_edits::try_spend_edit(redis_connection, channel_id)? {
+                println!(
+                    "Discord event sync: deferring the topic update of channel {}, it was edited \
+                     too often recently",
+                    channel_id.0
+                );
+                mark_series_dirty(redis_connection, series_id)?;
+                return Ok(());
+            }
+            channel_id.edit(discord_api.http(), |channel_edit| {
                 channel_edit.topic(topic);
                 if category.is_some() {
                     channel_edit.category(category);
@@ -812,6 +1826,51 @@ fn sync_channel_topic_and_category(
                 channel_edit
             })?;
         }
+        crate::discord_channel_edits::record_topic_applied(
+            redis_connection,
+            channel_id,
+            &topic_hash,
+        )?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const NOW: i64 = 1_600_000_000;
+
+    fn series(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn recurring_sync_picks_only_dirty_series() {
+        let sync_times: HashMap<String, i64> = vec![
+            ("a".to_string(), NOW - 60),
+            ("b".to_string(), NOW - 60),
+            ("c".to_string(), NOW - 60),
+        ]
+        .into_iter()
+        .collect();
+        let selected =
+            select_series_to_sync(series(&["a", "b", "c"]), &series(&["b"]), &sync_times, NOW);
+        assert_eq!(selected, series(&["b"]));
+    }
+
+    #[test]
+    fn recurring_sync_picks_stale_and_unsynced_series() {
+        let stale_time = NOW - FULL_SYNC_INTERVAL_HOURS * 60 * 60 - 1;
+        let sync_times: HashMap<String, i64> = vec![
+            ("fresh".to_string(), NOW - 60),
+            ("stale".to_string(), stale_time),
+        ]
+        .into_iter()
+        .collect();
+        let selected =
+            select_series_to_sync(series(&["fresh", "stale", "new"]), &[], &sync_times, NOW);
+        assert_eq!(selected, series(&["stale", "new"]));
+    }
+}
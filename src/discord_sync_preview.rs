@@ -0,0 +1,276 @@
+use redis::Commands;
+use serenity::model::id::{ChannelId, RoleId};
+use std::collections::HashMap;
+
+// "preview <series_id>" shows what the next Discord sync would change for a
+// single series, without changing anything. It goes through the same steps as
+// the sync (see discord_sync::sync_event_series) up to the topic: creating the
+// channel and the roles, handing out the player, host and game master roles
+// (or the member overwrites in channels without roles), and updating the topic
+// and the category. The sync never takes roles away,
+// so neither does the preview. The creation budget is not taken into account.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RoleKind {
+    Player,
+    Host,
+    GameMaster,
+}
+
+// Why the sync would leave the series alone
+pub enum Skipped {
+    Paused,
+    NoUpcomingEvents,
+    InvalidName(String),
+    AwaitingApproval(crate::discord_channel_approval::Decision),
+    WouldPropose,
+}
+
+pub enum Change {
+    CreateChannel(String),
+    CreateRole(String),
+    AddRole {
+        discord_id: u64,
+        tag: String,
+        role: RoleKind,
+    },
+    // In channels without roles
+    GrantAccess {
+        discord_id: u64,
+        tag: String,
+        as_host: bool,
+    },
+    Topic {
+        old: Option<String>,
+        new: String,
+    },
+    Category {
+        old: Option<String>,
+        new: String,
+    },
+}
+
+pub enum Preview {
+    Skipped(Skipped),
+    Changes(Vec<Change>),
+}
+
+// Only reads from Redis and Discord's cache
+pub fn preview(
+    series_id: &str,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Preview, crate::BoxedError> {
+    if crate::discord_sync::is_sync_paused(con, series_id)? {
+        return Ok(Preview::Skipped(Skipped::Paused));
+    }
+    let (upcoming, past) = crate::discord_sync::series_events(series_id, con)?;
+    let next_event = match upcoming.first() {
+        Some(event) => event,
+        None => return Ok(Preview::Skipped(Skipped::NoUpcomingEvents)),
+    };
+    let series_name = match crate::discord_sync::series_name(&next_event.name) {
+        Ok(series_name) => series_name,
+        Err(_) => {
+            return Ok(Preview::Skipped(Skipped::InvalidName(
+                next_event.name.clone(),
+            )))
+        }
+    };
+    let mut changes = vec![];
+    // Step 1: The channel
+    let channel_id: Option<u64> = con.get(format!("event_series:{}:discord_channel", series_id))?;
+    let channel_id = match channel_id.map(ChannelId) {
+        Some(channel_id)
+            if crate::discord_state_cache::channel_exists(channel_id, discord_api)? =>
+        {
+            Some(channel_id)
+        }
+        _ => None,
+    };
+    if channel_id.is_none() {
+        if crate::feature_flags::is_enabled(
+            con,
+            crate::discord_sync::GUILD_ID,
+            crate::feature_flags::Feature::ChannelApproval,
+        )? {
+            match crate::discord_channel_approval::decision(con, series_id)? {
+                Some(crate::discord_channel_approval::Decision::Approved) => (),
+                Some(decision) => {
+                    return Ok(Preview::Skipped(Skipped::AwaitingApproval(decision)));
+                }
+                None => return Ok(Preview::Skipped(Skipped::WouldPropose)),
+            }
+        }
+        changes.push(Change::CreateChannel(series_name.to_string()));
+    }
+    // Channels without roles give access with member overwrites instead
+    let uses_overwrites = crate::discord_channel_access::would_use_overwrites(con, channel_id)?;
+    // Steps 2 and 3: The player and host roles
+    let host_role_name = format!("[Host] {}", series_name);
+    let mut role_ids = vec![];
+    for &(role_kind, role_name) in &[
+        (RoleKind::Player, series_name),
+        (RoleKind::Host, host_role_name.as_str()),
+    ] {
+        let role_id = match channel_id {
+            Some(channel_id) => {
+                let redis_channel_role_key = if role_kind == RoleKind::Host {
+                    format!("discord_channel:{}:discord_host_role", channel_id.0)
+                } else {
+                    format!("discord_channel:{}:discord_role", channel_id.0)
+                };
+                let role_id: Option<u64> = con.get(&redis_channel_role_key)?;
+                match role_id.map(RoleId) {
+                    Some(role_id)
+                        if crate::discord_state_cache::role_exists(role_id, discord_api)? =>
+                    {
+                        Some(role_id)
+                    }
+                    _ => None,
+                }
+            }
+            None => None,
+        };
+        if role_id.is_none() && !uses_overwrites {
+            changes.push(Change::CreateRole(role_name.to_string()));
+        }
+        role_ids.push((role_kind, role_id));
+    }
+    // Steps 5 and 6: The role assignments. A new channel has nobody removed
+    // from it yet, which is all that the channel ID is needed for.
+    let candidate_channel_id = channel_id.unwrap_or(ChannelId(0));
+    let mut candidates = vec![
+        (
+            RoleKind::Player,
+            role_ids[0].1,
+            crate::discord_sync::role_assignment_candidates(
+                series_id,
+                candidate_channel_id,
+                /*is_host_role*/ false,
+                con,
+            )?,
+        ),
+        (
+            RoleKind::Host,
+            role_ids[1].1,
+            crate::discord_sync::role_assignment_candidates(
+                series_id,
+                candidate_channel_id,
+                /*is_host_role*/ true,
+                con,
+            )?,
+        ),
+    ];
+    if let Some(game_master_role) = crate::guild_config::game_master_role(con)? {
+        if crate::feature_flags::is_enabled(
+            con,
+            crate::discord_sync::GUILD_ID,
+            crate::feature_flags::Feature::GameMasterRole,
+        )? {
+            candidates.push((
+                RoleKind::GameMaster,
+                Some(game_master_role),
+                crate::discord_sync::game_master_candidates(series_id, con)?,
+            ));
+        }
+    }
+    // Members that aren't on the server can't get a role
+    let members: HashMap<u64, (String, Vec<RoleId>)> = {
+        match crate::discord_sync::GUILD_ID.to_guild_cached(&discord_api.cache) {
+            Some(guild) => guild
+                .read()
+                .members
+                .values()
+                .map(|member| {
+                    let user = member.user.read();
+                    (user.id.0, (user.tag(), member.roles.clone()))
+                })
+                .collect(),
+            None => HashMap::new(),
+        }
+    };
+    for (role_kind, role_id, discord_ids) in candidates {
+        for discord_id in discord_ids {
+            let (tag, member_role_ids) = match members.get(&discord_id) {
+                Some(member) => member,
+                None => continue,
+            };
+            if uses_overwrites && role_kind != RoleKind::GameMaster {
+                let as_host = role_kind == RoleKind::Host;
+                let has_access = match channel_id {
+                    Some(channel_id) if as_host => {
+                        crate::discord_channel_access::is_host(con, channel_id, discord_id)?
+                    }
+                    Some(channel_id) => {
+                        crate::discord_channel_access::is_player(con, channel_id, discord_id)?
+                    }
+                    None => false,
+                };
+                if !has_access {
+                    changes.push(Change::GrantAccess {
+                        discord_id: discord_id,
+                        tag: tag.clone(),
+                        as_host: as_host,
+                    });
+                }
+                continue;
+            }
+            let has_role = role_id.map_or(false, |role_id| member_role_ids.contains(&role_id));
+            if !has_role {
+                changes.push(Change::AddRole {
+                    discord_id: discord_id,
+                    tag: tag.clone(),
+                    role: role_kind,
+                });
+            }
+        }
+    }
+    // Step 7a: The topic and the category. A new channel has the default
+    // settings.
+    let past_event_ids: Vec<&str> = past.iter().map(|event| event.id.as_str()).collect();
+    let session_number =
+        crate::discord_session_counter::next_session_number_with(series_id, &past_event_ids, con)?;
+    let series_tags = crate::event_tags::series_tags(con, series_id)?;
+    let (topic, category) = crate::discord_sync::channel_topic_and_category(
+        series_id,
+        candidate_channel_id,
+        next_event,
+        session_number,
+        &series_tags,
+        con,
+    )?;
+    let (current_topic, current_category) = match channel_id {
+        Some(channel_id) => match channel_id.to_channel(discord_api)? {
+            serenity::model::channel::Channel::Guild(channel) => {
+                let channel = channel.read();
+                (channel.topic.clone(), channel.category_id)
+            }
+            _ => (None, None),
+        },
+        None => (None, None),
+    };
+    if current_topic.as_ref() != Some(&topic) {
+        changes.push(Change::Topic {
+            old: current_topic,
+            new: topic,
+        });
+    }
+    if let Some(category) = category {
+        if current_category != Some(category) {
+            changes.push(Change::Category {
+                old: current_category.map(|old| category_name(old, discord_api)),
+                new: category_name(category, discord_api),
+            });
+        }
+    }
+    Ok(Preview::Changes(changes))
+}
+
+// Falls back to the ID for categories that aren't cached
+fn category_name(category_id: ChannelId, discord_api: &crate::discord_bot::CacheAndHttp) -> String {
+    match category_id.to_channel_cached(&discord_api.cache) {
+        Some(serenity::model::channel::Channel::Category(category)) => category.read().name.clone(),
+        _ => category_id.0.to_string(),
+    }
+}
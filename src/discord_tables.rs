@@ -0,0 +1,244 @@
+use rand::seq::SliceRandom;
+use redis::Commands;
+use serenity::http::CacheHttp;
+use serenity::model::{
+    channel::ChannelType, channel::PermissionOverwrite, channel::PermissionOverwriteType,
+    id::ChannelId, id::RoleId, id::UserId, permissions::Permissions,
+};
+use simple_error::SimpleError;
+
+// Big one-shot events are often played at several tables at once. Hosts can
+// split the players of a channel into temporary table channels which are
+// deleted again once the event is over.
+pub const MAX_TABLES: usize = 10;
+
+pub enum TablesResult {
+    Created(Vec<(ChannelId, Vec<UserId>)>),
+    AlreadyExist,
+    NotEnoughPlayers(usize),
+}
+
+pub fn create_tables(
+    channel_id: ChannelId,
+    channel_access: &crate::discord_bot_commands::ChannelAccess,
+    num_tables: usize,
+    bot_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<TablesResult, crate::BoxedError> {
+    let redis_channel_tables_key = format!("discord_channel:{}:table_channels", channel_id.0);
+    let existing_tables: Vec<u64> = redis_connection.smembers(&redis_channel_tables_key)?;
+    if !existing_tables.is_empty() {
+        return Ok(TablesResult::AlreadyExist);
+    }
+    let channel = match channel_id.to_channel(discord_api)?.guild() {
+        Some(channel) => channel,
+        None => {
+            return Err(SimpleError::new("Tables can only be created in guild channels").into())
+        }
+    };
+    let (channel_name, category_id) = {
+        let channel = channel.read();
+        (channel.name.clone(), channel.category_id)
+    };
+    // Find the players of this channel (but not the hosts, they float between
+    // tables). The hosts get into every table with their role or, in channels
+    // without roles, with overwrites of their own.
+    let (mut player_ids, host_overwrite_kinds): (Vec<UserId>, Vec<PermissionOverwriteType>) =
+        match channel_access {
+            crate::discord_bot_commands::ChannelAccess::Roles(channel_roles) => {
+                let (user_role_id, host_role_id) =
+                    (RoleId(channel_roles.user), RoleId(channel_roles.host));
+                let guild = crate::discord_sync::GUILD_ID
+                    .to_guild_cached(&discord_api.cache)
+                    .ok_or_else(|| SimpleError::new("Could not find the guild in the cache"))?;
+                let guild = guild.read();
+                let player_ids = guild
+                    .members
+                    .values()
+                    .filter(|member| {
+                        member.roles.contains(&user_role_id)
+                            && !member.roles.contains(&host_role_id)
+                            && !member.user.read().bot
+                    })
+                    .map(|member| member.user.read().id)
+                    .collect();
+                (
+                    player_ids,
+                    vec![PermissionOverwriteType::Role(host_role_id)],
+                )
+            }
+            crate::discord_bot_commands::ChannelAccess::Overwrites => {
+                let host_ids = crate::discord_channel_access::hosts(redis_connection, channel_id)?;
+                let player_ids =
+                    crate::discord_channel_access::players(redis_connection, channel_id)?
+                        .into_iter()
+                        .filter(|player_id| !host_ids.contains(player_id))
+                        .map(UserId)
+                        .collect();
+                let host_overwrite_kinds = host_ids
+                    .into_iter()
+                    .map(|host_id| PermissionOverwriteType::Member(UserId(host_id)))
+                    .collect();
+                (player_ids, host_overwrite_kinds)
+            }
+        };
+    if player_ids.len() < num_tables {
+        return Ok(TablesResult::NotEnoughPlayers(player_ids.len()));
+    }
+    player_ids.shuffle(&mut rand::thread_rng());
+    let mut tables: Vec<Vec<UserId>> = vec![vec![]; num_tables];
+    for (i, player_id) in player_ids.into_iter().enumerate() {
+        tables[i % num_tables].push(player_id);
+    }
+    // The tables are cleaned up a day after the channel's last event
+    let redis_channel_expiration_key = format!("discord_channel:{}:expiration_time", channel_id.0);
+    let expiration_time: Option<String> = redis_connection.get(&redis_channel_expiration_key)?;
+    let deletion_time = expiration_time
+        .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
+        .transpose()?
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .filter(|t| *t > chrono::Utc::now())
+        .unwrap_or_else(|| chrono::Utc::now() + chrono::Duration::days(1));
+    // The @everyone role has the same id as the guild
+    let role_everyone_id = RoleId(crate::discord_sync::GUILD_ID.0);
+    let mut created_tables = Vec::with_capacity(num_tables);
+    for (i, table_player_ids) in tables.into_iter().enumerate() {
+        let mut permission_overwrites = vec![
+            PermissionOverwrite {
+                allow: Permissions::empty(),
+                deny: Permissions::READ_MESSAGES,
+                kind: PermissionOverwriteType::Role(role_everyone_id),
+            },
+            PermissionOverwrite {
+                allow: Permissions::READ_MESSAGES,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Member(UserId(bot_id)),
+            },
+        ];
+        permission_overwrites.extend(host_overwrite_kinds.iter().map(|kind| PermissionOverwrite {
+            allow: Permissions::READ_MESSAGES | Permissions::MANAGE_MESSAGES,
+            deny: Permissions::empty(),
+            kind: *kind,
+        }));
+        permission_overwrites.extend(table_player_ids.iter().map(|player_id| {
+            PermissionOverwrite {
+                allow: Permissions::READ_MESSAGES,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Member(*player_id),
+            }
+        }));
+        let table_channel = crate::discord_sync::GUILD_ID.create_channel(
+            discord_api.http(),
+            |channel_builder| {
+                channel_builder
+                    .name(format!("{}-table-{}", channel_name, i + 1))
+                    .kind(ChannelType::Text)
+                    .permissions(permission_overwrites);
+                if let Some(category_id) = category_id {
+                    channel_builder.category(category_id);
+                }
+                channel_builder
+            },
+        )?;
+        println!(
+            "Created table channel {} for channel {}",
+            table_channel.id.0, channel_id.0
+        );
+        let redis_table_deletion_key =
+            format!("discord_table_channel:{}:deletion_time", table_channel.id.0);
+        let redis_table_parent_key = format!(
+            "discord_table_channel:{}:discord_channel",
+            table_channel.id.0
+        );
+        let _: () = redis::pipe()
+            .atomic()
+            .sadd(&redis_channel_tables_key, table_channel.id.0)
+            .ignore()
+            .sadd("discord_table_channels", table_channel.id.0)
+            .ignore()
+            .set(&redis_table_deletion_key, deletion_time.to_rfc3339())
+            .ignore()
+            .set(&redis_table_parent_key, channel_id.0)
+            .ignore()
+            .query(redis_connection)?;
+        created_tables.push((table_channel.id, table_player_ids));
+    }
+    Ok(TablesResult::Created(created_tables))
+}
+
+// Deletes all table channels whose event is over
+pub fn delete_expired_tables(
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let table_channel_ids: Vec<u64> = redis_connection.smembers("discord_table_channels")?;
+    for table_channel_id in table_channel_ids {
+        let redis_table_deletion_key =
+            format!("discord_table_channel:{}:deletion_time", table_channel_id);
+        let deletion_time: Option<String> = redis_connection.get(&redis_table_deletion_key)?;
+        let deletion_time = deletion_time
+            .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
+            .transpose()?
+            .map(|t| t.with_timezone(&chrono::Utc));
+        if let Some(deletion_time) = deletion_time {
+            if deletion_time > chrono::Utc::now() {
+                continue;
+            }
+        }
+        // Check whether the channel still exists on Discord
+        let channel_exists = match ChannelId(table_channel_id).to_channel(discord_api) {
+            Ok(_) => true,
+            Err(err) => {
+                if let serenity::Error::Http(http_err) = &err {
+                    if let serenity::http::HttpError::UnsuccessfulRequest(response) =
+                        http_err.as_ref()
+                    {
+                        if response.status_code == reqwest::StatusCode::NOT_FOUND {
+                            false
+                        } else {
+                            return Err(err.into());
+                        }
+                    } else {
+                        return Err(err.into());
+                    }
+                } else {
+                    return Err(err.into());
+                }
+            }
+        };
+        if channel_exists {
+            if !crate::discord_ownership::verify_managed_channel(
+                ChannelId(table_channel_id),
+                "discord_table_channels",
+                redis_connection,
+                discord_api,
+            )? {
+                continue;
+            }
+            ChannelId(table_channel_id).delete(&discord_api.http)?;
+            println!("Deleted table channel {}", table_channel_id);
+        }
+        // Forget about the table channel
+        let redis_table_parent_key =
+            format!("discord_table_channel:{}:discord_channel", table_channel_id);
+        let parent_channel_id: Option<u64> = redis_connection.get(&redis_table_parent_key)?;
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .srem("discord_table_channels", table_channel_id)
+            .ignore()
+            .del(&redis_table_deletion_key)
+            .ignore()
+            .del(&redis_table_parent_key)
+            .ignore();
+        if let Some(parent_channel_id) = parent_channel_id {
+            pipe.srem(
+                format!("discord_channel:{}:table_channels", parent_channel_id),
+                table_channel_id,
+            )
+            .ignore();
+        }
+        let _: () = pipe.query(redis_connection)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,209 @@
+use lazy_static::lazy_static;
+use serenity::{
+    model::{channel::Message, id::ChannelId, id::MessageId},
+    prelude::*,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+// In busy channels, commands and the bot's answers to them bury the actual
+// conversation. Organizers can have the bot tidy up after some classes of
+// commands with the "tidy_commands" config key, and the hosts of a channel
+// can override that with the "tidy" channel setting. The invoking message of
+// a tidied command is deleted once the command was handled, and the replies
+// sent through `say` are deleted TRANSIENT_REPLY_SECONDS later, so there is
+// some time to read them. Deleting the invoking message needs the "Manage
+// Messages" permission.
+pub const TRANSIENT_REPLY_SECONDS: i64 = 30;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CommandClass {
+    // Commands that anyone can use, like rolls and subscriptions
+    Player,
+    // Commands of the hosts of a channel
+    Host,
+    Organizer,
+}
+
+impl CommandClass {
+    pub const ALL: [CommandClass; 3] = [
+        CommandClass::Player,
+        CommandClass::Host,
+        CommandClass::Organizer,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandClass::Player => "player",
+            CommandClass::Host => "host",
+            CommandClass::Organizer => "organizer",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CommandClass> {
+        CommandClass::ALL
+            .iter()
+            .find(|class| class.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+// Parses "off", "all" or a comma-separated list of command classes
+pub fn parse_classes(value: &str) -> Option<Vec<CommandClass>> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("off") {
+        return Some(vec![]);
+    }
+    if value.eq_ignore_ascii_case("all") {
+        return Some(CommandClass::ALL.to_vec());
+    }
+    let mut classes = vec![];
+    for name in value.split(',') {
+        let class = CommandClass::from_name(name.trim())?;
+        if !classes.contains(&class) {
+            classes.push(class);
+        }
+    }
+    Some(classes)
+}
+
+// The inverse of `parse_classes`, in the order of CommandClass::ALL
+pub fn classes_name(classes: &[CommandClass]) -> String {
+    if classes.is_empty() {
+        return "off".to_string();
+    }
+    CommandClass::ALL
+        .iter()
+        .filter(|class| classes.contains(class))
+        .map(CommandClass::name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// The channel setting if there is one, the server-wide default otherwise
+pub fn tidied_classes<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+) -> Result<Vec<CommandClass>, crate::BoxedError> {
+    match crate::discord_channel_settings::settings(con, channel_id)?.tidy {
+        Some(classes) => Ok(classes),
+        None => crate::guild_config::tidy_commands(con),
+    }
+}
+
+lazy_static! {
+    // The invoking messages of the commands that are currently being handled
+    // and should be tidied up
+    static ref TIDIED_COMMANDS: Mutex<HashSet<MessageId>> = Mutex::new(HashSet::new());
+}
+
+// Deletes the invoking message when dropped, i.e. once the command was
+// handled, however the handler returned
+pub struct TidyGuard {
+    http: Arc<serenity::http::raw::Http>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+}
+
+impl Drop for TidyGuard {
+    fn drop(&mut self) {
+        TIDIED_COMMANDS.lock().remove(&self.message_id);
+        if let Err(err) = self.channel_id.delete_message(&self.http, self.message_id) {
+            eprintln!(
+                "Could not delete the command message {}: {}",
+                self.message_id.0, err
+            );
+        }
+    }
+}
+
+// Returns a guard if the command in the message should be tidied up. The
+// caller keeps it around while handling the command.
+pub fn begin(
+    ctx: &Context,
+    msg: &Message,
+    regexes: &crate::discord_bot_commands::Regexes,
+    redis_client: &crate::redis_namespace::Client,
+) -> Result<Option<TidyGuard>, crate::BoxedError> {
+    let class = match regexes.command_class(&msg.content) {
+        Some(class) => class,
+        None => return Ok(None),
+    };
+    let mut redis_connection = redis_client.get_connection()?;
+    if !tidied_classes(&mut redis_connection, msg.channel_id)?.contains(&class) {
+        return Ok(None);
+    }
+    TIDIED_COMMANDS.lock().insert(msg.id);
+    Ok(Some(TidyGuard {
+        http: ctx.http.clone(),
+        channel_id: msg.channel_id,
+        message_id: msg.id,
+    }))
+}
+
+// The body of an inline reply to the message with the given ID. If the
+// message was deleted in the meantime, the reply is sent as a plain message.
+fn reply_body(message_id: MessageId, content: &str) -> serde_json::Value {
+    serde_json::json!({
+        "content": content,
+        "message_reference": {
+            "message_id": message_id.0.to_string(),
+            "fail_if_not_exists": false,
+        },
+    })
+}
+
+// Replies to a command with one of Discord's inline replies. Replies to
+// tidied commands are transient.
+pub fn say(
+    ctx: &Context,
+    msg: &Message,
+    content: impl std::fmt::Display,
+) -> serenity::Result<Message> {
+    let reply = ctx
+        .http
+        .send_message(msg.channel_id.0, &reply_body(msg.id, &content.to_string()))?;
+    if !TIDIED_COMMANDS.lock().contains(&msg.id) {
+        return Ok(reply);
+    }
+    let task_scheduler = {
+        let data = ctx.data.read();
+        data.get::<crate::discord_bot::TaskSchedulerKey>()
+            .expect("Task scheduler was not set")
+            .clone()
+    };
+    let http = ctx.http.clone();
+    let (channel_id, reply_id) = (reply.channel_id, reply.id);
+    task_scheduler.lock().add_task_datetime(
+        white_rabbit::Utc::now() + white_rabbit::Duration::seconds(TRANSIENT_REPLY_SECONDS),
+        move |_| {
+            if let Err(err) = channel_id.delete_message(&http, reply_id) {
+                eprintln!(
+                    "Could not delete the transient reply {}: {}",
+                    reply_id.0, err
+                );
+            }
+            white_rabbit::DateResult::Done
+        },
+    );
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies_reference_the_command() {
+        assert_eq!(
+            reply_body(MessageId(1234), "Welcome!"),
+            serde_json::json!({
+                "content": "Welcome!",
+                "message_reference": {
+                    "message_id": "1234",
+                    "fail_if_not_exists": false,
+                },
+            })
+        );
+    }
+}
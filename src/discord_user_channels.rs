@@ -0,0 +1,88 @@
+use redis::{Commands, PipelineCommands};
+use serenity::model::id::ChannelId;
+use std::collections::BTreeMap;
+
+// Organizers can look up every bot-managed channel that a user has access to
+// with "channels @user", e.g. when offboarding someone or looking into a
+// report. The channels come from the roles of the member (through the
+// `discord_channel:{}:discord_role` and `discord_host_role` mappings) and from
+// the member overwrites of channels without roles, of co-hosts and of the
+// members of archived channels whose roles were pruned.
+
+pub struct UserChannel {
+    pub is_player: bool,
+    pub is_host: bool,
+    pub is_cohost: bool,
+    pub is_guest: bool,
+    pub archived: bool,
+}
+
+// The bot-managed channels where the user has any kind of access, ordered by
+// channel ID (which is the order they were created in)
+pub fn user_channels(
+    discord_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Vec<(ChannelId, UserChannel)>, crate::BoxedError> {
+    // Users who left the server have no roles anymore, but they might still
+    // have overwrites
+    let member_role_ids: Vec<u64> =
+        match crate::discord_sync::GUILD_ID.member(discord_api, discord_id) {
+            Ok(member) => member.roles.iter().map(|role_id| role_id.0).collect(),
+            Err(_) => vec![],
+        };
+    let channel_ids: Vec<u64> = redis_connection.smembers("discord_channels")?;
+    if channel_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut pipe = redis::pipe();
+    for &channel_id in &channel_ids {
+        pipe.get(format!("discord_channel:{}:discord_role", channel_id))
+            .get(format!("discord_channel:{}:discord_host_role", channel_id))
+            .sismember(
+                format!("discord_channel:{}:cohosts", channel_id),
+                discord_id,
+            )
+            .hexists(format!("discord_channel:{}:guests", channel_id), discord_id)
+            .sismember(
+                format!("discord_channel:{}:pruned_members", channel_id),
+                discord_id,
+            )
+            .get(format!("discord_channel:{}:archived", channel_id));
+    }
+    let channel_fields: Vec<(Option<u64>, Option<u64>, bool, bool, bool, Option<bool>)> =
+        pipe.query(redis_connection)?;
+    let mut channels = BTreeMap::new();
+    for (channel_id, (role_id, host_role_id, is_cohost, is_guest, is_pruned, archived)) in
+        channel_ids.into_iter().zip(channel_fields)
+    {
+        let has_role = |role_id: Option<u64>| {
+            role_id
+                .map(|role_id| member_role_ids.contains(&role_id))
+                .unwrap_or(false)
+        };
+        let channel = UserChannel {
+            is_player: has_role(role_id) || is_pruned,
+            is_host: has_role(host_role_id),
+            is_cohost,
+            is_guest,
+            archived: archived.unwrap_or(false),
+        };
+        channels.insert(channel_id, channel);
+    }
+    // Channels without roles give access with member overwrites
+    let memberships = crate::discord_channel_access::memberships(redis_connection, discord_id)?;
+    for (channel_id, is_player, is_host) in memberships {
+        if let Some(channel) = channels.get_mut(&channel_id.0) {
+            channel.is_player |= is_player;
+            channel.is_host |= is_host;
+        }
+    }
+    Ok(channels
+        .into_iter()
+        .filter(|(_, channel)| {
+            channel.is_player || channel.is_host || channel.is_cohost || channel.is_guest
+        })
+        .map(|(channel_id, channel)| (ChannelId(channel_id), channel))
+        .collect())
+}
@@ -0,0 +1,111 @@
+use redis::Commands;
+use serenity::http::CacheHttp;
+use serenity::model::{
+    channel::ChannelType, channel::PermissionOverwrite, channel::PermissionOverwriteType,
+    id::ChannelId, id::RoleId, id::UserId, permissions::Permissions,
+};
+
+// Series that are played online get a voice channel next to their text
+// channel, with the same name, category and permissions. The voice channel is
+// removed again once the next session of the series is not online anymore.
+pub fn sync_voice_channel(
+    channel_id: ChannelId,
+    role_id: RoleId,
+    host_role_id: RoleId,
+    is_online: bool,
+    bot_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    creation_budget: &mut crate::discord_creation_budget::CreationBudget,
+) -> Result<(), crate::BoxedError> {
+    let redis_channel_voice_channel_key = format!("discord_channel:{}:voice_channel", channel_id.0);
+    let voice_channel_id: Option<u64> = redis_connection.get(&redis_channel_voice_channel_key)?;
+    match (voice_channel_id, is_online) {
+        (Some(_), true) | (None, false) => Ok(()),
+        (Some(_), false) => delete_voice_channel(channel_id, redis_connection, discord_api),
+        (None, true) => {
+            let channel = match channel_id.to_channel(discord_api)?.guild() {
+                Some(channel) => channel,
+                None => return Ok(()),
+            };
+            let (channel_name, category_id) = {
+                let channel = channel.read();
+                (channel.name.clone(), channel.category_id)
+            };
+            if !creation_budget.try_spend_channel() {
+                println!(
+                    "Discord event sync: not creating voice channel \"{}\", the creation budget \
+                     is exhausted",
+                    channel_name
+                );
+                return Ok(());
+            }
+            // Same permissions as the series channel
+            let role_everyone_id = RoleId(crate::discord_sync::GUILD_ID.0);
+            let permission_overwrites = vec![
+                PermissionOverwrite {
+                    allow: Permissions::empty(),
+                    deny: Permissions::READ_MESSAGES | Permissions::CONNECT,
+                    kind: PermissionOverwriteType::Role(role_everyone_id),
+                },
+                PermissionOverwrite {
+                    allow: Permissions::READ_MESSAGES | Permissions::CONNECT,
+                    deny: Permissions::empty(),
+                    kind: PermissionOverwriteType::Member(UserId(bot_id)),
+                },
+                PermissionOverwrite {
+                    allow: Permissions::READ_MESSAGES | Permissions::CONNECT,
+                    deny: Permissions::empty(),
+                    kind: PermissionOverwriteType::Role(role_id),
+                },
+                PermissionOverwrite {
+                    allow: Permissions::READ_MESSAGES | Permissions::CONNECT,
+                    deny: Permissions::empty(),
+                    kind: PermissionOverwriteType::Role(host_role_id),
+                },
+            ];
+            let voice_channel = crate::discord_sync::GUILD_ID.create_channel(
+                discord_api.http(),
+                |channel_builder| {
+                    channel_builder
+                        .name(&channel_name)
+                        .kind(ChannelType::Voice)
+                        .permissions(permission_overwrites);
+                    if let Some(category_id) = category_id {
+                        channel_builder.category(category_id);
+                    }
+                    channel_builder
+                },
+            )?;
+            println!(
+                "Discord event sync: created voice channel {} \"{}\"",
+                voice_channel.id.0, &voice_channel.name
+            );
+            let _: () =
+                redis_connection.set(&redis_channel_voice_channel_key, voice_channel.id.0)?;
+            Ok(())
+        }
+    }
+}
+
+// Deletes the voice channel belonging to a series channel, if there is one
+pub fn delete_voice_channel(
+    channel_id: ChannelId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let redis_channel_voice_channel_key = format!("discord_channel:{}:voice_channel", channel_id.0);
+    let voice_channel_id: Option<u64> = redis_connection.get(&redis_channel_voice_channel_key)?;
+    if let Some(voice_channel_id) = voice_channel_id {
+        match ChannelId(voice_channel_id).delete(discord_api.http()) {
+            Ok(_) => println!("Deleted voice channel {}", voice_channel_id),
+            // Most likely, the channel was already deleted manually
+            Err(err) => eprintln!(
+                "Could not delete voice channel {}: {}",
+                voice_channel_id, err
+            ),
+        }
+        let _: () = redis_connection.del(&redis_channel_voice_channel_key)?;
+    }
+    Ok(())
+}
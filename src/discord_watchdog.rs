@@ -0,0 +1,90 @@
+use lazy_static::lazy_static;
+use serenity::client::bridge::gateway::{ShardId, ShardManager};
+use serenity::gateway::ConnectionStage;
+use serenity::prelude::Mutex;
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Serenity reconnects on its own most of the time, but a shard that got stuck
+// would otherwise leave the bot idle without anybody noticing. The watchdog
+// restarts shards that have been disconnected for too long and exposes their
+// state in the health endpoint.
+const CHECK_INTERVAL_SECS: u64 = 30;
+const MAX_DISCONNECTED_SECS: i64 = 5 * 60;
+
+lazy_static! {
+    // Shards that are currently not connected and since when
+    static ref DISCONNECTED_SHARDS: Mutex<HashMap<u64, chrono::DateTime<chrono::Utc>>> =
+        Mutex::new(HashMap::new());
+}
+
+pub fn spawn(shard_manager: Arc<Mutex<ShardManager>>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(CHECK_INTERVAL_SECS));
+        check_shards(&shard_manager);
+    });
+}
+
+fn check_shards(shard_manager: &Arc<Mutex<ShardManager>>) {
+    let now = chrono::Utc::now();
+    let shard_stages: Vec<(ShardId, ConnectionStage)> = {
+        let shard_manager = shard_manager.lock();
+        let runners = shard_manager.runners.lock();
+        runners
+            .iter()
+            .map(|(shard_id, runner)| (*shard_id, runner.stage))
+            .collect()
+    };
+    let mut shards_to_restart = vec![];
+    {
+        let mut disconnected_shards = DISCONNECTED_SHARDS.lock();
+        for (shard_id, stage) in shard_stages {
+            if stage == ConnectionStage::Connected {
+                if disconnected_shards.remove(&shard_id.0).is_some() {
+                    println!("Watchdog: shard {} is connected again", shard_id.0);
+                }
+                continue;
+            }
+            let disconnected_since = *disconnected_shards.entry(shard_id.0).or_insert(now);
+            if now - disconnected_since > chrono::Duration::seconds(MAX_DISCONNECTED_SECS) {
+                shards_to_restart.push(shard_id);
+                // Give the restarted shard some time before trying again
+                disconnected_shards.insert(shard_id.0, now);
+            }
+        }
+    }
+    for shard_id in shards_to_restart {
+        eprintln!(
+            "Watchdog: shard {} has been disconnected for more than {} seconds, restarting it",
+            shard_id.0, MAX_DISCONNECTED_SECS
+        );
+        crate::error_reporting::report(
+            &SimpleError::new("Discord shard disconnected, restarting it").into(),
+            &[("shard", &shard_id.0.to_string())],
+        );
+        shard_manager.lock().restart(shard_id);
+    }
+}
+
+// Human readable status for the health endpoint
+pub fn health_status() -> String {
+    let disconnected_shards = DISCONNECTED_SHARDS.lock();
+    if disconnected_shards.is_empty() {
+        return "discord: ok".to_string();
+    }
+    let mut shard_ids: Vec<_> = disconnected_shards.keys().collect();
+    shard_ids.sort_unstable();
+    let descriptions: Vec<_> = shard_ids
+        .into_iter()
+        .map(|shard_id| {
+            format!(
+                "shard {} since {}",
+                shard_id,
+                disconnected_shards[shard_id].to_rfc3339()
+            )
+        })
+        .collect();
+    format!("discord: disconnected: {}", descriptions.join(", "))
+}
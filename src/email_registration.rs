@@ -0,0 +1,154 @@
+use crate::identity::{Identity, Provider};
+use redis::Commands;
+
+// Links email addresses to Discord users, for event sources that only know
+// the email addresses of the hosts and attendees (like Google Calendar).
+// Users ask for their own address with "register email" in a DM, since the
+// address is private. Hosts are matched by their address as well, so nobody
+// can be trusted with an address that they only typed: the request waits
+// until an organizer approves it with "approve email <user>" (also in a DM).
+// An address belongs to at most one Discord user, taking somebody else's
+// address needs an organizer to free it first (with
+// "unregister email <address>").
+// The addresses are email identities (see identity.rs). Registering an address
+// replaces the user's previous one.
+
+// Maps the Discord ID of each user with an open request to the address
+pub const REDIS_PENDING_REQUESTS_KEY: &'static str = "email_requests";
+
+pub fn normalize(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+// Only catches typos, whether the address exists is up to the user
+pub fn is_valid_email(email: &str) -> bool {
+    let mut parts = email.split('@');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(local), Some(domain), None) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.contains(char::is_whitespace)
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Registration {
+    Registered,
+    // The user had registered this address before
+    Unchanged,
+    TakenByOtherUser,
+    // Waits for an organizer to approve it
+    Pending,
+}
+
+// Asking again replaces the user's open request, if any
+pub fn request(
+    discord_id: u64,
+    email: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Registration, crate::BoxedError> {
+    let email = normalize(email);
+    let registration = match discord_user(&email, con)? {
+        Some(owner_id) if owner_id == discord_id => Registration::Unchanged,
+        Some(_) => Registration::TakenByOtherUser,
+        None => {
+            let _: () = con.hset(REDIS_PENDING_REQUESTS_KEY, discord_id, &email)?;
+            Registration::Pending
+        }
+    };
+    Ok(registration)
+}
+
+pub fn pending_request<C: redis::ConnectionLike>(
+    discord_id: u64,
+    con: &mut C,
+) -> Result<Option<String>, crate::BoxedError> {
+    Ok(con.hget(REDIS_PENDING_REQUESTS_KEY, discord_id)?)
+}
+
+// Registers the address that the user asked for. Returns None if there was no
+// open request.
+pub fn approve(
+    discord_id: u64,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Option<(String, Registration)>, crate::BoxedError> {
+    let email = match pending_request(discord_id, con)? {
+        Some(email) => email,
+        None => return Ok(None),
+    };
+    let registration = register(discord_id, &email, con)?;
+    let _: () = con.hdel(REDIS_PENDING_REQUESTS_KEY, discord_id)?;
+    Ok(Some((email, registration)))
+}
+
+// Replaces the user's previous address, if any. Only for addresses that are
+// known to belong to the user, see request() otherwise.
+pub fn register(
+    discord_id: u64,
+    email: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Registration, crate::BoxedError> {
+    let identity = Identity::email(email);
+    let redis_email_discord_user_key = identity.redis_discord_user_key();
+    let redis_identities_key = crate::identity::redis_identities_key(discord_id);
+    let mut registration = Registration::Registered;
+    let mut previous_identity = None;
+    redis::transaction(
+        con,
+        &[&redis_email_discord_user_key, &redis_identities_key],
+        |con, pipe| {
+            let owner_id = crate::identity::discord_user(&identity, con)?;
+            registration = match owner_id {
+                Some(owner_id) if owner_id == discord_id => Registration::Unchanged,
+                Some(_) => Registration::TakenByOtherUser,
+                None => Registration::Registered,
+            };
+            if registration == Registration::Registered {
+                previous_identity =
+                    crate::identity::linked_identity(discord_id, Provider::Email, con)?;
+                crate::identity::add_link(pipe, discord_id, &identity);
+            }
+            pipe.query(con)
+        },
+    )?;
+    if let Some(previous_identity) = previous_identity {
+        crate::identity::remove_link(discord_id, &previous_identity, con)?;
+    }
+    Ok(registration)
+}
+
+// Returns the address that was registered. Also withdraws an open request.
+pub fn unregister(
+    discord_id: u64,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Option<String>, crate::BoxedError> {
+    let _: () = con.hdel(REDIS_PENDING_REQUESTS_KEY, discord_id)?;
+    let identity = crate::identity::linked_identity(discord_id, Provider::Email, con)?;
+    if let Some(ref identity) = identity {
+        crate::identity::remove_link(discord_id, identity, con)?;
+    }
+    Ok(identity.map(|identity| identity.id))
+}
+
+// Frees an address, returns the user it belonged to
+pub fn unregister_address(
+    email: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Option<u64>, crate::BoxedError> {
+    let discord_id = discord_user(email, con)?;
+    if let Some(discord_id) = discord_id {
+        crate::identity::remove_link(discord_id, &Identity::email(email), con)?;
+    }
+    Ok(discord_id)
+}
+
+pub fn discord_user<C: redis::ConnectionLike>(
+    email: &str,
+    con: &mut C,
+) -> Result<Option<u64>, crate::BoxedError> {
+    Ok(crate::identity::discord_user(&Identity::email(email), con)?)
+}
@@ -1,14 +1,18 @@
 use crate::meetup_api::Error as MeetupApiError;
 use askama::Error as AskamaError;
 use backtrace::Backtrace;
+use base64::DecodeError as Base64DecodeError;
 use chrono::format::ParseError as ChronoParseError;
 use hyper::http::Error as HttpError;
+use hyper::Error as HyperError;
 use redis::RedisError;
 use regex::Error as RegexError;
 use reqwest::Error as ReqwestError;
+use serde_json::Error as SerdeJsonError;
 use serenity::Error as SerenityError;
 use simple_error::SimpleError;
 use std::num::ParseIntError;
+use std::string::FromUtf8Error;
 use tokio::timer::Error as TokioTimerError;
 use url::ParseError as UrlParseError;
 
@@ -116,6 +120,15 @@ impl From<HttpError> for BoxedError {
     }
 }
 
+impl From<HyperError> for BoxedError {
+    fn from(err: HyperError) -> Self {
+        BoxedError {
+            inner: Box::new(err),
+            backtrace: Backtrace::new(),
+        }
+    }
+}
+
 impl From<TokioTimerError> for BoxedError {
     fn from(err: TokioTimerError) -> Self {
         BoxedError {
@@ -151,3 +164,30 @@ impl From<ParseIntError> for BoxedError {
         }
     }
 }
+
+impl From<SerdeJsonError> for BoxedError {
+    fn from(err: SerdeJsonError) -> Self {
+        BoxedError {
+            inner: Box::new(err),
+            backtrace: Backtrace::new(),
+        }
+    }
+}
+
+impl From<Base64DecodeError> for BoxedError {
+    fn from(err: Base64DecodeError) -> Self {
+        BoxedError {
+            inner: Box::new(err),
+            backtrace: Backtrace::new(),
+        }
+    }
+}
+
+impl From<FromUtf8Error> for BoxedError {
+    fn from(err: FromUtf8Error) -> Self {
+        BoxedError {
+            inner: Box::new(err),
+            backtrace: Backtrace::new(),
+        }
+    }
+}
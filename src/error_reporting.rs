@@ -0,0 +1,191 @@
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serenity::prelude::Mutex;
+use std::collections::HashMap;
+
+// Besides logging them, panics and high-severity errors can be sent to an
+// error reporting webhook, configured via ERROR_REPORTING_WEBHOOK_URL.
+// The webhook receives a JSON object per error. An error that keeps happening
+// (like a failing sync every few minutes) is only sent once per interval, which
+// organizers can change or turn off with the "error_report_interval_minutes"
+// config key.
+lazy_static! {
+    static ref WEBHOOK_URL: Option<String> = std::env::var("ERROR_REPORTING_WEBHOOK_URL").ok();
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+    // For reading the interval, set once Redis is available
+    static ref REDIS_CLIENT: Mutex<Option<crate::redis_namespace::Client>> = Mutex::new(None);
+    static ref THROTTLE: Mutex<Throttle> = Mutex::new(Throttle::default());
+}
+
+// Remembers when each error (severity and message) was last sent
+#[derive(Default)]
+struct Throttle {
+    sent_times: HashMap<String, chrono::DateTime<chrono::Utc>>,
+}
+
+impl Throttle {
+    // Whether the error wasn't sent within the interval, in which case it
+    // counts as sent now
+    fn allows(
+        &mut self,
+        error_key: &str,
+        interval: chrono::Duration,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> bool {
+        self.sent_times
+            .retain(|_, sent_time| now - *sent_time < interval);
+        if self.sent_times.contains_key(error_key) {
+            return false;
+        }
+        self.sent_times.insert(error_key.to_string(), now);
+        true
+    }
+}
+
+pub fn set_redis_client(redis_client: crate::redis_namespace::Client) {
+    *REDIS_CLIENT.lock() = Some(redis_client);
+}
+
+// None if reporting is turned off
+fn report_interval() -> Result<Option<chrono::Duration>, crate::BoxedError> {
+    let redis_client = REDIS_CLIENT.lock().clone();
+    match redis_client {
+        Some(redis_client) => {
+            let mut con = redis_client.get_connection()?;
+            crate::guild_config::error_report_interval(&mut con)
+        }
+        None => Ok(Some(chrono::Duration::minutes(
+            crate::guild_config::DEFAULT_ERROR_REPORT_INTERVAL_MINUTES,
+        ))),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorReport<'a> {
+    instance: &'a str,
+    severity: &'a str,
+    message: String,
+    backtrace: Option<String>,
+    context: std::collections::HashMap<&'a str, &'a str>,
+    time: String,
+}
+
+// Reports all panics in addition to the default panic output
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic_info| {
+        default_hook(panic_info);
+        let location = panic_info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()))
+            .unwrap_or_else(|| "unknown location".to_string());
+        let message = if let Some(message) = panic_info.payload().downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = panic_info.payload().downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "unknown panic".to_string()
+        };
+        // Mention the scheduled task that panicked, if any
+        let current_task = crate::task_supervisor::current_task();
+        let context = match current_task {
+            Some(ref task_name) => vec![("task", task_name.as_str())],
+            None => vec![],
+        };
+        send_report(
+            "panic",
+            format!("{} at {}", message, location),
+            Some(format!("{:?}", backtrace::Backtrace::new())),
+            &context,
+        );
+    }));
+}
+
+// Reports an error together with some context like the event series,
+// the command or the user that triggered it
+pub fn report(error: &crate::BoxedError, context: &[(&str, &str)]) {
+    send_report(
+        "error",
+        error.inner.to_string(),
+        Some(format!("{:?}", error.backtrace)),
+        context,
+    );
+}
+
+fn send_report(
+    severity: &str,
+    message: String,
+    backtrace: Option<String>,
+    context: &[(&str, &str)],
+) {
+    let webhook_url = match *WEBHOOK_URL {
+        Some(ref webhook_url) => webhook_url,
+        None => return,
+    };
+    let now = chrono::Utc::now();
+    let error_key = format!("{}:{}", severity, message);
+    let report = ErrorReport {
+        instance: &crate::leader_election::INSTANCE_ID,
+        severity: severity,
+        message: message,
+        backtrace: backtrace,
+        context: context.iter().cloned().collect(),
+        time: now.to_rfc3339(),
+    };
+    let body = match serde_json::to_string(&report) {
+        Ok(body) => body,
+        Err(err) => {
+            eprintln!("Could not serialize the error report: {}", err);
+            return;
+        }
+    };
+    // Don't block the caller (which might be panicking) on Redis or the
+    // webhook
+    std::thread::spawn(move || {
+        let interval = match report_interval() {
+            Ok(Some(interval)) => interval,
+            Ok(None) => return,
+            Err(err) => {
+                eprintln!("Could not read the error report interval: {}", err);
+                chrono::Duration::minutes(
+                    crate::guild_config::DEFAULT_ERROR_REPORT_INTERVAL_MINUTES,
+                )
+            }
+        };
+        if !THROTTLE.lock().allows(&error_key, interval, now) {
+            return;
+        }
+        let res = HTTP_CLIENT
+            .post(webhook_url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send();
+        if let Err(err) = res.and_then(|response| response.error_for_status()) {
+            eprintln!("Could not send the error report: {}", err);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_the_same_error_once_per_interval() {
+        let mut throttle = Throttle::default();
+        let interval = chrono::Duration::minutes(60);
+        let now = chrono::Utc::now();
+        assert!(throttle.allows("error:a", interval, now));
+        assert!(!throttle.allows("error:a", interval, now + chrono::Duration::minutes(59)));
+        assert!(throttle.allows("error:b", interval, now + chrono::Duration::minutes(59)));
+        assert!(throttle.allows("error:a", interval, now + chrono::Duration::minutes(60)));
+    }
+
+    #[test]
+    fn zero_interval_sends_every_error() {
+        let mut throttle = Throttle::default();
+        let now = chrono::Utc::now();
+        assert!(throttle.allows("error:a", chrono::Duration::zero(), now));
+        assert!(throttle.allows("error:a", chrono::Duration::zero(), now));
+    }
+}
@@ -0,0 +1,79 @@
+// Where a session takes place according to Meetup: online (possibly with a
+// link to the virtual table or call) or at a venue.
+// The online link is only shown in places that are restricted to the players
+// of a channel, since Meetup only reveals it to attendees as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Online { link: Option<String> },
+    Venue(String),
+    Unknown,
+}
+
+// Meetup's placeholder venue for online events
+const ONLINE_VENUE_NAME: &'static str = "Online event";
+
+impl Location {
+    pub fn from_meetup_event(event: &crate::meetup_api::Event) -> Location {
+        let is_online_venue = event
+            .venue
+            .as_ref()
+            .map(|venue| venue.name.eq_ignore_ascii_case(ONLINE_VENUE_NAME))
+            .unwrap_or(false);
+        if event.is_online_event || is_online_venue {
+            // Hosts usually put the link to the call into "How to find us"
+            let link = event
+                .how_to_find_us
+                .as_ref()
+                .map(|how_to_find_us| how_to_find_us.trim())
+                .filter(|how_to_find_us| {
+                    how_to_find_us.starts_with("https://") || how_to_find_us.starts_with("http://")
+                })
+                .map(|link| link.to_string());
+            return Location::Online { link: link };
+        }
+        match &event.venue {
+            Some(venue) => {
+                let parts: Vec<&str> = [
+                    Some(venue.name.as_str()),
+                    venue.address_1.as_ref().map(String::as_str),
+                    venue.city.as_ref().map(String::as_str),
+                ]
+                .iter()
+                .filter_map(|part| *part)
+                .map(str::trim)
+                .filter(|part| !part.is_empty())
+                .collect();
+                if parts.is_empty() {
+                    Location::Unknown
+                } else {
+                    Location::Venue(parts.join(", "))
+                }
+            }
+            None => Location::Unknown,
+        }
+    }
+
+    // Stored as the "is_online" and "location" fields of the event hash
+    pub fn from_redis_fields(is_online: Option<bool>, location: Option<String>) -> Location {
+        match (is_online, location) {
+            (Some(true), link) => Location::Online { link: link },
+            (_, Some(venue)) => Location::Venue(venue),
+            _ => Location::Unknown,
+        }
+    }
+
+    pub fn to_redis_fields(&self) -> (bool, Option<String>) {
+        match self {
+            Location::Online { link } => (true, link.clone()),
+            Location::Venue(venue) => (false, Some(venue.clone())),
+            Location::Unknown => (false, None),
+        }
+    }
+
+    pub fn is_online(&self) -> bool {
+        match self {
+            Location::Online { .. } => true,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::meetup_api;
+use crate::meetup_sync::BoxedFuture;
+use futures::{future, stream, Future, Stream};
+use serenity::prelude::RwLock;
+use simple_error::SimpleError;
+use std::sync::Arc;
+
+// Where the Meetup sync gets its events from. Meetup is the only source so far,
+// but other sources (e.g. Google Calendar or a JSON feed) only need to
+// implement this trait to feed the same pipeline: the events end up in Redis
+// as described in schema.md, and the Discord sync takes it from there.
+// The Meetup types double as the bot's event model, so other sources convert
+// their events to those. An event carries its hosts, the members that RSVP'd
+// are queried separately (and only for the next event of each series).
+pub trait EventSource: Send + Sync {
+    // For log messages
+    fn name(&self) -> &'static str;
+
+    fn user_ids(&self) -> UserIds {
+        UserIds::Meetup
+    }
+
+    // The upcoming events of all groups, including their hosts
+    fn get_upcoming_events(&self) -> BoxedStream<meetup_api::Event>;
+
+    fn get_rsvps(&self, urlname: &str, event_id: &str) -> BoxedFuture<Vec<meetup_api::RSVP>>;
+}
+
+pub type BoxedStream<T, E = crate::BoxedError> = Box<dyn Stream<Item = T, Error = E> + Send>;
+
+pub type SharedEventSource = Arc<dyn EventSource>;
+
+// What the user IDs of an event's hosts and RSVPs refer to. Sources without
+// accounts of their own resolve their users to Discord users directly, those
+// are stored apart from the Meetup users.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserIds {
+    Meetup,
+    Discord,
+}
+
+impl UserIds {
+    pub fn event_hosts_key(self, event_id: &str) -> String {
+        match self {
+            UserIds::Meetup => format!("meetup_event:{}:meetup_hosts", event_id),
+            UserIds::Discord => format!("meetup_event:{}:discord_hosts", event_id),
+        }
+    }
+
+    pub fn event_users_key(self, event_id: &str) -> String {
+        match self {
+            UserIds::Meetup => format!("meetup_event:{}:meetup_users", event_id),
+            UserIds::Discord => format!("meetup_event:{}:discord_users", event_id),
+        }
+    }
+}
+
+// The client is swapped when the organizer logs in again, so this only holds
+// on to the shared slot
+pub struct MeetupEventSource {
+    client: Arc<RwLock<Option<meetup_api::AsyncClient>>>,
+}
+
+impl MeetupEventSource {
+    pub fn new(client: Arc<RwLock<Option<meetup_api::AsyncClient>>>) -> Self {
+        MeetupEventSource { client: client }
+    }
+}
+
+impl EventSource for MeetupEventSource {
+    fn name(&self) -> &'static str {
+        "Meetup"
+    }
+
+    fn get_upcoming_events(&self) -> BoxedStream<meetup_api::Event> {
+        match *self.client.read() {
+            Some(ref client) => Box::new(
+                client
+                    .get_upcoming_events_all_groups()
+                    .from_err::<crate::BoxedError>(),
+            ),
+            None => Box::new(
+                stream::once::<meetup_api::Event, _>(Err(SimpleError::new(
+                    "Meetup API unavailable",
+                )))
+                .from_err::<crate::BoxedError>(),
+            ),
+        }
+    }
+
+    fn get_rsvps(&self, urlname: &str, event_id: &str) -> BoxedFuture<Vec<meetup_api::RSVP>> {
+        match *self.client.read() {
+            Some(ref client) => Box::new(
+                client
+                    .get_rsvps(urlname, event_id)
+                    .from_err::<crate::BoxedError>(),
+            ),
+            None => Box::new(
+                future::err::<Vec<meetup_api::RSVP>, _>(SimpleError::new("Meetup API unavailable"))
+                    .from_err::<crate::BoxedError>(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    Meetup,
+    GoogleCalendar,
+    Warhorn,
+    Eventbrite,
+}
+
+impl SourceKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SourceKind::Meetup => "meetup",
+            SourceKind::GoogleCalendar => "google_calendar",
+            SourceKind::Warhorn => "warhorn",
+            SourceKind::Eventbrite => "eventbrite",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SourceKind> {
+        [
+            SourceKind::Meetup,
+            SourceKind::GoogleCalendar,
+            SourceKind::Warhorn,
+            SourceKind::Eventbrite,
+        ]
+        .iter()
+        .find(|kind| kind.name().eq_ignore_ascii_case(name))
+        .cloned()
+    }
+
+    // Whether the bot was started with what this source needs
+    pub fn is_configured(&self) -> bool {
+        match self {
+            SourceKind::Meetup => true,
+            SourceKind::GoogleCalendar => std::env::var("GOOGLE_CALENDAR_ID").is_ok(),
+            SourceKind::Warhorn => crate::secrets::load("WARHORN_API_TOKEN")
+                .map(|token| token.is_some())
+                .unwrap_or(false),
+            SourceKind::Eventbrite => crate::secrets::load("EVENTBRITE_API_TOKEN")
+                .map(|token| token.is_some())
+                .unwrap_or(false),
+        }
+    }
+}
+
+// Uses the source that the "event_source" setting picks, such that the
+// organizers can switch sources without restarting the bot. The setting is
+// read on every call.
+pub struct ConfiguredEventSource {
+    meetup: MeetupEventSource,
+    google_calendar: Option<crate::google_calendar::GoogleCalendarEventSource>,
+    warhorn: Option<crate::warhorn::WarhornEventSource>,
+    eventbrite: Option<crate::eventbrite::EventbriteEventSource>,
+    redis_client: crate::redis_namespace::Client,
+}
+
+impl ConfiguredEventSource {
+    pub fn new(
+        meetup: MeetupEventSource,
+        google_calendar: Option<crate::google_calendar::GoogleCalendarEventSource>,
+        warhorn: Option<crate::warhorn::WarhornEventSource>,
+        eventbrite: Option<crate::eventbrite::EventbriteEventSource>,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Self {
+        ConfiguredEventSource {
+            meetup: meetup,
+            google_calendar: google_calendar,
+            warhorn: warhorn,
+            eventbrite: eventbrite,
+            redis_client: redis_client,
+        }
+    }
+
+    fn current(&self) -> Result<&dyn EventSource, crate::BoxedError> {
+        let mut redis_connection = self.redis_client.get_connection()?;
+        let kind = crate::guild_config::event_source(&mut redis_connection)?;
+        let source = match kind {
+            SourceKind::Meetup => Some(&self.meetup as &dyn EventSource),
+            SourceKind::GoogleCalendar => self
+                .google_calendar
+                .as_ref()
+                .map(|source| source as &dyn EventSource),
+            SourceKind::Warhorn => self
+                .warhorn
+                .as_ref()
+                .map(|source| source as &dyn EventSource),
+            SourceKind::Eventbrite => self
+                .eventbrite
+                .as_ref()
+                .map(|source| source as &dyn EventSource),
+        };
+        source.ok_or_else(|| {
+            SimpleError::new(format!(
+                "The event source {} is selected but not configured",
+                kind.name()
+            ))
+            .into()
+        })
+    }
+}
+
+impl EventSource for ConfiguredEventSource {
+    fn name(&self) -> &'static str {
+        match self.current() {
+            Ok(source) => source.name(),
+            Err(_) => "an unconfigured source",
+        }
+    }
+
+    fn user_ids(&self) -> UserIds {
+        match self.current() {
+            Ok(source) => source.user_ids(),
+            Err(_) => UserIds::Meetup,
+        }
+    }
+
+    fn get_upcoming_events(&self) -> BoxedStream<meetup_api::Event> {
+        match self.current() {
+            Ok(source) => source.get_upcoming_events(),
+            Err(err) => Box::new(stream::once::<meetup_api::Event, _>(Err(err))),
+        }
+    }
+
+    fn get_rsvps(&self, urlname: &str, event_id: &str) -> BoxedFuture<Vec<meetup_api::RSVP>> {
+        match self.current() {
+            Ok(source) => source.get_rsvps(urlname, event_id),
+            Err(err) => Box::new(future::err::<Vec<meetup_api::RSVP>, _>(err)),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use redis::Commands;
+use regex::Regex;
+
+// Tags describe what kind of game an event is (game system, language,
+// whether it is beginner-friendly, whether it is played online or in person)
+// and are derived from keywords in the event's title and description on Meetup.
+// The tags of a series are the union of the tags of its events. They show up
+// in the channel topic and the community digest, and users can subscribe to
+// them to hear about new games.
+// Organizers can change the keywords of a tag, add new tags or disable tags.
+const DEFAULT_TAG_RULES: &[(&'static str, &[&'static str])] = &[
+    (
+        "dnd5e",
+        &[
+            "d&d",
+            "dnd",
+            "dnd5e",
+            "d&d5e",
+            "5e",
+            "dungeons & dragons",
+            "dungeons and dragons",
+        ],
+    ),
+    ("coc", &["coc", "call of cthulhu", "cthulhu"]),
+    ("en", &["english", "[en]", "(en)"]),
+    ("de", &["german", "deutsch", "[de]", "(de)"]),
+    ("fr", &["french", "français", "francais", "[fr]", "(fr)"]),
+    (
+        "beginner",
+        &[
+            "beginner friendly",
+            "beginner-friendly",
+            "beginners welcome",
+            "new players welcome",
+            "no experience needed",
+            "no experience required",
+        ],
+    ),
+    ("online", &["online", "roll20", "foundry vtt", "virtual"]),
+    ("offline", &["in person", "in-person", "offline"]),
+];
+
+fn redis_tag_rules_key() -> String {
+    format!("guild:{}:tag_rules", crate::discord_sync::GUILD_ID.0)
+}
+
+pub struct TagRule {
+    pub tag: String,
+    pub keywords: Vec<String>,
+    regex: Regex,
+}
+
+pub struct TagRules {
+    rules: Vec<TagRule>,
+}
+
+impl TagRules {
+    // The default rules, overridden by the rules configured by the organizers.
+    // A configured rule without keywords disables the tag.
+    pub fn load<C: redis::ConnectionLike>(con: &mut C) -> Result<TagRules, crate::BoxedError> {
+        let mut configured_rules: Vec<(String, String)> = con.hgetall(redis_tag_rules_key())?;
+        let mut rules = vec![];
+        for &(tag, default_keywords) in DEFAULT_TAG_RULES {
+            let keywords = match configured_rules
+                .iter()
+                .position(|(configured_tag, _)| configured_tag == tag)
+            {
+                Some(index) => parse_keywords(&configured_rules.remove(index).1),
+                None => default_keywords
+                    .iter()
+                    .map(|keyword| keyword.to_string())
+                    .collect(),
+            };
+            if !keywords.is_empty() {
+                rules.push(TagRule::new(tag.to_string(), keywords)?);
+            }
+        }
+        configured_rules.sort_unstable();
+        for (tag, keywords) in configured_rules {
+            let keywords = parse_keywords(&keywords);
+            if !keywords.is_empty() {
+                rules.push(TagRule::new(tag, keywords)?);
+            }
+        }
+        Ok(TagRules { rules: rules })
+    }
+
+    pub fn rules(&self) -> &[TagRule] {
+        &self.rules
+    }
+
+    pub fn tags(&self) -> Vec<&str> {
+        self.rules.iter().map(|rule| rule.tag.as_str()).collect()
+    }
+
+    pub fn is_known_tag(&self, tag: &str) -> bool {
+        self.rules.iter().any(|rule| rule.tag == tag)
+    }
+
+    pub fn extract_tags(&self, title: &str, description: &str) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.regex.is_match(title) || rule.regex.is_match(description))
+            .map(|rule| rule.tag.clone())
+            .collect()
+    }
+}
+
+impl TagRule {
+    fn new(tag: String, keywords: Vec<String>) -> Result<TagRule, crate::BoxedError> {
+        // Keywords only match as whole words, such that e.g. "coc" does not
+        // match "cocoa"
+        let escaped_keywords: Vec<_> = keywords
+            .iter()
+            .map(|keyword| regex::escape(keyword))
+            .collect();
+        let regex = Regex::new(&format!(
+            r"(?i)(?:^|[^\w])(?:{})(?:$|[^\w])",
+            escaped_keywords.join("|")
+        ))?;
+        Ok(TagRule {
+            tag: tag,
+            keywords: keywords,
+            regex: regex,
+        })
+    }
+}
+
+// Keywords are stored and entered as a comma-separated list
+fn parse_keywords(keywords: &str) -> Vec<String> {
+    keywords
+        .split(',')
+        .map(|keyword| keyword.trim().to_lowercase())
+        .filter(|keyword| !keyword.is_empty())
+        .collect()
+}
+
+// Passing no keywords disables the tag
+pub fn set_rule<C: redis::ConnectionLike>(
+    con: &mut C,
+    tag: &str,
+    keywords: &str,
+) -> Result<Vec<String>, crate::BoxedError> {
+    let keywords = parse_keywords(keywords);
+    let _: () = con.hset(redis_tag_rules_key(), tag, keywords.join(","))?;
+    Ok(keywords)
+}
+
+// Restores the default keywords of a tag (or removes a custom tag)
+pub fn reset_rule<C: redis::ConnectionLike>(
+    con: &mut C,
+    tag: &str,
+) -> Result<(), crate::BoxedError> {
+    let _: () = con.hdel(redis_tag_rules_key(), tag)?;
+    Ok(())
+}
+
+pub fn series_tags<C: redis::ConnectionLike>(
+    con: &mut C,
+    series_id: &str,
+) -> Result<Vec<String>, crate::BoxedError> {
+    let mut tags: Vec<String> = con.smembers(format!("event_series:{}:tags", series_id))?;
+    tags.sort_unstable();
+    Ok(tags)
+}
+
+// Recomputes the tags of a series from the tags of its events
+pub fn update_series_tags<C: redis::ConnectionLike>(
+    con: &mut C,
+    series_id: &str,
+) -> Result<Vec<String>, crate::BoxedError> {
+    let redis_series_events_key = format!("event_series:{}:meetup_events", series_id);
+    let redis_series_tags_key = format!("event_series:{}:tags", series_id);
+    let event_ids: Vec<String> = con.smembers(&redis_series_events_key)?;
+    if event_ids.is_empty() {
+        let _: () = con.del(&redis_series_tags_key)?;
+    } else {
+        let redis_event_tags_keys: Vec<_> = event_ids
+            .iter()
+            .map(|event_id| format!("meetup_event:{}:tags", event_id))
+            .collect();
+        let _: () = con.sunionstore(&redis_series_tags_key, redis_event_tags_keys)?;
+    }
+    series_tags(con, series_id)
+}
@@ -0,0 +1,99 @@
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+// Key events are sent as JSON to the webhooks listed (comma-separated) in
+// EVENT_WEBHOOK_URLS, so that operators can hook up their own tooling.
+// If EVENT_WEBHOOK_SECRET is set, each request carries the hex encoded
+// HMAC-SHA256 of its body in the X-Signature header.
+// Delivery is best effort: failures are logged, not retried.
+lazy_static! {
+    static ref WEBHOOK_URLS: Vec<String> = std::env::var("EVENT_WEBHOOK_URLS")
+        .map(|urls| {
+            urls.split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    static ref SIGNING_KEY: Option<ring::hmac::SigningKey> =
+        crate::secrets::load("EVENT_WEBHOOK_SECRET")
+            .expect("Could not load EVENT_WEBHOOK_SECRET")
+            .map(|secret| ring::hmac::SigningKey::new(&ring::digest::SHA256, secret.as_bytes()));
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    ChannelCreated {
+        series_id: String,
+        channel_id: u64,
+    },
+    // The series' channel was deleted or archived
+    SeriesClosed {
+        series_id: Option<String>,
+        channel_id: u64,
+        archived: bool,
+    },
+    UserLinked {
+        discord_id: u64,
+        meetup_id: u64,
+    },
+    SyncFailed {
+        series_id: String,
+        consecutive_failures: u64,
+        error: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct Payload<'a> {
+    instance: &'a str,
+    time: String,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+// Returns immediately, the webhooks are called in the background
+pub fn emit(event: Event) {
+    if WEBHOOK_URLS.is_empty() {
+        return;
+    }
+    let payload = Payload {
+        instance: &crate::leader_election::INSTANCE_ID,
+        time: chrono::Utc::now().to_rfc3339(),
+        event: &event,
+    };
+    let body = match serde_json::to_string(&payload) {
+        Ok(body) => body,
+        Err(err) => {
+            eprintln!("Could not serialize the webhook event: {}", err);
+            return;
+        }
+    };
+    let signature = SIGNING_KEY.as_ref().map(|key| {
+        ring::hmac::sign(key, body.as_bytes())
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()
+    });
+    std::thread::spawn(move || {
+        for url in WEBHOOK_URLS.iter() {
+            let mut request = HTTP_CLIENT
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(ref signature) = signature {
+                request = request.header("X-Signature", format!("sha256={}", signature));
+            }
+            if let Err(err) = request
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                eprintln!("Could not send the event to webhook {}: {}", url, err);
+            }
+        }
+    });
+}
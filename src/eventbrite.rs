@@ -0,0 +1,280 @@
+use crate::event_source::{BoxedStream, EventSource, UserIds};
+use crate::meetup_api;
+use crate::meetup_sync::BoxedFuture;
+use futures::{stream, Future, Stream};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+// Eventbrite as an event source, for big public one-shots that sell tickets
+// there. The bot reads the upcoming events of one organization (set with
+// EVENTBRITE_ORGANIZATION_ID) and makes each of them an adventure of its own.
+// Eventbrite only knows the email addresses of the attendees, so those are
+// matched to the addresses that the Discord users registered. Since attendees
+// don't always remember which address they used, they can also claim their
+// ticket with the order number from their confirmation email
+// ("claim ticket <order number>" in a DM), which registers the order's
+// address for them.
+// Eventbrite has no hosts, organizers add them with "add host".
+const API_URL: &'static str = "https://www.eventbriteapi.com/v3";
+
+// Eventbrite's lists have different names for their items, but the same
+// pagination
+#[derive(Deserialize)]
+struct Page<T> {
+    #[serde(alias = "events", alias = "attendees")]
+    items: Vec<T>,
+    pagination: Pagination,
+}
+
+#[derive(Deserialize)]
+struct Pagination {
+    #[serde(default)]
+    has_more_items: bool,
+    #[serde(default)]
+    continuation: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Text {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EventbriteEvent {
+    id: String,
+    name: Text,
+    #[serde(default)]
+    description: Option<Text>,
+    url: String,
+    start: EventbriteTime,
+    #[serde(default)]
+    online_event: bool,
+    #[serde(default)]
+    capacity: Option<u32>,
+    #[serde(default)]
+    venue: Option<EventbriteVenue>,
+}
+
+#[derive(Deserialize)]
+struct EventbriteTime {
+    utc: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize)]
+struct EventbriteVenue {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    address: Option<EventbriteAddress>,
+}
+
+#[derive(Deserialize)]
+struct EventbriteAddress {
+    #[serde(default)]
+    address_1: Option<String>,
+    #[serde(default)]
+    city: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Attendee {
+    profile: Profile,
+    #[serde(default)]
+    cancelled: bool,
+    #[serde(default)]
+    refunded: bool,
+}
+
+#[derive(Deserialize)]
+struct Profile {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Order {
+    #[serde(default)]
+    email: Option<String>,
+}
+
+pub struct EventbriteEventSource {
+    api_token: String,
+    organization_id: String,
+    http_client: reqwest::r#async::Client,
+    redis_client: crate::redis_namespace::Client,
+}
+
+impl EventbriteEventSource {
+    pub fn new(
+        api_token: String,
+        organization_id: String,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Self {
+        EventbriteEventSource {
+            api_token: api_token,
+            organization_id: organization_id,
+            http_client: reqwest::r#async::Client::new(),
+            redis_client: redis_client,
+        }
+    }
+
+    // Follows the continuation tokens until the last page
+    fn get_all<T: DeserializeOwned + Send + 'static>(&self, url: String) -> BoxedStream<T> {
+        let http_client = self.http_client.clone();
+        let authorization = format!("Bearer {}", self.api_token);
+        let pages = stream::unfold(Some(None), move |continuation: Option<Option<String>>| {
+            let page_url = match continuation? {
+                Some(continuation) => format!("{}&continuation={}", url, continuation),
+                None => url.clone(),
+            };
+            let page = http_client
+                .get(&page_url)
+                .header(reqwest::header::AUTHORIZATION, authorization.as_str())
+                .send()
+                .and_then(|response| response.error_for_status())
+                .and_then(|mut response| response.json::<Page<T>>())
+                .from_err::<crate::BoxedError>()
+                .map(|page| {
+                    let next = match page.pagination.continuation {
+                        Some(continuation) if page.pagination.has_more_items => {
+                            Some(Some(continuation))
+                        }
+                        _ => None,
+                    };
+                    (stream::iter_ok::<_, crate::BoxedError>(page.items), next)
+                });
+            Some(page)
+        });
+        Box::new(pages.flatten())
+    }
+}
+
+impl EventSource for EventbriteEventSource {
+    fn name(&self) -> &'static str {
+        "Eventbrite"
+    }
+
+    fn user_ids(&self) -> UserIds {
+        UserIds::Discord
+    }
+
+    fn get_upcoming_events(&self) -> BoxedStream<meetup_api::Event> {
+        let url = format!(
+            "{}/organizations/{}/events/?status=live&time_filter=current_future&expand=venue",
+            API_URL, self.organization_id
+        );
+        let organization_id = self.organization_id.clone();
+        Box::new(
+            self.get_all::<EventbriteEvent>(url)
+                .map(move |event| to_meetup_event(event, &organization_id)),
+        )
+    }
+
+    fn get_rsvps(&self, _urlname: &str, event_id: &str) -> BoxedFuture<Vec<meetup_api::RSVP>> {
+        let url = format!(
+            "{}/events/{}/attendees/?status=attending",
+            API_URL,
+            event_id.trim_start_matches("eventbrite")
+        );
+        let redis_client = self.redis_client.clone();
+        let rsvps = self
+            .get_all::<Attendee>(url)
+            .collect()
+            .and_then(move |attendees| {
+                let mut con = redis_client.get_connection()?;
+                let mut rsvps: Vec<meetup_api::RSVP> = vec![];
+                for attendee in attendees {
+                    if attendee.cancelled || attendee.refunded {
+                        continue;
+                    }
+                    let email = match attendee.profile.email {
+                        Some(ref email) => email,
+                        None => continue,
+                    };
+                    let discord_id = match crate::email_registration::discord_user(email, &mut con)?
+                    {
+                        Some(discord_id) => discord_id,
+                        None => continue,
+                    };
+                    // One order can hold several tickets for the same address
+                    if rsvps.iter().any(|rsvp| rsvp.member.id == discord_id) {
+                        continue;
+                    }
+                    rsvps.push(meetup_api::RSVP {
+                        member: meetup_api::User {
+                            id: discord_id,
+                            name: attendee.profile.name.clone().unwrap_or_default(),
+                            photo: None,
+                            group_profile: None,
+                        },
+                        response: meetup_api::RSVPResponse::Yes,
+                        answers: vec![],
+                    });
+                }
+                Ok(rsvps)
+            });
+        Box::new(rsvps)
+    }
+}
+
+// Converts an Eventbrite event into the bot's event model, with the tag that
+// makes the Meetup sync start a new adventure for it
+fn to_meetup_event(event: EventbriteEvent, organization_id: &str) -> meetup_api::Event {
+    let venue = event.venue.and_then(|venue| {
+        let (address_1, city) = match venue.address {
+            Some(address) => (address.address_1, address.city),
+            None => (None, None),
+        };
+        venue.name.map(|name| meetup_api::Venue {
+            name: name,
+            address_1: address_1,
+            city: city,
+        })
+    });
+    let description = event
+        .description
+        .and_then(|description| description.text)
+        .unwrap_or_default();
+    meetup_api::Event {
+        // The series tags only allow letters and digits
+        id: format!("eventbrite{}", event.id)
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect(),
+        name: event.name.text.unwrap_or_default(),
+        time: event.start.utc,
+        event_hosts: vec![],
+        link: event.url,
+        group: meetup_api::Group {
+            urlname: organization_id.to_string(),
+        },
+        description: format!("[new adventure]\n\n{}", description),
+        rsvp_limit: event.capacity,
+        venue: venue,
+        is_online_event: event.online_event,
+        how_to_find_us: None,
+    }
+}
+
+// The address that an order was placed with, None if the order doesn't exist
+// or doesn't belong to the organization's events
+pub fn order_email(api_token: &str, order_id: &str) -> Result<Option<String>, crate::BoxedError> {
+    let url = format!("{}/orders/{}/", API_URL, order_id);
+    let mut response = reqwest::Client::new()
+        .get(&url)
+        .header(
+            reqwest::header::AUTHORIZATION,
+            format!("Bearer {}", api_token),
+        )
+        .send()?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND
+        || response.status() == reqwest::StatusCode::FORBIDDEN
+    {
+        return Ok(None);
+    }
+    let order: Order = response.error_for_status()?.json()?;
+    Ok(order.email)
+}
@@ -0,0 +1,96 @@
+use redis::Commands;
+use serenity::model::id::GuildId;
+
+// Optional subsystems that organizers can switch on and off per guild
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Feature {
+    WelcomeMessage,
+    ExpirationReminders,
+    ProfileSync,
+    GameMasterRole,
+    ScheduledEvents,
+    VoiceChannels,
+    LinkModeration,
+    MembershipSync,
+    ChannelApproval,
+    HostOrientation,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 10] = [
+        Feature::WelcomeMessage,
+        Feature::ExpirationReminders,
+        Feature::ProfileSync,
+        Feature::GameMasterRole,
+        Feature::ScheduledEvents,
+        Feature::VoiceChannels,
+        Feature::LinkModeration,
+        Feature::MembershipSync,
+        Feature::ChannelApproval,
+        Feature::HostOrientation,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::WelcomeMessage => "welcome_message",
+            Feature::ExpirationReminders => "expiration_reminders",
+            Feature::ProfileSync => "profile_sync",
+            Feature::GameMasterRole => "game_master_role",
+            Feature::ScheduledEvents => "scheduled_events",
+            Feature::VoiceChannels => "voice_channels",
+            Feature::LinkModeration => "link_moderation",
+            Feature::MembershipSync => "membership_sync",
+            Feature::ChannelApproval => "channel_approval",
+            Feature::HostOrientation => "host_orientation",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Feature> {
+        Feature::ALL
+            .iter()
+            .find(|feature| feature.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    // Whether the feature is enabled if no organizer has toggled it yet
+    pub fn enabled_by_default(&self) -> bool {
+        match self {
+            Feature::WelcomeMessage => true,
+            Feature::ExpirationReminders => true,
+            Feature::ProfileSync => true,
+            Feature::GameMasterRole => true,
+            // Requires the bot to have the "Manage Events" permission
+            Feature::ScheduledEvents => false,
+            // Not every community wants a voice channel per online game
+            Feature::VoiceChannels => false,
+            // Requires the bot to have the "Manage Messages" permission
+            Feature::LinkModeration => false,
+            // Requires a Meetup account that can see the member lists
+            Feature::MembershipSync => false,
+            // New series get their channel right away
+            Feature::ChannelApproval => false,
+            Feature::HostOrientation => true,
+        }
+    }
+}
+
+pub fn is_enabled<C: redis::ConnectionLike>(
+    con: &mut C,
+    guild_id: GuildId,
+    feature: Feature,
+) -> Result<bool, crate::BoxedError> {
+    let redis_guild_features_key = format!("guild:{}:feature_flags", guild_id.0);
+    let enabled: Option<bool> = con.hget(&redis_guild_features_key, feature.name())?;
+    Ok(enabled.unwrap_or(feature.enabled_by_default()))
+}
+
+pub fn set_enabled<C: redis::ConnectionLike>(
+    con: &mut C,
+    guild_id: GuildId,
+    feature: Feature,
+    enabled: bool,
+) -> Result<(), crate::BoxedError> {
+    let redis_guild_features_key = format!("guild:{}:feature_flags", guild_id.0);
+    let _: () = con.hset(&redis_guild_features_key, feature.name(), enabled)?;
+    Ok(())
+}
@@ -0,0 +1,439 @@
+use crate::event_source::{BoxedStream, EventSource, UserIds};
+use crate::meetup_api;
+use crate::meetup_sync::BoxedFuture;
+use futures::{future, stream, Future, Stream};
+use lazy_static::lazy_static;
+use redis::Commands;
+use serde::Deserialize;
+use serenity::prelude::Mutex;
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Google Calendar as an event source, for communities that plan their games
+// in a shared calendar instead of on Meetup. The bot reads the calendar with a
+// service account, so the calendar needs to be shared with the service
+// account's email address.
+// Events are grouped into series by their title: "[Campaign: <name>] ..." or
+// "[Adventure: <name>] ...". The first event of a name starts the series, the
+// later ones with the same name (ignoring case) continue it. Other events are
+// ignored, as are all-day events.
+// The calendar only knows the email addresses of the hosts (the creator of an
+// event) and the attendees, those are matched to the addresses that the
+// Discord users registered with "register email" (and an organizer approved).
+// Attendees count once they accepted the invitation.
+const CALENDAR_API_URL: &'static str = "https://www.googleapis.com/calendar/v3/calendars";
+const TOKEN_SCOPE: &'static str = "https://www.googleapis.com/auth/calendar.readonly";
+
+lazy_static! {
+    static ref SERIES_TITLE_REGEX: regex::Regex = regex::Regex::new(
+        r"^\s*\[\s*(?i)(?P<type>campaign|adventure)\s*:\s*(?P<name>[^\]]*[^\]\s])\s*\]"
+    )
+    .unwrap();
+}
+
+// The ID of the event that started the series with this name
+fn redis_series_first_event_key(series_name: &str) -> String {
+    format!("google_calendar:series:{}", series_name.to_lowercase())
+}
+
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventList {
+    #[serde(default)]
+    items: Vec<CalendarEvent>,
+    // Only set if there are more events than fit on one page
+    #[serde(default)]
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CalendarEvent {
+    id: String,
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    location: Option<String>,
+    #[serde(default)]
+    hangout_link: Option<String>,
+    html_link: String,
+    start: EventTime,
+    #[serde(default)]
+    creator: Option<Person>,
+    #[serde(default)]
+    attendees: Vec<Attendee>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventTime {
+    // All-day events only have a date
+    #[serde(default)]
+    date_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Deserialize)]
+struct Person {
+    #[serde(default)]
+    email: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Attendee {
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    display_name: Option<String>,
+    #[serde(default)]
+    response_status: String,
+    #[serde(default)]
+    organizer: bool,
+    // Meeting rooms and the like
+    #[serde(default)]
+    resource: bool,
+}
+
+pub fn validate_service_account_key(key: &str) -> Result<(), String> {
+    let key: ServiceAccountKey = serde_json::from_str(key)
+        .map_err(|err| format!("not a service account key file ({})", err))?;
+    rsa_key_pair(&key.private_key).map(|_| ())
+}
+
+fn rsa_key_pair(pem: &str) -> Result<ring::signature::RsaKeyPair, String> {
+    let base64_der: String = pem
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    let der = base64::decode(&base64_der)
+        .map_err(|err| format!("the private key is not valid base64 ({})", err))?;
+    ring::signature::RsaKeyPair::from_pkcs8(untrusted::Input::from(&der))
+        .map_err(|err| format!("the private key was rejected ({:?})", err))
+}
+
+fn base64url(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+pub struct GoogleCalendarEventSource {
+    calendar_id: String,
+    client_email: String,
+    token_uri: String,
+    key_pair: Arc<ring::signature::RsaKeyPair>,
+    http_client: reqwest::r#async::Client,
+    redis_client: crate::redis_namespace::Client,
+    // With the time it expires
+    access_token: Arc<Mutex<Option<(String, Instant)>>>,
+    // The calendar returns the attendees together with the events, so they
+    // are kept from the last query until the Meetup sync asks for them
+    rsvps: Arc<Mutex<HashMap<String, Vec<meetup_api::RSVP>>>>,
+}
+
+impl GoogleCalendarEventSource {
+    pub fn new(
+        calendar_id: String,
+        service_account_key: &str,
+        redis_client: crate::redis_namespace::Client,
+    ) -> Result<Self, crate::BoxedError> {
+        let key: ServiceAccountKey = serde_json::from_str(service_account_key)?;
+        let key_pair = rsa_key_pair(&key.private_key).map_err(SimpleError::new)?;
+        Ok(GoogleCalendarEventSource {
+            calendar_id: calendar_id,
+            client_email: key.client_email,
+            token_uri: key.token_uri,
+            key_pair: Arc::new(key_pair),
+            http_client: reqwest::r#async::Client::new(),
+            redis_client: redis_client,
+            access_token: Arc::new(Mutex::new(None)),
+            rsvps: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    // The signed JWT that the service account trades for an access token
+    fn token_request_assertion(&self) -> Result<String, crate::BoxedError> {
+        let now = chrono::Utc::now().timestamp();
+        let header = base64url(br#"{"alg":"RS256","typ":"JWT"}"#);
+        let claims = serde_json::json!({
+            "iss": self.client_email,
+            "scope": TOKEN_SCOPE,
+            "aud": self.token_uri,
+            "iat": now,
+            "exp": now + 3600,
+        });
+        let signing_input = format!("{}.{}", header, base64url(claims.to_string().as_bytes()));
+        let mut signature = vec![0; self.key_pair.public_modulus_len()];
+        self.key_pair
+            .sign(
+                &ring::signature::RSA_PKCS1_SHA256,
+                &ring::rand::SystemRandom::new(),
+                signing_input.as_bytes(),
+                &mut signature,
+            )
+            .map_err(|_| SimpleError::new("Could not sign the access token request"))?;
+        Ok(format!("{}.{}", signing_input, base64url(&signature)))
+    }
+
+    fn access_token(&self) -> BoxedFuture<String> {
+        if let Some((ref access_token, expires)) = *self.access_token.lock() {
+            if Instant::now() < expires {
+                return Box::new(future::ok(access_token.clone()));
+            }
+        }
+        let assertion = match self.token_request_assertion() {
+            Ok(assertion) => assertion,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        let cached_access_token = self.access_token.clone();
+        let access_token = self
+            .http_client
+            .post(&self.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json::<TokenResponse>())
+            .from_err::<crate::BoxedError>()
+            .map(move |token| {
+                // Renew the token a minute before it expires
+                let expires =
+                    Instant::now() + Duration::from_secs(token.expires_in.saturating_sub(60));
+                *cached_access_token.lock() = Some((token.access_token.clone(), expires));
+                token.access_token
+            });
+        Box::new(access_token)
+    }
+}
+
+impl EventSource for GoogleCalendarEventSource {
+    fn name(&self) -> &'static str {
+        "Google Calendar"
+    }
+
+    fn user_ids(&self) -> UserIds {
+        UserIds::Discord
+    }
+
+    fn get_upcoming_events(&self) -> BoxedStream<meetup_api::Event> {
+        let url = format!(
+            "{}/{}/events?singleEvents=true&orderBy=startTime&maxResults=250&timeMin={}",
+            CALENDAR_API_URL,
+            url::percent_encoding::utf8_percent_encode(
+                &self.calendar_id,
+                url::percent_encoding::PATH_SEGMENT_ENCODE_SET
+            ),
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        );
+        let http_client = self.http_client.clone();
+        let redis_client = self.redis_client.clone();
+        let calendar_id = self.calendar_id.clone();
+        let cached_rsvps = self.rsvps.clone();
+        let events = self
+            .access_token()
+            .and_then(move |access_token| {
+                // Follows the page tokens until all events are there
+                future::loop_fn(
+                    (None, vec![]),
+                    move |(page_token, mut calendar_events): (Option<String>, Vec<_>)| {
+                        let mut request = http_client.get(&url).header(
+                            reqwest::header::AUTHORIZATION,
+                            format!("Bearer {}", access_token),
+                        );
+                        if let Some(page_token) = page_token {
+                            request = request.query(&[("pageToken", page_token)]);
+                        }
+                        request
+                            .send()
+                            .and_then(|response| response.error_for_status())
+                            .and_then(|mut response| response.json::<EventList>())
+                            .from_err::<crate::BoxedError>()
+                            .map(move |event_list| {
+                                calendar_events.extend(event_list.items);
+                                match event_list.next_page_token {
+                                    Some(page_token) => {
+                                        future::Loop::Continue((Some(page_token), calendar_events))
+                                    }
+                                    None => future::Loop::Break(calendar_events),
+                                }
+                            })
+                    },
+                )
+            })
+            .and_then(move |calendar_events| {
+                let mut con = redis_client.get_connection()?;
+                let mut events = vec![];
+                let mut rsvps = HashMap::new();
+                for calendar_event in calendar_events {
+                    if let Some((event, event_rsvps)) =
+                        to_meetup_event(calendar_event, &calendar_id, &mut con)?
+                    {
+                        rsvps.insert(event.id.clone(), event_rsvps);
+                        events.push(event);
+                    }
+                }
+                *cached_rsvps.lock() = rsvps;
+                Ok(stream::iter_ok::<_, crate::BoxedError>(events))
+            })
+            .flatten_stream();
+        Box::new(events)
+    }
+
+    fn get_rsvps(&self, _urlname: &str, event_id: &str) -> BoxedFuture<Vec<meetup_api::RSVP>> {
+        match self.rsvps.lock().get(event_id) {
+            Some(rsvps) => Box::new(future::ok(rsvps.clone())),
+            None => Box::new(future::err(
+                SimpleError::new(format!("Unknown calendar event {}", event_id)).into(),
+            )),
+        }
+    }
+}
+
+fn discord_user(
+    email: &str,
+    name: Option<&str>,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Option<meetup_api::User>, crate::BoxedError> {
+    let discord_id = crate::email_registration::discord_user(email, con)?;
+    Ok(discord_id.map(|discord_id| meetup_api::User {
+        id: discord_id,
+        name: name.unwrap_or_default().to_string(),
+        photo: None,
+        group_profile: None,
+    }))
+}
+
+// Converts a calendar event into the bot's event model, with the tags that
+// the Meetup sync uses to put it into a series. Returns None for events that
+// don't belong to a series.
+fn to_meetup_event(
+    calendar_event: CalendarEvent,
+    calendar_id: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Option<(meetup_api::Event, Vec<meetup_api::RSVP>)>, crate::BoxedError> {
+    let time = match calendar_event.start.date_time {
+        Some(time) => time,
+        None => return Ok(None),
+    };
+    let (is_campaign, series_name) = match SERIES_TITLE_REGEX.captures(&calendar_event.summary) {
+        Some(captures) => (
+            captures["type"].eq_ignore_ascii_case("campaign"),
+            captures["name"].to_string(),
+        ),
+        None => return Ok(None),
+    };
+    // The series tags only allow letters and digits (recurring events have
+    // IDs like "<id>_20200101T180000Z")
+    let event_id: String = format!("gcal{}", calendar_event.id)
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    let redis_series_first_event_key = redis_series_first_event_key(&series_name);
+    let _: () = redis::cmd("SET")
+        .arg(&redis_series_first_event_key)
+        .arg(&event_id)
+        .arg("NX")
+        .query(con)?;
+    let first_event_id: String = con.get(&redis_series_first_event_key)?;
+    let series_tag = if first_event_id != event_id {
+        format!("[campaign {}]", first_event_id)
+    } else if is_campaign {
+        "[new campaign]".to_string()
+    } else {
+        "[new adventure]".to_string()
+    };
+    let mut event_hosts = vec![];
+    let host_emails = calendar_event
+        .creator
+        .iter()
+        .filter_map(|creator| creator.email.as_ref())
+        .chain(
+            calendar_event
+                .attendees
+                .iter()
+                .filter(|attendee| attendee.organizer)
+                .filter_map(|attendee| attendee.email.as_ref()),
+        );
+    for email in host_emails {
+        if let Some(host) = discord_user(email, None, con)? {
+            if !event_hosts
+                .iter()
+                .any(|other: &meetup_api::User| other.id == host.id)
+            {
+                event_hosts.push(host);
+            }
+        }
+    }
+    let mut rsvps = vec![];
+    for attendee in &calendar_event.attendees {
+        let response = match attendee.response_status.as_str() {
+            "accepted" => meetup_api::RSVPResponse::Yes,
+            "declined" => meetup_api::RSVPResponse::No,
+            _ => continue,
+        };
+        let email = match attendee.email {
+            Some(ref email) if !attendee.resource => email,
+            _ => continue,
+        };
+        let name = attendee.display_name.as_ref().map(String::as_str);
+        if let Some(member) = discord_user(email, name, con)? {
+            rsvps.push(meetup_api::RSVP {
+                member: member,
+                response: response,
+                answers: vec![],
+            });
+        }
+    }
+    // Online games put the link to the call into the location
+    let location = calendar_event
+        .location
+        .map(|location| location.trim().to_string())
+        .filter(|location| !location.is_empty());
+    let link = location
+        .as_ref()
+        .filter(|location| location.starts_with("https://") || location.starts_with("http://"))
+        .cloned()
+        .or(calendar_event.hangout_link);
+    let venue = match (&link, location) {
+        (None, Some(location)) => Some(meetup_api::Venue {
+            name: location,
+            address_1: None,
+            city: None,
+        }),
+        _ => None,
+    };
+    let event = meetup_api::Event {
+        id: event_id,
+        name: calendar_event.summary,
+        time: time,
+        event_hosts: event_hosts,
+        link: calendar_event.html_link,
+        group: meetup_api::Group {
+            urlname: calendar_id.to_string(),
+        },
+        description: format!("{}\n\n{}", series_tag, calendar_event.description),
+        rsvp_limit: None,
+        venue: venue,
+        is_online_event: link.is_some(),
+        how_to_find_us: link,
+    };
+    Ok(Some((event, rsvps)))
+}
@@ -0,0 +1,526 @@
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, RoleId};
+
+// Runtime settings that organizers can change with the "config" command.
+// A value stored in Redis overrides the built-in default (the constants in
+// discord_sync and the hard-coded intervals), removing it restores the
+// default. Every change is recorded in an audit log.
+const MAX_AUDIT_LOG_ENTRIES: isize = 200;
+pub const MIN_SYNC_INTERVAL_MINUTES: i64 = 5;
+pub const MAX_SYNC_INTERVAL_MINUTES: i64 = 24 * 60;
+pub const MAX_EXPIRATION_GRACE_HOURS: i64 = 30 * 24;
+pub const MAX_MEMBERSHIP_GRACE_DAYS: i64 = 365;
+pub const DEFAULT_SYNC_INTERVAL_MINUTES: i64 = 15;
+pub const MAX_ERROR_REPORT_INTERVAL_MINUTES: i64 = 24 * 60;
+pub const DEFAULT_ERROR_REPORT_INTERVAL_MINUTES: i64 = 60;
+const DEFAULT_EXPIRATION_GRACE_HOURS: i64 = 24;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConfigKey {
+    // Minutes between two recurring Discord syncs
+    SyncInterval,
+    // Hours after the last session that a channel expires
+    ExpirationGrace,
+    OneShotCategory,
+    CampaignCategory,
+    // Where organizer notifications are posted
+    StaffChannel,
+    // Where the Meetup sync gets the events from
+    EventSource,
+    // The slug of the event on Warhorn, for the Warhorn event source
+    WarhornEvent,
+    // Whether and how the Meetup first name goes into the Discord nickname
+    MeetupNicknames,
+    // Days after leaving the Meetup groups that members lose their channels
+    MembershipGrace,
+    // The Meetup Pro group whose membership dues gate the dues roles
+    DuesGroup,
+    // When the roles of archived channels are replaced with member overwrites
+    RolePruning,
+    // Whether new channels get roles or member overwrites
+    ChannelAccess,
+    // The classes of commands that are cleaned up after, see discord_tidy
+    TidyCommands,
+    // The role that the hosts of upcoming sessions get
+    GameMasterRole,
+    // Whether the bot's status shows the next event, see discord_presence
+    NextEventStatus,
+    // Minutes that the same error is only sent to the error reporting
+    // webhook once, see error_reporting
+    ErrorReportInterval,
+}
+
+impl ConfigKey {
+    pub const ALL: [ConfigKey; 16] = [
+        ConfigKey::SyncInterval,
+        ConfigKey::ExpirationGrace,
+        ConfigKey::OneShotCategory,
+        ConfigKey::CampaignCategory,
+        ConfigKey::StaffChannel,
+        ConfigKey::EventSource,
+        ConfigKey::WarhornEvent,
+        ConfigKey::MeetupNicknames,
+        ConfigKey::MembershipGrace,
+        ConfigKey::DuesGroup,
+        ConfigKey::RolePruning,
+        ConfigKey::ChannelAccess,
+        ConfigKey::TidyCommands,
+        ConfigKey::GameMasterRole,
+        ConfigKey::NextEventStatus,
+        ConfigKey::ErrorReportInterval,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigKey::SyncInterval => "sync_interval_minutes",
+            ConfigKey::ExpirationGrace => "expiration_grace_hours",
+            ConfigKey::OneShotCategory => "one_shot_category",
+            ConfigKey::CampaignCategory => "campaign_category",
+            ConfigKey::StaffChannel => "staff_channel",
+            ConfigKey::EventSource => "event_source",
+            ConfigKey::WarhornEvent => "warhorn_event",
+            ConfigKey::MeetupNicknames => "meetup_nicknames",
+            ConfigKey::MembershipGrace => "membership_grace_days",
+            ConfigKey::DuesGroup => "dues_group",
+            ConfigKey::RolePruning => "role_pruning",
+            ConfigKey::ChannelAccess => "channel_access",
+            ConfigKey::TidyCommands => "tidy_commands",
+            ConfigKey::GameMasterRole => "game_master_role",
+            ConfigKey::NextEventStatus => "next_event_status",
+            ConfigKey::ErrorReportInterval => "error_report_interval_minutes",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ConfigKey> {
+        ConfigKey::ALL
+            .iter()
+            .find(|key| key.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    pub fn is_channel(&self) -> bool {
+        match self {
+            ConfigKey::OneShotCategory | ConfigKey::CampaignCategory | ConfigKey::StaffChannel => {
+                true
+            }
+            ConfigKey::SyncInterval
+            | ConfigKey::ExpirationGrace
+            | ConfigKey::EventSource
+            | ConfigKey::WarhornEvent
+            | ConfigKey::MeetupNicknames
+            | ConfigKey::MembershipGrace
+            | ConfigKey::DuesGroup
+            | ConfigKey::RolePruning
+            | ConfigKey::ChannelAccess
+            | ConfigKey::TidyCommands
+            | ConfigKey::GameMasterRole
+            | ConfigKey::NextEventStatus
+            | ConfigKey::ErrorReportInterval => false,
+        }
+    }
+
+    // The built-in value, for display
+    pub fn default_value(&self) -> Option<String> {
+        match self {
+            ConfigKey::SyncInterval => Some(DEFAULT_SYNC_INTERVAL_MINUTES.to_string()),
+            ConfigKey::ExpirationGrace => Some(DEFAULT_EXPIRATION_GRACE_HOURS.to_string()),
+            ConfigKey::OneShotCategory => {
+                crate::discord_sync::ONE_SHOT_CATEGORY_ID.map(|id| id.0.to_string())
+            }
+            ConfigKey::CampaignCategory => {
+                crate::discord_sync::CAMPAIGN_CATEGORY_ID.map(|id| id.0.to_string())
+            }
+            ConfigKey::StaffChannel => {
+                crate::discord_sync::STAFF_CHANNEL_ID.map(|id| id.0.to_string())
+            }
+            ConfigKey::EventSource => {
+                Some(crate::event_source::SourceKind::Meetup.name().to_string())
+            }
+            ConfigKey::WarhornEvent => None,
+            ConfigKey::MeetupNicknames => Some(
+                crate::discord_nicknames::NicknameStyle::Off
+                    .name()
+                    .to_string(),
+            ),
+            // Members keep their channels
+            ConfigKey::MembershipGrace => Some("off".to_string()),
+            // Dues are not checked
+            ConfigKey::DuesGroup => Some("none".to_string()),
+            ConfigKey::RolePruning => Some(
+                crate::discord_role_limit::PruningPolicy::Off
+                    .name()
+                    .to_string(),
+            ),
+            ConfigKey::ChannelAccess => Some(
+                crate::discord_channel_access::AccessMode::Roles
+                    .name()
+                    .to_string(),
+            ),
+            ConfigKey::TidyCommands => Some("off".to_string()),
+            ConfigKey::GameMasterRole => Some(
+                crate::discord_sync::GAME_MASTER_ID
+                    .map(|id| id.0.to_string())
+                    .unwrap_or_else(|| "none".to_string()),
+            ),
+            ConfigKey::NextEventStatus => Some("on".to_string()),
+            ConfigKey::ErrorReportInterval => {
+                Some(DEFAULT_ERROR_REPORT_INTERVAL_MINUTES.to_string())
+            }
+        }
+    }
+
+    // Checks the value given to the "config set" command and normalizes it
+    // for storage. Channels and roles can be given as a mention, an ID or
+    // "none". Whether they actually exist is up to the caller to check.
+    pub fn parse_value(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        match self {
+            ConfigKey::SyncInterval => value
+                .parse::<i64>()
+                .ok()
+                .filter(|minutes| {
+                    *minutes >= MIN_SYNC_INTERVAL_MINUTES && *minutes <= MAX_SYNC_INTERVAL_MINUTES
+                })
+                .map(|minutes| minutes.to_string()),
+            ConfigKey::ExpirationGrace => value
+                .parse::<i64>()
+                .ok()
+                .filter(|hours| *hours >= 0 && *hours <= MAX_EXPIRATION_GRACE_HOURS)
+                .map(|hours| hours.to_string()),
+            ConfigKey::OneShotCategory | ConfigKey::CampaignCategory | ConfigKey::StaffChannel => {
+                if value.eq_ignore_ascii_case("none") {
+                    return Some("none".to_string());
+                }
+                let id = value.trim_start_matches("<#").trim_end_matches('>');
+                id.parse::<u64>().ok().map(|id| id.to_string())
+            }
+            ConfigKey::EventSource => crate::event_source::SourceKind::from_name(value)
+                .map(|kind| kind.name().to_string()),
+            ConfigKey::WarhornEvent => Some(value.to_lowercase()).filter(|slug| {
+                !slug.is_empty()
+                    && slug
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            }),
+            ConfigKey::MeetupNicknames => crate::discord_nicknames::NicknameStyle::from_name(value)
+                .map(|style| style.name().to_string()),
+            ConfigKey::MembershipGrace => {
+                if value.eq_ignore_ascii_case("off") {
+                    return Some("off".to_string());
+                }
+                value
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|days| *days >= 0 && *days <= MAX_MEMBERSHIP_GRACE_DAYS)
+                    .map(|days| days.to_string())
+            }
+            ConfigKey::DuesGroup => {
+                if value.eq_ignore_ascii_case("none") {
+                    return Some("none".to_string());
+                }
+                crate::meetup_api::URLNAMES
+                    .iter()
+                    .find(|urlname| urlname.eq_ignore_ascii_case(value))
+                    .map(|urlname| urlname.to_string())
+            }
+            ConfigKey::RolePruning => crate::discord_role_limit::PruningPolicy::from_name(value)
+                .map(|policy| policy.name().to_string()),
+            ConfigKey::ChannelAccess => crate::discord_channel_access::AccessMode::from_name(value)
+                .map(|mode| mode.name().to_string()),
+            ConfigKey::TidyCommands => crate::discord_tidy::parse_classes(value)
+                .map(|classes| crate::discord_tidy::classes_name(&classes)),
+            ConfigKey::GameMasterRole => {
+                if value.eq_ignore_ascii_case("none") {
+                    return Some("none".to_string());
+                }
+                let id = value.trim_start_matches("<@&").trim_end_matches('>');
+                id.parse::<u64>().ok().map(|id| id.to_string())
+            }
+            ConfigKey::NextEventStatus => {
+                if value.eq_ignore_ascii_case("on") || value.eq_ignore_ascii_case("off") {
+                    Some(value.to_lowercase())
+                } else {
+                    None
+                }
+            }
+            ConfigKey::ErrorReportInterval => {
+                if value.eq_ignore_ascii_case("off") {
+                    return Some("off".to_string());
+                }
+                value
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|minutes| {
+                        *minutes >= 0 && *minutes <= MAX_ERROR_REPORT_INTERVAL_MINUTES
+                    })
+                    .map(|minutes| minutes.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub time: String,
+    pub discord_user: u64,
+    pub key: String,
+    // None means the default
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+fn redis_config_key() -> String {
+    format!("guild:{}:config", crate::discord_sync::GUILD_ID.0)
+}
+
+fn redis_audit_log_key() -> String {
+    format!("guild:{}:config_audit_log", crate::discord_sync::GUILD_ID.0)
+}
+
+// The value that overrides the default, if any
+pub fn get_override<C: redis::ConnectionLike>(
+    con: &mut C,
+    key: ConfigKey,
+) -> Result<Option<String>, crate::BoxedError> {
+    Ok(con.hget(redis_config_key(), key.name())?)
+}
+
+// Passing None restores the default. The change and the audit entry are
+// written together.
+pub fn set<C: redis::ConnectionLike>(
+    con: &mut C,
+    key: ConfigKey,
+    value: Option<&str>,
+    discord_user: u64,
+) -> Result<(), crate::BoxedError> {
+    let old_value = get_override(con, key)?;
+    let entry = AuditEntry {
+        time: chrono::Utc::now().to_rfc3339(),
+        discord_user: discord_user,
+        key: key.name().to_string(),
+        old_value: old_value,
+        new_value: value.map(str::to_string),
+    };
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    match value {
+        Some(value) => pipe.hset(redis_config_key(), key.name(), value),
+        None => pipe.hdel(redis_config_key(), key.name()),
+    }
+    .ignore()
+    .lpush(redis_audit_log_key(), serde_json::to_string(&entry)?)
+    .ignore()
+    .ltrim(redis_audit_log_key(), 0, MAX_AUDIT_LOG_ENTRIES - 1)
+    .ignore();
+    let _: () = pipe.query(con)?;
+    Ok(())
+}
+
+// The most recent changes first
+pub fn audit_log<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Vec<AuditEntry>, crate::BoxedError> {
+    let entries: Vec<String> = con.lrange(redis_audit_log_key(), 0, -1)?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| match serde_json::from_str(entry) {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                eprintln!(
+                    "Could not parse the config audit entry \"{}\": {}",
+                    entry, err
+                );
+                None
+            }
+        })
+        .collect())
+}
+
+// The accessors below are what the rest of the bot uses. Invalid overrides
+// (which the "config set" command doesn't let through) fall back to the
+// default.
+
+fn number_or<C: redis::ConnectionLike>(
+    con: &mut C,
+    key: ConfigKey,
+    default: i64,
+) -> Result<i64, crate::BoxedError> {
+    Ok(get_override(con, key)?
+        .and_then(|value| key.parse_value(&value))
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default))
+}
+
+fn channel_or<C: redis::ConnectionLike>(
+    con: &mut C,
+    key: ConfigKey,
+    default: Option<ChannelId>,
+) -> Result<Option<ChannelId>, crate::BoxedError> {
+    let value = get_override(con, key)?.and_then(|value| key.parse_value(&value));
+    Ok(match value.as_ref().map(String::as_str) {
+        Some("none") => None,
+        Some(id) => id.parse().ok().map(ChannelId).or(default),
+        None => default,
+    })
+}
+
+pub fn sync_interval<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<chrono::Duration, crate::BoxedError> {
+    let minutes = number_or(con, ConfigKey::SyncInterval, DEFAULT_SYNC_INTERVAL_MINUTES)?;
+    Ok(chrono::Duration::minutes(minutes))
+}
+
+pub fn expiration_grace<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<chrono::Duration, crate::BoxedError> {
+    let hours = number_or(
+        con,
+        ConfigKey::ExpirationGrace,
+        DEFAULT_EXPIRATION_GRACE_HOURS,
+    )?;
+    Ok(chrono::Duration::hours(hours))
+}
+
+pub fn one_shot_category<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<ChannelId>, crate::BoxedError> {
+    channel_or(
+        con,
+        ConfigKey::OneShotCategory,
+        crate::discord_sync::ONE_SHOT_CATEGORY_ID,
+    )
+}
+
+pub fn campaign_category<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<ChannelId>, crate::BoxedError> {
+    channel_or(
+        con,
+        ConfigKey::CampaignCategory,
+        crate::discord_sync::CAMPAIGN_CATEGORY_ID,
+    )
+}
+
+pub fn staff_channel<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<ChannelId>, crate::BoxedError> {
+    channel_or(
+        con,
+        ConfigKey::StaffChannel,
+        crate::discord_sync::STAFF_CHANNEL_ID,
+    )
+}
+
+pub fn event_source<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<crate::event_source::SourceKind, crate::BoxedError> {
+    Ok(get_override(con, ConfigKey::EventSource)?
+        .and_then(|value| crate::event_source::SourceKind::from_name(&value))
+        .unwrap_or(crate::event_source::SourceKind::Meetup))
+}
+
+pub fn warhorn_event<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<String>, crate::BoxedError> {
+    Ok(get_override(con, ConfigKey::WarhornEvent)?
+        .and_then(|value| ConfigKey::WarhornEvent.parse_value(&value)))
+}
+
+pub fn meetup_nicknames<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<crate::discord_nicknames::NicknameStyle, crate::BoxedError> {
+    Ok(get_override(con, ConfigKey::MeetupNicknames)?
+        .and_then(|value| crate::discord_nicknames::NicknameStyle::from_name(&value))
+        .unwrap_or(crate::discord_nicknames::NicknameStyle::Off))
+}
+
+// None if members keep their channels after leaving the Meetup groups
+pub fn membership_grace<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<chrono::Duration>, crate::BoxedError> {
+    Ok(get_override(con, ConfigKey::MembershipGrace)?
+        .and_then(|value| ConfigKey::MembershipGrace.parse_value(&value))
+        .and_then(|value| value.parse().ok())
+        .map(chrono::Duration::days))
+}
+
+// None if dues are not checked
+pub fn dues_group<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<&'static str>, crate::BoxedError> {
+    Ok(get_override(con, ConfigKey::DuesGroup)?.and_then(|value| {
+        crate::meetup_api::URLNAMES
+            .iter()
+            .find(|urlname| urlname.eq_ignore_ascii_case(&value))
+            .cloned()
+    }))
+}
+
+pub fn role_pruning<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<crate::discord_role_limit::PruningPolicy, crate::BoxedError> {
+    Ok(get_override(con, ConfigKey::RolePruning)?
+        .and_then(|value| crate::discord_role_limit::PruningPolicy::from_name(&value))
+        .unwrap_or(crate::discord_role_limit::PruningPolicy::Off))
+}
+
+pub fn channel_access<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<crate::discord_channel_access::AccessMode, crate::BoxedError> {
+    Ok(get_override(con, ConfigKey::ChannelAccess)?
+        .and_then(|value| crate::discord_channel_access::AccessMode::from_name(&value))
+        .unwrap_or(crate::discord_channel_access::AccessMode::Roles))
+}
+
+pub fn tidy_commands<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Vec<crate::discord_tidy::CommandClass>, crate::BoxedError> {
+    Ok(get_override(con, ConfigKey::TidyCommands)?
+        .and_then(|value| crate::discord_tidy::parse_classes(&value))
+        .unwrap_or_default())
+}
+
+// None if hosts don't get a game master role
+pub fn game_master_role<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<RoleId>, crate::BoxedError> {
+    let value = get_override(con, ConfigKey::GameMasterRole)?
+        .and_then(|value| ConfigKey::GameMasterRole.parse_value(&value));
+    Ok(match value.as_ref().map(String::as_str) {
+        Some("none") => None,
+        Some(id) => id
+            .parse()
+            .ok()
+            .map(RoleId)
+            .or(crate::discord_sync::GAME_MASTER_ID),
+        None => crate::discord_sync::GAME_MASTER_ID,
+    })
+}
+
+pub fn next_event_status<C: redis::ConnectionLike>(con: &mut C) -> Result<bool, crate::BoxedError> {
+    Ok(get_override(con, ConfigKey::NextEventStatus)?
+        .as_ref()
+        .map(String::as_str)
+        != Some("off"))
+}
+
+// None if no errors are sent to the webhook
+pub fn error_report_interval<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<chrono::Duration>, crate::BoxedError> {
+    let value = get_override(con, ConfigKey::ErrorReportInterval)?
+        .and_then(|value| ConfigKey::ErrorReportInterval.parse_value(&value));
+    Ok(match value.as_ref().map(String::as_str) {
+        Some("off") => None,
+        Some(minutes) => Some(chrono::Duration::minutes(
+            minutes
+                .parse()
+                .unwrap_or(DEFAULT_ERROR_REPORT_INTERVAL_MINUTES),
+        )),
+        None => Some(chrono::Duration::minutes(
+            DEFAULT_ERROR_REPORT_INTERVAL_MINUTES,
+        )),
+    })
+}
@@ -0,0 +1,129 @@
+use redis::Commands;
+use serenity::model::id::{ChannelId, UserId};
+
+// Anybody who can manage roles could give themselves a channel's host role
+// and thereby gain the bot's host commands in that channel. Therefore, host
+// commands double-check that the user is a host according to our own data:
+// either a Meetup host of one of the channel's events or a host that was
+// added with the "add host" command.
+// Organizers decide per guild whether mismatches are ignored, reported to
+// the organizers or whether such users are refused.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Off,
+    Alert,
+    Enforce,
+}
+
+impl Mode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Off => "off",
+            Mode::Alert => "alert",
+            Mode::Enforce => "enforce",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Mode> {
+        [Mode::Off, Mode::Alert, Mode::Enforce]
+            .iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+// Hosts that were added before this check existed are not recorded as manual
+// hosts, so by default mismatches are only reported
+const DEFAULT_MODE: Mode = Mode::Alert;
+
+// Don't report the same user and channel more than once a day
+pub const ALERT_COOLDOWN_SECS: usize = 24 * 60 * 60;
+
+pub fn mode<C: redis::ConnectionLike>(con: &mut C) -> Result<Mode, crate::BoxedError> {
+    let redis_host_verification_key = format!(
+        "guild:{}:host_verification",
+        crate::discord_sync::GUILD_ID.0
+    );
+    let mode: Option<String> = con.get(&redis_host_verification_key)?;
+    Ok(mode
+        .as_ref()
+        .and_then(|mode| Mode::from_name(mode))
+        .unwrap_or(DEFAULT_MODE))
+}
+
+pub fn set_mode<C: redis::ConnectionLike>(
+    con: &mut C,
+    mode: Mode,
+) -> Result<(), crate::BoxedError> {
+    let redis_host_verification_key = format!(
+        "guild:{}:host_verification",
+        crate::discord_sync::GUILD_ID.0
+    );
+    let _: () = con.set(&redis_host_verification_key, mode.name())?;
+    Ok(())
+}
+
+// Whether the user is a host of the channel according to Redis
+pub fn is_verified_host<C: redis::ConnectionLike>(
+    con: &mut C,
+    channel_id: ChannelId,
+    user_id: UserId,
+) -> Result<bool, crate::BoxedError> {
+    let redis_channel_manual_hosts_key = format!("discord_channel:{}:manual_hosts", channel_id.0);
+    let is_manual_host: bool = con.sismember(&redis_channel_manual_hosts_key, user_id.0)?;
+    if is_manual_host {
+        return Ok(true);
+    }
+    let meetup_id = crate::identity::meetup_id(user_id.0, con)?;
+    let meetup_id = match meetup_id {
+        Some(meetup_id) => meetup_id,
+        None => return Ok(false),
+    };
+    let redis_channel_series_key = format!("discord_channel:{}:event_series", channel_id.0);
+    let series_id: Option<String> = con.get(&redis_channel_series_key)?;
+    let series_id = match series_id {
+        Some(series_id) => series_id,
+        None => return Ok(false),
+    };
+    let redis_series_events_key = format!("event_series:{}:meetup_events", series_id);
+    let event_ids: Vec<String> = con.smembers(&redis_series_events_key)?;
+    for event_id in event_ids {
+        let redis_event_hosts_key = format!("meetup_event:{}:meetup_hosts", event_id);
+        let is_event_host: bool = con.sismember(&redis_event_hosts_key, meetup_id)?;
+        if is_event_host {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Decides whether a user with the channel's host role may use host commands
+pub fn check_host(
+    channel_id: ChannelId,
+    user_id: UserId,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<bool, crate::BoxedError> {
+    let mode = mode(redis_connection)?;
+    if mode == Mode::Off || is_verified_host(redis_connection, channel_id, user_id)? {
+        return Ok(true);
+    }
+    let redis_alert_key = format!(
+        "host_verification:discord_channel:{}:discord_user:{}:alerted",
+        channel_id.0, user_id.0
+    );
+    let already_alerted: bool = redis_connection.exists(&redis_alert_key)?;
+    if !already_alerted {
+        let _: () = redis_connection.set_ex(&redis_alert_key, true, ALERT_COOLDOWN_SECS)?;
+        crate::discord_notifications::notify_organizers(
+            discord_api,
+            &crate::strings::HOST_VERIFICATION_MISMATCH(
+                user_id.0,
+                channel_id.0,
+                mode == Mode::Enforce,
+            ),
+            redis_connection,
+        )?;
+    }
+    Ok(mode != Mode::Enforce)
+}
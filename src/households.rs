@@ -0,0 +1,134 @@
+use redis::{Commands, PipelineCommands};
+
+// Households share one Meetup account between several Discord users, e.g.
+// couples that RSVP together from one account. The Discord user that linked
+// the Meetup account asks to share it with "share meetup @user", and once an
+// organizer approved it with "approve household @user", the Discord sync
+// treats the other user like the account's owner: they get the same channel
+// roles.
+// The household belongs to the Meetup account, so it is dissolved when the
+// account is unlinked.
+pub const REDIS_PENDING_REQUESTS_KEY: &'static str = "household_requests";
+
+pub fn redis_household_members_key(meetup_id: u64) -> String {
+    format!("meetup_user:{}:household_members", meetup_id)
+}
+
+pub fn redis_household_meetup_user_key(discord_id: u64) -> String {
+    format!("discord_user:{}:household_meetup_user", discord_id)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    Requested { meetup_id: u64 },
+    // The requesting user has no Meetup account to share
+    NotLinked,
+    // The other user already uses a Meetup account, their own or a shared one
+    MemberLinked,
+}
+
+pub fn request<C: redis::ConnectionLike>(
+    owner_id: u64,
+    member_id: u64,
+    con: &mut C,
+) -> Result<Request, crate::BoxedError> {
+    let meetup_id = match crate::identity::meetup_id(owner_id, con)? {
+        Some(meetup_id) => meetup_id,
+        None => return Ok(Request::NotLinked),
+    };
+    if owner_id == member_id
+        || crate::identity::meetup_id(member_id, con)?.is_some()
+        || household_meetup_user(member_id, con)?.is_some()
+    {
+        return Ok(Request::MemberLinked);
+    }
+    let _: () = con.hset(REDIS_PENDING_REQUESTS_KEY, member_id, meetup_id)?;
+    Ok(Request::Requested {
+        meetup_id: meetup_id,
+    })
+}
+
+// (Discord user, Meetup account they asked to share)
+pub fn pending_requests<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Vec<(u64, u64)>, crate::BoxedError> {
+    let mut requests: Vec<(u64, u64)> = con.hgetall(REDIS_PENDING_REQUESTS_KEY)?;
+    requests.sort_unstable();
+    Ok(requests)
+}
+
+// Returns the Meetup account that is shared now, None if there was no request
+// or the account was unlinked in the meantime
+pub fn approve<C: redis::ConnectionLike>(
+    member_id: u64,
+    con: &mut C,
+) -> Result<Option<u64>, crate::BoxedError> {
+    let meetup_id: Option<u64> = con.hget(REDIS_PENDING_REQUESTS_KEY, member_id)?;
+    let _: () = con.hdel(REDIS_PENDING_REQUESTS_KEY, member_id)?;
+    let meetup_id = match meetup_id {
+        Some(meetup_id) => meetup_id,
+        None => return Ok(None),
+    };
+    if crate::identity::meetup_discord_user(meetup_id, con)?.is_none() {
+        return Ok(None);
+    }
+    let _: () = redis::pipe()
+        .atomic()
+        .sadd(redis_household_members_key(meetup_id), member_id)
+        .ignore()
+        .set(redis_household_meetup_user_key(member_id), meetup_id)
+        .ignore()
+        .query(con)?;
+    Ok(Some(meetup_id))
+}
+
+// Returns whether there was a request
+pub fn reject<C: redis::ConnectionLike>(
+    member_id: u64,
+    con: &mut C,
+) -> Result<bool, crate::BoxedError> {
+    let num_removed: u64 = con.hdel(REDIS_PENDING_REQUESTS_KEY, member_id)?;
+    Ok(num_removed > 0)
+}
+
+// The shared Meetup account that the user belongs to, if any
+pub fn household_meetup_user<C: redis::ConnectionLike>(
+    discord_id: u64,
+    con: &mut C,
+) -> Result<Option<u64>, crate::BoxedError> {
+    Ok(con.get(redis_household_meetup_user_key(discord_id))?)
+}
+
+// Returns the Meetup account the user shared
+pub fn remove_member<C: redis::ConnectionLike>(
+    member_id: u64,
+    con: &mut C,
+) -> Result<Option<u64>, crate::BoxedError> {
+    let meetup_id = household_meetup_user(member_id, con)?;
+    if let Some(meetup_id) = meetup_id {
+        let _: () = redis::pipe()
+            .atomic()
+            .srem(redis_household_members_key(meetup_id), member_id)
+            .ignore()
+            .del(redis_household_meetup_user_key(member_id))
+            .ignore()
+            .query(con)?;
+    }
+    Ok(meetup_id)
+}
+
+pub fn dissolve<C: redis::ConnectionLike>(
+    meetup_id: u64,
+    con: &mut C,
+) -> Result<(), crate::BoxedError> {
+    let redis_household_members_key = redis_household_members_key(meetup_id);
+    let member_ids: Vec<u64> = con.smembers(&redis_household_members_key)?;
+    let mut pipe = redis::pipe();
+    pipe.atomic().del(&redis_household_members_key).ignore();
+    for member_id in member_ids {
+        pipe.del(redis_household_meetup_user_key(member_id))
+            .ignore();
+    }
+    let _: () = pipe.query(con)?;
+    Ok(())
+}
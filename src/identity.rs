@@ -0,0 +1,348 @@
+use lazy_static::lazy_static;
+use redis::{Commands, PipelineCommands};
+use serenity::prelude::Mutex;
+use std::collections::HashMap;
+use std::time::Instant;
+
+// The accounts that a Discord user is known by elsewhere: their Meetup
+// account, the email addresses they registered for the calendar, Eventbrite
+// and Warhorn, and their Warhorn account. A user can have any number of them,
+// each one belongs to at most one Discord user. One of a user's identities is
+// their primary one, which wins whenever a single account of a kind is needed
+// (e.g. the Meetup account in the member export). The first identity a user
+// links becomes the primary one.
+// Identities are written as "<provider>:<ID>", e.g. "meetup:1234" or
+// "email:player@example.com".
+// Almost every command and the Discord sync look up Meetup links, so
+// meetup_id and meetup_discord_user keep their answers (including the absence
+// of a link) in memory for LINK_CACHE_TTL_SECS. Linking and unlinking in this
+// process invalidates the affected entries right away, the TTL takes care of
+// changes made by other processes (e.g. botctl).
+const LINK_CACHE_TTL_SECS: u64 = 60;
+const MAX_CACHED_LINKS: usize = 10_000;
+
+type CachedLinks = HashMap<u64, (Option<u64>, Instant)>;
+
+#[derive(Default)]
+struct LinkCache {
+    // Discord ID to Meetup ID
+    meetup_ids: CachedLinks,
+    // Meetup ID to Discord ID
+    discord_ids: CachedLinks,
+    // Incremented by every invalidation, such that a lookup that raced with
+    // a change doesn't cache the old link
+    generation: u64,
+}
+
+lazy_static! {
+    static ref LINK_CACHE: Mutex<LinkCache> = Mutex::new(LinkCache::default());
+}
+
+fn cached_link(links: &CachedLinks, id: u64) -> Option<Option<u64>> {
+    links
+        .get(&id)
+        .filter(|(_, cached_at)| cached_at.elapsed().as_secs() < LINK_CACHE_TTL_SECS)
+        .map(|(linked_id, _)| *linked_id)
+}
+
+fn cache_link(links: &mut CachedLinks, id: u64, linked_id: Option<u64>) {
+    // Rather start over than keep track of the least recently used entries
+    if links.len() >= MAX_CACHED_LINKS {
+        links.clear();
+    }
+    links.insert(id, (linked_id, Instant::now()));
+}
+
+// Forgets the cached Meetup links of the user and the identity. Called once
+// a change of the links was written to Redis.
+pub fn invalidate_cached_links(discord_id: u64, identity: &Identity) {
+    let mut cache = LINK_CACHE.lock();
+    cache.generation += 1;
+    cache.meetup_ids.remove(&discord_id);
+    if let Some(meetup_id) = identity.meetup_id() {
+        cache.discord_ids.remove(&meetup_id);
+    }
+}
+
+fn invalidate_all_cached_links() {
+    let mut cache = LINK_CACHE.lock();
+    cache.generation += 1;
+    cache.meetup_ids.clear();
+    cache.discord_ids.clear();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Provider {
+    Meetup,
+    Warhorn,
+    Email,
+}
+
+impl Provider {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Provider::Meetup => "meetup",
+            Provider::Warhorn => "warhorn",
+            Provider::Email => "email",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Provider> {
+        [Provider::Meetup, Provider::Warhorn, Provider::Email]
+            .iter()
+            .find(|provider| provider.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Identity {
+    pub provider: Provider,
+    pub id: String,
+}
+
+impl Identity {
+    pub fn meetup(meetup_id: u64) -> Self {
+        Identity {
+            provider: Provider::Meetup,
+            id: meetup_id.to_string(),
+        }
+    }
+
+    pub fn warhorn(warhorn_id: &str) -> Self {
+        Identity {
+            provider: Provider::Warhorn,
+            id: warhorn_id.to_string(),
+        }
+    }
+
+    pub fn email(email: &str) -> Self {
+        Identity {
+            provider: Provider::Email,
+            id: crate::email_registration::normalize(email),
+        }
+    }
+
+    pub fn parse(identity: &str) -> Option<Self> {
+        let mut parts = identity.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some(provider), Some(id)) if !id.trim().is_empty() => {
+                match Provider::from_name(provider)? {
+                    Provider::Meetup => id.trim().parse::<u64>().ok().map(Identity::meetup),
+                    Provider::Warhorn => Some(Identity::warhorn(id.trim())),
+                    Provider::Email => Some(Identity::email(id)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn meetup_id(&self) -> Option<u64> {
+        match self.provider {
+            Provider::Meetup => self.id.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn redis_discord_user_key(&self) -> String {
+        format!("identity:{}:discord_user", self)
+    }
+}
+
+impl std::fmt::Display for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.provider.name(), self.id)
+    }
+}
+
+pub fn redis_identities_key(discord_id: u64) -> String {
+    format!("discord_user:{}:identities", discord_id)
+}
+
+pub fn redis_primary_identity_key(discord_id: u64) -> String {
+    format!("discord_user:{}:primary_identity", discord_id)
+}
+
+// For batched lookups of Meetup users
+pub fn redis_meetup_discord_user_key(meetup_id: u64) -> String {
+    Identity::meetup(meetup_id).redis_discord_user_key()
+}
+
+pub fn discord_user<C: redis::ConnectionLike>(
+    identity: &Identity,
+    con: &mut C,
+) -> redis::RedisResult<Option<u64>> {
+    con.get(identity.redis_discord_user_key())
+}
+
+// Cached, see LINK_CACHE
+pub fn meetup_discord_user<C: redis::ConnectionLike>(
+    meetup_id: u64,
+    con: &mut C,
+) -> redis::RedisResult<Option<u64>> {
+    let generation = {
+        let cache = LINK_CACHE.lock();
+        if let Some(discord_id) = cached_link(&cache.discord_ids, meetup_id) {
+            return Ok(discord_id);
+        }
+        cache.generation
+    };
+    let discord_id = discord_user(&Identity::meetup(meetup_id), con)?;
+    let mut cache = LINK_CACHE.lock();
+    if cache.generation == generation {
+        cache_link(&mut cache.discord_ids, meetup_id, discord_id);
+    }
+    Ok(discord_id)
+}
+
+// The primary identity comes first, the others are sorted
+pub fn identities<C: redis::ConnectionLike>(
+    discord_id: u64,
+    con: &mut C,
+) -> redis::RedisResult<Vec<Identity>> {
+    let (identities, primary): (Vec<String>, Option<String>) = redis::pipe()
+        .smembers(redis_identities_key(discord_id))
+        .get(redis_primary_identity_key(discord_id))
+        .query(con)?;
+    let primary = primary.and_then(|primary| Identity::parse(&primary));
+    let mut identities: Vec<Identity> = identities
+        .iter()
+        .filter_map(|identity| Identity::parse(identity))
+        .collect();
+    identities
+        .sort_unstable_by_key(|identity| (Some(identity) != primary.as_ref(), identity.clone()));
+    Ok(identities)
+}
+
+// The user's identity of this kind, the primary one if it is
+pub fn linked_identity<C: redis::ConnectionLike>(
+    discord_id: u64,
+    provider: Provider,
+    con: &mut C,
+) -> redis::RedisResult<Option<Identity>> {
+    Ok(identities(discord_id, con)?
+        .into_iter()
+        .find(|identity| identity.provider == provider))
+}
+
+// Cached, see LINK_CACHE. Transactions that watch the links have to check
+// them with linked_identity instead.
+pub fn meetup_id<C: redis::ConnectionLike>(
+    discord_id: u64,
+    con: &mut C,
+) -> redis::RedisResult<Option<u64>> {
+    let generation = {
+        let cache = LINK_CACHE.lock();
+        if let Some(meetup_id) = cached_link(&cache.meetup_ids, discord_id) {
+            return Ok(meetup_id);
+        }
+        cache.generation
+    };
+    let meetup_id = linked_identity(discord_id, Provider::Meetup, con)?
+        .and_then(|identity| identity.meetup_id());
+    let mut cache = LINK_CACHE.lock();
+    if cache.generation == generation {
+        cache_link(&mut cache.meetup_ids, discord_id, meetup_id);
+    }
+    Ok(meetup_id)
+}
+
+// Adds the link to a pipeline, for callers that check for conflicts in a
+// transaction first. Once the pipeline ran, Meetup links have to be
+// invalidated in the cache with invalidate_cached_links.
+pub fn add_link(pipe: &mut redis::Pipeline, discord_id: u64, identity: &Identity) {
+    pipe.sadd(redis_identities_key(discord_id), identity.to_string())
+        .ignore()
+        .set(identity.redis_discord_user_key(), discord_id)
+        .ignore()
+        .cmd("SET")
+        .arg(redis_primary_identity_key(discord_id))
+        .arg(identity.to_string())
+        .arg("NX")
+        .ignore();
+}
+
+// If this was the primary identity, the next one takes its place
+pub fn remove_link<C: redis::ConnectionLike>(
+    discord_id: u64,
+    identity: &Identity,
+    con: &mut C,
+) -> redis::RedisResult<()> {
+    let remaining: Vec<Identity> = identities(discord_id, con)?
+        .into_iter()
+        .filter(|other| other != identity)
+        .collect();
+    let redis_primary_identity_key = redis_primary_identity_key(discord_id);
+    let mut pipe = redis::pipe();
+    pipe.atomic()
+        .srem(redis_identities_key(discord_id), identity.to_string())
+        .ignore()
+        .del(identity.redis_discord_user_key())
+        .ignore();
+    match remaining.first() {
+        Some(primary) => pipe.set(&redis_primary_identity_key, primary.to_string()),
+        None => pipe.del(&redis_primary_identity_key),
+    }
+    .ignore();
+    let _: () = pipe.query(con)?;
+    invalidate_cached_links(discord_id, identity);
+    Ok(())
+}
+
+// Returns false if the identity is not linked to this user
+pub fn set_primary<C: redis::ConnectionLike>(
+    discord_id: u64,
+    identity: &Identity,
+    con: &mut C,
+) -> redis::RedisResult<bool> {
+    let is_linked: bool = con.sismember(redis_identities_key(discord_id), identity.to_string())?;
+    if is_linked {
+        let _: () = con.set(redis_primary_identity_key(discord_id), identity.to_string())?;
+        invalidate_cached_links(discord_id, identity);
+    }
+    Ok(is_linked)
+}
+
+// Moves the links from before identities existed ("discord_user:{}:meetup_user",
+// "meetup_user:{}:discord_user", "discord_user:{}:email" and
+// "email:{}:discord_user") over. Returns the number of migrated links.
+pub fn migrate<C: redis::ConnectionLike>(con: &mut C) -> Result<u64, crate::BoxedError> {
+    let mut num_migrated = 0;
+    let meetup_link_keys: Vec<String> = con.keys("discord_user:*:meetup_user")?;
+    for key in meetup_link_keys {
+        let discord_id: u64 = match key.split(':').nth(1).and_then(|id| id.parse().ok()) {
+            Some(discord_id) => discord_id,
+            None => continue,
+        };
+        let meetup_id: Option<u64> = con.get(&key)?;
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(&key).ignore();
+        if let Some(meetup_id) = meetup_id {
+            add_link(&mut pipe, discord_id, &Identity::meetup(meetup_id));
+            pipe.del(format!("meetup_user:{}:discord_user", meetup_id))
+                .ignore();
+            num_migrated += 1;
+        }
+        let _: () = pipe.query(con)?;
+    }
+    let email_link_keys: Vec<String> = con.keys("discord_user:*:email")?;
+    for key in email_link_keys {
+        let discord_id: u64 = match key.split(':').nth(1).and_then(|id| id.parse().ok()) {
+            Some(discord_id) => discord_id,
+            None => continue,
+        };
+        let email: Option<String> = con.get(&key)?;
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(&key).ignore();
+        if let Some(email) = email {
+            add_link(&mut pipe, discord_id, &Identity::email(&email));
+            pipe.del(format!("email:{}:discord_user", email)).ignore();
+            num_migrated += 1;
+        }
+        let _: () = pipe.query(con)?;
+    }
+    if num_migrated > 0 {
+        invalidate_all_cached_links();
+    }
+    Ok(num_migrated)
+}
@@ -0,0 +1,43 @@
+use redis::Commands;
+
+// Organizers can make the bot ignore users entirely with "ignore @user", e.g.
+// the webhook accounts of other bots that ended up linked to a Meetup account.
+// The bot doesn't react to messages of ignored users, the Discord sync doesn't
+// give them channel access, and they get no host digests or nudges. Other bots
+// are always ignored, whether they are on the list or not.
+pub const IGNORED_USERS_KEY: &'static str = "ignored_discord_users";
+
+pub fn is_ignored<C: redis::ConnectionLike>(
+    con: &mut C,
+    discord_id: u64,
+) -> Result<bool, crate::BoxedError> {
+    Ok(con.sismember(IGNORED_USERS_KEY, discord_id)?)
+}
+
+pub fn ignored_users<C: redis::ConnectionLike>(con: &mut C) -> Result<Vec<u64>, crate::BoxedError> {
+    Ok(con.smembers(IGNORED_USERS_KEY)?)
+}
+
+// Returns false if the user already was (or wasn't) on the list
+pub fn set_ignored<C: redis::ConnectionLike>(
+    con: &mut C,
+    discord_id: u64,
+    ignored: bool,
+) -> Result<bool, crate::BoxedError> {
+    let num_changed: u32 = if ignored {
+        con.sadd(IGNORED_USERS_KEY, discord_id)?
+    } else {
+        con.srem(IGNORED_USERS_KEY, discord_id)?
+    };
+    Ok(num_changed > 0)
+}
+
+// Only looks at the cache. Users that are not in it are not considered bots.
+pub fn is_bot(discord_api: &crate::discord_bot::CacheAndHttp, discord_id: u64) -> bool {
+    discord_api
+        .cache
+        .read()
+        .user(discord_id)
+        .map(|user| user.read().bot)
+        .unwrap_or(false)
+}
@@ -0,0 +1,120 @@
+use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::Duration;
+
+// When several bot processes share the same Redis server (one per range of
+// Discord shards), only one of them, the leader, runs the schedulers and the
+// Meetup and Discord syncing. Leadership is a lease in Redis that the leader
+// has to renew periodically. If the leader dies, another instance takes over
+// once the lease expired.
+// The lease is renewed on a thread of its own, since the task scheduler runs
+// long tasks like the syncs one after the other. And in case the renewal is
+// late anyway, an instance stops considering itself the leader as soon as its
+// lease might have expired.
+const REDIS_LEADER_KEY: &'static str = "leader_instance";
+const LEASE_SECS: u64 = 60;
+const RENEWAL_INTERVAL_SECS: i64 = 20;
+
+lazy_static! {
+    // Identifies this process in the logs and in Redis
+    pub static ref INSTANCE_ID: String = std::env::var("INSTANCE_ID").unwrap_or_else(|_| {
+        format!(
+            "{}-{}",
+            std::process::id(),
+            crate::meetup_oauth2::new_random_id(4)
+        )
+    });
+    // Acquires the lease if it is free and extends it if we already hold it
+    static ref ACQUIRE_OR_RENEW_SCRIPT: redis::Script = redis::Script::new(
+        r"
+        local current_leader = redis.call('GET', KEYS[1])
+        if current_leader == ARGV[1] then
+            redis.call('EXPIRE', KEYS[1], ARGV[2])
+            return 1
+        elseif not current_leader then
+            redis.call('SET', KEYS[1], ARGV[1], 'EX', ARGV[2])
+            return 1
+        end
+        return 0
+        "
+    );
+}
+
+static IS_LEADER: AtomicBool = AtomicBool::new(false);
+// When the lease that this instance last got runs out (Unix timestamp)
+static LEASE_EXPIRATION: AtomicI64 = AtomicI64::new(0);
+
+// Whether this instance held the leadership lease the last time it checked
+// and the lease is still valid
+pub fn is_leader() -> bool {
+    IS_LEADER.load(Ordering::SeqCst)
+        && chrono::Utc::now().timestamp() < LEASE_EXPIRATION.load(Ordering::SeqCst)
+}
+
+pub fn try_acquire_leadership(
+    redis_client: &crate::redis_namespace::Client,
+) -> Result<bool, crate::BoxedError> {
+    // The lease starts at the latest when Redis gets the request
+    let requested_at = chrono::Utc::now().timestamp();
+    let mut con = redis_client.get_connection()?;
+    let acquired: bool = ACQUIRE_OR_RENEW_SCRIPT
+        .key(REDIS_LEADER_KEY)
+        .arg(&*INSTANCE_ID)
+        .arg(LEASE_SECS)
+        .invoke(&mut con)?;
+    if acquired {
+        LEASE_EXPIRATION.store(requested_at + LEASE_SECS as i64, Ordering::SeqCst);
+    }
+    let was_leader = IS_LEADER.swap(acquired, Ordering::SeqCst);
+    if acquired && !was_leader {
+        println!("Instance {} is now the leader", &*INSTANCE_ID);
+    } else if !acquired && was_leader {
+        println!("Instance {} lost the leadership", &*INSTANCE_ID);
+    }
+    Ok(acquired)
+}
+
+// Periodically renews (or tries to acquire) the leadership lease
+pub fn spawn_renewal(redis_client: crate::redis_namespace::Client) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(RENEWAL_INTERVAL_SECS as u64));
+        if let Err(err) = try_acquire_leadership(&redis_client) {
+            eprintln!("Could not renew the leadership lease: {}", err);
+            // Without a working Redis connection we can't be sure that
+            // nobody else took over, so step down
+            IS_LEADER.store(false, Ordering::SeqCst);
+        }
+    });
+}
+
+// Wraps a recurring task such that it only runs on the leader. On all other
+// instances it is postponed until they might have become the leader.
+pub fn only_on_leader(
+    mut task: impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    move |ctx| {
+        if is_leader() {
+            task(ctx)
+        } else {
+            white_rabbit::DateResult::Repeat(
+                white_rabbit::Utc::now() + white_rabbit::Duration::seconds(RENEWAL_INTERVAL_SECS),
+            )
+        }
+    }
+}
+
+// The counterpart of only_on_leader, for tasks that keep the other instances
+// up-to-date with what the leader does
+pub fn only_on_followers(
+    mut task: impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    move |ctx| {
+        if is_leader() {
+            white_rabbit::DateResult::Repeat(
+                white_rabbit::Utc::now() + white_rabbit::Duration::seconds(RENEWAL_INTERVAL_SECS),
+            )
+        } else {
+            task(ctx)
+        }
+    }
+}
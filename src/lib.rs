@@ -0,0 +1,107 @@
+#![recursion_limit = "256"]
+// The bot itself (src/main.rs) and the maintenance tool botctl
+// (src/bin/botctl.rs) share all of the code below
+pub mod admin_api;
+pub mod dice_roller;
+pub mod discord_absences;
+pub mod discord_api_budget;
+pub mod discord_bot;
+pub mod discord_bot_commands;
+pub mod discord_call_priority;
+pub mod discord_channel_access;
+pub mod discord_channel_approval;
+pub mod discord_channel_edits;
+pub mod discord_channel_settings;
+pub mod discord_channel_style;
+pub mod discord_characters;
+pub mod discord_command_aliases;
+pub mod discord_command_usage;
+pub mod discord_community_digest;
+pub mod discord_confirmations;
+pub mod discord_creation_budget;
+pub mod discord_crosspost;
+pub mod discord_data_deletion;
+pub mod discord_edited_commands;
+pub mod discord_end_of_game;
+pub mod discord_guests;
+pub mod discord_history_privacy;
+pub mod discord_host_digest;
+pub mod discord_host_orientation;
+pub mod discord_inactive_members;
+pub mod discord_invites;
+pub mod discord_linking_wizard;
+pub mod discord_macros;
+pub mod discord_member_export;
+pub mod discord_moderation;
+pub mod discord_nicknames;
+pub mod discord_notifications;
+pub mod discord_ownership;
+pub mod discord_pagination;
+pub mod discord_presence;
+pub mod discord_quick_commands;
+pub mod discord_recaps;
+pub mod discord_replies;
+pub mod discord_reports;
+pub mod discord_role_cleanup;
+pub mod discord_role_limit;
+pub mod discord_role_retries;
+pub mod discord_safety;
+pub mod discord_scheduled_events;
+pub mod discord_session_channels;
+pub mod discord_session_counter;
+pub mod discord_setup;
+pub mod discord_state_cache;
+pub mod discord_subscriptions;
+pub mod discord_sync;
+pub mod discord_sync_preview;
+pub mod discord_tables;
+pub mod discord_tidy;
+pub mod discord_user_channels;
+pub mod discord_voice_channels;
+pub mod discord_watchdog;
+pub mod email_registration;
+pub mod error;
+pub mod error_reporting;
+pub mod event_location;
+pub mod event_source;
+pub mod event_tags;
+pub mod event_webhooks;
+pub mod eventbrite;
+pub mod feature_flags;
+pub mod google_calendar;
+pub mod guild_config;
+pub mod host_verification;
+pub mod households;
+pub mod identity;
+pub mod ignore_list;
+pub mod leader_election;
+pub mod link_suggestions;
+pub mod maintenance_mode;
+pub mod meetup_api;
+pub mod meetup_backfill;
+pub mod meetup_circuit_breaker;
+pub mod meetup_dedup;
+pub mod meetup_dues;
+pub mod meetup_membership;
+pub mod meetup_oauth2;
+pub mod meetup_organizer_tokens;
+pub mod meetup_sync;
+pub mod persistent_tasks;
+pub mod quarantine;
+pub mod redis_benchmark;
+pub mod redis_dump;
+pub mod redis_namespace;
+pub mod redis_storage;
+pub mod scheduled_commands;
+pub mod secrets;
+pub mod staging_mode;
+pub mod strings;
+pub mod sync_stats;
+pub mod task_supervisor;
+pub mod token_encryption;
+pub mod vacuum;
+pub mod warhorn;
+
+pub use error::BoxedError;
+
+pub type Result<T> = std::result::Result<T, BoxedError>;
@@ -0,0 +1,397 @@
+use crate::meetup_api;
+use redis::{Commands, PipelineCommands};
+use simple_error::SimpleError;
+use std::collections::{HashMap, HashSet};
+
+// Suggestions for Discord users that should be linked to a Meetup account.
+// The Meetup sync remembers the name and photo of the Meetup members that
+// RSVP'd, and there are two ways to get from those to a guild member:
+// - Meetup events can ask a question when members RSVP; if one of the
+//   questions mentions Discord (e.g. "What's your Discord username?"), the
+//   Discord sync looks for a guild member with the answered name and tells
+//   the organizers about the suggestion. Answers that don't match anybody are
+//   kept, the member might join the server later.
+// - Organizers can ask for a ranked list of suggestions with "suggest links",
+//   which compares the Meetup names with the user names and nicknames of the
+//   guild members. The Meetup photo and the Discord avatar are shown next to
+//   each other, so the organizer can compare those.
+// Organizers confirm a suggestion with "confirm link <Meetup ID>" (or throw
+// it away with "dismiss link <Meetup ID>"). Nothing is linked without
+// confirmation.
+pub const REDIS_RSVP_ANSWERS_KEY: &'static str = "meetup_rsvp_discord_answers";
+
+// Meetup user -> name and photo, of everybody who RSVP'd
+const REDIS_RSVP_NAMES_KEY: &'static str = "meetup_rsvp_names";
+const REDIS_RSVP_PHOTOS_KEY: &'static str = "meetup_rsvp_photos";
+
+// Meetup user -> suggested Discord user
+const REDIS_SUGGESTIONS_KEY: &'static str = "link_suggestions";
+
+// "<Meetup ID>:<Discord ID>" pairs that an organizer dismissed, such that they
+// aren't suggested again
+const REDIS_DISMISSED_KEY: &'static str = "dismissed_link_suggestions";
+
+// Pairs with a lower name similarity (between 0 and 1) are not suggested
+const MIN_NAME_SIMILARITY: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub meetup_id: u64,
+    pub meetup_name: String,
+    pub meetup_photo: Option<String>,
+    pub discord_id: u64,
+    pub discord_tag: String,
+    pub discord_avatar: String,
+    pub similarity: f64,
+}
+
+struct Member {
+    id: u64,
+    name: String,
+    tag: String,
+    nick: Option<String>,
+    avatar: String,
+}
+
+// Adds what the link suggestions need to know about the members that RSVP'd
+// yes to a pipeline
+pub fn remember_rsvps(pipe: &mut redis::Pipeline, rsvps: &[meetup_api::RSVP]) {
+    for rsvp in rsvps {
+        if rsvp.response != meetup_api::RSVPResponse::Yes {
+            continue;
+        }
+        let member = &rsvp.member;
+        pipe.hset(REDIS_RSVP_NAMES_KEY, member.id, &member.name)
+            .ignore();
+        match member.photo {
+            Some(ref photo) => pipe.hset(REDIS_RSVP_PHOTOS_KEY, member.id, &photo.thumb_link),
+            None => pipe.hdel(REDIS_RSVP_PHOTOS_KEY, member.id),
+        }
+        .ignore();
+        if let Some(answer) = discord_answer(rsvp) {
+            pipe.hset(REDIS_RSVP_ANSWERS_KEY, member.id, answer)
+                .ignore();
+        }
+    }
+}
+
+// The answer to the RSVP question that asks for the Discord username, if any
+pub fn discord_answer(rsvp: &crate::meetup_api::RSVP) -> Option<&str> {
+    rsvp.answers
+        .iter()
+        .find(|answer| answer.question.to_lowercase().contains("discord"))
+        .map(|answer| answer.answer.trim())
+        .filter(|answer| !answer.is_empty())
+}
+
+// The member that the answer names: either the full tag ("name#1234") or a
+// user name or nickname that only one member has
+fn find_member(answer: &str, members: &[Member]) -> Option<u64> {
+    let answer = answer.trim().trim_start_matches('@').trim().to_lowercase();
+    if answer.is_empty() {
+        return None;
+    }
+    if answer.contains('#') {
+        return members
+            .iter()
+            .find(|member| member.tag.to_lowercase() == answer)
+            .map(|member| member.id);
+    }
+    let by_name: Vec<u64> = members
+        .iter()
+        .filter(|member| member.name.to_lowercase() == answer)
+        .map(|member| member.id)
+        .collect();
+    if by_name.len() == 1 {
+        return Some(by_name[0]);
+    }
+    let by_nick: Vec<u64> = members
+        .iter()
+        .filter(|member| {
+            member
+                .nick
+                .as_ref()
+                .map(|nick| nick.to_lowercase() == answer)
+                .unwrap_or(false)
+        })
+        .map(|member| member.id)
+        .collect();
+    if by_name.is_empty() && by_nick.len() == 1 {
+        Some(by_nick[0])
+    } else {
+        None
+    }
+}
+
+// Collects the members first, such that the guild is not locked while we
+// query Redis
+fn guild_members(
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<Vec<Member>, crate::BoxedError> {
+    let guild = crate::discord_sync::GUILD_ID
+        .to_guild_cached(&discord_api.cache)
+        .ok_or_else(|| SimpleError::new("Could not find the guild in the cache"))?;
+    let guild = guild.read();
+    Ok(guild
+        .members
+        .values()
+        .filter(|member| !member.user.read().bot)
+        .map(|member| {
+            let user = member.user.read();
+            Member {
+                id: user.id.0,
+                name: user.name.clone(),
+                tag: user.tag(),
+                nick: member.nick.clone(),
+                avatar: user.face(),
+            }
+        })
+        .collect())
+}
+
+// Letters and digits only, lower case
+fn normalize(name: &str) -> Vec<char> {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_char == b_char { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Between 0 (nothing in common) and 1 (the same name, ignoring case, spaces
+// and punctuation)
+fn name_similarity(meetup_name: &str, discord_name: &str) -> f64 {
+    let meetup_chars = normalize(meetup_name);
+    let discord_chars = normalize(discord_name);
+    if meetup_chars.is_empty() || discord_chars.is_empty() {
+        return 0.0;
+    }
+    let max_len = meetup_chars.len().max(discord_chars.len());
+    let edit_similarity = 1.0 - levenshtein(&meetup_chars, &discord_chars) as f64 / max_len as f64;
+    // Discord names often contain a part of the real name, e.g. "anna_rpg"
+    // for "Anna Schmidt". Very short parts match too many names by chance.
+    let discord_name: String = discord_chars.into_iter().collect();
+    let parts: Vec<String> = meetup_name
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|part| part.chars().count() >= 3)
+        .collect();
+    let part_similarity = if parts.is_empty() {
+        0.0
+    } else {
+        let num_contained = parts
+            .iter()
+            .filter(|part| discord_name.contains(part.as_str()))
+            .count();
+        0.9 * num_contained as f64 / parts.len() as f64
+    };
+    edit_similarity.max(part_similarity)
+}
+
+// Returns whether this is a new suggestion
+pub fn suggest<C: redis::ConnectionLike>(
+    meetup_id: u64,
+    discord_id: u64,
+    con: &mut C,
+) -> Result<bool, crate::BoxedError> {
+    let is_dismissed: bool =
+        con.sismember(REDIS_DISMISSED_KEY, format!("{}:{}", meetup_id, discord_id))?;
+    if is_dismissed {
+        return Ok(false);
+    }
+    Ok(con.hset_nx(REDIS_SUGGESTIONS_KEY, meetup_id, discord_id)?)
+}
+
+// Removes the suggestion and returns the suggested Discord user
+pub fn take<C: redis::ConnectionLike>(
+    meetup_id: u64,
+    con: &mut C,
+) -> Result<Option<u64>, crate::BoxedError> {
+    let (discord_id, _): (Option<u64>, u64) = redis::pipe()
+        .atomic()
+        .hget(REDIS_SUGGESTIONS_KEY, meetup_id)
+        .hdel(REDIS_SUGGESTIONS_KEY, meetup_id)
+        .query(con)?;
+    Ok(discord_id)
+}
+
+// Returns whether there was a suggestion
+pub fn dismiss<C: redis::ConnectionLike>(
+    meetup_id: u64,
+    con: &mut C,
+) -> Result<bool, crate::BoxedError> {
+    let discord_id = match take(meetup_id, con)? {
+        Some(discord_id) => discord_id,
+        None => return Ok(false),
+    };
+    let _: () = con.sadd(REDIS_DISMISSED_KEY, format!("{}:{}", meetup_id, discord_id))?;
+    Ok(true)
+}
+
+// Turns the stored RSVP answers into suggestions and tells the organizers
+// about new ones. Called by the Discord sync, which has the guild's members.
+pub fn suggest_from_rsvp_answers(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    bot_id: u64,
+) -> Result<(), crate::BoxedError> {
+    let answers: Vec<(u64, String)> = con.hgetall(REDIS_RSVP_ANSWERS_KEY)?;
+    if answers.is_empty() {
+        return Ok(());
+    }
+    let members = guild_members(discord_api)?;
+    for (meetup_id, answer) in answers {
+        if crate::identity::meetup_discord_user(meetup_id, con)?.is_some() {
+            let _: () = con.hdel(REDIS_RSVP_ANSWERS_KEY, meetup_id)?;
+            continue;
+        }
+        let discord_id = match find_member(&answer, &members) {
+            Some(discord_id) => discord_id,
+            None => continue,
+        };
+        let _: () = con.hdel(REDIS_RSVP_ANSWERS_KEY, meetup_id)?;
+        // Users that linked a different account already need to unlink first,
+        // nothing to suggest there
+        if crate::identity::meetup_id(discord_id, con)?.is_some() {
+            continue;
+        }
+        if suggest(meetup_id, discord_id, con)? {
+            crate::discord_notifications::notify_organizers(
+                discord_api,
+                &crate::strings::LINK_SUGGESTION_FROM_RSVP_ANSWER(
+                    meetup_id, discord_id, &answer, bot_id,
+                ),
+                con,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// The best matches between the Meetup members that RSVP'd and aren't linked
+// yet and the guild members without a Meetup account, best first. Each account
+// shows up at most once. The suggestions are stored, such that organizers can
+// confirm them.
+pub fn rank_suggestions(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    max_suggestions: usize,
+) -> Result<Vec<Suggestion>, crate::BoxedError> {
+    let (names, mut photos, pending): (
+        HashMap<u64, String>,
+        HashMap<u64, String>,
+        HashMap<u64, u64>,
+    ) = redis::pipe()
+        .hgetall(REDIS_RSVP_NAMES_KEY)
+        .hgetall(REDIS_RSVP_PHOTOS_KEY)
+        .hgetall(REDIS_SUGGESTIONS_KEY)
+        .query(con)?;
+    if names.is_empty() {
+        return Ok(vec![]);
+    }
+    let names: Vec<(u64, String)> = names.into_iter().collect();
+    let redis_meetup_discord_keys: Vec<_> = names
+        .iter()
+        .map(|&(meetup_id, _)| crate::identity::redis_meetup_discord_user_key(meetup_id))
+        .collect();
+    // Make sure that this is an MGET even if there is only a single key
+    let (linked_discord_ids,): (Vec<Option<u64>>,) = redis::pipe()
+        .cmd("MGET")
+        .arg(redis_meetup_discord_keys)
+        .query(con)?;
+    let (linked_meetup_users, unlinked_meetup_users): (Vec<_>, Vec<_>) = names
+        .into_iter()
+        .zip(linked_discord_ids)
+        .partition(|(_, discord_id)| discord_id.is_some());
+    let unlinked_meetup_users: Vec<(u64, String)> = unlinked_meetup_users
+        .into_iter()
+        .map(|(meetup_user, _)| meetup_user)
+        .collect();
+    // Linked members won't need a suggestion anymore
+    if !linked_meetup_users.is_empty() {
+        let linked_meetup_ids: Vec<u64> = linked_meetup_users
+            .iter()
+            .map(|((meetup_id, _), _)| *meetup_id)
+            .collect();
+        let _: () = redis::pipe()
+            .hdel(REDIS_RSVP_NAMES_KEY, &linked_meetup_ids)
+            .ignore()
+            .hdel(REDIS_RSVP_PHOTOS_KEY, &linked_meetup_ids)
+            .ignore()
+            .query(con)?;
+    }
+    let members = guild_members(discord_api)?;
+    let mut candidates: Vec<(f64, &(u64, String), &Member)> = vec![];
+    for meetup_user in &unlinked_meetup_users {
+        for member in &members {
+            let similarity = member
+                .nick
+                .as_ref()
+                .map(|nick| name_similarity(&meetup_user.1, nick))
+                .unwrap_or(0.0)
+                .max(name_similarity(&meetup_user.1, &member.name));
+            if similarity >= MIN_NAME_SIMILARITY {
+                candidates.push((similarity, meetup_user, member));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then((a.1).0.cmp(&(b.1).0))
+            .then(a.2.id.cmp(&b.2.id))
+    });
+    let mut suggestions = vec![];
+    let mut used_meetup_ids = HashSet::new();
+    let mut used_discord_ids = HashSet::new();
+    for (similarity, &(meetup_id, ref meetup_name), member) in candidates {
+        if suggestions.len() >= max_suggestions {
+            break;
+        }
+        if used_meetup_ids.contains(&meetup_id) || used_discord_ids.contains(&member.id) {
+            continue;
+        }
+        // Don't replace a suggestion that is waiting for an organizer
+        if let Some(&discord_id) = pending.get(&meetup_id) {
+            if discord_id != member.id {
+                continue;
+            }
+        }
+        // Only checked for the best matches, there are many more members
+        // than suggestions
+        if crate::identity::meetup_id(member.id, con)?.is_some()
+            || crate::households::household_meetup_user(member.id, con)?.is_some()
+        {
+            used_discord_ids.insert(member.id);
+            continue;
+        }
+        if !pending.contains_key(&meetup_id) && !suggest(meetup_id, member.id, con)? {
+            // Dismissed before
+            continue;
+        }
+        used_meetup_ids.insert(meetup_id);
+        used_discord_ids.insert(member.id);
+        suggestions.push(Suggestion {
+            meetup_id: meetup_id,
+            meetup_name: meetup_name.clone(),
+            meetup_photo: photos.remove(&meetup_id),
+            discord_id: member.id,
+            discord_tag: member.tag.clone(),
+            discord_avatar: member.avatar.clone(),
+            similarity: similarity,
+        });
+    }
+    Ok(suggestions)
+}
@@ -1,43 +1,96 @@
-#![recursion_limit = "256"]
-pub mod discord_bot;
-pub mod discord_bot_commands;
-pub mod discord_end_of_game;
-pub mod discord_sync;
-pub mod error;
-pub mod meetup_api;
-pub mod meetup_oauth2;
-pub mod meetup_sync;
-pub mod strings;
-pub mod vacuum;
-
-use error::BoxedError;
 use futures::{Future, Stream};
 use redis::Commands;
 use serenity::prelude::{Mutex, RwLock};
 use std::env;
 use std::sync::Arc;
+use swissrpg_discord_bot::secrets::exit_on_config_error;
+use swissrpg_discord_bot::{
+    admin_api, discord_bot, discord_community_digest, discord_crosspost, discord_guests,
+    discord_host_digest, discord_presence, discord_role_retries, discord_watchdog, error_reporting,
+    event_source, eventbrite, google_calendar, guild_config, identity, leader_election, meetup_api,
+    meetup_oauth2, meetup_sync, persistent_tasks, redis_namespace, redis_storage, secrets,
+    staging_mode, task_supervisor, token_encryption, warhorn,
+};
 use tokio;
 
-type Result<T> = std::result::Result<T, BoxedError>;
-
 fn main() {
+    error_reporting::install_panic_hook();
+
+    let redis_url = exit_on_config_error(secrets::load_or(
+        "REDIS_URL",
+        "redis://127.0.0.1/",
+        secrets::validate_redis_url,
+    ));
+    // Optional namespace for all Redis keys, in case the Redis instance is shared
+    let redis_key_prefix = env::var("REDIS_KEY_PREFIX").unwrap_or_default();
+    if staging_mode::is_active() {
+        println!("Running in staging mode, the debug commands are enabled");
+    }
+    if !token_encryption::is_enabled() {
+        println!("REDIS_ENCRYPTION_KEY is not set, OAuth2 tokens are stored unencrypted");
+    }
+
     let meetup_client_id =
         env::var("MEETUP_CLIENT_ID").expect("Found no MEETUP_CLIENT_ID in environment");
-    let meetup_client_secret =
-        env::var("MEETUP_CLIENT_SECRET").expect("Found no MEETUP_CLIENT_SECRET in environment");
-    let discord_token = env::var("DISCORD_TOKEN").expect("Found no DISCORD_TOKEN in environment");
+    let meetup_client_secret = exit_on_config_error(secrets::load_required(
+        "MEETUP_CLIENT_SECRET",
+        secrets::validate_meetup_client_secret,
+    ));
+    let discord_token = exit_on_config_error(secrets::load_required(
+        "DISCORD_TOKEN",
+        secrets::validate_discord_token,
+    ));
+    // Optional sharding configuration for running several bot processes:
+    // DISCORD_SHARD_COUNT is the total number of shards and DISCORD_SHARD_RANGE
+    // (e.g. "0-3") the shards this process is responsible for
+    let shard_count: Option<u64> = env::var("DISCORD_SHARD_COUNT")
+        .ok()
+        .map(|count| count.parse().expect("Invalid DISCORD_SHARD_COUNT"));
+    let shard_range: Option<[u64; 2]> = env::var("DISCORD_SHARD_RANGE").ok().map(|range| {
+        let mut bounds = range
+            .split('-')
+            .map(|bound| bound.trim().parse().expect("Invalid DISCORD_SHARD_RANGE"));
+        match (bounds.next(), bounds.next(), bounds.next()) {
+            (Some(first), Some(last), None) => [first, last],
+            _ => panic!("DISCORD_SHARD_RANGE needs to be of the form \"first-last\""),
+        }
+    });
+    // Optional token for the admin API, which is disabled without one
+    let admin_api_token = exit_on_config_error(secrets::load_optional(
+        "ADMIN_API_TOKEN",
+        secrets::validate_admin_api_token,
+    ));
+    let http_port: u16 = env::var("HTTP_PORT")
+        .map(|port| port.parse().expect("Invalid HTTP_PORT"))
+        .unwrap_or(3000);
+    println!("Starting instance {}", &*leader_election::INSTANCE_ID);
 
-    // Connect to the local Redis server
-    let redis_client =
-        redis::Client::open("redis://127.0.0.1/").expect("Could not create a Redis client");
+    // Connect to the Redis server
+    let redis_client = redis_namespace::Client::open(&redis_url, &redis_key_prefix)
+        .expect("Could not create a Redis client");
+    error_reporting::set_redis_client(redis_client.clone());
     let mut redis_connection = redis_client
         .get_connection()
         .expect("Could not connect to Redis");
 
+    // Move the account links from before the identity model over
+    let num_migrated_links =
+        identity::migrate(&mut redis_connection).expect("Could not migrate the account links");
+    if num_migrated_links > 0 {
+        println!(
+            "Migrated {} account links to identities",
+            num_migrated_links
+        );
+    }
+
     // Create a Meetup API client (might not be possible if there is no access token yet)
     let meetup_access_token: Option<String> = redis_connection
         .get("meetup_access_token")
         .expect("Meetup access token could not be loaded from Redis");
+    let meetup_access_token = meetup_access_token.map(|meetup_access_token| {
+        token_encryption::decrypt(&meetup_access_token)
+            .expect("Meetup access token could not be decrypted")
+    });
     let (meetup_client, async_meetup_client) = match meetup_access_token {
         Some(meetup_access_token) => (
             Arc::new(RwLock::new(Some(meetup_api::Client::new(
@@ -49,6 +102,55 @@ fn main() {
         ),
         None => (Arc::new(RwLock::new(None)), Arc::new(RwLock::new(None))),
     };
+    // Where the Meetup sync gets the events from. Meetup is always available,
+    // the other sources only if they are configured. Which one is used is up
+    // to the "event_source" setting.
+    let google_calendar_event_source =
+        env::var("GOOGLE_CALENDAR_ID")
+            .ok()
+            .map(|google_calendar_id| {
+                let service_account_key = exit_on_config_error(secrets::load_required(
+                    "GOOGLE_SERVICE_ACCOUNT_KEY",
+                    google_calendar::validate_service_account_key,
+                ));
+                exit_on_config_error(google_calendar::GoogleCalendarEventSource::new(
+                    google_calendar_id,
+                    &service_account_key,
+                    redis_client.clone(),
+                ))
+            });
+    let warhorn_event_source = exit_on_config_error(secrets::load_optional(
+        "WARHORN_API_TOKEN",
+        secrets::validate_warhorn_api_token,
+    ))
+    .map(|warhorn_api_token| {
+        warhorn::WarhornEventSource::new(warhorn_api_token, redis_client.clone())
+    });
+    let eventbrite_event_source = exit_on_config_error(secrets::load_optional(
+        "EVENTBRITE_API_TOKEN",
+        secrets::validate_eventbrite_api_token,
+    ))
+    .map(|eventbrite_api_token| {
+        let organization_id = env::var("EVENTBRITE_ORGANIZATION_ID")
+            .expect("EVENTBRITE_API_TOKEN is set, but EVENTBRITE_ORGANIZATION_ID is not");
+        eventbrite::EventbriteEventSource::new(
+            eventbrite_api_token,
+            organization_id,
+            redis_client.clone(),
+        )
+    });
+    let event_source: event_source::SharedEventSource =
+        Arc::new(event_source::ConfiguredEventSource::new(
+            event_source::MeetupEventSource::new(async_meetup_client.clone()),
+            google_calendar_event_source,
+            warhorn_event_source,
+            eventbrite_event_source,
+            redis_client.clone(),
+        ));
+
+    // Find out whether this instance is responsible for the schedulers and syncing
+    leader_election::try_acquire_leadership(&redis_client)
+        .expect("Could not take part in the leader election");
 
     // Create a Meetup OAuth2 consumer
     let meetup_oauth2_consumer =
@@ -75,15 +177,52 @@ fn main() {
     };
     task_scheduler.lock().add_task_datetime(
         next_refresh_time,
-        meetup_oauth2_consumer.token_refresh_task(
-            redis_client
-                .get_connection()
-                .expect("Could not connect to Redis"),
-            meetup_client.clone(),
+        task_supervisor::supervised(
+            "meetup_token_refresh".to_string(),
+            leader_election::only_on_leader(
+                meetup_oauth2_consumer.token_refresh_task(
+                    redis_client
+                        .get_connection()
+                        .expect("Could not connect to Redis"),
+                    meetup_client.clone(),
+                ),
+            ),
         ),
     );
+    // The other instances pick up the token that the leader refreshed
+    task_scheduler.lock().add_task_datetime(
+        white_rabbit::Utc::now(),
+        task_supervisor::supervised(
+            "meetup_token_reload".to_string(),
+            leader_election::only_on_followers(meetup_oauth2::token_reload_task(
+                redis_client
+                    .get_connection()
+                    .expect("Could not connect to Redis"),
+                meetup_client.clone(),
+                async_meetup_client.clone(),
+            )),
+        ),
+    );
+    // Regularly check the tokens of all organizers and fall back to a working one
+    task_scheduler.lock().add_task_datetime(
+        white_rabbit::Utc::now(),
+        task_supervisor::supervised(
+            "meetup_organizer_tokens_check".to_string(),
+            leader_election::only_on_leader(
+                meetup_oauth2_consumer.organizer_tokens_check_task(
+                    redis_client
+                        .get_connection()
+                        .expect("Could not connect to Redis"),
+                    meetup_client.clone(),
+                    async_meetup_client.clone(),
+                ),
+            ),
+        ),
+    );
+    // Keep renewing (or trying to acquire) the leadership lease
+    leader_election::spawn_renewal(redis_client.clone());
 
-    let (tx, rx) = futures::sync::mpsc::channel::<crate::meetup_sync::BoxedFuture<(), ()>>(1);
+    let (tx, rx) = futures::sync::mpsc::channel::<meetup_sync::BoxedFuture<(), ()>>(1);
     let spawn_other_futures_future = rx.for_each(|fut| tokio::spawn(fut));
 
     let mut bot = discord_bot::create_discord_client(
@@ -91,20 +230,147 @@ fn main() {
         redis_client.clone(),
         meetup_client.clone(),
         async_meetup_client.clone(),
-        task_scheduler,
-        tx,
+        event_source.clone(),
+        task_scheduler.clone(),
+        tx.clone(),
     )
     .expect("Could not create the Discord bot");
 
+    // Put the tasks that were scheduled before the last shutdown back into the scheduler
+    let bot_id = *bot
+        .data
+        .read()
+        .get::<discord_bot::BotIdKey>()
+        .expect("Bot ID was not set");
+    let discord_api = discord_bot::CacheAndHttp {
+        cache: bot.cache_and_http.cache.clone(),
+        http: bot.cache_and_http.http.clone(),
+    };
+    persistent_tasks::rehydrate_tasks(&task_scheduler, &redis_client, &discord_api, bot_id.0)
+        .expect("Could not rehydrate the persisted tasks");
+    // Keep Discord in sync with the dirty series (the boot sequence brings
+    // the first run forward on the leader). Every instance has the task, such
+    // that it keeps running when the leadership changes, but it only runs on
+    // the leader.
+    let sync_interval = guild_config::sync_interval(&mut redis_connection)
+        .expect("Could not read the sync interval");
+    persistent_tasks::ensure_recurring_task(
+        &task_scheduler,
+        &redis_client,
+        &discord_api,
+        bot_id.0,
+        persistent_tasks::TaskType::SyncDiscord,
+        white_rabbit::Utc::now() + white_rabbit::Duration::minutes(sync_interval.num_minutes()),
+    )
+    .expect("Could not schedule the Discord sync");
+    // Post the weekly community digest
+    task_scheduler.lock().add_task_datetime(
+        white_rabbit::Utc::now(),
+        task_supervisor::supervised(
+            "community_digest".to_string(),
+            leader_election::only_on_leader(
+                discord_community_digest::create_community_digest_task(
+                    redis_client.clone(),
+                    discord_api.clone(),
+                ),
+            ),
+        ),
+    );
+    // Send the hosts their weekly digest
+    task_scheduler.lock().add_task_datetime(
+        discord_host_digest::next_digest_time(white_rabbit::Utc::now()),
+        task_supervisor::supervised(
+            "host_digest".to_string(),
+            leader_election::only_on_leader(discord_host_digest::create_host_digest_task(
+                redis_client.clone(),
+                discord_api.clone(),
+            )),
+        ),
+    );
+
+    // Retry the role assignments of the Discord sync that failed
+    task_scheduler.lock().add_task_datetime(
+        white_rabbit::Utc::now(),
+        task_supervisor::supervised(
+            "role_retries".to_string(),
+            leader_election::only_on_leader(discord_role_retries::create_role_retry_task(
+                redis_client.clone(),
+                discord_api.clone(),
+            )),
+        ),
+    );
+
+    // Cross-post games with free spots to the companion server
+    task_scheduler.lock().add_task_datetime(
+        white_rabbit::Utc::now(),
+        task_supervisor::supervised(
+            "crosspost".to_string(),
+            leader_election::only_on_leader(discord_crosspost::create_crosspost_task(
+                redis_client.clone(),
+            )),
+        ),
+    );
+
+    // Give short lived keys without a TTL theirs and watch the dataset's growth
+    task_scheduler.lock().add_task_datetime(
+        white_rabbit::Utc::now(),
+        task_supervisor::supervised(
+            "storage_check".to_string(),
+            leader_election::only_on_leader(redis_storage::create_storage_check_task(
+                redis_client.clone(),
+                discord_api.clone(),
+            )),
+        ),
+    );
+
+    // End the guest access of trial players
+    task_scheduler.lock().add_task_datetime(
+        white_rabbit::Utc::now(),
+        task_supervisor::supervised(
+            "guest_expiration".to_string(),
+            leader_election::only_on_leader(discord_guests::create_guest_expiration_task(
+                redis_client.clone(),
+                discord_api.clone(),
+            )),
+        ),
+    );
+
+    // Show the next event in the bot's status (once the shards are connected)
+    task_scheduler.lock().add_task_datetime(
+        white_rabbit::Utc::now() + white_rabbit::Duration::minutes(1),
+        task_supervisor::supervised(
+            "presence".to_string(),
+            discord_presence::create_presence_task(redis_client.clone(), bot.shard_manager.clone()),
+        ),
+    );
+
+    // Restart shards that lost their connection to Discord for too long
+    discord_watchdog::spawn(bot.shard_manager.clone());
+
+    // The admin API shares the HTTP server with the Meetup OAuth2 logins
+    let admin_api = admin_api_token.map(|admin_api_token| {
+        Arc::new(admin_api::AdminApi::new(
+            admin_api_token,
+            redis_client.clone(),
+            discord_api.clone(),
+            bot_id.0,
+            task_scheduler.clone(),
+            async_meetup_client.clone(),
+            event_source.clone(),
+            tx,
+        ))
+    });
+
     // Start a server to handle Meetup OAuth2 logins
     let meetup_oauth2_server = meetup_oauth2_consumer.create_auth_server(
-        ([127, 0, 0, 1], 3000).into(),
+        ([127, 0, 0, 1], http_port).into(),
         redis_client
             .get_connection()
             .expect("Could not connect to Redis"),
         bot.cache_and_http.clone(),
         meetup_client.clone(),
         async_meetup_client.clone(),
+        admin_api,
         bot.data
             .read()
             .get::<discord_bot::BotNameKey>()
@@ -113,21 +379,34 @@ fn main() {
     );
 
     // let meetup_syncing_task = meetup_sync::create_recurring_syncing_task(
-    //     async_meetup_client.clone(),
+    //     event_source.clone(),
     //     redis_client.clone(),
     // )
     // .map_err(|err| eprintln!("Meetup syncing task failed: {}", err));
 
+    // Keep the cached Meetup profiles of linked users up-to-date
+    let profile_syncing_task = meetup_sync::create_recurring_profile_syncing_task(
+        async_meetup_client.clone(),
+        redis_client.clone(),
+    )
+    .map_err(|err| eprintln!("Profile syncing task failed: {}", err));
+
     std::thread::spawn(move || {
         tokio::run(
             meetup_oauth2_server
-                .join(spawn_other_futures_future)
+                .join3(spawn_other_futures_future, profile_syncing_task)
                 .map(|_| ()),
         )
     });
 
     // Finally, start the Discord bot
-    if let Err(why) = bot.start() {
+    let start_result = match (shard_count, shard_range) {
+        (Some(shard_count), Some(shard_range)) => bot.start_shard_range(shard_range, shard_count),
+        (Some(shard_count), None) => bot.start_shards(shard_count),
+        (None, Some(_)) => panic!("DISCORD_SHARD_RANGE requires DISCORD_SHARD_COUNT"),
+        (None, None) => bot.start(),
+    };
+    if let Err(why) = start_result {
         println!("Client error: {:?}", why);
     }
 }
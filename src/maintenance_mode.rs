@@ -0,0 +1,24 @@
+use redis::Commands;
+
+// While the maintenance mode is active, the bot still answers informational
+// commands but refuses everything that would change Discord or Redis, and the
+// scheduled tasks are put on hold. This is useful during Redis migrations or
+// Discord incidents.
+const REDIS_MAINTENANCE_MODE_KEY: &'static str = "maintenance_mode";
+
+pub fn is_active<C: redis::ConnectionLike>(con: &mut C) -> Result<bool, crate::BoxedError> {
+    let active: Option<bool> = con.get(REDIS_MAINTENANCE_MODE_KEY)?;
+    Ok(active.unwrap_or(false))
+}
+
+pub fn set_active<C: redis::ConnectionLike>(
+    con: &mut C,
+    active: bool,
+) -> Result<(), crate::BoxedError> {
+    if active {
+        let _: () = con.set(REDIS_MAINTENANCE_MODE_KEY, true)?;
+    } else {
+        let _: () = con.del(REDIS_MAINTENANCE_MODE_KEY)?;
+    }
+    Ok(())
+}
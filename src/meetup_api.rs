@@ -1,4 +1,5 @@
 use chrono::serde::ts_milliseconds;
+use futures::future;
 use futures::stream;
 use futures::{Future, Stream};
 use reqwest::header::{HeaderMap, AUTHORIZATION};
@@ -28,6 +29,17 @@ pub struct Photo {
 pub struct GroupProfile {
     pub role: Option<LeadershipRole>,
     pub status: UserStatus,
+    // Only on Meetup Pro groups that charge dues
+    #[serde(default)]
+    pub membership_dues: Option<MembershipDues>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MembershipDues {
+    pub status: String,
+    // Milliseconds since the epoch
+    #[serde(default)]
+    pub paid_until: Option<i64>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -38,11 +50,23 @@ pub struct User {
     pub group_profile: Option<GroupProfile>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct GroupMember {
+    pub id: u64,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Group {
     pub urlname: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct Venue {
+    pub name: String,
+    pub address_1: Option<String>,
+    pub city: Option<String>,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum UserStatus {
     None,
@@ -70,6 +94,15 @@ pub struct Event {
     pub link: String,
     pub group: Group,
     pub description: String,
+    // Not set if the event has no RSVP limit
+    #[serde(default)]
+    pub rsvp_limit: Option<u32>,
+    #[serde(default)]
+    pub venue: Option<Venue>,
+    #[serde(default)]
+    pub is_online_event: bool,
+    #[serde(default)]
+    pub how_to_find_us: Option<String>,
 }
 
 impl<'de> Deserialize<'de> for UserStatus {
@@ -128,6 +161,15 @@ pub enum RSVPResponse {
 pub struct RSVP {
     pub member: User,
     pub response: RSVPResponse,
+    // Only set if the event asks questions when members RSVP
+    #[serde(default)]
+    pub answers: Vec<RSVPAnswer>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RSVPAnswer {
+    pub question: String,
+    pub answer: String,
 }
 
 impl<'de> Deserialize<'de> for RSVPResponse {
@@ -166,11 +208,11 @@ impl Client {
     pub fn get_group_profile(&self, id: Option<u64>, urlname: &str) -> crate::Result<Option<User>> {
         let url = match id {
             Some(id) => format!(
-                "{}/{}/members/{}?&sign=true&photo-host=public&only=id,name,photo,group_profile&omit=group_profile.group,group_profile.answers",
+                "{}/{}/members/{}?&sign=true&photo-host=public&fields=membership_dues&only=id,name,photo,group_profile&omit=group_profile.group,group_profile.answers",
                 BASE_URL, urlname, id
             ),
             _ => format!(
-                "{}/{}/members/self?&sign=true&photo-host=public&only=id,name,photo,group_profile&omit=group_profile.group,group_profile.answers",
+                "{}/{}/members/self?&sign=true&photo-host=public&fields=membership_dues&only=id,name,photo,group_profile&omit=group_profile.group,group_profile.answers",
                 BASE_URL, urlname
             ),
         };
@@ -183,6 +225,25 @@ impl Client {
         }
     }
 
+    // The group profile of the user that the token belongs to. Unlike
+    // get_group_profile, this tells a rejected token (None) apart from other
+    // errors like timeouts or outages of Meetup (Err).
+    pub fn get_own_group_profile(&self, urlname: &str) -> crate::Result<Option<User>> {
+        let url = format!(
+            "{}/{}/members/self?&sign=true&photo-host=public&fields=membership_dues&only=id,name,photo,group_profile&omit=group_profile.group,group_profile.answers",
+            BASE_URL, urlname
+        );
+        let response = self
+            .client
+            .execute(Request::new(Method::GET, url.parse()?))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Ok(None);
+        }
+        let mut response = response.error_for_status()?;
+        Ok(Some(response.json::<User>()?))
+    }
+
     pub fn get_member_profile(&self, id: Option<u64>) -> crate::Result<Option<User>> {
         let url = match id {
             Some(id) => format!(
@@ -202,6 +263,49 @@ impl Client {
             return Ok(None);
         }
     }
+
+    // Oldest first. Follows Meetup's pagination, since a group can have
+    // any number of past events.
+    pub fn get_past_events(&self, urlname: &str) -> crate::Result<Vec<Event>> {
+        let mut url = Some(format!("{}/{}/events?&sign=true&photo-host=public&page=200&fields=event_hosts&status=past&only=description,event_hosts.id,event_hosts.name,id,link,time,name,group.urlname,rsvp_limit,venue.name,venue.address_1,venue.city,is_online_event,how_to_find_us", BASE_URL, urlname));
+        let mut events = vec![];
+        while let Some(current_url) = url {
+            let mut response = self
+                .client
+                .execute(Request::new(Method::GET, current_url.parse()?))?
+                .error_for_status()?;
+            url = response
+                .headers()
+                .get(reqwest::header::LINK)
+                .and_then(|link| link.to_str().ok())
+                .and_then(next_page_url);
+            let page: Vec<Event> = response.json()?;
+            events.extend(page);
+        }
+        Ok(events)
+    }
+
+    // Get members that RSVP'd
+    pub fn get_rsvps(&self, urlname: &str, event_id: &str) -> crate::Result<Vec<RSVP>> {
+        let url = format!("{}/{}/events/{}/rsvps?&sign=true&photo-host=public&page=200&fields=answers&only=response,member,answers&omit=member.event_context", BASE_URL, urlname, event_id);
+        let mut response = self
+            .client
+            .execute(Request::new(Method::GET, url.parse()?))?
+            .error_for_status()?;
+        Ok(response.json()?)
+    }
+}
+
+// Extracts the URL marked with rel="next" from a Link header
+fn next_page_url(link_header: &str) -> Option<String> {
+    link_header
+        .split(',')
+        .find(|link| link.contains("rel=\"next\""))
+        .and_then(|link| {
+            let start = link.find('<')?;
+            let end = link.find('>')?;
+            link.get(start + 1..end).map(str::to_string)
+        })
 }
 
 #[derive(Debug)]
@@ -265,11 +369,11 @@ impl AsyncClient {
     ) -> impl Future<Item = Option<User>, Error = Error> {
         let url = match id {
             Some(id) => format!(
-                "{}/{}/members/{}?&sign=true&photo-host=public&only=id,name,photo,group_profile&omit=group_profile.group,group_profile.answers",
+                "{}/{}/members/{}?&sign=true&photo-host=public&fields=membership_dues&only=id,name,photo,group_profile&omit=group_profile.group,group_profile.answers",
                 BASE_URL, urlname, id
             ),
             _ => format!(
-                "{}/{}/members/self?&sign=true&photo-host=public&only=id,name,photo,group_profile&omit=group_profile.group,group_profile.answers",
+                "{}/{}/members/self?&sign=true&photo-host=public&fields=membership_dues&only=id,name,photo,group_profile&omit=group_profile.group,group_profile.answers",
                 BASE_URL, urlname
             ),
         };
@@ -281,8 +385,8 @@ impl AsyncClient {
             .map(|user: User| Some(user))
     }
 
-    // Gets the user with the specified ID
-    // TODO: currently we cannot distinguish between a non-existing user or some other kind of error
+    // Gets the user with the specified ID.
+    // Returns None if Meetup reports that the profile does not exist (anymore).
     pub fn get_member_profile(
         &self,
         id: Option<u64>,
@@ -301,14 +405,21 @@ impl AsyncClient {
             .get(&url)
             .send()
             .from_err::<Error>()
-            .and_then(Self::try_deserialize)
-            .map(|user: User| Some(user))
+            .and_then(|response| {
+                let status = response.status();
+                if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::GONE
+                {
+                    future::Either::A(future::ok(None))
+                } else {
+                    future::Either::B(Self::try_deserialize(response).map(|user: User| Some(user)))
+                }
+            })
     }
 
     // Doesn't implement pagination. But since Meetup returns 200 elements per page,
     // this does not matter for us anyway
     pub fn get_upcoming_events(&self, urlname: &str) -> impl Stream<Item = Event, Error = Error> {
-        let url = format!("{}/{}/events?&sign=true&photo-host=public&page=200&fields=event_hosts&has_ended=false&status=upcoming&only=description,event_hosts.id,event_hosts.name,id,link,time,name,group.urlname", BASE_URL, 
+        let url = format!("{}/{}/events?&sign=true&photo-host=public&page=200&fields=event_hosts&has_ended=false&status=upcoming&only=description,event_hosts.id,event_hosts.name,id,link,time,name,group.urlname,rsvp_limit,venue.name,venue.address_1,venue.city,is_online_event,how_to_find_us", BASE_URL, 
         urlname);
         let request = self.client.get(&url);
         request
@@ -319,6 +430,36 @@ impl AsyncClient {
             .flatten_stream()
     }
 
+    // The IDs of all members of the group. Follows Meetup's pagination, since
+    // a group can have thousands of members.
+    pub fn get_group_member_ids(&self, urlname: &str) -> impl Stream<Item = u64, Error = Error> {
+        let client = self.client.clone();
+        let url = format!(
+            "{}/{}/members?&sign=true&page=200&only=id",
+            BASE_URL, urlname
+        );
+        stream::unfold(Some(url), move |url| {
+            let url = url?;
+            let page = client
+                .get(&url)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .from_err::<Error>()
+                .and_then(|response| {
+                    let next_url = response
+                        .headers()
+                        .get(reqwest::header::LINK)
+                        .and_then(|link| link.to_str().ok())
+                        .and_then(next_page_url);
+                    Self::try_deserialize(response)
+                        .map(move |members: Vec<GroupMember>| (members, next_url))
+                });
+            Some(page)
+        })
+        .map(|members| stream::iter_ok(members.into_iter().map(|member| member.id)))
+        .flatten()
+    }
+
     pub fn get_upcoming_events_all_groups(&self) -> impl Stream<Item = Event, Error = Error> {
         let streams: Vec<_> = URLNAMES
             .iter()
@@ -333,7 +474,7 @@ impl AsyncClient {
         urlname: &str,
         event_id: &str,
     ) -> impl Future<Item = Vec<RSVP>, Error = Error> {
-        let url = format!("{}/{}/events/{}/rsvps?&sign=true&photo-host=public&page=200&only=response,member&omit=member.photo,member.event_context", BASE_URL, urlname, event_id);
+        let url = format!("{}/{}/events/{}/rsvps?&sign=true&photo-host=public&page=200&fields=answers&only=response,member,answers&omit=member.event_context", BASE_URL, urlname, event_id);
         let request = self.client.get(&url);
         request
             .send()
@@ -0,0 +1,131 @@
+use crate::meetup_api;
+use redis::{Commands, PipelineCommands};
+
+// One-shot import of a Meetup group's past events, for communities that start
+// using the bot when they already have some history. The events are grouped
+// into series by the same tags as in the Meetup sync ("[new adventure]",
+// "[new campaign]", "[campaign <event ID>]") and stored with their hosts and
+// the members that RSVP'd 'yes', such that session counts and attendance are
+// right from the start.
+// Imported series are marked historical and are not added to "event_series",
+// so the Discord sync doesn't create channels for them. Once an upcoming event
+// continues one of them with "[campaign <event ID>]", the Meetup sync picks the
+// series up like any other.
+// Events that Redis already knows about are skipped, so the import can be
+// repeated.
+pub const REDIS_HISTORICAL_SERIES_KEY: &'static str = "historical_event_series";
+
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub num_imported_events: u64,
+    pub num_new_series: u64,
+    pub num_known_events: u64,
+    pub num_ignored_events: u64,
+}
+
+pub fn import_past_events(
+    meetup_client: &meetup_api::Client,
+    urlname: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<ImportSummary, crate::BoxedError> {
+    let tag_rules = crate::event_tags::TagRules::load(con)?;
+    let mut summary = ImportSummary::default();
+    // Oldest first, such that the events a campaign session refers to are
+    // imported before it
+    for event in meetup_client.get_past_events(urlname)? {
+        let redis_event_series_key = format!("meetup_event:{}:event_series", event.id);
+        let known_series_id: Option<String> = con.get(&redis_event_series_key)?;
+        if known_series_id.is_some() {
+            summary.num_known_events += 1;
+            continue;
+        }
+        let is_new_adventure = crate::meetup_sync::NEW_ADVENTURE_REGEX.is_match(&event.description);
+        let is_new_campaign = crate::meetup_sync::NEW_CAMPAIGN_REGEX.is_match(&event.description);
+        let series_event_id = crate::meetup_sync::EVENT_SERIES_REGEX
+            .captures(&event.description)
+            .and_then(|captures| captures.name("event_id"))
+            .map(|event_id| event_id.as_str().to_string());
+        let (series_id, is_new_series) = if is_new_adventure || is_new_campaign {
+            (crate::meetup_oauth2::new_random_id(16), true)
+        } else if let Some(series_event_id) = series_event_id {
+            let series_id: Option<String> =
+                con.get(format!("meetup_event:{}:event_series", series_event_id))?;
+            match series_id {
+                Some(series_id) => (series_id, false),
+                None => {
+                    println!(
+                        "Ignoring event \"{}\" since the event {} it continues was not imported",
+                        event.name, series_event_id
+                    );
+                    summary.num_ignored_events += 1;
+                    continue;
+                }
+            }
+        } else {
+            summary.num_ignored_events += 1;
+            continue;
+        };
+        let attendee_ids: Vec<u64> = meetup_client
+            .get_rsvps(urlname, &event.id)?
+            .into_iter()
+            .filter(|rsvp| rsvp.response == meetup_api::RSVPResponse::Yes)
+            .map(|rsvp| rsvp.member.id)
+            .collect();
+        let host_ids: Vec<u64> = event.event_hosts.iter().map(|user| user.id).collect();
+        let tags = tag_rules.extract_tags(&event.name, &event.description);
+        let redis_event_key = format!("meetup_event:{}", event.id);
+        let redis_event_hosts_key = format!("meetup_event:{}:meetup_hosts", event.id);
+        let redis_event_users_key = format!("meetup_event:{}:meetup_users", event.id);
+        let redis_event_tags_key = format!("meetup_event:{}:tags", event.id);
+        let redis_series_events_key = format!("event_series:{}:meetup_events", series_id);
+        let redis_completed_sessions_key = format!("event_series:{}:completed_sessions", series_id);
+        let event_hash = &[
+            ("name", event.name.clone()),
+            ("time", event.time.to_rfc3339()),
+            ("link", event.link.clone()),
+            ("urlname", event.group.urlname.clone()),
+            ("historical", "1".to_string()),
+        ];
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        if is_new_series {
+            let series_type = if is_new_campaign {
+                "campaign"
+            } else {
+                "adventure"
+            };
+            pipe.sadd(REDIS_HISTORICAL_SERIES_KEY, &series_id)
+                .ignore()
+                .set(format!("event_series:{}:type", series_id), series_type)
+                .ignore();
+        }
+        pipe.sadd("meetup_events", &event.id)
+            .ignore()
+            .set(&redis_event_series_key, &series_id)
+            .ignore()
+            .sadd(&redis_series_events_key, &event.id)
+            .ignore()
+            .sadd(&redis_completed_sessions_key, &event.id)
+            .ignore()
+            .hset_multiple(&redis_event_key, event_hash)
+            .ignore();
+        if !host_ids.is_empty() {
+            pipe.sadd(&redis_event_hosts_key, host_ids).ignore();
+        }
+        if !attendee_ids.is_empty() {
+            pipe.sadd(&redis_event_users_key, attendee_ids).ignore();
+        }
+        if !tags.is_empty() {
+            pipe.sadd(&redis_event_tags_key, tags).ignore();
+        }
+        let _: () = pipe.query(con)?;
+        println!("Imported event \"{}\"", event.name);
+        summary.num_imported_events += 1;
+        if is_new_series {
+            summary.num_new_series += 1;
+        }
+        // A naive rate limit for the Meetup API, like in the Meetup sync
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+    Ok(summary)
+}
@@ -0,0 +1,109 @@
+use crate::strings;
+use redis::Commands;
+
+// Number of consecutive failed Meetup API interactions after which the
+// circuit breaker trips
+const FAILURE_THRESHOLD: u64 = 5;
+// How long Meetup-dependent steps are skipped once the circuit breaker tripped
+const COOL_DOWN_MINUTES: i64 = 15;
+
+const REDIS_FAILURES_KEY: &'static str = "meetup_api:consecutive_failures";
+const REDIS_OPEN_UNTIL_KEY: &'static str = "meetup_api:circuit_open_until";
+const REDIS_ANNOUNCED_DEGRADED_KEY: &'static str = "meetup_api:degraded_announced";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CircuitState {
+    // Everything is fine, talk to Meetup
+    Closed,
+    // Meetup seems to be down, don't talk to it until the cool-down is over
+    Open {
+        until: chrono::DateTime<chrono::Utc>,
+    },
+    // The cool-down is over, the next Meetup interaction is a probe
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn allows_requests(&self) -> bool {
+        match self {
+            CircuitState::Open { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+pub fn state<C: redis::ConnectionLike>(con: &mut C) -> Result<CircuitState, crate::BoxedError> {
+    let open_until: Option<String> = con.get(REDIS_OPEN_UNTIL_KEY)?;
+    let open_until = open_until
+        .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
+        .transpose()?
+        .map(|t| t.with_timezone(&chrono::Utc));
+    match open_until {
+        Some(until) if until > chrono::Utc::now() => Ok(CircuitState::Open { until: until }),
+        Some(_) => Ok(CircuitState::HalfOpen),
+        None => Ok(CircuitState::Closed),
+    }
+}
+
+pub fn record_success<C: redis::ConnectionLike>(con: &mut C) -> Result<(), crate::BoxedError> {
+    let was_open: bool = con.exists(REDIS_OPEN_UNTIL_KEY)?;
+    let _: () = con.del(&[REDIS_FAILURES_KEY, REDIS_OPEN_UNTIL_KEY])?;
+    if was_open {
+        println!("Meetup API probe succeeded, closing the circuit breaker");
+    }
+    Ok(())
+}
+
+pub fn record_failure<C: redis::ConnectionLike>(con: &mut C) -> Result<(), crate::BoxedError> {
+    let current_state = state(con)?;
+    let num_failures: u64 = con.incr(REDIS_FAILURES_KEY, 1)?;
+    // A failed probe re-opens the circuit right away
+    if num_failures >= FAILURE_THRESHOLD || current_state == CircuitState::HalfOpen {
+        let open_until = chrono::Utc::now() + chrono::Duration::minutes(COOL_DOWN_MINUTES);
+        let _: () = con.set(REDIS_OPEN_UNTIL_KEY, open_until.to_rfc3339())?;
+        println!(
+            "Meetup API failed {} times in a row, skipping Meetup until {}",
+            num_failures, open_until
+        );
+    }
+    Ok(())
+}
+
+// Lets the organizers know when the bot enters or leaves the degraded mode.
+// This is called periodically from the Discord syncing task, since the
+// Meetup syncing task has no access to Discord.
+pub fn announce_state_changes(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let is_degraded = state(con)? != CircuitState::Closed;
+    let was_announced: bool = con.exists(REDIS_ANNOUNCED_DEGRADED_KEY)?;
+    if is_degraded && !was_announced {
+        crate::discord_notifications::notify_organizers(
+            discord_api,
+            strings::MEETUP_API_DEGRADED_NOTIFICATION,
+            con,
+        )?;
+        let _: () = con.set(REDIS_ANNOUNCED_DEGRADED_KEY, true)?;
+    } else if !is_degraded && was_announced {
+        crate::discord_notifications::notify_organizers(
+            discord_api,
+            strings::MEETUP_API_RECOVERED_NOTIFICATION,
+            con,
+        )?;
+        let _: () = con.del(REDIS_ANNOUNCED_DEGRADED_KEY)?;
+    }
+    Ok(())
+}
+
+// Human readable status for the health endpoint
+pub fn health_status<C: redis::ConnectionLike>(con: &mut C) -> Result<String, crate::BoxedError> {
+    match state(con)? {
+        CircuitState::Open { until } => Ok(format!(
+            "degraded: Meetup API unavailable, serving cached data until {}",
+            until.to_rfc3339()
+        )),
+        CircuitState::HalfOpen => Ok("degraded: probing Meetup API".to_string()),
+        CircuitState::Closed => Ok("ok".to_string()),
+    }
+}
@@ -0,0 +1,280 @@
+use crate::meetup_api;
+use redis::{Commands, PipelineCommands};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+// Meetup sometimes lists a recurring event twice, e.g. the original and a
+// clone that was created when the event was edited. Both carry the "[new
+// campaign]" tag, so each of them would get a series and a channel of its
+// own. Before the Meetup sync creates a new series, it therefore looks for
+// upcoming events with the same title (ignoring case and punctuation) within
+// DUPLICATE_WINDOW_MINUTES that already belong to a series. If exactly one
+// series matches and its event has the same hosts, the event joins that
+// series. Otherwise (several series, or different hosts, as with two tables
+// of the same adventure) the event is flagged for the organizers and left
+// alone until it indicates its series with "[campaign <event ID>]" or an
+// organizer allows it to start a series of its own.
+const DUPLICATE_WINDOW_MINUTES: i64 = 60;
+const REDIS_FLAGGED_KEY: &'static str = "flagged_duplicate_meetup_events";
+const REDIS_ALLOWED_KEY: &'static str = "allowed_duplicate_meetup_events";
+
+#[derive(Debug, PartialEq)]
+pub enum Duplicate {
+    // Not a duplicate, the event starts a new series
+    None,
+    // The series that the event is a duplicate of
+    Series(String),
+    // The series that the event might be a duplicate of
+    Ambiguous(Vec<String>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlaggedEvent {
+    pub name: String,
+    pub time: String,
+    pub link: String,
+    pub candidate_series: Vec<String>,
+}
+
+// Lowercase words, without tags and punctuation
+pub fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Checks an event that would start a new series
+pub fn find_duplicate<C: redis::ConnectionLike>(
+    event: &meetup_api::Event,
+    user_ids: crate::event_source::UserIds,
+    con: &mut C,
+) -> Result<Duplicate, crate::BoxedError> {
+    // Events that were synced before keep their series, and organizers have
+    // the last word
+    let redis_event_series_key = format!("meetup_event:{}:event_series", event.id);
+    let (series_id, is_allowed): (Option<String>, bool) = redis::pipe()
+        .get(&redis_event_series_key)
+        .sismember(REDIS_ALLOWED_KEY, &event.id)
+        .query(con)?;
+    if series_id.is_some() || is_allowed {
+        return Ok(Duplicate::None);
+    }
+    let title = normalize_title(&event.name);
+    let event_ids: Vec<String> = con.smembers("meetup_events")?;
+    let event_ids: Vec<String> = event_ids
+        .into_iter()
+        .filter(|event_id| *event_id != event.id)
+        .collect();
+    if event_ids.is_empty() {
+        return Ok(Duplicate::None);
+    }
+    let mut pipe = redis::pipe();
+    for event_id in &event_ids {
+        pipe.hget(format!("meetup_event:{}", event_id), &["name", "time"]);
+    }
+    let names_and_times: Vec<(Option<String>, Option<String>)> = pipe.query(con)?;
+    let window = chrono::Duration::minutes(DUPLICATE_WINDOW_MINUTES);
+    let candidate_ids: Vec<&String> = event_ids
+        .iter()
+        .zip(names_and_times)
+        .filter_map(|(event_id, name_and_time)| match name_and_time {
+            (Some(name), Some(time)) => {
+                let time = chrono::DateTime::parse_from_rfc3339(&time).ok()?;
+                let is_close = (time.with_timezone(&chrono::Utc) - event.time)
+                    .num_seconds()
+                    .abs()
+                    <= window.num_seconds();
+                if is_close && normalize_title(&name) == title {
+                    Some(event_id)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+        .collect();
+    if candidate_ids.is_empty() {
+        return Ok(Duplicate::None);
+    }
+    let mut pipe = redis::pipe();
+    for event_id in &candidate_ids {
+        pipe.get(format!("meetup_event:{}:event_series", event_id))
+            .smembers(user_ids.event_hosts_key(event_id));
+    }
+    let candidates: Vec<(Option<String>, HashSet<u64>)> = pipe.query(con)?;
+    let host_ids: HashSet<u64> = event.event_hosts.iter().map(|host| host.id).collect();
+    Ok(classify(candidates, &host_ids))
+}
+
+// Decides based on the series and hosts of the matching events
+fn classify(candidates: Vec<(Option<String>, HashSet<u64>)>, host_ids: &HashSet<u64>) -> Duplicate {
+    // Series with whether one of their matching events has the same hosts
+    let mut candidate_series: BTreeMap<String, bool> = BTreeMap::new();
+    for (series_id, candidate_host_ids) in candidates {
+        if let Some(series_id) = series_id {
+            *candidate_series.entry(series_id).or_insert(false) |= candidate_host_ids == *host_ids;
+        }
+    }
+    let mut candidate_series: Vec<(String, bool)> = candidate_series.into_iter().collect();
+    match candidate_series.len() {
+        0 => Duplicate::None,
+        1 if candidate_series[0].1 => Duplicate::Series(candidate_series.remove(0).0),
+        _ => Duplicate::Ambiguous(
+            candidate_series
+                .into_iter()
+                .map(|(series_id, _)| series_id)
+                .collect(),
+        ),
+    }
+}
+
+pub fn flag<C: redis::ConnectionLike>(
+    event: &meetup_api::Event,
+    candidate_series: Vec<String>,
+    con: &mut C,
+) -> Result<(), crate::BoxedError> {
+    let flagged_event = FlaggedEvent {
+        name: event.name.clone(),
+        time: event.time.to_rfc3339(),
+        link: event.link.clone(),
+        candidate_series: candidate_series,
+    };
+    let _: () = con.hset(
+        REDIS_FLAGGED_KEY,
+        &event.id,
+        serde_json::to_string(&flagged_event)?,
+    )?;
+    Ok(())
+}
+
+// Called in the transaction that syncs the event. Once the event belongs to a
+// series, it isn't checked anymore.
+pub fn unflag(pipe: &mut redis::Pipeline, event_id: &str) {
+    pipe.hdel(REDIS_FLAGGED_KEY, event_id)
+        .ignore()
+        .srem(REDIS_ALLOWED_KEY, event_id)
+        .ignore();
+}
+
+// The flagged events that are still upcoming, soonest first. Past ones are
+// forgotten.
+pub fn flagged_events<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Vec<(String, FlaggedEvent)>, crate::BoxedError> {
+    let flagged: Vec<(String, String)> = con.hgetall(REDIS_FLAGGED_KEY)?;
+    let now = chrono::Utc::now();
+    let mut upcoming = vec![];
+    let mut past_event_ids = vec![];
+    for (event_id, flagged_event) in flagged {
+        let flagged_event: FlaggedEvent = match serde_json::from_str(&flagged_event) {
+            Ok(flagged_event) => flagged_event,
+            Err(err) => {
+                eprintln!(
+                    "Could not parse the flagged duplicate event \"{}\": {}",
+                    flagged_event, err
+                );
+                continue;
+            }
+        };
+        let is_upcoming = chrono::DateTime::parse_from_rfc3339(&flagged_event.time)
+            .map(|time| time > now)
+            .unwrap_or(false);
+        if is_upcoming {
+            upcoming.push((event_id, flagged_event));
+        } else {
+            past_event_ids.push(event_id);
+        }
+    }
+    if !past_event_ids.is_empty() {
+        let _: () = con.hdel(REDIS_FLAGGED_KEY, past_event_ids)?;
+    }
+    upcoming.sort_by(|(_, event1), (_, event2)| event1.time.cmp(&event2.time));
+    Ok(upcoming)
+}
+
+// Lets a flagged event start a series of its own with the next Meetup sync.
+// Returns false if the event is not flagged.
+pub fn allow<C: redis::ConnectionLike>(
+    event_id: &str,
+    con: &mut C,
+) -> Result<bool, crate::BoxedError> {
+    let (num_removed,): (u64,) = redis::pipe()
+        .atomic()
+        .hdel(REDIS_FLAGGED_KEY, event_id)
+        .sadd(REDIS_ALLOWED_KEY, event_id)
+        .ignore()
+        .query(con)?;
+    if num_removed == 0 {
+        let _: () = con.srem(REDIS_ALLOWED_KEY, event_id)?;
+    }
+    Ok(num_removed > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts(ids: &[u64]) -> HashSet<u64> {
+        ids.iter().cloned().collect()
+    }
+
+    #[test]
+    fn normalize_title_ignores_case_tags_and_punctuation() {
+        assert_eq!(
+            normalize_title("Curse of Strahd [new campaign]"),
+            "curse of strahd new campaign"
+        );
+        assert_eq!(
+            normalize_title("  CURSE of Strahd!! -- [New Campaign] "),
+            "curse of strahd new campaign"
+        );
+        assert_eq!(normalize_title("Töten & Würfeln"), "töten würfeln");
+        assert_eq!(normalize_title("[]!?"), "");
+    }
+
+    #[test]
+    fn one_series_with_the_same_hosts_is_a_duplicate() {
+        let candidates = vec![
+            (Some("series1".to_string()), hosts(&[1, 2])),
+            // Another event of the same series with different hosts
+            (Some("series1".to_string()), hosts(&[3])),
+            // Events without a series don't count
+            (None, hosts(&[1, 2])),
+        ];
+        assert_eq!(
+            classify(candidates, &hosts(&[2, 1])),
+            Duplicate::Series("series1".to_string())
+        );
+    }
+
+    #[test]
+    fn several_series_are_ambiguous() {
+        let candidates = vec![
+            (Some("series2".to_string()), hosts(&[1])),
+            (Some("series1".to_string()), hosts(&[1])),
+        ];
+        assert_eq!(
+            classify(candidates, &hosts(&[1])),
+            Duplicate::Ambiguous(vec!["series1".to_string(), "series2".to_string()])
+        );
+    }
+
+    #[test]
+    fn one_series_with_different_hosts_is_ambiguous() {
+        let candidates = vec![(Some("series1".to_string()), hosts(&[1, 2]))];
+        assert_eq!(
+            classify(candidates, &hosts(&[1])),
+            Duplicate::Ambiguous(vec!["series1".to_string()])
+        );
+    }
+
+    #[test]
+    fn no_series_is_not_a_duplicate() {
+        let candidates = vec![(None, hosts(&[1]))];
+        assert_eq!(classify(candidates, &hosts(&[1])), Duplicate::None);
+        assert_eq!(classify(vec![], &hosts(&[1])), Duplicate::None);
+    }
+}
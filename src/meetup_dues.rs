@@ -0,0 +1,329 @@
+use crate::meetup_api;
+use futures::{future, stream, Future, Stream};
+use redis::{Commands, PipelineCommands};
+use serenity::http::CacheHttp;
+use serenity::model::id::RoleId;
+use simple_error::SimpleError;
+use std::collections::HashSet;
+
+// Groups on Meetup Pro can charge membership dues. Once organizers picked the
+// group with "config set dues_group <urlname>", the dues status of all linked
+// members is refreshed once a day and shown by "whois @user". Roles that
+// organizers gate with "dues role @role" are given by the Discord sync to
+// exactly the linked members whose dues are paid (or who are exempt), and
+// taken away from everybody else. Members of a household share the status of
+// the Meetup account they share.
+const REDIS_GATED_ROLES_KEY: &'static str = "dues_gated_roles";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DuesStatus {
+    Paid,
+    Exempt,
+    Unpaid,
+    Expired,
+}
+
+impl DuesStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DuesStatus::Paid => "paid",
+            DuesStatus::Exempt => "exempt",
+            DuesStatus::Unpaid => "unpaid",
+            DuesStatus::Expired => "expired",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DuesStatus> {
+        [
+            DuesStatus::Paid,
+            DuesStatus::Exempt,
+            DuesStatus::Unpaid,
+            DuesStatus::Expired,
+        ]
+        .iter()
+        .find(|status| status.name().eq_ignore_ascii_case(name))
+        .cloned()
+    }
+
+    pub fn is_active(&self) -> bool {
+        *self == DuesStatus::Paid || *self == DuesStatus::Exempt
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Dues {
+    pub status: DuesStatus,
+    pub paid_until: Option<chrono::DateTime<chrono::Utc>>,
+    // When the status was last refreshed from Meetup
+    pub checked_time: chrono::DateTime<chrono::Utc>,
+}
+
+fn redis_dues_key(meetup_id: u64) -> String {
+    format!("meetup_user:{}:dues", meetup_id)
+}
+
+fn from_timestamp(timestamp: i64) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::<chrono::Utc>::from_utc(
+        chrono::NaiveDateTime::from_timestamp(timestamp, 0),
+        chrono::Utc,
+    )
+}
+
+// Meetup knows more states than we care about, anything that isn't paid or
+// exempt counts as unpaid. Paid dues expire at the end of the paid period.
+fn status(membership_dues: Option<&meetup_api::MembershipDues>) -> DuesStatus {
+    let membership_dues = match membership_dues {
+        Some(membership_dues) => membership_dues,
+        None => return DuesStatus::Unpaid,
+    };
+    match membership_dues.status.as_str() {
+        "paid" | "active" => match membership_dues.paid_until {
+            Some(paid_until) if paid_until / 1000 < chrono::Utc::now().timestamp() => {
+                DuesStatus::Expired
+            }
+            _ => DuesStatus::Paid,
+        },
+        "exempt" => DuesStatus::Exempt,
+        "expired" | "overdue" => DuesStatus::Expired,
+        _ => DuesStatus::Unpaid,
+    }
+}
+
+pub fn dues<C: redis::ConnectionLike>(
+    meetup_id: u64,
+    con: &mut C,
+) -> Result<Option<Dues>, crate::BoxedError> {
+    let (status, paid_until, checked_time): (Option<String>, Option<i64>, Option<i64>) = con.hget(
+        redis_dues_key(meetup_id),
+        &["status", "paid_until", "checked_time"],
+    )?;
+    let (status, checked_time) = match (
+        status
+            .as_ref()
+            .and_then(|status| DuesStatus::from_name(status)),
+        checked_time,
+    ) {
+        (Some(status), Some(checked_time)) => (status, checked_time),
+        _ => return Ok(None),
+    };
+    Ok(Some(Dues {
+        status: status,
+        paid_until: paid_until.map(from_timestamp),
+        checked_time: from_timestamp(checked_time),
+    }))
+}
+
+// Refreshes the dues status of all linked members, one request per second
+pub fn sync_dues_task(
+    meetup_client: meetup_api::AsyncClient,
+    mut redis_client: crate::redis_namespace::Client,
+) -> impl Future<Item = (), Error = crate::BoxedError> + Send + 'static {
+    let urlname = match crate::guild_config::dues_group(&mut redis_client) {
+        Ok(Some(urlname)) => urlname,
+        Ok(None) => return future::Either::A(future::ok(())),
+        Err(err) => return future::Either::A(future::err(err)),
+    };
+    match crate::maintenance_mode::is_active(&mut redis_client) {
+        Ok(false) => (),
+        Ok(true) => {
+            println!("Dues syncing task: maintenance mode is active, skipping");
+            return future::Either::A(future::ok(()));
+        }
+        Err(err) => return future::Either::A(future::err(err)),
+    }
+    match crate::meetup_circuit_breaker::state(&mut redis_client) {
+        Ok(state) if !state.allows_requests() => {
+            println!("Dues syncing task: Meetup API circuit breaker is open, skipping");
+            return future::Either::A(future::ok(()));
+        }
+        Ok(_) => (),
+        Err(err) => return future::Either::A(future::err(err)),
+    }
+    let meetup_user_ids: Vec<u64> = match redis_client.smembers("meetup_users") {
+        Ok(ids) => ids,
+        Err(err) => return future::Either::A(future::err(err.into())),
+    };
+    let dues_sync_future = stream::iter_ok(meetup_user_ids).for_each(move |meetup_id| {
+        let redis_client = redis_client.clone();
+        meetup_client
+            .get_group_profile(Some(meetup_id), urlname)
+            .from_err::<crate::BoxedError>()
+            .and_then(move |user| {
+                let mut con = redis_client.get_connection()?;
+                store_dues(meetup_id, user.as_ref(), &mut con)
+            })
+            .then(move |res| {
+                // "Catch" any errors and don't abort the stream
+                if let Err(err) = res {
+                    eprintln!("Dues sync of Meetup user {} failed: {}", meetup_id, err);
+                }
+                // Add a 1s delay between each item as a naive rate limit for the Meetup API
+                tokio::timer::Delay::new(
+                    std::time::Instant::now() + std::time::Duration::from_secs(1),
+                )
+                .from_err::<crate::BoxedError>()
+            })
+    });
+    future::Either::B(dues_sync_future)
+}
+
+fn store_dues(
+    meetup_id: u64,
+    user: Option<&meetup_api::User>,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    let membership_dues = user
+        .and_then(|user| user.group_profile.as_ref())
+        .and_then(|group_profile| group_profile.membership_dues.as_ref());
+    let redis_dues_key = redis_dues_key(meetup_id);
+    let mut pipe = redis::pipe();
+    pipe.atomic()
+        .hset(&redis_dues_key, "status", status(membership_dues).name())
+        .ignore()
+        .hset(
+            &redis_dues_key,
+            "checked_time",
+            chrono::Utc::now().timestamp(),
+        )
+        .ignore();
+    match membership_dues.and_then(|membership_dues| membership_dues.paid_until) {
+        Some(paid_until) => pipe.hset(&redis_dues_key, "paid_until", paid_until / 1000),
+        None => pipe.hdel(&redis_dues_key, "paid_until"),
+    }
+    .ignore();
+    let _: () = pipe.query(con)?;
+    Ok(())
+}
+
+pub fn gated_roles<C: redis::ConnectionLike>(con: &mut C) -> Result<Vec<u64>, crate::BoxedError> {
+    let mut role_ids: Vec<u64> = con.smembers(REDIS_GATED_ROLES_KEY)?;
+    role_ids.sort_unstable();
+    Ok(role_ids)
+}
+
+pub fn set_gated<C: redis::ConnectionLike>(
+    role_id: u64,
+    gated: bool,
+    con: &mut C,
+) -> Result<(), crate::BoxedError> {
+    let _: () = if gated {
+        con.sadd(REDIS_GATED_ROLES_KEY, role_id)?
+    } else {
+        con.srem(REDIS_GATED_ROLES_KEY, role_id)?
+    };
+    Ok(())
+}
+
+// Part of the Discord sync. Members with several Meetup accounts get the
+// roles if the dues of any of them are paid. Takes four round trips to Redis,
+// plus one for each changed role.
+pub fn sync_gated_roles(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let gated_role_ids: HashSet<RoleId> = gated_roles(con)?.into_iter().map(RoleId).collect();
+    if gated_role_ids.is_empty() || crate::guild_config::dues_group(con)?.is_none() {
+        return Ok(());
+    }
+    // Collect the members first, such that the guild is not locked while
+    // we query Redis
+    let members: Vec<(u64, HashSet<RoleId>)> = {
+        let guild = crate::discord_sync::GUILD_ID
+            .to_guild_cached(&discord_api.cache)
+            .ok_or_else(|| SimpleError::new("Could not find the guild in the cache"))?;
+        let guild = guild.read();
+        guild
+            .members
+            .values()
+            .filter(|member| !member.user.read().bot)
+            .map(|member| {
+                (
+                    member.user.read().id.0,
+                    member
+                        .roles
+                        .iter()
+                        .filter(|role_id| gated_role_ids.contains(role_id))
+                        .cloned()
+                        .collect(),
+                )
+            })
+            .collect()
+    };
+    if members.is_empty() {
+        return Ok(());
+    }
+    let mut pipe = redis::pipe();
+    for &(discord_id, _) in &members {
+        pipe.smembers(crate::identity::redis_identities_key(discord_id))
+            .get(crate::households::redis_household_meetup_user_key(
+                discord_id,
+            ));
+    }
+    let links: Vec<(Vec<String>, Option<u64>)> = pipe.query(con)?;
+    // All Meetup accounts of each member, including the household's
+    let meetup_ids: Vec<Vec<u64>> = links
+        .into_iter()
+        .map(|(identities, household_meetup_id)| {
+            identities
+                .iter()
+                .filter_map(|identity| crate::identity::Identity::parse(identity))
+                .filter_map(|identity| identity.meetup_id())
+                .chain(household_meetup_id)
+                .collect()
+        })
+        .collect();
+    let linked_meetup_ids: Vec<u64> = meetup_ids
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let mut active_meetup_ids = HashSet::new();
+    if !linked_meetup_ids.is_empty() {
+        let mut pipe = redis::pipe();
+        for &meetup_id in &linked_meetup_ids {
+            pipe.hget(redis_dues_key(meetup_id), "status");
+        }
+        let statuses: Vec<Option<String>> = pipe.query(con)?;
+        for (meetup_id, status) in linked_meetup_ids.into_iter().zip(statuses) {
+            let is_active = status
+                .as_ref()
+                .and_then(|status| DuesStatus::from_name(status))
+                .map_or(false, |status| status.is_active());
+            if is_active {
+                active_meetup_ids.insert(meetup_id);
+            }
+        }
+    }
+    for ((discord_id, current_role_ids), meetup_ids) in members.into_iter().zip(meetup_ids) {
+        let is_active = meetup_ids
+            .iter()
+            .any(|meetup_id| active_meetup_ids.contains(meetup_id));
+        for role_id in &gated_role_ids {
+            let has_role = current_role_ids.contains(role_id);
+            let result = if is_active && !has_role {
+                discord_api.http().add_member_role(
+                    crate::discord_sync::GUILD_ID.0,
+                    discord_id,
+                    role_id.0,
+                )
+            } else if !is_active && has_role {
+                discord_api.http().remove_member_role(
+                    crate::discord_sync::GUILD_ID.0,
+                    discord_id,
+                    role_id.0,
+                )
+            } else {
+                continue;
+            };
+            if let Err(err) = result {
+                eprintln!(
+                    "Could not update the dues role {} of user {}: {}",
+                    role_id.0, discord_id, err
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use crate::meetup_api;
+use futures::{future, stream, Future, Stream};
+use redis::{Commands, PipelineCommands};
+use serenity::http::CacheHttp;
+use serenity::model::id::{ChannelId, RoleId};
+use simple_error::SimpleError;
+use std::collections::HashSet;
+
+// Notices when linked members leave (or are removed from) all of our Meetup
+// groups. Once a day, the member lists of the groups are compared with the
+// linked Meetup accounts, and the links of members that are in none of the
+// groups are flagged. The Discord sync then lets the organizers know and, if
+// "config set membership_grace_days <days>" is set, removes the member from
+// the channels where they are a player once the grace period is over.
+// Members that join a group again are unflagged, but are not added back to
+// the channels they were removed from.
+// Only runs if the "membership_sync" feature is enabled, since it needs a
+// Meetup account that can see the member lists.
+
+// Maps Meetup IDs to the time (Unix timestamp) they were first missing from
+// the groups
+const REDIS_LEFT_GROUP_KEY: &'static str = "meetup_users:left_group";
+// Meetup IDs whose departure the organizers were told about
+const REDIS_LEFT_GROUP_NOTIFIED_KEY: &'static str = "meetup_users:left_group_notified";
+// Meetup IDs whose channel access was removed
+const REDIS_LEFT_GROUP_REMOVED_KEY: &'static str = "meetup_users:left_group_removed";
+
+// Flags the links of members that aren't in any group anymore and unflags
+// the ones that are back
+pub fn sync_group_memberships_task(
+    meetup_client: meetup_api::AsyncClient,
+    mut redis_client: crate::redis_namespace::Client,
+) -> impl Future<Item = (), Error = crate::BoxedError> + Send + 'static {
+    match crate::feature_flags::is_enabled(
+        &mut redis_client,
+        crate::discord_sync::GUILD_ID,
+        crate::feature_flags::Feature::MembershipSync,
+    ) {
+        Ok(true) => (),
+        Ok(false) => return future::Either::A(future::ok(())),
+        Err(err) => return future::Either::A(future::err(err)),
+    }
+    match crate::maintenance_mode::is_active(&mut redis_client) {
+        Ok(false) => (),
+        Ok(true) => {
+            println!("Membership syncing task: maintenance mode is active, skipping");
+            return future::Either::A(future::ok(()));
+        }
+        Err(err) => return future::Either::A(future::err(err)),
+    }
+    match crate::meetup_circuit_breaker::state(&mut redis_client) {
+        Ok(state) if !state.allows_requests() => {
+            println!("Membership syncing task: Meetup API circuit breaker is open, skipping");
+            return future::Either::A(future::ok(()));
+        }
+        Ok(_) => (),
+        Err(err) => return future::Either::A(future::err(err)),
+    }
+    // A failed request fails the whole task, since a partial member list
+    // would flag members that didn't leave
+    let member_ids_futures: Vec<_> = meetup_api::URLNAMES
+        .iter()
+        .map(|urlname| meetup_client.get_group_member_ids(urlname).collect())
+        .collect();
+    let fut = stream::futures_unordered(member_ids_futures)
+        .concat2()
+        .from_err::<crate::BoxedError>()
+        .and_then(move |member_ids| {
+            let member_ids: HashSet<u64> = member_ids.into_iter().collect();
+            let mut con = redis_client.get_connection()?;
+            flag_departures(&member_ids, &mut con)
+        });
+    future::Either::B(fut)
+}
+
+fn flag_departures(
+    member_ids: &HashSet<u64>,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<(), crate::BoxedError> {
+    // Something is off if Meetup says that our groups are empty
+    if member_ids.is_empty() {
+        return Err(SimpleError::new("Meetup returned no group members").into());
+    }
+    let linked_meetup_ids: Vec<u64> = con.smembers("meetup_users")?;
+    let now = chrono::Utc::now().timestamp();
+    let mut pipe = redis::pipe();
+    for meetup_id in linked_meetup_ids {
+        if member_ids.contains(&meetup_id) {
+            pipe.hdel(REDIS_LEFT_GROUP_KEY, meetup_id)
+                .ignore()
+                .srem(REDIS_LEFT_GROUP_NOTIFIED_KEY, meetup_id)
+                .ignore()
+                .srem(REDIS_LEFT_GROUP_REMOVED_KEY, meetup_id)
+                .ignore();
+        } else {
+            pipe.hset_nx(REDIS_LEFT_GROUP_KEY, meetup_id, now).ignore();
+        }
+    }
+    let _: () = pipe.query(con)?;
+    Ok(())
+}
+
+// (Meetup ID, time at which they were first missing from the groups)
+pub fn departures<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Vec<(u64, chrono::DateTime<chrono::Utc>)>, crate::BoxedError> {
+    let departures: Vec<(u64, i64)> = con.hgetall(REDIS_LEFT_GROUP_KEY)?;
+    let mut departures: Vec<_> = departures
+        .into_iter()
+        .map(|(meetup_id, timestamp)| {
+            (
+                meetup_id,
+                chrono::DateTime::<chrono::Utc>::from_utc(
+                    chrono::NaiveDateTime::from_timestamp(timestamp, 0),
+                    chrono::Utc,
+                ),
+            )
+        })
+        .collect();
+    departures.sort_unstable_by_key(|&(_, time)| time);
+    Ok(departures)
+}
+
+// Part of the Discord sync: tells the organizers about new departures and
+// removes the channel access of members whose grace period is over
+pub fn enforce_departures(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let departures = departures(con)?;
+    if departures.is_empty() {
+        return Ok(());
+    }
+    let grace = crate::guild_config::membership_grace(con)?;
+    for (meetup_id, left_time) in departures {
+        let discord_id = match crate::identity::meetup_discord_user(meetup_id, con)? {
+            Some(discord_id) => discord_id,
+            None => {
+                // The link is gone, so there is nothing to enforce
+                forget(meetup_id, con)?;
+                continue;
+            }
+        };
+        let (is_notified, is_removed): (bool, bool) = redis::pipe()
+            .sismember(REDIS_LEFT_GROUP_NOTIFIED_KEY, meetup_id)
+            .sismember(REDIS_LEFT_GROUP_REMOVED_KEY, meetup_id)
+            .query(con)?;
+        if !is_notified {
+            crate::discord_notifications::notify_organizers(
+                discord_api,
+                &crate::strings::MEETUP_GROUP_LEFT(meetup_id, discord_id, grace),
+                con,
+            )?;
+            let _: () = con.sadd(REDIS_LEFT_GROUP_NOTIFIED_KEY, meetup_id)?;
+        }
+        let grace = match grace {
+            Some(grace) => grace,
+            None => continue,
+        };
+        if is_removed || left_time + grace > chrono::Utc::now() {
+            continue;
+        }
+        let num_channels = remove_from_channels(discord_id, con, discord_api)?;
+        let _: () = con.sadd(REDIS_LEFT_GROUP_REMOVED_KEY, meetup_id)?;
+        crate::discord_notifications::notify_organizers(
+            discord_api,
+            &crate::strings::MEETUP_GROUP_ACCESS_REMOVED(meetup_id, discord_id, num_channels),
+            con,
+        )?;
+    }
+    Ok(())
+}
+
+// Takes the player role (or overwrite) of all bot-managed channels away and
+// remembers the removal, such that the Discord sync doesn't add them back.
+// Returns the number of channels.
+fn remove_from_channels(
+    discord_id: u64,
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<usize, crate::BoxedError> {
+    let member_role_ids: Vec<RoleId> =
+        match crate::discord_sync::GUILD_ID.member(discord_api, discord_id) {
+            Ok(member) => member.roles,
+            // The user might not be on the server (anymore)
+            Err(_) => return Ok(0),
+        };
+    let channel_ids: Vec<u64> = con.smembers("discord_channels")?;
+    let mut num_channels = 0;
+    for channel_id in channel_ids {
+        // Channels without roles give access with member overwrites
+        let uses_overwrites =
+            crate::discord_channel_access::uses_overwrites(con, ChannelId(channel_id))?;
+        let result = if uses_overwrites {
+            if !crate::discord_channel_access::is_player(con, ChannelId(channel_id), discord_id)? {
+                continue;
+            }
+            crate::discord_channel_access::revoke(
+                ChannelId(channel_id),
+                discord_id,
+                /*as_host*/ false,
+                con,
+                discord_api,
+            )
+        } else {
+            let role_id: Option<u64> =
+                con.get(format!("discord_channel:{}:discord_role", channel_id))?;
+            let role_id = match role_id {
+                Some(role_id) if member_role_ids.contains(&RoleId(role_id)) => role_id,
+                _ => continue,
+            };
+            discord_api
+                .http()
+                .remove_member_role(crate::discord_sync::GUILD_ID.0, discord_id, role_id)
+                .map_err(|err| err.into())
+        };
+        if let Err(err) = result {
+            eprintln!(
+                "Could not remove user {} from channel {}: {}",
+                discord_id, channel_id, err
+            );
+            continue;
+        }
+        let _: () = con.sadd(
+            format!("discord_channel:{}:removed_users", channel_id),
+            discord_id,
+        )?;
+        num_channels += 1;
+    }
+    Ok(num_channels)
+}
+
+fn forget<C: redis::ConnectionLike>(meetup_id: u64, con: &mut C) -> Result<(), crate::BoxedError> {
+    let _: () = redis::pipe()
+        .hdel(REDIS_LEFT_GROUP_KEY, meetup_id)
+        .ignore()
+        .srem(REDIS_LEFT_GROUP_NOTIFIED_KEY, meetup_id)
+        .ignore()
+        .srem(REDIS_LEFT_GROUP_REMOVED_KEY, meetup_id)
+        .ignore()
+        .query(con)?;
+    Ok(())
+}
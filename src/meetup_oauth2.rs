@@ -25,6 +25,11 @@ use url::Url;
 
 const DOMAIN: &'static str = "bot.swissrpg.ch";
 const BASE_URL: &'static str = "https://bot.swissrpg.ch";
+pub const CSRF_TTL_SECS: usize = 60 * 60;
+// Linking links that were never opened stop working after a day. Once the
+// link is opened, the user has LINKING_ID_VISITED_TTL_SECS left to log in.
+pub const LINKING_ID_TTL_SECS: usize = 24 * 60 * 60;
+const LINKING_ID_VISITED_TTL_SECS: usize = 10 * 60;
 lazy_static! {
     static ref LINK_URL_REGEX: regex::Regex =
         regex::Regex::new(r"^/link/(?P<id>[a-zA-Z0-9\-_]+)$").unwrap();
@@ -46,6 +51,22 @@ struct LinkingMessageTemplate<'a> {
     content: Option<&'a str>,
     safe_content: Option<&'a str>,
     img_url: Option<&'a str>,
+    discord_app_url: Option<&'a str>,
+    discord_web_url: Option<&'a str>,
+}
+
+// Links that open our Discord server, in the app and in the browser
+fn discord_server_urls() -> (String, String) {
+    (
+        format!(
+            "discord://discordapp.com/channels/{}",
+            crate::discord_sync::GUILD_ID.0
+        ),
+        format!(
+            "https://discordapp.com/channels/{}",
+            crate::discord_sync::GUILD_ID.0
+        ),
+    )
 }
 
 pub fn new_random_id(num_bytes: u32) -> String {
@@ -56,14 +77,14 @@ pub fn new_random_id(num_bytes: u32) -> String {
 }
 
 fn generate_csrf_cookie(
-    redis_connection_mutex: &Mutex<redis::Connection>,
+    redis_connection_mutex: &Mutex<crate::redis_namespace::Connection>,
     csrf_state: &str,
 ) -> crate::Result<Cookie<'static>> {
     let random_csrf_user_id = new_random_id(16);
     let redis_csrf_key = format!("csrf:{}", &random_csrf_user_id);
     let _: () = redis_connection_mutex
         .lock()
-        .set_ex(&redis_csrf_key, csrf_state, 3600)?;
+        .set_ex(&redis_csrf_key, csrf_state, CSRF_TTL_SECS)?;
     Ok(Cookie::build("csrf_user_id", random_csrf_user_id)
         .domain(DOMAIN)
         .http_only(true)
@@ -73,7 +94,7 @@ fn generate_csrf_cookie(
 }
 
 fn check_csrf_cookie(
-    redis_connection_mutex: &Mutex<redis::Connection>,
+    redis_connection_mutex: &Mutex<crate::redis_namespace::Connection>,
     headers: &hyper::HeaderMap<hyper::header::HeaderValue>,
     csrf_state: &str,
 ) -> crate::Result<bool> {
@@ -104,12 +125,15 @@ fn check_csrf_cookie(
 }
 
 pub fn generate_meetup_linking_link(
-    redis_connection: &Mutex<redis::Connection>,
+    redis_connection: &Mutex<crate::redis_namespace::Connection>,
     discord_id: u64,
 ) -> crate::Result<String> {
     let linking_id = new_random_id(16);
     let redis_key = format!("meetup_linking:{}:discord_user", &linking_id);
-    let _: () = match redis_connection.lock().set(&redis_key, discord_id) {
+    let _: () = match redis_connection
+        .lock()
+        .set_ex(&redis_key, discord_id, LINKING_ID_TTL_SECS)
+    {
         Ok(id) => id,
         Err(err) => {
             return Err(SimpleError::new(format!(
@@ -129,6 +153,8 @@ enum HandlerResponse {
         content: Option<Cow<'static, str>>,
         safe_content: Option<Cow<'static, str>>,
         img_url: Option<Cow<'static, str>>,
+        // Whether the page links back to the Discord server
+        back_to_discord: bool,
     },
 }
 
@@ -139,6 +165,26 @@ impl HandlerResponse {
             .map_err(Into::into)
             .map(|html_body| HandlerResponse::Response(Response::new(html_body.into())))
     }
+
+    // For the pages at the end of the linking process
+    pub fn with_back_to_discord(self) -> Self {
+        match self {
+            HandlerResponse::Message {
+                title,
+                content,
+                safe_content,
+                img_url,
+                ..
+            } => HandlerResponse::Message {
+                title: title,
+                content: content,
+                safe_content: safe_content,
+                img_url: img_url,
+                back_to_discord: true,
+            },
+            response => response,
+        }
+    }
 }
 
 impl From<(&'static str, &'static str)> for HandlerResponse {
@@ -148,6 +194,7 @@ impl From<(&'static str, &'static str)> for HandlerResponse {
             content: Some(Cow::Borrowed(content)),
             safe_content: None,
             img_url: None,
+            back_to_discord: false,
         }
     }
 }
@@ -159,6 +206,7 @@ impl From<(String, &'static str)> for HandlerResponse {
             content: Some(Cow::Borrowed(content)),
             safe_content: None,
             img_url: None,
+            back_to_discord: false,
         }
     }
 }
@@ -170,6 +218,7 @@ impl From<(&'static str, String)> for HandlerResponse {
             content: Some(Cow::Owned(content)),
             safe_content: None,
             img_url: None,
+            back_to_discord: false,
         }
     }
 }
@@ -181,6 +230,7 @@ impl From<(String, String)> for HandlerResponse {
             content: Some(Cow::Owned(content)),
             safe_content: None,
             img_url: None,
+            back_to_discord: false,
         }
     }
 }
@@ -197,17 +247,41 @@ fn get_group_profiles(
 // TODO: switch to async Redis
 type ResponseFuture = Box<dyn Future<Item = HandlerResponse, Error = crate::BoxedError> + Send>;
 fn meetup_http_handler(
-    redis_connection_mutex: &Arc<Mutex<redis::Connection>>,
+    redis_connection_mutex: &Arc<Mutex<crate::redis_namespace::Connection>>,
     oauth2_authorization_client: &BasicClient,
     oauth2_link_client: &BasicClient,
-    _discord_http: &serenity::CacheAndHttp,
+    discord_http: &serenity::CacheAndHttp,
     meetup_client: &Arc<RwLock<Option<meetup_api::Client>>>,
     async_meetup_client: &Arc<RwLock<Option<meetup_api::AsyncClient>>>,
+    admin_api: &Option<Arc<crate::admin_api::AdminApi>>,
     req: Request<Body>,
     bot_name: String,
 ) -> ResponseFuture {
+    if req.uri().path().starts_with("/api/") {
+        if let Some(admin_api) = admin_api {
+            return Box::new(admin_api.handle(req).map(HandlerResponse::Response));
+        }
+    }
     let (method, path) = (req.method(), req.uri().path());
-    if let (&Method::GET, "/authorize") = (method, path) {
+    if let (&Method::GET, "/health") = (method, path) {
+        let status =
+            crate::meetup_circuit_breaker::health_status(&mut *redis_connection_mutex.lock());
+        Box::new(future::result(status.map(|status| {
+            let status = format!(
+                "{}\n{}\n{}\ninstance: {}{}",
+                status,
+                crate::task_supervisor::health_status(),
+                crate::discord_watchdog::health_status(),
+                &*crate::leader_election::INSTANCE_ID,
+                if crate::leader_election::is_leader() {
+                    " (leader)"
+                } else {
+                    ""
+                }
+            );
+            HandlerResponse::Response(Response::new(status.into()))
+        })))
+    } else if let (&Method::GET, "/authorize") = (method, path) {
         // Generate the authorization URL to which we'll redirect the user.
         let (authorize_url, csrf_state) = oauth2_authorization_client
             .authorize_url(CsrfToken::new_random)
@@ -284,38 +358,37 @@ fn meetup_http_handler(
                 get_group_profiles(new_async_meetup_client.clone())
                     .from_err::<crate::BoxedError>()
                     .and_then(move |user_profiles| {
-                        let is_organizer = user_profiles.iter().all(|profile| {
-                            let is_organizer = match profile {
-                                Some(meetup_api::User {
-                                    group_profile:
-                                        Some(meetup_api::GroupProfile {
-                                            status: meetup_api::UserStatus::Active,
-                                            role: Some(role),
-                                        }),
-                                    ..
-                                }) => {
-                                    *role == meetup_api::LeadershipRole::Organizer
-                                        || *role == meetup_api::LeadershipRole::Coorganizer
-                                        || *role == meetup_api::LeadershipRole::AssistantOrganizer
-                                }
-                                _ => false,
-                            };
-                            is_organizer
-                        });
+                        let is_organizer = user_profiles
+                            .iter()
+                            .all(crate::meetup_organizer_tokens::is_organizer_profile);
                         if !is_organizer {
                             return future::ok(("Only the organizer can log in", "").into());
                         }
-                        // Store the new access and refresh tokens in Redis
+                        // Store the new (encrypted) access and refresh tokens in Redis
+                        let encrypted_access_token = match crate::token_encryption::encrypt(
+                            token_res.access_token().secret(),
+                        ) {
+                            Ok(token) => token,
+                            Err(err) => return future::err(err),
+                        };
+                        let encrypted_refresh_token = match token_res
+                            .refresh_token()
+                            .map(|token| crate::token_encryption::encrypt(token.secret()))
+                        {
+                            Some(Ok(token)) => Some(token),
+                            Some(Err(err)) => return future::err(err),
+                            None => None,
+                        };
                         let res: RedisResult<()> = redis::transaction(
                             &mut *redis_connection_mutex.lock(),
                             &["meetup_access_token", "meetup_refresh_token"],
-                            |con, pipe| match token_res.refresh_token() {
-                                Some(refresh_token) => pipe
-                                    .set("meetup_access_token", token_res.access_token().secret())
-                                    .set("meetup_refresh_token", refresh_token.secret())
+                            |con, pipe| match encrypted_refresh_token {
+                                Some(ref refresh_token) => pipe
+                                    .set("meetup_access_token", &encrypted_access_token)
+                                    .set("meetup_refresh_token", refresh_token)
                                     .query(con),
                                 None => pipe
-                                    .set("meetup_access_token", token_res.access_token().secret())
+                                    .set("meetup_access_token", &encrypted_access_token)
                                     .del("meetup_refresh_token")
                                     .query(con),
                             },
@@ -323,6 +396,21 @@ fn meetup_http_handler(
                         if let Err(err) = res {
                             return future::err(err.into());
                         }
+                        // Register the organizer's tokens such that they can be used as
+                        // a fallback if another organizer logs in later
+                        if let Some(organizer) =
+                            user_profiles.iter().filter_map(Option::as_ref).next()
+                        {
+                            if let Err(err) = crate::meetup_organizer_tokens::register(
+                                &mut *redis_connection_mutex.lock(),
+                                organizer.id,
+                                &organizer.name,
+                                &encrypted_access_token,
+                                encrypted_refresh_token.as_ref().map(String::as_str),
+                            ) {
+                                eprintln!("Could not register the organizer's tokens: {}", err);
+                            }
+                        }
                         // Replace the meetup client
                         let new_blocking_meetup_client =
                             meetup_api::Client::new(token_res.access_token().secret());
@@ -341,7 +429,7 @@ fn meetup_http_handler(
         };
         let redis_key = format!("meetup_linking:{}:discord_user", &linking_id);
         let (discord_id,): (Option<u64>,) = match redis::pipe()
-            .expire(&redis_key, 600)
+            .expire(&redis_key, LINKING_ID_VISITED_TTL_SECS)
             .ignore()
             .get(&redis_key)
             .query(&mut *redis_connection_mutex.lock())
@@ -429,11 +517,11 @@ fn meetup_http_handler(
             Some(id) => id,
             None => {
                 return Box::new(future::ok(
-                    (
+                    HandlerResponse::from((
                         strings::OAUTH2_LINK_EXPIRED_TITLE,
                         strings::OAUTH2_LINK_EXPIRED_CONTENT,
-                    )
-                        .into(),
+                    ))
+                    .with_back_to_discord(),
                 ))
             }
         };
@@ -468,6 +556,7 @@ fn meetup_http_handler(
                     safe_content: Some(Cow::Owned(strings::OAUTH2_AUTHORISATION_DENIED(
                         &linking_url,
                     ))),
+                    back_to_discord: true,
                 }));
             } else {
                 // Some other error occured
@@ -498,6 +587,10 @@ fn meetup_http_handler(
         // Exchange the code with a token.
         let code = AuthorizationCode::new(code.to_string());
         let redis_connection_mutex = redis_connection_mutex.clone();
+        let discord_api = crate::discord_bot::CacheAndHttp {
+            cache: discord_http.cache.clone(),
+            http: discord_http.http.clone(),
+        };
         let future = oauth2_link_client
             .clone()
             .set_redirect_url(RedirectUrl::new(
@@ -520,11 +613,16 @@ fn meetup_http_handler(
                                 return future::ok(("Could not find Meetup ID", "").into());
                             }
                         };
-                        let redis_key_d2m = format!("discord_user:{}:meetup_user", discord_id);
-                        let redis_key_m2d = format!("meetup_user:{}:discord_user", meetup_user.id);
+                        let identity = crate::identity::Identity::meetup(meetup_user.id);
+                        let redis_identities_key =
+                            crate::identity::redis_identities_key(discord_id);
+                        let redis_key_m2d = identity.redis_discord_user_key();
                         // Check that the Discord ID has not been linked yet
                         let existing_meetup_id: RedisResult<Option<u64>> =
-                            redis_connection_mutex.lock().get(&redis_key_d2m);
+                            crate::identity::meetup_id(
+                                discord_id,
+                                &mut *redis_connection_mutex.lock(),
+                            );
                         match existing_meetup_id {
                             Ok(Some(existing_meetup_id)) => {
                                 if existing_meetup_id == meetup_user.id {
@@ -577,31 +675,53 @@ fn meetup_http_handler(
                                 if let Some(refresh_token) = token_res.refresh_token() {
                                     let redis_user_tokens_key =
                                         format!("meetup_user:{}:oauth2_tokens", meetup_user.id);
-                                    let fields = &[
-                                        ("access_token", token_res.access_token().secret()),
-                                        ("refresh_token", refresh_token.secret()),
-                                    ];
-                                    let _: redis::RedisResult<()> = redis::pipe()
-                                        .hset_multiple(&redis_user_tokens_key, fields)
-                                        .query(&mut *redis_connection);
+                                    let encrypted_tokens = crate::token_encryption::encrypt(
+                                        token_res.access_token().secret(),
+                                    )
+                                    .and_then(|access_token| {
+                                        crate::token_encryption::encrypt(refresh_token.secret())
+                                            .map(|refresh_token| (access_token, refresh_token))
+                                    });
+                                    match encrypted_tokens {
+                                        Ok((access_token, refresh_token)) => {
+                                            let fields = &[
+                                                ("access_token", access_token),
+                                                ("refresh_token", refresh_token),
+                                            ];
+                                            let _: redis::RedisResult<()> = redis::pipe()
+                                                .hset_multiple(&redis_user_tokens_key, fields)
+                                                .query(&mut *redis_connection);
+                                        }
+                                        Err(err) => {
+                                            eprintln!(
+                                                "Could not encrypt the user's tokens: {}",
+                                                err
+                                            )
+                                        }
+                                    }
                                 }
                             }
                             redis::transaction(
                                 &mut *redis_connection,
-                                &[&redis_key_d2m, &redis_key_m2d],
+                                &[&redis_identities_key, &redis_key_m2d],
                                 |con, pipe| {
-                                    let linked_meetup_id: Option<u64> = con.get(&redis_key_d2m)?;
+                                    let linked_meetup_identity = crate::identity::linked_identity(
+                                        discord_id,
+                                        crate::identity::Provider::Meetup,
+                                        con,
+                                    )?;
                                     let linked_discord_id: Option<u64> = con.get(&redis_key_m2d)?;
-                                    if linked_meetup_id.is_some() || linked_discord_id.is_some() {
+                                    if linked_meetup_identity.is_some()
+                                        || linked_discord_id.is_some()
+                                    {
                                         // The meetup id was linked in the meantime, abort
                                         successful = false;
                                         // Execute empty transaction just to get out of the closure
                                         pipe.query(con)
                                     } else {
                                         pipe.sadd("meetup_users", meetup_user.id)
-                                            .sadd("discord_users", discord_id)
-                                            .set(&redis_key_d2m, meetup_user.id)
-                                            .set(&redis_key_m2d, discord_id);
+                                            .sadd("discord_users", discord_id);
+                                        crate::identity::add_link(pipe, discord_id, &identity);
                                         successful = true;
                                         pipe.query(con)
                                     }
@@ -620,29 +740,59 @@ fn meetup_http_handler(
                                     .into(),
                             );
                         }
-                        if let Some(photo) = meetup_user.photo {
-                            future::ok(
-                                HandlerResponse::Message {
-                                    title: Cow::Borrowed(strings::OAUTH2_LINKING_SUCCESS_TITLE),
-                                    content: Some(Cow::Owned(
-                                        strings::OAUTH2_LINKING_SUCCESS_CONTENT(&meetup_user.name),
-                                    )),
-                                    safe_content: None,
-                                    img_url: Some(Cow::Owned(photo.thumb_link)),
-                                }
-                                .into(),
-                            )
-                        } else {
-                            future::ok(
-                                (
-                                    strings::OAUTH2_LINKING_SUCCESS_TITLE,
-                                    strings::OAUTH2_LINKING_SUCCESS_CONTENT(&meetup_user.name),
-                                )
-                                    .into(),
-                            )
+                        crate::identity::invalidate_cached_links(discord_id, &identity);
+                        crate::event_webhooks::emit(crate::event_webhooks::Event::UserLinked {
+                            discord_id: discord_id,
+                            meetup_id: meetup_user.id,
+                        });
+                        // The user gets their channel roles with the next sync
+                        if let Err(err) = crate::discord_sync::mark_member_series_dirty(
+                            &mut *redis_connection_mutex.lock(),
+                            meetup_user.id,
+                        ) {
+                            eprintln!(
+                                "Could not mark the series of user {} for syncing: {}",
+                                discord_id, err
+                            );
+                        }
+                        // Confirm on Discord right away, instead of waiting
+                        // for the linking wizard to notice
+                        let confirmation_result = crate::discord_linking_wizard::complete(
+                            discord_id,
+                            &meetup_user.name,
+                            &mut *redis_connection_mutex.lock(),
+                            &discord_api,
+                        );
+                        if let Err(err) = confirmation_result {
+                            eprintln!(
+                                "Could not confirm the linking of user {} on Discord: {}",
+                                discord_id, err
+                            );
+                        }
+                        if let Err(err) = crate::discord_nicknames::sync_linked_user(
+                            discord_id,
+                            meetup_user.id,
+                            &meetup_user.name,
+                            &mut *redis_connection_mutex.lock(),
+                            &discord_api,
+                        ) {
+                            eprintln!(
+                                "Could not sync the nickname of user {}: {}",
+                                discord_id, err
+                            );
                         }
+                        future::ok(HandlerResponse::Message {
+                            title: Cow::Borrowed(strings::OAUTH2_LINKING_SUCCESS_TITLE),
+                            content: Some(Cow::Owned(strings::OAUTH2_LINKING_SUCCESS_CONTENT(
+                                &meetup_user.name,
+                            ))),
+                            safe_content: None,
+                            img_url: meetup_user.photo.map(|photo| Cow::Owned(photo.thumb_link)),
+                            back_to_discord: true,
+                        })
                     })
-            });
+            })
+            .map(HandlerResponse::with_back_to_discord);
         Box::new(future)
     } else {
         Box::new(future::ok(("Unknown route", "").into()))
@@ -691,10 +841,11 @@ impl OAuth2Consumer {
     pub fn create_auth_server(
         &self,
         addr: std::net::SocketAddr,
-        redis_connection: redis::Connection,
+        redis_connection: crate::redis_namespace::Connection,
         discord_http: Arc<serenity::CacheAndHttp>,
         meetup_client: Arc<RwLock<Option<meetup_api::Client>>>,
         async_meetup_client: Arc<RwLock<Option<meetup_api::AsyncClient>>>,
+        admin_api: Option<Arc<crate::admin_api::AdminApi>>,
         bot_name: String,
     ) -> impl Future<Item = (), Error = ()> + Send + 'static {
         let redis_connection_mutex = Arc::new(Mutex::new(redis_connection));
@@ -712,6 +863,7 @@ impl OAuth2Consumer {
                 let discord_http = discord_http.clone();
                 let meetup_client = meetup_client.clone();
                 let async_meetup_client = async_meetup_client.clone();
+                let admin_api = admin_api.clone();
                 let bot_name = bot_name.clone();
                 service_fn(move |req| {
                     meetup_http_handler(
@@ -721,6 +873,7 @@ impl OAuth2Consumer {
                         &discord_http,
                         &meetup_client,
                         &async_meetup_client,
+                        &admin_api,
                         req,
                         bot_name.clone(),
                     )
@@ -731,12 +884,24 @@ impl OAuth2Consumer {
                             content,
                             safe_content,
                             img_url,
+                            back_to_discord,
                         } => {
+                            let (discord_app_url, discord_web_url) = discord_server_urls();
                             let rendered_template = LinkingMessageTemplate {
                                 title: &title,
                                 content: content.as_ref().map(Cow::as_ref),
                                 safe_content: safe_content.as_ref().map(Cow::as_ref),
                                 img_url: img_url.as_ref().map(Cow::as_ref),
+                                discord_app_url: if back_to_discord {
+                                    Some(&discord_app_url)
+                                } else {
+                                    None
+                                },
+                                discord_web_url: if back_to_discord {
+                                    Some(&discord_web_url)
+                                } else {
+                                    None
+                                },
                             }
                             .render();
                             future::result(
@@ -779,7 +944,7 @@ impl OAuth2Consumer {
     // Refreshes the authorization token
     pub fn token_refresh_task(
         &self,
-        mut redis_connection: redis::Connection,
+        mut redis_connection: crate::redis_namespace::Connection,
         meetup_client: Arc<RwLock<Option<meetup_api::Client>>>,
     ) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static
     {
@@ -809,6 +974,16 @@ impl OAuth2Consumer {
                         );
                     }
                 };
+                let refresh_token = match crate::token_encryption::decrypt(&refresh_token) {
+                    Ok(refresh_token) => refresh_token,
+                    Err(err) => {
+                        eprintln!("Could not decrypt the Meetup refresh token: {}", err);
+                        // Try to refresh again in an hour
+                        return white_rabbit::DateResult::Repeat(
+                            white_rabbit::Utc::now() + white_rabbit::Duration::hours(1),
+                        );
+                    }
+                };
                 // Try to exchange the refresh token for fresh access and refresh tokens
                 let refresh_token = oauth2::RefreshToken::new(refresh_token);
                 let refresh_token_response = match oauth2_client
@@ -840,11 +1015,24 @@ impl OAuth2Consumer {
                     }
                 };
                 *meetup_client.write() = Some(meetup_api::Client::new(new_access_token.secret()));
-                // Store the new tokens in Redis
-                let res: RedisResult<()> = redis_connection.set_multiple(&[
-                    ("meetup_access_token", new_access_token.secret()),
-                    ("meetup_refresh_token", new_refresh_token.secret()),
-                ]);
+                // Store the new (encrypted) tokens in Redis
+                let res = crate::token_encryption::encrypt(new_access_token.secret())
+                    .and_then(|access_token| {
+                        crate::token_encryption::encrypt(new_refresh_token.secret())
+                            .map(|refresh_token| (access_token, refresh_token))
+                    })
+                    .and_then(|(access_token, refresh_token)| {
+                        let res: RedisResult<()> = redis_connection.set_multiple(&[
+                            ("meetup_access_token", &access_token),
+                            ("meetup_refresh_token", &refresh_token),
+                        ]);
+                        res?;
+                        crate::meetup_organizer_tokens::store_active_tokens(
+                            &mut redis_connection,
+                            &access_token,
+                            &refresh_token,
+                        )
+                    });
                 if let Err(err) = res {
                     eprintln!("Error storing new Meetup tokens in Redis: {}", err);
                 }
@@ -860,4 +1048,70 @@ impl OAuth2Consumer {
             };
         refresh_meetup_access_token_task
     }
+
+    // Checks the tokens of all registered organizers and switches to the next
+    // working one if the active one stopped working
+    pub fn organizer_tokens_check_task(
+        &self,
+        mut redis_connection: crate::redis_namespace::Connection,
+        meetup_client: Arc<RwLock<Option<meetup_api::Client>>>,
+        async_meetup_client: Arc<RwLock<Option<meetup_api::AsyncClient>>>,
+    ) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static
+    {
+        let oauth2_client = self.authorization_client.clone();
+        let meetup_clients = (meetup_client, async_meetup_client);
+        move |_context: &mut white_rabbit::Context| -> white_rabbit::DateResult {
+            if let Err(err) = crate::meetup_organizer_tokens::check_all_accounts(
+                &mut redis_connection,
+                &oauth2_client,
+                &meetup_clients,
+            ) {
+                eprintln!("Could not check the Meetup organizer tokens: {}", err);
+                crate::error_reporting::report(&err, &[("task", "organizer_tokens_check")]);
+            }
+            white_rabbit::DateResult::Repeat(
+                white_rabbit::Utc::now() + white_rabbit::Duration::hours(1),
+            )
+        }
+    }
+}
+
+// The instances that aren't the leader don't refresh the Meetup token
+// themselves, so they load the one that the leader stored in Redis, both
+// after a refresh and after a switch to another organizer's token
+pub fn token_reload_task(
+    mut redis_connection: crate::redis_namespace::Connection,
+    meetup_client: Arc<RwLock<Option<meetup_api::Client>>>,
+    async_meetup_client: Arc<RwLock<Option<meetup_api::AsyncClient>>>,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    let mut loaded_access_token: Option<String> = None;
+    move |_context: &mut white_rabbit::Context| -> white_rabbit::DateResult {
+        let access_token: RedisResult<Option<String>> = redis_connection.get("meetup_access_token");
+        match access_token {
+            Ok(Some(access_token)) => {
+                if loaded_access_token.as_ref() != Some(&access_token) {
+                    match crate::token_encryption::decrypt(&access_token) {
+                        Ok(decrypted_access_token) => {
+                            *meetup_client.write() =
+                                Some(meetup_api::Client::new(&decrypted_access_token));
+                            *async_meetup_client.write() =
+                                Some(meetup_api::AsyncClient::new(&decrypted_access_token));
+                            loaded_access_token = Some(access_token);
+                        }
+                        Err(err) => {
+                            eprintln!("Could not decrypt the Meetup access token: {}", err)
+                        }
+                    }
+                }
+            }
+            Ok(None) => (),
+            Err(err) => eprintln!(
+                "Could not reload the Meetup access token. Redis error: {}",
+                err
+            ),
+        }
+        white_rabbit::DateResult::Repeat(
+            white_rabbit::Utc::now() + white_rabbit::Duration::minutes(10),
+        )
+    }
 }
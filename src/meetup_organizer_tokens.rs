@@ -0,0 +1,385 @@
+use crate::meetup_api;
+use oauth2::basic::BasicClient;
+use oauth2::TokenResponse;
+use redis::Commands;
+use serenity::prelude::RwLock;
+use simple_error::SimpleError;
+use std::sync::Arc;
+
+// Every organizer who logs in with their Meetup account registers their
+// tokens here. Only one of them (the active one) is used for API calls and is
+// mirrored to `meetup_access_token` and `meetup_refresh_token`. The accounts
+// are checked periodically and if the active one stops working (e.g. because
+// the person is no longer an organizer), the next working one takes over.
+const REDIS_ACCOUNTS_KEY: &'static str = "meetup_organizer_accounts";
+const REDIS_ACTIVE_ACCOUNT_KEY: &'static str = "meetup_organizer_active";
+
+fn account_key(meetup_user_id: u64) -> String {
+    format!("meetup_organizer:{}", meetup_user_id)
+}
+
+#[derive(Debug, Clone)]
+pub struct OrganizerAccount {
+    pub meetup_user_id: u64,
+    pub name: String,
+    pub healthy: Option<bool>,
+    pub last_check: Option<String>,
+    pub is_active: bool,
+}
+
+pub type MeetupClients = (
+    Arc<RwLock<Option<meetup_api::Client>>>,
+    Arc<RwLock<Option<meetup_api::AsyncClient>>>,
+);
+
+// Whether the group profile belongs to an organizer that can use the API on
+// behalf of the group
+pub fn is_organizer_profile(profile: &Option<meetup_api::User>) -> bool {
+    match profile {
+        Some(meetup_api::User {
+            group_profile:
+                Some(meetup_api::GroupProfile {
+                    status: meetup_api::UserStatus::Active,
+                    role: Some(role),
+                }),
+            ..
+        }) => {
+            *role == meetup_api::LeadershipRole::Organizer
+                || *role == meetup_api::LeadershipRole::Coorganizer
+                || *role == meetup_api::LeadershipRole::AssistantOrganizer
+        }
+        _ => false,
+    }
+}
+
+// Registers (or updates) the tokens of an organizer after they logged in
+// and makes them the active ones
+pub fn register<C: redis::ConnectionLike>(
+    con: &mut C,
+    meetup_user_id: u64,
+    name: &str,
+    encrypted_access_token: &str,
+    encrypted_refresh_token: Option<&str>,
+) -> Result<(), crate::BoxedError> {
+    let account_key = account_key(meetup_user_id);
+    let now = chrono::Utc::now().to_rfc3339();
+    let accounts: Vec<u64> = con.lrange(REDIS_ACCOUNTS_KEY, 0, -1)?;
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    if !accounts.contains(&meetup_user_id) {
+        pipe.rpush(REDIS_ACCOUNTS_KEY, meetup_user_id).ignore();
+    }
+    pipe.hset_multiple(
+        &account_key,
+        &[
+            ("name", name),
+            ("access_token", encrypted_access_token),
+            ("healthy", "1"),
+            ("last_check", now.as_str()),
+        ],
+    )
+    .ignore();
+    match encrypted_refresh_token {
+        Some(encrypted_refresh_token) => pipe
+            .hset(&account_key, "refresh_token", encrypted_refresh_token)
+            .ignore(),
+        None => pipe.hdel(&account_key, "refresh_token").ignore(),
+    };
+    pipe.set(REDIS_ACTIVE_ACCOUNT_KEY, meetup_user_id).ignore();
+    let _: () = pipe.query(con)?;
+    Ok(())
+}
+
+pub fn list<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Vec<OrganizerAccount>, crate::BoxedError> {
+    let account_ids: Vec<u64> = con.lrange(REDIS_ACCOUNTS_KEY, 0, -1)?;
+    let active_account_id: Option<u64> = con.get(REDIS_ACTIVE_ACCOUNT_KEY)?;
+    let mut accounts = Vec::with_capacity(account_ids.len());
+    for meetup_user_id in account_ids {
+        let (name, healthy, last_check): (Option<String>, Option<bool>, Option<String>) =
+            redis::cmd("HMGET")
+                .arg(account_key(meetup_user_id))
+                .arg("name")
+                .arg("healthy")
+                .arg("last_check")
+                .query(con)?;
+        accounts.push(OrganizerAccount {
+            meetup_user_id: meetup_user_id,
+            name: name.unwrap_or_else(|| "unknown".to_string()),
+            healthy: healthy,
+            last_check: last_check,
+            is_active: active_account_id == Some(meetup_user_id),
+        });
+    }
+    Ok(accounts)
+}
+
+// Forgets the tokens of an organizer. If they were the active ones, the
+// next working account takes over. Returns false if there was no such account.
+pub fn revoke<C: redis::ConnectionLike>(
+    con: &mut C,
+    meetup_user_id: u64,
+    meetup_clients: &MeetupClients,
+) -> Result<bool, crate::BoxedError> {
+    let (num_removed, _): (u32, ()) = redis::pipe()
+        .atomic()
+        .lrem(REDIS_ACCOUNTS_KEY, 0, meetup_user_id)
+        .del(account_key(meetup_user_id))
+        .query(con)?;
+    if num_removed == 0 {
+        return Ok(false);
+    }
+    let active_account_id: Option<u64> = con.get(REDIS_ACTIVE_ACCOUNT_KEY)?;
+    if active_account_id == Some(meetup_user_id) {
+        activate_first_healthy_account(con, meetup_clients)?;
+    }
+    Ok(true)
+}
+
+// Keeps the stored tokens of the active account in sync after a token refresh
+pub fn store_active_tokens<C: redis::ConnectionLike>(
+    con: &mut C,
+    encrypted_access_token: &str,
+    encrypted_refresh_token: &str,
+) -> Result<(), crate::BoxedError> {
+    let active_account_id: Option<u64> = con.get(REDIS_ACTIVE_ACCOUNT_KEY)?;
+    if let Some(active_account_id) = active_account_id {
+        let _: () = con.hset_multiple(
+            account_key(active_account_id),
+            &[
+                ("access_token", encrypted_access_token),
+                ("refresh_token", encrypted_refresh_token),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum TokenHealth {
+    Healthy,
+    // Meetup rejected the token or the account is no longer an organizer
+    Unhealthy,
+    // Meetup could not be asked (e.g. an outage or a timeout), which says
+    // nothing about the token
+    Unknown,
+}
+
+// Checks that the account can still access all our Meetup groups as an organizer
+fn check_access_token(access_token: &str) -> TokenHealth {
+    let client = meetup_api::Client::new(access_token);
+    let mut health = TokenHealth::Healthy;
+    for urlname in meetup_api::URLNAMES.iter() {
+        match client.get_own_group_profile(urlname) {
+            Ok(profile) if is_organizer_profile(&profile) => (),
+            Ok(_) => return TokenHealth::Unhealthy,
+            Err(err) => {
+                eprintln!(
+                    "Could not check the organizer token with group {}: {}",
+                    urlname, err
+                );
+                health = TokenHealth::Unknown;
+            }
+        }
+    }
+    health
+}
+
+enum Refresh {
+    Refreshed(String),
+    // There is no refresh token or Meetup rejected it
+    Revoked,
+    // Meetup could not be asked, try again with the next check
+    Failed,
+}
+
+// Tries to get new tokens for an account whose access token stopped working
+fn refresh_account_tokens<C: redis::ConnectionLike>(
+    con: &mut C,
+    meetup_user_id: u64,
+    oauth2_client: &BasicClient,
+) -> Result<Refresh, crate::BoxedError> {
+    let encrypted_refresh_token: Option<String> =
+        con.hget(account_key(meetup_user_id), "refresh_token")?;
+    let refresh_token = match encrypted_refresh_token {
+        Some(token) => oauth2::RefreshToken::new(crate::token_encryption::decrypt(&token)?),
+        None => return Ok(Refresh::Revoked),
+    };
+    let token_response = match oauth2_client
+        .exchange_refresh_token(&refresh_token)
+        .request(oauth2::curl::http_client)
+    {
+        Ok(token_response) => token_response,
+        Err(err) => {
+            eprintln!(
+                "Could not refresh the tokens of organizer {}: {}",
+                meetup_user_id, err
+            );
+            return Ok(match err {
+                oauth2::RequestTokenError::ServerResponse(ref response)
+                    if *response.error() == oauth2::basic::BasicErrorResponseType::InvalidGrant =>
+                {
+                    Refresh::Revoked
+                }
+                _ => Refresh::Failed,
+            });
+        }
+    };
+    let access_token = token_response.access_token().secret().clone();
+    let _: () = con.hset(
+        account_key(meetup_user_id),
+        "access_token",
+        crate::token_encryption::encrypt(&access_token)?,
+    )?;
+    if let Some(refresh_token) = token_response.refresh_token() {
+        let _: () = con.hset(
+            account_key(meetup_user_id),
+            "refresh_token",
+            crate::token_encryption::encrypt(refresh_token.secret())?,
+        )?;
+    }
+    Ok(Refresh::Refreshed(access_token))
+}
+
+// Checks all registered accounts and falls back to the next working one if
+// the active account stopped working
+pub fn check_all_accounts<C: redis::ConnectionLike>(
+    con: &mut C,
+    oauth2_client: &BasicClient,
+    meetup_clients: &MeetupClients,
+) -> Result<(), crate::BoxedError> {
+    let account_ids: Vec<u64> = con.lrange(REDIS_ACCOUNTS_KEY, 0, -1)?;
+    if account_ids.is_empty() {
+        // Nobody registered yet, keep using the tokens we have (if any)
+        return Ok(());
+    }
+    let active_account_id: Option<u64> = con.get(REDIS_ACTIVE_ACCOUNT_KEY)?;
+    let mut active_account_is_healthy = false;
+    let mut active_account_was_refreshed = false;
+    for &meetup_user_id in &account_ids {
+        let encrypted_access_token: Option<String> =
+            con.hget(account_key(meetup_user_id), "access_token")?;
+        let mut health = match encrypted_access_token {
+            Some(token) => check_access_token(&crate::token_encryption::decrypt(&token)?),
+            None => TokenHealth::Unhealthy,
+        };
+        if health == TokenHealth::Unhealthy {
+            health = match refresh_account_tokens(con, meetup_user_id, oauth2_client)? {
+                Refresh::Refreshed(access_token) => {
+                    if active_account_id == Some(meetup_user_id) {
+                        active_account_was_refreshed = true;
+                    }
+                    check_access_token(&access_token)
+                }
+                Refresh::Revoked => TokenHealth::Unhealthy,
+                Refresh::Failed => TokenHealth::Unknown,
+            };
+        }
+        // Keep the tokens and their last known state until Meetup can be
+        // asked again
+        if health == TokenHealth::Unknown {
+            eprintln!(
+                "Could not check the Meetup tokens of organizer {}, keeping them",
+                meetup_user_id
+            );
+            if active_account_id == Some(meetup_user_id) {
+                active_account_is_healthy = true;
+            }
+            continue;
+        }
+        let healthy = health == TokenHealth::Healthy;
+        if !healthy {
+            eprintln!(
+                "The Meetup tokens of organizer {} stopped working",
+                meetup_user_id
+            );
+        }
+        if active_account_id == Some(meetup_user_id) {
+            active_account_is_healthy = healthy;
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+        let _: () = con.hset_multiple(
+            account_key(meetup_user_id),
+            &[
+                ("healthy", if healthy { "1" } else { "0" }),
+                ("last_check", now.as_str()),
+            ],
+        )?;
+    }
+    match active_account_id {
+        Some(active_account_id) if active_account_is_healthy => {
+            // Make sure the clients use the refreshed tokens
+            if active_account_was_refreshed {
+                activate_account(con, active_account_id, meetup_clients)?;
+            }
+        }
+        _ => activate_first_healthy_account(con, meetup_clients)?,
+    }
+    Ok(())
+}
+
+// Switches the Meetup clients over to the first account that passed its last check
+fn activate_first_healthy_account<C: redis::ConnectionLike>(
+    con: &mut C,
+    meetup_clients: &MeetupClients,
+) -> Result<(), crate::BoxedError> {
+    let account_ids: Vec<u64> = con.lrange(REDIS_ACCOUNTS_KEY, 0, -1)?;
+    for meetup_user_id in account_ids {
+        let healthy: Option<bool> = con.hget(account_key(meetup_user_id), "healthy")?;
+        if healthy == Some(true) && activate_account(con, meetup_user_id, meetup_clients)? {
+            println!(
+                "Switched to the Meetup tokens of organizer {}",
+                meetup_user_id
+            );
+            return Ok(());
+        }
+    }
+    // None of the accounts work anymore
+    let _: () = redis::pipe()
+        .atomic()
+        .del(REDIS_ACTIVE_ACCOUNT_KEY)
+        .del("meetup_access_token")
+        .del("meetup_refresh_token")
+        .query(con)?;
+    *meetup_clients.0.write() = None;
+    *meetup_clients.1.write() = None;
+    let err = SimpleError::new("None of the registered Meetup organizer tokens work anymore");
+    eprintln!("{}", err);
+    crate::error_reporting::report(&err.into(), &[]);
+    Ok(())
+}
+
+// Makes the tokens of this account the ones used for API calls.
+// Returns false if the account has no access token.
+fn activate_account<C: redis::ConnectionLike>(
+    con: &mut C,
+    meetup_user_id: u64,
+    meetup_clients: &MeetupClients,
+) -> Result<bool, crate::BoxedError> {
+    let (encrypted_access_token, encrypted_refresh_token): (Option<String>, Option<String>) =
+        redis::cmd("HMGET")
+            .arg(account_key(meetup_user_id))
+            .arg("access_token")
+            .arg("refresh_token")
+            .query(con)?;
+    let encrypted_access_token = match encrypted_access_token {
+        Some(token) => token,
+        None => return Ok(false),
+    };
+    let access_token = crate::token_encryption::decrypt(&encrypted_access_token)?;
+    let mut pipe = redis::pipe();
+    pipe.atomic()
+        .set(REDIS_ACTIVE_ACCOUNT_KEY, meetup_user_id)
+        .ignore()
+        .set("meetup_access_token", &encrypted_access_token)
+        .ignore();
+    match encrypted_refresh_token {
+        Some(ref token) => pipe.set("meetup_refresh_token", token).ignore(),
+        None => pipe.del("meetup_refresh_token").ignore(),
+    };
+    let _: () = pipe.query(con)?;
+    *meetup_clients.0.write() = Some(meetup_api::Client::new(&access_token));
+    *meetup_clients.1.write() = Some(meetup_api::AsyncClient::new(&access_token));
+    Ok(true)
+}
@@ -19,18 +19,20 @@ const EVENT_SERIES_PATTERN: &'static str =
 const CHANNEL_PATTERN: &'static str = r"(?i)[\[\(]\s*channel\s*(?P<channel_id>[0-9]+)\s*[\]\)]";
 
 lazy_static! {
-    static ref NEW_ADVENTURE_REGEX: regex::Regex =
+    pub(crate) static ref NEW_ADVENTURE_REGEX: regex::Regex =
         regex::Regex::new(NEW_ADVENTURE_PATTERN).unwrap();
-    static ref NEW_CAMPAIGN_REGEX: regex::Regex = regex::Regex::new(NEW_CAMPAIGN_PATTERN).unwrap();
-    static ref EVENT_SERIES_REGEX: regex::Regex = regex::Regex::new(EVENT_SERIES_PATTERN).unwrap();
+    pub(crate) static ref NEW_CAMPAIGN_REGEX: regex::Regex =
+        regex::Regex::new(NEW_CAMPAIGN_PATTERN).unwrap();
+    pub(crate) static ref EVENT_SERIES_REGEX: regex::Regex =
+        regex::Regex::new(EVENT_SERIES_PATTERN).unwrap();
     static ref CHANNEL_REGEX: regex::Regex = regex::Regex::new(CHANNEL_PATTERN).unwrap();
 }
 
 pub type BoxedFuture<T, E = crate::BoxedError> = Box<dyn Future<Item = T, Error = E> + Send>;
 
 pub fn create_recurring_syncing_task(
-    meetup_client: Arc<RwLock<Option<meetup_api::AsyncClient>>>,
-    redis_client: redis::Client,
+    event_source: crate::event_source::SharedEventSource,
+    redis_client: crate::redis_namespace::Client,
 ) -> impl Future<Item = (), Error = crate::BoxedError> {
     // Run forever
     tokio::timer::Interval::new_interval(Duration::from_secs(15 * 60))
@@ -40,9 +42,10 @@ pub fn create_recurring_syncing_task(
         })
         .for_each(move |_| {
             tokio::spawn(
-                sync_task(meetup_client.clone(), redis_client.clone())
+                sync_task(event_source.clone(), redis_client.clone())
                     .map_err(|err| {
                         eprintln!("Syncing task failed: {}", err);
+                        crate::error_reporting::report(&err, &[("task", "sync_meetup")]);
                         err
                     })
                     .timeout(Duration::from_secs(360))
@@ -54,36 +57,81 @@ pub fn create_recurring_syncing_task(
         })
 }
 
-// TODO: Introduce a type like "Meetup connection" that contains
-// an Arc<RwLock<Option<MeetupClient>>> internally and has the same
-// methods as MeetupClient (so we don't need to match on the Option
-// every time we want to use the client)
+// Hands a one-off sync to the futures executor. Used by the "sync meetup"
+// command and the admin API.
+pub fn start_sync_task(
+    event_source: crate::event_source::SharedEventSource,
+    redis_client: crate::redis_namespace::Client,
+    future_spawner: &mut futures::sync::mpsc::Sender<BoxedFuture<(), ()>>,
+) -> Result<(), futures::sync::mpsc::TrySendError<BoxedFuture<(), ()>>> {
+    let sync_task = Box::new(
+        sync_task(event_source, redis_client)
+            .map_err(|err| {
+                eprintln!("Syncing task failed: {}", err);
+                crate::error_reporting::report(&err, &[("task", "sync_meetup")]);
+                err
+            })
+            .timeout(Duration::from_secs(60))
+            .map_err(|err| {
+                eprintln!("Syncing task timed out: {}", err);
+            }),
+    );
+    future_spawner.try_send(sync_task)
+}
+
 pub fn sync_task(
-    meetup_client: Arc<RwLock<Option<meetup_api::AsyncClient>>>,
-    mut redis_client: redis::Client,
+    event_source: crate::event_source::SharedEventSource,
+    mut redis_client: crate::redis_namespace::Client,
 ) -> impl Future<Item = (), Error = crate::BoxedError> + Send + 'static {
-    let upcoming_events = match *meetup_client.read() {
-        Some(ref meetup_client) => meetup_client
-            .get_upcoming_events_all_groups()
-            .from_err::<crate::BoxedError>(),
-        None => {
-            return Box::new(
-                future::err(SimpleError::new("Meetup API unavailable"))
-                    .from_err::<crate::BoxedError>(),
-            ) as BoxedFuture<_>
+    match crate::maintenance_mode::is_active(&mut redis_client) {
+        Ok(true) => {
+            println!("Syncing task: maintenance mode is active, skipping Meetup sync");
+            return Box::new(future::ok(())) as BoxedFuture<_>;
         }
-    };
+        Ok(false) => (),
+        Err(err) => return Box::new(future::err(err)) as BoxedFuture<_>,
+    }
+    // Don't hammer Meetup while it is having an outage.
+    // Discord will be synced based on the data cached in Redis in the meantime.
+    match crate::meetup_circuit_breaker::state(&mut redis_client) {
+        Ok(state) if !state.allows_requests() => {
+            println!("Syncing task: Meetup API circuit breaker is open, skipping Meetup sync");
+            return Box::new(future::ok(())) as BoxedFuture<_>;
+        }
+        Ok(_) => (),
+        Err(err) => return Box::new(future::err(err)) as BoxedFuture<_>,
+    }
+    println!(
+        "Syncing task: fetching the upcoming events from {}",
+        event_source.name()
+    );
+    let upcoming_events = event_source.get_upcoming_events();
+    let user_ids = event_source.user_ids();
     let event_sync_future = {
         let redis_client = redis_client.clone();
-        upcoming_events.for_each(move |event| {
-            sync_event(event, redis_client.clone()).then(|res| {
-                // "Catch" any errors and don't abort the stream
-                if let Err(err) = res {
-                    eprintln!("Event sync failed: {}", err);
+        let mut breaker_redis_client = redis_client.clone();
+        upcoming_events
+            .for_each(move |event| {
+                sync_event(event, user_ids, redis_client.clone()).then(|res| {
+                    // "Catch" any errors and don't abort the stream
+                    if let Err(err) = res {
+                        eprintln!("Event sync failed: {}", err);
+                    }
+                    future::ok(())
+                })
+            })
+            .then(move |res| {
+                // Only errors of the Meetup event stream itself reach this point
+                let breaker_res = if res.is_ok() {
+                    crate::meetup_circuit_breaker::record_success(&mut breaker_redis_client)
+                } else {
+                    crate::meetup_circuit_breaker::record_failure(&mut breaker_redis_client)
+                };
+                if let Err(err) = breaker_res {
+                    eprintln!("Could not update the Meetup API circuit breaker: {}", err);
                 }
-                future::ok(())
+                res
             })
-        })
     };
     let series_sync_future_fun = move || {
         // This code is in a closure such that the Redis query in the next line
@@ -94,8 +142,8 @@ pub fn sync_task(
             .and_then(move |event_series: Vec<String>| {
                 stream::iter_ok(event_series).for_each(move |series_id| {
                     let redis_client = redis_client.clone();
-                    let meetup_client = meetup_client.clone();
-                    sync_event_series(series_id, meetup_client, redis_client).and_then(|_| {
+                    let event_source = event_source.clone();
+                    sync_event_series(series_id, event_source, redis_client).and_then(|_| {
                         // Add a 1s delay between each item as a naive rate limit for the Meetup API
                         tokio::timer::Delay::new(
                             std::time::Instant::now() + std::time::Duration::from_secs(1),
@@ -112,7 +160,8 @@ pub fn sync_task(
 // event is fine.
 fn sync_event(
     event: meetup_api::Event,
-    mut redis_client: redis::Client,
+    user_ids: crate::event_source::UserIds,
+    mut redis_client: crate::redis_namespace::Client,
 ) -> impl Future<Item = (), Error = crate::BoxedError> {
     let is_new_adventure = NEW_ADVENTURE_REGEX.is_match(&event.description);
     let is_new_campaign = NEW_CAMPAIGN_REGEX.is_match(&event.description);
@@ -197,12 +246,54 @@ fn sync_event(
     } else {
         None
     };
+    // A new series might be a duplicate of an existing one (see meetup_dedup)
+    let indicated_event_series_id =
+        if (is_new_adventure || is_new_campaign) && indicated_channel_id.is_none() {
+            match crate::meetup_dedup::find_duplicate(&event, user_ids, &mut redis_client) {
+                Ok(crate::meetup_dedup::Duplicate::None) => None,
+                Ok(crate::meetup_dedup::Duplicate::Series(series_id)) => {
+                    println!(
+                        "Syncing task: Event \"{}\" is a duplicate, adding it to series {}",
+                        event.name, series_id
+                    );
+                    Some(series_id)
+                }
+                Ok(crate::meetup_dedup::Duplicate::Ambiguous(candidate_series)) => {
+                    println!(
+                        "Syncing task: Event \"{}\" might be a duplicate of the series {}, \
+                         flagging it for review",
+                        event.name,
+                        candidate_series.join(", ")
+                    );
+                    if let Err(err) =
+                        crate::meetup_dedup::flag(&event, candidate_series, &mut redis_client)
+                    {
+                        eprintln!(
+                            "Syncing task: error flagging event \"{}\" as a possible duplicate: {}",
+                            event.name, err
+                        );
+                    }
+                    return Box::new(future::ok(()));
+                }
+                Err(err) => {
+                    // Skipped rather than risking a duplicate series, the next
+                    // sync checks it again
+                    eprintln!(
+                        "Syncing task: error checking whether event \"{}\" is a duplicate: {}",
+                        event.name, err
+                    );
+                    return Box::new(future::ok(()));
+                }
+            }
+        } else {
+            indicated_event_series_id
+        };
     // TODO: figure out whether this event belongs to a series
     // For now, we assume that an event that reaches this method does not yet
     // belong to a series and create a new one
     let redis_events_key = "meetup_events";
     let redis_series_key = "event_series";
-    let redis_event_hosts_key = format!("meetup_event:{}:meetup_hosts", event.id);
+    let redis_event_hosts_key = user_ids.event_hosts_key(&event.id);
     let redis_event_series_key = format!("meetup_event:{}:event_series", event.id);
     let redis_event_key = format!("meetup_event:{}", event.id);
     let redis_channel_series_key = format!(
@@ -210,6 +301,10 @@ fn sync_event(
         indicated_channel_id.unwrap_or(0)
     );
     let event_name = event.name.clone();
+    let tags = match crate::event_tags::TagRules::load(&mut redis_client) {
+        Ok(tag_rules) => tag_rules.extract_tags(&event.name, &event.description),
+        Err(err) => return Box::new(future::err(err)) as BoxedFuture<_>,
+    };
     // technically: check that series id doesn't exist yet and generate a new one until it does not
     // practically: we will never generate a colliding id
     let fut = redis_client
@@ -229,10 +324,14 @@ fn sync_event(
                     let redis_event_key = redis_event_key.clone();
                     let redis_channel_series_key = redis_channel_series_key.clone();
                     let indicated_event_series_id = indicated_event_series_id.clone();
+                    let tags = tags.clone();
                     let mut query = redis::pipe();
-                    query.get(&redis_event_series_key).get(&redis_channel_series_key);
+                    query
+                        .get(&redis_event_series_key)
+                        .get(&redis_channel_series_key)
+                        .hget(&redis_event_key, "sync_state");
                     let transaction_future = query.query_async(con).and_then(
-                        move |(con, (existing_series_id, indicated_channel_series)): (_, (Option<String>, Option<String>))| {
+                        move |(con, (existing_series_id, indicated_channel_series, previous_sync_state)): (_, (Option<String>, Option<String>, Option<String>))| {
                             if existing_series_id.is_none() {
                                 // If this event has no series ID yet but also
                                 // doesn't indicate that it is the start of a
@@ -321,6 +420,25 @@ fn sync_event(
                             };
                             let redis_series_events_key = format!("event_series:{}:meetup_events", &series_id);
                             let host_user_ids: Vec<_> = event.event_hosts.iter().map(|user| user.id).collect();
+                            let rsvp_limit = event.rsvp_limit;
+                            let (is_online, location) =
+                                crate::event_location::Location::from_meetup_event(&event).to_redis_fields();
+                            let redis_event_tags_key = format!("meetup_event:{}:tags", &event.id);
+                            // Everything the Discord sync cares about, such that
+                            // the series is only marked dirty if something changed
+                            let sync_state = format!(
+                                "{}|{}|{}|{}|{:?}|{:?}|{}|{:?}|{}|{}",
+                                event.name,
+                                event.time.to_rfc3339(),
+                                event.link,
+                                event.group.urlname,
+                                host_user_ids,
+                                rsvp_limit,
+                                is_online,
+                                location,
+                                tags.join(","),
+                                series_id
+                            );
                             let event_hash = &[
                                 ("name", event.name),
                                 ("time", event.time.to_rfc3339()),
@@ -335,10 +453,32 @@ fn sync_event(
                             }
                             pipe.sadd(redis_events_key, &event.id)
                                 .sadd(redis_series_key, &series_id)
-                                .sadd(&redis_event_hosts_key, host_user_ids)
                                 .set(&redis_event_series_key, &series_id)
                                 .sadd(&redis_series_events_key, &event.id)
                                 .hset_multiple(&redis_event_key, event_hash);
+                            // Sources that resolve their users to Discord users
+                            // might not know any of the hosts
+                            if !host_user_ids.is_empty() {
+                                pipe.sadd(&redis_event_hosts_key, host_user_ids);
+                            }
+                            match rsvp_limit {
+                                Some(rsvp_limit) => pipe.hset(&redis_event_key, "rsvp_limit", rsvp_limit),
+                                None => pipe.hdel(&redis_event_key, "rsvp_limit"),
+                            };
+                            pipe.hset(&redis_event_key, "is_online", is_online);
+                            match location {
+                                Some(location) => pipe.hset(&redis_event_key, "location", location),
+                                None => pipe.hdel(&redis_event_key, "location"),
+                            };
+                            pipe.del(&redis_event_tags_key);
+                            if !tags.is_empty() {
+                                pipe.sadd(&redis_event_tags_key, tags);
+                            }
+                            if previous_sync_state.as_ref() != Some(&sync_state) {
+                                pipe.sadd(crate::discord_sync::REDIS_DIRTY_SERIES_KEY, &series_id);
+                            }
+                            pipe.hset(&redis_event_key, "sync_state", sync_state);
+                            crate::meetup_dedup::unflag(&mut pipe, &event.id);
                             pipe.query_async(con)
                         },
                     );
@@ -357,8 +497,8 @@ fn sync_event(
 
 fn sync_event_series(
     series_id: String,
-    meetup_client: Arc<RwLock<Option<meetup_api::AsyncClient>>>,
-    mut redis_client: redis::Client,
+    event_source: crate::event_source::SharedEventSource,
+    mut redis_client: crate::redis_namespace::Client,
 ) -> impl Future<Item = (), Error = crate::BoxedError> {
     let redis_series_events_key = format!("event_series:{}:meetup_events", &series_id);
     // Get all events belonging to this event series
@@ -397,6 +537,28 @@ fn sync_event_series(
         .collect();
     // Sort by date
     upcoming.sort_unstable_by_key(|pair| pair.1);
+    // Discord shows the next event of the series, so once that changes (e.g.
+    // because the last session is over), the series needs to be synced again
+    let redis_series_next_event_key = format!("event_series:{}:next_meetup_event", &series_id);
+    let next_event_id = upcoming.first().map(|next_event| next_event.0.clone());
+    let previous_next_event_id: Option<String> =
+        match redis_client.get(&redis_series_next_event_key) {
+            Ok(id) => id,
+            Err(err) => return Box::new(future::err(err.into())) as BoxedFuture<_>,
+        };
+    if previous_next_event_id != next_event_id {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        match &next_event_id {
+            Some(next_event_id) => pipe.set(&redis_series_next_event_key, next_event_id),
+            None => pipe.del(&redis_series_next_event_key),
+        };
+        pipe.sadd(crate::discord_sync::REDIS_DIRTY_SERIES_KEY, &series_id);
+        let res: redis::RedisResult<()> = pipe.query(&mut redis_client);
+        if let Err(err) = res {
+            return Box::new(future::err(err.into())) as BoxedFuture<_>;
+        }
+    }
     // The first element in this vector will be the next upcoming event
     if let Some(next_event) = upcoming.first() {
         let next_event_id = next_event.0.clone();
@@ -408,21 +570,12 @@ fn sync_event_series(
         );
         let rsvp_future = {
             // Query the RSVPs for that event
-            let rsvps = match *meetup_client.read() {
-                Some(ref meetup_client) => meetup_client
-                    .get_rsvps(group_urlname, &next_event_id)
-                    .from_err::<crate::BoxedError>(),
-                None => {
-                    return Box::new(
-                        future::err(SimpleError::new("Meetup API unavailable"))
-                            .from_err::<crate::BoxedError>(),
-                    ) as BoxedFuture<_>
-                }
-            };
+            let rsvps = event_source.get_rsvps(group_urlname, &next_event_id);
+            let user_ids = event_source.user_ids();
             // Sync the RSVPs
             rsvps.and_then(move |rsvps| {
                 println!("Syncing task: Found {} RSVPs", rsvps.len());
-                sync_rsvps(&next_event_id, rsvps, redis_client)
+                sync_rsvps(&next_event_id, series_id, rsvps, user_ids, redis_client)
             })
         };
         Box::new(rsvp_future)
@@ -433,8 +586,10 @@ fn sync_event_series(
 
 fn sync_rsvps(
     event_id: &str,
+    series_id: String,
     rsvps: Vec<meetup_api::RSVP>,
-    redis_client: redis::Client,
+    user_ids: crate::event_source::UserIds,
+    redis_client: crate::redis_namespace::Client,
 ) -> impl Future<Item = (), Error = crate::BoxedError> {
     let rsvp_yes_user_ids: Vec<_> = rsvps
         .iter()
@@ -446,33 +601,211 @@ fn sync_rsvps(
             }
         })
         .collect();
-    let redis_event_users_key = format!("meetup_event:{}:meetup_users", event_id);
+    if rsvp_yes_user_ids.is_empty() {
+        return Box::new(future::ok(())) as BoxedFuture<_>;
+    }
+    // The Meetup members that RSVP'd are candidates for the link suggestions
+    let link_suggestions_pipe = if user_ids == crate::event_source::UserIds::Meetup {
+        let mut pipe = redis::pipe();
+        crate::link_suggestions::remember_rsvps(&mut pipe, &rsvps);
+        Some(pipe)
+    } else {
+        None
+    };
+    let redis_event_users_key = user_ids.event_users_key(event_id);
     let fut = redis_client
         .get_async_connection()
         .and_then(move |con| {
-            let mut pipe = redis::pipe();
-            pipe.sadd(redis_event_users_key, rsvp_yes_user_ids);
-            pipe.query_async(con)
+            redis::cmd("SADD")
+                .arg(redis_event_users_key)
+                .arg(rsvp_yes_user_ids)
+                .query_async(con)
+        })
+        .and_then(move |(con, num_added): (_, u64)| {
+            // New players need to be added to the channel
+            if num_added > 0 {
+                future::Either::A(
+                    redis::cmd("SADD")
+                        .arg(crate::discord_sync::REDIS_DIRTY_SERIES_KEY)
+                        .arg(series_id)
+                        .query_async(con)
+                        .map(|(con, _): (_, u64)| (con, ())),
+                )
+            } else {
+                future::Either::B(future::ok::<_, redis::RedisError>((con, ())))
+            }
+        })
+        .and_then(move |(con, ())| match link_suggestions_pipe {
+            Some(pipe) => future::Either::A(pipe.query_async::<_, ()>(con)),
+            None => future::Either::B(future::ok::<_, redis::RedisError>((con, ()))),
         })
         .map(|(_, ())| ())
         .from_err::<crate::BoxedError>();
     Box::new(fut)
 }
 
+pub fn create_recurring_profile_syncing_task(
+    meetup_client: Arc<RwLock<Option<meetup_api::AsyncClient>>>,
+    redis_client: crate::redis_namespace::Client,
+) -> impl Future<Item = (), Error = crate::BoxedError> {
+    // Run forever, once a day
+    tokio::timer::Interval::new_interval(Duration::from_secs(24 * 60 * 60))
+        .map_err(|err| {
+            eprintln!("Interval timer error: {}", err);
+            err.into()
+        })
+        .for_each(move |_| {
+            if !crate::leader_election::is_leader() {
+                return future::ok(());
+            }
+            tokio::spawn(
+                sync_linked_profiles_task(meetup_client.clone(), redis_client.clone()).map_err(
+                    |err| {
+                        eprintln!("Profile syncing task failed: {}", err);
+                        crate::error_reporting::report(&err, &[("task", "sync_linked_profiles")]);
+                    },
+                ),
+            );
+            // Also notice linked members that left the Meetup groups and
+            // refresh the membership dues
+            if let Some(ref meetup_client) = *meetup_client.read() {
+                tokio::spawn(
+                    crate::meetup_membership::sync_group_memberships_task(
+                        meetup_client.clone(),
+                        redis_client.clone(),
+                    )
+                    .map_err(|err| {
+                        eprintln!("Membership syncing task failed: {}", err);
+                        crate::error_reporting::report(&err, &[("task", "sync_group_memberships")]);
+                    }),
+                );
+                tokio::spawn(
+                    crate::meetup_dues::sync_dues_task(meetup_client.clone(), redis_client.clone())
+                        .map_err(|err| {
+                            eprintln!("Dues syncing task failed: {}", err);
+                            crate::error_reporting::report(&err, &[("task", "sync_dues")]);
+                        }),
+                );
+            }
+            future::ok(())
+        })
+}
+
+// Refreshes the cached Meetup profiles (name and photo) of all linked users.
+// Links whose Meetup profile does not exist anymore are flagged for review
+// by an organizer.
+pub fn sync_linked_profiles_task(
+    meetup_client: Arc<RwLock<Option<meetup_api::AsyncClient>>>,
+    mut redis_client: crate::redis_namespace::Client,
+) -> impl Future<Item = (), Error = crate::BoxedError> + Send + 'static {
+    let meetup_client = match *meetup_client.read() {
+        Some(ref meetup_client) => meetup_client.clone(),
+        None => {
+            return Box::new(
+                future::err(SimpleError::new("Meetup API unavailable"))
+                    .from_err::<crate::BoxedError>(),
+            ) as BoxedFuture<_>
+        }
+    };
+    match crate::feature_flags::is_enabled(
+        &mut redis_client,
+        crate::discord_sync::GUILD_ID,
+        crate::feature_flags::Feature::ProfileSync,
+    ) {
+        Ok(true) => (),
+        Ok(false) => return Box::new(future::ok(())) as BoxedFuture<_>,
+        Err(err) => return Box::new(future::err(err)) as BoxedFuture<_>,
+    }
+    match crate::maintenance_mode::is_active(&mut redis_client) {
+        Ok(false) => (),
+        Ok(true) => {
+            println!("Profile syncing task: maintenance mode is active, skipping");
+            return Box::new(future::ok(())) as BoxedFuture<_>;
+        }
+        Err(err) => return Box::new(future::err(err)) as BoxedFuture<_>,
+    }
+    match crate::meetup_circuit_breaker::state(&mut redis_client) {
+        Ok(state) if !state.allows_requests() => {
+            println!("Profile syncing task: Meetup API circuit breaker is open, skipping");
+            return Box::new(future::ok(())) as BoxedFuture<_>;
+        }
+        Ok(_) => (),
+        Err(err) => return Box::new(future::err(err)) as BoxedFuture<_>,
+    }
+    let meetup_user_ids: Vec<u64> = match redis_client.smembers("meetup_users") {
+        Ok(ids) => ids,
+        Err(err) => return Box::new(future::err(err.into())) as BoxedFuture<_>,
+    };
+    let profile_sync_future = stream::iter_ok(meetup_user_ids).for_each(move |meetup_id| {
+        let redis_client = redis_client.clone();
+        meetup_client
+            .get_member_profile(Some(meetup_id))
+            .from_err::<crate::BoxedError>()
+            .and_then(move |profile| sync_linked_profile(meetup_id, profile, redis_client))
+            .then(move |res| {
+                // "Catch" any errors and don't abort the stream
+                if let Err(err) = res {
+                    eprintln!("Profile sync of Meetup user {} failed: {}", meetup_id, err);
+                }
+                // Add a 1s delay between each item as a naive rate limit for the Meetup API
+                tokio::timer::Delay::new(
+                    std::time::Instant::now() + std::time::Duration::from_secs(1),
+                )
+                .from_err::<crate::BoxedError>()
+            })
+    });
+    Box::new(profile_sync_future)
+}
+
+fn sync_linked_profile(
+    meetup_id: u64,
+    profile: Option<meetup_api::User>,
+    redis_client: crate::redis_namespace::Client,
+) -> impl Future<Item = (), Error = crate::BoxedError> {
+    let redis_user_profile_key = format!("meetup_user:{}:profile", meetup_id);
+    let mut pipe = redis::pipe();
+    match profile {
+        Some(user) => {
+            pipe.hset(&redis_user_profile_key, "name", &user.name)
+                .ignore();
+            if let Some(photo) = user.photo {
+                pipe.hset(&redis_user_profile_key, "photo", photo.thumb_link)
+                    .ignore();
+            } else {
+                pipe.hdel(&redis_user_profile_key, "photo").ignore();
+            }
+            // The profile exists (again), so there is nothing to review
+            pipe.srem("flagged_meetup_users", meetup_id).ignore();
+        }
+        None => {
+            println!(
+                "Meetup profile {} seems to have been deactivated, flagging the link for review",
+                meetup_id
+            );
+            pipe.sadd("flagged_meetup_users", meetup_id).ignore();
+        }
+    }
+    redis_client
+        .get_async_connection()
+        .and_then(move |con| pipe.query_async(con))
+        .map(|(_, ())| ())
+        .from_err::<crate::BoxedError>()
+}
+
 // A direct translation of redis::transaction for the async case
 // (except for the fact that it doesn't retry)
 fn async_redis_transaction<
     K: redis::ToRedisArgs,
     T: redis::FromRedisValue + Send + 'static,
     F: FnMut(
-        redis::aio::Connection,
+        crate::redis_namespace::AsyncConnection,
         redis::Pipeline,
-    ) -> redis::RedisFuture<(redis::aio::Connection, Option<T>)>,
+    ) -> redis::RedisFuture<(crate::redis_namespace::AsyncConnection, Option<T>)>,
 >(
-    con: redis::aio::Connection,
+    con: crate::redis_namespace::AsyncConnection,
     keys: &[K],
     mut func: F,
-) -> impl Future<Item = (redis::aio::Connection, T), Error = crate::BoxedError> {
+) -> impl Future<Item = (crate::redis_namespace::AsyncConnection, T), Error = crate::BoxedError> {
     redis::cmd("WATCH")
         .arg(keys)
         .query_async(con)
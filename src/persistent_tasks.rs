@@ -0,0 +1,275 @@
+use lazy_static::lazy_static;
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use serenity::prelude::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+lazy_static! {
+    // The generation of each recurring task in this process's scheduler.
+    // Scheduling a recurring task again replaces the previous one, which
+    // notices that it is outdated the next time it is due and stops.
+    static ref RECURRING_GENERATIONS: std::sync::Mutex<HashMap<String, u64>> =
+        std::sync::Mutex::new(HashMap::new());
+}
+
+// Tasks that are persisted to Redis such that they survive restarts of the bot
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskType {
+    SyncDiscord,
+    EndOfGame,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PersistedTask {
+    task_type: TaskType,
+    recurring: bool,
+    due_time: String,
+}
+
+impl TaskType {
+    fn name(&self) -> &'static str {
+        match self {
+            TaskType::SyncDiscord => "sync_discord",
+            TaskType::EndOfGame => "end_of_game",
+        }
+    }
+}
+
+// Schedules a task and persists it to Redis.
+// There can only be one recurring task of each type, such that rehydrating
+// the tasks after a restart does not lead to duplicates. Scheduling it again
+// replaces it, e.g. to run it earlier.
+pub fn schedule_task(
+    task_scheduler: &Arc<Mutex<white_rabbit::Scheduler>>,
+    redis_client: &crate::redis_namespace::Client,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    bot_id: u64,
+    task_type: TaskType,
+    due_time: white_rabbit::DateTime<white_rabbit::Utc>,
+    recurring: bool,
+) -> Result<(), crate::BoxedError> {
+    let task_id = if recurring {
+        recurring_task_id(task_type)
+    } else {
+        format!(
+            "{}:{}",
+            task_type.name(),
+            crate::meetup_oauth2::new_random_id(8)
+        )
+    };
+    let persisted_task = PersistedTask {
+        task_type: task_type,
+        recurring: recurring,
+        due_time: due_time.to_rfc3339(),
+    };
+    let mut con = redis_client.get_connection()?;
+    let _: () = con.hset(
+        "scheduled_tasks",
+        &task_id,
+        serde_json::to_string(&persisted_task)?,
+    )?;
+    add_to_scheduler(
+        task_scheduler,
+        redis_client,
+        discord_api,
+        bot_id,
+        task_id,
+        persisted_task,
+        due_time,
+    );
+    Ok(())
+}
+
+// Schedules the recurring task of this type, unless this process already has
+// it (e.g. because it was rehydrated)
+pub fn ensure_recurring_task(
+    task_scheduler: &Arc<Mutex<white_rabbit::Scheduler>>,
+    redis_client: &crate::redis_namespace::Client,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    bot_id: u64,
+    task_type: TaskType,
+    due_time: white_rabbit::DateTime<white_rabbit::Utc>,
+) -> Result<(), crate::BoxedError> {
+    let is_scheduled = RECURRING_GENERATIONS
+        .lock()
+        .map(|generations| generations.contains_key(&recurring_task_id(task_type)))
+        .unwrap_or(false);
+    if is_scheduled {
+        return Ok(());
+    }
+    schedule_task(
+        task_scheduler,
+        redis_client,
+        discord_api,
+        bot_id,
+        task_type,
+        due_time,
+        /*recurring*/ true,
+    )
+}
+
+fn recurring_task_id(task_type: TaskType) -> String {
+    format!("{}:recurring", task_type.name())
+}
+
+// Puts all tasks that were persisted to Redis back into the scheduler.
+// Overdue tasks are run right away.
+pub fn rehydrate_tasks(
+    task_scheduler: &Arc<Mutex<white_rabbit::Scheduler>>,
+    redis_client: &crate::redis_namespace::Client,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    bot_id: u64,
+) -> Result<(), crate::BoxedError> {
+    let mut con = redis_client.get_connection()?;
+    let persisted_tasks: std::collections::HashMap<String, String> =
+        con.hgetall("scheduled_tasks")?;
+    for (task_id, persisted_task) in persisted_tasks {
+        let persisted_task: PersistedTask = match serde_json::from_str(&persisted_task) {
+            Ok(task) => task,
+            Err(err) => {
+                eprintln!("Dropping unparseable persisted task {}: {}", task_id, err);
+                let _: () = con.hdel("scheduled_tasks", &task_id)?;
+                continue;
+            }
+        };
+        // Recurring tasks are put into every instance's scheduler, but only run
+        // on the leader. One-off tasks are only rehydrated by the leader, such
+        // that they don't run once per instance.
+        if !persisted_task.recurring && !crate::leader_election::is_leader() {
+            continue;
+        }
+        let now = white_rabbit::Utc::now();
+        let due_time = match white_rabbit::DateTime::parse_from_rfc3339(&persisted_task.due_time)
+        {
+            Ok(due_time) => std::cmp::max(due_time.with_timezone(&white_rabbit::Utc), now),
+            Err(_) => now,
+        };
+        println!(
+            "Rehydrating persisted task {} due at {}",
+            task_id, due_time
+        );
+        add_to_scheduler(
+            task_scheduler,
+            redis_client,
+            discord_api,
+            bot_id,
+            task_id,
+            persisted_task,
+            due_time,
+        );
+    }
+    Ok(())
+}
+
+fn add_to_scheduler(
+    task_scheduler: &Arc<Mutex<white_rabbit::Scheduler>>,
+    redis_client: &crate::redis_namespace::Client,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+    bot_id: u64,
+    task_id: String,
+    persisted_task: PersistedTask,
+    due_time: white_rabbit::DateTime<white_rabbit::Utc>,
+) {
+    let mut task: Box<
+        dyn FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync,
+    > = match persisted_task.task_type {
+        TaskType::SyncDiscord => Box::new(crate::discord_sync::create_sync_discord_task(
+            redis_client.clone(),
+            discord_api.clone(),
+            bot_id,
+            persisted_task.recurring,
+        )),
+        TaskType::EndOfGame => Box::new(crate::discord_end_of_game::create_end_of_game_task(
+            redis_client.clone(),
+            discord_api.clone(),
+            bot_id,
+            persisted_task.recurring,
+        )),
+    };
+    // Panics are caught and the task is retried with a backoff
+    let mut task = crate::task_supervisor::supervised(task_id.clone(), task);
+    let redis_client = redis_client.clone();
+    let generation = if persisted_task.recurring {
+        RECURRING_GENERATIONS
+            .lock()
+            .map(|mut generations| {
+                let generation = generations.entry(task_id.clone()).or_insert(0);
+                *generation += 1;
+                *generation
+            })
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    task_scheduler
+        .lock()
+        .add_task_datetime(due_time, move |ctx: &mut white_rabbit::Context| {
+            // Replaced by a newer copy, which also owns the persisted state
+            if persisted_task.recurring && !is_current_generation(&task_id, generation) {
+                println!("Dropping task {}, it was scheduled again", task_id);
+                return white_rabbit::DateResult::Done;
+            }
+            // Don't touch the persisted state either, it belongs to the leader
+            if persisted_task.recurring && !crate::leader_election::is_leader() {
+                return white_rabbit::DateResult::Repeat(
+                    white_rabbit::Utc::now() + white_rabbit::Duration::minutes(1),
+                );
+            }
+            // Put everything on hold during maintenance
+            match redis_client
+                .get_connection()
+                .map_err(Into::into)
+                .and_then(|mut con| crate::maintenance_mode::is_active(&mut con))
+            {
+                Ok(false) => (),
+                Ok(true) => {
+                    println!("Postponing task {} due to maintenance", task_id);
+                    return white_rabbit::DateResult::Repeat(
+                        white_rabbit::Utc::now() + white_rabbit::Duration::minutes(1),
+                    );
+                }
+                Err(err) => {
+                    eprintln!("Could not check for maintenance mode: {}", err);
+                    return white_rabbit::DateResult::Repeat(
+                        white_rabbit::Utc::now() + white_rabbit::Duration::minutes(1),
+                    );
+                }
+            }
+            let result = task(ctx);
+            // Keep the persisted version of this task in sync
+            let persist_result: Result<(), crate::BoxedError> = (|| {
+                let mut con = redis_client.get_connection()?;
+                match &result {
+                    white_rabbit::DateResult::Repeat(next_time) => {
+                        let persisted_task = PersistedTask {
+                            due_time: next_time.to_rfc3339(),
+                            ..persisted_task.clone()
+                        };
+                        let _: () = con.hset(
+                            "scheduled_tasks",
+                            &task_id,
+                            serde_json::to_string(&persisted_task)?,
+                        )?;
+                    }
+                    white_rabbit::DateResult::Done => {
+                        let _: () = con.hdel("scheduled_tasks", &task_id)?;
+                    }
+                }
+                Ok(())
+            })();
+            if let Err(err) = persist_result {
+                eprintln!("Could not persist the state of task {}: {}", task_id, err);
+            }
+            result
+        });
+}
+
+fn is_current_generation(task_id: &str, generation: u64) -> bool {
+    RECURRING_GENERATIONS
+        .lock()
+        .map(|generations| generations.get(task_id) == Some(&generation))
+        // A thread panicked while holding the lock, better run the task twice
+        // than not at all
+        .unwrap_or(true)
+}
@@ -0,0 +1,116 @@
+use redis::Commands;
+use serenity::model::{channel::PermissionOverwriteType, id::ChannelId, id::UserId};
+
+// Organizers can quarantine a user while they look into a harassment report.
+// Quarantined users lose all channel roles, are skipped by the Discord sync
+// and can't link their Meetup account until they are released again.
+pub const QUARANTINED_USERS_KEY: &'static str = "quarantined_discord_users";
+
+pub fn is_quarantined<C: redis::ConnectionLike>(
+    con: &mut C,
+    discord_id: u64,
+) -> Result<bool, crate::BoxedError> {
+    Ok(con.sismember(QUARANTINED_USERS_KEY, discord_id)?)
+}
+
+pub fn quarantined_users<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Vec<u64>, crate::BoxedError> {
+    Ok(con.smembers(QUARANTINED_USERS_KEY)?)
+}
+
+// Returns the number of channels the user was removed from
+pub fn quarantine(
+    discord_id: u64,
+    redis_connection: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<usize, crate::BoxedError> {
+    // Block the sync from adding the user back first
+    let _: () = redis_connection.sadd(QUARANTINED_USERS_KEY, discord_id)?;
+    let member_role_ids = match crate::discord_sync::GUILD_ID.member(discord_api, discord_id) {
+        Ok(member) => member.roles,
+        // The user might not be on the server (anymore)
+        Err(_) => vec![],
+    };
+    let channel_ids: Vec<u64> = redis_connection.smembers("discord_channels")?;
+    let mut num_channels = 0;
+    for channel_id in channel_ids {
+        let redis_channel_role_key = format!("discord_channel:{}:discord_role", channel_id);
+        let redis_channel_host_role_key =
+            format!("discord_channel:{}:discord_host_role", channel_id);
+        let redis_channel_cohosts_key = format!("discord_channel:{}:cohosts", channel_id);
+        let redis_channel_manual_hosts_key = format!("discord_channel:{}:manual_hosts", channel_id);
+        let (role_id, host_role_id, is_cohost): (Option<u64>, Option<u64>, bool) = redis::pipe()
+            .get(&redis_channel_role_key)
+            .get(&redis_channel_host_role_key)
+            .sismember(&redis_channel_cohosts_key, discord_id)
+            .query(redis_connection)?;
+        let mut was_member = false;
+        for role_id in role_id.iter().chain(host_role_id.iter()) {
+            if !member_role_ids
+                .iter()
+                .any(|member_role| member_role.0 == *role_id)
+            {
+                continue;
+            }
+            was_member = true;
+            if let Err(err) = discord_api.http.remove_member_role(
+                crate::discord_sync::GUILD_ID.0,
+                discord_id,
+                *role_id,
+            ) {
+                eprintln!(
+                    "Could not remove role {} from quarantined user {}: {}",
+                    role_id, discord_id, err
+                );
+            }
+        }
+        // Channels without roles give access with member overwrites
+        match crate::discord_channel_access::remove_member(
+            ChannelId(channel_id),
+            discord_id,
+            redis_connection,
+            discord_api,
+        ) {
+            Ok(true) => was_member = true,
+            Ok(false) => (),
+            Err(err) => eprintln!(
+                "Could not remove the overwrite of quarantined user {}: {}",
+                discord_id, err
+            ),
+        }
+        if is_cohost {
+            was_member = true;
+            if let Err(err) = ChannelId(channel_id).delete_permission(
+                &discord_api.http,
+                PermissionOverwriteType::Member(UserId(discord_id)),
+            ) {
+                eprintln!(
+                    "Could not remove the co-host permissions of quarantined user {}: {}",
+                    discord_id, err
+                );
+            }
+        }
+        // Hosting privileges are not restored on release
+        let _: () = redis::pipe()
+            .srem(&redis_channel_cohosts_key, discord_id)
+            .ignore()
+            .srem(&redis_channel_manual_hosts_key, discord_id)
+            .ignore()
+            .query(redis_connection)?;
+        if was_member {
+            num_channels += 1;
+        }
+    }
+    Ok(num_channels)
+}
+
+// Returns false if the user was not quarantined.
+// The next Discord sync gives the user their channel roles back.
+pub fn release<C: redis::ConnectionLike>(
+    con: &mut C,
+    discord_id: u64,
+) -> Result<bool, crate::BoxedError> {
+    let removed: u32 = con.srem(QUARANTINED_USERS_KEY, discord_id)?;
+    Ok(removed > 0)
+}
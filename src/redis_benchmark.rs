@@ -0,0 +1,153 @@
+use redis::{Commands, ConnectionLike, RedisResult, Value};
+use serenity::model::id::ChannelId;
+use std::time::Instant;
+
+// Compares the number of Redis round trips (and the time) that the Discord
+// sync's role assignment reads take with the batched reads against the old
+// implementation that asked for every Meetup user separately. Only reads from
+// Redis, so it can be run against a production database.
+
+// Counts the round trips to Redis, a pipeline being a single one
+pub struct CountingConnection<C> {
+    inner: C,
+    round_trips: usize,
+}
+
+impl<C: ConnectionLike> CountingConnection<C> {
+    pub fn new(inner: C) -> Self {
+        CountingConnection {
+            inner: inner,
+            round_trips: 0,
+        }
+    }
+
+    pub fn take_round_trips(&mut self) -> usize {
+        std::mem::replace(&mut self.round_trips, 0)
+    }
+}
+
+impl<C: ConnectionLike> ConnectionLike for CountingConnection<C> {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.round_trips += 1;
+        self.inner.req_packed_command(cmd)
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.round_trips += 1;
+        self.inner.req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+}
+
+pub struct BenchmarkResult {
+    pub num_reads: usize,
+    pub num_users: usize,
+    pub unbatched_round_trips: usize,
+    pub unbatched_ms: u64,
+    pub batched_round_trips: usize,
+    pub batched_ms: u64,
+}
+
+// Runs both implementations for the user and host roles of every event series
+// with a channel
+pub fn run<C: ConnectionLike>(con: C) -> Result<BenchmarkResult, crate::BoxedError> {
+    let mut con = CountingConnection::new(con);
+    let event_series: Vec<String> = con.smembers("event_series")?;
+    let mut reads = vec![];
+    for series_id in event_series {
+        let channel_id: Option<u64> =
+            con.get(format!("event_series:{}:discord_channel", series_id))?;
+        if let Some(channel_id) = channel_id {
+            reads.push((series_id.clone(), ChannelId(channel_id), false));
+            reads.push((series_id, ChannelId(channel_id), true));
+        }
+    }
+    con.take_round_trips();
+    let mut result = BenchmarkResult {
+        num_reads: reads.len(),
+        num_users: 0,
+        unbatched_round_trips: 0,
+        unbatched_ms: 0,
+        batched_round_trips: 0,
+        batched_ms: 0,
+    };
+    let started = Instant::now();
+    for (series_id, channel_id, is_host_role) in &reads {
+        let users =
+            unbatched_role_assignment_candidates(series_id, *channel_id, *is_host_role, &mut con)?;
+        result.num_users += users.len();
+    }
+    result.unbatched_ms = elapsed_ms(started);
+    result.unbatched_round_trips = con.take_round_trips();
+    let started = Instant::now();
+    for (series_id, channel_id, is_host_role) in &reads {
+        crate::discord_sync::role_assignment_candidates(
+            series_id,
+            *channel_id,
+            *is_host_role,
+            &mut con,
+        )?;
+    }
+    result.batched_ms = elapsed_ms(started);
+    result.batched_round_trips = con.take_round_trips();
+    Ok(result)
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    let elapsed = started.elapsed();
+    elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis())
+}
+
+// The reads as they were done before they were batched, as the baseline
+fn unbatched_role_assignment_candidates<C: ConnectionLike>(
+    event_series_id: &str,
+    channel: ChannelId,
+    is_host_role: bool,
+    con: &mut C,
+) -> Result<Vec<u64>, crate::BoxedError> {
+    let event_ids: Vec<String> =
+        con.smembers(format!("event_series:{}:meetup_events", event_series_id))?;
+    if event_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let redis_event_users_keys: Vec<_> = event_ids
+        .iter()
+        .map(|event_id| {
+            if is_host_role {
+                format!("meetup_event:{}:meetup_hosts", event_id)
+            } else {
+                format!("meetup_event:{}:meetup_users", event_id)
+            }
+        })
+        .collect();
+    let (meetup_user_ids,): (Vec<u64>,) =
+        redis::pipe().sunion(redis_event_users_keys).query(con)?;
+    let mut discord_user_ids = vec![];
+    for meetup_id in meetup_user_ids {
+        // Not through the link cache, which would skew the comparison
+        let discord_id =
+            crate::identity::discord_user(&crate::identity::Identity::meetup(meetup_id), con)?;
+        discord_user_ids.extend(discord_id);
+    }
+    let redis_channel_removed_hosts_key = format!("discord_channel:{}:removed_hosts", channel.0);
+    let redis_channel_removed_users_key = format!("discord_channel:{}:removed_users", channel.0);
+    let mut ignore_discord_user_ids: Vec<u64> = if is_host_role {
+        con.sunion(&[
+            &redis_channel_removed_hosts_key,
+            &redis_channel_removed_users_key,
+        ])?
+    } else {
+        con.smembers(&redis_channel_removed_users_key)?
+    };
+    ignore_discord_user_ids.extend(crate::quarantine::quarantined_users(con)?);
+    discord_user_ids.retain(|id| !ignore_discord_user_ids.contains(id));
+    Ok(discord_user_ids)
+}
@@ -0,0 +1,76 @@
+use redis::Commands;
+use std::collections::HashMap;
+
+// Collects everything Redis knows about an event series, for debugging with
+// botctl: the series' own keys, those of its channel and those of its Meetup
+// events. Values are converted to JSON according to their Redis type.
+
+pub fn series_keys(
+    series_id: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Vec<String>, crate::BoxedError> {
+    let mut keys: Vec<String> = con.keys(format!("event_series:{}:*", series_id))?;
+    let channel_id: Option<u64> = con.get(format!("event_series:{}:discord_channel", series_id))?;
+    if let Some(channel_id) = channel_id {
+        let channel_keys: Vec<String> = con.keys(format!("discord_channel:{}:*", channel_id))?;
+        keys.extend(channel_keys);
+    }
+    let event_ids: Vec<String> =
+        con.smembers(format!("event_series:{}:meetup_events", series_id))?;
+    for event_id in event_ids {
+        let event_key = format!("meetup_event:{}", event_id);
+        let event_exists: bool = con.exists(&event_key)?;
+        if event_exists {
+            keys.push(event_key);
+        }
+        let event_keys: Vec<String> = con.keys(format!("meetup_event:{}:*", event_id))?;
+        keys.extend(event_keys);
+    }
+    keys.sort_unstable();
+    keys.dedup();
+    Ok(keys)
+}
+
+pub fn dump_keys(
+    keys: &[String],
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<serde_json::Value, crate::BoxedError> {
+    let mut dump = serde_json::Map::new();
+    for key in keys {
+        dump.insert(key.clone(), dump_key(key, con)?);
+    }
+    Ok(serde_json::Value::Object(dump))
+}
+
+fn dump_key(
+    key: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<serde_json::Value, crate::BoxedError> {
+    let key_type: String = redis::cmd("TYPE").arg(key).query(con)?;
+    let value = match key_type.as_str() {
+        "string" => {
+            let value: String = con.get(key)?;
+            serde_json::json!(value)
+        }
+        "list" => {
+            let values: Vec<String> = con.lrange(key, 0, -1)?;
+            serde_json::json!(values)
+        }
+        "set" => {
+            let mut values: Vec<String> = con.smembers(key)?;
+            values.sort_unstable();
+            serde_json::json!(values)
+        }
+        "zset" => {
+            let values: Vec<(String, f64)> = con.zrange_withscores(key, 0, -1)?;
+            serde_json::json!(values)
+        }
+        "hash" => {
+            let values: HashMap<String, String> = con.hgetall(key)?;
+            serde_json::json!(values)
+        }
+        // The key expired in the meantime or is of a type the bot doesn't use
+        _ => serde_json::Value::Null,
+    };
+    Ok(value)
+}
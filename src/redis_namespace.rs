@@ -0,0 +1,551 @@
+use futures::Future;
+use redis::{ConnectionLike, RedisResult, Value};
+
+// Wrappers around the Redis client and connection that transparently put all
+// keys into a namespace, such that several bots can share a Redis instance.
+// The namespace is a prefix (e.g. "swissrpg:") that gets prepended to every
+// key of every command and stripped from keys returned by KEYS and SCAN.
+// Without a prefix, commands are passed through unchanged.
+
+#[derive(Clone)]
+pub struct Client {
+    inner: redis::Client,
+    prefix: String,
+}
+
+pub struct Connection {
+    inner: redis::Connection,
+    prefix: String,
+}
+
+pub struct AsyncConnection {
+    inner: redis::aio::Connection,
+    prefix: String,
+}
+
+impl Client {
+    pub fn open(url: &str, prefix: &str) -> RedisResult<Self> {
+        Ok(Client {
+            inner: redis::Client::open(url)?,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    pub fn get_connection(&self) -> RedisResult<Connection> {
+        Ok(Connection {
+            inner: self.inner.get_connection()?,
+            prefix: self.prefix.clone(),
+        })
+    }
+
+    pub fn get_async_connection(
+        &self,
+    ) -> impl Future<Item = AsyncConnection, Error = redis::RedisError> {
+        let prefix = self.prefix.clone();
+        self.inner
+            .get_async_connection()
+            .map(move |con| AsyncConnection {
+                inner: con,
+                prefix: prefix,
+            })
+    }
+}
+
+impl ConnectionLike for Client {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.get_connection()?.req_packed_command(cmd)
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.get_connection()?
+            .req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+}
+
+impl ConnectionLike for Connection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        if self.prefix.is_empty() {
+            return self.inner.req_packed_command(cmd);
+        }
+        let (cmd, keys_in_results) = prefix_packed_commands(cmd, &self.prefix)?;
+        let value = self.inner.req_packed_command(&cmd)?;
+        Ok(strip_prefix_from_results(vec![value], keys_in_results, 0, &self.prefix).remove(0))
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        if self.prefix.is_empty() {
+            return self.inner.req_packed_commands(cmd, offset, count);
+        }
+        let (cmd, keys_in_results) = prefix_packed_commands(cmd, &self.prefix)?;
+        let values = self.inner.req_packed_commands(&cmd, offset, count)?;
+        Ok(strip_prefix_from_results(
+            values,
+            keys_in_results,
+            offset,
+            &self.prefix,
+        ))
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+}
+
+impl redis::aio::ConnectionLike for AsyncConnection {
+    fn req_packed_command(self, cmd: Vec<u8>) -> redis::RedisFuture<(Self, Value)> {
+        let prefix = self.prefix;
+        if prefix.is_empty() {
+            return Box::new(self.inner.req_packed_command(cmd).map(move |(con, value)| {
+                (
+                    AsyncConnection {
+                        inner: con,
+                        prefix: prefix,
+                    },
+                    value,
+                )
+            }));
+        }
+        let (cmd, keys_in_results) = match prefix_packed_commands(&cmd, &prefix) {
+            Ok(prefixed) => prefixed,
+            Err(err) => return Box::new(futures::future::err(err)),
+        };
+        Box::new(self.inner.req_packed_command(cmd).map(move |(con, value)| {
+            let value =
+                strip_prefix_from_results(vec![value], keys_in_results, 0, &prefix).remove(0);
+            (
+                AsyncConnection {
+                    inner: con,
+                    prefix: prefix,
+                },
+                value,
+            )
+        }))
+    }
+
+    fn req_packed_commands(
+        self,
+        cmd: Vec<u8>,
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisFuture<(Self, Vec<Value>)> {
+        let prefix = self.prefix;
+        let (cmd, keys_in_results) = if prefix.is_empty() {
+            (cmd, vec![])
+        } else {
+            match prefix_packed_commands(&cmd, &prefix) {
+                Ok(prefixed) => prefixed,
+                Err(err) => return Box::new(futures::future::err(err)),
+            }
+        };
+        Box::new(
+            self.inner
+                .req_packed_commands(cmd, offset, count)
+                .map(move |(con, values)| {
+                    let values = if prefix.is_empty() {
+                        values
+                    } else {
+                        strip_prefix_from_results(values, keys_in_results, offset, &prefix)
+                    };
+                    (
+                        AsyncConnection {
+                            inner: con,
+                            prefix: prefix,
+                        },
+                        values,
+                    )
+                }),
+        )
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+}
+
+// Prefixes the keys of all commands in a packed command (or pipeline) and
+// remembers for each command whether its result contains keys
+fn prefix_packed_commands(
+    packed: &[u8],
+    prefix: &str,
+) -> RedisResult<(Vec<u8>, Vec<KeysInResult>)> {
+    let mut commands = parse_packed_commands(packed)?;
+    let keys_in_results = commands
+        .iter_mut()
+        .map(|args| prefix_keys(args, prefix.as_bytes()))
+        .collect();
+    Ok((pack_commands(&commands), keys_in_results))
+}
+
+// The results correspond to the commands starting at the specified offset.
+// In a transaction (an atomic pipeline), the commands between MULTI and EXEC
+// only reply with QUEUED and their actual results are in the reply of EXEC.
+fn strip_prefix_from_results(
+    values: Vec<Value>,
+    keys_in_results: Vec<KeysInResult>,
+    offset: usize,
+    prefix: &str,
+) -> Vec<Value> {
+    let mut queued: Option<Vec<KeysInResult>> = None;
+    let mut keys_in_replies = vec![];
+    for keys_in_result in keys_in_results {
+        match keys_in_result {
+            KeysInResult::Multi => {
+                queued = Some(vec![]);
+                keys_in_replies.push(KeysInReply::None);
+            }
+            KeysInResult::Exec => {
+                keys_in_replies.push(KeysInReply::Exec(queued.take().unwrap_or_default()));
+            }
+            keys_in_result => match queued {
+                Some(ref mut queued) => {
+                    queued.push(keys_in_result);
+                    keys_in_replies.push(KeysInReply::None);
+                }
+                None => keys_in_replies.push(KeysInReply::Single(keys_in_result)),
+            },
+        }
+    }
+    let mut keys_in_replies = keys_in_replies.into_iter().skip(offset);
+    values
+        .into_iter()
+        .map(|value| match keys_in_replies.next() {
+            Some(KeysInReply::Single(keys_in_result)) => {
+                strip_prefix_from_result(value, &keys_in_result, prefix.as_bytes())
+            }
+            Some(KeysInReply::Exec(queued)) => match value {
+                Value::Bulk(values) => Value::Bulk(
+                    values
+                        .into_iter()
+                        .zip(queued.iter())
+                        .map(|(value, keys_in_result)| {
+                            strip_prefix_from_result(value, keys_in_result, prefix.as_bytes())
+                        })
+                        .collect(),
+                ),
+                // The transaction was aborted
+                other => other,
+            },
+            _ => value,
+        })
+        .collect()
+}
+
+fn strip_prefix_from_result(value: Value, keys_in_result: &KeysInResult, prefix: &[u8]) -> Value {
+    match keys_in_result {
+        KeysInResult::List => strip_prefix_from_list(value, prefix),
+        KeysInResult::Scan => strip_prefix_from_scan(value, prefix),
+        _ => value,
+    }
+}
+
+enum KeysInResult {
+    None,
+    // The result is a list of keys (KEYS)
+    List,
+    // The result is a cursor and a list of keys (SCAN)
+    Scan,
+    // Start and end of a transaction
+    Multi,
+    Exec,
+}
+
+// What the reply of a command contains, once the commands of a transaction
+// are matched up with the reply of EXEC
+enum KeysInReply {
+    None,
+    Single(KeysInResult),
+    Exec(Vec<KeysInResult>),
+}
+
+// Which arguments (not counting the command name) of a command are keys
+enum KeyPositions {
+    None,
+    First,
+    FirstTwo,
+    All,
+    // MSET key value [key value ...]
+    EveryOther,
+    // EVAL script numkeys key [key ...] arg [arg ...]
+    Script,
+}
+
+fn key_positions(command_name: &[u8]) -> KeyPositions {
+    match command_name.to_ascii_uppercase().as_slice() {
+        b"PING" | b"ECHO" | b"MULTI" | b"EXEC" | b"DISCARD" | b"UNWATCH" | b"SELECT" | b"AUTH"
+        | b"INFO" | b"SCRIPT" | b"CLIENT" | b"CONFIG" | b"TIME" | b"DBSIZE" | b"FLUSHDB"
+        | b"FLUSHALL" | b"QUIT" | b"KEYS" | b"SCAN" => KeyPositions::None,
+        b"DEL" | b"EXISTS" | b"UNLINK" | b"TOUCH" | b"MGET" | b"WATCH" | b"SUNION" | b"SINTER"
+        | b"SDIFF" | b"SUNIONSTORE" | b"SINTERSTORE" | b"SDIFFSTORE" => KeyPositions::All,
+        b"RENAME" | b"RENAMENX" | b"SMOVE" | b"RPOPLPUSH" => KeyPositions::FirstTwo,
+        b"MSET" | b"MSETNX" => KeyPositions::EveryOther,
+        b"EVAL" | b"EVALSHA" => KeyPositions::Script,
+        // Most commands operate on a single key
+        _ => KeyPositions::First,
+    }
+}
+
+// Prefixes the keys of a single command in place
+fn prefix_keys(args: &mut Vec<Vec<u8>>, prefix: &[u8]) -> KeysInResult {
+    let prefixed = |arg: &Vec<u8>| [prefix, arg.as_slice()].concat();
+    if args.is_empty() {
+        return KeysInResult::None;
+    }
+    let command_name = args[0].to_ascii_uppercase();
+    match command_name.as_slice() {
+        b"MULTI" => return KeysInResult::Multi,
+        b"EXEC" => return KeysInResult::Exec,
+        b"KEYS" => {
+            if args.len() > 1 {
+                args[1] = prefixed(&args[1]);
+            }
+            return KeysInResult::List;
+        }
+        b"SCAN" => {
+            // Only scan this namespace
+            let match_position = args
+                .iter()
+                .position(|arg| arg.eq_ignore_ascii_case(b"MATCH"));
+            match match_position {
+                Some(position) if position + 1 < args.len() => {
+                    args[position + 1] = prefixed(&args[position + 1]);
+                }
+                _ => {
+                    args.push(b"MATCH".to_vec());
+                    args.push([prefix, b"*"].concat());
+                }
+            }
+            return KeysInResult::Scan;
+        }
+        // MEMORY USAGE key [SAMPLES count]
+        b"MEMORY" => {
+            if args.len() > 2 && args[1].eq_ignore_ascii_case(b"USAGE") {
+                args[2] = prefixed(&args[2]);
+            }
+            return KeysInResult::None;
+        }
+        _ => (),
+    }
+    let num_args = args.len();
+    let key_indices: Vec<usize> = match key_positions(&command_name) {
+        KeyPositions::None => vec![],
+        KeyPositions::First => (1..std::cmp::min(2, num_args)).collect(),
+        KeyPositions::FirstTwo => (1..std::cmp::min(3, num_args)).collect(),
+        KeyPositions::All => (1..num_args).collect(),
+        KeyPositions::EveryOther => (1..num_args).step_by(2).collect(),
+        KeyPositions::Script => {
+            let num_keys = args
+                .get(2)
+                .and_then(|num_keys| std::str::from_utf8(num_keys).ok())
+                .and_then(|num_keys| num_keys.parse::<usize>().ok())
+                .unwrap_or(0);
+            (3..std::cmp::min(3 + num_keys, num_args)).collect()
+        }
+    };
+    for index in key_indices {
+        args[index] = prefixed(&args[index]);
+    }
+    KeysInResult::None
+}
+
+fn strip_prefix(key: Vec<u8>, prefix: &[u8]) -> Vec<u8> {
+    if key.starts_with(prefix) {
+        key[prefix.len()..].to_vec()
+    } else {
+        key
+    }
+}
+
+fn strip_prefix_from_list(value: Value, prefix: &[u8]) -> Value {
+    match value {
+        Value::Bulk(keys) => Value::Bulk(
+            keys.into_iter()
+                .map(|key| match key {
+                    Value::Data(key) => Value::Data(strip_prefix(key, prefix)),
+                    other => other,
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn strip_prefix_from_scan(value: Value, prefix: &[u8]) -> Value {
+    match value {
+        Value::Bulk(mut cursor_and_keys) => {
+            if cursor_and_keys.len() == 2 {
+                let keys = cursor_and_keys.pop().unwrap();
+                cursor_and_keys.push(strip_prefix_from_list(keys, prefix));
+            }
+            Value::Bulk(cursor_and_keys)
+        }
+        other => other,
+    }
+}
+
+// redis-rs packs every command as an array of bulk strings:
+// *<num args>\r\n$<len>\r\n<arg>\r\n...
+// Pipelines are simply several of those arrays in a row.
+fn parse_packed_commands(packed: &[u8]) -> RedisResult<Vec<Vec<Vec<u8>>>> {
+    fn invalid() -> redis::RedisError {
+        (
+            redis::ErrorKind::ClientError,
+            "Could not parse packed Redis command",
+        )
+            .into()
+    }
+    fn read_number(packed: &[u8], pos: &mut usize, marker: u8) -> RedisResult<usize> {
+        if packed.get(*pos) != Some(&marker) {
+            return Err(invalid());
+        }
+        let start = *pos + 1;
+        let end = packed[start..]
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .map(|offset| start + offset)
+            .ok_or_else(invalid)?;
+        *pos = end + 2;
+        std::str::from_utf8(&packed[start..end])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(invalid)
+    }
+    let mut commands = vec![];
+    let mut pos = 0;
+    while pos < packed.len() {
+        let num_args = read_number(packed, &mut pos, b'*')?;
+        let mut args = Vec::with_capacity(num_args);
+        for _ in 0..num_args {
+            let len = read_number(packed, &mut pos, b'$')?;
+            if pos + len + 2 > packed.len() {
+                return Err(invalid());
+            }
+            args.push(packed[pos..pos + len].to_vec());
+            pos += len + 2;
+        }
+        commands.push(args);
+    }
+    Ok(commands)
+}
+
+fn pack_commands(commands: &[Vec<Vec<u8>>]) -> Vec<u8> {
+    let mut packed = vec![];
+    for args in commands {
+        packed.extend_from_slice(format!("*{}\r\n", args.len()).as_bytes());
+        for arg in args {
+            packed.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+            packed.extend_from_slice(arg);
+            packed.extend_from_slice(b"\r\n");
+        }
+    }
+    packed
+}
+
+// Moves all keys that are not in the namespace yet into it.
+// Used once when switching an existing deployment to a key prefix.
+pub fn migrate_to_prefix(url: &str, prefix: &str) -> Result<u64, crate::BoxedError> {
+    if prefix.is_empty() {
+        return Ok(0);
+    }
+    let mut con = redis::Client::open(url)?.get_connection()?;
+    let keys: Vec<String> = redis::cmd("KEYS").arg("*").query(&mut con)?;
+    let mut num_migrated = 0;
+    for key in keys {
+        if key.starts_with(prefix) {
+            continue;
+        }
+        let prefixed_key = format!("{}{}", prefix, key);
+        let renamed: bool = redis::cmd("RENAMENX")
+            .arg(&key)
+            .arg(&prefixed_key)
+            .query(&mut con)?;
+        if renamed {
+            num_migrated += 1;
+        } else {
+            eprintln!(
+                "Not migrating key {} since {} already exists",
+                key, prefixed_key
+            );
+        }
+    }
+    Ok(num_migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREFIX: &str = "test:";
+
+    fn data(value: &str) -> Value {
+        Value::Data(value.as_bytes().to_vec())
+    }
+
+    fn keys_in_results(commands: &[&[&str]]) -> Vec<KeysInResult> {
+        let commands: Vec<Vec<Vec<u8>>> = commands
+            .iter()
+            .map(|args| args.iter().map(|arg| arg.as_bytes().to_vec()).collect())
+            .collect();
+        let (_, keys_in_results) = prefix_packed_commands(&pack_commands(&commands), PREFIX)
+            .expect("Could not prefix the commands");
+        keys_in_results
+    }
+
+    #[test]
+    fn strips_prefix_from_keys_reply() {
+        let keys_in_results = keys_in_results(&[&["GET", "a"], &["KEYS", "*"]]);
+        let values = vec![data("1"), Value::Bulk(vec![data("test:a"), data("test:b")])];
+        assert_eq!(
+            strip_prefix_from_results(values, keys_in_results, 0, PREFIX),
+            vec![data("1"), Value::Bulk(vec![data("a"), data("b")])]
+        );
+    }
+
+    #[test]
+    fn strips_prefix_inside_exec_reply() {
+        // An atomic pipeline only returns the reply of EXEC
+        let keys_in_results = keys_in_results(&[
+            &["MULTI"],
+            &["GET", "a"],
+            &["KEYS", "*"],
+            &["SCAN", "0"],
+            &["EXEC"],
+        ]);
+        let values = vec![Value::Bulk(vec![
+            data("test:value"),
+            Value::Bulk(vec![data("test:a"), data("test:b")]),
+            Value::Bulk(vec![data("0"), Value::Bulk(vec![data("test:c")])]),
+        ])];
+        assert_eq!(
+            strip_prefix_from_results(values, keys_in_results, 4, PREFIX),
+            vec![Value::Bulk(vec![
+                // Values are left alone, even if they look like a key
+                data("test:value"),
+                Value::Bulk(vec![data("a"), data("b")]),
+                Value::Bulk(vec![data("0"), Value::Bulk(vec![data("c")])]),
+            ])]
+        );
+    }
+
+    #[test]
+    fn leaves_aborted_transaction_alone() {
+        let keys_in_results = keys_in_results(&[&["MULTI"], &["KEYS", "*"], &["EXEC"]]);
+        assert_eq!(
+            strip_prefix_from_results(vec![Value::Nil], keys_in_results, 2, PREFIX),
+            vec![Value::Nil]
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use redis::{Commands, PipelineCommands};
+use std::collections::HashMap;
+
+// Redis holds everything the bot knows, so its memory use grows with the
+// community. Short lived data (linking IDs, cooldowns, confirmation and
+// pagination state, ...) is written with a TTL by the code that owns it, and
+// EXPIRING_FAMILIES lists those keys with their TTL. The daily storage check
+// gives keys of these families that have no TTL (e.g. because they were
+// written before the TTL was introduced) their TTL after all, records the
+// number of keys and warns the organizers when it grew by more than
+// GROWTH_WARNING_FACTOR within a week. "storage report" shows the number of
+// keys and the estimated memory per key family. Apart from the expiring
+// families, a family is everything that starts with the same word (e.g.
+// "meetup_event:*").
+const SCAN_BATCH_SIZE: usize = 1000;
+// The memory of each family is estimated from this many of its keys
+const MEMORY_SAMPLE_SIZE: usize = 10;
+const GROWTH_WARNING_FACTOR: f64 = 1.5;
+// Small datasets grow fast in relative terms without it being a problem
+const MIN_GROWTH_WARNING_KEYS: u64 = 1000;
+const KEY_COUNT_HISTORY_DAYS: i64 = 30;
+const REDIS_KEY_COUNTS_KEY: &'static str = "storage:key_counts";
+
+pub struct ExpiringFamily {
+    // The key with "*" for its variable parts
+    pub pattern: &'static str,
+    pub ttl_secs: usize,
+}
+
+pub const EXPIRING_FAMILIES: &[ExpiringFamily] = &[
+    ExpiringFamily {
+        pattern: "meetup_linking:*:discord_user",
+        ttl_secs: crate::meetup_oauth2::LINKING_ID_TTL_SECS,
+    },
+    ExpiringFamily {
+        pattern: "csrf:*",
+        ttl_secs: crate::meetup_oauth2::CSRF_TTL_SECS,
+    },
+    ExpiringFamily {
+        pattern: "discord_user:*:linking_wizard",
+        ttl_secs: crate::discord_linking_wizard::WIZARD_KEY_TTL_SECS,
+    },
+    ExpiringFamily {
+        pattern: "discord_user:*:linking_help_requested",
+        ttl_secs: crate::discord_linking_wizard::HELP_REQUEST_COOLDOWN_SECONDS,
+    },
+    ExpiringFamily {
+        pattern: "host_verification:discord_channel:*:discord_user:*:alerted",
+        ttl_secs: crate::host_verification::ALERT_COOLDOWN_SECS,
+    },
+    ExpiringFamily {
+        pattern: "pending_action:*",
+        ttl_secs: crate::discord_confirmations::PENDING_ACTION_TTL_SECS,
+    },
+    ExpiringFamily {
+        pattern: "paginated_message:*",
+        ttl_secs: crate::discord_pagination::PAGINATION_TTL_SECS,
+    },
+    ExpiringFamily {
+        pattern: "discord_message:*:command",
+        ttl_secs: crate::discord_edited_commands::EDIT_WINDOW_SECONDS as usize,
+    },
+    ExpiringFamily {
+        pattern: "command_usage:*",
+        ttl_secs: crate::discord_command_usage::RETENTION_SECS,
+    },
+    ExpiringFamily {
+        pattern: "command_usage:*:users:*",
+        ttl_secs: crate::discord_command_usage::RETENTION_SECS,
+    },
+];
+
+pub struct FamilyStats {
+    pub family: String,
+    pub num_keys: u64,
+    // Extrapolated from the sampled keys
+    pub estimated_bytes: u64,
+    // Only for expiring families
+    pub num_without_ttl: u64,
+}
+
+pub struct StorageStats {
+    // Largest first
+    pub families: Vec<FamilyStats>,
+    pub num_keys: u64,
+    // Keys of expiring families without a TTL, with the TTL they should have
+    pub keys_without_ttl: Vec<(String, usize)>,
+}
+
+// "*" stands for exactly one part of the key
+fn matches_pattern(key: &str, pattern: &str) -> bool {
+    let key_parts: Vec<_> = key.split(':').collect();
+    let pattern_parts: Vec<_> = pattern.split(':').collect();
+    key_parts.len() == pattern_parts.len()
+        && key_parts
+            .iter()
+            .zip(&pattern_parts)
+            .all(|(key_part, pattern_part)| *pattern_part == "*" || key_part == pattern_part)
+}
+
+fn expiring_family(key: &str) -> Option<&'static ExpiringFamily> {
+    EXPIRING_FAMILIES
+        .iter()
+        .find(|family| matches_pattern(key, family.pattern))
+}
+
+fn family_name(key: &str) -> String {
+    if let Some(family) = expiring_family(key) {
+        return family.pattern.to_string();
+    }
+    match key.find(':') {
+        Some(index) => format!("{}:*", &key[..index]),
+        None => key.to_string(),
+    }
+}
+
+// Goes through all keys (of the namespace) with SCAN, which doesn't block
+// Redis like KEYS would
+pub fn collect_stats(
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<StorageStats, crate::BoxedError> {
+    let mut num_keys_per_family: HashMap<String, u64> = HashMap::new();
+    let mut sample_keys_per_family: HashMap<String, Vec<String>> = HashMap::new();
+    let mut num_without_ttl_per_family: HashMap<String, u64> = HashMap::new();
+    let mut keys_without_ttl = vec![];
+    let mut cursor: u64 = 0;
+    loop {
+        let (next_cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("COUNT")
+            .arg(SCAN_BATCH_SIZE)
+            .query(con)?;
+        let expiring_keys: Vec<_> = keys
+            .iter()
+            .filter_map(|key| expiring_family(key).map(|family| (key, family)))
+            .collect();
+        if !expiring_keys.is_empty() {
+            let mut pipe = redis::pipe();
+            for (key, _) in &expiring_keys {
+                pipe.ttl(*key);
+            }
+            let ttls: Vec<i64> = pipe.query(con)?;
+            for ((key, family), ttl) in expiring_keys.into_iter().zip(ttls) {
+                // -1 means that the key exists but has no TTL
+                if ttl == -1 {
+                    *num_without_ttl_per_family
+                        .entry(family.pattern.to_string())
+                        .or_insert(0) += 1;
+                    keys_without_ttl.push((key.clone(), family.ttl_secs));
+                }
+            }
+        }
+        for key in keys {
+            let family = family_name(&key);
+            *num_keys_per_family.entry(family.clone()).or_insert(0) += 1;
+            let sample_keys = sample_keys_per_family.entry(family).or_default();
+            if sample_keys.len() < MEMORY_SAMPLE_SIZE {
+                sample_keys.push(key);
+            }
+        }
+        cursor = next_cursor;
+        if cursor == 0 {
+            break;
+        }
+    }
+    let mut families = Vec::with_capacity(num_keys_per_family.len());
+    for (family, num_keys) in num_keys_per_family {
+        let sample_keys = &sample_keys_per_family[&family];
+        let mut pipe = redis::pipe();
+        for key in sample_keys {
+            pipe.cmd("MEMORY").arg("USAGE").arg(key);
+        }
+        // Keys that expired in the meantime have no memory usage
+        let sample_bytes: Vec<Option<u64>> = pipe.query(con)?;
+        let sample_bytes: Vec<u64> = sample_bytes.into_iter().filter_map(|bytes| bytes).collect();
+        let estimated_bytes = if sample_bytes.is_empty() {
+            0
+        } else {
+            sample_bytes.iter().sum::<u64>() * num_keys / sample_bytes.len() as u64
+        };
+        families.push(FamilyStats {
+            num_without_ttl: num_without_ttl_per_family
+                .get(&family)
+                .cloned()
+                .unwrap_or(0),
+            family: family,
+            num_keys: num_keys,
+            estimated_bytes: estimated_bytes,
+        });
+    }
+    families.sort_by(|stats1, stats2| {
+        stats2
+            .estimated_bytes
+            .cmp(&stats1.estimated_bytes)
+            .then_with(|| stats1.family.cmp(&stats2.family))
+    });
+    Ok(StorageStats {
+        num_keys: families.iter().map(|stats| stats.num_keys).sum(),
+        families: families,
+        keys_without_ttl: keys_without_ttl,
+    })
+}
+
+// The number of keys a week ago, if it was recorded
+fn key_count_a_week_ago<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Option<u64>, crate::BoxedError> {
+    let week_ago = chrono::Utc::now().date().naive_utc() - chrono::Duration::days(7);
+    Ok(con.hget(
+        REDIS_KEY_COUNTS_KEY,
+        week_ago.format("%Y-%m-%d").to_string(),
+    )?)
+}
+
+// The number of keys a week ago, if the dataset grew unexpectedly since
+pub fn unexpected_growth<C: redis::ConnectionLike>(
+    con: &mut C,
+    num_keys: u64,
+) -> Result<Option<u64>, crate::BoxedError> {
+    Ok(key_count_a_week_ago(con)?.filter(|&num_keys_before| {
+        num_keys >= num_keys_before + MIN_GROWTH_WARNING_KEYS
+            && num_keys as f64 > num_keys_before as f64 * GROWTH_WARNING_FACTOR
+    }))
+}
+
+pub fn create_storage_check_task(
+    redis_client: crate::redis_namespace::Client,
+    discord_api: crate::discord_bot::CacheAndHttp,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    move |_ctx| {
+        if let Err(err) = check_storage(&redis_client, &discord_api) {
+            eprintln!("Storage check task failed: {}", err);
+            crate::error_reporting::report(&err, &[("task", "storage_check")]);
+        }
+        white_rabbit::DateResult::Repeat(white_rabbit::Utc::now() + white_rabbit::Duration::days(1))
+    }
+}
+
+fn check_storage(
+    redis_client: &crate::redis_namespace::Client,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let mut con = redis_client.get_connection()?;
+    let stats = collect_stats(&mut con)?;
+    if !stats.keys_without_ttl.is_empty() {
+        let mut pipe = redis::pipe();
+        for (key, ttl_secs) in &stats.keys_without_ttl {
+            pipe.expire(key, *ttl_secs).ignore();
+        }
+        let _: () = pipe.query(&mut con)?;
+        println!(
+            "Storage check: gave {} keys their TTL",
+            stats.keys_without_ttl.len()
+        );
+    }
+    if let Some(num_keys_before) = unexpected_growth(&mut con, stats.num_keys)? {
+        crate::discord_notifications::notify_organizers(
+            discord_api,
+            &crate::strings::STORAGE_GROWTH_WARNING(num_keys_before, stats.num_keys),
+            &mut con,
+        )?;
+    }
+    // Remember today's count and forget the ones that are no longer needed
+    let today = chrono::Utc::now().date().naive_utc();
+    let oldest_day = today - chrono::Duration::days(KEY_COUNT_HISTORY_DAYS);
+    let days: Vec<String> = con.hkeys(REDIS_KEY_COUNTS_KEY)?;
+    let mut pipe = redis::pipe();
+    pipe.hset(
+        REDIS_KEY_COUNTS_KEY,
+        today.format("%Y-%m-%d").to_string(),
+        stats.num_keys,
+    )
+    .ignore();
+    for day in days {
+        let is_old = chrono::NaiveDate::parse_from_str(&day, "%Y-%m-%d")
+            .map(|day| day < oldest_day)
+            .unwrap_or(true);
+        if is_old {
+            pipe.hdel(REDIS_KEY_COUNTS_KEY, day).ignore();
+        }
+    }
+    let _: () = pipe.query(&mut con)?;
+    Ok(())
+}
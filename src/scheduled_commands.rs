@@ -0,0 +1,223 @@
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::channel::Message,
+    prelude::{Context, EventHandler, Mutex},
+};
+use std::sync::Arc;
+
+// Organizers can have any bot command run at a later point in time with the
+// "schedule" command. The command is run as if the organizer had sent it in
+// the same channel at that time, so permissions are checked when it runs,
+// not when it is scheduled.
+// Running a command requires a serenity Context, which only exists while the
+// bot is connected. Scheduled commands are therefore not part of the
+// persistent tasks, but are put into the scheduler from within the bot (and
+// again by the boot sequence after a restart).
+pub const MAX_SCHEDULE_DAYS: i64 = 365;
+
+const REDIS_SCHEDULED_COMMANDS_KEY: &'static str = "scheduled_commands";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledCommand {
+    // The command without the bot mention
+    pub command: String,
+    pub due_time: String,
+    pub discord_user: u64,
+    pub channel_id: u64,
+    // The message that scheduled the command, which is replayed with the
+    // command as its content
+    message: serde_json::Value,
+}
+
+// Accepts "YYYY-MM-DD HH:MM" in UTC or an RFC 3339 date
+pub fn parse_due_time(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let value = value.trim();
+    if let Ok(due_time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(due_time.with_timezone(&chrono::Utc));
+    }
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M")
+        .ok()
+        .map(|due_time| chrono::DateTime::from_utc(due_time, chrono::Utc))
+}
+
+// Persists the command and puts it into the scheduler. Returns the ID which
+// can be used to cancel it.
+pub fn schedule(
+    ctx: &Context,
+    msg: &Message,
+    command: &str,
+    due_time: chrono::DateTime<chrono::Utc>,
+    redis_client: &crate::redis_namespace::Client,
+) -> Result<String, crate::BoxedError> {
+    let task_scheduler = {
+        let data = ctx.data.read();
+        data.get::<crate::discord_bot::TaskSchedulerKey>()
+            .expect("Task scheduler was not set")
+            .clone()
+    };
+    let command_id = crate::meetup_oauth2::new_random_id(6);
+    let scheduled_command = ScheduledCommand {
+        command: command.trim().to_string(),
+        due_time: due_time.to_rfc3339(),
+        discord_user: msg.author.id.0,
+        channel_id: msg.channel_id.0,
+        message: serde_json::to_value(msg)?,
+    };
+    let mut con = redis_client.get_connection()?;
+    let _: () = con.hset(
+        REDIS_SCHEDULED_COMMANDS_KEY,
+        &command_id,
+        serde_json::to_string(&scheduled_command)?,
+    )?;
+    add_to_scheduler(
+        ctx,
+        &task_scheduler,
+        redis_client,
+        command_id.clone(),
+        due_time,
+    );
+    Ok(command_id)
+}
+
+// Pending commands ordered by due time
+pub fn list<C: redis::ConnectionLike>(
+    con: &mut C,
+) -> Result<Vec<(String, ScheduledCommand)>, crate::BoxedError> {
+    let scheduled_commands: std::collections::HashMap<String, String> =
+        con.hgetall(REDIS_SCHEDULED_COMMANDS_KEY)?;
+    let mut scheduled_commands: Vec<_> = scheduled_commands
+        .into_iter()
+        .filter_map(|(command_id, scheduled_command)| {
+            match serde_json::from_str::<ScheduledCommand>(&scheduled_command) {
+                Ok(scheduled_command) => Some((command_id, scheduled_command)),
+                Err(err) => {
+                    eprintln!(
+                        "Could not parse the scheduled command {}: {}",
+                        command_id, err
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
+    scheduled_commands.sort_by(|(_, a), (_, b)| a.due_time.cmp(&b.due_time));
+    Ok(scheduled_commands)
+}
+
+// The task stays in the scheduler, but does nothing once the command is gone
+// from Redis. Returns false if there is no such command.
+pub fn cancel<C: redis::ConnectionLike>(
+    con: &mut C,
+    command_id: &str,
+) -> Result<bool, crate::BoxedError> {
+    let num_removed: u32 = con.hdel(REDIS_SCHEDULED_COMMANDS_KEY, command_id)?;
+    Ok(num_removed > 0)
+}
+
+// Puts all pending commands back into the scheduler after a restart.
+// Overdue commands are run right away.
+pub fn rehydrate(
+    ctx: &Context,
+    redis_client: &crate::redis_namespace::Client,
+) -> Result<(), crate::BoxedError> {
+    let task_scheduler = {
+        let data = ctx.data.read();
+        data.get::<crate::discord_bot::TaskSchedulerKey>()
+            .expect("Task scheduler was not set")
+            .clone()
+    };
+    let mut con = redis_client.get_connection()?;
+    let now = white_rabbit::Utc::now();
+    for (command_id, scheduled_command) in list(&mut con)? {
+        let due_time = match white_rabbit::DateTime::parse_from_rfc3339(&scheduled_command.due_time)
+        {
+            Ok(due_time) => std::cmp::max(due_time.with_timezone(&white_rabbit::Utc), now),
+            Err(_) => now,
+        };
+        println!(
+            "Rehydrating scheduled command {} due at {}",
+            command_id, due_time
+        );
+        add_to_scheduler(ctx, &task_scheduler, redis_client, command_id, due_time);
+    }
+    Ok(())
+}
+
+fn add_to_scheduler(
+    ctx: &Context,
+    task_scheduler: &Arc<Mutex<white_rabbit::Scheduler>>,
+    redis_client: &crate::redis_namespace::Client,
+    command_id: String,
+    due_time: white_rabbit::DateTime<white_rabbit::Utc>,
+) {
+    // The scheduler needs tasks to be Sync, which a Context is not
+    let ctx = Mutex::new(ctx.clone());
+    let redis_client = redis_client.clone();
+    task_scheduler
+        .lock()
+        .add_task_datetime(due_time, move |_: &mut white_rabbit::Context| {
+            let run_result: Result<white_rabbit::DateResult, crate::BoxedError> = (|| {
+                let mut con = redis_client.get_connection()?;
+                // Wait until maintenance is over, like the persistent tasks
+                if crate::maintenance_mode::is_active(&mut con)? {
+                    println!(
+                        "Postponing scheduled command {} due to maintenance",
+                        command_id
+                    );
+                    return Ok(white_rabbit::DateResult::Repeat(
+                        white_rabbit::Utc::now() + white_rabbit::Duration::minutes(1),
+                    ));
+                }
+                let scheduled_command: Option<String> =
+                    con.hget(REDIS_SCHEDULED_COMMANDS_KEY, &command_id)?;
+                // Cancelled in the meantime
+                let scheduled_command = match scheduled_command {
+                    Some(scheduled_command) => scheduled_command,
+                    None => return Ok(white_rabbit::DateResult::Done),
+                };
+                // Remove it first, such that it runs at most once
+                let _: () = con.hdel(REDIS_SCHEDULED_COMMANDS_KEY, &command_id)?;
+                let scheduled_command: ScheduledCommand = serde_json::from_str(&scheduled_command)?;
+                run(&ctx.lock(), &scheduled_command)?;
+                Ok(white_rabbit::DateResult::Done)
+            })();
+            match run_result {
+                Ok(result) => result,
+                Err(err) => {
+                    eprintln!("Could not run scheduled command {}: {}", command_id, err);
+                    // Retries are no-ops once the command was removed
+                    white_rabbit::DateResult::Repeat(
+                        white_rabbit::Utc::now() + white_rabbit::Duration::minutes(1),
+                    )
+                }
+            }
+        });
+}
+
+// Replays the message that scheduled the command with the command as its
+// content, through the regular message handler
+fn run(ctx: &Context, scheduled_command: &ScheduledCommand) -> Result<(), crate::BoxedError> {
+    let mut msg: Message = serde_json::from_value(scheduled_command.message.clone())?;
+    let bot_mention = {
+        let data = ctx.data.read();
+        data.get::<crate::discord_bot::RegexesKey>()
+            .expect("Regexes were not compiled")
+            .bot_mention
+            .clone()
+    };
+    msg.content = format!("{} {}", bot_mention, scheduled_command.command);
+    println!(
+        "Running the command \"{}\" scheduled by user {}",
+        scheduled_command.command, scheduled_command.discord_user
+    );
+    let _ = msg.channel_id.say(
+        &ctx.http,
+        crate::strings::SCHEDULED_COMMAND_RUNNING(
+            &scheduled_command.command,
+            scheduled_command.discord_user,
+        ),
+    );
+    crate::discord_bot::Handler.message(ctx.clone(), msg);
+    Ok(())
+}
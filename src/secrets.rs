@@ -0,0 +1,158 @@
+use simple_error::SimpleError;
+use std::path::PathBuf;
+
+// Secrets can be passed to the bot in three ways, checked in this order:
+// * directly as an environment variable, e.g. DISCORD_TOKEN
+// * as a path to a file containing the secret, e.g. DISCORD_TOKEN_FILE
+//   (the convention used for Docker secrets)
+// * as a systemd credential with the lowercase name, e.g. `discord_token`
+//   in $CREDENTIALS_DIRECTORY (see LoadCredential= in systemd.exec)
+// Surrounding whitespace (like a trailing newline in a file) is ignored.
+pub fn load(name: &str) -> Result<Option<String>, crate::BoxedError> {
+    if let Ok(value) = std::env::var(name) {
+        return non_empty(name, value).map(Some);
+    }
+    let file_var = format!("{}_FILE", name);
+    if let Ok(path) = std::env::var(&file_var) {
+        return read_secret_file(name, PathBuf::from(path)).map(Some);
+    }
+    if let Ok(credentials_directory) = std::env::var("CREDENTIALS_DIRECTORY") {
+        let path = PathBuf::from(credentials_directory).join(name.to_lowercase());
+        if path.exists() {
+            return read_secret_file(name, path).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+// Like `load`, but also checks that the secret is present and valid
+pub fn load_required(
+    name: &str,
+    validate: impl Fn(&str) -> Result<(), String>,
+) -> Result<String, crate::BoxedError> {
+    let value = load(name)?.ok_or_else(|| {
+        SimpleError::new(format!(
+            "Found no {name} in the environment. Set either {name}, {name}_FILE or provide \
+             the systemd credential \"{lowercase_name}\"",
+            name = name,
+            lowercase_name = name.to_lowercase()
+        ))
+    })?;
+    validate(&value).map_err(|err| SimpleError::new(format!("{} is invalid: {}", name, err)))?;
+    Ok(value)
+}
+
+// Configuration errors are reported without a backtrace, since the backtrace
+// would only bury the actual message
+pub fn exit_on_config_error<T>(res: Result<T, crate::BoxedError>) -> T {
+    match res {
+        Ok(value) => value,
+        Err(err) => {
+            eprintln!("Configuration error: {}", err.inner);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Like `load_required`, but falls back to a default if the secret is not set
+pub fn load_or(
+    name: &str,
+    default: &str,
+    validate: impl Fn(&str) -> Result<(), String>,
+) -> Result<String, crate::BoxedError> {
+    let value = load(name)?.unwrap_or_else(|| default.to_string());
+    validate(&value).map_err(|err| SimpleError::new(format!("{} is invalid: {}", name, err)))?;
+    Ok(value)
+}
+
+// Like `load`, but also checks that the secret is valid if it is present
+pub fn load_optional(
+    name: &str,
+    validate: impl Fn(&str) -> Result<(), String>,
+) -> Result<Option<String>, crate::BoxedError> {
+    let value = load(name)?;
+    if let Some(ref value) = value {
+        validate(value).map_err(|err| SimpleError::new(format!("{} is invalid: {}", name, err)))?;
+    }
+    Ok(value)
+}
+
+fn read_secret_file(name: &str, path: PathBuf) -> Result<String, crate::BoxedError> {
+    let value = std::fs::read_to_string(&path).map_err(|err| {
+        SimpleError::new(format!(
+            "Could not read {} from \"{}\": {}",
+            name,
+            path.display(),
+            err
+        ))
+    })?;
+    non_empty(name, value)
+}
+
+fn non_empty(name: &str, value: String) -> Result<String, crate::BoxedError> {
+    let value = value.trim();
+    if value.is_empty() {
+        Err(SimpleError::new(format!("{} is empty", name)).into())
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+// Discord bot tokens consist of three base64 encoded parts separated by dots
+pub fn validate_discord_token(token: &str) -> Result<(), String> {
+    if token.starts_with("Bot ") {
+        return Err("the token must not start with \"Bot \"".to_string());
+    }
+    let parts: Vec<_> = token.split('.').collect();
+    let is_base64 = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    if parts.len() != 3 || !parts.iter().all(|part| is_base64(part)) {
+        return Err("this does not look like a Discord bot token".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_meetup_client_secret(secret: &str) -> Result<(), String> {
+    if secret.chars().any(char::is_whitespace) {
+        return Err("the client secret must not contain whitespace".to_string());
+    }
+    Ok(())
+}
+
+// The admin API token is compared as is, so it should be hard to guess
+pub fn validate_admin_api_token(token: &str) -> Result<(), String> {
+    if token.chars().any(char::is_whitespace) {
+        return Err("the token must not contain whitespace".to_string());
+    }
+    if token.len() < 32 {
+        return Err("the token needs to be at least 32 characters long".to_string());
+    }
+    Ok(())
+}
+
+// Sent as bearer tokens in a header
+pub fn validate_eventbrite_api_token(token: &str) -> Result<(), String> {
+    validate_warhorn_api_token(token)
+}
+
+pub fn validate_warhorn_api_token(token: &str) -> Result<(), String> {
+    if token.chars().any(char::is_whitespace) {
+        return Err("the token must not contain whitespace".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_redis_url(url: &str) -> Result<(), String> {
+    let parsed_url = url::Url::parse(url).map_err(|err| err.to_string())?;
+    match parsed_url.scheme() {
+        "redis" | "unix" | "redis+unix" => Ok(()),
+        scheme => Err(format!(
+            "unsupported scheme \"{}\", expected \"redis\" or \"unix\"",
+            scheme
+        )),
+    }
+}
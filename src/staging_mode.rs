@@ -0,0 +1,17 @@
+use lazy_static::lazy_static;
+
+// A staging deployment runs against a test guild and unlocks the debug
+// commands, which are too dangerous (or too noisy) for the production guild.
+// Set STAGING_MODE=1 (or "true") in the environment to enable it.
+lazy_static! {
+    static ref STAGING_MODE: bool = std::env::var("STAGING_MODE")
+        .map(|value| {
+            let value = value.trim();
+            value == "1" || value.eq_ignore_ascii_case("true")
+        })
+        .unwrap_or(false);
+}
+
+pub fn is_active() -> bool {
+    *STAGING_MODE
+}
@@ -10,6 +10,22 @@ pub const UNSPECIFIED_ERROR: &'static str = "Something went wrong";
 
 pub const INVALID_COMMAND: &'static str = "Sorry, I do not understand that command";
 
+#[allow(non_snake_case)]
+pub fn INVALID_COMMAND_SUGGESTION(usage: &str) -> String {
+    format!("{} Did you mean `{}`?", INVALID_COMMAND, usage)
+}
+
+#[allow(non_snake_case)]
+pub fn CONFIRMATION_PROMPT(prompt: &str, confirm_emoji: &str, cancel_emoji: &str) -> String {
+    format!(
+        "{}\nReact with {} to confirm or with {} to cancel. \
+         This request expires in five minutes.",
+        prompt, confirm_emoji, cancel_emoji
+    )
+}
+
+pub const CONFIRMATION_CANCELLED: &'static str = "Alright, I cancelled that request";
+
 // ** Welcome messages **
 
 pub const WELCOME_MESSAGE_PART1: &'static str =
@@ -64,6 +80,10 @@ on Meetup and I will extend the lifetime of this channel.",
     )
 }
 
+pub const CHANNEL_ARCHIVED: &'static str =
+    "This adventure is over, so I archived this channel. You can still read it, but no new \
+     messages can be posted.";
+
 // ** Meetup linking **
 
 #[allow(non_snake_case)]
@@ -76,6 +96,49 @@ pub fn MEETUP_LINKING_MESSAGE(linking_url: &str) -> String {
     )
 }
 
+#[allow(non_snake_case)]
+pub fn LINKING_WIZARD_STEPS(timeout_minutes: i64) -> String {
+    format!(
+        "Let's link your Meetup profile to your Discord account! It only takes a minute:\n\
+         **1.** Open the link below\n\
+         **2.** Log in to Meetup if you aren't already and allow me access to your profile\n\
+         **3.** Come back here, I will let you know once it worked\n\
+         If nothing happens within {} minutes, I will help you figure out what went wrong.",
+        timeout_minutes
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn LINKING_WIZARD_SUCCESS(meetup_name: Option<&str>) -> String {
+    match meetup_name {
+        Some(meetup_name) => format!(
+            "All done! Your Discord account is now linked to {}'s Meetup profile.",
+            meetup_name
+        ),
+        None => "All done! Your Discord account is now linked to your Meetup profile.".to_string(),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn LINKING_WIZARD_TIMEOUT(timeout_minutes: i64) -> String {
+    format!(
+        "It's been {} minutes and your Meetup profile isn't linked yet. \
+         Here is what you can do:\n\
+         \u{2022} Reply with ***link meetup*** to get a new link, \
+         in case the old one expired or didn't work\n\
+         \u{2022} Make sure that you are logged in to the right Meetup account \
+         and that you clicked on \"Allow\"\n\
+         \u{2022} Reply with ***linking help*** and an organizer will get in touch with you",
+        timeout_minutes
+    )
+}
+
+pub const LINKING_HELP_REQUESTED: &'static str =
+    "I let the organizers know, one of them will get in touch with you soon";
+
+pub const LINKING_HELP_ALREADY_REQUESTED: &'static str =
+    "The organizers already know that you need help, they will get in touch with you soon";
+
 #[allow(non_snake_case)]
 pub fn MEETUP_ALREADY_LINKED_SUCCESS(linking_url: &str) -> String {
     format!(
@@ -119,19 +182,280 @@ pub fn NONEXISTENT_MEETUP_LINKED_MESSAGE(bot_id: u64) -> String {
     )
 }
 
+pub const MEETUP_UNLINK_CONFIRMATION: &'static str =
+    "Do you really want to unlink your Meetup account?";
+
+pub const DELETE_MY_DATA_CONFIRMATION: &'static str =
+    "Do you really want me to forget about you? This unlinks your Meetup account, email \
+     address and Warhorn account, withdraws your open requests and removes your \
+     subscriptions and characters. It can't be undone.";
+
+pub const USER_DATA_DELETED: &'static str = "Done, I deleted your data";
+
+#[allow(non_snake_case)]
+pub fn MEETUP_UNLINK_ORGANIZER_CONFIRMATION(discord_id: u64) -> String {
+    format!(
+        "Do you really want to unlink <@{}>'s Meetup account?",
+        discord_id
+    )
+}
+
 pub const MEETUP_UNLINK_SUCCESS: &'static str = "Unlinked your Meetup account";
 pub const MEETUP_UNLINK_NOT_LINKED: &'static str =
     "There was seemingly no meetup account linked to you";
 
+// ** Email registration **
+
+pub const EMAIL_INVALID: &'static str =
+    "That doesn't look like an email address. Please write \"register email\" \
+     followed by the address you sign up for games with.";
+
+#[allow(non_snake_case)]
+pub fn EMAIL_REGISTERED(email: &str) -> String {
+    format!(
+        "Thanks, I registered {}. Once you accept the invitation to a game \
+         or sign up for it with this address, you will get access to its channel.",
+        email
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn EMAIL_REGISTRATION_PENDING(email: &str) -> String {
+    format!(
+        "Thanks, I asked the organisers to confirm that {} is yours. \
+         I will let you know once they did.",
+        email
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn EMAIL_APPROVAL_REQUESTED(discord_id: u64, email: &str) -> String {
+    format!(
+        "<@{}> wants to register the email address {}. If it is theirs, \
+         write \"approve email <@{}>\" to me in a DM.",
+        discord_id, email, discord_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn EMAIL_APPROVED(discord_id: u64, email: &str) -> String {
+    format!(
+        "The address {} is now registered to <@{}>.",
+        email, discord_id
+    )
+}
+
+pub const EMAIL_NO_PENDING_REQUEST: &'static str =
+    "This user hasn't asked to register an email address.";
+
+pub const EMAIL_TAKEN_WHILE_PENDING: &'static str =
+    "Somebody else registered this address in the meantime. \
+     Free it with \"unregister email <address>\" first.";
+
+pub const EMAIL_ALREADY_REGISTERED: &'static str = "You already registered this address.";
+
+pub const EMAIL_TAKEN: &'static str =
+    "Somebody else already registered this address. If it is yours, \
+     please contact an @Organiser.";
+
+#[allow(non_snake_case)]
+pub fn EMAIL_UNREGISTERED(email: &str) -> String {
+    format!("Your address {} is not registered anymore.", email)
+}
+
+pub const EMAIL_NOT_REGISTERED: &'static str = "You haven't registered an email address.";
+
+#[allow(non_snake_case)]
+pub fn EMAIL_ADDRESS_FREED(discord_id: u64) -> String {
+    format!(
+        "The address is not registered to <@{}> anymore.",
+        discord_id
+    )
+}
+
+pub const EMAIL_ADDRESS_NOT_REGISTERED: &'static str = "Nobody registered this address.";
+
+// Doesn't show the address, which only the buyer and the organizers should see
+pub const TICKET_CLAIMED: &'static str =
+    "Thanks, I registered the address of this order. Once the game comes up, \
+     you will get access to its channel.";
+
+// Doesn't show the address either
+pub const TICKET_CLAIM_PENDING: &'static str =
+    "Thanks, I asked the organisers to confirm that this order is yours. \
+     I will let you know once they did.";
+
+#[allow(non_snake_case)]
+pub fn TICKET_APPROVAL_REQUESTED(discord_id: u64, order_id: &str, email: &str) -> String {
+    format!(
+        "<@{}> claimed the ticket of Eventbrite order #{} ({}). If it is theirs, \
+         write \"approve email <@{}>\" to me in a DM.",
+        discord_id, order_id, email, discord_id
+    )
+}
+
+pub const TICKET_NOT_FOUND: &'static str =
+    "I couldn't find this order. Please write \"claim ticket\" followed by the \
+     order number from your Eventbrite confirmation email.";
+
+pub const TICKET_CLAIMING_UNAVAILABLE: &'static str =
+    "This server doesn't sell tickets on Eventbrite.";
+
+// ** Identities **
+
+#[allow(non_snake_case)]
+pub fn IDENTITIES(identities: &[crate::identity::Identity]) -> String {
+    if identities.is_empty() {
+        return "You haven't linked any accounts yet. Link your Meetup account with \
+                \"link meetup\" or register your email address with \"register email\"."
+            .to_string();
+    }
+    let lines: Vec<String> = identities
+        .iter()
+        .enumerate()
+        .map(|(i, identity)| {
+            if i == 0 {
+                format!("• `{}` (primary)", identity)
+            } else {
+                format!("• `{}`", identity)
+            }
+        })
+        .collect();
+    format!(
+        "These accounts are linked to you:\n{}\nWrite \"primary identity <account>\" \
+         to make another one your primary account.",
+        lines.join("\n")
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn PRIMARY_IDENTITY_SET(identity: &crate::identity::Identity) -> String {
+    format!("`{}` is now your primary account.", identity)
+}
+
+pub const PRIMARY_IDENTITY_NOT_LINKED: &'static str =
+    "This account isn't linked to you. Write \"identities\" to see the ones that are.";
+
+// ** Nicknames **
+
+pub const NICKNAME_SYNC_ENABLED: &'static str =
+    "Got it, your nickname will show your Meetup first name again (if the organizers \
+     turned that on).";
+
+pub const NICKNAME_SYNC_DISABLED: &'static str =
+    "Got it, I won't touch your nickname anymore. Write \"nickname sync on\" if you \
+     change your mind.";
+
+// ** Households **
+
+#[allow(non_snake_case)]
+pub fn HOUSEHOLD_REQUESTED(member_id: u64) -> String {
+    format!(
+        "Thanks, I asked the organizers to let <@{}> share your Meetup account. \
+         I'll let them know once it's approved.",
+        member_id
+    )
+}
+
+pub const HOUSEHOLD_NOT_LINKED: &'static str =
+    "You can only share a Meetup account that is linked to you. \
+     Link yours with \"link meetup\" first.";
+
+#[allow(non_snake_case)]
+pub fn HOUSEHOLD_MEMBER_LINKED(member_id: u64) -> String {
+    format!(
+        "<@{}> already uses a Meetup account, so they can't share yours.",
+        member_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn HOUSEHOLD_REQUEST_NOTIFICATION(owner_id: u64, member_id: u64, meetup_id: u64) -> String {
+    format!(
+        "<@{owner_id}> would like to share their Meetup account {meetup_id} with <@{member_id}>. \
+         Write \"approve household <@{member_id}>\" or \"reject household <@{member_id}>\" \
+         (mentioning me) to decide.",
+        owner_id = owner_id,
+        member_id = member_id,
+        meetup_id = meetup_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn HOUSEHOLD_APPROVED(member_id: u64, meetup_id: u64) -> String {
+    format!(
+        "<@{}> now shares the Meetup account {} and gets the same channels with \
+         the next sync.",
+        member_id, meetup_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn HOUSEHOLD_REJECTED(member_id: u64) -> String {
+    format!("Rejected the request of <@{}>.", member_id)
+}
+
+#[allow(non_snake_case)]
+pub fn HOUSEHOLD_NO_REQUEST(member_id: u64) -> String {
+    format!(
+        "There is no pending request for <@{}> (or the Meetup account was unlinked \
+         in the meantime).",
+        member_id
+    )
+}
+
+pub const HOUSEHOLD_NO_PENDING_REQUESTS: &'static str = "There are no pending household requests.";
+
+pub const HOUSEHOLD_REQUESTS_TITLE: &'static str = "Pending household requests";
+
+#[allow(non_snake_case)]
+pub fn HOUSEHOLD_PENDING_REQUEST(member_id: u64, meetup_id: u64, owner_id: Option<u64>) -> String {
+    match owner_id {
+        Some(owner_id) => format!(
+            "<@{}> would like to share the Meetup account {} of <@{}>",
+            member_id, meetup_id, owner_id
+        ),
+        None => format!(
+            "<@{}> would like to share the Meetup account {} (not linked anymore)",
+            member_id, meetup_id
+        ),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn HOUSEHOLD_NOT_A_MEMBER(member_id: u64) -> String {
+    format!("<@{}> doesn't share a Meetup account.", member_id)
+}
+
+pub const HOUSEHOLD_NOT_ALLOWED: &'static str =
+    "Only the owner of the Meetup account, the user sharing it and organizers can do that.";
+
+#[allow(non_snake_case)]
+pub fn HOUSEHOLD_MEMBER_REMOVED(member_id: u64) -> String {
+    format!(
+        "<@{}> doesn't share the Meetup account anymore. They keep the channels \
+         they already have, but won't be added to new ones.",
+        member_id
+    )
+}
+
 // ** Channel administration **
 
 pub const NOT_A_CHANNEL_ADMIN: &'static str = "Only channel hosts and organizers can do that";
 
+pub const NOT_A_REPLY: &'static str = "Please use this command in a reply to the message you mean";
+
 pub const CHANNEL_NOT_BOT_CONTROLLED: &'static str =
     "This channel does not seem to be under my control";
 
+pub const CHANNEL_NEEDS_ROLES: &'static str =
+    "This only works in channels with a player and a host role, this channel gives its members access individually";
+
 pub const CHANNEL_NOT_YET_CLOSEABLE: &'static str = "The channel cannot be closed yet";
 
+pub const CHANNEL_CLOSE_CONFIRMATION: &'static str =
+    "Do you really want to close this channel?";
+
 pub const CHANNEL_MARKED_FOR_CLOSING: &'static str =
     "I marked this channel the be closed in the next 24 hours.\n\
      Thanks for playing and hope to see you soon!";
@@ -149,33 +473,2675 @@ pub fn CHANNEL_ADDED_NEW_HOST(discord_id: u64) -> String {
     format!("<@{}> is now a host of this channel", discord_id)
 }
 
+#[allow(non_snake_case)]
+pub fn CHANNEL_REMOVED_USER(discord_id: u64) -> String {
+    format!("<@{}> was removed from this channel", discord_id)
+}
+
+pub const CHANNEL_BATCH_UNKNOWN_USER: &'static str = "I don't know this user";
+
+pub const CHANNEL_BATCH_QUARANTINED: &'static str = "they are currently quarantined";
+
+pub const CHANNEL_BATCH_ROLE_ERROR: &'static str = "something went wrong with the channel role";
+
+#[allow(non_snake_case)]
+pub fn CHANNEL_BATCH_RESULT(add: bool, succeeded: &[u64], failed: &[(String, &str)]) -> String {
+    let mut lines = vec![];
+    if !succeeded.is_empty() {
+        let mentions = succeeded
+            .iter()
+            .map(|id| format!("<@{}>", id))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if add {
+            lines.push(format!("Welcome {}!", mentions));
+        } else {
+            lines.push(format!("Removed {} from this channel", mentions));
+        }
+    }
+    for (user, reason) in failed {
+        lines.push(format!(
+            "Could not {} {}: {}",
+            if add { "add" } else { "remove" },
+            user,
+            reason
+        ));
+    }
+    lines.join("\n")
+}
+
+#[allow(non_snake_case)]
+pub fn CHANNEL_ADDED_NEW_COHOST(discord_id: u64) -> String {
+    format!("<@{}> is now a co-host of this channel", discord_id)
+}
+
+#[allow(non_snake_case)]
+pub fn CHANNEL_REMOVED_COHOST(discord_id: u64) -> String {
+    format!("<@{}> is no longer a co-host of this channel", discord_id)
+}
+
+#[allow(non_snake_case)]
+pub fn CHANNEL_ADDED_GUEST(
+    discord_id: u64,
+    expiration_time: chrono::DateTime<chrono::Utc>,
+) -> String {
+    format!(
+        "Welcome <@{}>! You are a guest in this channel until {}.",
+        discord_id,
+        expiration_time.format("%A, %B %e at %H:%M UTC")
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn GUEST_PERIOD_INVALID(max_days: i64) -> String {
+    format!(
+        "Please give a period like \"1 week\", \"3 days\" or \"12 hours\", \
+         of at most {} days",
+        max_days
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn GUEST_ACCESS_ENDED(discord_id: u64, channel_id: u64) -> String {
+    format!(
+        "The guest access of <@{}> to <#{}> ended and I removed them from the channel. \
+         If they should stay, add them again with `add @user`.",
+        discord_id, channel_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn TABLES_INVALID_COUNT(max_tables: usize) -> String {
+    format!(
+        "Please choose a number of tables between 2 and {}",
+        max_tables
+    )
+}
+
+pub const TABLES_ALREADY_EXIST: &'static str =
+    "This channel already has tables. They will be cleaned up after the event.";
+
+#[allow(non_snake_case)]
+pub fn TABLES_NOT_ENOUGH_PLAYERS(num_players: usize) -> String {
+    format!(
+        "There are only {} players in this channel, that's not enough for this many tables",
+        num_players
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn TABLES_CREATED(tables: &[(u64, Vec<u64>)]) -> String {
+    let table_lines: Vec<_> = tables
+        .iter()
+        .map(|(channel_id, player_ids)| {
+            let players: Vec<_> = player_ids.iter().map(|id| format!("<@{}>", id)).collect();
+            format!("<#{}>: {}", channel_id, players.join(", "))
+        })
+        .collect();
+    format!(
+        "I randomly assigned everybody to a table:\n{}\n\
+         The table channels will be deleted after the event.",
+        table_lines.join("\n")
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn MEMBER_EXPORT_DM(channel_id: u64) -> String {
+    format!(
+        "Here are the current members of <#{}>. Please handle this list with care, \
+         it contains personal data.",
+        channel_id
+    )
+}
+
+pub const MEMBER_EXPORT_SENT: &'static str = "I sent you the member list via DM";
+
+pub const MEMBER_EXPORT_DM_FAILED: &'static str =
+    "I could not send you the member list. Please make sure that you accept direct \
+     messages from server members.";
+
+#[allow(non_snake_case)]
+pub fn SESSION_CHANNELS_TOGGLED(enabled: bool) -> String {
+    if enabled {
+        "From now on, each upcoming session gets its own discussion channel a week before it \
+         takes place"
+            .to_string()
+    } else {
+        "Upcoming sessions won't get their own discussion channel anymore".to_string()
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn SERIES_SYNC_PAUSE_TOGGLED(paused: bool) -> String {
+    if paused {
+        "The bot won't touch this channel or its roles until someone says \"resume sync\". \
+         Take your time!"
+            .to_string()
+    } else {
+        "The bot will sync this channel again in the next few minutes".to_string()
+    }
+}
+
+pub const STYLE_USAGE: &'static str =
+    "Tell me the colour of the channel role, the emoji in front of the channel name or both, \
+     e.g. \"style colour #aa33ff emoji 🐉\". Use \"none\" to go back to the default.";
+
+pub const STYLE_INVALID_COLOUR: &'static str =
+    "That's not a colour I understand, please give it in hex like #aa33ff";
+
+pub const STYLE_INVALID_EMOJI: &'static str =
+    "Please pick a single standard emoji, server emojis can't be part of a channel name";
+
+pub const STYLE_UPDATED: &'static str =
+    "Looking good! The style stays when the channel is synced with Meetup.";
+
+pub const STYLE_RENAME_DEFERRED: &'static str =
+    "Style saved! Discord only allows a few channel renames per ten minutes, so the new emoji \
+     will show up in the channel name within the next syncs.";
+
+#[allow(non_snake_case)]
+pub fn HIDE_HISTORY_INVALID_DAYS(max_days: u64) -> String {
+    format!(
+        "Please choose a number of days between 1 and {} or \"off\"",
+        max_days
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn HIDE_HISTORY_SET(days: Option<u64>) -> String {
+    match days {
+        Some(1) => "From now on, players who are removed from this channel can't read its \
+                    history for a day, even if they come back"
+            .to_string(),
+        Some(days) => format!(
+            "From now on, players who are removed from this channel can't read its history \
+             for {} days, even if they come back",
+            days
+        ),
+        None => "Players who are removed from this channel won't lose access to its history \
+                 anymore"
+            .to_string(),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn SESSION_CHANNEL_DETAILS(
+    event_name: &str,
+    event_time: chrono::DateTime<chrono::Utc>,
+    event_link: &str,
+    event_location: &crate::event_location::Location,
+) -> String {
+    format!(
+        "This channel is for everything about the session **{}** on {}.\n{}\n{}\
+         It will be archived the day after the session.",
+        event_name,
+        event_time.format("%A, %B %e at %H:%M UTC"),
+        event_link,
+        EVENT_LOCATION(event_location)
+            .map(|location| format!("{}\n", location))
+            .unwrap_or_default()
+    )
+}
+
+// Where the session takes place, None if Meetup doesn't tell
+#[allow(non_snake_case)]
+pub fn EVENT_LOCATION(location: &crate::event_location::Location) -> Option<String> {
+    match location {
+        crate::event_location::Location::Online { link: Some(link) } => {
+            Some(format!("Online: {}", link))
+        }
+        crate::event_location::Location::Online { link: None } => Some("Online".to_string()),
+        crate::event_location::Location::Venue(venue) => Some(format!("Location: {}", venue)),
+        crate::event_location::Location::Unknown => None,
+    }
+}
+
+pub const SESSION_CHANNEL_ARCHIVED: &'static str =
+    "This session is over, so I archived this channel. It will be deleted in a week.";
+
+#[allow(non_snake_case)]
+pub fn SCHEDULED_EVENT_DESCRIPTION(event_link: &str) -> String {
+    format!(
+        "Sign up on Meetup to join this session and get access to its Discord channel: {}",
+        event_link
+    )
+}
+
 pub const CHANNEL_ADD_USER_INVALID_DISCORD: &'static str =
-    "Seems like the specified Discord ID is invalid";
+    "Seems like the specified Discord user is invalid or unknown. If they can't be mentioned, try their Discord ID instead";
 
-// **************************************
-// *** Meetup linking webpage replies ***
-// **************************************
+// ** Channel settings **
 
 #[allow(non_snake_case)]
-pub fn OAUTH2_AUTHORISATION_DENIED(linking_url: &str) -> String {
+pub fn CHANNEL_SETTINGS(
+    settings: &crate::discord_channel_settings::ChannelSettings,
+    bot_id: u64,
+) -> String {
+    let toggle = |enabled: bool| if enabled { "on" } else { "off" };
     format!(
-        "Looks like you declined the authorisation. If you want to \
-         start over, click the button below to give it another go. \
-         If you are still having issues, please contact an organiser \
-         by email (organisers@swissrpg.ch) or on Discord (@Organiser).<br>\
-         <a href=\"{linking_url}\" class=\"button\" style=\"margin-top: 1em;\">Start Over</a>",
-        linking_url = linking_url
+        "The settings of this channel:\n\
+         \u{2022} `reminders`: {} (expiration reminders, `on` or `off`)\n\
+         \u{2022} `reminder_lead_time`: {} (hours before the channel expires that the \
+         reminders start, up to {})\n\
+         \u{2022} `topic`: {} (template with the placeholders `{{name}}`, `{{link}}`, \
+         `{{location}}`, `{{session}}` and `{{tags}}`, or `default`)\n\
+         \u{2022} `closing`: {} (what happens when the channel is closed, `delete` or \
+         `archive`)\n\
+         \u{2022} `reaction_signup`: {} (asking to join the next session by reacting to the \
+         session message, `on` or `off`)\n\
+         \u{2022} `tidy`: {} (the classes of commands whose messages and replies are cleaned \
+         up, `off`, `all`, a list like `player,host` or `default` for the server setting)\n\
+         Change a setting with ***<@{}> settings <setting> <value>***",
+        toggle(settings.reminders),
+        settings.reminder_lead_hours,
+        crate::discord_channel_settings::MAX_REMINDER_LEAD_HOURS,
+        settings
+            .topic_template
+            .as_ref()
+            .map(|template| format!("`{}`", template))
+            .unwrap_or_else(|| "default".to_string()),
+        settings.closing.name(),
+        toggle(settings.reaction_signup),
+        settings
+            .tidy
+            .as_ref()
+            .map(|classes| crate::discord_tidy::classes_name(classes))
+            .unwrap_or_else(|| "default".to_string()),
+        bot_id
     )
 }
 
-pub const OAUTH2_LINK_EXPIRED_TITLE: &'static str = "This link seems to have expired";
-pub const OAUTH2_LINK_EXPIRED_CONTENT: &'static str =
-    "Get a new link from the bot with the \"link meetup\" command";
+#[allow(non_snake_case)]
+pub fn CHANNEL_SETTING_UNKNOWN(name: &str) -> String {
+    format!(
+        "There is no setting called `{}`. The settings are: {}",
+        name,
+        crate::discord_channel_settings::Setting::ALL
+            .iter()
+            .map(|setting| format!("`{}`", setting.name()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
 
-pub const OAUTH2_LINKING_SUCCESS_TITLE: &'static str = "Linking Success!";
 #[allow(non_snake_case)]
-pub fn OAUTH2_LINKING_SUCCESS_CONTENT(name: &str) -> String {
-    format!("Successfully linked to {}'s Meetup account", name)
+pub fn CHANNEL_SETTING_INVALID_VALUE(setting: crate::discord_channel_settings::Setting) -> String {
+    use crate::discord_channel_settings::Setting;
+    let expected = match setting {
+        Setting::Reminders | Setting::ReactionSignup => "`on` or `off`".to_string(),
+        Setting::ReminderLeadTime => format!(
+            "a number of hours between 0 and {}",
+            crate::discord_channel_settings::MAX_REMINDER_LEAD_HOURS
+        ),
+        Setting::Topic => format!(
+            "a template of at most {} characters or `default`",
+            crate::discord_channel_settings::MAX_TOPIC_CHARS
+        ),
+        Setting::Closing => "`delete` or `archive`".to_string(),
+        Setting::Tidy => "`off`, `all`, a comma-separated list of `player`, `host` and \
+                          `organizer`, or `default`"
+            .to_string(),
+    };
+    format!("The setting `{}` expects {}", setting.name(), expected)
+}
+
+#[allow(non_snake_case)]
+pub fn CHANNEL_SETTING_CHANGED(setting: crate::discord_channel_settings::Setting) -> String {
+    format!("Got it, I changed the setting `{}`", setting.name())
+}
+
+// ** Link review **
+
+pub const NO_FLAGGED_LINKS: &'static str = "There are no flagged Meetup links to review";
+
+pub const FLAGGED_LINKS_TITLE: &'static str =
+    "Deactivated Meetup profiles and members who left the Meetup groups, with links to review";
+
+// ** Link suggestions **
+
+#[allow(non_snake_case)]
+pub fn LINK_SUGGESTION_FROM_RSVP_ANSWER(
+    meetup_id: u64,
+    discord_id: u64,
+    answer: &str,
+    bot_id: u64,
+) -> String {
+    format!(
+        "The Meetup member https://www.meetup.com/members/{meetup_id}/ gave `{answer}` as \
+         their Discord username when they RSVP'd, that looks like <@{discord_id}>. \
+         To link them, write:\n<@{bot_id}> confirm link {meetup_id}\n\
+         If that's the wrong person, write:\n<@{bot_id}> dismiss link {meetup_id}",
+        meetup_id = meetup_id,
+        discord_id = discord_id,
+        // In a code span, such that it can't ping anybody
+        answer = answer.replace('`', "'"),
+        bot_id = bot_id,
+    )
+}
+
+pub const LINK_SUGGESTIONS_NONE: &'static str =
+    "I couldn't find any Meetup members with a name like one of the server members'.";
+
+#[allow(non_snake_case)]
+pub fn LINK_SUGGESTIONS_TITLE(num_suggestions: usize) -> String {
+    format!(
+        "These are the {} best matches between Meetup members that RSVP'd and server \
+         members that haven't linked their Meetup account. Please compare the pictures \
+         before you confirm one, names can be misleading.",
+        num_suggestions
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn LINK_SUGGESTION(meetup_id: u64, discord_id: u64, similarity: f64, bot_id: u64) -> String {
+    format!(
+        "Meetup: https://www.meetup.com/members/{meetup_id}/\n\
+         Discord: <@{discord_id}>\n\
+         Name similarity: {similarity:.0}%\n\
+         Confirm: <@{bot_id}> confirm link {meetup_id}\n\
+         Dismiss: <@{bot_id}> dismiss link {meetup_id}",
+        meetup_id = meetup_id,
+        discord_id = discord_id,
+        similarity = similarity * 100.0,
+        bot_id = bot_id,
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn LINK_SUGGESTION_NOT_FOUND(meetup_id: u64) -> String {
+    format!(
+        "There is no link suggestion for the Meetup member {}. It might have been \
+         confirmed or dismissed already.",
+        meetup_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn LINK_SUGGESTION_DISMISSED(meetup_id: u64) -> String {
+    format!(
+        "Dismissed the suggestion for the Meetup member {}, I won't suggest it again.",
+        meetup_id
+    )
+}
+
+// ** Meetup membership **
+
+#[allow(non_snake_case)]
+pub fn MEETUP_GROUP_LEFT(
+    meetup_id: u64,
+    discord_id: u64,
+    grace: Option<chrono::Duration>,
+) -> String {
+    let consequence = match grace {
+        Some(grace) => format!(
+            "Unless they join again, I will remove them from their channels in {} days.",
+            grace.num_days()
+        ),
+        None => "They keep their channels for now.".to_string(),
+    };
+    format!(
+        "<@{}> (Meetup user {}) is not a member of our Meetup groups anymore. {}",
+        discord_id, meetup_id, consequence
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn MEETUP_GROUP_ACCESS_REMOVED(meetup_id: u64, discord_id: u64, num_channels: usize) -> String {
+    format!(
+        "<@{}> (Meetup user {}) left our Meetup groups and the grace period is over, so I \
+         removed them from {} channels",
+        discord_id, meetup_id, num_channels
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn USER_CHANNELS_TITLE(user_name: &str) -> String {
+    format!("Channels of {}", user_name)
+}
+
+#[allow(non_snake_case)]
+pub fn USER_CHANNELS_NONE(discord_id: u64) -> String {
+    format!(
+        "<@{}> doesn't have access to any bot-managed channels",
+        discord_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn USER_CHANNELS_LINE(
+    channel_id: u64,
+    channel: &crate::discord_user_channels::UserChannel,
+) -> String {
+    let roles: Vec<&str> = [
+        (channel.is_host, "host"),
+        (channel.is_cohost, "co-host"),
+        (channel.is_player, "player"),
+        (channel.is_guest, "guest"),
+    ]
+    .iter()
+    .filter(|(has_role, _)| *has_role)
+    .map(|(_, role)| *role)
+    .collect();
+    format!(
+        "<#{}>: {}{}",
+        channel_id,
+        roles.join(", "),
+        if channel.archived { " (archived)" } else { "" }
+    )
+}
+
+// ** Membership dues **
+
+#[allow(non_snake_case)]
+pub fn WHOIS(
+    discord_id: u64,
+    identities: &[crate::identity::Identity],
+    household_meetup_id: Option<u64>,
+    dues_group: Option<&str>,
+    dues: Option<&crate::meetup_dues::Dues>,
+) -> String {
+    let mut lines = vec![format!("Here is what I know about <@{}>:", discord_id)];
+    if identities.is_empty() {
+        lines.push("• No linked accounts".to_string());
+    } else {
+        lines.push(format!(
+            "• Linked accounts: {}",
+            identities
+                .iter()
+                .map(|identity| format!("`{}`", identity))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if let Some(household_meetup_id) = household_meetup_id {
+        lines.push(format!(
+            "• Shares the Meetup account {} of their household",
+            household_meetup_id
+        ));
+    }
+    let dues_line = match (dues_group, dues) {
+        (None, _) => "• Membership dues are not checked, see `config set dues_group`".to_string(),
+        (Some(dues_group), None) => format!(
+            "• Membership dues in {}: not checked yet (or no linked Meetup account)",
+            dues_group
+        ),
+        (Some(dues_group), Some(dues)) => {
+            let status = match dues.paid_until {
+                Some(paid_until) => format!(
+                    "{} (paid until {})",
+                    dues.status.name(),
+                    paid_until.format("%Y-%m-%d")
+                ),
+                None => dues.status.name().to_string(),
+            };
+            format!(
+                "• Membership dues in {}: {}, checked on {}",
+                dues_group,
+                status,
+                dues.checked_time.format("%Y-%m-%d")
+            )
+        }
+    };
+    lines.push(dues_line);
+    lines.join("\n")
+}
+
+#[allow(non_snake_case)]
+pub fn DUES_ROLE_SET(role_id: u64, dues_group: Option<&str>) -> String {
+    match dues_group {
+        Some(dues_group) => format!(
+            "From the next sync on, <@&{}> is only for members with paid dues in {}. \
+             Everybody else loses the role.",
+            role_id, dues_group
+        ),
+        None => format!(
+            "<@&{}> is now gated on membership dues, but nothing happens until you pick the \
+             group with `config set dues_group <urlname>`",
+            role_id
+        ),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn DUES_ROLE_REMOVED(role_id: u64) -> String {
+    format!(
+        "<@&{}> is not gated on membership dues anymore. I won't hand it out or take it away.",
+        role_id
+    )
+}
+
+// ** Inactive members **
+
+#[allow(non_snake_case)]
+pub fn INACTIVE_MEMBERS_NONE(min_days: u32) -> String {
+    format!(
+        "Everybody who joined more than {} days ago either linked their Meetup account or \
+         plays in one of the channels",
+        min_days
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn INACTIVE_MEMBERS_TITLE(num_members: usize, min_days: u32) -> String {
+    format!(
+        "{} members joined more than {} days ago, never linked their Meetup account and \
+         aren't in any channel",
+        num_members, min_days
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn INACTIVE_MEMBER_LINE(
+    discord_id: u64,
+    tag: &str,
+    joined_at: chrono::DateTime<chrono::Utc>,
+    last_nudge_time: Option<chrono::DateTime<chrono::Utc>>,
+) -> String {
+    let line = format!(
+        "<@{}> ({}), joined on {}",
+        discord_id,
+        tag,
+        joined_at.format("%Y-%m-%d")
+    );
+    match last_nudge_time {
+        Some(last_nudge_time) => {
+            format!("{}, nudged on {}", line, last_nudge_time.format("%Y-%m-%d"))
+        }
+        None => line,
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn INACTIVE_MEMBERS_NONE_TO_NUDGE(min_days: u32) -> String {
+    format!(
+        "There is nobody to nudge: everybody who joined more than {} days ago linked their \
+         Meetup account, plays in one of the channels or was nudged in the last 30 days",
+        min_days
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn NUDGE_INACTIVE_MEMBERS_PROMPT(num_members: usize) -> String {
+    format!(
+        "Should I send a reminder how to get started to {} inactive members via DM?",
+        num_members
+    )
+}
+
+pub const INACTIVE_MEMBER_NUDGE: &'static str =
+    "Hi! You joined our Discord server a while ago, but it looks like you haven't joined a \
+     game yet. Here is how to get started:\n\
+     \u{2022} Reply with ***link meetup*** to connect your Meetup account, such that you get \
+     access to the channels of the games you sign up for\n\
+     \u{2022} Have a look at the upcoming games on our Meetup page and RSVP to one that \
+     sounds fun\n\
+     If you have any questions, just ask in one of the public channels. See you at the table!";
+
+#[allow(non_snake_case)]
+pub fn INACTIVE_MEMBERS_NUDGED(num_nudged: usize, num_failed: usize) -> String {
+    if num_failed == 0 {
+        format!("Sent the reminder to {} members", num_nudged)
+    } else {
+        format!(
+            "Sent the reminder to {} members, {} members don't accept DMs from me",
+            num_nudged, num_failed
+        )
+    }
+}
+
+// ** Organizer notifications **
+
+#[allow(non_snake_case)]
+pub fn SERIES_SYNC_FAILURE_NOTIFICATION(series_id: &str, num_failures: u64, error: &str) -> String {
+    format!(
+        "Syncing the event series {} to Discord failed {} times in a row. \
+         I will keep retrying, but this probably needs a closer look.\n\
+         The latest error was:\n```{}```",
+        series_id, num_failures, error
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn LINKING_HELP_NOTIFICATION(discord_id: u64) -> String {
+    format!(
+        "<@{}> could not link their Meetup profile and asked for help",
+        discord_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn REPORT_NOTIFICATION(
+    discord_id: Option<u64>,
+    channels: &[serenity::model::id::ChannelId],
+    text: &str,
+    attachment_urls: &[&str],
+) -> String {
+    let reporter = match discord_id {
+        Some(discord_id) => format!("<@{}> sent a report", discord_id),
+        None => "Somebody sent an anonymous report".to_string(),
+    };
+    let channels = if discord_id.is_none() {
+        "".to_string()
+    } else if channels.is_empty() {
+        "\nThey are not in any of my game channels.".to_string()
+    } else {
+        format!(
+            "\nTheir game channels: {}",
+            channels
+                .iter()
+                .map(|channel| format!("<#{}>", channel.0))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    let attachments = if attachment_urls.is_empty() {
+        "".to_string()
+    } else {
+        format!("\nAttachments:\n{}", attachment_urls.join("\n"))
+    };
+    format!(
+        ":rotating_light: {}:\n>>> {}\n{}{}",
+        reporter, text, channels, attachments
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn MODERATION_DELETION_NOTIFICATION(
+    discord_id: u64,
+    channel_id: u64,
+    reason: &str,
+    content: &str,
+) -> String {
+    format!(
+        ":wastebasket: I removed a message by <@{}> in <#{}> because it contained a {}:\n>>> {}",
+        discord_id, channel_id, reason, content
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn ROLE_ASSIGNMENT_FAILED_NOTIFICATION(
+    discord_id: u64,
+    role_id: u64,
+    num_attempts: u32,
+    error: &str,
+) -> String {
+    format!(
+        "I tried {} times to give <@{}> the role <@&{}>, but it keeps failing. \
+         I gave up for now, the next Discord sync will try again. \
+         Please check my permissions and the position of my role.\n\
+         The latest error was:\n```{}```",
+        num_attempts, discord_id, role_id, error
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn CHANNEL_DELETION_REFUSED_NOTIFICATION(channel_id: u64, reason: &str) -> String {
+    format!(
+        "I was about to delete the channel <#{}>, but I didn't because {}. \
+         Please check whether this channel is still in use and clean it up by hand. \
+         I won't touch it in the meantime.",
+        channel_id, reason
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn ROLE_DELETION_REFUSED_NOTIFICATION(role_id: u64, reason: &str) -> String {
+    format!(
+        "I was about to delete the role <@&{}>, but I didn't because {}. \
+         Please check whether this role is still in use and clean it up by hand. \
+         I won't touch it in the meantime.",
+        role_id, reason
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn CREATION_BUDGET_EXCEEDED_NOTIFICATION(num_channels: u64, num_roles: u64) -> String {
+    format!(
+        "The last Discord sync wanted to create {} more channels and {} more roles \
+         than a single sync is allowed to. If this is expected, approve it with \
+         `approve creation` and the next sync will create them. Otherwise, please \
+         check the Meetup data for duplicate or broken events.",
+        num_channels, num_roles
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn GUILD_LIMIT_WARNING_NOTIFICATION(
+    num_channels: u64,
+    channel_limit: u64,
+    num_roles: u64,
+    role_limit: u64,
+) -> String {
+    format!(
+        "This server is getting close to Discord's limits: it has {}/{} channels \
+         and {}/{} roles. Please clean up old channels and roles before I can't \
+         create new ones anymore.",
+        num_channels, channel_limit, num_roles, role_limit
+    )
+}
+
+pub const CREATION_APPROVED: &'static str =
+    "Got it, the next Discord sync may create as many channels and roles as it needs.";
+
+// (Meetup ID, Discord ID if linked)
+#[allow(non_snake_case)]
+pub fn CHANNEL_PROPOSAL(
+    series_name: &str,
+    next_event_time: chrono::DateTime<chrono::Utc>,
+    next_event_link: &str,
+    hosts: &[(u64, Option<u64>)],
+    approve_emoji: &str,
+    reject_emoji: &str,
+) -> String {
+    let hosts = if hosts.is_empty() {
+        "nobody yet".to_string()
+    } else {
+        hosts
+            .iter()
+            .map(|&(meetup_id, discord_id)| match discord_id {
+                Some(discord_id) => format!("<@{}>", discord_id),
+                None => format!("Meetup user {} (not linked)", meetup_id),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "There is a new game that wants a channel: **{}**\n\
+         Next session: {} ({})\n\
+         Hosted by: {}\n\
+         React with {} to create the channel with the next sync or with {} to never create it.",
+        series_name,
+        next_event_time.format("%A, %B %e at %H:%M UTC"),
+        next_event_link,
+        hosts,
+        approve_emoji,
+        reject_emoji
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn CHANNEL_PROPOSAL_APPROVED(organizer_id: u64, series_name: &str) -> String {
+    format!(
+        "<@{}> approved **{}**, the next Discord sync will create its channel.",
+        organizer_id, series_name
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn CHANNEL_PROPOSAL_REJECTED(organizer_id: u64, series_name: &str) -> String {
+    format!(
+        "<@{}> rejected **{}**, I won't create a channel for it. \
+         React to the proposal again to change this.",
+        organizer_id, series_name
+    )
+}
+
+pub const ORGANIZER_TOKEN_INVALID_NOTIFICATION: &'static str =
+    "I just restarted and could not verify the organizer's Meetup token. \
+     Syncing with Meetup will not work until an organizer logs in again at \
+     https://bot.swissrpg.ch/authorize";
+
+pub const MEETUP_API_DEGRADED_NOTIFICATION: &'static str =
+    "The Meetup API keeps failing, so I paused syncing with Meetup for now. \
+     Discord channels will be kept in sync with the last known Meetup data \
+     and I will automatically resume once Meetup is reachable again.";
+
+pub const MEETUP_API_RECOVERED_NOTIFICATION: &'static str =
+    "The Meetup API is reachable again and I resumed syncing with Meetup.";
+
+// ** Reports **
+
+#[allow(non_snake_case)]
+pub fn REPORT_TOO_LONG(max_chars: usize) -> String {
+    format!(
+        "Sorry, your report is too long. Please keep it under {} characters, \
+         you can always send a second one.",
+        max_chars
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn REPORT_SENT(anonymous: bool) -> String {
+    if anonymous {
+        "Thank you, I passed your report on to the organizers without your name. \
+         Since they can't get back to you, please include everything they need to know."
+            .to_string()
+    } else {
+        "Thank you, I passed your report on to the organizers. \
+         They might get in touch with you if they have questions."
+            .to_string()
+    }
+}
+
+// ** Feature flags **
+
+pub const FEATURES_TITLE: &'static str = "Features";
+
+#[allow(non_snake_case)]
+pub fn UNKNOWN_FEATURE(feature_name: &str) -> String {
+    format!("I don't know a feature called \"{}\"", feature_name)
+}
+
+#[allow(non_snake_case)]
+pub fn FEATURE_TOGGLED(feature_name: &str, enabled: bool) -> String {
+    format!(
+        "The feature `{}` is now {}",
+        feature_name,
+        if enabled { "enabled" } else { "disabled" }
+    )
+}
+
+// ** Link moderation **
+
+#[allow(non_snake_case)]
+pub fn BLOCKED_LINK_PATTERN_TOO_LONG(max_chars: usize) -> String {
+    format!("Please keep the pattern under {} characters", max_chars)
+}
+
+#[allow(non_snake_case)]
+pub fn BLOCKED_LINK_PATTERN_TOGGLED(pattern: &str, block: bool, changed: bool) -> String {
+    match (block, changed) {
+        (true, true) => format!(
+            "Links matching `{}` will be removed from game channels \
+             (if the `link_moderation` feature is enabled)",
+            pattern
+        ),
+        (true, false) => format!("Links matching `{}` are already blocked", pattern),
+        (false, true) => format!("Links matching `{}` are no longer blocked", pattern),
+        (false, false) => format!("Links matching `{}` weren't blocked", pattern),
+    }
+}
+
+pub const BLOCKED_LINK_PATTERNS_TITLE: &'static str =
+    "Blocked link patterns (Discord invites are always blocked)";
+
+pub const NO_BLOCKED_LINK_PATTERNS: &'static str =
+    "There are no blocked link patterns. Only Discord invites are blocked.";
+
+#[allow(non_snake_case)]
+pub fn MODERATION_DELETION_NOTE(channel_id: u64, reason: &str) -> String {
+    format!(
+        "I removed your message in <#{}> because it contained a {}. \
+         If you think this was a mistake, please talk to the hosts of the game.",
+        channel_id, reason
+    )
+}
+
+// ** Quick commands **
+
+#[allow(non_snake_case)]
+pub fn SESSION_MESSAGE(
+    session_number: Option<u64>,
+    event_name: &str,
+    event_time: chrono::DateTime<chrono::Utc>,
+    event_link: &str,
+    event_location: &crate::event_location::Location,
+    schedule_emoji: &str,
+    join_emoji: Option<&str>,
+    remove_emoji: &str,
+    latest_recap_link: Option<&str>,
+) -> String {
+    let join = match join_emoji {
+        Some(join_emoji) => format!(
+            " or with {} to ask the hosts to join the next session",
+            join_emoji
+        ),
+        None => String::new(),
+    };
+    let session = match session_number {
+        Some(number) => format!(" (Session {})", number),
+        None => String::new(),
+    };
+    let recap = match latest_recap_link {
+        Some(link) => format!("\nCatch up on the last session: {}", link),
+        None => String::new(),
+    };
+    format!(
+        "**Next session{}:** {}\n{}\n{}\n{}\n\
+         React to this message with {} to get the schedule of this channel{}.\n\
+         Hosts can react with {} to a message to remove its author from this channel.{}",
+        session,
+        event_name,
+        event_time.format("%A, %B %e at %H:%M UTC"),
+        event_link,
+        EVENT_LOCATION(event_location)
+            .map(|location| format!("{}\n", location))
+            .unwrap_or_default(),
+        schedule_emoji,
+        join,
+        remove_emoji,
+        recap
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SESSION_MILESTONE_ANNOUNCEMENT(num_sessions: u64) -> String {
+    format!(
+        ":tada: This campaign just completed its **{}th session**! \
+         Congratulations to everyone at the table, here's to the next {}!",
+        num_sessions, num_sessions
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn QUICK_COMMAND_NO_UPCOMING_SESSIONS(channel_id: u64) -> String {
+    format!("There are no upcoming sessions in <#{}>", channel_id)
+}
+
+// Embed titles can't mention channels, so the channel is the first line
+#[allow(non_snake_case)]
+pub fn QUICK_COMMAND_SCHEDULE_CHANNEL(channel_id: u64) -> String {
+    format!("In <#{}>:", channel_id)
+}
+
+pub const QUICK_COMMAND_SCHEDULE_TITLE: &'static str = "Upcoming sessions";
+
+#[allow(non_snake_case)]
+pub fn QUICK_COMMAND_SCHEDULE_LINE(
+    event_name: &str,
+    event_time: chrono::DateTime<chrono::Utc>,
+    event_link: &str,
+) -> String {
+    format!(
+        "\u{2022} {}: **{}** (<{}>)",
+        event_time.format("%A, %B %e at %H:%M UTC"),
+        event_name,
+        event_link
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn QUICK_COMMAND_JOIN_REQUEST(discord_id: u64, hosts_mention: &str) -> String {
+    format!(
+        "{}: <@{}> would like to join the next session",
+        hosts_mention, discord_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn RECAP_TOO_LONG(max_chars: usize) -> String {
+    format!(
+        "This recap is too long, please keep it under {} characters",
+        max_chars
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn RECAP_STORED(session_date: chrono::NaiveDate) -> String {
+    format!(
+        "Saved the recap of the session on {}. Everyone can read up on the campaign so far \
+         with the \"recaps\" command.",
+        session_date.format("%A, %B %e")
+    )
+}
+
+pub const RECAPS_TITLE: &'static str = "Session recaps";
+
+pub const NO_RECAPS: &'static str = "There are no recaps for this channel yet";
+
+#[allow(non_snake_case)]
+pub fn RECAP_LINE(session_date: &str, recap: &crate::discord_recaps::Recap) -> String {
+    const PREVIEW_CHARS: usize = 80;
+    let mut preview: String = recap
+        .text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(PREVIEW_CHARS)
+        .collect();
+    if recap.text.chars().count() > PREVIEW_CHARS {
+        preview.push_str("...");
+    }
+    format!(
+        "**{}** by <@{}>: {} ([full recap]({}))",
+        session_date,
+        recap.discord_user,
+        preview,
+        recap.link()
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SAFETY_FIELD_TOO_LONG(max_chars: usize) -> String {
+    format!("Please keep this under {} characters", max_chars)
+}
+
+#[allow(non_snake_case)]
+pub fn SAFETY_FIELD_SET(field: crate::discord_safety::Field, is_set: bool) -> String {
+    if is_set {
+        format!(
+            "Updated the {} of this game. New players get them when they join.",
+            field.name()
+        )
+    } else {
+        format!("Removed the {} of this game", field.name())
+    }
+}
+
+pub const NO_SAFETY_INFO: &'static str =
+    "The hosts haven't declared any lines, veils or safety tools for this game yet";
+
+fn safety_sections(safety_info: &crate::discord_safety::SafetyInfo) -> String {
+    let mut sections = vec![];
+    if let Some(lines) = &safety_info.lines {
+        sections.push(format!(
+            "**Lines** (this doesn't happen in the game): {}",
+            lines
+        ));
+    }
+    if let Some(veils) = &safety_info.veils {
+        sections.push(format!(
+            "**Veils** (this only happens off-screen): {}",
+            veils
+        ));
+    }
+    if let Some(tools) = &safety_info.tools {
+        sections.push(format!("**Safety tools:** {}", tools));
+    }
+    sections.join("\n")
+}
+
+#[allow(non_snake_case)]
+pub fn SAFETY_MESSAGE(safety_info: &crate::discord_safety::SafetyInfo) -> String {
+    format!(
+        ":shield: **Safety at this table**\n{}\n\
+         If anything makes you uncomfortable, talk to the hosts or an organizer at any time.",
+        safety_sections(safety_info)
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SAFETY_NOTE(channel_id: u64, safety_info: &crate::discord_safety::SafetyInfo) -> String {
+    format!(
+        "Welcome to <#{}>! Before you start playing, here is how the hosts keep the game \
+         fun for everyone:\n{}\n\
+         If anything makes you uncomfortable, talk to the hosts or an organizer at any time.",
+        channel_id,
+        safety_sections(safety_info)
+    )
+}
+
+pub const CHARACTER_NOT_A_PLAYER: &'static str =
+    "Only the players of this channel can register a character here";
+
+pub const CHARACTER_INVALID_SHEET_URL: &'static str =
+    "That doesn't look like a link to a character sheet. It should start with https://";
+
+#[allow(non_snake_case)]
+pub fn CHARACTER_NAME_TOO_LONG(max_chars: usize) -> String {
+    format!(
+        "That name is a bit long, please keep it under {} characters",
+        max_chars
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn CHARACTER_SET(name: &str) -> String {
+    format!(
+        "Welcome to the party, {}! Everyone can see your character with the \"party\" command.",
+        name
+    )
+}
+
+pub const CHARACTER_REMOVED: &'static str = "I removed your character from this channel";
+
+pub const NO_CHARACTER: &'static str = "You don't have a character in this channel";
+
+pub const PARTY_TITLE: &'static str = "The party";
+
+pub const PARTY_EMPTY: &'static str = "There is nobody in this channel yet";
+
+#[allow(non_snake_case)]
+pub fn PARTY_LINE(
+    discord_id: u64,
+    is_host: bool,
+    character: Option<&crate::discord_characters::Character>,
+) -> String {
+    let host = if is_host { " (host)" } else { "" };
+    match character {
+        Some(character) => format!(
+            "<@{}>{}: [{}]({})",
+            discord_id, host, character.name, character.sheet_url
+        ),
+        None => format!("<@{}>{}: no character yet", discord_id, host),
+    }
+}
+
+pub const ABSENT_NOT_A_PLAYER: &'static str =
+    "Only the players of this channel can say that they can't make it";
+
+pub const ABSENT_NO_UPCOMING_SESSION: &'static str =
+    "There is no upcoming session in this channel to be absent from";
+
+#[allow(non_snake_case)]
+pub fn ABSENCE_RECORDED(
+    discord_id: u64,
+    hosts_mention: &str,
+    session_time: chrono::DateTime<chrono::Utc>,
+    reason: Option<&str>,
+) -> String {
+    let reason = match reason {
+        Some(reason) => format!(" (\"{}\")", reason.trim()),
+        None => String::new(),
+    };
+    format!(
+        "{}: <@{}> can't make it to the next session on {}{}",
+        hosts_mention,
+        discord_id,
+        session_time.format("%A, %B %e"),
+        reason
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn QUICK_COMMAND_REMOVE_CONFIRMATION(discord_id: u64) -> String {
+    format!(
+        "Do you really want to remove <@{}> from this channel?",
+        discord_id
+    )
+}
+
+pub const QUICK_COMMAND_INVALID_EMOJI: &'static str =
+    "Please use a standard emoji, custom server emojis are not supported";
+
+#[allow(non_snake_case)]
+pub fn QUICK_COMMAND_EMOJI_SET(command_name: &str, emoji: &str) -> String {
+    format!(
+        "The `{}` quick command now uses {}. The session messages will be updated \
+         during the next sync.",
+        command_name, emoji
+    )
+}
+
+// ** Macros **
+
+#[allow(non_snake_case)]
+pub fn MACRO_INVALID_NAME(max_chars: usize) -> String {
+    format!(
+        "A macro name is a single word of up to {} letters, digits, \"-\" or \"_\", \
+         and can't be the name of one of my commands",
+        max_chars
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn MACRO_INVALID_TEXT(max_chars: usize) -> String {
+    format!(
+        "The text of a macro can have up to {} characters and can't ping the whole server",
+        max_chars
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn MACRO_LIMIT_REACHED(max_macros: usize) -> String {
+    format!(
+        "This channel already has {} macros, please remove one first",
+        max_macros
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn MACRO_ADDED(name: &str) -> String {
+    format!(
+        "Got it! Everyone in this channel can now use \"{name}\" to see the text. \
+         Use \"macro remove {name}\" to get rid of it.",
+        name = name
+    )
+}
+
+pub const MACRO_REMOVED: &'static str = "I removed the macro";
+
+#[allow(non_snake_case)]
+pub fn MACRO_NOT_FOUND(name: &str) -> String {
+    format!("There is no macro called \"{}\" in this channel", name)
+}
+
+pub const MACROS_NONE: &'static str =
+    "This channel has no macros yet. Hosts can add one with \"macro add <name> <text>\".";
+
+#[allow(non_snake_case)]
+pub fn MACROS_LIST(names: &[String]) -> String {
+    let names: Vec<_> = names.iter().map(|name| format!("`{}`", name)).collect();
+    format!("Macros in this channel: {}", names.join(", "))
+}
+
+// ** Quarantine **
+
+#[allow(non_snake_case)]
+pub fn USER_QUARANTINED(discord_id: u64, num_channels: usize) -> String {
+    format!(
+        "<@{}> is now quarantined. I removed them from {} channel(s) and won't add them \
+         to any channel until they are released.",
+        discord_id, num_channels
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn USER_RELEASED(discord_id: u64) -> String {
+    format!(
+        "<@{}> is no longer quarantined. They will get their channels back with the next sync.",
+        discord_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn USER_NOT_QUARANTINED(discord_id: u64) -> String {
+    format!("<@{}> is not quarantined", discord_id)
+}
+
+#[allow(non_snake_case)]
+pub fn CHANNEL_ADD_USER_QUARANTINED(discord_id: u64) -> String {
+    format!(
+        "<@{}> is currently quarantined and can't be added to channels",
+        discord_id
+    )
+}
+
+pub const USER_QUARANTINED_REFUSED: &'static str =
+    "Sorry, you can't do that right now. Please get in touch with an organizer.";
+
+// ** Ignore list **
+
+#[allow(non_snake_case)]
+pub fn USER_IGNORED(discord_id: u64, ignored: bool, changed: bool) -> String {
+    match (ignored, changed) {
+        (true, true) => format!(
+            "I'll ignore <@{}> from now on: I won't answer them, add them to channels or \
+             send them reminders.",
+            discord_id
+        ),
+        (true, false) => format!("I'm already ignoring <@{}>", discord_id),
+        (false, true) => format!(
+            "I'm no longer ignoring <@{}>. They will get their channels with the next sync.",
+            discord_id
+        ),
+        (false, false) => format!("I'm not ignoring <@{}>", discord_id),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn IGNORED_USERS(discord_ids: &[u64]) -> String {
+    if discord_ids.is_empty() {
+        return "I'm not ignoring anybody, except for other bots".to_string();
+    }
+    let mentions: Vec<_> = discord_ids.iter().map(|id| format!("<@{}>", id)).collect();
+    format!(
+        "I'm ignoring other bots and these users: {}",
+        mentions.join(", ")
+    )
+}
+
+// ** Invites **
+
+#[allow(non_snake_case)]
+pub fn INVITE_LABEL_INVALID() -> String {
+    format!(
+        "The label of an invite can be at most {} characters long",
+        crate::discord_invites::MAX_LABEL_CHARS
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn INVITE_CREATED(label: &str, code: &str) -> String {
+    format!(
+        "Here is the invite for `{}`: https://discord.gg/{}\n\
+         I'll count everybody who joins through it, see ***invite stats***.",
+        label, code
+    )
+}
+
+pub const NO_INVITES: &'static str =
+    "There are no tracked invites yet. Create one with ***invite create <label>***";
+
+pub const INVITE_STATS_TITLE: &'static str = "Members who joined through each invite label";
+
+#[allow(non_snake_case)]
+pub fn INVITE_STATS_LINE(label: &str, num_joins: u64) -> String {
+    format!("`{}`: {}", label, num_joins)
+}
+
+#[allow(non_snake_case)]
+pub fn INVITE_STATS_UNATTRIBUTED(num_joins: u64) -> String {
+    format!(
+        "Other invites, or joins that couldn't be attributed: {}",
+        num_joins
+    )
+}
+
+// ** Usage report **
+
+#[allow(non_snake_case)]
+pub fn USAGE_REPORT_TITLE(num_days: i64) -> String {
+    format!("Commands used in the last {} days", num_days)
+}
+
+#[allow(non_snake_case)]
+pub fn USAGE_REPORT_SUMMARY(num_uses: u64, num_users: usize) -> String {
+    format!(
+        "{} commands by {} different members in total",
+        num_uses, num_users
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn USAGE_REPORT_LINE(command: &str, num_uses: u64, num_users: usize) -> String {
+    format!(
+        "`{}`: {} time(s) by {} member(s)",
+        command, num_uses, num_users
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn USAGE_REPORT_UNUSED(commands: &[String]) -> String {
+    let commands: Vec<_> = commands
+        .iter()
+        .map(|command| format!("`{}`", command))
+        .collect();
+    format!("Not used at all: {}", commands.join(", "))
+}
+
+// ** Storage report **
+
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn STORAGE_REPORT_TITLE(num_keys: u64, estimated_bytes: u64) -> String {
+    format!(
+        "Redis holds {} keys using about {}",
+        num_keys,
+        human_bytes(estimated_bytes)
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn STORAGE_REPORT_LINE(
+    family: &str,
+    num_keys: u64,
+    estimated_bytes: u64,
+    num_without_ttl: u64,
+) -> String {
+    let without_ttl = if num_without_ttl > 0 {
+        format!(
+            " ({} without expiry, fixed by the next storage check)",
+            num_without_ttl
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        "`{}`: {} key(s), ~{}{}",
+        family,
+        num_keys,
+        human_bytes(estimated_bytes),
+        without_ttl
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn STORAGE_GROWTH_WARNING(num_keys_before: u64, num_keys: u64) -> String {
+    format!(
+        "\u{26A0} The number of keys in Redis grew from {} to {} within a week, more than \
+         expected. Use `storage report` to find out what grew.",
+        num_keys_before, num_keys
+    )
+}
+
+// ** Duplicate events **
+
+pub const NO_DUPLICATE_EVENTS: &'static str =
+    "There are no Meetup events that might be duplicates of existing series";
+
+pub const DUPLICATE_EVENTS_TITLE: &'static str =
+    "Meetup events that might be duplicates of existing series. Add `[campaign <event ID>]` to \
+     an event's description to add it to a series, or use `allow duplicate <event ID>` to give \
+     it a channel of its own.";
+
+#[allow(non_snake_case)]
+pub fn DUPLICATE_EVENT_LINE(
+    event_id: &str,
+    flagged_event: &crate::meetup_dedup::FlaggedEvent,
+    time: chrono::DateTime<chrono::Utc>,
+    candidates: &[String],
+) -> String {
+    format!(
+        "Event {} \"{}\" on {} (<{}>), like {}",
+        event_id,
+        flagged_event.name,
+        time.format("%Y-%m-%d %H:%M UTC"),
+        flagged_event.link,
+        candidates.join(", ")
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn DUPLICATE_ALLOWED(event_id: &str) -> String {
+    format!(
+        "Got it, the next Meetup sync gives event {} a series and channel of its own",
+        event_id
+    )
+}
+
+pub const DUPLICATE_NOT_FLAGGED: &'static str =
+    "That event isn't flagged as a possible duplicate. `list duplicate events` shows the ones \
+     that are.";
+
+// ** Host digest **
+
+pub const HOST_DIGEST_INTRO: &'static str =
+    "Good morning! Here is what's coming up in the games you host:";
+
+#[allow(non_snake_case)]
+pub fn HOST_DIGEST_SERIES_HEADER(channel_id: Option<u64>, next_event_name: &str) -> String {
+    match channel_id {
+        Some(channel_id) => format!("**<#{}>**", channel_id),
+        None => format!("**{}** (no channel yet)", next_event_name),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn HOST_DIGEST_SESSION_LINE(
+    event_name: &str,
+    event_time: chrono::DateTime<chrono::Utc>,
+    event_link: &str,
+    num_rsvps: usize,
+    num_unlinked: usize,
+) -> String {
+    let unlinked = if num_unlinked > 0 {
+        format!(", {} without a linked Discord account", num_unlinked)
+    } else {
+        String::new()
+    };
+    format!(
+        "\u{2022} {}: **{}** (<{}>) \u{2014} {} RSVP(s){}",
+        event_time.format("%A, %B %e at %H:%M UTC"),
+        event_name,
+        event_link,
+        num_rsvps,
+        unlinked
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn HOST_DIGEST_CHANNEL_EXPIRATION(expiration_time: chrono::DateTime<chrono::Utc>) -> String {
+    format!(
+        "The channel expires on {}.",
+        expiration_time.format("%A, %B %e at %H:%M UTC")
+    )
+}
+
+pub const HOST_DIGEST_OUTRO: &'static str =
+    "Don't want these weekly messages? Reply with `digest off`.";
+
+#[allow(non_snake_case)]
+pub fn HOST_DIGEST_TOGGLED(enabled: bool) -> String {
+    if enabled {
+        "Alright, I'll send you a digest of your upcoming games every Monday.".to_string()
+    } else {
+        "Alright, I won't send you the weekly digest anymore. Use `digest on` to get it \
+         back."
+            .to_string()
+    }
+}
+
+// ** Host orientation **
+
+#[allow(non_snake_case)]
+pub fn HOST_ORIENTATION(channel_id: u64, expiration_grace_hours: i64, bot_id: u64) -> String {
+    format!(
+        "Hi! I just created <#{channel_id}> for the game you host. Welcome aboard! A few \
+         things that help running it:\n\
+         \u{2022} **Players**: everybody who RSVPs on Meetup gets access to the channel \
+         automatically, once they linked their Meetup account. You can add players yourself \
+         with `<@{bot_id}> add @user` in the channel, and remove them with \
+         `<@{bot_id}> remove @user`.\n\
+         \u{2022} **Expiration**: the channel stays open as long as there are upcoming \
+         sessions on Meetup, and closes {expiration_grace_hours} hours after the last one. \
+         I'll remind you before that happens, so you can schedule the next session. Use \
+         `<@{bot_id}> close channel` to close it early.\n\
+         \u{2022} **Host commands**: `<@{bot_id}> add host @user` and \
+         `<@{bot_id}> add cohost @user` to share the work, `<@{bot_id}> settings` for the \
+         channel settings, `<@{bot_id}> safety set lines|veils|tools <text>` for the safety \
+         tools, `<@{bot_id}> macro add <name> <text>` for macros and \
+         `<@{bot_id}> recap <text>` to keep a recap of the last session.\n\
+         Have fun!",
+        channel_id = channel_id,
+        expiration_grace_hours = expiration_grace_hours,
+        bot_id = bot_id,
+    )
+}
+
+// ** Presence **
+
+#[allow(non_snake_case)]
+pub fn NEXT_EVENT_STATUS(name: &str, time: chrono::DateTime<chrono::Utc>) -> String {
+    format!("{} \u{2013} {}", name, time.format("%a %H:%M UTC"))
+}
+
+// ** Community digest **
+
+pub const COMMUNITY_DIGEST_DEFAULT_TEMPLATE: &'static str = "**What's new this week**
+
+__Newly opened games__
+{new_games}
+
+__New campaign channels__
+{new_channels}
+
+__One-shots this week__
+{one_shots}";
+
+pub const COMMUNITY_DIGEST_NOTHING: &'static str = "Nothing this time";
+
+#[allow(non_snake_case)]
+pub fn COMMUNITY_DIGEST_GAME_LINE(
+    event_name: &str,
+    event_time: chrono::DateTime<chrono::Utc>,
+    event_link: &str,
+    free_spots: Option<u32>,
+    tags: &[String],
+) -> String {
+    let spots = match free_spots {
+        Some(0) => " \u{2014} full".to_string(),
+        Some(free_spots) => format!(" \u{2014} {} spot(s) left", free_spots),
+        None => String::new(),
+    };
+    let tags = if tags.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " {}",
+            tags.iter()
+                .map(|tag| format!("`{}`", tag))
+                .collect::<Vec<_>>()
+                .join(" ")
+        )
+    };
+    format!(
+        "\u{2022} {}: **{}** (<{}>){}{}",
+        event_time.format("%A, %B %e at %H:%M UTC"),
+        event_name,
+        event_link,
+        spots,
+        tags
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn COMMUNITY_DIGEST_CHANNEL_LINE(channel_id: u64) -> String {
+    format!("\u{2022} <#{}>", channel_id)
+}
+
+#[allow(non_snake_case)]
+pub fn COMMUNITY_DIGEST_CHANNEL_SET(channel_id: Option<u64>, weekday: &str, hour: u32) -> String {
+    match channel_id {
+        Some(channel_id) => format!(
+            "I will post the community digest in <#{}> every {} at {:02}:00 UTC.",
+            channel_id, weekday, hour
+        ),
+        None => "I won't post the community digest anymore.".to_string(),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn COMMUNITY_DIGEST_SCHEDULE_SET(weekday: &str, hour: u32) -> String {
+    format!(
+        "The community digest will be posted every {} at {:02}:00 UTC.",
+        weekday, hour
+    )
+}
+
+pub const COMMUNITY_DIGEST_INVALID_SCHEDULE: &'static str =
+    "Please specify a day of the week and an hour between 0 and 23 (UTC), e.g. `friday 17`.";
+
+pub const COMMUNITY_DIGEST_TEMPLATE_SET: &'static str =
+    "Got it, I will use this template for the community digest. The placeholders \
+     `{new_games}`, `{new_channels}` and `{one_shots}` are replaced with the respective lists.";
+
+pub const COMMUNITY_DIGEST_TEMPLATE_RESET: &'static str =
+    "The community digest uses the default template again.";
+
+// ** Cross-posting **
+
+pub const CROSSPOST_DEFAULT_TEMPLATE: &'static str =
+    "\u{1F3B2} **{name}** on {time} has {spots}! {tags}\n<{link}>";
+
+#[allow(non_snake_case)]
+pub fn CROSSPOST(
+    template: &str,
+    event_name: &str,
+    event_time: chrono::DateTime<chrono::Utc>,
+    event_link: &str,
+    free_spots: Option<u32>,
+    tags: &[String],
+) -> String {
+    let spots = match free_spots {
+        Some(1) => "1 free spot".to_string(),
+        Some(free_spots) => format!("{} free spots", free_spots),
+        None => "free spots".to_string(),
+    };
+    let tags = tags
+        .iter()
+        .map(|tag| format!("`{}`", tag))
+        .collect::<Vec<_>>()
+        .join(" ");
+    template
+        .replace("{name}", event_name)
+        .replace(
+            "{time}",
+            &event_time.format("%A, %B %e at %H:%M UTC").to_string(),
+        )
+        .replace("{link}", event_link)
+        .replace("{spots}", &spots)
+        .replace("{tags}", &tags)
+}
+
+pub const CROSSPOST_WEBHOOK_INVALID: &'static str =
+    "That doesn't look like a Discord webhook URL. You can create one in the settings \
+     of the channel that the games should be posted to, under \"Integrations\".";
+
+#[allow(non_snake_case)]
+pub fn CROSSPOST_WEBHOOK_SET(enabled: bool) -> String {
+    if enabled {
+        "Got it, I will cross-post games with free spots through this webhook. \
+         I deleted your message, since anybody with the URL can post through it."
+            .to_string()
+    } else {
+        "I removed my cross-posts and won't cross-post games anymore.".to_string()
+    }
+}
+
+pub const CROSSPOST_TEMPLATE_SET: &'static str =
+    "Got it, I will use this template for cross-posts. The placeholders `{name}`, \
+     `{time}`, `{link}`, `{spots}` and `{tags}` are replaced with the details of the game.";
+
+pub const CROSSPOST_TEMPLATE_RESET: &'static str = "Cross-posts use the default template again.";
+
+// ** Subscriptions **
+
+#[allow(non_snake_case)]
+pub fn SUBSCRIPTION_UNKNOWN_TAG(tag: &str, known_tags: &[&str]) -> String {
+    format!(
+        "I don't know the tag `{}`. You can subscribe to these tags: {}",
+        tag,
+        known_tags
+            .iter()
+            .map(|tag| format!("`{}`", tag))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SUBSCRIBED(tag: &str, was_new: bool) -> String {
+    if was_new {
+        format!(
+            "Alright, I'll let you know about new games tagged `{}`. Make sure you allow direct \
+             messages from server members.",
+            tag
+        )
+    } else {
+        format!("You are already subscribed to `{}`", tag)
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn UNSUBSCRIBED(tag: &str, was_subscribed: bool) -> String {
+    if was_subscribed {
+        format!("You won't hear about new games tagged `{}` anymore", tag)
+    } else {
+        format!("You are not subscribed to `{}`", tag)
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn SUBSCRIPTIONS_LIST(tags: &[String], known_tags: &[&str]) -> String {
+    let known_tags = known_tags
+        .iter()
+        .map(|tag| format!("`{}`", tag))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if tags.is_empty() {
+        format!(
+            "You are not subscribed to any tags. Available tags: {}",
+            known_tags
+        )
+    } else {
+        format!(
+            "You are subscribed to: {}\nAvailable tags: {}",
+            tags.iter()
+                .map(|tag| format!("`{}`", tag))
+                .collect::<Vec<_>>()
+                .join(", "),
+            known_tags
+        )
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn SUBSCRIPTION_ROLE_SET(tag: &str, role_id: Option<u64>) -> String {
+    match role_id {
+        Some(role_id) => format!(
+            "Subscribers of `{}` now get the <@&{}> role, which I will ping in the community \
+             digest channel for new games.",
+            tag, role_id
+        ),
+        None => format!("Subscribers of `{}` will be notified by DM again", tag),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn SUBSCRIPTION_ANNOUNCEMENT(
+    role_ids: &[u64],
+    event_name: &str,
+    event_time: chrono::DateTime<chrono::Utc>,
+    event_link: &str,
+) -> String {
+    format!(
+        "{} A new game was just published: **{}** on {}\n<{}>",
+        role_ids
+            .iter()
+            .map(|role_id| format!("<@&{}>", role_id))
+            .collect::<Vec<_>>()
+            .join(" "),
+        event_name,
+        event_time.format("%A, %B %e at %H:%M UTC"),
+        event_link
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SUBSCRIPTION_DM(
+    tags: &[String],
+    event_name: &str,
+    event_time: chrono::DateTime<chrono::Utc>,
+    event_link: &str,
+) -> String {
+    format!(
+        "A new game matching your subscriptions ({}) was just published: **{}** on {}\n<{}>\n\
+         Use `unsubscribe <tag>` on the server to stop these messages.",
+        tags.iter()
+            .map(|tag| format!("`{}`", tag))
+            .collect::<Vec<_>>()
+            .join(", "),
+        event_name,
+        event_time.format("%A, %B %e at %H:%M UTC"),
+        event_link
+    )
+}
+
+// ** Tag rules **
+
+pub const TAG_RULES_TITLE: &'static str =
+    "Tags of events whose title or description contain one of the keywords";
+
+#[allow(non_snake_case)]
+pub fn TAG_RULE_LINE(tag: &str, keywords: &[String]) -> String {
+    format!("\u{2022} `{}`: {}", tag, keywords.join(", "))
+}
+
+#[allow(non_snake_case)]
+pub fn TAG_RULE_SET(tag: &str, keywords: &[String]) -> String {
+    if keywords.is_empty() {
+        format!("The tag `{}` is now disabled", tag)
+    } else {
+        format!(
+            "Events mentioning one of {} will be tagged `{}` from the next sync on",
+            keywords
+                .iter()
+                .map(|keyword| format!("\"{}\"", keyword))
+                .collect::<Vec<_>>()
+                .join(", "),
+            tag
+        )
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn TAG_RULE_RESET(tag: &str) -> String {
+    format!(
+        "The tag `{}` uses its default keywords again (or was removed if it is not a built-in \
+         tag)",
+        tag
+    )
+}
+
+// ** Host verification **
+
+#[allow(non_snake_case)]
+pub fn HOST_VERIFICATION_MISMATCH(discord_id: u64, channel_id: u64, refused: bool) -> String {
+    format!(
+        "<@{}> has the host role of <#{}>, but is neither a Meetup host of its events nor \
+         was added with the \"add host\" command. {}",
+        discord_id,
+        channel_id,
+        if refused {
+            "I refused their host commands."
+        } else {
+            "I still accepted their host commands."
+        }
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn HOST_VERIFICATION_MODE_SET(mode_name: &str) -> String {
+    let description = match mode_name {
+        "off" => "I won't check the host role against Meetup anymore",
+        "alert" => "I will let the organizers know about hosts that are not hosts on Meetup",
+        _ => "I will refuse host commands from hosts that are not hosts on Meetup",
+    };
+    format!("Host verification is now `{}`: {}", mode_name, description)
+}
+
+// ** Permission check **
+
+pub const PERMISSION_CHECK_TITLE: &'static str = "Permission check";
+
+pub const PERMISSION_CHECK_MANAGE_CHANNELS: &'static str = "I can manage channels";
+
+pub const PERMISSION_CHECK_MANAGE_ROLES: &'static str = "I can manage roles";
+
+#[allow(non_snake_case)]
+pub fn PERMISSION_CHECK_CATEGORY(category_id: u64) -> String {
+    format!(
+        "I can manage channels and permissions in the category <#{}>",
+        category_id
+    )
+}
+
+pub const PERMISSION_CHECK_REACTIONS: &'static str =
+    "I can add reactions and read the message history in this channel";
+
+#[allow(non_snake_case)]
+pub fn PERMISSION_CHECK_ROLE_HIERARCHY(roles_above_bot: &[String]) -> String {
+    if roles_above_bot.is_empty() {
+        "My role is above all the roles I manage".to_string()
+    } else {
+        format!(
+            "My role is above all the roles I manage (not the case for: {})",
+            roles_above_bot.join(", ")
+        )
+    }
+}
+
+pub const PERMISSION_CHECK_DIRECT_MESSAGES: &'static str = "I can send you direct messages";
+
+pub const PERMISSION_FIX_GUILD_PERMISSION: &'static str =
+    "Grant my role this permission in the server settings under \"Roles\"";
+
+pub const PERMISSION_FIX_CATEGORY: &'static str =
+    "Allow my role \"Manage Channels\" and \"Manage Permissions\" in the category's permissions";
+
+pub const PERMISSION_FIX_REACTIONS: &'static str =
+    "Allow my role \"Add Reactions\" and \"Read Message History\" in this channel";
+
+pub const PERMISSION_FIX_ROLE_HIERARCHY: &'static str =
+    "Drag my role above these roles in the server settings under \"Roles\"";
+
+pub const PERMISSION_FIX_DIRECT_MESSAGES: &'static str =
+    "Make sure that direct messages from server members are allowed in your privacy settings";
+
+// ** Sync statistics **
+
+pub const NO_SYNC_STATS: &'static str = "I haven't recorded any Discord syncs yet";
+
+#[allow(non_snake_case)]
+pub fn SYNC_STATS_TITLE(num_runs: usize, average_num_series: u64) -> String {
+    format!(
+        "Discord sync over the last {} runs ({} series per run on average)",
+        num_runs, average_num_series
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SYNC_STATS_LINE(step: &str, summary: &crate::sync_stats::DurationSummary) -> String {
+    format!(
+        "`{}`: avg {} ms, median {} ms, p95 {} ms, max {} ms, {} API calls on average",
+        step,
+        summary.average_ms,
+        summary.median_ms,
+        summary.p95_ms,
+        summary.max_ms,
+        summary.average_api_calls
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SYNC_STATS_BUDGET_LINE(summary: &crate::sync_stats::BudgetSummary) -> String {
+    format!(
+        "API budget: {}% used on average, {}% at most. Deferred {} steps and {} series.",
+        summary.average_percent,
+        summary.max_percent,
+        summary.deferred_steps,
+        summary.deferred_series
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SYNC_STATS_QUEUE_LINE(summary: &crate::sync_stats::QueueSummary) -> String {
+    format!(
+        "Waiting for commands: {} API calls waited {} ms on average, {} ms at most. \
+         Up to {} calls were waiting at once.",
+        summary.waited_calls,
+        summary.average_wait_ms,
+        summary.peak_wait_ms,
+        summary.peak_queue_depth
+    )
+}
+
+// ** Sync preview **
+
+#[allow(non_snake_case)]
+pub fn SYNC_PREVIEW(series_id: &str, preview: &crate::discord_sync_preview::Preview) -> String {
+    use crate::discord_sync_preview::{Change, Preview, RoleKind, Skipped};
+    // Discord doesn't allow more than 2000 characters per message
+    const MAX_DIFF_CHARS: usize = 1700;
+    let changes = match preview {
+        Preview::Skipped(skipped) => {
+            let reason = match skipped {
+                Skipped::Paused => "its sync is paused".to_string(),
+                Skipped::NoUpcomingEvents => "it has no upcoming events".to_string(),
+                Skipped::InvalidName(event_name) => format!(
+                    "I can't make a channel name out of the event \"{}\"",
+                    event_name
+                ),
+                Skipped::AwaitingApproval(decision) => {
+                    format!("its channel proposal is {}", decision.name())
+                }
+                Skipped::WouldPropose => {
+                    "its channel needs an organizer's approval, so I'd only post a proposal"
+                        .to_string()
+                }
+            };
+            return format!(
+                "The next sync would leave `{}` alone, since {}.",
+                series_id, reason
+            );
+        }
+        Preview::Changes(changes) => changes,
+    };
+    if changes.is_empty() {
+        return format!(
+            "The next sync would not change anything for `{}`.",
+            series_id
+        );
+    }
+    let lines: Vec<String> = changes
+        .iter()
+        .flat_map(|change| match change {
+            Change::CreateChannel(name) => vec![format!("+ channel #{}", name)],
+            Change::CreateRole(name) => vec![format!("+ role @{}", name)],
+            Change::AddRole {
+                discord_id,
+                tag,
+                role,
+            } => {
+                let role = match role {
+                    RoleKind::Player => "player",
+                    RoleKind::Host => "host",
+                    RoleKind::GameMaster => "game master",
+                };
+                vec![format!("+ {} ({}) gets the {} role", tag, discord_id, role)]
+            }
+            Change::GrantAccess {
+                discord_id,
+                tag,
+                as_host,
+            } => {
+                let role = if *as_host { "host" } else { "player" };
+                vec![format!(
+                    "+ {} ({}) gets access as a {}",
+                    tag, discord_id, role
+                )]
+            }
+            Change::Topic { old, new } => {
+                let mut lines = vec![];
+                if let Some(old) = old {
+                    lines.push(format!("- topic: {}", old.replace('\n', " ")));
+                }
+                lines.push(format!("+ topic: {}", new.replace('\n', " ")));
+                lines
+            }
+            Change::Category { old, new } => {
+                let mut lines = vec![];
+                if let Some(old) = old {
+                    lines.push(format!("- category: {}", old));
+                }
+                lines.push(format!("+ category: {}", new));
+                lines
+            }
+        })
+        .collect();
+    let mut diff = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if diff.len() + line.len() > MAX_DIFF_CHARS {
+            diff.push_str(&format!("… and {} more\n", lines.len() - i));
+            break;
+        }
+        diff.push_str(line);
+        diff.push('\n');
+    }
+    format!(
+        "The next sync would make these changes for `{}`:\n```diff\n{}```",
+        series_id, diff
+    )
+}
+
+// ** Role cleanup **
+
+pub const ROLE_CLEANUP_NONE: &'static str =
+    "There are no roles to clean up, all of them belong to a channel of a running series";
+
+#[allow(non_snake_case)]
+pub fn ROLE_CLEANUP_TITLE(num_roles: usize) -> String {
+    format!(
+        "{} roles belong to channels that are gone or to series that ended",
+        num_roles
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn ROLE_CLEANUP_LINE(candidate: &crate::discord_role_cleanup::Candidate) -> String {
+    let kind = if candidate.is_host_role {
+        "host role"
+    } else {
+        "player role"
+    };
+    let reason = match candidate.reason {
+        crate::discord_role_cleanup::Reason::ChannelGone => "the channel is gone".to_string(),
+        crate::discord_role_cleanup::Reason::SeriesEnded(last_event_time) => format!(
+            "the last event was on {}",
+            last_event_time.format("%B %e, %Y")
+        ),
+    };
+    format!("`{}` ({}): {}", candidate.name, kind, reason)
+}
+
+#[allow(non_snake_case)]
+pub fn ROLE_CLEANUP_PROMPT(num_roles: usize) -> String {
+    format!(
+        "Should I delete these {} roles? Members of channels that still exist lose their access \
+         to them.",
+        num_roles
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn ROLE_CLEANUP_DONE(num_deleted: usize, num_failed: usize) -> String {
+    if num_failed == 0 {
+        format!("Deleted {} roles", num_deleted)
+    } else {
+        format!(
+            "Deleted {} roles, {} roles could not be deleted",
+            num_deleted, num_failed
+        )
+    }
+}
+
+// ** Role limit **
+
+#[allow(non_snake_case)]
+pub fn ROLE_USAGE(
+    usage: &crate::discord_role_limit::RoleUsage,
+    policy: crate::discord_role_limit::PruningPolicy,
+) -> String {
+    use crate::discord_role_limit::PruningPolicy;
+    let pruning = match policy {
+        PruningPolicy::Off => "Role pruning is off.",
+        PruningPolicy::NearLimit => {
+            "Their roles are replaced with member permissions once the server gets close to the \
+             limit."
+        }
+        PruningPolicy::Always => {
+            "Their roles are replaced with member permissions after each sync."
+        }
+    };
+    format!(
+        "This server has {}/{} roles, {} of them belong to my channels. {} archived channels \
+         still have their roles. {}",
+        usage.num_roles,
+        usage.limit(),
+        usage.num_managed_roles,
+        usage.num_prunable_channels,
+        pruning
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn ROLE_LIMIT_WARNING_NOTIFICATION(
+    usage: &crate::discord_role_limit::RoleUsage,
+    policy: crate::discord_role_limit::PruningPolicy,
+) -> String {
+    let pruning_hint = if policy == crate::discord_role_limit::PruningPolicy::Off
+        && usage.num_prunable_channels > 0
+    {
+        format!(
+            " {} archived channels still have their roles, `config set role_pruning near_limit` \
+             replaces them with member permissions.",
+            usage.num_prunable_channels
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        "This server has {}/{} roles, {} of them belong to my channels. Once the limit is \
+         reached I can't create channels for new games anymore. `cleanup roles` deletes the \
+         roles of channels that are gone and of series that ended.{}",
+        usage.num_roles,
+        usage.limit(),
+        usage.num_managed_roles,
+        pruning_hint
+    )
+}
+
+// ** Runtime config **
+
+// Channels are shown as mentions, "none" as is
+fn config_value(key: crate::guild_config::ConfigKey, value: &str) -> String {
+    if key.is_channel() && value != "none" {
+        format!("<#{}>", value)
+    } else {
+        format!("`{}`", value)
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn CONFIG_UNKNOWN_KEY(name: &str) -> String {
+    format!(
+        "There is no config key called `{}`. The keys are: {}",
+        name,
+        crate::guild_config::ConfigKey::ALL
+            .iter()
+            .map(|key| format!("`{}`", key.name()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+pub const CONFIG_TITLE: &'static str = "Settings";
+
+#[allow(non_snake_case)]
+pub fn CONFIG_VALUE_LINE(key: crate::guild_config::ConfigKey, value: Option<&str>) -> String {
+    let default = key.default_value().unwrap_or_else(|| "none".to_string());
+    match value {
+        Some(value) => format!(
+            "`{}`: {} (default: {})",
+            key.name(),
+            config_value(key, value),
+            config_value(key, &default)
+        ),
+        None => format!(
+            "`{}`: {} (default)",
+            key.name(),
+            config_value(key, &default)
+        ),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn CONFIG_INVALID_VALUE(key: crate::guild_config::ConfigKey) -> String {
+    use crate::guild_config::ConfigKey;
+    let expected = match key {
+        ConfigKey::SyncInterval => format!(
+            "a number of minutes between {} and {}",
+            crate::guild_config::MIN_SYNC_INTERVAL_MINUTES,
+            crate::guild_config::MAX_SYNC_INTERVAL_MINUTES
+        ),
+        ConfigKey::ExpirationGrace => format!(
+            "a number of hours between 0 and {}",
+            crate::guild_config::MAX_EXPIRATION_GRACE_HOURS
+        ),
+        ConfigKey::OneShotCategory | ConfigKey::CampaignCategory => {
+            "the ID of a category of this server or `none`".to_string()
+        }
+        ConfigKey::StaffChannel => "a text channel of this server or `none`".to_string(),
+        ConfigKey::EventSource => {
+            "one of `meetup`, `google_calendar`, `warhorn` or `eventbrite`".to_string()
+        }
+        ConfigKey::WarhornEvent => {
+            "the slug of a Warhorn event (the part after `/events/` in its URL)".to_string()
+        }
+        ConfigKey::MeetupNicknames => "one of `off`, `first_name` or `suffix`".to_string(),
+        ConfigKey::MembershipGrace => format!(
+            "a number of days between 0 and {} or `off`",
+            crate::guild_config::MAX_MEMBERSHIP_GRACE_DAYS
+        ),
+        ConfigKey::DuesGroup => format!(
+            "one of {} or `none`",
+            crate::meetup_api::URLNAMES
+                .iter()
+                .map(|urlname| format!("`{}`", urlname))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        ConfigKey::RolePruning => "one of `off`, `near_limit` or `always`".to_string(),
+        ConfigKey::ChannelAccess => "either `roles` or `overwrites`".to_string(),
+        ConfigKey::GameMasterRole => "a role of this server or `none`".to_string(),
+        ConfigKey::NextEventStatus => "either `on` or `off`".to_string(),
+        ConfigKey::ErrorReportInterval => format!(
+            "a number of minutes between 0 and {} or `off`",
+            crate::guild_config::MAX_ERROR_REPORT_INTERVAL_MINUTES
+        ),
+        ConfigKey::TidyCommands => {
+            "`off`, `all` or a comma-separated list of `player`, `host` and `organizer`".to_string()
+        }
+    };
+    format!(
+        "`{}` expects {}. Use `default` to go back to the default value.",
+        key.name(),
+        expected
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn CONFIG_EVENT_SOURCE_NOT_CONFIGURED(source: &str) -> String {
+    format!(
+        "The bot was started without the credentials for `{}`, so it can't use it yet.",
+        source
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn CONFIG_VALUE_SET(key: crate::guild_config::ConfigKey, value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("`{}` is now {}", key.name(), config_value(key, value)),
+        None => format!("`{}` is back to its default value", key.name()),
+    }
+}
+
+pub const CONFIG_LOG_TITLE: &'static str = "Config changes";
+
+pub const NO_CONFIG_CHANGES: &'static str = "Nobody changed the config yet";
+
+#[allow(non_snake_case)]
+pub fn CONFIG_LOG_LINE(entry: &crate::guild_config::AuditEntry) -> String {
+    let value = |value: &Option<String>| match value {
+        Some(value) => format!("`{}`", value),
+        None => "default".to_string(),
+    };
+    format!(
+        "{}: <@{}> changed `{}` from {} to {}",
+        entry.time,
+        entry.discord_user,
+        entry.key,
+        value(&entry.old_value),
+        value(&entry.new_value)
+    )
+}
+
+// ** Setup **
+
+pub const SETUP_NOT_GUILD_OWNER: &'static str = "Only the owner of the server can set me up";
+
+fn setup_step_description(step: crate::discord_setup::SetupStep) -> &'static str {
+    use crate::discord_setup::SetupStep;
+    match step {
+        SetupStep::OneShotCategory => "the category for the channels of one-shots",
+        SetupStep::CampaignCategory => "the category for the channels of campaigns",
+        SetupStep::GameMasterRole => "the role that the hosts of upcoming sessions get",
+        SetupStep::StaffChannel => "where organizers get notifications, only visible to them",
+        SetupStep::AnnouncementChannel => "where new games are announced",
+    }
+}
+
+fn setup_next_step(next_step: Option<crate::discord_setup::SetupStep>, bot_id: u64) -> String {
+    match next_step {
+        Some(step) => format!(
+            "Next up is `{step}`. Write ***<@{bot_id}> setup {step} create*** to create it, or \
+             name an existing one instead of `create`. `none` skips a step.",
+            step = step.name(),
+            bot_id = bot_id
+        ),
+        None => "Everything is set up!".to_string(),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn SETUP_OVERVIEW(
+    steps: &[(crate::discord_setup::SetupStep, Option<String>)],
+    organizer_role: &str,
+    next_step: Option<crate::discord_setup::SetupStep>,
+    bot_id: u64,
+) -> String {
+    let lines: Vec<String> = steps
+        .iter()
+        .map(|(step, value)| {
+            format!(
+                "\u{2022} `{}`: {} ({})",
+                step.name(),
+                value.as_ref().map(String::as_str).unwrap_or("not set up"),
+                setup_step_description(*step)
+            )
+        })
+        .collect();
+    format!(
+        "Let's set me up on this server. These are the steps:\n{}\n\
+         The organizer role is {}, that one is built into the bot.\n{}",
+        lines.join("\n"),
+        organizer_role,
+        setup_next_step(next_step, bot_id)
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SETUP_UNKNOWN_STEP(name: &str) -> String {
+    format!(
+        "There is no setup step called `{}`. The steps are: {}",
+        name,
+        crate::discord_setup::SetupStep::ALL
+            .iter()
+            .map(|step| format!("`{}`", step.name()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SETUP_INVALID_VALUE(step: crate::discord_setup::SetupStep) -> String {
+    use crate::discord_setup::SetupStep;
+    let expected = match step {
+        SetupStep::OneShotCategory | SetupStep::CampaignCategory => "a category of this server",
+        SetupStep::GameMasterRole => "a role of this server",
+        SetupStep::StaffChannel | SetupStep::AnnouncementChannel => "a text channel of this server",
+    };
+    format!("`{}` expects `create`, `none` or {}", step.name(), expected)
+}
+
+#[allow(non_snake_case)]
+pub fn SETUP_STEP_DONE(
+    step: crate::discord_setup::SetupStep,
+    value: Option<&str>,
+    next_step: Option<crate::discord_setup::SetupStep>,
+    bot_id: u64,
+) -> String {
+    format!(
+        "`{}` is now {}. {}",
+        step.name(),
+        value.unwrap_or("not set up"),
+        setup_next_step(next_step, bot_id)
+    )
+}
+
+// ** Scheduled commands **
+
+pub const SCHEDULE_COMMAND_INVALID_TIME: &'static str = "I don't understand this date. \
+Use `YYYY-MM-DD HH:MM` (in UTC) or an RFC 3339 date like `2020-01-31T19:00:00+01:00`.";
+
+#[allow(non_snake_case)]
+pub fn SCHEDULE_COMMAND_TIME_OUT_OF_RANGE(max_days: i64) -> String {
+    format!(
+        "Commands can only be scheduled in the future, and at most {} days ahead",
+        max_days
+    )
+}
+
+pub const SCHEDULE_COMMAND_NESTED: &'static str =
+    "A scheduled command can't schedule other commands";
+
+#[allow(non_snake_case)]
+pub fn COMMAND_SCHEDULED(
+    command_id: &str,
+    command: &str,
+    due_time: &chrono::DateTime<chrono::Utc>,
+) -> String {
+    format!(
+        "I will run `{}` here on {} UTC. To cancel it, use `unschedule {}`.",
+        command,
+        due_time.format("%Y-%m-%d %H:%M"),
+        command_id
+    )
+}
+
+pub const SCHEDULED_COMMANDS_TITLE: &'static str = "Scheduled commands";
+
+pub const NO_SCHEDULED_COMMANDS: &'static str = "There are no scheduled commands";
+
+#[allow(non_snake_case)]
+pub fn SCHEDULED_COMMAND_LINE(
+    command_id: &str,
+    scheduled_command: &crate::scheduled_commands::ScheduledCommand,
+) -> String {
+    format!(
+        "`{}`: `{}` on {} in <#{}> (by <@{}>)",
+        command_id,
+        scheduled_command.command,
+        scheduled_command.due_time,
+        scheduled_command.channel_id,
+        scheduled_command.discord_user
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SCHEDULED_COMMAND_CANCELLED(command_id: &str) -> String {
+    format!("Cancelled the scheduled command `{}`", command_id)
+}
+
+#[allow(non_snake_case)]
+pub fn SCHEDULED_COMMAND_NOT_FOUND(command_id: &str) -> String {
+    format!(
+        "There is no pending scheduled command `{}`. It might have run already.",
+        command_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SCHEDULED_COMMAND_RUNNING(command: &str, discord_user: u64) -> String {
+    format!(
+        "Running the command `{}` that <@{}> scheduled",
+        command, discord_user
+    )
+}
+
+// ** Dice **
+
+#[allow(non_snake_case)]
+pub fn INVALID_DICE_EXPRESSION(max_dice: u32, max_sides: u32) -> String {
+    format!(
+        "I can't roll that. Try something like `3d6+2`, `d20 adv` or `2d8 + 1d4 - 1` \
+         (at most {} dice with up to {} sides).",
+        max_dice, max_sides
+    )
+}
+
+fn roll_mode(mode: crate::dice_roller::Mode) -> &'static str {
+    match mode {
+        crate::dice_roller::Mode::Normal => "",
+        crate::dice_roller::Mode::Advantage => " with advantage",
+        crate::dice_roller::Mode::Disadvantage => " with disadvantage",
+    }
+}
+
+fn roll_details(roll: &crate::dice_roller::Roll) -> String {
+    match &roll.discarded {
+        Some(discarded) => format!(
+            "{} = **{}** (the other roll was {} = {})",
+            roll.kept.details, roll.kept.total, discarded.details, discarded.total
+        ),
+        None => format!("{} = **{}**", roll.kept.details, roll.kept.total),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn ROLL_RESULT(discord_id: u64, roll: &crate::dice_roller::Roll) -> String {
+    format!(
+        "<@{}> rolled {}{}: {}",
+        discord_id,
+        roll.expression,
+        roll_mode(roll.mode),
+        roll_details(roll)
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn GM_ROLL_RESULT(channel_id: u64, roll: &crate::dice_roller::Roll) -> String {
+    format!(
+        "Your secret roll in <#{}>: {}{}: {}",
+        channel_id,
+        roll.expression,
+        roll_mode(roll.mode),
+        roll_details(roll)
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn GM_ROLL_ANNOUNCEMENT(discord_id: u64) -> String {
+    format!(
+        "<@{}> rolled the dice behind the screen :game_die:",
+        discord_id
+    )
+}
+
+pub const GM_ROLL_DM_FAILED: &'static str =
+    "I couldn't send you the result via DM. Please check your privacy settings.";
+
+#[allow(non_snake_case)]
+pub fn ROLL_LOG_ENTRY(
+    time: chrono::DateTime<chrono::Utc>,
+    discord_id: u64,
+    roll: &crate::dice_roller::Roll,
+) -> String {
+    format!(
+        "{} <@{}>: {}{} = **{}**",
+        time.format("%Y-%m-%d %H:%M"),
+        discord_id,
+        roll.expression,
+        roll_mode(roll.mode),
+        roll.kept.total
+    )
+}
+
+pub const ROLL_LOG_TITLE: &'static str = "Recent rolls in this channel";
+
+pub const NO_ROLLS_LOGGED: &'static str = "Nobody rolled any dice in this channel yet";
+
+// ** Debug commands **
+
+pub const DEBUG_COMMANDS_UNAVAILABLE: &'static str =
+    "Debug commands are only available when I am running in staging mode";
+
+#[allow(non_snake_case)]
+pub fn DEBUG_WELCOME_SENT(user_id: u64) -> String {
+    format!("Sent the welcome message to <@{}>", user_id)
+}
+
+#[allow(non_snake_case)]
+pub fn DEBUG_UNKNOWN_SERIES(series_id: &str) -> String {
+    format!("There is no event series with the ID `{}`", series_id)
+}
+
+#[allow(non_snake_case)]
+pub fn DEBUG_SERIES_SYNCED(series_id: &str) -> String {
+    format!("Synced the event series `{}` with Discord", series_id)
+}
+
+#[allow(non_snake_case)]
+pub fn DEBUG_NO_KEYS(pattern: &str) -> String {
+    format!("No Redis keys match `{}`", pattern)
+}
+
+#[allow(non_snake_case)]
+pub fn DEBUG_KEYS_TITLE(pattern: &str, num_keys: usize) -> String {
+    format!("Redis keys matching {} ({})", pattern, num_keys)
+}
+
+// ** Organizer tokens **
+
+pub const ORGANIZER_TOKENS_TITLE: &'static str = "Meetup organizer tokens";
+
+pub const NO_ORGANIZER_TOKENS: &'static str =
+    "No organizer has logged in with their Meetup account yet";
+
+#[allow(non_snake_case)]
+pub fn ORGANIZER_TOKEN_LINE(
+    name: &str,
+    meetup_user_id: u64,
+    healthy: Option<bool>,
+    last_check: Option<&str>,
+    is_active: bool,
+) -> String {
+    format!(
+        "{status} {name} (Meetup ID `{id}`){active}, last checked {last_check}",
+        status = match healthy {
+            Some(true) => "\u{2705}",
+            Some(false) => "\u{274C}",
+            None => "\u{2753}",
+        },
+        name = name,
+        id = meetup_user_id,
+        active = if is_active { ", **in use**" } else { "" },
+        last_check = last_check.unwrap_or("never")
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn ORGANIZER_TOKEN_REVOKED(meetup_user_id: u64) -> String {
+    format!(
+        "I forgot the Meetup tokens of the organizer with Meetup ID {}",
+        meetup_user_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn UNKNOWN_ORGANIZER_TOKEN(meetup_user_id: u64) -> String {
+    format!(
+        "I don't have any Meetup tokens of an organizer with Meetup ID {}",
+        meetup_user_id
+    )
+}
+
+// ** Maintenance mode **
+
+pub const MAINTENANCE_MODE_ACTIVE: &'static str =
+    "Sorry, I am currently in maintenance mode and can't make any changes. \
+     Please try again later!";
+
+pub const MAINTENANCE_MODE_ON: &'static str =
+    "Maintenance mode is on. I will refuse all changes and put my scheduled tasks on hold \
+     until you turn it off again.";
+
+pub const MAINTENANCE_MODE_OFF: &'static str = "Maintenance mode is off. Back to business!";
+
+// **************************************
+// *** Meetup linking webpage replies ***
+// **************************************
+
+#[allow(non_snake_case)]
+pub fn OAUTH2_AUTHORISATION_DENIED(linking_url: &str) -> String {
+    format!(
+        "Looks like you declined the authorisation. If you want to \
+         start over, click the button below to give it another go. \
+         If you are still having issues, please contact an organiser \
+         by email (organisers@swissrpg.ch) or on Discord (@Organiser).<br>\
+         <a href=\"{linking_url}\" class=\"button\" style=\"margin-top: 1em;\">Start Over</a>",
+        linking_url = linking_url
+    )
+}
+
+pub const OAUTH2_LINK_EXPIRED_TITLE: &'static str = "This link seems to have expired";
+pub const OAUTH2_LINK_EXPIRED_CONTENT: &'static str =
+    "Get a new link from the bot with the \"link meetup\" command";
+
+pub const OAUTH2_LINKING_SUCCESS_TITLE: &'static str = "Linking Success!";
+#[allow(non_snake_case)]
+pub fn OAUTH2_LINKING_SUCCESS_CONTENT(name: &str) -> String {
+    format!(
+        "Welcome, {}! Your Discord account is now linked to your Meetup account. \
+         I also sent you a confirmation on Discord.",
+        name
+    )
 }
 
 pub const OAUTH2_ALREADY_LINKED_SUCCESS_TITLE: &'static str = "All good!";
@@ -0,0 +1,269 @@
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+// Profiles the Discord sync: how long each step of syncing the event series
+// takes and how many Discord API requests it makes, summed up over all series
+// of a run. The last runs are kept in Redis for the "sync stats" command, such
+// that regressions in the sync path show up.
+// Discord API requests are counted globally, so requests made by commands
+// while a sync is running are attributed to the sync step that is running.
+// Each run also records how much of the Discord API budget (see
+// discord_api_budget) it used and what it deferred because of it, and how
+// long the calls of the background tasks waited for commands (see
+// discord_call_priority).
+const REDIS_SYNC_STATS_KEY: &'static str = "discord_sync:stats";
+pub const MAX_RECORDED_RUNS: usize = 50;
+pub const DEFAULT_SHOWN_RUNS: usize = 20;
+
+// The profiled steps of syncing a series, in the order they run
+pub const STEPS: &[&'static str] = &[
+    "channel",
+    "roles",
+    "permissions",
+    "role_assignments",
+    "topic",
+    "session_channels",
+    "scheduled_events",
+    "voice_channel",
+];
+
+static DISCORD_API_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+// Called for every request to the Discord API
+pub fn record_discord_api_call() {
+    DISCORD_API_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn discord_api_calls() -> usize {
+    DISCORD_API_CALLS.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct StepStats {
+    pub duration_ms: u64,
+    pub api_calls: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRun {
+    pub time: String,
+    pub num_series: u64,
+    pub total: StepStats,
+    pub steps: BTreeMap<String, StepStats>,
+    // Missing in runs recorded before the API budget
+    #[serde(default)]
+    pub api_budget: u64,
+    #[serde(default)]
+    pub deferred_steps: u64,
+    #[serde(default)]
+    pub deferred_series: u64,
+    #[serde(default)]
+    pub waited_calls: u64,
+    #[serde(default)]
+    pub wait_ms: u64,
+    #[serde(default)]
+    pub peak_wait_ms: u64,
+    #[serde(default)]
+    pub peak_queue_depth: u64,
+}
+
+pub struct StepStart {
+    started: Instant,
+    api_calls: usize,
+}
+
+pub struct SyncProfile {
+    start: StepStart,
+    queue_start: crate::discord_call_priority::QueueMetrics,
+    num_series: u64,
+    steps: BTreeMap<&'static str, StepStats>,
+    deferred_steps: u64,
+    deferred_series: u64,
+}
+
+impl StepStart {
+    fn now() -> Self {
+        StepStart {
+            started: Instant::now(),
+            api_calls: discord_api_calls(),
+        }
+    }
+
+    fn elapsed(&self) -> StepStats {
+        let elapsed = self.started.elapsed();
+        StepStats {
+            duration_ms: elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis()),
+            api_calls: discord_api_calls().saturating_sub(self.api_calls) as u64,
+        }
+    }
+}
+
+impl SyncProfile {
+    pub fn new() -> Self {
+        crate::discord_call_priority::reset_peaks();
+        SyncProfile {
+            start: StepStart::now(),
+            queue_start: crate::discord_call_priority::metrics(),
+            num_series: 0,
+            steps: BTreeMap::new(),
+            deferred_steps: 0,
+            deferred_series: 0,
+        }
+    }
+
+    pub fn start_step(&self) -> StepStart {
+        StepStart::now()
+    }
+
+    // Steps that fail are not recorded
+    pub fn finish_step(&mut self, step: &'static str, start: StepStart) {
+        let elapsed = start.elapsed();
+        let stats = self.steps.entry(step).or_default();
+        stats.duration_ms += elapsed.duration_ms;
+        stats.api_calls += elapsed.api_calls;
+    }
+
+    pub fn count_series(&mut self) {
+        self.num_series += 1;
+    }
+
+    pub fn record_api_budget(&mut self, api_budget: &crate::discord_api_budget::ApiBudget) {
+        self.deferred_steps = api_budget.num_deferred_steps();
+        self.deferred_series = api_budget.num_deferred_series();
+    }
+
+    pub fn save<C: redis::ConnectionLike>(self, con: &mut C) -> Result<(), crate::BoxedError> {
+        let queue = crate::discord_call_priority::metrics();
+        let run = SyncRun {
+            time: chrono::Utc::now().to_rfc3339(),
+            num_series: self.num_series,
+            total: self.start.elapsed(),
+            steps: self
+                .steps
+                .into_iter()
+                .map(|(step, stats)| (step.to_string(), stats))
+                .collect(),
+            api_budget: crate::discord_api_budget::MAX_API_CALLS_PER_SYNC,
+            deferred_steps: self.deferred_steps,
+            deferred_series: self.deferred_series,
+            waited_calls: queue
+                .num_waited_calls
+                .saturating_sub(self.queue_start.num_waited_calls),
+            wait_ms: queue
+                .total_wait_ms
+                .saturating_sub(self.queue_start.total_wait_ms),
+            peak_wait_ms: queue.peak_wait_ms,
+            peak_queue_depth: queue.peak_depth,
+        };
+        let _: () = redis::pipe()
+            .lpush(REDIS_SYNC_STATS_KEY, serde_json::to_string(&run)?)
+            .ignore()
+            .ltrim(REDIS_SYNC_STATS_KEY, 0, MAX_RECORDED_RUNS as isize - 1)
+            .ignore()
+            .query(con)?;
+        Ok(())
+    }
+}
+
+// The last runs, most recent first
+pub fn recent_runs<C: redis::ConnectionLike>(
+    con: &mut C,
+    num_runs: usize,
+) -> Result<Vec<SyncRun>, crate::BoxedError> {
+    if num_runs == 0 {
+        return Ok(vec![]);
+    }
+    let runs: Vec<String> = con.lrange(REDIS_SYNC_STATS_KEY, 0, num_runs as isize - 1)?;
+    Ok(runs
+        .iter()
+        .filter_map(|run| match serde_json::from_str(run) {
+            Ok(run) => Some(run),
+            Err(err) => {
+                eprintln!("Could not parse the recorded sync run \"{}\": {}", run, err);
+                None
+            }
+        })
+        .collect())
+}
+
+pub struct DurationSummary {
+    pub average_ms: u64,
+    pub median_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+    pub average_api_calls: u64,
+}
+
+// Summarizes the given measurements of a step (or of whole runs)
+pub fn summarize(stats: &[StepStats]) -> Option<DurationSummary> {
+    if stats.is_empty() {
+        return None;
+    }
+    let mut durations: Vec<u64> = stats.iter().map(|stats| stats.duration_ms).collect();
+    durations.sort_unstable();
+    let num = durations.len() as u64;
+    // Nearest-rank percentile
+    let percentile = |p: u64| durations[((p * num + 99) / 100).max(1) as usize - 1];
+    Some(DurationSummary {
+        average_ms: durations.iter().sum::<u64>() / num,
+        median_ms: percentile(50),
+        p95_ms: percentile(95),
+        max_ms: durations[durations.len() - 1],
+        average_api_calls: stats.iter().map(|stats| stats.api_calls).sum::<u64>() / num,
+    })
+}
+
+pub struct BudgetSummary {
+    pub average_percent: u64,
+    pub max_percent: u64,
+    pub deferred_steps: u64,
+    pub deferred_series: u64,
+}
+
+// How much of the Discord API budget the runs used. Runs recorded before the
+// budget existed don't count.
+pub fn summarize_budget(runs: &[SyncRun]) -> Option<BudgetSummary> {
+    let runs: Vec<_> = runs.iter().filter(|run| run.api_budget > 0).collect();
+    if runs.is_empty() {
+        return None;
+    }
+    let percentages: Vec<u64> = runs
+        .iter()
+        .map(|run| run.total.api_calls * 100 / run.api_budget)
+        .collect();
+    Some(BudgetSummary {
+        average_percent: percentages.iter().sum::<u64>() / percentages.len() as u64,
+        max_percent: percentages.iter().cloned().max().unwrap_or(0),
+        deferred_steps: runs.iter().map(|run| run.deferred_steps).sum(),
+        deferred_series: runs.iter().map(|run| run.deferred_series).sum(),
+    })
+}
+
+pub struct QueueSummary {
+    pub waited_calls: u64,
+    // Per call that waited
+    pub average_wait_ms: u64,
+    pub peak_wait_ms: u64,
+    pub peak_queue_depth: u64,
+}
+
+// How long the calls of the runs waited for commands. None if none of them did.
+pub fn summarize_queue(runs: &[SyncRun]) -> Option<QueueSummary> {
+    let waited_calls: u64 = runs.iter().map(|run| run.waited_calls).sum();
+    if waited_calls == 0 {
+        return None;
+    }
+    Some(QueueSummary {
+        waited_calls: waited_calls,
+        average_wait_ms: runs.iter().map(|run| run.wait_ms).sum::<u64>() / waited_calls,
+        peak_wait_ms: runs.iter().map(|run| run.peak_wait_ms).max().unwrap_or(0),
+        peak_queue_depth: runs
+            .iter()
+            .map(|run| run.peak_queue_depth)
+            .max()
+            .unwrap_or(0),
+    })
+}
@@ -0,0 +1,103 @@
+use lazy_static::lazy_static;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// A panic inside a scheduled task would otherwise take down the scheduler
+// thread and silently stop all syncing. Supervised tasks catch their panics,
+// get marked as unhealthy and are retried with an exponential backoff.
+const INITIAL_BACKOFF_SECS: i64 = 60;
+const MAX_BACKOFF_SECS: i64 = 60 * 60;
+
+#[derive(Debug, Clone)]
+struct TaskHealth {
+    consecutive_panics: u32,
+    last_panic_time: chrono::DateTime<chrono::Utc>,
+}
+
+lazy_static! {
+    // Tasks whose last run panicked
+    static ref UNHEALTHY_TASKS: Mutex<HashMap<String, TaskHealth>> = Mutex::new(HashMap::new());
+}
+
+thread_local! {
+    // Name of the supervised task that is currently running on this thread,
+    // such that panic reports can mention it
+    static CURRENT_TASK: RefCell<Option<String>> = RefCell::new(None);
+}
+
+pub fn current_task() -> Option<String> {
+    CURRENT_TASK.with(|current_task| current_task.borrow().clone())
+}
+
+pub fn supervised(
+    task_name: String,
+    mut task: impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static,
+) -> impl FnMut(&mut white_rabbit::Context) -> white_rabbit::DateResult + Send + Sync + 'static {
+    move |ctx| {
+        CURRENT_TASK.with(|current_task| *current_task.borrow_mut() = Some(task_name.clone()));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| task(ctx)));
+        CURRENT_TASK.with(|current_task| *current_task.borrow_mut() = None);
+        let mut unhealthy_tasks = match UNHEALTHY_TASKS.lock() {
+            Ok(unhealthy_tasks) => unhealthy_tasks,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        match result {
+            Ok(result) => {
+                if unhealthy_tasks.remove(&task_name).is_some() {
+                    println!("Task {} recovered", task_name);
+                }
+                result
+            }
+            Err(_) => {
+                // The panic itself was already logged (and reported) by the panic hook
+                let now = chrono::Utc::now();
+                let health = unhealthy_tasks
+                    .entry(task_name.clone())
+                    .or_insert(TaskHealth {
+                        consecutive_panics: 0,
+                        last_panic_time: now,
+                    });
+                health.consecutive_panics += 1;
+                health.last_panic_time = now;
+                let backoff_secs = std::cmp::min(
+                    INITIAL_BACKOFF_SECS << std::cmp::min(health.consecutive_panics - 1, 16),
+                    MAX_BACKOFF_SECS,
+                );
+                eprintln!(
+                    "Task {} panicked ({} times in a row), retrying in {} seconds",
+                    task_name, health.consecutive_panics, backoff_secs
+                );
+                white_rabbit::DateResult::Repeat(
+                    white_rabbit::Utc::now() + white_rabbit::Duration::seconds(backoff_secs),
+                )
+            }
+        }
+    }
+}
+
+// Human readable status for the health endpoint
+pub fn health_status() -> String {
+    let unhealthy_tasks = match UNHEALTHY_TASKS.lock() {
+        Ok(unhealthy_tasks) => unhealthy_tasks,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if unhealthy_tasks.is_empty() {
+        return "tasks: ok".to_string();
+    }
+    let mut task_names: Vec<_> = unhealthy_tasks.keys().collect();
+    task_names.sort_unstable();
+    let descriptions: Vec<_> = task_names
+        .into_iter()
+        .map(|task_name| {
+            let health = &unhealthy_tasks[task_name];
+            format!(
+                "{} (panicked {} times in a row, last at {})",
+                task_name,
+                health.consecutive_panics,
+                health.last_panic_time.to_rfc3339()
+            )
+        })
+        .collect();
+    format!("tasks: unhealthy: {}", descriptions.join(", "))
+}
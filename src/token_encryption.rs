@@ -0,0 +1,136 @@
+use lazy_static::lazy_static;
+use redis::Commands;
+use ring::aead;
+use ring::rand::SecureRandom;
+use simple_error::SimpleError;
+
+// OAuth2 tokens are encrypted before they are written to Redis, such that a
+// leaked dump or replica does not give away access to the Meetup accounts.
+// The key is a base64 encoded 32 byte ChaCha20-Poly1305 key, configured via
+// REDIS_ENCRYPTION_KEY. Without a key, tokens are stored in plain text.
+// Values without the prefix are treated as plain text, such that existing
+// tokens keep working until they are migrated.
+const ENCRYPTED_VALUE_PREFIX: &'static str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+lazy_static! {
+    static ref ENCRYPTION_KEY: Option<Vec<u8>> = crate::secrets::load("REDIS_ENCRYPTION_KEY")
+        .expect("Could not load REDIS_ENCRYPTION_KEY")
+        .map(|key| {
+            let key = base64::decode(key.trim()).expect("REDIS_ENCRYPTION_KEY is not valid base64");
+            if key.len() != aead::CHACHA20_POLY1305.key_len() {
+                panic!(
+                    "REDIS_ENCRYPTION_KEY needs to be {} bytes long",
+                    aead::CHACHA20_POLY1305.key_len()
+                );
+            }
+            key
+        });
+    static ref RANDOM: ring::rand::SystemRandom = ring::rand::SystemRandom::new();
+}
+
+// Also validates the configured key
+pub fn is_enabled() -> bool {
+    ENCRYPTION_KEY.is_some()
+}
+
+pub fn encrypt(plaintext: &str) -> Result<String, crate::BoxedError> {
+    let key = match *ENCRYPTION_KEY {
+        Some(ref key) => key,
+        None => return Ok(plaintext.to_string()),
+    };
+    let sealing_key = aead::SealingKey::new(&aead::CHACHA20_POLY1305, key)
+        .map_err(|_| SimpleError::new("Invalid encryption key"))?;
+    let mut nonce = [0u8; NONCE_LEN];
+    RANDOM
+        .fill(&mut nonce)
+        .map_err(|_| SimpleError::new("Could not generate a nonce"))?;
+    let tag_len = aead::CHACHA20_POLY1305.tag_len();
+    let mut in_out = plaintext.as_bytes().to_vec();
+    in_out.extend(std::iter::repeat(0).take(tag_len));
+    let sealed_len = aead::seal_in_place(
+        &sealing_key,
+        aead::Nonce::assume_unique_for_key(nonce),
+        aead::Aad::empty(),
+        &mut in_out,
+        tag_len,
+    )
+    .map_err(|_| SimpleError::new("Could not encrypt the value"))?;
+    // Store the nonce in front of the ciphertext
+    let mut stored = nonce.to_vec();
+    stored.extend_from_slice(&in_out[..sealed_len]);
+    Ok(format!(
+        "{}{}",
+        ENCRYPTED_VALUE_PREFIX,
+        base64::encode(&stored)
+    ))
+}
+
+pub fn decrypt(stored: &str) -> Result<String, crate::BoxedError> {
+    let encoded = if stored.starts_with(ENCRYPTED_VALUE_PREFIX) {
+        &stored[ENCRYPTED_VALUE_PREFIX.len()..]
+    } else {
+        // Not encrypted (yet)
+        return Ok(stored.to_string());
+    };
+    let key = match *ENCRYPTION_KEY {
+        Some(ref key) => key,
+        None => {
+            return Err(SimpleError::new(
+                "Found an encrypted value but REDIS_ENCRYPTION_KEY is not set",
+            )
+            .into())
+        }
+    };
+    let opening_key = aead::OpeningKey::new(&aead::CHACHA20_POLY1305, key)
+        .map_err(|_| SimpleError::new("Invalid encryption key"))?;
+    let mut decoded = base64::decode(encoded)?;
+    if decoded.len() < NONCE_LEN {
+        return Err(SimpleError::new("Encrypted value is too short").into());
+    }
+    let mut ciphertext = decoded.split_off(NONCE_LEN);
+    let nonce = aead::Nonce::try_assume_unique_for_key(&decoded)
+        .map_err(|_| SimpleError::new("Invalid nonce"))?;
+    let plaintext = aead::open_in_place(
+        &opening_key,
+        nonce,
+        aead::Aad::empty(),
+        /*in_prefix_len*/ 0,
+        &mut ciphertext,
+    )
+    .map_err(|_| SimpleError::new("Could not decrypt the value (wrong key?)"))?;
+    Ok(String::from_utf8(plaintext.to_vec())?)
+}
+
+// Encrypts all tokens that are still stored in plain text.
+// Returns the number of encrypted values.
+pub fn migrate<C: redis::ConnectionLike>(con: &mut C) -> Result<usize, crate::BoxedError> {
+    if !is_enabled() {
+        return Err(SimpleError::new("REDIS_ENCRYPTION_KEY is not set").into());
+    }
+    let mut num_encrypted = 0;
+    for &key in &["meetup_access_token", "meetup_refresh_token"] {
+        let value: Option<String> = con.get(key)?;
+        if let Some(value) = value {
+            if !value.starts_with(ENCRYPTED_VALUE_PREFIX) {
+                let _: () = con.set(key, encrypt(&value)?)?;
+                num_encrypted += 1;
+            }
+        }
+    }
+    let mut token_hash_keys: Vec<String> = con.keys("meetup_user:*:oauth2_tokens")?;
+    let organizer_keys: Vec<String> = con.keys("meetup_organizer:*")?;
+    token_hash_keys.extend(organizer_keys);
+    for key in token_hash_keys {
+        for &field in &["access_token", "refresh_token"] {
+            let value: Option<String> = con.hget(&key, field)?;
+            if let Some(value) = value {
+                if !value.starts_with(ENCRYPTED_VALUE_PREFIX) {
+                    let _: () = con.hset(&key, field, encrypt(&value)?)?;
+                    num_encrypted += 1;
+                }
+            }
+        }
+    }
+    Ok(num_encrypted)
+}
@@ -1,6 +1,7 @@
 use redis::Commands;
 use regex::Regex;
-use serenity::model::id::ChannelId;
+use serenity::http::CacheHttp;
+use serenity::model::id::{ChannelId, RoleId};
 use std::collections::HashSet;
 
 // Vacuum task:
@@ -33,7 +34,7 @@ use std::collections::HashSet;
 // - check "orphaned_roles" and "orphaned_channels"
 
 pub fn vacuum(
-    redis_client: redis::Client,
+    redis_client: crate::redis_namespace::Client,
     discord_api: crate::discord_bot::CacheAndHttp,
 ) -> Result<(), crate::BoxedError> {
     let mut con = redis_client.get_connection()?;
@@ -42,23 +43,15 @@ pub fn vacuum(
 }
 
 fn vacuum_discord_channels(
-    con: &mut redis::Connection,
+    con: &mut crate::redis_namespace::Connection,
     discord_api: &crate::discord_bot::CacheAndHttp,
 ) -> Result<(), crate::BoxedError> {
     // Step 0: Figure out all the Discord channel IDs that Redis knows about.
     // We could just use the `discord_channels` set, but maybe the Redis state
     // is inconsistent, so we actually scan for all keys that might contain
     // a channel ID.
-    let orphaned_discord_channel_ids: Vec<u64> = con.smembers("orphaned_discord_channels")?;
     // Step 1: Try to delete orphaned Discord channels
-    for orphaned_channel_id in orphaned_discord_channel_ids {
-        if channel_exists(ChannelId(orphaned_channel_id), discord_api)? {
-            // Delete it from Discord
-            discord_api.http.delete_channel(orphaned_channel_id)?;
-        }
-        // Remove it from orphaned_channels (only if Discord deletion was successful)
-        let _: () = con.srem("orphaned_discord_channels", orphaned_channel_id)?;
-    }
+    delete_orphaned_channels(con, discord_api)?;
     // Step 2: Check all channel IDs we store in Redis.
     // If a channel does not exist anymore on Discord, remove it from Redis
     let discord_channel_ids = get_redis_discord_channel_ids(con)?;
@@ -74,8 +67,97 @@ fn vacuum_discord_channels(
     Ok(())
 }
 
+// Channels and roles that the bot created but couldn't delete again
+pub fn orphaned_channel_ids(
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Vec<u64>, crate::BoxedError> {
+    let mut channel_ids: Vec<u64> = con.smembers("orphaned_discord_channels")?;
+    channel_ids.sort_unstable();
+    Ok(channel_ids)
+}
+
+pub fn orphaned_role_ids(
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Vec<u64>, crate::BoxedError> {
+    let mut role_ids: Vec<u64> = con.smembers("orphaned_discord_roles")?;
+    role_ids.sort_unstable();
+    Ok(role_ids)
+}
+
+// Returns the number of channels that are gone now. Channels that don't pass
+// the ownership check are kept.
+pub fn delete_orphaned_channels(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<usize, crate::BoxedError> {
+    let mut num_deleted = 0;
+    for orphaned_channel_id in orphaned_channel_ids(con)? {
+        if channel_exists(ChannelId(orphaned_channel_id), discord_api)? {
+            if !crate::discord_ownership::verify_managed_channel(
+                ChannelId(orphaned_channel_id),
+                "orphaned_discord_channels",
+                con,
+                discord_api,
+            )? {
+                continue;
+            }
+            // Delete it from Discord
+            discord_api.http.delete_channel(orphaned_channel_id)?;
+        }
+        // Remove it from orphaned_channels (only if Discord deletion was successful)
+        let _: () = con.srem("orphaned_discord_channels", orphaned_channel_id)?;
+        num_deleted += 1;
+    }
+    Ok(num_deleted)
+}
+
+// Returns the number of roles that are gone now. Roles that a channel uses
+// after all are only removed from the set, roles that don't pass the
+// ownership check are kept.
+pub fn delete_orphaned_roles(
+    con: &mut crate::redis_namespace::Connection,
+    discord_api: &crate::discord_bot::CacheAndHttp,
+) -> Result<usize, crate::BoxedError> {
+    let mut num_deleted = 0;
+    for orphaned_role_id in orphaned_role_ids(con)? {
+        let (channel_id, host_channel_id): (Option<u64>, Option<u64>) = redis::pipe()
+            .get(format!("discord_role:{}:discord_channel", orphaned_role_id))
+            .get(format!(
+                "discord_host_role:{}:discord_channel",
+                orphaned_role_id
+            ))
+            .query(con)?;
+        if channel_id.is_none() && host_channel_id.is_none() {
+            if !crate::discord_ownership::verify_managed_role(
+                RoleId(orphaned_role_id),
+                "orphaned_discord_roles",
+                con,
+                discord_api,
+            )? {
+                continue;
+            }
+            match discord_api
+                .http()
+                .delete_role(crate::discord_sync::GUILD_ID.0, orphaned_role_id)
+            {
+                Ok(()) => (),
+                Err(err) if is_not_found(&err) => (),
+                Err(err) => return Err(err.into()),
+            }
+            num_deleted += 1;
+        } else {
+            eprintln!(
+                "Orphaned role {} belongs to a channel, keeping it",
+                orphaned_role_id
+            );
+        }
+        let _: () = con.srem("orphaned_discord_roles", orphaned_role_id)?;
+    }
+    Ok(num_deleted)
+}
+
 fn get_redis_discord_channel_ids(
-    con: &mut redis::Connection,
+    con: &mut crate::redis_namespace::Connection,
 ) -> Result<HashSet<u64>, crate::BoxedError> {
     let mut all_channel_ids = HashSet::new();
     // Relevant keys:
@@ -88,9 +170,20 @@ fn get_redis_discord_channel_ids(
     // "discord_channel:{}:discord_host_role"
     // "discord_channel:{}:removed_users"
     // "discord_channel:{}:removed_hosts"
+    // "discord_channel:{}:cohosts"
+    // "discord_channel:{}:voice_channel"
+    // "discord_channel:{}:hide_history_days"
+    // "discord_channel:{}:settings"
+    // "discord_channel:{}:archived"
+    // "discord_channel:{}:history_hidden_until"
+    // "discord_overwrite_channels"
+    // "discord_channel:{}:overwrite_players"
+    // "discord_channel:{}:overwrite_hosts"
     {
         let discord_channels: Vec<u64> = con.smembers("discord_channels")?;
         all_channel_ids.extend(discord_channels);
+        let overwrite_channels: Vec<u64> = con.smembers("discord_overwrite_channels")?;
+        all_channel_ids.extend(overwrite_channels);
     }
     let redis_channel_key_patterns = [
         "event_series:*:discord_channel",
@@ -122,6 +215,50 @@ fn get_redis_discord_channel_ids(
             "discord_channel:*:removed_hosts",
             "^discord_channel:(?P<channel_id>[0-9]+):removed_hosts$",
         ),
+        (
+            "discord_channel:*:manual_hosts",
+            "^discord_channel:(?P<channel_id>[0-9]+):manual_hosts$",
+        ),
+        (
+            "discord_channel:*:cohosts",
+            "^discord_channel:(?P<channel_id>[0-9]+):cohosts$",
+        ),
+        (
+            "discord_channel:*:session_message",
+            "^discord_channel:(?P<channel_id>[0-9]+):session_message$",
+        ),
+        (
+            "discord_channel:*:voice_channel",
+            "^discord_channel:(?P<channel_id>[0-9]+):voice_channel$",
+        ),
+        (
+            "discord_channel:*:settings",
+            "^discord_channel:(?P<channel_id>[0-9]+):settings$",
+        ),
+        (
+            "discord_channel:*:archived",
+            "^discord_channel:(?P<channel_id>[0-9]+):archived$",
+        ),
+        (
+            "discord_channel:*:hide_history_days",
+            "^discord_channel:(?P<channel_id>[0-9]+):hide_history_days$",
+        ),
+        (
+            "discord_channel:*:pruned_members",
+            "^discord_channel:(?P<channel_id>[0-9]+):pruned_members$",
+        ),
+        (
+            "discord_channel:*:overwrite_players",
+            "^discord_channel:(?P<channel_id>[0-9]+):overwrite_players$",
+        ),
+        (
+            "discord_channel:*:overwrite_hosts",
+            "^discord_channel:(?P<channel_id>[0-9]+):overwrite_hosts$",
+        ),
+        (
+            "discord_channel:*:history_hidden_until",
+            "^discord_channel:(?P<channel_id>[0-9]+):history_hidden_until$",
+        ),
     ];
     for (redis_key_pattern, redis_key_regex) in &redis_key_pattern_regex_pairs {
         let redis_key_regex = Regex::new(redis_key_regex)?;
@@ -133,7 +270,7 @@ fn get_redis_discord_channel_ids(
 }
 
 fn get_ids_from_key_values<T>(
-    con: &mut redis::Connection,
+    con: &mut crate::redis_namespace::Connection,
     key_name_pattern: &str,
 ) -> Result<Vec<T>, crate::BoxedError>
 where
@@ -163,7 +300,7 @@ where
 }
 
 fn get_ids_from_key_names<T>(
-    con: &mut redis::Connection,
+    con: &mut crate::redis_namespace::Connection,
     key_name_pattern: &str,
     key_name_regex: &Regex,
 ) -> Result<Vec<T>, crate::BoxedError>
@@ -199,21 +336,16 @@ fn channel_exists(
 ) -> Result<bool, crate::BoxedError> {
     match channel_id.to_channel(discord_api) {
         Ok(_) => Ok(true),
-        Err(err) => {
-            if let serenity::Error::Http(http_err) = &err {
-                if let serenity::http::HttpError::UnsuccessfulRequest(response) = http_err.as_ref()
-                {
-                    if response.status_code == reqwest::StatusCode::NOT_FOUND {
-                        Ok(false)
-                    } else {
-                        return Err(err.into());
-                    }
-                } else {
-                    return Err(err.into());
-                }
-            } else {
-                return Err(err.into());
-            }
+        Err(ref err) if is_not_found(err) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn is_not_found(err: &serenity::Error) -> bool {
+    if let serenity::Error::Http(http_err) = err {
+        if let serenity::http::HttpError::UnsuccessfulRequest(response) = http_err.as_ref() {
+            return response.status_code == reqwest::StatusCode::NOT_FOUND;
         }
     }
+    false
 }
@@ -0,0 +1,320 @@
+use crate::event_source::{BoxedStream, EventSource, UserIds};
+use crate::meetup_api;
+use crate::meetup_sync::BoxedFuture;
+use futures::{future, stream, Future, Stream};
+use serde::Deserialize;
+use serenity::prelude::Mutex;
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Warhorn as an event source, for conventions and game days that schedule
+// their sessions there. The bot reads the sessions of one Warhorn event (set
+// with "config set warhorn_event <slug>") through the GraphQL API. Each
+// session is a one-shot, so it becomes an adventure of its own.
+// Like the calendar, Warhorn only tells the bot the email addresses of the
+// GMs and players, those are matched to the addresses that the Discord users
+// registered with "register email". Warhorn only shares those addresses with
+// the event's managers, so the API token needs to belong to one. Once a
+// Warhorn account was matched, it is linked as an identity of the Discord
+// user, so it stays matched if its address changes.
+const GRAPHQL_URL: &'static str = "https://warhorn.net/graphql";
+
+const SESSIONS_QUERY: &'static str = "
+query EventSessions($slug: String!, $startsAfter: ISO8601DateTime!) {
+  event(slug: $slug) {
+    sessions(startsAfter: $startsAfter, first: 100) {
+      nodes {
+        id
+        name
+        startsAt
+        maxPlayers
+        location
+        scenario { name blurb }
+        gmSignups { user { id name email } }
+        playerSignups { user { id name email } }
+      }
+    }
+  }
+}";
+
+#[derive(Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQLError>,
+}
+
+#[derive(Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct EventData {
+    event: Option<WarhornEvent>,
+}
+
+#[derive(Deserialize)]
+struct WarhornEvent {
+    sessions: SessionConnection,
+}
+
+#[derive(Deserialize)]
+struct SessionConnection {
+    nodes: Vec<Session>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Session {
+    id: String,
+    #[serde(default)]
+    name: Option<String>,
+    starts_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    max_players: Option<u16>,
+    #[serde(default)]
+    location: Option<String>,
+    scenario: Scenario,
+    #[serde(default)]
+    gm_signups: Vec<Signup>,
+    #[serde(default)]
+    player_signups: Vec<Signup>,
+}
+
+#[derive(Deserialize)]
+struct Scenario {
+    name: String,
+    #[serde(default)]
+    blurb: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Signup {
+    user: WarhornUser,
+}
+
+#[derive(Deserialize)]
+struct WarhornUser {
+    id: String,
+    name: String,
+    // Only visible to the event's managers
+    #[serde(default)]
+    email: Option<String>,
+}
+
+pub struct WarhornEventSource {
+    api_token: String,
+    http_client: reqwest::r#async::Client,
+    redis_client: crate::redis_namespace::Client,
+    // Warhorn returns the signups together with the sessions, so they are
+    // kept from the last query until the Meetup sync asks for them
+    rsvps: Arc<Mutex<HashMap<String, Vec<meetup_api::RSVP>>>>,
+}
+
+impl WarhornEventSource {
+    pub fn new(api_token: String, redis_client: crate::redis_namespace::Client) -> Self {
+        WarhornEventSource {
+            api_token: api_token,
+            http_client: reqwest::r#async::Client::new(),
+            redis_client: redis_client,
+            rsvps: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl EventSource for WarhornEventSource {
+    fn name(&self) -> &'static str {
+        "Warhorn"
+    }
+
+    fn user_ids(&self) -> UserIds {
+        UserIds::Discord
+    }
+
+    fn get_upcoming_events(&self) -> BoxedStream<meetup_api::Event> {
+        // The event can be changed at runtime, so look it up for every sync
+        let slug = match self
+            .redis_client
+            .get_connection()
+            .map_err(crate::BoxedError::from)
+            .and_then(|mut con| crate::guild_config::warhorn_event(&mut con))
+        {
+            Ok(Some(slug)) => slug,
+            Ok(None) => {
+                return Box::new(stream::once::<meetup_api::Event, crate::BoxedError>(Err(
+                    SimpleError::new("No Warhorn event is set (config set warhorn_event <slug>)")
+                        .into(),
+                )))
+            }
+            Err(err) => return Box::new(stream::once::<meetup_api::Event, _>(Err(err))),
+        };
+        let query = serde_json::json!({
+            "query": SESSIONS_QUERY,
+            "variables": {
+                "slug": slug,
+                "startsAfter": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            },
+        });
+        let redis_client = self.redis_client.clone();
+        let cached_rsvps = self.rsvps.clone();
+        let events = self
+            .http_client
+            .post(GRAPHQL_URL)
+            .header(
+                reqwest::header::AUTHORIZATION,
+                format!("Bearer {}", self.api_token),
+            )
+            .json(&query)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json::<GraphQLResponse<EventData>>())
+            .from_err::<crate::BoxedError>()
+            .and_then(move |response| {
+                if let Some(error) = response.errors.first() {
+                    return Err(SimpleError::new(format!(
+                        "Warhorn rejected the query: {}",
+                        error.message
+                    ))
+                    .into());
+                }
+                let warhorn_event = match response.data.and_then(|data| data.event) {
+                    Some(warhorn_event) => warhorn_event,
+                    None => {
+                        return Err(
+                            SimpleError::new(format!("Unknown Warhorn event {}", slug)).into()
+                        )
+                    }
+                };
+                let mut con = redis_client.get_connection()?;
+                let mut events = vec![];
+                let mut rsvps = HashMap::new();
+                for session in warhorn_event.sessions.nodes {
+                    let (event, event_rsvps) = to_meetup_event(session, &slug, &mut con)?;
+                    rsvps.insert(event.id.clone(), event_rsvps);
+                    events.push(event);
+                }
+                *cached_rsvps.lock() = rsvps;
+                Ok(stream::iter_ok::<_, crate::BoxedError>(events))
+            })
+            .flatten_stream();
+        Box::new(events)
+    }
+
+    fn get_rsvps(&self, _urlname: &str, event_id: &str) -> BoxedFuture<Vec<meetup_api::RSVP>> {
+        match self.rsvps.lock().get(event_id) {
+            Some(rsvps) => Box::new(future::ok(rsvps.clone())),
+            None => Box::new(future::err(
+                SimpleError::new(format!("Unknown Warhorn session {}", event_id)).into(),
+            )),
+        }
+    }
+}
+
+// Looks for a linked Warhorn account first, then for the address
+fn discord_user(
+    user: &WarhornUser,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Option<u64>, crate::BoxedError> {
+    let identity = crate::identity::Identity::warhorn(&user.id);
+    if let Some(discord_id) = crate::identity::discord_user(&identity, con)? {
+        return Ok(Some(discord_id));
+    }
+    let email = match user.email {
+        Some(ref email) => email,
+        None => return Ok(None),
+    };
+    let discord_id = crate::email_registration::discord_user(email, con)?;
+    if let Some(discord_id) = discord_id {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        crate::identity::add_link(&mut pipe, discord_id, &identity);
+        let _: () = pipe.query(con)?;
+    }
+    Ok(discord_id)
+}
+
+// Signups of users that didn't register their address are skipped
+fn discord_users(
+    signups: &[Signup],
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<Vec<meetup_api::User>, crate::BoxedError> {
+    let mut users: Vec<meetup_api::User> = vec![];
+    for signup in signups {
+        if let Some(discord_id) = discord_user(&signup.user, con)? {
+            if !users.iter().any(|user| user.id == discord_id) {
+                users.push(meetup_api::User {
+                    id: discord_id,
+                    name: signup.user.name.clone(),
+                    photo: None,
+                    group_profile: None,
+                });
+            }
+        }
+    }
+    Ok(users)
+}
+
+// Converts a session into the bot's event model, with the tag that makes the
+// Meetup sync start a new adventure for it
+fn to_meetup_event(
+    session: Session,
+    slug: &str,
+    con: &mut crate::redis_namespace::Connection,
+) -> Result<(meetup_api::Event, Vec<meetup_api::RSVP>), crate::BoxedError> {
+    // The series tags only allow letters and digits
+    let event_id: String = format!("warhorn{}", session.id)
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    let event_hosts = discord_users(&session.gm_signups, con)?;
+    let rsvps = discord_users(&session.player_signups, con)?
+        .into_iter()
+        .map(|member| meetup_api::RSVP {
+            member: member,
+            response: meetup_api::RSVPResponse::Yes,
+            answers: vec![],
+        })
+        .collect();
+    let location = session
+        .location
+        .map(|location| location.trim().to_string())
+        .filter(|location| !location.is_empty());
+    // Online sessions put the link to the table into the location
+    let link = location
+        .as_ref()
+        .filter(|location| location.starts_with("https://") || location.starts_with("http://"))
+        .cloned();
+    let venue = match (&link, location) {
+        (None, Some(location)) => Some(meetup_api::Venue {
+            name: location,
+            address_1: None,
+            city: None,
+        }),
+        _ => None,
+    };
+    let name = session.name.unwrap_or(session.scenario.name);
+    let event = meetup_api::Event {
+        id: event_id,
+        name: name,
+        time: session.starts_at,
+        event_hosts: event_hosts,
+        link: format!(
+            "https://warhorn.net/events/{}/schedule/sessions/{}",
+            slug, session.id
+        ),
+        group: meetup_api::Group {
+            urlname: slug.to_string(),
+        },
+        description: format!(
+            "[new adventure]\n\n{}",
+            session.scenario.blurb.unwrap_or_default()
+        ),
+        rsvp_limit: session.max_players.map(u32::from),
+        venue: venue,
+        is_online_event: link.is_some(),
+        how_to_find_us: link,
+    };
+    Ok((event, rsvps))
+}